@@ -0,0 +1,15 @@
+fn main() {
+    // The API embeds dashboard/ with include_dir, which cannot tell cargo to watch it
+    println!("cargo:rerun-if-changed=dashboard");
+
+    // gRPC stubs are generated with a pure-Rust proto compiler, so no protoc is needed
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto");
+        let fds = protox::compile(["proto/predictor.proto", "proto/trades.proto"], ["proto"]).expect("❌ Invalid proto definitions");
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_fds(fds)
+            .expect("❌ gRPC code generation failed");
+    }
+}
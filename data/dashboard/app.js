@@ -0,0 +1,200 @@
+// Live view over the API: /prices, /predict and /status for the initial state, /sse for
+// trades and predictions as they happen. Served from /dashboard/, so API paths are relative
+// to the parent. The API key, when the API needs one, is kept in localStorage.
+
+const API = new URL("..", location.href);
+const STATUS_EVERY_MS = 10000;
+const MAX_PREDICTIONS = 50;
+
+const $ = (id) => document.getElementById(id);
+const prices = new Map();
+let source = null;
+
+function apiKey() {
+  return localStorage.getItem("apiKey") || "";
+}
+
+function url(path, params = {}) {
+  const u = new URL(path, API);
+  for (const [k, v] of Object.entries(params)) u.searchParams.set(k, v);
+  return u;
+}
+
+async function get(path) {
+  const headers = apiKey() ? { "X-API-Key": apiKey() } : {};
+  const res = await fetch(url(path), { headers });
+  if (!res.ok) {
+    const body = await res.json().catch(() => ({}));
+    throw new Error(`${path}: ${res.status} ${body.error || res.statusText}`);
+  }
+  return res.json();
+}
+
+function showError(e) {
+  $("error").hidden = !e;
+  $("error").textContent = e ? String(e.message || e) : "";
+}
+
+function cell(text, cls) {
+  const td = document.createElement("td");
+  td.textContent = text;
+  if (cls) td.className = cls;
+  return td;
+}
+
+function row(cells) {
+  const tr = document.createElement("tr");
+  tr.append(...cells);
+  return tr;
+}
+
+function age(ms) {
+  if (ms == null) return "–";
+  const s = Math.max(0, Math.round(ms / 1000));
+  if (s < 60) return `${s}s`;
+  if (s < 3600) return `${Math.floor(s / 60)}m ${s % 60}s`;
+  return `${Math.floor(s / 3600)}h ${Math.floor((s % 3600) / 60)}m`;
+}
+
+function num(v, digits = 2) {
+  return v == null ? "–" : Number(v).toLocaleString(undefined, { maximumFractionDigits: digits });
+}
+
+// --- Prices ---
+
+function renderPrices(flash) {
+  const body = $("prices");
+  body.replaceChildren(
+    ...[...prices.values()]
+      .sort((a, b) => a.symbol.localeCompare(b.symbol))
+      .map((p) => {
+        const tr = row([
+          cell(p.symbol),
+          cell(num(p.price, 6), "num"),
+          cell(num(p.volume, 4), "num"),
+          cell(age(p.ts ? Date.now() - p.ts : null), "num"),
+        ]);
+        if (p.symbol === flash) tr.className = "flash";
+        return tr;
+      }),
+  );
+}
+
+function onTrade(t) {
+  prices.set(t.symbol, t);
+  renderPrices(t.symbol);
+}
+
+// --- Predictions ---
+
+function addPrediction(p, atTop = true) {
+  const ret = p.predicted_return * 100;
+  const tr = row([
+    cell(new Date(p.feature_ts).toLocaleTimeString()),
+    cell(p.symbol),
+    cell(p.horizon),
+    cell(num(p.base_price, 6), "num"),
+    cell(num(p.predicted_price, 6), "num"),
+    cell(`${ret >= 0 ? "+" : ""}${ret.toFixed(3)}%`, `num ${ret >= 0 ? "ok" : "bad"}`),
+    cell(`${Math.round(p.confidence * 100)}%`, "num"),
+    cell(`${p.model}@${p.model_version}`),
+  ]);
+  const body = $("predictions");
+  if (atTop) {
+    tr.className = "flash";
+    body.prepend(tr);
+  } else {
+    body.append(tr);
+  }
+  while (body.children.length > MAX_PREDICTIONS) body.lastChild.remove();
+}
+
+// --- Feed health ---
+
+const STATUS_CLASS = { ok: "ok", healthy: "ok", stale: "warn", degraded: "warn", no_data: "warn" };
+
+async function refreshStatus() {
+  try {
+    const s = await get("status");
+    $("generated").textContent = `as of ${new Date(s.generated_at).toLocaleTimeString()}`;
+
+    const beats = new Map(s.heartbeats.map((h) => [h.service, h]));
+    const names = new Set([...Object.keys(s.services), ...beats.keys()]);
+    $("services").replaceChildren(
+      ...[...names].sort().map((name) => {
+        const li = document.createElement("li");
+        const report = s.services[name];
+        const beat = beats.get(name);
+        const state = beat?.stale ? "stale" : report?.status || beat?.state || "down";
+        li.textContent = `${name}: ${state}`;
+        li.className = STATUS_CLASS[state] || (state === "down" ? "bad" : "");
+        return li;
+      }),
+    );
+
+    $("exchanges").replaceChildren(
+      ...s.exchanges.map((e) =>
+        row([
+          cell(e.exchange),
+          cell(e.status, STATUS_CLASS[e.status] || "bad"),
+          cell(`${e.fresh}/${e.symbols}`, "num"),
+          cell(age(e.last_trade_age_ms), "num"),
+        ]),
+      ),
+    );
+
+    const f = s.fetcher;
+    $("fetcher").textContent = f
+      ? `Fetcher: last insert ${age(f.age_secs * 1000)} ago (${f.rows} rows)`
+      : "Fetcher: no inserts recorded";
+    showError(null);
+  } catch (e) {
+    showError(e);
+  }
+}
+
+// --- Live stream ---
+
+function connect() {
+  if (source) source.close();
+  const params = { streams: "trades,predictions" };
+  if (apiKey()) params.api_key = apiKey();
+  source = new EventSource(url("sse", params));
+  source.onopen = () => {
+    $("stream").textContent = "live";
+    $("stream").className = "badge ok";
+  };
+  source.onerror = () => {
+    $("stream").textContent = "reconnecting";
+    $("stream").className = "badge warn";
+  };
+  source.addEventListener("trade", (e) => onTrade(JSON.parse(e.data)));
+  source.addEventListener("prediction", (e) => addPrediction(JSON.parse(e.data)));
+  source.addEventListener("lagged", (e) => console.warn("stream lagged", e.data));
+}
+
+async function load() {
+  try {
+    for (const p of await get("prices")) prices.set(p.symbol, p);
+    renderPrices();
+    const latest = await get("predict");
+    $("predictions").replaceChildren();
+    latest.sort((a, b) => b.feature_ts - a.feature_ts).forEach((p) => addPrediction(p, false));
+    showError(null);
+  } catch (e) {
+    showError(e);
+  }
+  await refreshStatus();
+  connect();
+}
+
+$("api-key").value = apiKey();
+$("key-form").addEventListener("submit", (e) => {
+  e.preventDefault();
+  localStorage.setItem("apiKey", $("api-key").value.trim());
+  load();
+});
+
+setInterval(refreshStatus, STATUS_EVERY_MS);
+setInterval(() => renderPrices(), 1000);
+load();
@@ -6,6 +6,7 @@ import os
 import subprocess
 from datetime import datetime
 import shutil
+import sys
 import traceback
 
 # ------------------------------------CONFIG --------------------------------------------------------
@@ -92,6 +93,7 @@ def commit_and_push():
         subprocess.run(["git", "push", "--force", push_url, f"HEAD:{BRANCH_NAME}"], check=True)
 
         print("✅ Backup pushed to GitHub successfully.")
+        return True
 
     except subprocess.CalledProcessError as e:
         print(f"❌ Git command failed: {e}")
@@ -99,24 +101,35 @@ def commit_and_push():
     except Exception as e:
         print(f"❌ Unexpected error during Git operations: {e}")
         traceback.print_exc()
+    return False
 
 # ---------------------------------------MAIN---------------------------------------------------------------
 
 if __name__ == "__main__":
+    # Mode: "export" (CSV only), "push" (commit existing CSV), or "all" (default)
+    mode = sys.argv[1] if len(sys.argv) > 1 else "all"
+    if mode not in ("export", "push", "all"):
+        print(f"❌ Unknown mode '{mode}' — expected export, push or all")
+        sys.exit(2)
+
     try:
-        print("📦 Starting backup.py script...")
+        print(f"📦 Starting backup.py script (mode: {mode})...")
         print(f"🕒 Timestamp: {COMMIT_TIME}")
         print(f"📊 Target GitHub repo: {REPO}")
         print(f"📍 CSV output path: {CSV_PATH}")
         print(f"🔌 DB connection: {DATABASE_URL}")
 
-        success = asyncio.run(export_stock_price_history())
+        if mode in ("export", "all"):
+            success = asyncio.run(export_stock_price_history())
+            if not success:
+                print("❌ Backup process aborted — export failed or returned no data.")
+                sys.exit(1)
 
-        if success:
-            commit_and_push()
-        else:
-            print("❌ Backup process aborted — export failed or returned no data.")
+        if mode in ("push", "all"):
+            if not commit_and_push():
+                sys.exit(1)
 
     except Exception as e:
         print("❌ Backup script crashed due to unhandled exception:")
-        traceback.print_exc()
\ No newline at end of file
+        traceback.print_exc()
+        sys.exit(1)
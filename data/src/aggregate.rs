@@ -0,0 +1,178 @@
+use std::{fmt, str::FromStr};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Client as PgClient;
+
+use crate::{bars::Timeframe, history::MAX_HISTORY_LIMIT};
+
+/// Column groups `/aggregate` can return per bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Field {
+    /// open, high, low, close, volume
+    Ohlcv,
+    /// Volume-weighted average price
+    Vwap,
+    /// Time-weighted (per snapshot) average price
+    Twap,
+    /// close / open - 1
+    Change,
+    /// Snapshots in the bucket
+    Count,
+}
+
+impl Field {
+    pub const ALL: [Field; 5] = [Field::Ohlcv, Field::Vwap, Field::Twap, Field::Change, Field::Count];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ohlcv => "ohlcv",
+            Self::Vwap => "vwap",
+            Self::Twap => "twap",
+            Self::Change => "change",
+            Self::Count => "count",
+        }
+    }
+
+    /// Comma-separated, deduplicated; `ohlcv` when empty
+    pub fn parse_list(list: Option<&str>) -> Result<Vec<Field>, String> {
+        let mut fields = list
+            .unwrap_or("")
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Field>, _>>()?;
+        if fields.is_empty() {
+            fields.push(Field::Ohlcv);
+        }
+        fields.sort();
+        fields.dedup();
+        Ok(fields)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|f| f.as_str() == s)
+            .ok_or_else(|| format!("unknown field '{s}' (use ohlcv, vwap, twap, change or count)"))
+    }
+}
+
+/// One bucket; only the requested fields are set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Aggregate {
+    /// Bucket start, ms since epoch
+    pub start: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f64>,
+    /// Falls back to `twap` for buckets without traded volume
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vwap: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub twap: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<i64>,
+}
+
+/// A page of buckets; pass `next` as `from` to continue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AggregatePage {
+    pub symbol: String,
+    pub tf: String,
+    pub fields: Vec<String>,
+    pub buckets: Vec<Aggregate>,
+    pub next: Option<i64>,
+}
+
+/// `symbol`'s stored snapshots bucketed to `tf` over `[from, to)`, computing only what
+/// `fields` asks for. Volume is differenced like `history::candles`, and weights the VWAP.
+pub async fn aggregate(
+    pg: &PgClient,
+    symbol: &str,
+    tf: Timeframe,
+    fields: &[Field],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    limit: i64,
+) -> Result<AggregatePage, String> {
+    let limit = limit.clamp(1, MAX_HISTORY_LIMIT);
+    let rows = pg
+        .query(
+            "WITH snaps AS ( \
+                 SELECT trade_time_stamp AS ts, close, volume, \
+                        CASE WHEN volume_start IS NOT NULL THEN volume \
+                             WHEN lag(volume_start) OVER w IS NULL THEN volume - lag(volume) OVER w END AS dv \
+                 FROM stock_price_history_current \
+                 WHERE symbol = $1 AND trade_time_stamp >= $2 AND trade_time_stamp < $3 \
+                 WINDOW w AS (ORDER BY trade_time_stamp) \
+             ), traded AS ( \
+                 SELECT ts, close, CASE WHEN dv IS NULL THEN 0 WHEN dv >= 0 THEN dv ELSE volume END AS v \
+                 FROM snaps \
+             ) \
+             SELECT (floor(extract(epoch FROM ts) * 1000 / $4::bigint) * $4::bigint)::bigint AS bucket, \
+                    (array_agg(close ORDER BY ts))[1], max(close), min(close), \
+                    (array_agg(close ORDER BY ts DESC))[1], \
+                    sum(v), sum(close * v) / NULLIF(sum(v), 0), avg(close), count(*) \
+             FROM traded GROUP BY 1 ORDER BY 1 LIMIT $5",
+            &[&symbol, &from, &to, &tf.millis(), &(limit + 1)],
+        )
+        .await
+        .map_err(|e| format!("aggregate query failed: {e}"))?;
+
+    let has = |f: Field| fields.contains(&f);
+    let mut buckets: Vec<Aggregate> = rows
+        .iter()
+        .map(|r| {
+            let (open, close): (f64, f64) = (r.get(1), r.get(4));
+            let twap: f64 = r.get(7);
+            let ohlcv = has(Field::Ohlcv);
+            Aggregate {
+                start: r.get(0),
+                open: ohlcv.then_some(open),
+                high: ohlcv.then(|| r.get(2)),
+                low: ohlcv.then(|| r.get(3)),
+                close: ohlcv.then_some(close),
+                volume: ohlcv.then(|| r.get(5)),
+                vwap: has(Field::Vwap).then(|| r.get::<_, Option<f64>>(6).unwrap_or(twap)),
+                twap: has(Field::Twap).then_some(twap),
+                change: has(Field::Change).then(|| if open != 0.0 { close / open - 1.0 } else { 0.0 }),
+                count: has(Field::Count).then(|| r.get(8)),
+            }
+        })
+        .collect();
+    let next = (buckets.len() as i64 > limit).then(|| {
+        buckets.truncate(limit as usize);
+        buckets.last().map(|b| b.start + tf.millis())
+    });
+
+    Ok(AggregatePage {
+        symbol: symbol.to_string(),
+        tf: tf.to_string(),
+        fields: fields.iter().map(|f| f.to_string()).collect(),
+        buckets,
+        next: next.flatten(),
+    })
+}
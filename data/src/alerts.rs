@@ -0,0 +1,159 @@
+use std::{env, time::Duration};
+
+use serde::Serialize;
+use tracing::error;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_TELEGRAM_API_URL: &str = "https://api.telegram.org";
+
+/// One operational alert, posted as JSON
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    /// e.g. `feature_drift`
+    pub kind: String,
+    pub message: String,
+    /// ms since epoch
+    pub ts: i64,
+    pub details: serde_json::Value,
+}
+
+/// JSON POST of every alert to `ALERT_WEBHOOK_URL`
+#[derive(Clone)]
+pub struct Webhook {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl Webhook {
+    pub fn new(url: String) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("❌ Failed to build HTTP client");
+        Self { url, http }
+    }
+
+    /// `None` when `ALERT_WEBHOOK_URL` is unset
+    pub fn from_env() -> Option<Self> {
+        env::var("ALERT_WEBHOOK_URL").ok().map(Self::new)
+    }
+
+    pub async fn send(&self, alert: &Alert) -> Result<(), String> {
+        let resp = self
+            .http
+            .post(&self.url)
+            .json(alert)
+            .send()
+            .await
+            .map_err(|e| format!("webhook request failed: {e}"))?;
+        if !resp.status().is_success() {
+            return Err(format!("webhook returned {}", resp.status()));
+        }
+        Ok(())
+    }
+
+    /// Fire-and-forget `send` so a slow endpoint never stalls the caller
+    pub fn notify(&self, alert: Alert) {
+        let hook = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = hook.send(&alert).await {
+                error!("❌ Alert '{}' not delivered: {e}", alert.kind);
+            }
+        });
+    }
+}
+
+/// A text message sent to the bot
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+    pub chat_id: i64,
+    pub text: String,
+}
+
+/// Telegram Bot API `sendMessage` to `TELEGRAM_CHAT_ID` or a per-message chat
+#[derive(Clone)]
+pub struct Telegram {
+    api_url: String,
+    token: String,
+    chat_id: Option<String>,
+    http: reqwest::Client,
+}
+
+impl Telegram {
+    /// `None` when `TELEGRAM_BOT_TOKEN` is unset; `TELEGRAM_API_URL` overrides the Bot API host
+    pub fn from_env() -> Option<Self> {
+        let token = env::var("TELEGRAM_BOT_TOKEN").ok()?;
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("❌ Failed to build HTTP client");
+        Some(Self {
+            api_url: env::var("TELEGRAM_API_URL").unwrap_or_else(|_| DEFAULT_TELEGRAM_API_URL.to_string()),
+            token,
+            chat_id: env::var("TELEGRAM_CHAT_ID").ok(),
+            http,
+        })
+    }
+
+    pub async fn send(&self, chat_id: Option<&str>, text: &str) -> Result<(), String> {
+        let chat_id = chat_id
+            .or(self.chat_id.as_deref())
+            .ok_or_else(|| "no Telegram chat id (set TELEGRAM_CHAT_ID)".to_string())?;
+        let resp = self
+            .http
+            .post(format!("{}/bot{}/sendMessage", self.api_url, self.token))
+            .json(&serde_json::json!({ "chat_id": chat_id, "text": text }))
+            .send()
+            .await
+            .map_err(|e| format!("Telegram request failed: {e}"))?;
+        if !resp.status().is_success() {
+            return Err(format!("Telegram returned {}", resp.status()));
+        }
+        Ok(())
+    }
+
+    /// Long-poll `getUpdates` from `offset` for up to `wait`. Returns the offset to ask for
+    /// next and the text messages received; other updates are acknowledged and skipped.
+    pub async fn updates(&self, offset: i64, wait: Duration) -> Result<(i64, Vec<ChatMessage>), String> {
+        let resp = self
+            .http
+            .get(format!("{}/bot{}/getUpdates", self.api_url, self.token))
+            .query(&[("offset", offset), ("timeout", wait.as_secs() as i64)])
+            .timeout(wait + REQUEST_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| format!("Telegram request failed: {e}"))?;
+        if !resp.status().is_success() {
+            return Err(format!("Telegram returned {}", resp.status()));
+        }
+        let body: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| format!("invalid Telegram response: {e}"))?;
+        let mut next = offset;
+        let mut messages = Vec::new();
+        for update in body["result"].as_array().into_iter().flatten() {
+            let Some(id) = update["update_id"].as_i64() else {
+                continue;
+            };
+            next = next.max(id + 1);
+            if let (Some(chat_id), Some(text)) = (update["message"]["chat"]["id"].as_i64(), update["message"]["text"].as_str()) {
+                messages.push(ChatMessage {
+                    chat_id,
+                    text: text.to_string(),
+                });
+            }
+        }
+        Ok((next, messages))
+    }
+
+    /// Fire-and-forget `send`
+    pub fn notify(&self, chat_id: Option<String>, text: String) {
+        let bot = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = bot.send(chat_id.as_deref(), &text).await {
+                error!("❌ Telegram message not delivered: {e}");
+            }
+        });
+    }
+}
@@ -0,0 +1,188 @@
+use std::{collections::HashMap, env};
+
+use serde::Serialize;
+
+use crate::bars::Bar;
+
+
+// Weight of the newest bar in the running mean/variance
+const EWMA_ALPHA: f64 = 0.05;
+// Bars before z-scores are trusted
+const MIN_OBS: u32 = 20;
+const DEFAULT_RETURN_SIGMA: f64 = 4.0;
+const DEFAULT_VOLUME_SIGMA: f64 = 4.0;
+const DEFAULT_ALERT_COOLDOWN_SECS: i64 = 300;
+
+/// Exponentially weighted mean and variance
+#[derive(Debug, Clone, Default)]
+struct Ewma {
+    mean: f64,
+    var: f64,
+    n: u32,
+}
+
+impl Ewma {
+    /// z-score of `x` against the state before it; 0 until warmed up
+    fn z(&self, x: f64) -> f64 {
+        if self.n < MIN_OBS || self.var <= 0.0 {
+            return 0.0;
+        }
+        (x - self.mean) / self.var.sqrt()
+    }
+
+    fn update(&mut self, x: f64) {
+        if !x.is_finite() {
+            return;
+        }
+        if self.n == 0 {
+            self.mean = x;
+        } else {
+            let d = x - self.mean;
+            self.mean += EWMA_ALPHA * d;
+            self.var = (1.0 - EWMA_ALPHA) * (self.var + EWMA_ALPHA * d * d);
+        }
+        self.n = self.n.saturating_add(1);
+    }
+}
+
+/// How unusual the last bar was
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnomalyScores {
+    /// Log return of the bar
+    pub ret: f64,
+    /// Signed z-score of `ret`
+    pub return_z: f64,
+    pub volume: f64,
+    /// z-score of log(1 + volume)
+    pub volume_z: f64,
+}
+
+/// Scores each closed bar's return and volume against their own recent history
+#[derive(Debug, Clone, Default)]
+pub struct AnomalyDetector {
+    returns: Ewma,
+    volumes: Ewma,
+    last_close: Option<f64>,
+    last: AnomalyScores,
+}
+
+impl AnomalyDetector {
+    pub fn last(&self) -> AnomalyScores {
+        self.last
+    }
+
+    pub fn update(&mut self, bar: &Bar) -> AnomalyScores {
+        let ret = match self.last_close {
+            Some(p) if p > 0.0 && bar.close > 0.0 => Some((bar.close / p).ln()),
+            _ => None,
+        };
+        let log_volume = bar.volume.max(0.0).ln_1p();
+
+        self.last = AnomalyScores {
+            ret: ret.unwrap_or(0.0),
+            return_z: ret.map_or(0.0, |r| self.returns.z(r)),
+            volume: bar.volume,
+            volume_z: self.volumes.z(log_volume),
+        };
+        if let Some(r) = ret {
+            self.returns.update(r);
+        }
+        self.volumes.update(log_volume);
+        self.last_close = Some(bar.close);
+        self.last
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    Jump,
+    VolumeSpike,
+}
+
+/// Published for every bar whose return or volume crosses its threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct AnomalyEvent {
+    pub symbol: String,
+    pub tf: String,
+    /// Close time of the bar, ms since epoch
+    pub ts: i64,
+    pub kind: AnomalyKind,
+    pub z: f64,
+    /// Log return for jumps, volume for spikes
+    pub value: f64,
+    pub close: f64,
+}
+
+/// Event thresholds and webhook throttling
+#[derive(Debug, Clone)]
+pub struct AnomalyConfig {
+    pub return_sigma: f64,
+    pub volume_sigma: f64,
+    /// Minimum gap between webhook alerts per symbol and kind
+    pub alert_cooldown_ms: i64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            return_sigma: DEFAULT_RETURN_SIGMA,
+            volume_sigma: DEFAULT_VOLUME_SIGMA,
+            alert_cooldown_ms: DEFAULT_ALERT_COOLDOWN_SECS * 1000,
+        }
+    }
+}
+
+impl AnomalyConfig {
+    /// `ANOMALY_RETURN_SIGMA`, `ANOMALY_VOLUME_SIGMA`, `ANOMALY_ALERT_COOLDOWN_SECS`
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let num = |k: &str, default: f64| env::var(k).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            return_sigma: num("ANOMALY_RETURN_SIGMA", d.return_sigma),
+            volume_sigma: num("ANOMALY_VOLUME_SIGMA", d.volume_sigma),
+            alert_cooldown_ms: env::var("ANOMALY_ALERT_COOLDOWN_SECS")
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .map_or(d.alert_cooldown_ms, |s| s * 1000),
+        }
+    }
+
+    /// Events for `bar` given its scores; volume spikes only count upwards
+    pub fn detect(&self, bar: &Bar, scores: &AnomalyScores) -> Vec<AnomalyEvent> {
+        let event = |kind, z, value| AnomalyEvent {
+            symbol: bar.symbol.clone(),
+            tf: bar.tf.clone(),
+            ts: bar.end(),
+            kind,
+            z,
+            value,
+            close: bar.close,
+        };
+        let mut events = Vec::new();
+        if scores.return_z.abs() >= self.return_sigma {
+            events.push(event(AnomalyKind::Jump, scores.return_z, scores.ret));
+        }
+        if scores.volume_z >= self.volume_sigma {
+            events.push(event(AnomalyKind::VolumeSpike, scores.volume_z, scores.volume));
+        }
+        events
+    }
+}
+
+/// Lets one alert per symbol and kind through per cooldown
+#[derive(Debug, Default)]
+pub struct AlertThrottle {
+    last: HashMap<(String, AnomalyKind), i64>,
+}
+
+impl AlertThrottle {
+    pub fn allow(&mut self, event: &AnomalyEvent, cooldown_ms: i64) -> bool {
+        let last = self.last.entry((event.symbol.clone(), event.kind)).or_insert(i64::MIN);
+        if event.ts.saturating_sub(*last) < cooldown_ms {
+            return false;
+        }
+        *last = event.ts;
+        true
+    }
+}
@@ -0,0 +1,1020 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    body::Body,
+    extract::{ws::WebSocketUpgrade, Path, Query, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    middleware,
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use chrono::{DateTime, NaiveDateTime};
+use futures::StreamExt;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Mutex};
+use tokio_postgres::Client as PgClient;
+
+use tracing::{error, info};
+use crate::{
+    aggregate::{self, AggregatePage, Field},
+    auth::{self, Auth, Guard, Permission, Principal},
+    bars::{Bar, Timeframe},
+    cache::QueryCache,
+    dashboard,
+    fanout::{self, Stream},
+    finnhub::FinnhubClient,
+    health::{self, Health},
+    history::{self, CandlePage, CSV_HEADER, DEFAULT_HISTORY_LIMIT, MAX_HISTORY_LIMIT},
+    importance::FeatureImportance,
+    keys::{
+        BAR_PREFIX, IMPORTANCE_PREFIX, METRICS_COMPONENTS_KEY, METRICS_PREFIX, OHLCV_PREFIX, PREDICTION_PREFIX,
+        PRICE_PREFIX, SIGNAL_PREFIX, SYMBOLS_KEY, TRADE_PREFIX,
+    },
+    market::{self, MarketSummary, DEFAULT_TOP, MAX_TOP},
+    predictions::Prediction,
+    ratelimit::{Quota, RateLimiter},
+    redis_conn::RedisConn,
+    relay,
+    signals::Signal,
+    status::{self, Queues, Status},
+    symbols as tracked,
+    ticks::{self, Tick},
+    webhooks::{self, NewSubscription, Subscription},
+};
+
+/// Channels relayed to WebSocket clients
+pub fn live_channels() -> [&'static str; 4] {
+    fanout::channels()
+}
+
+/// Shared by every handler; the multiplexed connection is cheap to clone
+#[derive(Clone)]
+pub struct AppState {
+    pub redis: RedisConn,
+    /// Backs the history endpoints; `None` without `DATABASE_URL`
+    pub pg: Option<Arc<PgClient>>,
+    /// Validates symbols added through `POST /symbols`; `None` without `FINNHUB_API_KEY`
+    pub finnhub: Option<Arc<Mutex<FinnhubClient>>>,
+    /// API keys / JWT validation; `None` leaves every route open
+    pub auth: Option<Arc<Auth>>,
+    /// Redis cache in front of the history queries; `None` disables it
+    pub cache: Option<QueryCache>,
+    /// Token buckets per API key / client IP; `None` disables rate limiting
+    pub limiter: Option<Arc<RateLimiter>>,
+    /// Backs `/healthz` and `/readyz`, which stay outside auth and rate limiting
+    pub health: Arc<Health>,
+    /// Fed by `relay::run` over `live_channels()`
+    pub live: broadcast::Sender<relay::Message>,
+}
+
+/// JSON error body with an HTTP status
+#[derive(Debug)]
+pub struct ApiError(pub StatusCode, pub String);
+
+impl ApiError {
+    pub fn not_found(msg: impl Into<String>) -> Self {
+        Self(StatusCode::NOT_FOUND, msg.into())
+    }
+
+    pub fn bad_request(msg: impl Into<String>) -> Self {
+        Self(StatusCode::BAD_REQUEST, msg.into())
+    }
+
+    pub fn unavailable(msg: impl Into<String>) -> Self {
+        Self(StatusCode::SERVICE_UNAVAILABLE, msg.into())
+    }
+}
+
+impl From<redis::RedisError> for ApiError {
+    fn from(e: redis::RedisError) -> Self {
+        Self(StatusCode::SERVICE_UNAVAILABLE, format!("redis error: {e}"))
+    }
+}
+
+/// Body of every error response
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(ErrorBody { error: self.1 })).into_response()
+    }
+}
+
+pub type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// `?symbols=A,B`; every tracked symbol when absent
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct SymbolsQuery {
+    pub symbols: Option<String>,
+}
+
+impl SymbolsQuery {
+    pub async fn resolve(&self, redis: &mut RedisConn) -> Result<Vec<String>, ApiError> {
+        let mut symbols: Vec<String> = match &self.symbols {
+            Some(list) => list
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            None => redis.smembers(SYMBOLS_KEY).await?,
+        };
+        symbols.sort();
+        symbols.dedup();
+        Ok(symbols)
+    }
+}
+
+/// HGETALL `{prefix}{symbol}` for every symbol in one round trip
+async fn hashes(
+    redis: &mut RedisConn,
+    prefix: &str,
+    symbols: &[String],
+) -> Result<Vec<HashMap<String, String>>, ApiError> {
+    let mut pipe = redis::pipe();
+    for symbol in symbols {
+        pipe.hgetall(format!("{prefix}{symbol}"));
+    }
+    Ok(pipe.query_async(redis).await?)
+}
+
+/// Last trade for a symbol
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Price {
+    pub symbol: String,
+    pub price: f64,
+    pub volume: Option<f64>,
+    /// Exchange time of the trade, ms since epoch
+    pub ts: Option<i64>,
+}
+
+impl Price {
+    pub fn from_fields(symbol: &str, fields: &HashMap<String, String>) -> Option<Self> {
+        Some(Self {
+            symbol: symbol.to_string(),
+            price: fields.get("price")?.parse().ok()?,
+            volume: fields.get("volume").and_then(|v| v.parse().ok()),
+            ts: fields.get("timestamp").and_then(|v| v.parse().ok()),
+        })
+    }
+}
+
+/// OHLCV of the current base-timeframe bar the ingester keeps per symbol
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Ohlcv {
+    pub symbol: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    /// Start of the bar, ms since epoch
+    pub volume_start: Option<i64>,
+    /// False when a trade in the bar came without a volume
+    pub volume_known: bool,
+    /// RFC 3339 time of the last trade
+    pub updated_at: Option<String>,
+}
+
+impl Ohlcv {
+    pub fn from_fields(symbol: &str, fields: &HashMap<String, String>) -> Option<Self> {
+        let num = |k: &str| fields.get(k)?.parse::<f64>().ok();
+        Some(Self {
+            symbol: symbol.to_string(),
+            open: num("open")?,
+            high: num("high")?,
+            low: num("low")?,
+            close: num("close")?,
+            volume: num("volume")?,
+            volume_start: fields.get("volume_start").and_then(|v| v.parse().ok()),
+            volume_known: fields.get("volume_known").is_none_or(|v| v == "true"),
+            updated_at: fields.get("updated_at").cloned(),
+        })
+    }
+}
+
+/// `?tf=1m` selects the last closed bar instead of the current one
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct OhlcvQuery {
+    pub tf: Option<String>,
+}
+
+/// Every tracked symbol
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/symbols", tag = "prices",
+    responses((status = 200, body = Vec<String>))
+))]
+async fn symbols(State(mut state): State<AppState>) -> ApiResult<Vec<String>> {
+    let mut symbols: Vec<String> = state.redis.smembers(SYMBOLS_KEY).await?;
+    symbols.sort();
+    Ok(Json(symbols))
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SymbolBody {
+    pub symbol: String,
+}
+
+/// Result of adding or removing a symbol
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SymbolChange {
+    pub symbol: String,
+    /// False when the symbol was already in the requested state
+    pub changed: bool,
+}
+
+/// Track a symbol the exchange lists, in both `stocks` and the Redis set
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post, path = "/symbols", tag = "symbols",
+    request_body = SymbolBody,
+    responses(
+        (status = 201, description = "Now tracked", body = SymbolChange),
+        (status = 200, description = "Already tracked", body = SymbolChange),
+        (status = 400, body = ErrorBody),
+        (status = 422, description = "Not listed on its exchange", body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    )
+))]
+async fn add_symbol(State(mut state): State<AppState>, Json(body): Json<SymbolBody>) -> Result<Response, ApiError> {
+    let symbol = body.symbol.trim().to_uppercase();
+    tracked::parse(&symbol).map_err(ApiError::bad_request)?;
+    let pg = state
+        .pg
+        .as_ref()
+        .ok_or_else(|| ApiError::unavailable("symbol management needs DATABASE_URL"))?;
+    let finnhub = state
+        .finnhub
+        .as_ref()
+        .ok_or_else(|| ApiError::unavailable("symbol validation needs FINNHUB_API_KEY"))?;
+
+    let listed = tracked::listed(&mut *finnhub.lock().await, &symbol)
+        .await
+        .map_err(ApiError::unavailable)?;
+    if !listed {
+        return Err(ApiError(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("{symbol} is not listed on its exchange"),
+        ));
+    }
+
+    let changed = tracked::add(pg, &mut state.redis, &symbol)
+        .await
+        .map_err(ApiError::unavailable)?;
+    if changed {
+        info!("➕ Tracking {symbol}");
+    }
+    let status = if changed { StatusCode::CREATED } else { StatusCode::OK };
+    Ok((status, Json(SymbolChange { symbol, changed })).into_response())
+}
+
+/// Stop tracking a symbol; its history stays in Postgres
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete, path = "/symbols/{symbol}", tag = "symbols",
+    params(("symbol" = String, Path, description = "EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT")),
+    responses((status = 200, body = SymbolChange), (status = 404, body = ErrorBody))
+))]
+async fn remove_symbol(State(mut state): State<AppState>, Path(symbol): Path<String>) -> ApiResult<SymbolChange> {
+    let symbol = symbol.trim().to_uppercase();
+    let pg = state
+        .pg
+        .as_ref()
+        .ok_or_else(|| ApiError::unavailable("symbol management needs DATABASE_URL"))?;
+    let changed = tracked::remove(pg, &mut state.redis, &symbol)
+        .await
+        .map_err(ApiError::unavailable)?;
+    if !changed {
+        return Err(ApiError::not_found(format!("{symbol} is not tracked")));
+    }
+    info!("➖ Stopped tracking {symbol}");
+    Ok(Json(SymbolChange { symbol, changed }))
+}
+
+/// Register a webhook for the caller; deliveries are signed with the given secret
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post, path = "/webhooks", tag = "webhooks",
+    request_body = NewSubscription,
+    responses(
+        (status = 201, body = Subscription),
+        (status = 400, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    )
+))]
+async fn add_webhook(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Json(body): Json<NewSubscription>,
+) -> Result<Response, ApiError> {
+    let body = body.validate().map_err(ApiError::bad_request)?;
+    let pg = state
+        .pg
+        .as_ref()
+        .ok_or_else(|| ApiError::unavailable("webhooks need DATABASE_URL"))?;
+    let sub = webhooks::insert(pg, &principal.name, &body)
+        .await
+        .map_err(ApiError::unavailable)?;
+    info!("🪝 Webhook #{} for {} → {}", sub.id, sub.event, sub.url);
+    Ok((StatusCode::CREATED, Json(sub)).into_response())
+}
+
+/// The caller's webhooks with their last delivery outcome
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/webhooks", tag = "webhooks",
+    responses((status = 200, body = Vec<Subscription>), (status = 503, body = ErrorBody))
+))]
+async fn list_webhooks(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<Vec<Subscription>> {
+    let pg = state
+        .pg
+        .as_ref()
+        .ok_or_else(|| ApiError::unavailable("webhooks need DATABASE_URL"))?;
+    webhooks::list(pg, Some(&principal.name))
+        .await
+        .map(Json)
+        .map_err(ApiError::unavailable)
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete, path = "/webhooks/{id}", tag = "webhooks",
+    params(("id" = i32, Path)),
+    responses((status = 204), (status = 404, body = ErrorBody))
+))]
+async fn remove_webhook(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    let pg = state
+        .pg
+        .as_ref()
+        .ok_or_else(|| ApiError::unavailable("webhooks need DATABASE_URL"))?;
+    match webhooks::delete(pg, &principal.name, id).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(ApiError::not_found(format!("no webhook #{id}"))),
+        Err(e) => Err(ApiError::unavailable(e)),
+    }
+}
+
+/// The trade hash, or just the price when only the plain key exists
+pub async fn latest_price(redis: &mut RedisConn, symbol: &str) -> redis::RedisResult<Option<Price>> {
+    let fields: HashMap<String, String> = redis.hgetall(format!("{TRADE_PREFIX}{symbol}")).await?;
+    if let Some(p) = Price::from_fields(symbol, &fields) {
+        return Ok(Some(p));
+    }
+    let price: Option<f64> = redis.get(format!("{PRICE_PREFIX}{symbol}")).await?;
+    Ok(price.map(|price| Price {
+        symbol: symbol.to_string(),
+        price,
+        volume: None,
+        ts: None,
+    }))
+}
+
+/// Current-bar OHLCV the ingester keeps for `symbol`
+pub async fn latest_ohlcv(redis: &mut RedisConn, symbol: &str) -> redis::RedisResult<Option<Ohlcv>> {
+    let fields: HashMap<String, String> = redis.hgetall(format!("{OHLCV_PREFIX}{symbol}")).await?;
+    Ok(Ohlcv::from_fields(symbol, &fields))
+}
+
+/// Last trade
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/prices/{symbol}", tag = "prices",
+    params(("symbol" = String, Path, description = "EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT")),
+    responses((status = 200, body = Price), (status = 404, body = ErrorBody))
+))]
+async fn price(State(mut state): State<AppState>, Path(symbol): Path<String>) -> ApiResult<Price> {
+    latest_price(&mut state.redis, &symbol)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("no price for {symbol}")))
+}
+
+/// Last trade of each symbol
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/prices", tag = "prices",
+    params(SymbolsQuery),
+    responses((status = 200, body = Vec<Price>))
+))]
+async fn prices(State(mut state): State<AppState>, Query(q): Query<SymbolsQuery>) -> ApiResult<Vec<Price>> {
+    let symbols = q.resolve(&mut state.redis).await?;
+    let found = hashes(&mut state.redis, TRADE_PREFIX, &symbols).await?;
+    let mut pipe = redis::pipe();
+    for symbol in &symbols {
+        pipe.get(format!("{PRICE_PREFIX}{symbol}"));
+    }
+    let plain: Vec<Option<f64>> = pipe.query_async(&mut state.redis).await?;
+    Ok(Json(
+        symbols
+            .iter()
+            .zip(found.iter().zip(plain))
+            .filter_map(|(s, (f, plain))| {
+                Price::from_fields(s, f).or_else(|| {
+                    Some(Price {
+                        symbol: s.clone(),
+                        price: plain?,
+                        volume: None,
+                        ts: None,
+                    })
+                })
+            })
+            .collect(),
+    ))
+}
+
+/// Current-bar OHLCV, or the last closed bar of `tf`
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/ohlcv/{symbol}", tag = "prices",
+    params(("symbol" = String, Path, description = "EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT"), OhlcvQuery),
+    responses(
+        (status = 200, description = "`Ohlcv` without `tf`, `Bar` with it", body = Ohlcv),
+        (status = 400, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    )
+))]
+async fn ohlcv(
+    State(mut state): State<AppState>,
+    Path(symbol): Path<String>,
+    Query(q): Query<OhlcvQuery>,
+) -> Result<Response, ApiError> {
+    match q.tf {
+        Some(tf) => {
+            let tf = Timeframe::parse(&tf)
+                .ok_or_else(|| ApiError::bad_request(format!("invalid timeframe '{tf}'")))?
+                .to_string();
+            let fields: HashMap<String, String> = state.redis.hgetall(format!("{BAR_PREFIX}{symbol}:{tf}")).await?;
+            Bar::from_fields(&symbol, &tf, &fields)
+                .map(|b| Json(b).into_response())
+                .ok_or_else(|| ApiError::not_found(format!("no closed {tf} bar for {symbol}")))
+        }
+        None => latest_ohlcv(&mut state.redis, &symbol)
+            .await?
+            .map(|o| Json(o).into_response())
+            .ok_or_else(|| ApiError::not_found(format!("no OHLCV for {symbol}"))),
+    }
+}
+
+/// `?limit=` keeps only the newest ticks
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct TicksQuery {
+    pub limit: Option<usize>,
+}
+
+/// The ingester's last raw ticks for `symbol`, oldest first
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/ticks/{symbol}", tag = "prices",
+    params(("symbol" = String, Path, description = "EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT"), TicksQuery),
+    responses((status = 200, body = Vec<Tick>), (status = 404, body = ErrorBody))
+))]
+async fn ticks(
+    State(mut state): State<AppState>,
+    Path(symbol): Path<String>,
+    Query(q): Query<TicksQuery>,
+) -> ApiResult<Vec<Tick>> {
+    let mut ticks = ticks::load(&mut state.redis, &symbol)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("no ticks for {symbol}")))?;
+    if let Some(limit) = q.limit {
+        ticks.drain(..ticks.len().saturating_sub(limit));
+    }
+    Ok(Json(ticks))
+}
+
+/// `?from=&to=` as ms, RFC 3339 or `YYYY-MM-DD[THH:MM:SS]`; `tf` defaults to 1m
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct HistoryQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub tf: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Candles resampled from `stock_price_history`; defaults to the last `limit` candles before `to`
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/history/{symbol}", tag = "prices",
+    params(("symbol" = String, Path, description = "EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT"), HistoryQuery),
+    responses(
+        (status = 200, body = CandlePage),
+        (status = 400, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    )
+))]
+async fn history(
+    State(mut state): State<AppState>,
+    Path(symbol): Path<String>,
+    Query(q): Query<HistoryQuery>,
+) -> ApiResult<CandlePage> {
+    let pg = state
+        .pg
+        .as_ref()
+        .ok_or_else(|| ApiError::unavailable("history needs DATABASE_URL"))?;
+    let tf_label = q.tf.as_deref().unwrap_or("1m");
+    let tf = Timeframe::parse(tf_label).ok_or_else(|| ApiError::bad_request(format!("invalid timeframe '{tf_label}'")))?;
+    let limit = q.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let (from, to) = history::window(tf, q.from.as_deref(), q.to.as_deref(), limit).map_err(ApiError::bad_request)?;
+    let query = || history::candles(pg, &symbol, tf, from, to, limit);
+    let page = match state.cache {
+        Some(cache) => {
+            let params = history::cache_params(tf, q.from.as_deref(), q.to.as_deref(), limit);
+            cache.get_or_compute(&mut state.redis, "history", &symbol, &params, query).await
+        }
+        None => query().await,
+    };
+    page.map(Json).map_err(ApiError::unavailable)
+}
+
+/// `/history` parameters plus `fields=ohlcv,vwap,twap,change,count` (default `ohlcv`)
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct AggregateQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub tf: Option<String>,
+    pub limit: Option<i64>,
+    pub fields: Option<String>,
+}
+
+/// Stored snapshots resampled to any timeframe, with only the requested fields per bucket
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/aggregate/{symbol}", tag = "prices",
+    params(("symbol" = String, Path, description = "EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT"), AggregateQuery),
+    responses(
+        (status = 200, body = AggregatePage),
+        (status = 400, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    )
+))]
+async fn aggregate(
+    State(mut state): State<AppState>,
+    Path(symbol): Path<String>,
+    Query(q): Query<AggregateQuery>,
+) -> ApiResult<AggregatePage> {
+    let pg = state
+        .pg
+        .as_ref()
+        .ok_or_else(|| ApiError::unavailable("aggregation needs DATABASE_URL"))?;
+    let tf_label = q.tf.as_deref().unwrap_or("1m");
+    let tf = Timeframe::parse(tf_label).ok_or_else(|| ApiError::bad_request(format!("invalid timeframe '{tf_label}'")))?;
+    let fields = Field::parse_list(q.fields.as_deref()).map_err(ApiError::bad_request)?;
+    let limit = q.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let (from, to) = history::window(tf, q.from.as_deref(), q.to.as_deref(), limit).map_err(ApiError::bad_request)?;
+    let query = || aggregate::aggregate(pg, &symbol, tf, &fields, from, to, limit);
+    let page = match state.cache {
+        Some(cache) => {
+            let names: Vec<&str> = fields.iter().map(Field::as_str).collect();
+            let params = format!(
+                "{}:{}",
+                history::cache_params(tf, q.from.as_deref(), q.to.as_deref(), limit),
+                names.join(",")
+            );
+            cache.get_or_compute(&mut state.redis, "aggregate", &symbol, &params, query).await
+        }
+        None => query().await,
+    };
+    page.map(Json).map_err(ApiError::unavailable)
+}
+
+/// `windows=1h,24h` (default `MARKET_WINDOWS`), built from `tf` bars (default 1m), `top` per list
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct MarketQuery {
+    pub windows: Option<String>,
+    pub tf: Option<String>,
+    pub top: Option<usize>,
+}
+
+/// Top gainers, losers, most active and most volatile symbols per window, from live bars
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/market/summary", tag = "prices",
+    params(MarketQuery),
+    responses(
+        (status = 200, body = MarketSummary),
+        (status = 400, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    )
+))]
+async fn market_summary(State(mut state): State<AppState>, Query(q): Query<MarketQuery>) -> ApiResult<MarketSummary> {
+    let tf_label = q.tf.as_deref().unwrap_or("1m");
+    let tf = Timeframe::parse(tf_label).ok_or_else(|| ApiError::bad_request(format!("invalid timeframe '{tf_label}'")))?;
+    let windows = q.windows.unwrap_or_else(market::windows_from_env);
+    let windows = windows
+        .split(',')
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .map(|w| Timeframe::parse(w).ok_or_else(|| ApiError::bad_request(format!("invalid window '{w}'"))))
+        .collect::<Result<Vec<_>, _>>()?;
+    if windows.is_empty() {
+        return Err(ApiError::bad_request("no windows given"));
+    }
+    let counts = market::bar_counts(tf, &windows).map_err(ApiError::bad_request)?;
+    let top = q.top.unwrap_or(DEFAULT_TOP).clamp(1, MAX_TOP);
+    Ok(Json(market::summary(&mut state.redis, tf, &windows, &counts, top).await?))
+}
+
+/// Start of the page after `page`, if any
+fn next_from(page: &CandlePage) -> Option<NaiveDateTime> {
+    page.next
+        .and_then(DateTime::from_timestamp_millis)
+        .map(|t| t.naive_utc())
+}
+
+fn attachment(content_type: &'static str, filename: String, body: Body) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// `{symbol}.csv` streamed page by page, or `{symbol}.parquet` built in memory, over
+/// the same range and timeframe parameters as `/history` (`limit` only sets the default range)
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/export/{file}", tag = "prices",
+    params(("file" = String, Path, description = "`{symbol}.csv` or `{symbol}.parquet`"), HistoryQuery),
+    responses(
+        (status = 200, content_type = "text/csv", description = "CSV attachment"),
+        (status = 200, content_type = "application/vnd.apache.parquet", description = "Parquet attachment"),
+        (status = 400, body = ErrorBody),
+        (status = 501, description = "Built without the parquet feature", body = ErrorBody),
+    )
+))]
+async fn export(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+    Query(q): Query<HistoryQuery>,
+) -> Result<Response, ApiError> {
+    let (symbol, format) = file
+        .rsplit_once('.')
+        .ok_or_else(|| ApiError::bad_request("expected {symbol}.csv or {symbol}.parquet"))?;
+    let symbol = symbol.to_string();
+    let pg = state
+        .pg
+        .clone()
+        .ok_or_else(|| ApiError::unavailable("export needs DATABASE_URL"))?;
+    let tf_label = q.tf.as_deref().unwrap_or("1m");
+    let tf = Timeframe::parse(tf_label).ok_or_else(|| ApiError::bad_request(format!("invalid timeframe '{tf_label}'")))?;
+    let limit = q.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let (from, to) = history::window(tf, q.from.as_deref(), q.to.as_deref(), limit).map_err(ApiError::bad_request)?;
+    let filename = format!("{}_{tf}.{format}", symbol.replace(':', "_"));
+
+    // The first page is fetched up front so a database error is still a proper status
+    let first = history::candles(&pg, &symbol, tf, from, to, MAX_HISTORY_LIMIT)
+        .await
+        .map_err(ApiError::unavailable)?;
+
+    match format {
+        "csv" => {
+            let head: String = std::iter::once(CSV_HEADER.to_string())
+                .chain(first.candles.iter().map(|c| c.csv_row(&symbol)))
+                .collect();
+            let rest = futures::stream::unfold(next_from(&first), move |cursor| {
+                let (pg, symbol) = (pg.clone(), symbol.clone());
+                async move {
+                    let from = cursor?;
+                    match history::candles(&pg, &symbol, tf, from, to, MAX_HISTORY_LIMIT).await {
+                        Ok(page) => {
+                            let chunk: String = page.candles.iter().map(|c| c.csv_row(&symbol)).collect();
+                            Some((Ok(chunk), next_from(&page)))
+                        }
+                        Err(e) => {
+                            error!("❌ CSV export of {symbol} aborted: {e}");
+                            Some((Err(e), None))
+                        }
+                    }
+                }
+            });
+            let body = Body::from_stream(futures::stream::once(async move { Ok::<_, String>(head) }).chain(rest));
+            Ok(attachment("text/csv", filename, body))
+        }
+        "parquet" => {
+            #[cfg(feature = "parquet")]
+            {
+                let mut cursor = next_from(&first);
+                let mut candles = first.candles;
+                while let Some(from) = cursor {
+                    if candles.len() > history::MAX_PARQUET_CANDLES {
+                        return Err(ApiError::bad_request(format!(
+                            "more than {} candles; narrow the range or use .csv",
+                            history::MAX_PARQUET_CANDLES
+                        )));
+                    }
+                    let page = history::candles(&pg, &symbol, tf, from, to, MAX_HISTORY_LIMIT)
+                        .await
+                        .map_err(ApiError::unavailable)?;
+                    cursor = next_from(&page);
+                    candles.extend(page.candles);
+                }
+                let bytes = history::parquet(&symbol, &candles).map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+                Ok(attachment("application/vnd.apache.parquet", filename, Body::from(bytes)))
+            }
+            #[cfg(not(feature = "parquet"))]
+            Err(ApiError(
+                StatusCode::NOT_IMPLEMENTED,
+                "Parquet export requires the `parquet` feature".to_string(),
+            ))
+        }
+        other => Err(ApiError::bad_request(format!("unsupported export format '{other}' (use csv or parquet)"))),
+    }
+}
+
+/// Latest model prediction
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/predict/{symbol}", tag = "predictions",
+    params(("symbol" = String, Path, description = "EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT")),
+    responses((status = 200, body = Prediction), (status = 404, body = ErrorBody))
+))]
+async fn prediction(State(mut state): State<AppState>, Path(symbol): Path<String>) -> ApiResult<Prediction> {
+    let fields: HashMap<String, String> = state.redis.hgetall(format!("{PREDICTION_PREFIX}{symbol}")).await?;
+    Prediction::from_fields(&symbol, &fields)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("no prediction for {symbol}")))
+}
+
+/// Latest prediction of each symbol
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/predict", tag = "predictions",
+    params(SymbolsQuery),
+    responses((status = 200, body = Vec<Prediction>))
+))]
+async fn predictions(State(mut state): State<AppState>, Query(q): Query<SymbolsQuery>) -> ApiResult<Vec<Prediction>> {
+    let symbols = q.resolve(&mut state.redis).await?;
+    let found = hashes(&mut state.redis, PREDICTION_PREFIX, &symbols).await?;
+    Ok(Json(
+        symbols
+            .iter()
+            .zip(&found)
+            .filter_map(|(s, f)| Prediction::from_fields(s, f))
+            .collect(),
+    ))
+}
+
+/// Current trading signal
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/signals/{symbol}", tag = "predictions",
+    params(("symbol" = String, Path, description = "EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT")),
+    responses((status = 200, body = Signal), (status = 404, body = ErrorBody))
+))]
+async fn signal(State(mut state): State<AppState>, Path(symbol): Path<String>) -> ApiResult<Signal> {
+    let fields: HashMap<String, String> = state.redis.hgetall(format!("{SIGNAL_PREFIX}{symbol}")).await?;
+    Signal::from_fields(&symbol, &fields)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("no signal for {symbol}")))
+}
+
+/// Current signal of each symbol
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/signals", tag = "predictions",
+    params(SymbolsQuery),
+    responses((status = 200, body = Vec<Signal>))
+))]
+async fn signals(State(mut state): State<AppState>, Query(q): Query<SymbolsQuery>) -> ApiResult<Vec<Signal>> {
+    let symbols = q.resolve(&mut state.redis).await?;
+    let found = hashes(&mut state.redis, SIGNAL_PREFIX, &symbols).await?;
+    Ok(Json(
+        symbols
+            .iter()
+            .zip(&found)
+            .filter_map(|(s, f)| Signal::from_fields(s, f))
+            .collect(),
+    ))
+}
+
+/// What the live model for `symbol` currently relies on, most important feature first
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/importance/{symbol}", tag = "predictions",
+    params(("symbol" = String, Path, description = "EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT")),
+    responses((status = 200, body = FeatureImportance), (status = 404, body = ErrorBody))
+))]
+async fn importance(State(mut state): State<AppState>, Path(symbol): Path<String>) -> ApiResult<FeatureImportance> {
+    let fields: HashMap<String, String> = state.redis.hgetall(format!("{IMPORTANCE_PREFIX}{symbol}")).await?;
+    FeatureImportance::from_fields(&symbol, &fields)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("no feature importance for {symbol}")))
+}
+
+/// Feature importance of each symbol's live model
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/importance", tag = "predictions",
+    params(SymbolsQuery),
+    responses((status = 200, body = Vec<FeatureImportance>))
+))]
+async fn importances(
+    State(mut state): State<AppState>,
+    Query(q): Query<SymbolsQuery>,
+) -> ApiResult<Vec<FeatureImportance>> {
+    let symbols = q.resolve(&mut state.redis).await?;
+    let found = hashes(&mut state.redis, IMPORTANCE_PREFIX, &symbols).await?;
+    Ok(Json(
+        symbols
+            .iter()
+            .zip(&found)
+            .filter_map(|(s, f)| FeatureImportance::from_fields(s, f))
+            .collect(),
+    ))
+}
+
+/// Prometheus text exposition of the `stock:metrics:*` hash of every component in `stock:metrics_components`
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/metrics", tag = "operations",
+    responses((status = 200, content_type = "text/plain", body = String))
+))]
+async fn metrics(State(mut state): State<AppState>) -> Result<String, ApiError> {
+    let mut components: Vec<String> = state.redis.smembers(METRICS_COMPONENTS_KEY).await?;
+    components.sort();
+    let found = hashes(&mut state.redis, METRICS_PREFIX, &components).await?;
+
+    let mut out = String::new();
+    for (component, fields) in components.iter().zip(found) {
+        let mut fields: Vec<_> = fields.into_iter().collect();
+        fields.sort();
+        for (name, value) in fields {
+            out.push_str(&format!("{component}_{name} {value}\n"));
+        }
+    }
+    // The API's own connection, live rather than flushed
+    for (name, value) in state.redis.stats().fields() {
+        out.push_str(&format!("api_{name} {value}\n"));
+    }
+    Ok(out)
+}
+
+/// Pipeline snapshot: service health, feed freshness, last fetcher insert and maintenance run
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/status", tag = "operations",
+    responses((status = 200, body = Status))
+))]
+async fn pipeline_status(State(mut state): State<AppState>) -> Result<Json<Status>, ApiError> {
+    let queues = Queues {
+        live_backlog: state.live.len(),
+        live_clients: state.live.receiver_count(),
+    };
+    Ok(Json(status::snapshot(&mut state.redis, queues).await?))
+}
+
+/// `?symbols=A,B&streams=trades,predictions`; every symbol / stream when absent
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct SseQuery {
+    pub symbols: Option<String>,
+    pub streams: Option<String>,
+}
+
+fn split(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn sse_events(
+    state: AppState,
+    principal: &Principal,
+    symbols: Vec<String>,
+    streams: Option<String>,
+) -> Result<Response, ApiError> {
+    let requested = streams
+        .as_deref()
+        .map(|list| split(list).map(|s| s.parse::<Stream>()).collect::<Result<Vec<_>, _>>())
+        .transpose()
+        .map_err(ApiError::bad_request)?;
+    let streams = Stream::resolve(requested, principal).map_err(|e| ApiError(StatusCode::FORBIDDEN, e))?;
+    let events = fanout::events(state.live.subscribe(), symbols, streams);
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()).into_response())
+}
+
+/// Live updates as Server-Sent Events, for clients that cannot use `/ws`
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/sse", tag = "live",
+    params(SseQuery),
+    responses(
+        (status = 200, content_type = "text/event-stream", description = "`trade`, `bar`, `prediction`, `signal` and `lagged` events"),
+        (status = 403, body = ErrorBody),
+    )
+))]
+async fn sse(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Query(q): Query<SseQuery>,
+) -> Result<Response, ApiError> {
+    let symbols = match q.symbols.as_deref() {
+        Some(list) => split(list).map(str::to_string).collect(),
+        None => vec!["*".to_string()],
+    };
+    sse_events(state, &principal, symbols, q.streams)
+}
+
+/// `/sse` for one symbol
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/sse/{symbol}", tag = "live",
+    params(("symbol" = String, Path, description = "EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT"), SseQuery),
+    responses(
+        (status = 200, content_type = "text/event-stream", description = "Events for this symbol"),
+        (status = 403, body = ErrorBody),
+    )
+))]
+async fn sse_symbol(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(symbol): Path<String>,
+    Query(q): Query<SseQuery>,
+) -> Result<Response, ApiError> {
+    sse_events(state, &principal, vec![symbol], q.streams)
+}
+
+/// WebSocket upgrade; send `{"op":"subscribe","symbols":[..],"streams":[..]}` to receive pushes
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/ws", tag = "live",
+    responses((status = 101, description = "Switching to the WebSocket protocol"))
+))]
+async fn ws(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    quota: Option<Extension<Quota>>,
+) -> Response {
+    let rx = state.live.subscribe();
+    let quota = quota.map(|q| q.0);
+    ws.on_upgrade(move |socket| fanout::serve(socket, rx, principal, quota))
+}
+
+/// Routes are grouped by the permission they need; the live streams and `/metrics`
+/// only need a valid caller, and the streams check permissions per subscription
+pub fn router(state: AppState) -> Router {
+    let guard = |needs: Option<Permission>| {
+        let g = Guard {
+            auth: state.auth.clone(),
+            limiter: state.limiter.clone(),
+            needs,
+        };
+        middleware::from_fn_with_state(g, auth::guard)
+    };
+
+    let prices = Router::new()
+        .route("/symbols", get(symbols))
+        .route("/prices", get(prices))
+        .route("/prices/{symbol}", get(price))
+        .route("/ohlcv/{symbol}", get(ohlcv))
+        .route("/ticks/{symbol}", get(ticks))
+        .route("/history/{symbol}", get(history))
+        .route("/aggregate/{symbol}", get(aggregate))
+        .route("/market/summary", get(market_summary))
+        .route("/export/{file}", get(export))
+        .route_layer(guard(Some(Permission::ReadPrices)));
+    let predictions = Router::new()
+        .route("/predict", get(predictions))
+        .route("/predict/{symbol}", get(prediction))
+        .route("/signals", get(signals))
+        .route("/signals/{symbol}", get(signal))
+        .route("/importance", get(importances))
+        .route("/importance/{symbol}", get(importance))
+        .route_layer(guard(Some(Permission::ReadPredictions)));
+    let manage = Router::new()
+        .route("/symbols", post(add_symbol))
+        .route("/symbols/{symbol}", delete(remove_symbol))
+        .route_layer(guard(Some(Permission::ManageSymbols)));
+    let hooks = Router::new()
+        .route("/webhooks", get(list_webhooks).post(add_webhook))
+        .route("/webhooks/{id}", delete(remove_webhook))
+        .route_layer(guard(Some(Permission::ManageWebhooks)));
+    let live = Router::new()
+        .route("/ws", get(ws))
+        .route("/sse", get(sse))
+        .route("/sse/{symbol}", get(sse_symbol))
+        .route("/metrics", get(metrics))
+        .route("/status", get(pipeline_status));
+    #[cfg(feature = "graphql")]
+    let live = crate::graphql::mount(live, state.clone());
+    let live = live.route_layer(guard(None));
+    let docs = Router::new();
+    #[cfg(feature = "openapi")]
+    let docs = crate::openapi::mount(docs);
+
+    Router::new()
+        .merge(health::routes(state.health.clone()))
+        .merge(dashboard::routes())
+        .merge(docs)
+        .merge(prices)
+        .merge(predictions)
+        .merge(manage)
+        .merge(hooks)
+        .merge(live)
+        .with_state(state)
+}
@@ -0,0 +1,312 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    env, fmt,
+    net::SocketAddr,
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::{
+    extract::{ConnectInfo, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    api::ApiError,
+    ratelimit::{Quota, RateLimiter},
+};
+
+/// Query parameter carrying the key or token where headers cannot be set
+/// (browser `WebSocket` and `EventSource`)
+pub const TOKEN_PARAM: &str = "api_key";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Symbols, prices, OHLCV, bars, history, and the trade/bar streams
+    ReadPrices,
+    /// Predictions, signals, feature importance, and their streams
+    ReadPredictions,
+    /// `POST /symbols` and `DELETE /symbols/{symbol}`
+    ManageSymbols,
+    /// Registering, listing and removing the caller's own webhooks
+    ManageWebhooks,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 4] = [
+        Permission::ReadPrices,
+        Permission::ReadPredictions,
+        Permission::ManageSymbols,
+        Permission::ManageWebhooks,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ReadPrices => "read_prices",
+            Self::ReadPredictions => "read_predictions",
+            Self::ManageSymbols => "manage_symbols",
+            Self::ManageWebhooks => "manage_webhooks",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.as_str() == s)
+            .ok_or_else(|| format!("unknown permission '{s}' (use read_prices, read_predictions, manage_symbols or manage_webhooks)"))
+    }
+}
+
+/// `"*"` grants every permission
+fn permissions<'de, D: serde::Deserializer<'de>>(d: D) -> Result<BTreeSet<Permission>, D::Error> {
+    let names: Vec<String> = Vec::deserialize(d)?;
+    let mut out = BTreeSet::new();
+    for name in names {
+        if name == "*" {
+            out.extend(Permission::ALL);
+        } else {
+            out.insert(name.parse().map_err(serde::de::Error::custom)?);
+        }
+    }
+    Ok(out)
+}
+
+/// Who is calling and what they may do; inserted into request extensions by `guard`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Principal {
+    pub name: String,
+    #[serde(deserialize_with = "permissions")]
+    pub permissions: BTreeSet<Permission>,
+    /// Overrides `RATE_LIMIT_PER_SEC` for this caller
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_per_sec: Option<f64>,
+}
+
+impl Principal {
+    /// Everything allowed; used when auth is disabled
+    pub fn open() -> Self {
+        Self {
+            name: "anonymous".to_string(),
+            permissions: Permission::ALL.into_iter().collect(),
+            rate_per_sec: None,
+        }
+    }
+
+    pub fn allows(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    pub fn require(&self, permission: Permission) -> Result<(), ApiError> {
+        if self.allows(permission) {
+            Ok(())
+        } else {
+            Err(ApiError(
+                StatusCode::FORBIDDEN,
+                format!("'{}' lacks the {permission} permission", self.name),
+            ))
+        }
+    }
+}
+
+/// One entry of `API_KEYS_FILE`
+#[derive(Debug, Deserialize)]
+struct KeyEntry {
+    key: String,
+    #[serde(flatten)]
+    principal: Principal,
+}
+
+/// HS256 claims; `exp` is required
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    exp: i64,
+    #[serde(default, deserialize_with = "permissions")]
+    permissions: BTreeSet<Permission>,
+    #[serde(default)]
+    rate_per_sec: Option<f64>,
+}
+
+/// Static API keys and/or HS256 JWTs
+#[derive(Debug, Default)]
+pub struct Auth {
+    keys: HashMap<String, Principal>,
+    jwt_secret: Option<Vec<u8>>,
+}
+
+impl Auth {
+    /// `API_KEYS_FILE` (JSON `[{"name","key","permissions"}]`) and `JWT_SECRET`;
+    /// `None` when neither is set, which leaves the API open
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let mut auth = Self::default();
+        if let Ok(path) = env::var("API_KEYS_FILE") {
+            let path = Path::new(&path);
+            let json = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+            let entries: Vec<KeyEntry> =
+                serde_json::from_str(&json).map_err(|e| format!("invalid API keys in {}: {e}", path.display()))?;
+            auth.keys = entries.into_iter().map(|e| (e.key, e.principal)).collect();
+        }
+        auth.jwt_secret = env::var("JWT_SECRET").ok().filter(|s| !s.is_empty()).map(String::into_bytes);
+
+        if auth.keys.is_empty() && auth.jwt_secret.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(auth))
+    }
+
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn jwt_enabled(&self) -> bool {
+        self.jwt_secret.is_some()
+    }
+
+    /// A static key, or a JWT when it has three dot-separated parts
+    pub fn authenticate(&self, token: &str) -> Result<Principal, String> {
+        if let Some(principal) = self.keys.get(token) {
+            return Ok(principal.clone());
+        }
+        match &self.jwt_secret {
+            Some(secret) if token.split('.').count() == 3 => verify_jwt(secret, token),
+            _ => Err("invalid API key".to_string()),
+        }
+    }
+}
+
+fn verify_jwt(secret: &[u8], token: &str) -> Result<Principal, String> {
+    let decode = |part: &str| URL_SAFE_NO_PAD.decode(part).map_err(|_| "malformed token".to_string());
+    let mut parts = token.split('.');
+    let (Some(header), Some(payload), Some(signature)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err("malformed token".to_string());
+    };
+
+    #[derive(Deserialize)]
+    struct Header {
+        alg: String,
+    }
+    let h: Header = serde_json::from_slice(&decode(header)?).map_err(|_| "malformed token header".to_string())?;
+    if h.alg != "HS256" {
+        return Err(format!("unsupported token algorithm {}", h.alg));
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(format!("{header}.{payload}").as_bytes());
+    mac.verify_slice(&decode(signature)?)
+        .map_err(|_| "invalid token signature".to_string())?;
+
+    let claims: Claims = serde_json::from_slice(&decode(payload)?).map_err(|e| format!("invalid token claims: {e}"))?;
+    if claims.exp <= Utc::now().timestamp() {
+        return Err("token expired".to_string());
+    }
+    Ok(Principal {
+        name: claims.sub,
+        permissions: claims.permissions,
+        rate_per_sec: claims.rate_per_sec,
+    })
+}
+
+/// `Authorization: Bearer …`, `X-API-Key: …`, or `?api_key=…`
+fn credential(headers: &HeaderMap, query: &HashMap<String, String>) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+        .map(|s| s.trim().to_string())
+        .or_else(|| query.get(TOKEN_PARAM).cloned())
+}
+
+/// Middleware state for one group of routes
+#[derive(Clone)]
+pub struct Guard {
+    /// `None` leaves the routes open
+    pub auth: Option<Arc<Auth>>,
+    /// `None` disables rate limiting
+    pub limiter: Option<Arc<RateLimiter>>,
+    /// Permission every route in the group needs
+    pub needs: Option<Permission>,
+}
+
+fn too_many_requests(wait: Duration) -> Response {
+    let mut resp = ApiError(StatusCode::TOO_MANY_REQUESTS, "rate limit exceeded".to_string()).into_response();
+    resp.headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(wait.as_secs_f64().ceil().max(1.0) as u64));
+    resp
+}
+
+/// Resolve the caller into a `Principal` extension and charge its rate limit (per key
+/// when authenticated, per client IP otherwise, failed attempts included); 401 without
+/// valid credentials, 403 when the group's permission is missing, 429 over the limit
+pub async fn guard(State(g): State<Guard>, mut req: Request, next: Next) -> Response {
+    let ip = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|c| c.0.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let principal = match &g.auth {
+        None => Principal::open(),
+        Some(auth) => {
+            let query = Query::<HashMap<String, String>>::try_from_uri(req.uri())
+                .map(|q| q.0)
+                .unwrap_or_default();
+            let result = credential(req.headers(), &query)
+                .ok_or_else(|| "missing API key or bearer token".to_string())
+                .and_then(|token| auth.authenticate(&token));
+            match result {
+                Ok(p) => p,
+                Err(e) => {
+                    if let Some(limiter) = &g.limiter
+                        && let Err(wait) = limiter.check(&format!("ip:{ip}"), None)
+                    {
+                        return too_many_requests(wait);
+                    }
+                    return ApiError(StatusCode::UNAUTHORIZED, e).into_response();
+                }
+            }
+        }
+    };
+
+    if let Some(limiter) = &g.limiter {
+        let quota = Quota {
+            limiter: limiter.clone(),
+            key: match g.auth {
+                Some(_) => format!("key:{}", principal.name),
+                None => format!("ip:{ip}"),
+            },
+            rate_per_sec: principal.rate_per_sec,
+        };
+        if let Err(wait) = quota.check() {
+            return too_many_requests(wait);
+        }
+        req.extensions_mut().insert(quota);
+    }
+    if let Some(needs) = g.needs
+        && let Err(e) = principal.require(needs)
+    {
+        return e.into_response();
+    }
+    req.extensions_mut().insert(principal);
+    next.run(req).await
+}
@@ -0,0 +1,604 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    str::FromStr,
+};
+
+use chrono::{Duration, NaiveDateTime, Timelike, Utc};
+use redis::AsyncCommands;
+use tokio_postgres::{types::ToSql, Client as PgClient, GenericClient};
+
+use tracing::{error, info, warn};
+use crate::{
+    binance::{BinanceClient, MAX_KLINES},
+    cache,
+    config::Config,
+    fetcher::try_connect_pg,
+    finnhub::{Candle, FinnhubClient},
+    history,
+    keys::SYMBOLS_KEY,
+    redis_conn,
+};
+
+
+/// Minutes in `[from, to)` with no persisted row, grouped by symbol
+pub async fn find_gaps(
+    pg: &PgClient,
+    symbols: &[String],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<BTreeMap<String, Vec<NaiveDateTime>>, String> {
+    let rows = pg
+        .query(
+            "SELECT s.symbol, m.minute \
+             FROM stocks s \
+             CROSS JOIN generate_series($2::timestamp, $3::timestamp - interval '1 minute', interval '1 minute') AS m(minute) \
+             WHERE s.symbol = ANY($1) \
+               AND NOT EXISTS ( \
+                 SELECT 1 FROM stock_price_history h \
+                 WHERE h.stock_id = s.id \
+                   AND h.trade_time_stamp >= m.minute \
+                   AND h.trade_time_stamp < m.minute + interval '1 minute') \
+             ORDER BY s.symbol, m.minute",
+            &[&symbols, &from, &to],
+        )
+        .await
+        .map_err(|e| format!("gap query failed: {e}"))?;
+
+    let mut gaps: BTreeMap<String, Vec<NaiveDateTime>> = BTreeMap::new();
+    for r in rows {
+        gaps.entry(r.get(0)).or_default().push(r.get(1));
+    }
+    Ok(gaps)
+}
+
+/// Insert candles as `stock_price_history` rows, returns rows written; Postgres converts
+/// the f64s when the columns are NUMERIC. Each row's volume is its candle's alone, so its
+/// `volume_start` is the candle's open.
+pub async fn insert_candles(
+    pg: &impl GenericClient,
+    stock_id: i32,
+    symbol: &str,
+    candles: &[Candle],
+) -> Result<u64, String> {
+    if candles.is_empty() {
+        return Ok(0);
+    }
+
+    let mut values: Vec<Box<dyn ToSql + Sync>> = Vec::new();
+    let mut placeholders = Vec::new();
+    let mut i = 1;
+
+    for c in candles {
+        placeholders.push(format!(
+            "(${}, ${}, ${}::float8, ${}::float8, ${}::float8, ${}::float8, ${}::float8, ${}, ${})",
+            i, i + 1, i + 2, i + 3, i + 4, i + 5, i + 6, i + 7, i + 8
+        ));
+        i += 9;
+
+        values.push(Box::new(stock_id));
+        values.push(Box::new(symbol.to_string()));
+        values.push(Box::new(c.open));
+        values.push(Box::new(c.high));
+        values.push(Box::new(c.low));
+        values.push(Box::new(c.close));
+        values.push(Box::new(c.volume));
+        values.push(Box::new(c.time));
+        values.push(Box::new(c.time.and_utc().timestamp_millis()));
+    }
+
+    let sql = format!(
+        "INSERT INTO stock_price_history \
+         (stock_id, symbol, open, high, low, close, volume, trade_time_stamp, volume_start) \
+         VALUES {}",
+        placeholders.join(", ")
+    );
+    let params: Vec<&(dyn ToSql + Sync)> =
+        values.iter().map(|v| v.as_ref() as &(dyn ToSql + Sync)).collect();
+
+    pg.execute(&sql, &params)
+        .await
+        .map_err(|e| format!("insert for {symbol} failed: {e}"))
+}
+
+/// Fill missing minutes in `[from, to)` for every active symbol from 1m candles, Binance's
+/// for `BINANCE:` symbols and Finnhub's for the rest
+pub async fn run(config: &Config, from: NaiveDateTime, to: NaiveDateTime) -> Result<u64, String> {
+    info!("🩹 Backfill checking {from} → {to}");
+    let mut redis = redis_conn::try_connect(&config.redis_url(), &config.tls.redis)
+        .await
+        .map_err(|e| e.to_string())?;
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    history::ensure_view(&pg)
+        .await
+        .map_err(|e| format!("could not create {}: {e}", history::HISTORY_VIEW))?;
+    let mut source = CandleSource::new(SourceKind::Auto, config.finnhub_key());
+
+    let symbols: Vec<String> = redis
+        .smembers(SYMBOLS_KEY)
+        .await
+        .map_err(|e| format!("Redis smembers error: {e}"))?;
+    if symbols.is_empty() {
+        warn!("⚠️ No symbols in Redis — nothing to backfill");
+        return Ok(0);
+    }
+
+    let id_map: HashMap<String, i32> = pg
+        .query("SELECT id, symbol FROM stocks WHERE symbol = ANY($1)", &[&symbols])
+        .await
+        .map_err(|e| format!("failed to load stock map: {e}"))?
+        .into_iter()
+        .map(|r| (r.get::<_, String>(1), r.get::<_, i32>(0)))
+        .collect();
+
+    let gaps = find_gaps(&pg, &symbols, from, to).await?;
+    if gaps.is_empty() {
+        info!("✅ No gaps found");
+        return Ok(0);
+    }
+
+    let mut inserted = 0;
+    for (symbol, minutes) in &gaps {
+        let Some(&stock_id) = id_map.get(symbol) else {
+            continue;
+        };
+        let (first, last) = (minutes[0], minutes[minutes.len() - 1]);
+
+        let candles = match source.minutes(symbol, first, last + Duration::minutes(1)).await {
+            Ok(c) => c,
+            Err(e) => {
+                error!("❌ {e}");
+                continue;
+            }
+        };
+        let missing: Vec<Candle> = candles
+            .into_iter()
+            .filter(|c| minutes.contains(&c.time))
+            .collect();
+
+        match insert_candles(&pg, stock_id, symbol, &missing).await {
+            Ok(n) => {
+                info!("🩹 {symbol}: {} gap minutes, {n} backfilled", minutes.len());
+                inserted += n;
+                if n > 0
+                    && let Err(e) = cache::invalidate(&mut redis, &[symbol]).await
+                {
+                    warn!("⚠️ Could not invalidate cached history for {symbol}: {e}");
+                }
+            }
+            Err(e) => error!("❌ {e}"),
+        }
+    }
+
+    info!("✨ Backfill finished: {inserted} rows");
+    Ok(inserted)
+}
+
+// -----------------------------------HISTORICAL LOAD------------------------------------------------------------------------------
+
+/// Which REST API [`load_range`] pulls 1m candles from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// Binance's public klines for `BINANCE:` symbols, Finnhub for the rest
+    Auto,
+    Binance,
+    Finnhub,
+}
+
+impl FromStr for SourceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "binance" => Ok(Self::Binance),
+            "finnhub" => Ok(Self::Finnhub),
+            other => Err(format!("unknown source '{other}' (auto, binance or finnhub)")),
+        }
+    }
+}
+
+/// Paced REST clients, created on first use so Binance-only runs need no Finnhub key
+pub struct CandleSource {
+    kind: SourceKind,
+    finnhub_key: Option<String>,
+    binance: Option<BinanceClient>,
+    finnhub: Option<FinnhubClient>,
+}
+
+impl CandleSource {
+    /// `finnhub_key` is only needed for symbols Finnhub serves; without it they fail
+    pub fn new(kind: SourceKind, finnhub_key: Option<String>) -> Self {
+        Self {
+            kind,
+            finnhub_key,
+            binance: None,
+            finnhub: None,
+        }
+    }
+
+    /// Which API serves `symbol`
+    pub fn resolve(&self, symbol: &str) -> SourceKind {
+        match self.kind {
+            SourceKind::Auto if symbol.starts_with("BINANCE:") => SourceKind::Binance,
+            SourceKind::Auto => SourceKind::Finnhub,
+            kind => kind,
+        }
+    }
+
+    /// 1m candles for `symbol` opening in `[from, to)`; at most [`MAX_KLINES`] minutes per call
+    pub async fn minutes(&mut self, symbol: &str, from: NaiveDateTime, to: NaiveDateTime) -> Result<Vec<Candle>, String> {
+        match self.resolve(symbol) {
+            SourceKind::Binance => {
+                let pair = symbol.split_once(':').map_or(symbol, |(_, pair)| pair);
+                self.binance
+                    .get_or_insert_with(BinanceClient::new)
+                    .klines(pair, "1m", from.and_utc(), to.and_utc())
+                    .await
+            }
+            // Finnhub's range is inclusive
+            _ => {
+                let finnhub = match &mut self.finnhub {
+                    Some(client) => client,
+                    None => {
+                        let key = self
+                            .finnhub_key
+                            .clone()
+                            .ok_or_else(|| format!("{symbol} candles need a Finnhub API key (FINNHUB_API_KEY)"))?;
+                        self.finnhub.insert(FinnhubClient::new(key))
+                    }
+                };
+                finnhub
+                    .crypto_candles(symbol, "1", from.and_utc(), (to - Duration::seconds(1)).and_utc())
+                    .await
+            }
+        }
+    }
+}
+
+/// Checkpoints of [`load_range`], one per symbol and start
+pub async fn ensure_table(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS backfill_progress ( \
+            symbol      TEXT        NOT NULL, \
+            range_from  TIMESTAMP   NOT NULL, \
+            done_until  TIMESTAMP   NOT NULL, \
+            source      TEXT        NOT NULL, \
+            rows        BIGINT      NOT NULL DEFAULT 0, \
+            updated_at  TIMESTAMPTZ NOT NULL DEFAULT now(), \
+            PRIMARY KEY (symbol, range_from))",
+    )
+    .await
+}
+
+/// `symbol`'s `stocks` id, registering it inactive (not streamed) when it is new
+async fn stock_id(pg: &PgClient, symbol: &str) -> Result<i32, String> {
+    let found = pg
+        .query_opt("SELECT id FROM stocks WHERE symbol = $1", &[&symbol])
+        .await
+        .map_err(|e| format!("stocks query failed: {e}"))?;
+    if let Some(row) = found {
+        return Ok(row.get(0));
+    }
+    info!("📝 Registering {symbol} in stocks (inactive)");
+    pg.query_one("INSERT INTO stocks (symbol, active) VALUES ($1, false) RETURNING id", &[&symbol])
+        .await
+        .map(|r| r.get(0))
+        .map_err(|e| format!("stocks insert failed: {e}"))
+}
+
+/// Load `symbol`'s 1m candles for `[from, to)` into `stock_price_history`, skipping minutes
+/// that already have rows. Each chunk commits with its checkpoint, so a rerun with the same
+/// `from` continues where the last one stopped; `restart` discards the checkpoint.
+pub async fn load_range(
+    pg: &mut PgClient,
+    source: &mut CandleSource,
+    symbol: &str,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    restart: bool,
+) -> Result<u64, String> {
+    let stock_id = stock_id(pg, symbol).await?;
+    let source_name = format!("{:?}", source.resolve(symbol)).to_lowercase();
+
+    let mut cursor = from;
+    let mut total: u64 = 0;
+    if restart {
+        pg.execute(
+            "DELETE FROM backfill_progress WHERE symbol = $1 AND range_from = $2",
+            &[&symbol, &from],
+        )
+        .await
+        .map_err(|e| format!("checkpoint reset failed: {e}"))?;
+    } else if let Some(row) = pg
+        .query_opt(
+            "SELECT done_until, rows FROM backfill_progress WHERE symbol = $1 AND range_from = $2",
+            &[&symbol, &from],
+        )
+        .await
+        .map_err(|e| format!("checkpoint query failed: {e}"))?
+    {
+        cursor = row.get(0);
+        total = row.get::<_, i64>(1) as u64;
+        if cursor >= to {
+            info!("✅ {symbol}: already loaded through {cursor}");
+            return Ok(0);
+        }
+        info!("⏩ {symbol}: resuming at {cursor} ({total} rows so far)");
+    }
+
+    let mut inserted = 0;
+    while cursor < to {
+        let chunk_end = (cursor + Duration::minutes(MAX_KLINES as i64)).min(to);
+        let candles = source.minutes(symbol, cursor, chunk_end).await?;
+
+        let tx = pg.transaction().await.map_err(|e| format!("transaction failed: {e}"))?;
+        let present: HashSet<NaiveDateTime> = tx
+            .query(
+                "SELECT DISTINCT date_trunc('minute', trade_time_stamp) FROM stock_price_history \
+                 WHERE stock_id = $1 AND trade_time_stamp >= $2 AND trade_time_stamp < $3",
+                &[&stock_id, &cursor, &chunk_end],
+            )
+            .await
+            .map_err(|e| format!("existing rows query failed: {e}"))?
+            .into_iter()
+            .map(|r| r.get(0))
+            .collect();
+        let missing: Vec<Candle> = candles
+            .into_iter()
+            .filter(|c| c.time >= cursor && c.time < chunk_end && !present.contains(&c.time))
+            .collect();
+        let n = insert_candles(&tx, stock_id, symbol, &missing).await?;
+        tx.execute(
+            "INSERT INTO backfill_progress (symbol, range_from, done_until, source, rows) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (symbol, range_from) DO UPDATE \
+             SET done_until = EXCLUDED.done_until, source = EXCLUDED.source, \
+                 rows = EXCLUDED.rows, updated_at = now()",
+            &[&symbol, &from, &chunk_end, &source_name, &((total + n) as i64)],
+        )
+        .await
+        .map_err(|e| format!("checkpoint write failed: {e}"))?;
+        tx.commit().await.map_err(|e| format!("commit failed: {e}"))?;
+
+        inserted += n;
+        total += n;
+        info!("🩹 {symbol}: loaded through {chunk_end} (+{n}, {total} rows)");
+        cursor = chunk_end;
+    }
+    Ok(inserted)
+}
+
+// -----------------------------------GAP REPAIR------------------------------------------------------------------------------
+
+// Repairs that still leave minutes missing; after this many the gap is left alone
+const MAX_REPAIR_ATTEMPTS: i32 = 3;
+// The fetcher may not have snapshotted the last couple of minutes yet
+const SETTLE_MINUTES: i64 = 2;
+
+/// Missing ranges [`repair_gaps`] found, one row per symbol and first missing minute.
+/// `status` is `open`, `repaired` or `unrepairable` (the source had no candles for it).
+pub async fn ensure_gaps_table(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS price_gaps ( \
+            id          BIGSERIAL   PRIMARY KEY, \
+            symbol      TEXT        NOT NULL, \
+            gap_start   TIMESTAMP   NOT NULL, \
+            gap_end     TIMESTAMP   NOT NULL, \
+            minutes     INTEGER     NOT NULL, \
+            status      TEXT        NOT NULL DEFAULT 'open', \
+            attempts    INTEGER     NOT NULL DEFAULT 0, \
+            rows_filled BIGINT      NOT NULL DEFAULT 0, \
+            last_error  TEXT, \
+            detected_at TIMESTAMPTZ NOT NULL DEFAULT now(), \
+            repaired_at TIMESTAMPTZ, \
+            UNIQUE (symbol, gap_start)); \
+         CREATE INDEX IF NOT EXISTS price_gaps_status_idx ON price_gaps (status, symbol)",
+    )
+    .await
+}
+
+/// Consecutive minutes as `[start, end)` ranges
+fn ranges(minutes: &[NaiveDateTime]) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut out: Vec<(NaiveDateTime, NaiveDateTime)> = Vec::new();
+    for &m in minutes {
+        match out.last_mut() {
+            Some((_, end)) if *end == m => *end = m + Duration::minutes(1),
+            _ => out.push((m, m + Duration::minutes(1))),
+        }
+    }
+    out
+}
+
+/// Oldest minute the cleaner keeps: the last maintenance start when it empties the table daily
+fn retention_cutoff(config: &Config, now: NaiveDateTime) -> NaiveDateTime {
+    match config.retention.history_days {
+        0 => {
+            let today = now.date().and_time(config.schedules.maintenance_start);
+            if today <= now { today } else { today - Duration::days(1) }
+        }
+        days => now - Duration::days(days as i64),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GapReport {
+    pub found: usize,
+    pub repaired: usize,
+    pub unrepairable: usize,
+    pub rows: u64,
+}
+
+/// Fill `[start, end)` for one symbol; minutes the source has no candle for stay missing
+async fn repair(
+    pg: &PgClient,
+    source: &mut CandleSource,
+    stock_id: i32,
+    symbol: &str,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Result<u64, String> {
+    let mut filled = 0;
+    let mut cursor = start;
+    while cursor < end {
+        let chunk_end = (cursor + Duration::minutes(MAX_KLINES as i64)).min(end);
+        let candles: Vec<Candle> = source
+            .minutes(symbol, cursor, chunk_end)
+            .await?
+            .into_iter()
+            .filter(|c| c.time >= cursor && c.time < chunk_end)
+            .collect();
+        filled += insert_candles(pg, stock_id, symbol, &candles).await?;
+        cursor = chunk_end;
+    }
+    Ok(filled)
+}
+
+/// Scan the last `gap_lookback_hours` of every tracked symbol for minutes without a row,
+/// record them in `price_gaps` and fill them from exchange REST candles. Minutes before a
+/// symbol's first row in the window, before the retention cutoff or inside gaps already
+/// marked unrepairable are not gaps.
+pub async fn repair_gaps(config: &Config) -> Result<GapReport, String> {
+    let mut redis = redis_conn::try_connect(&config.redis_url(), &config.tls.redis)
+        .await
+        .map_err(|e| e.to_string())?;
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    ensure_gaps_table(&pg)
+        .await
+        .map_err(|e| format!("failed to create price_gaps: {e}"))?;
+
+    let now = Utc::now().naive_utc();
+    let now = now.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(now);
+    let to = now - Duration::minutes(SETTLE_MINUTES);
+    let from = (to - Duration::hours(config.schedules.gap_lookback_hours as i64)).max(retention_cutoff(config, now));
+    if from >= to {
+        return Ok(GapReport::default());
+    }
+
+    let symbols: Vec<String> = redis
+        .smembers(SYMBOLS_KEY)
+        .await
+        .map_err(|e| format!("Redis smembers error: {e}"))?;
+    if symbols.is_empty() {
+        return Ok(GapReport::default());
+    }
+    let first_rows: HashMap<String, (i32, NaiveDateTime)> = pg
+        .query(
+            "SELECT s.symbol, s.id, min(h.trade_time_stamp) FROM stocks s \
+             JOIN stock_price_history h ON h.stock_id = s.id \
+             WHERE s.symbol = ANY($1) AND h.trade_time_stamp >= $2 AND h.trade_time_stamp < $3 \
+             GROUP BY s.symbol, s.id",
+            &[&symbols, &from, &to],
+        )
+        .await
+        .map_err(|e| format!("first row query failed: {e}"))?
+        .into_iter()
+        .map(|r| (r.get(0), (r.get(1), r.get(2))))
+        .collect();
+    let mut settled: HashMap<String, Vec<(NaiveDateTime, NaiveDateTime)>> = HashMap::new();
+    for r in pg
+        .query(
+            "SELECT symbol, gap_start, gap_end FROM price_gaps \
+             WHERE status = 'unrepairable' AND symbol = ANY($1) AND gap_end > $2",
+            &[&symbols, &from],
+        )
+        .await
+        .map_err(|e| format!("price_gaps query failed: {e}"))?
+    {
+        settled.entry(r.get(0)).or_default().push((r.get(1), r.get(2)));
+    }
+
+    let mut report = GapReport::default();
+    let mut source = CandleSource::new(SourceKind::Auto, config.finnhub_key());
+    for (symbol, minutes) in find_gaps(&pg, &symbols, from, to).await? {
+        let Some(&(stock_id, first)) = first_rows.get(&symbol) else {
+            continue;
+        };
+        let skip = settled.get(&symbol);
+        let minutes: Vec<NaiveDateTime> = minutes
+            .into_iter()
+            .filter(|m| *m >= first && !skip.is_some_and(|s| s.iter().any(|(a, b)| m >= a && m < b)))
+            .collect();
+
+        let mut filled_any = false;
+        for (start, end) in ranges(&minutes) {
+            report.found += 1;
+            let count = (end - start).num_minutes() as i32;
+            // A gap that reappears after a repair (rows deleted, partial fill) is open again
+            let row = pg
+                .query_one(
+                    "INSERT INTO price_gaps (symbol, gap_start, gap_end, minutes) VALUES ($1, $2, $3, $4) \
+                     ON CONFLICT (symbol, gap_start) DO UPDATE \
+                     SET gap_end = EXCLUDED.gap_end, minutes = EXCLUDED.minutes, status = 'open' \
+                     RETURNING id, attempts",
+                    &[&symbol, &start, &end, &count],
+                )
+                .await
+                .map_err(|e| format!("price_gaps insert failed: {e}"))?;
+            let (id, attempts): (i64, i32) = (row.get(0), row.get(1));
+
+            let (filled, error) = match repair(&pg, &mut source, stock_id, &symbol, start, end).await {
+                Ok(n) => (n, None),
+                Err(e) => (0, Some(e)),
+            };
+            let status = if filled as i32 >= count {
+                report.repaired += 1;
+                info!("🩹 {symbol}: repaired {count} missing minutes from {start}");
+                "repaired"
+            } else if attempts + 1 >= MAX_REPAIR_ATTEMPTS {
+                report.unrepairable += 1;
+                warn!("⚠️ {symbol}: {} of {count} minutes from {start} could not be repaired; giving up", count - filled as i32);
+                "unrepairable"
+            } else {
+                if let Some(e) = &error {
+                    warn!("⚠️ {symbol}: gap from {start} not repaired yet: {e}");
+                }
+                "open"
+            };
+            pg.execute(
+                "UPDATE price_gaps SET status = $2, attempts = attempts + 1, rows_filled = rows_filled + $3, \
+                 last_error = $4, repaired_at = CASE WHEN $2 = 'repaired' THEN now() END \
+                 WHERE id = $1",
+                &[&id, &status, &(filled as i64), &error],
+            )
+            .await
+            .map_err(|e| format!("price_gaps update failed: {e}"))?;
+            report.rows += filled;
+            filled_any |= filled > 0;
+        }
+        if filled_any && let Err(e) = cache::invalidate(&mut redis, &[&symbol]).await {
+            warn!("⚠️ Could not invalidate cached history for {symbol}: {e}");
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(day: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn retention_cutoff_is_the_last_clean_when_the_table_is_emptied_daily() {
+        // Maintenance starts at 05:00
+        let config = Config::default();
+        assert_eq!(retention_cutoff(&config, at(2, 6, 0)), at(2, 5, 0));
+        assert_eq!(retention_cutoff(&config, at(2, 5, 0)), at(2, 5, 0));
+        assert_eq!(retention_cutoff(&config, at(2, 4, 59)), at(1, 5, 0));
+    }
+
+    #[test]
+    fn retention_cutoff_keeps_the_configured_days() {
+        let mut config = Config::default();
+        config.retention.history_days = 7;
+        assert_eq!(retention_cutoff(&config, at(10, 4, 30)), at(3, 4, 30));
+    }
+}
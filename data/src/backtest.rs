@@ -0,0 +1,357 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use chrono::NaiveDateTime;
+use tokio_postgres::Client as PgClient;
+
+use tracing::warn;
+use crate::{
+    bars::{BarEngine, Timeframe},
+    correlation::Benchmarks,
+    features::{FeatureExtractor, FEATURE_NAMES},
+    history,
+    importance::FeatureImportance,
+    models::ModelSpec,
+    normalize::{Method, Normalizer},
+};
+
+/// One replayed trade
+#[derive(Debug, Clone)]
+pub struct Tick {
+    pub symbol: String,
+    pub ts_ms: i64,
+    pub price: f64,
+    pub volume: f64,
+}
+
+/// Turn persisted OHLCV snapshots into ticks. Snapshots carry the latest trade
+/// price as `close` and the ingester's running `volume` total, so volume is differenced
+/// per symbol and rows without a new trade timestamp are dropped. Backfilled candles, the
+/// rows with a `volume_start`, count in full, and the snapshot after one adds nothing.
+pub fn ticks_from_snapshots<I>(rows: I) -> Vec<Tick>
+where
+    I: IntoIterator<Item = (String, NaiveDateTime, f64, f64, Option<i64>)>,
+{
+    let mut last: HashMap<String, (NaiveDateTime, f64, Option<i64>)> = HashMap::new();
+    let mut ticks = Vec::new();
+
+    for (symbol, ts, close, volume, volume_start) in rows {
+        let traded = match last.get(&symbol) {
+            Some(&(prev_ts, _, _)) if ts <= prev_ts => None,
+            _ if volume_start.is_some() => Some(volume),
+            Some(&(_, prev_vol, None)) => Some(if volume >= prev_vol { volume - prev_vol } else { volume }),
+            _ => Some(0.0),
+        };
+        if let Some(v) = traded {
+            ticks.push(Tick {
+                symbol: symbol.clone(),
+                ts_ms: ts.and_utc().timestamp_millis(),
+                price: close,
+                volume: v,
+            });
+            last.insert(symbol, (ts, volume, volume_start));
+        }
+    }
+
+    ticks
+}
+
+/// Ticks from `stock_price_history` (through its readers' view) in `[from, to)`; all symbols when `symbols` is empty
+pub async fn load_history(
+    pg: &PgClient,
+    symbols: &[String],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<Vec<Tick>, String> {
+    history::ensure_view(pg)
+        .await
+        .map_err(|e| format!("could not create {}: {e}", history::HISTORY_VIEW))?;
+    let rows = pg
+        .query(
+            "SELECT symbol, trade_time_stamp, close, volume, volume_start FROM stock_price_history_current \
+             WHERE trade_time_stamp >= $1 AND trade_time_stamp < $2 \
+               AND (cardinality($3::text[]) = 0 OR symbol = ANY($3)) \
+             ORDER BY trade_time_stamp",
+            &[&from, &to, &symbols],
+        )
+        .await
+        .map_err(|e| format!("history query failed: {e}"))?;
+
+    Ok(ticks_from_snapshots(
+        rows.into_iter().map(|r| (r.get(0), r.get(1), r.get(2), r.get(3), r.get(4))),
+    ))
+}
+
+/// Ticks from a `scripts/push.py` CSV export (header row required)
+pub fn load_csv(path: &Path, symbols: &[String]) -> Result<Vec<Tick>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    let mut lines = text.lines();
+    let header: Vec<&str> = lines.next().ok_or("empty CSV")?.split(',').collect();
+    let col = |name: &str| {
+        header
+            .iter()
+            .position(|h| *h == name)
+            .ok_or(format!("CSV is missing column '{name}'"))
+    };
+    let (c_sym, c_ts, c_close, c_vol) =
+        (col("symbol")?, col("trade_time_stamp")?, col("close")?, col("volume")?);
+    // Exports from before the column have running-total volumes
+    let c_start = col("volume_start").ok();
+
+    let mut rows = Vec::new();
+    for line in lines {
+        let f: Vec<&str> = line.split(',').collect();
+        let parsed = (|| {
+            let symbol = f.get(c_sym)?.to_string();
+            let ts = NaiveDateTime::parse_from_str(f.get(c_ts)?, "%Y-%m-%d %H:%M:%S%.f").ok()?;
+            let volume_start = c_start.and_then(|c| f.get(c)?.parse().ok());
+            Some((symbol, ts, f.get(c_close)?.parse().ok()?, f.get(c_vol)?.parse().ok()?, volume_start))
+        })();
+        match parsed {
+            Some(row) if symbols.is_empty() || symbols.contains(&row.0) => rows.push(row),
+            Some(_) => {}
+            None => warn!("⚠️ Skipping malformed CSV row: {line}"),
+        }
+    }
+    rows.sort_by_key(|r| r.1);
+    Ok(ticks_from_snapshots(rows))
+}
+
+/// Per-symbol accuracy and PnL of a sign-following strategy
+#[derive(Debug, Clone, Default)]
+pub struct SymbolReport {
+    pub bars: u64,
+    pub predictions: u64,
+    pub hits: u64,
+    pub abs_err: f64,
+    pub sq_err: f64,
+    /// Cumulative log return of holding sign(prediction) each bar, net of fees
+    pub pnl: f64,
+    pub position_changes: u64,
+    pub max_drawdown: f64,
+    /// What the model relied on by the end of the replay
+    pub importance: Option<FeatureImportance>,
+    peak: f64,
+    position: f64,
+}
+
+impl SymbolReport {
+    pub fn hit_rate(&self) -> f64 {
+        self.hits as f64 / self.predictions.max(1) as f64
+    }
+
+    pub fn mae(&self) -> f64 {
+        self.abs_err / self.predictions.max(1) as f64
+    }
+
+    pub fn rmse(&self) -> f64 {
+        (self.sq_err / self.predictions.max(1) as f64).sqrt()
+    }
+
+    fn record(&mut self, predicted: f64, realized: f64, fee: f64) {
+        self.predictions += 1;
+        if predicted.signum() == realized.signum() {
+            self.hits += 1;
+        }
+        let err = predicted - realized;
+        self.abs_err += err.abs();
+        self.sq_err += err * err;
+
+        let position = if predicted > 0.0 { 1.0 } else if predicted < 0.0 { -1.0 } else { 0.0 };
+        if position != self.position {
+            self.position_changes += 1;
+            self.pnl -= fee * (position - self.position).abs();
+            self.position = position;
+        }
+        self.pnl += position * realized;
+        self.peak = self.peak.max(self.pnl);
+        self.max_drawdown = self.max_drawdown.max(self.peak - self.pnl);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BacktestConfig {
+    pub tf: Timeframe,
+    /// Cost per unit of position change, in basis points
+    pub fee_bps: f64,
+}
+
+/// Features at one bar close paired with the next bar's realized log return
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub ts: i64,
+    pub x: Vec<f64>,
+    pub realized: f64,
+}
+
+/// Replayed bars for one symbol
+#[derive(Debug, Clone, Default)]
+pub struct Series {
+    pub bars: u64,
+    pub samples: Vec<Sample>,
+}
+
+/// Replay ticks through bars → features, pairing each vector with the next bar's return
+pub fn build_samples(ticks: &[Tick], tf: Timeframe) -> BTreeMap<String, Series> {
+    let mut engine = BarEngine::new(vec![tf]);
+    let mut features: HashMap<String, FeatureExtractor> = HashMap::new();
+    let mut benchmarks = Benchmarks::from_env();
+    let mut pending: HashMap<String, (i64, Vec<f64>, f64)> = HashMap::new();
+    let mut series: BTreeMap<String, Series> = BTreeMap::new();
+
+    for tick in ticks {
+        for bar in engine.on_trade(&tick.symbol, tick.price, Some(tick.volume), tick.ts_ms) {
+            let s = series.entry(bar.symbol.clone()).or_default();
+            s.bars += 1;
+
+            if let Some((ts, x, prev_close)) = pending.remove(&bar.symbol)
+                && prev_close > 0.0
+                && bar.close > 0.0
+            {
+                s.samples.push(Sample {
+                    ts,
+                    x,
+                    realized: (bar.close / prev_close).ln(),
+                });
+            }
+            benchmarks.on_bar(&bar);
+            if let Some(fv) = features.entry(bar.symbol.clone()).or_default().update(&bar, &benchmarks) {
+                pending.insert(bar.symbol.clone(), (fv.ts, fv.values, bar.close));
+            }
+        }
+    }
+
+    series
+}
+
+/// Online replay: every prediction is scored, then the model learns from the outcome
+pub fn run(ticks: &[Tick], spec: &ModelSpec, cfg: BacktestConfig) -> BTreeMap<String, SymbolReport> {
+    let fee = cfg.fee_bps / 10_000.0;
+
+    build_samples(ticks, cfg.tf)
+        .into_iter()
+        .map(|(symbol, series)| {
+            let mut model = spec.build(FEATURE_NAMES.len());
+            let mut report = SymbolReport {
+                bars: series.bars,
+                ..Default::default()
+            };
+            for s in &series.samples {
+                report.record(model.predict(&s.x), s.realized, fee);
+                model.update(&s.x, s.realized);
+            }
+            if let Some(last) = series.samples.last() {
+                let x: Vec<Vec<f64>> = series.samples.iter().map(|s| s.x.clone()).collect();
+                let std = Normalizer::fit(Method::ZScore, &x).stds();
+                report.importance = FeatureImportance::compute(&symbol, model.as_ref(), &std, last.ts);
+            }
+            (symbol, report)
+        })
+        .collect()
+}
+
+/// Rolling split sizes, in samples (bars)
+#[derive(Debug, Clone, Copy)]
+pub struct WalkForwardConfig {
+    pub train: usize,
+    pub test: usize,
+}
+
+/// Out-of-sample metrics for one walk-forward window
+#[derive(Debug, Clone)]
+pub struct WindowReport {
+    pub index: usize,
+    /// First/last test sample timestamps, ms since epoch
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub report: SymbolReport,
+}
+
+/// Walk-forward validation: a fresh model learns on each train window and is
+/// scored with frozen weights on the following test window; windows advance by
+/// the test size.
+pub fn walk_forward(
+    ticks: &[Tick],
+    spec: &ModelSpec,
+    cfg: BacktestConfig,
+    wf: WalkForwardConfig,
+) -> BTreeMap<String, Vec<WindowReport>> {
+    let fee = cfg.fee_bps / 10_000.0;
+    let test = wf.test.max(1);
+
+    build_samples(ticks, cfg.tf)
+        .into_iter()
+        .map(|(symbol, series)| {
+            let samples = &series.samples;
+            let mut windows = Vec::new();
+            let mut start = 0;
+
+            while start + wf.train + test <= samples.len() {
+                let (train, rest) = samples[start..].split_at(wf.train);
+                let test_set = &rest[..test];
+
+                let mut model = spec.build(FEATURE_NAMES.len());
+                for s in train {
+                    model.update(&s.x, s.realized);
+                }
+                let mut report = SymbolReport {
+                    bars: test as u64,
+                    ..Default::default()
+                };
+                for s in test_set {
+                    report.record(model.predict(&s.x), s.realized, fee);
+                }
+
+                windows.push(WindowReport {
+                    index: windows.len(),
+                    start_ts: test_set[0].ts,
+                    end_ts: test_set[test - 1].ts,
+                    report,
+                });
+                start += test;
+            }
+
+            (symbol, windows)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(secs: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(0, 0, secs).unwrap()
+    }
+
+    fn volumes(rows: Vec<(u32, f64, Option<i64>)>) -> Vec<f64> {
+        let rows = rows.into_iter().map(|(s, v, start)| ("X".to_string(), at(s), 1.0, v, start));
+        ticks_from_snapshots(rows).into_iter().map(|t| t.volume).collect()
+    }
+
+    #[test]
+    fn differences_the_running_total() {
+        assert_eq!(volumes(vec![(0, 10.0, None), (10, 12.5, None), (20, 15.0, None)]), [0.0, 2.5, 2.5]);
+    }
+
+    #[test]
+    fn a_drop_counts_in_full() {
+        assert_eq!(volumes(vec![(0, 10.0, None), (10, 3.0, None)]), [0.0, 3.0]);
+    }
+
+    #[test]
+    fn stale_snapshots_are_dropped() {
+        assert_eq!(volumes(vec![(0, 10.0, None), (0, 11.0, None), (10, 12.0, None)]), [0.0, 2.0]);
+    }
+
+    #[test]
+    fn backfilled_candles_count_in_full_and_the_next_snapshot_adds_nothing() {
+        let rows = vec![(0, 10.0, None), (10, 4.0, Some(10_000)), (20, 5.0, Some(20_000)), (30, 30.0, None), (40, 31.0, None)];
+        assert_eq!(volumes(rows), [0.0, 4.0, 5.0, 0.0, 1.0]);
+    }
+}
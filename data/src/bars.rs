@@ -0,0 +1,482 @@
+use std::{collections::HashMap, env, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use tracing::warn;
+use crate::{
+    clock::{self, SharedClock},
+    kalman::{KalmanConfig, KalmanEstimate, KalmanFilter},
+};
+
+/// A day of 1m bars
+pub const DEFAULT_BAR_HISTORY_LEN: usize = 1440;
+
+const DEFAULT_TIMEFRAMES: &str = "1m,5m";
+const DEFAULT_LARGE_TRADE_MULTIPLE: f64 = 5.0;
+/// Weight of each trade in the running average trade size
+const TRADE_SIZE_ALPHA: f64 = 0.01;
+
+/// Fixed bar width in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timeframe {
+    pub secs: i64,
+}
+
+impl Timeframe {
+    /// Parse "30s", "1m", "15m", "1h", "1d"; `None` for anything else
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        // Split before the last char, not byte, so input like "1µ" is rejected rather than panicking
+        let (at, unit) = s.char_indices().last()?;
+        let n: i64 = s[..at].parse().ok().filter(|&n| n > 0)?;
+        let mult = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            _ => return None,
+        };
+        Some(Self { secs: n.checked_mul(mult)? })
+    }
+
+    pub fn millis(&self) -> i64 {
+        self.secs * 1000
+    }
+
+    /// Start (ms) of the bucket containing `ts_ms`
+    pub fn bucket_start(&self, ts_ms: i64) -> i64 {
+        ts_ms - ts_ms.rem_euclid(self.millis())
+    }
+}
+
+impl fmt::Display for Timeframe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.secs {
+            s if s % 86_400 == 0 => write!(f, "{}d", s / 86_400),
+            s if s % 3_600 == 0 => write!(f, "{}h", s / 3_600),
+            s if s % 60 == 0 => write!(f, "{}m", s / 60),
+            s => write!(f, "{s}s"),
+        }
+    }
+}
+
+/// Bars kept per list under `BAR_HISTORY_PREFIX`, from `BAR_HISTORY_LEN`
+pub fn history_len_from_env() -> usize {
+    env::var("BAR_HISTORY_LEN")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n: &usize| n > 0)
+        .unwrap_or(DEFAULT_BAR_HISTORY_LEN)
+}
+
+/// Timeframes from `BAR_TIMEFRAMES` (comma separated), invalid entries ignored
+pub fn timeframes_from_env() -> Vec<Timeframe> {
+    let raw = env::var("BAR_TIMEFRAMES").unwrap_or_else(|_| DEFAULT_TIMEFRAMES.to_string());
+    let mut tfs: Vec<Timeframe> = raw
+        .split(',')
+        .filter_map(|s| {
+            let tf = Timeframe::parse(s);
+            if tf.is_none() {
+                warn!("⚠️ Ignoring invalid timeframe '{s}'");
+            }
+            tf
+        })
+        .collect();
+    tfs.sort_by_key(|tf| tf.secs);
+    tfs.dedup();
+    tfs
+}
+
+/// How bars are built for a symbol
+#[derive(Debug, Clone, PartialEq)]
+pub struct BarRules {
+    pub timeframes: Vec<Timeframe>,
+    /// A trade this far from the last accepted price (fraction) is an outlier, unless the next
+    /// trade confirms the new level; `None` accepts every trade
+    pub max_jump: Option<f64>,
+    /// Bars closing with fewer trades are dropped instead of published
+    pub min_ticks: u32,
+}
+
+impl BarRules {
+    pub fn new(timeframes: Vec<Timeframe>) -> Self {
+        Self {
+            timeframes,
+            max_jump: None,
+            min_ticks: 1,
+        }
+    }
+}
+
+/// A closed OHLCV bar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Bar {
+    pub symbol: String,
+    pub tf: String,
+    /// Bucket start, ms since epoch
+    pub start: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub trades: u32,
+    /// False when a trade in the bar came without a volume, so `volume` undercounts
+    #[serde(default = "volume_known")]
+    pub volume_known: bool,
+    /// Tick-rule classified volume (trades carry no aggressor side)
+    #[serde(default)]
+    pub buy_volume: f64,
+    #[serde(default)]
+    pub sell_volume: f64,
+    /// Trades larger than `LARGE_TRADE_MULTIPLE` times the symbol's typical size
+    #[serde(default)]
+    pub large_trades: u32,
+    /// Tick-rule signed volume of the large trades
+    #[serde(default)]
+    pub large_signed_volume: f64,
+    /// Kalman fair price as of the last trade in the bar
+    #[serde(default)]
+    pub fair_price: Option<f64>,
+    /// USD per unit of the quote asset at close, for comparing pairs across quotes
+    #[serde(default)]
+    pub usd_rate: Option<f64>,
+    /// Exchange time of the trade that closed the bar, ms since epoch
+    #[serde(default)]
+    pub closed_by_ts: i64,
+    /// Ingester wall clock when the bar was closed and published, ms since epoch
+    #[serde(default)]
+    pub published_at: i64,
+}
+
+fn volume_known() -> bool {
+    true
+}
+
+/// One classified trade print
+#[derive(Debug, Clone, Copy)]
+struct Print {
+    price: f64,
+    /// Zero when the trade had none
+    volume: f64,
+    volume_known: bool,
+    side: i8,
+    large: bool,
+}
+
+impl Bar {
+    fn new(symbol: &str, tf: Timeframe, start: i64, print: &Print) -> Self {
+        let mut bar = Self {
+            symbol: symbol.to_string(),
+            tf: tf.to_string(),
+            start,
+            open: print.price,
+            high: print.price,
+            low: print.price,
+            close: print.price,
+            volume: 0.0,
+            trades: 0,
+            volume_known: true,
+            buy_volume: 0.0,
+            sell_volume: 0.0,
+            large_trades: 0,
+            large_signed_volume: 0.0,
+            fair_price: None,
+            usd_rate: None,
+            closed_by_ts: 0,
+            published_at: 0,
+        };
+        bar.add(print);
+        bar
+    }
+
+    fn add(&mut self, print: &Print) {
+        self.high = self.high.max(print.price);
+        self.low = self.low.min(print.price);
+        self.close = print.price;
+        self.volume += print.volume;
+        self.trades += 1;
+        self.volume_known &= print.volume_known;
+        match print.side {
+            1 => self.buy_volume += print.volume,
+            -1 => self.sell_volume += print.volume,
+            _ => {}
+        }
+        if print.large {
+            self.large_trades += 1;
+            self.large_signed_volume += print.side as f64 * print.volume;
+        }
+    }
+
+    /// Bar close time, ms since epoch
+    pub fn end(&self) -> i64 {
+        Timeframe::parse(&self.tf)
+            .map(|tf| self.start + tf.millis())
+            .unwrap_or(self.start)
+    }
+
+    /// Redis hash fields
+    pub fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = vec![
+            ("start".to_string(), self.start.to_string()),
+            ("open".to_string(), self.open.to_string()),
+            ("high".to_string(), self.high.to_string()),
+            ("low".to_string(), self.low.to_string()),
+            ("close".to_string(), self.close.to_string()),
+            ("volume".to_string(), self.volume.to_string()),
+            ("trades".to_string(), self.trades.to_string()),
+            ("volume_known".to_string(), self.volume_known.to_string()),
+            ("buy_volume".to_string(), self.buy_volume.to_string()),
+            ("sell_volume".to_string(), self.sell_volume.to_string()),
+            ("large_trades".to_string(), self.large_trades.to_string()),
+            ("large_signed_volume".to_string(), self.large_signed_volume.to_string()),
+        ];
+        if let Some(fair) = self.fair_price {
+            fields.push(("fair_price".to_string(), fair.to_string()));
+        }
+        if let Some(rate) = self.usd_rate {
+            fields.push(("usd_rate".to_string(), rate.to_string()));
+        }
+        fields
+    }
+
+    /// Inverse of `fields`; `None` when the hash is missing or incomplete
+    pub fn from_fields(symbol: &str, tf: &str, fields: &HashMap<String, String>) -> Option<Self> {
+        let num = |k: &str| fields.get(k)?.parse::<f64>().ok();
+        let int = |k: &str| fields.get(k).and_then(|v| v.parse::<u32>().ok()).unwrap_or(0);
+        Some(Self {
+            symbol: symbol.to_string(),
+            tf: tf.to_string(),
+            start: fields.get("start")?.parse().ok()?,
+            open: num("open")?,
+            high: num("high")?,
+            low: num("low")?,
+            close: num("close")?,
+            volume: num("volume")?,
+            trades: int("trades"),
+            volume_known: fields.get("volume_known").is_none_or(|v| v == "true"),
+            buy_volume: num("buy_volume").unwrap_or(0.0),
+            sell_volume: num("sell_volume").unwrap_or(0.0),
+            large_trades: int("large_trades"),
+            large_signed_volume: num("large_signed_volume").unwrap_or(0.0),
+            fair_price: num("fair_price"),
+            usd_rate: num("usd_rate"),
+            closed_by_ts: 0,
+            published_at: 0,
+        })
+    }
+}
+
+/// Builds bars for every symbol and timeframe from the trade stream.
+/// A bar closes when the first trade of a later bucket arrives.
+pub struct BarEngine {
+    rules: BarRules,
+    /// Symbols built differently from `rules`
+    symbol_rules: HashMap<String, BarRules>,
+    open: HashMap<(String, Timeframe), Bar>,
+    /// Last price and tick-rule side per symbol
+    last_tick: HashMap<String, (f64, i8)>,
+    /// Price of a rejected jump per symbol, accepted if the next trade is near it
+    pending_jump: HashMap<String, f64>,
+    /// EWMA trade size per symbol
+    typical_size: HashMap<String, f64>,
+    large_multiple: f64,
+    kalman_cfg: KalmanConfig,
+    kalman: HashMap<String, KalmanFilter>,
+    clock: SharedClock,
+}
+
+impl BarEngine {
+    /// Fair prices use `KalmanConfig::from_env`; `LARGE_TRADE_MULTIPLE` sets the large-trade threshold
+    pub fn new(timeframes: Vec<Timeframe>) -> Self {
+        Self::with_clock(timeframes, clock::system())
+    }
+
+    /// Closed bars are stamped `published_at` from `clock`
+    pub fn with_clock(timeframes: Vec<Timeframe>, clock: SharedClock) -> Self {
+        Self {
+            rules: BarRules::new(timeframes),
+            symbol_rules: HashMap::new(),
+            open: HashMap::new(),
+            last_tick: HashMap::new(),
+            pending_jump: HashMap::new(),
+            typical_size: HashMap::new(),
+            large_multiple: env::var("LARGE_TRADE_MULTIPLE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_LARGE_TRADE_MULTIPLE),
+            kalman_cfg: KalmanConfig::from_env(),
+            kalman: HashMap::new(),
+            clock,
+        }
+    }
+
+    /// Tick rule: uptick = buy, downtick = sell, zero tick repeats the last side
+    fn classify(&mut self, symbol: &str, price: f64) -> i8 {
+        let side = match self.last_tick.get(symbol) {
+            Some(&(last, _)) if price > last => 1,
+            Some(&(last, _)) if price < last => -1,
+            Some(&(_, side)) => side,
+            None => 0,
+        };
+        self.last_tick.insert(symbol.to_string(), (price, side));
+        side
+    }
+
+    /// Whether `volume` dwarfs the symbol's running average trade size (updated afterwards)
+    fn is_large(&mut self, symbol: &str, volume: f64) -> bool {
+        let typical = self.typical_size.entry(symbol.to_string()).or_insert(volume);
+        let large = *typical > 0.0 && volume > self.large_multiple * *typical;
+        *typical += TRADE_SIZE_ALPHA * (volume - *typical);
+        large
+    }
+
+    /// Replace the default rules and the per-symbol overrides
+    pub fn set_rules(&mut self, rules: BarRules, symbol_rules: HashMap<String, BarRules>) {
+        self.rules = rules;
+        self.symbol_rules = symbol_rules;
+    }
+
+    /// Default timeframes, for symbols without an override
+    pub fn timeframes(&self) -> &[Timeframe] {
+        &self.rules.timeframes
+    }
+
+    pub fn rules_for(&self, symbol: &str) -> &BarRules {
+        self.symbol_rules.get(symbol).unwrap_or(&self.rules)
+    }
+
+    /// Whether `price` jumps further from the symbol's last accepted price than its
+    /// `max_jump` allows. A rejected price is remembered, and a next trade within
+    /// `max_jump` of it is accepted, so a real level shift costs one trade.
+    pub fn is_outlier(&mut self, symbol: &str, price: f64) -> bool {
+        let Some(max_jump) = self.rules_for(symbol).max_jump else {
+            return false;
+        };
+        let near = |reference: f64| (price / reference - 1.0).abs() <= max_jump;
+        let outlier = match (self.last_tick.get(symbol), self.pending_jump.get(symbol)) {
+            (None, _) => false,
+            (Some(&(last, _)), _) if near(last) => false,
+            (_, Some(&pending)) => !near(pending),
+            _ => true,
+        };
+        if outlier {
+            self.pending_jump.insert(symbol.to_string(), price);
+        } else {
+            self.pending_jump.remove(symbol);
+        }
+        outlier
+    }
+
+    /// Latest Kalman fair-price estimate for `symbol`
+    pub fn fair_price(&self, symbol: &str) -> Option<KalmanEstimate> {
+        self.kalman.get(symbol).and_then(|k| k.estimate())
+    }
+
+    /// Apply a trade, returning any bars it closed with at least the symbol's `min_ticks`
+    /// trades. Late trades for an already-closed bucket are folded into the current bar. A
+    /// trade without a volume counts as zero and marks its bars `volume_known: false`.
+    pub fn on_trade(&mut self, symbol: &str, price: f64, volume: Option<f64>, ts_ms: i64) -> Vec<Bar> {
+        let mut closed = Vec::new();
+        let print = Print {
+            price,
+            volume: volume.unwrap_or(0.0),
+            volume_known: volume.is_some(),
+            side: self.classify(symbol, price),
+            large: volume.is_some_and(|v| self.is_large(symbol, v)),
+        };
+        // Closed bars carry the estimate from before the trade that closed them
+        let fair = self.fair_price(symbol).map(|k| k.fair_price);
+
+        let rules = self.symbol_rules.get(symbol).unwrap_or(&self.rules);
+        let min_ticks = rules.min_ticks;
+        for &tf in &rules.timeframes {
+            let start = tf.bucket_start(ts_ms);
+            match self.open.get_mut(&(symbol.to_string(), tf)) {
+                Some(bar) if start > bar.start => {
+                    let mut done =
+                        std::mem::replace(bar, Bar::new(symbol, tf, start, &print));
+                    done.fair_price = fair;
+                    done.closed_by_ts = ts_ms;
+                    if done.trades >= min_ticks {
+                        done.published_at = self.clock.now_ms();
+                        closed.push(done);
+                    }
+                }
+                Some(bar) => bar.add(&print),
+                None => {
+                    self.open.insert(
+                        (symbol.to_string(), tf),
+                        Bar::new(symbol, tf, start, &print),
+                    );
+                }
+            }
+        }
+
+        let cfg = self.kalman_cfg;
+        self.kalman
+            .entry(symbol.to_string())
+            .or_insert_with(|| KalmanFilter::new(cfg))
+            .update(price, ts_ms);
+
+        closed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    #[test]
+    fn bar_rolls_over_on_the_first_trade_of_the_next_bucket() {
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 10).unwrap());
+        let m1 = Timeframe::parse("1m").unwrap();
+        let mut engine = BarEngine::with_clock(vec![m1], clock.clone());
+
+        assert!(engine.on_trade("X", 100.0, Some(1.0), clock.now_ms()).is_empty());
+        clock.advance(Duration::from_secs(30));
+        assert!(engine.on_trade("X", 101.0, Some(2.0), clock.now_ms()).is_empty());
+        clock.advance(Duration::from_secs(15));
+        assert!(engine.on_trade("X", 99.0, None, clock.now_ms()).is_empty());
+
+        // 00:01:05: the 00:00 bar closes now, not at the boundary
+        clock.advance(Duration::from_secs(10));
+        let closed = engine.on_trade("X", 102.0, Some(1.0), clock.now_ms());
+        assert_eq!(closed.len(), 1);
+        let bar = &closed[0];
+        assert_eq!(bar.start, Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().timestamp_millis());
+        assert_eq!((bar.open, bar.high, bar.low, bar.close), (100.0, 101.0, 99.0, 99.0));
+        assert_eq!((bar.volume, bar.trades, bar.volume_known), (3.0, 3, false));
+        assert_eq!(bar.closed_by_ts, clock.now_ms());
+        assert_eq!(bar.published_at, clock.now_ms());
+
+        // A late trade for the closed bucket is folded into the new bar
+        let late = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 59).unwrap().timestamp_millis();
+        assert!(engine.on_trade("X", 98.0, Some(1.0), late).is_empty());
+        clock.advance(Duration::from_secs(60));
+        let bar = &engine.on_trade("X", 100.0, Some(1.0), clock.now_ms())[0];
+        assert_eq!((bar.open, bar.low, bar.trades), (102.0, 98.0, 2));
+    }
+
+    #[test]
+    fn bars_with_too_few_trades_are_not_published() {
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+        let mut engine = BarEngine::with_clock(Vec::new(), clock.clone());
+        let rules = BarRules { min_ticks: 2, ..BarRules::new(vec![Timeframe::parse("1m").unwrap()]) };
+        engine.set_rules(rules, HashMap::new());
+
+        engine.on_trade("X", 100.0, Some(1.0), clock.now_ms());
+        clock.advance(Duration::from_secs(60));
+        assert!(engine.on_trade("X", 100.0, Some(1.0), clock.now_ms()).is_empty());
+        engine.on_trade("X", 100.0, Some(1.0), clock.now_ms());
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(engine.on_trade("X", 100.0, Some(1.0), clock.now_ms()).len(), 1);
+    }
+}
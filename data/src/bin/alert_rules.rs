@@ -0,0 +1,306 @@
+use std::{collections::HashMap, env, process::ExitCode, time::Duration};
+
+use clap::{Parser, Subcommand};
+use tracing::{error, info, warn};
+use data_collection::{
+    alerts::{Alert, Telegram, Webhook},
+    bars::Bar,
+    cli::{self, ConfigArgs},
+    config::Need,
+    fetcher::try_connect_pg,
+    heartbeat::Heartbeat,
+    keys::{BARS_CHANNEL, PREDICTIONS_CHANNEL, RULE_ALERTS_CHANNEL},
+    notify::Notifier,
+    predictions::Prediction,
+    redis_conn::{RedisClient, RedisConn},
+    rules::{self, Condition, Rule, RuleEngine, RuleEvent, DEFAULT_COOLDOWN_SECS},
+};
+use dotenv::dotenv;
+use futures::StreamExt;
+use redis::AsyncCommands;
+use tokio::time::{interval, sleep};
+use tokio_postgres::Client as PgClient;
+
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(3);
+const DEFAULT_RULES_POLL_SECS: u64 = 30;
+
+/// Manages alert rules, or runs them against live bars and predictions
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Evaluate the rules on every bar and prediction and deliver what fires
+    Run,
+    /// Every table rule; x marks a disabled one
+    List,
+    /// Add a rule to the table
+    #[command(after_help = "KIND: price_cross_above, price_cross_below, predicted_return_above (%), \
+                            predicted_return_below (%), vol_spike (x average)")]
+    Add {
+        name: String,
+        kind: String,
+        #[arg(allow_negative_numbers = true)]
+        threshold: f64,
+        /// Only this symbol (default: all)
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Only this timeframe's bars (default: all)
+        #[arg(long)]
+        tf: Option<String>,
+        /// Deliver to this webhook instead of ALERT_WEBHOOK_URL
+        #[arg(long)]
+        webhook: Option<String>,
+        /// Deliver to this Telegram chat instead of TELEGRAM_CHAT_ID
+        #[arg(long, value_name = "CHAT_ID")]
+        telegram: Option<String>,
+        /// Seconds before the rule may fire again
+        #[arg(long, default_value_t = DEFAULT_COOLDOWN_SECS)]
+        cooldown: i64,
+    },
+    Enable { id: i32 },
+    Disable { id: i32 },
+    Remove { id: i32 },
+}
+
+fn print(rule: &Rule, enabled: bool) {
+    info!(
+        "{} #{:<4} {:<24} {:<20} {:<4} {:<45} cooldown {}s{}{}",
+        if enabled { " " } else { "x" },
+        rule.id.unwrap_or_default(),
+        rule.name,
+        rule.symbol.as_deref().unwrap_or("*"),
+        rule.tf.as_deref().unwrap_or("*"),
+        rule.condition.to_string(),
+        rule.cooldown_secs,
+        rule.webhook_url.as_deref().map(|u| format!(" webhook {u}")).unwrap_or_default(),
+        rule.telegram_chat_id
+            .as_deref()
+            .map(|c| format!(" telegram {c}"))
+            .unwrap_or_default()
+    );
+}
+
+/// `ALERT_RULES_FILE` plus the enabled table rules; a source that fails to load contributes none
+async fn load_rules(pg: &PgClient) -> Vec<Rule> {
+    let mut all = rules::from_env().unwrap_or_else(|e| {
+        error!("❌ {e}");
+        Vec::new()
+    });
+    match rules::load_table(pg).await {
+        Ok(table) => all.extend(table),
+        Err(e) => error!("❌ {e}"),
+    }
+    all
+}
+
+/// Rule's own webhook, else `ALERT_WEBHOOK_URL`; rule's chat, else `TELEGRAM_CHAT_ID`;
+/// plus Discord/Slack for every rule
+struct Delivery {
+    webhook: Option<Webhook>,
+    per_rule: HashMap<String, Webhook>,
+    telegram: Option<Telegram>,
+    notifier: Option<Notifier>,
+}
+
+impl Delivery {
+    fn send(&mut self, rule: &Rule, event: &RuleEvent) {
+        let hook = match &rule.webhook_url {
+            Some(url) => Some(
+                &*self
+                    .per_rule
+                    .entry(url.clone())
+                    .or_insert_with(|| Webhook::new(url.clone())),
+            ),
+            None => self.webhook.as_ref(),
+        };
+        let alert = Alert {
+            kind: "rule".to_string(),
+            message: event.message.clone(),
+            ts: event.ts,
+            details: serde_json::to_value(event).unwrap_or_default(),
+        };
+        if let Some(n) = &self.notifier {
+            n.notify(alert.clone());
+        }
+        if let Some(hook) = hook {
+            hook.notify(alert);
+        }
+        if let Some(bot) = &self.telegram {
+            bot.notify(rule.telegram_chat_id.clone(), format!("🔔 {}", event.message));
+        }
+    }
+}
+
+async fn watch(client: RedisClient, mut redis: RedisConn, pg: PgClient) {
+
+    let mut delivery = Delivery {
+        webhook: Webhook::from_env(),
+        per_rule: HashMap::new(),
+        telegram: Telegram::from_env(),
+        notifier: Notifier::from_env("alert-rules"),
+    };
+    if delivery.webhook.is_none() && delivery.telegram.is_none() && delivery.notifier.is_none() {
+        warn!("⚠️ None of ALERT_WEBHOOK_URL, TELEGRAM_BOT_TOKEN, DISCORD_WEBHOOK_URL or SLACK_WEBHOOK_URL set — rules without their own webhook only publish to '{RULE_ALERTS_CHANNEL}'");
+    }
+
+    let mut engine = RuleEngine::new(load_rules(&pg).await);
+    info!("🔔 {} alert rules loaded", engine.rules().len());
+    let poll = env::var("ALERT_RULES_POLL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RULES_POLL_SECS);
+    let mut reload_tick = interval(Duration::from_secs(poll.max(1)));
+    reload_tick.tick().await;
+    let heartbeat = Heartbeat::spawn("alert_rules", redis.clone());
+    let mut beat_tick = heartbeat.ticker();
+
+    loop {
+        heartbeat.pause("subscribing", RESUBSCRIBE_DELAY);
+        let mut pubsub = match client.pubsub().await {
+            Ok(p) => p,
+            Err(e) => {
+                error!("❌ Redis pub/sub connection failed: {e}, retrying...");
+                sleep(RESUBSCRIBE_DELAY).await;
+                continue;
+            }
+        };
+        if let Err(e) = pubsub.subscribe(&[BARS_CHANNEL, PREDICTIONS_CHANNEL]).await {
+            error!("❌ Subscribe failed: {e}, retrying...");
+            sleep(RESUBSCRIBE_DELAY).await;
+            continue;
+        }
+        info!("📡 Subscribed to '{BARS_CHANNEL}' and '{PREDICTIONS_CHANNEL}'");
+
+        let mut stream = pubsub.on_message();
+        loop {
+            let msg = tokio::select! {
+                msg = stream.next() => match msg {
+                    Some(m) => m,
+                    None => break,
+                },
+                _ = beat_tick.tick() => {
+                    heartbeat.beat("subscribed");
+                    continue;
+                }
+                _ = reload_tick.tick() => {
+                    let before = engine.rules().len();
+                    engine.set_rules(load_rules(&pg).await);
+                    if engine.rules().len() != before {
+                        info!("🔔 {} alert rules loaded", engine.rules().len());
+                    }
+                    continue;
+                }
+            };
+
+            let payload: String = match msg.get_payload() {
+                Ok(p) => p,
+                Err(e) => {
+                    warn!("⚠️ Unreadable payload: {e}");
+                    continue;
+                }
+            };
+            let fired = if msg.get_channel_name() == BARS_CHANNEL {
+                match serde_json::from_str::<Bar>(&payload) {
+                    Ok(bar) => engine.on_bar(&bar),
+                    Err(e) => {
+                        warn!("⚠️ Invalid bar JSON: {e}");
+                        continue;
+                    }
+                }
+            } else {
+                match serde_json::from_str::<Prediction>(&payload) {
+                    Ok(p) => engine.on_prediction(&p),
+                    Err(e) => {
+                        warn!("⚠️ Invalid prediction JSON: {e}");
+                        continue;
+                    }
+                }
+            };
+
+            for (rule, event) in fired {
+                info!("🔔 {}", event.message);
+                if let Ok(json) = serde_json::to_string(&event)
+                    && let Err(e) = redis.publish::<_, _, ()>(RULE_ALERTS_CHANNEL, json).await
+                {
+                    error!("❌ Redis publish error: {e}");
+                }
+                delivery.send(&rule, &event);
+            }
+        }
+
+        warn!("🔁 Subscription dropped. Resubscribing...");
+        sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
+
+async fn set_enabled(pg: &PgClient, id: i32, enabled: bool) -> Result<(), String> {
+    match rules::set_enabled(pg, id, enabled).await? {
+        true => info!("✅ Rule #{id} {}", if enabled { "enabled" } else { "disabled" }),
+        false => return Err(format!("no alert rule #{id}")),
+    }
+    Ok(())
+}
+
+async fn run(args: Cli) -> Result<(), String> {
+    let needs: &[Need] = match args.command {
+        Command::Run => &[Need::Redis, Need::Postgres],
+        _ => &[Need::Postgres],
+    };
+    let (config, _log) = args.common.start(env!("CARGO_CRATE_NAME"), needs).await?;
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    rules::ensure_table(&pg)
+        .await
+        .map_err(|e| format!("Failed to create alert_rules table: {e}"))?;
+
+    match args.command {
+        Command::Run => {
+            let client = RedisClient::open_with(&config.redis_url(), &config.tls.redis).map_err(|e| e.to_string())?;
+            info!("🌐 Connecting to Redis ({})...", client.describe());
+            let redis = client.connect().await.map_err(|e| e.to_string())?;
+            watch(client, redis, pg).await;
+        }
+        Command::List => {
+            for (rule, enabled) in rules::list(&pg).await? {
+                print(&rule, enabled);
+            }
+        }
+        Command::Add { name, kind, threshold, symbol, tf, webhook, telegram, cooldown } => {
+            let mut rule = Rule {
+                id: None,
+                name,
+                symbol,
+                tf,
+                condition: Condition::new(&kind, threshold)?,
+                webhook_url: webhook,
+                telegram_chat_id: telegram,
+                cooldown_secs: cooldown,
+            };
+            rule.id = Some(rules::insert(&pg, &rule).await?);
+            info!("✅ Added:");
+            print(&rule, true);
+        }
+        Command::Enable { id } => set_enabled(&pg, id, true).await?,
+        Command::Disable { id } => set_enabled(&pg, id, false).await?,
+        Command::Remove { id } => match rules::delete(&pg, id).await? {
+            true => info!("🗑️ Rule #{id} removed"),
+            false => return Err(format!("no alert rule #{id}")),
+        },
+    }
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse()).await)
+}
@@ -0,0 +1,156 @@
+use std::{env, net::SocketAddr, process::ExitCode, sync::Arc};
+
+use clap::Parser;
+use tracing::{error, info, warn};
+use data_collection::{
+    api::{self, AppState},
+    auth::Auth,
+    cache::QueryCache,
+    cli::{self, ConfigArgs},
+    config::Need,
+    ratelimit::{RateLimitConfig, RateLimiter},
+    fetcher::try_connect_pg,
+    finnhub::FinnhubClient,
+    health::{self, Health},
+    heartbeat::Heartbeat,
+    history,
+    layers::{HttpLayers, Origins},
+    redis_conn::RedisClient,
+    relay, shutdown, symbols, webhooks,
+};
+use dotenv::dotenv;
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast, Mutex},
+};
+
+const DEFAULT_API_ADDR: &str = "0.0.0.0:8080";
+// Messages buffered per slow WebSocket client before it starts skipping
+const LIVE_BUFFER: usize = 1024;
+
+/// REST, WebSocket and dashboard API over the pipeline's Redis and Postgres
+#[derive(Parser)]
+#[command(version, after_help = "Without Postgres, /history, symbol management and webhooks are disabled")]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+}
+
+async fn run(args: ConfigArgs) -> Result<(), String> {
+    let (config, _log) = args.start(env!("CARGO_CRATE_NAME"), &[Need::Redis]).await?;
+    info!("🌍 API server starting…");
+
+    let addr = env::var("API_ADDR").unwrap_or_else(|_| DEFAULT_API_ADDR.to_string());
+
+    let client = RedisClient::open_with(&config.redis_url(), &config.tls.redis).map_err(|e| e.to_string())?;
+    info!("🌐 Connecting to Redis ({})...", client.describe());
+    let redis = client.connect().await.map_err(|e| e.to_string())?;
+    let pg = match config.optional_database_url() {
+        Some(url) => {
+            let pg = try_connect_pg(&url, &config.tls.postgres).await.map_err(|e| e.to_string())?;
+            if let Err(e) = symbols::ensure_columns(&pg).await {
+                warn!("⚠️ Could not add stocks.active: {e}");
+            }
+            if let Err(e) = webhooks::ensure_table(&pg).await {
+                warn!("⚠️ Could not create webhook_subscriptions: {e}");
+            }
+            if let Err(e) = history::ensure_view(&pg).await {
+                warn!("⚠️ Could not create {}: {e}", history::HISTORY_VIEW);
+            }
+            Some(Arc::new(pg))
+        }
+        None => {
+            warn!("⚠️ DATABASE_URL not set — /history, symbol management and webhooks disabled");
+            None
+        }
+    };
+    let finnhub = match config.finnhub_key() {
+        Some(key) => Some(Arc::new(Mutex::new(FinnhubClient::new(key)))),
+        None => {
+            warn!("⚠️ FINNHUB_API_KEY not set — POST /symbols disabled");
+            None
+        }
+    };
+    let auth = match Auth::from_env() {
+        Ok(Some(auth)) => {
+            info!(
+                "🔑 API auth enabled: {} static keys, JWT {}",
+                auth.key_count(),
+                if auth.jwt_enabled() { "on" } else { "off" }
+            );
+            Some(Arc::new(auth))
+        }
+        Ok(None) => {
+            warn!("⚠️ Neither API_KEYS_FILE nor JWT_SECRET set — API is open, keep it on localhost");
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    let limiter = match RateLimitConfig::from_env() {
+        Some(config) => {
+            info!(
+                "🚦 Rate limit: {}/s per caller, burst {}",
+                config.rate_per_sec, config.burst
+            );
+            Some(Arc::new(RateLimiter::new(config)))
+        }
+        None => {
+            warn!("⚠️ RATE_LIMIT_PER_SEC=0 — rate limiting disabled");
+            None
+        }
+    };
+    let health = Health::new("api");
+    tokio::spawn(health::probe(health.clone(), Some(redis.clone()), pg.clone()));
+    Heartbeat::spawn("api", redis.clone()).keep_beating("serving");
+    let layers = HttpLayers::from_env()?;
+    match &layers.cors {
+        Some(Origins::Any) => info!("🌐 CORS: any origin"),
+        Some(Origins::List(list)) => info!("🌐 CORS: {} allowed origins", list.len()),
+        None => info!("🌐 CORS disabled — set CORS_ORIGINS for dashboards on other origins"),
+    }
+    if !layers.compression {
+        warn!("⚠️ COMPRESSION=0 — responses are sent uncompressed");
+    }
+    let cache = QueryCache::from_env();
+    match cache {
+        Some(c) => info!("🗃️ Query cache: {}s TTL", c.ttl_secs),
+        None => warn!("⚠️ QUERY_CACHE_TTL_SECS=0 — history queries go straight to Postgres"),
+    }
+    let (live, _) = broadcast::channel(LIVE_BUFFER);
+    tokio::spawn(relay::run(client, api::live_channels().to_vec(), live.clone()));
+    let app = layers.apply(api::router(AppState {
+        redis,
+        pg,
+        finnhub,
+        auth,
+        cache,
+        limiter,
+        health,
+        live,
+    }));
+
+    let listener = TcpListener::bind(&addr)
+        .await
+        .map_err(|e| format!("Cannot bind {addr}: {e}"))?;
+    info!("✅ Listening on http://{addr}");
+    // Client addresses key the rate limiter for unauthenticated callers
+    let shutdown = shutdown::on_signal();
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned());
+    tokio::select! {
+        res = server => {
+            if let Err(e) = res {
+                error!("❌ API server error: {e}");
+            }
+        }
+        _ = shutdown::drain_deadline(shutdown) => {}
+    }
+    info!("👋 API server stopped");
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse().common).await)
+}
@@ -0,0 +1,129 @@
+use std::process::ExitCode;
+
+use chrono::{NaiveDateTime, Timelike, Utc};
+use clap::Parser;
+use data_collection::{
+    backfill::{self, CandleSource, SourceKind},
+    cache,
+    cli::{self, ConfigArgs},
+    config::Need,
+    fetcher::try_connect_pg,
+    history,
+    keys::SYMBOLS_KEY,
+    redis_conn,
+};
+use dotenv::dotenv;
+use redis::AsyncCommands;
+
+/// Loads 1m candles into stock_price_history, skipping minutes that already have rows
+#[derive(Parser)]
+#[command(
+    version,
+    after_help = "Progress is checkpointed per symbol and --from; rerun with the same --from to resume. \
+                  Symbols default to the tracked set in Redis."
+)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+
+    /// Start, YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS UTC
+    #[arg(long, value_parser = cli::parse_time)]
+    from: NaiveDateTime,
+
+    /// End (default: now)
+    #[arg(long, value_parser = cli::parse_time)]
+    to: Option<NaiveDateTime>,
+
+    /// Comma-separated symbols (default: the tracked set in Redis)
+    #[arg(long, value_delimiter = ',')]
+    symbols: Option<Vec<String>>,
+
+    /// auto, binance or finnhub
+    #[arg(long, default_value = "auto")]
+    source: SourceKind,
+
+    /// Ignore the checkpoint and load the whole range again
+    #[arg(long)]
+    restart: bool,
+}
+
+/// Candles open on the minute
+fn to_minute(t: NaiveDateTime) -> NaiveDateTime {
+    t.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(t)
+}
+
+async fn run(args: Cli) -> Result<(), String> {
+    let needs: &[Need] = match args.symbols {
+        Some(_) => &[Need::Postgres],
+        None => &[Need::Postgres, Need::Redis],
+    };
+    let (config, _log) = args.common.start(env!("CARGO_CRATE_NAME"), needs).await?;
+
+    let from = to_minute(args.from);
+    let to = to_minute(args.to.unwrap_or_else(|| Utc::now().naive_utc()));
+    if from >= to {
+        return Err("--from must be before --to".to_string());
+    }
+
+    let mut pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    backfill::ensure_table(&pg)
+        .await
+        .map_err(|e| format!("Failed to create backfill_progress table: {e}"))?;
+    history::ensure_view(&pg)
+        .await
+        .map_err(|e| format!("Failed to create {}: {e}", history::HISTORY_VIEW))?;
+    // Only needed for the default symbols and to drop cached history afterwards
+    let mut redis = match config.optional_redis_url() {
+        Some(url) => Some(redis_conn::try_connect(&url, &config.tls.redis).await.map_err(|e| e.to_string())?),
+        None => None,
+    };
+
+    let symbols: Vec<String> = match (args.symbols, &mut redis) {
+        (Some(s), _) => s.iter().map(|x| x.trim().to_string()).filter(|x| !x.is_empty()).collect(),
+        (None, Some(redis)) => redis
+            .smembers(SYMBOLS_KEY)
+            .await
+            .map_err(|e| format!("Redis smembers error: {e}"))?,
+        (None, None) => unreachable!(),
+    };
+    if symbols.is_empty() {
+        println!("⚠️ No symbols to backfill");
+        return Ok(());
+    }
+
+    println!("📥 Backfilling {} symbols {from} → {to}", symbols.len());
+    let mut source = CandleSource::new(args.source, config.finnhub_key());
+    let (mut total, mut failed) = (0, 0);
+    for symbol in &symbols {
+        match backfill::load_range(&mut pg, &mut source, symbol, from, to, args.restart).await {
+            Ok(n) => {
+                println!("✅ {symbol}: {n} rows");
+                total += n;
+                if n > 0
+                    && let Some(redis) = &mut redis
+                    && let Err(e) = cache::invalidate(redis, &[symbol]).await
+                {
+                    eprintln!("⚠️ Could not invalidate cached history for {symbol}: {e}");
+                }
+            }
+            Err(e) => {
+                eprintln!("❌ {symbol}: {e} — rerun with the same --from to resume");
+                failed += 1;
+            }
+        }
+    }
+
+    println!("✨ Backfill finished: {total} rows, {failed} symbols failed");
+    if failed > 0 {
+        return Err(format!("{failed} of {} symbols failed", symbols.len()));
+    }
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse()).await)
+}
@@ -0,0 +1,185 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use clap::Parser;
+use data_collection::{
+    backtest::{self, BacktestConfig, WalkForwardConfig},
+    bars::Timeframe,
+    cli::{self, ConfigArgs},
+    config::Need,
+    features::FEATURE_NAMES,
+    fetcher::try_connect_pg,
+    models::ModelSpec,
+};
+use dotenv::dotenv;
+
+const TOP_FEATURES: usize = 5;
+
+/// Replays recorded ticks through the configured model and reports hit rate, error and PnL
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+
+    /// Replay this ticks CSV instead of stock_price_history
+    #[arg(long)]
+    csv: Option<PathBuf>,
+
+    /// Start, YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS UTC (default: a day before --to)
+    #[arg(long, value_parser = cli::parse_time)]
+    from: Option<NaiveDateTime>,
+
+    /// End (default: now)
+    #[arg(long, value_parser = cli::parse_time)]
+    to: Option<NaiveDateTime>,
+
+    /// Comma-separated symbols (default: all)
+    #[arg(long, value_delimiter = ',')]
+    symbols: Vec<String>,
+
+    /// Bar timeframe
+    #[arg(long, default_value = "1m", value_parser = cli::parse_timeframe)]
+    tf: Timeframe,
+
+    /// Fee charged per position change, in basis points
+    #[arg(long, default_value_t = 10.0)]
+    fee_bps: f64,
+
+    /// Walk-forward window sizes in bars
+    #[arg(long, value_name = "TRAIN,TEST", value_parser = parse_walk_forward)]
+    walk_forward: Option<WalkForwardConfig>,
+}
+
+fn fmt_ms(ms: i64) -> String {
+    DateTime::from_timestamp_millis(ms)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// `TRAIN,TEST` window sizes in bars
+fn parse_walk_forward(s: &str) -> Result<WalkForwardConfig, String> {
+    let parsed = s
+        .split_once(',')
+        .and_then(|(a, b)| Some((a.trim().parse().ok()?, b.trim().parse().ok()?)));
+    match parsed {
+        Some((train, test)) if test > 0 => Ok(WalkForwardConfig { train, test }),
+        _ => Err(format!("invalid window sizes '{s}' (TRAIN,TEST bars, TEST > 0)")),
+    }
+}
+
+async fn run(args: Cli) -> Result<(), String> {
+    let needs: &[Need] = if args.csv.is_some() { &[] } else { &[Need::Postgres] };
+    let (config, _log) = args.common.start(env!("CARGO_CRATE_NAME"), needs).await?;
+
+    let to = args.to.unwrap_or_else(|| Utc::now().naive_utc());
+    let from = args.from.unwrap_or(to - Duration::days(1));
+    let symbols: Vec<String> = args.symbols.iter().map(|x| x.trim().to_string()).filter(|x| !x.is_empty()).collect();
+    let (tf, fee_bps) = (args.tf, args.fee_bps);
+
+    // --- Load ticks ---
+    let ticks = match &args.csv {
+        Some(path) => {
+            println!("📂 Loading {}…", path.display());
+            backtest::load_csv(path, &symbols)?
+        }
+        None => {
+            println!("📥 Loading stock_price_history {from} → {to}…");
+            let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+                .await
+                .map_err(|e| e.to_string())?;
+            backtest::load_history(&pg, &symbols, from, to).await?
+        }
+    };
+    println!("✅ Replaying {} ticks on {tf} bars (fee {fee_bps} bps)", ticks.len());
+
+    let spec = ModelSpec::from_env(FEATURE_NAMES.len());
+    let cfg = BacktestConfig { tf, fee_bps };
+
+    // --- Walk-forward ---
+    if let Some(wf) = args.walk_forward {
+        println!("🚶 Walk-forward: train {} bars, test {} bars", wf.train, wf.test);
+        let results = backtest::walk_forward(&ticks, &spec, cfg, wf);
+
+        println!(
+            "\n{:<22} {:>4} {:>16} {:>16} {:>6} {:>8} {:>11} {:>10}",
+            "symbol", "win", "test_from", "test_to", "preds", "hit%", "rmse", "pnl"
+        );
+        for (symbol, windows) in &results {
+            for w in windows {
+                println!(
+                    "{:<22} {:>4} {:>16} {:>16} {:>6} {:>7.2}% {:>11.6} {:>9.4}%",
+                    symbol,
+                    w.index,
+                    fmt_ms(w.start_ts),
+                    fmt_ms(w.end_ts),
+                    w.report.predictions,
+                    w.report.hit_rate() * 100.0,
+                    w.report.rmse(),
+                    w.report.pnl * 100.0
+                );
+            }
+            if !windows.is_empty() {
+                let n = windows.len() as f64;
+                let rates: Vec<f64> = windows.iter().map(|w| w.report.hit_rate()).collect();
+                let mean = rates.iter().sum::<f64>() / n;
+                let sd = (rates.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n).sqrt();
+                println!(
+                    "{:<22} ↳ {} windows, hit% {:.2} ± {:.2}",
+                    symbol,
+                    windows.len(),
+                    mean * 100.0,
+                    sd * 100.0
+                );
+            }
+        }
+        if results.values().all(|w| w.is_empty()) {
+            println!("⚠️ Not enough bars for a single train+test window");
+        }
+        return Ok(());
+    }
+
+    // --- Replay ---
+    let reports = backtest::run(&ticks, &spec, cfg);
+
+    println!(
+        "\n{:<22} {:>7} {:>7} {:>8} {:>11} {:>11} {:>10} {:>10} {:>7}",
+        "symbol", "bars", "preds", "hit%", "mae", "rmse", "pnl", "max_dd", "turns"
+    );
+    for (symbol, r) in &reports {
+        println!(
+            "{:<22} {:>7} {:>7} {:>7.2}% {:>11.6} {:>11.6} {:>9.4}% {:>9.4}% {:>7}",
+            symbol,
+            r.bars,
+            r.predictions,
+            r.hit_rate() * 100.0,
+            r.mae(),
+            r.rmse(),
+            r.pnl * 100.0,
+            r.max_drawdown * 100.0,
+            r.position_changes
+        );
+    }
+    if reports.is_empty() {
+        println!("⚠️ No bars closed — nothing to report");
+    }
+
+    // --- What each model ended up relying on ---
+    for (symbol, r) in &reports {
+        let Some(importance) = &r.importance else {
+            continue;
+        };
+        let top: Vec<String> = importance
+            .top(TOP_FEATURES)
+            .iter()
+            .map(|f| format!("{} {:.1}%", f.feature, f.importance * 100.0))
+            .collect();
+        println!("🔎 {symbol} top features: {}", top.join(", "));
+    }    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse()).await)
+}
@@ -0,0 +1,197 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use clap::{Parser, ValueEnum};
+use data_collection::{
+    backtest,
+    bars::Timeframe,
+    cli::{self, ConfigArgs},
+    config::Need,
+    dataset,
+    features::FEATURE_SCHEMA_VERSION,
+    fetcher::try_connect_pg,
+    labels::{Label, PriceIndex},
+    normalize::{Method, Normalizer},
+    redis_conn,
+};
+use dotenv::dotenv;
+
+/// Where the feature rows come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Source {
+    /// The predictor's feature store
+    Store,
+    /// Recomputed from the ticks
+    Replay,
+}
+
+/// Exports labeled feature rows to CSV or Parquet for offline training
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+
+    /// Output file, .csv or .parquet
+    #[arg(long)]
+    out: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = Source::Store)]
+    source: Source,
+
+    /// Ticks CSV to label (and replay) instead of stock_price_history
+    #[arg(long)]
+    csv: Option<PathBuf>,
+
+    /// Start, YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS UTC (default: a day before --to)
+    #[arg(long, value_parser = cli::parse_time)]
+    from: Option<NaiveDateTime>,
+
+    /// End (default: now)
+    #[arg(long, value_parser = cli::parse_time)]
+    to: Option<NaiveDateTime>,
+
+    /// Comma-separated symbols (default: all)
+    #[arg(long, value_delimiter = ',')]
+    symbols: Vec<String>,
+
+    /// Feature timeframe
+    #[arg(long, default_value = "1m", value_parser = cli::parse_timeframe)]
+    tf: Timeframe,
+
+    /// Targets, e.g. ret:1m,dir:5m,tb:15m:20:20,next_tick (default: ret:TF at the --tf timeframe)
+    #[arg(long)]
+    labels: Option<String>,
+
+    /// Scale features with zscore or minmax
+    #[arg(long)]
+    normalize: Option<Method>,
+
+    /// Feature statistics to scale with: "live" from Redis, or a saved STATS.json
+    #[arg(long, value_name = "live|PATH")]
+    stats: Option<String>,
+
+    /// Save the statistics used to this JSON file
+    #[arg(long)]
+    save_stats: Option<PathBuf>,
+}
+
+async fn run(args: Cli) -> Result<(), String> {
+    let live_stats = args.stats.as_deref() == Some("live");
+    let replay_csv = args.csv.is_some() && args.source == Source::Replay;
+    let mut needs = Vec::new();
+    if !replay_csv {
+        needs.push(Need::Postgres);
+    }
+    if live_stats {
+        needs.push(Need::Redis);
+    }
+    let (config, _log) = args.common.start(env!("CARGO_CRATE_NAME"), &needs).await?;
+
+    let to = args.to.unwrap_or_else(|| Utc::now().naive_utc());
+    let from = args.from.unwrap_or(to - Duration::days(1));
+    let symbols: Vec<String> = args.symbols.iter().map(|x| x.trim().to_string()).filter(|x| !x.is_empty()).collect();
+    let tf = args.tf;
+    let labels = Label::parse_list(&args.labels.clone().unwrap_or_else(|| format!("ret:{tf}")))?;
+    let longest = labels.iter().map(|l| l.horizon_ms()).max().unwrap_or(0);
+
+    // --- Prices for the forward returns (and the replay source) ---
+    let pg = if replay_csv {
+        None
+    } else {
+        let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+            .await
+            .map_err(|e| e.to_string())?;
+        Some(pg)
+    };
+    let ticks = match (&args.csv, &pg) {
+        (Some(path), _) => {
+            println!("📂 Loading {}…", path.display());
+            backtest::load_csv(path, &symbols)?
+        }
+        (None, Some(pg)) => {
+            let until = to + Duration::milliseconds(longest);
+            println!("📥 Loading stock_price_history {from} → {until}…");
+            backtest::load_history(pg, &symbols, from, until).await?
+        }
+        (None, None) => unreachable!(),
+    };
+
+    // --- Features ---
+    let rows = match (args.source, &pg) {
+        (Source::Store, Some(pg)) => {
+            println!("🗄️ Reading stored {tf} features (schema v{FEATURE_SCHEMA_VERSION})…");
+            dataset::load_stored(pg, &tf.to_string(), &symbols, from, to).await?
+        }
+        (Source::Store, None) => unreachable!(),
+        (Source::Replay, _) => {
+            println!("🔁 Recomputing {tf} features from {} ticks…", ticks.len());
+            dataset::replay(&ticks, tf)
+        }
+    };
+
+    let feature_rows = rows.len();
+    let mut ds = dataset::with_labels(rows, &PriceIndex::new(&ticks), &labels);
+    println!(
+        "🏷️ {} of {feature_rows} rows labeled with {}",
+        ds.rows.len(),
+        ds.target_names.join(", ")
+    );
+
+    // --- Scaling: the predictor's live statistics, a saved file, or fitted on this export ---
+    let method = args.normalize;
+    let normalizer = match args.stats.as_deref() {
+        Some("live") => {
+            let mut redis = redis_conn::try_connect(&config.redis_url(), &config.tls.redis)
+                .await
+                .map_err(|e| e.to_string())?;
+            let n = Normalizer::load_redis(&mut redis, &tf.to_string())
+                .await?
+                .ok_or_else(|| format!("No live {tf} feature statistics in Redis yet"))?;
+            println!("📏 Using live {tf} feature statistics over {} vectors", n.count());
+            Some(n)
+        }
+        Some(path) => {
+            let n = Normalizer::load(Path::new(path))?;
+            println!("📏 Using feature statistics from {path} over {} vectors", n.count());
+            Some(n)
+        }
+        None => method.map(|m| {
+            let values: Vec<Vec<f64>> = ds.rows.iter().map(|(r, _)| r.values.clone()).collect();
+            println!("📏 Fitting feature statistics on {} exported rows", values.len());
+            Normalizer::fit(m, &values)
+        }),
+    };
+    if let Some(mut n) = normalizer {
+        n.method = method.unwrap_or(n.method);
+        dataset::normalize(&mut ds, &n);
+        println!("📏 Features scaled with {}", n.method);
+        if let Some(path) = &args.save_stats {
+            n.save(path)?;
+            println!("💾 Saved feature statistics to {}", path.display());
+        }
+    }
+
+    // --- Write ---
+    let path = args.out.as_path();
+    let written = match path.extension().and_then(|e| e.to_str()) {
+        Some("csv") => dataset::write_csv(&ds, path),
+        #[cfg(feature = "parquet")]
+        Some("parquet") => dataset::write_parquet(&ds, path),
+        #[cfg(not(feature = "parquet"))]
+        Some("parquet") => Err("Parquet output requires the `parquet` feature".to_string()),
+        _ => Err(format!("unsupported output '{}' (use .csv or .parquet)", path.display())),
+    };
+    written?;
+    println!("✅ Wrote {} rows to {}", ds.rows.len(), path.display());
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse()).await)
+}
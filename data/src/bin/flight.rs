@@ -0,0 +1,342 @@
+use std::{env, process::ExitCode, sync::Arc};
+
+use arrow_array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray, TimestampMillisecondArray};
+use arrow_flight::{
+    encode::FlightDataEncoderBuilder,
+    error::FlightError,
+    flight_service_server::{FlightService, FlightServiceServer},
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo, HandshakeRequest,
+    HandshakeResponse, PollInfo, PutResult, SchemaResult, Ticket,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, NaiveDateTime};
+use clap::Parser;
+use tracing::{info, warn};
+use data_collection::{
+    bars::Timeframe,
+    cli::{self, ConfigArgs},
+    config::Need,
+    features::{FEATURE_NAMES, FEATURE_SCHEMA_VERSION},
+    fetcher::try_connect_pg,
+    health::{self, Health},
+    history::{self, Candle, DEFAULT_HISTORY_LIMIT, MAX_HISTORY_LIMIT},
+    shutdown,
+};
+use dotenv::dotenv;
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Client as PgClient;
+use tonic::{transport::Server, Request, Response, Status, Streaming};
+
+const DEFAULT_FLIGHT_ADDR: &str = "0.0.0.0:8815";
+// Feature rows fetched from Postgres per record batch
+const FEATURE_PAGE: i64 = 10_000;
+
+/// What a ticket (or a descriptor's `cmd`) asks for, as JSON, e.g.
+/// `{"dataset":"bars","symbols":["BINANCE:BTCUSDT"],"tf":"1m","from":"2024-01-01"}`.
+/// Times take the same forms as the HTTP history endpoints; `from` defaults to 500 bars before `to`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "dataset", rename_all = "lowercase")]
+enum Query {
+    /// Candles resampled from the OHLCV snapshots, one symbol after another
+    Bars {
+        symbols: Vec<String>,
+        tf: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// Stored feature vectors at the current schema version; no symbols means all of them
+    Features {
+        #[serde(default)]
+        symbols: Vec<String>,
+        tf: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+impl Query {
+    fn parse(bytes: &[u8]) -> Result<Self, Status> {
+        serde_json::from_slice(bytes).map_err(|e| Status::invalid_argument(format!("invalid query: {e}")))
+    }
+
+    fn schema(&self) -> SchemaRef {
+        match self {
+            Query::Bars { .. } => bars_schema(),
+            Query::Features { .. } => features_schema(),
+        }
+    }
+
+    /// The query's record batches, read from Postgres a page at a time as the client pulls
+    fn batches(self, pg: Arc<PgClient>) -> Result<BoxStream<'static, Result<RecordBatch, FlightError>>, Status> {
+        let (Query::Bars { tf, from, to, .. } | Query::Features { tf, from, to, .. }) = &self;
+        let tf = Timeframe::parse(tf).ok_or_else(|| Status::invalid_argument(format!("invalid timeframe '{tf}'")))?;
+        let (from, to) = history::window(tf, from.as_deref(), to.as_deref(), DEFAULT_HISTORY_LIMIT)
+            .map_err(Status::invalid_argument)?;
+        let schema = self.schema();
+
+        Ok(match self {
+            Query::Bars { symbols, .. } => {
+                if symbols.is_empty() {
+                    return Err(Status::invalid_argument("bars need at least one symbol"));
+                }
+                // (symbol index, start of the next page)
+                futures::stream::try_unfold((0, from), move |(i, page_from)| {
+                    let (pg, schema) = (pg.clone(), schema.clone());
+                    let symbol = symbols.get(i).cloned();
+                    async move {
+                        let Some(symbol) = symbol else { return Ok(None) };
+                        let page = history::candles(&pg, &symbol, tf, page_from, to, MAX_HISTORY_LIMIT)
+                            .await
+                            .map_err(|e| FlightError::from(Status::unavailable(e)))?;
+                        let next = match page.next.and_then(naive_ms) {
+                            Some(next) => (i, next),
+                            None => (i + 1, from),
+                        };
+                        Ok(Some((bars_batch(&schema, &symbol, &page.candles)?, next)))
+                    }
+                })
+                .try_filter(|batch| std::future::ready(batch.num_rows() > 0))
+                .boxed()
+            }
+            Query::Features { symbols, .. } => {
+                let tf = tf.to_string();
+                // Keyset cursor: last (symbol, ts) sent, None once exhausted
+                futures::stream::try_unfold(Some((String::new(), from)), move |cursor| {
+                    let (pg, schema, tf, symbols) = (pg.clone(), schema.clone(), tf.clone(), symbols.clone());
+                    async move {
+                        let Some((after_symbol, after_ts)) = cursor else { return Ok(None) };
+                        let rows = pg
+                            .query(
+                                "SELECT symbol, ts, feature_values FROM features \
+                                 WHERE tf = $1 AND schema_version = $2 AND ts >= $3 AND ts < $4 \
+                                   AND (cardinality($5::text[]) = 0 OR symbol = ANY($5)) \
+                                   AND (symbol, ts) > ($6, $7) \
+                                 ORDER BY symbol, ts LIMIT $8",
+                                &[&tf, &FEATURE_SCHEMA_VERSION, &from, &to, &symbols, &after_symbol, &after_ts, &FEATURE_PAGE],
+                            )
+                            .await
+                            .map_err(|e| FlightError::from(Status::unavailable(format!("feature query failed: {e}"))))?;
+                        let next = (rows.len() as i64 == FEATURE_PAGE)
+                            .then(|| rows.last().map(|r| (r.get(0), r.get(1))))
+                            .flatten();
+                        let rows: Vec<(String, NaiveDateTime, Vec<f64>)> = rows
+                            .into_iter()
+                            .map(|r| (r.get(0), r.get(1), r.get(2)))
+                            .filter(|(_, _, values): &(_, _, Vec<f64>)| values.len() == FEATURE_NAMES.len())
+                            .collect();
+                        if rows.is_empty() && next.is_none() {
+                            return Ok(None);
+                        }
+                        Ok(Some((features_batch(&schema, &rows)?, next)))
+                    }
+                })
+                .try_filter(|batch| std::future::ready(batch.num_rows() > 0))
+                .boxed()
+            }
+        })
+    }
+}
+
+fn naive_ms(ms: i64) -> Option<NaiveDateTime> {
+    DateTime::from_timestamp_millis(ms).map(|t| t.naive_utc())
+}
+
+fn timestamp_field(name: &str) -> Field {
+    Field::new(name, DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())), false)
+}
+
+/// `symbol`, `start`, OHLCV doubles and `snapshots`, as in the Parquet history export
+fn bars_schema() -> SchemaRef {
+    let mut fields = vec![Field::new("symbol", DataType::Utf8, false), timestamp_field("start")];
+    for name in ["open", "high", "low", "close", "volume"] {
+        fields.push(Field::new(name, DataType::Float64, false));
+    }
+    fields.push(Field::new("snapshots", DataType::Int64, false));
+    Arc::new(Schema::new(fields))
+}
+
+/// `symbol`, `ts` and one double column per feature, in `FEATURE_NAMES` order
+fn features_schema() -> SchemaRef {
+    let mut fields = vec![Field::new("symbol", DataType::Utf8, false), timestamp_field("ts")];
+    fields.extend(FEATURE_NAMES.iter().map(|name| Field::new(*name, DataType::Float64, false)));
+    Arc::new(Schema::new(fields))
+}
+
+fn bars_batch(schema: &SchemaRef, symbol: &str, candles: &[Candle]) -> Result<RecordBatch, FlightError> {
+    let doubles = |f: fn(&Candle) -> f64| Arc::new(candles.iter().map(f).collect::<Float64Array>()) as ArrayRef;
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(vec![symbol; candles.len()])),
+        Arc::new(TimestampMillisecondArray::from_iter_values(candles.iter().map(|c| c.start)).with_timezone("UTC")),
+        doubles(|c| c.open),
+        doubles(|c| c.high),
+        doubles(|c| c.low),
+        doubles(|c| c.close),
+        doubles(|c| c.volume),
+        Arc::new(Int64Array::from_iter_values(candles.iter().map(|c| c.snapshots))),
+    ];
+    RecordBatch::try_new(schema.clone(), columns).map_err(FlightError::Arrow)
+}
+
+fn features_batch(schema: &SchemaRef, rows: &[(String, NaiveDateTime, Vec<f64>)]) -> Result<RecordBatch, FlightError> {
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(rows.iter().map(|(s, _, _)| Some(s.as_str())).collect::<StringArray>()),
+        Arc::new(
+            TimestampMillisecondArray::from_iter_values(rows.iter().map(|(_, ts, _)| ts.and_utc().timestamp_millis()))
+                .with_timezone("UTC"),
+        ),
+    ];
+    for i in 0..FEATURE_NAMES.len() {
+        columns.push(Arc::new(rows.iter().map(|(_, _, v)| v[i]).collect::<Float64Array>()));
+    }
+    RecordBatch::try_new(schema.clone(), columns).map_err(FlightError::Arrow)
+}
+
+/// A descriptor names a dataset by path (`["bars"]`) or carries a full query as `cmd`
+fn descriptor_schema(descriptor: &FlightDescriptor) -> Result<SchemaRef, Status> {
+    match descriptor.path.first().map(String::as_str) {
+        Some("bars") => Ok(bars_schema()),
+        Some("features") => Ok(features_schema()),
+        Some(other) => Err(Status::not_found(format!("unknown dataset '{other}'"))),
+        None => Ok(Query::parse(&descriptor.cmd)?.schema()),
+    }
+}
+
+fn info_for(descriptor: FlightDescriptor, schema: &Schema) -> Result<FlightInfo, Status> {
+    FlightInfo::new()
+        .try_with_schema(schema)
+        .map(|info| info.with_descriptor(descriptor))
+        .map_err(|e| Status::internal(format!("cannot encode schema: {e}")))
+}
+
+struct DataService {
+    pg: Arc<PgClient>,
+}
+
+#[tonic::async_trait]
+impl FlightService for DataService {
+    type HandshakeStream = BoxStream<'static, Result<HandshakeResponse, Status>>;
+    type ListFlightsStream = BoxStream<'static, Result<FlightInfo, Status>>;
+    type DoGetStream = BoxStream<'static, Result<FlightData, Status>>;
+    type DoPutStream = BoxStream<'static, Result<PutResult, Status>>;
+    type DoActionStream = BoxStream<'static, Result<arrow_flight::Result, Status>>;
+    type ListActionsStream = BoxStream<'static, Result<ActionType, Status>>;
+    type DoExchangeStream = BoxStream<'static, Result<FlightData, Status>>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("no authentication; call do_get directly"))
+    }
+
+    /// The two datasets, by path, with their schemas
+    async fn list_flights(&self, _request: Request<Criteria>) -> Result<Response<Self::ListFlightsStream>, Status> {
+        let infos = [("bars", bars_schema()), ("features", features_schema())]
+            .into_iter()
+            .map(|(name, schema)| info_for(FlightDescriptor::new_path(vec![name.to_string()]), &schema))
+            .collect::<Vec<_>>();
+        Ok(Response::new(futures::stream::iter(infos).boxed()))
+    }
+
+    /// For a `cmd` query: its schema and a single endpoint on this server whose ticket is the query
+    async fn get_flight_info(&self, request: Request<FlightDescriptor>) -> Result<Response<FlightInfo>, Status> {
+        let descriptor = request.into_inner();
+        let query = Query::parse(&descriptor.cmd)?;
+        let ticket = Ticket::new(descriptor.cmd.clone());
+        let info = info_for(descriptor, &query.schema())?
+            .with_endpoint(FlightEndpoint::new().with_ticket(ticket))
+            .with_ordered(true);
+        Ok(Response::new(info))
+    }
+
+    async fn poll_flight_info(&self, _request: Request<FlightDescriptor>) -> Result<Response<PollInfo>, Status> {
+        Err(Status::unimplemented("queries are not long-running; use get_flight_info"))
+    }
+
+    async fn get_schema(&self, request: Request<FlightDescriptor>) -> Result<Response<SchemaResult>, Status> {
+        let descriptor = request.into_inner();
+        let schema = descriptor_schema(&descriptor)?;
+        let info = info_for(descriptor, &schema)?;
+        Ok(Response::new(SchemaResult { schema: info.schema }))
+    }
+
+    async fn do_get(&self, request: Request<Ticket>) -> Result<Response<Self::DoGetStream>, Status> {
+        let query = Query::parse(&request.into_inner().ticket)?;
+        info!("✈️ Serving {query:?}");
+        let schema = query.schema();
+        let batches = query.batches(self.pg.clone())?;
+        let stream = FlightDataEncoderBuilder::new()
+            .with_schema(schema)
+            .build(batches)
+            .map_err(Status::from);
+        Ok(Response::new(stream.boxed()))
+    }
+
+    async fn do_put(&self, _request: Request<Streaming<FlightData>>) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("read-only server"))
+    }
+
+    async fn do_action(&self, _request: Request<Action>) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("no actions"))
+    }
+
+    async fn list_actions(&self, _request: Request<Empty>) -> Result<Response<Self::ListActionsStream>, Status> {
+        Ok(Response::new(futures::stream::empty().boxed()))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("read-only server"))
+    }
+}
+
+/// Arrow Flight server for bars and stored features
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+}
+
+async fn run(args: ConfigArgs) -> Result<(), String> {
+    let (config, _log) = args.start(env!("CARGO_CRATE_NAME"), &[Need::Postgres]).await?;
+    info!("✈️ Arrow Flight server starting…");
+
+    let addr = env::var("FLIGHT_ADDR")
+        .unwrap_or_else(|_| DEFAULT_FLIGHT_ADDR.to_string())
+        .parse()
+        .map_err(|e| format!("Invalid FLIGHT_ADDR: {e}"))?;
+
+    let health = Health::new("flight");
+    health::spawn_server(health.clone());
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    let pg = Arc::new(pg);
+    if let Err(e) = history::ensure_view(&pg).await {
+        warn!("⚠️ Could not create {}: {e}", history::HISTORY_VIEW);
+    }
+    tokio::spawn(health::probe(health, None, Some(pg.clone())));
+
+    info!("✅ Serving bars and features over Arrow Flight on {addr}");
+    let shutdown = shutdown::on_signal();
+    let server = Server::builder()
+        .add_service(FlightServiceServer::new(DataService { pg }))
+        .serve_with_shutdown(addr, shutdown.clone().cancelled_owned());
+    tokio::select! {
+        // A port already in use fails here, before anything is served
+        res = server => res.map_err(|e| format!("Flight server error: {e}"))?,
+        _ = shutdown::drain_deadline(shutdown) => {}
+    }
+    info!("👋 Flight server stopped");
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse().common).await)
+}
@@ -0,0 +1,215 @@
+use std::{collections::HashMap, env, pin::Pin, process::ExitCode};
+
+use clap::Parser;
+use tracing::{error, info, warn};
+use data_collection::{
+    cli::{self, ConfigArgs},
+    config::Need,
+    health::{self, Health},
+    heartbeat::Heartbeat,
+    keys::{PREDICTIONS_CHANNEL, PREDICTION_PREFIX, TRADES_CHANNEL},
+    predictions::Prediction,
+    redis_conn::{RedisClient, RedisConn},
+    relay::{self, Trade},
+    shutdown,
+};
+use dotenv::dotenv;
+use futures::{Stream, StreamExt};
+use redis::AsyncCommands;
+use tokio::sync::broadcast;
+use tonic::{transport::Server, Request, Response, Status};
+
+mod pb {
+    tonic::include_proto!("predictor.v1");
+}
+
+mod trades_pb {
+    tonic::include_proto!("trades.v1");
+}
+
+use pb::predictor_server::{Predictor, PredictorServer};
+use trades_pb::trades_server::{Trades, TradesServer};
+
+const DEFAULT_GRPC_ADDR: &str = "0.0.0.0:50051";
+// Predictions buffered per slow stream before it starts skipping
+const STREAM_BUFFER: usize = 1024;
+// Trades arrive far more often, so slow trade streams get more headroom
+const TRADE_STREAM_BUFFER: usize = 8192;
+
+impl From<Prediction> for pb::Prediction {
+    fn from(p: Prediction) -> Self {
+        Self {
+            symbol: p.symbol,
+            horizon: p.horizon,
+            predicted_return: p.predicted_return,
+            predicted_price: p.predicted_price,
+            base_price: p.base_price,
+            confidence: p.confidence,
+            std_dev: p.std_dev,
+            q10: p.q10,
+            q50: p.q50,
+            q90: p.q90,
+            model: p.model,
+            model_version: p.model_version,
+            feature_ts: p.feature_ts,
+            target_ts: p.target_ts,
+        }
+    }
+}
+
+impl From<Trade> for trades_pb::Trade {
+    fn from(t: Trade) -> Self {
+        Self {
+            symbol: t.symbol,
+            price: t.price,
+            volume: t.volume,
+            ts: t.ts,
+        }
+    }
+}
+
+struct PredictorService {
+    redis: RedisConn,
+    live: broadcast::Sender<relay::Message>,
+}
+
+type PredictionStream = Pin<Box<dyn Stream<Item = Result<pb::Prediction, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Predictor for PredictorService {
+    async fn get_prediction(
+        &self,
+        request: Request<pb::GetPredictionRequest>,
+    ) -> Result<Response<pb::Prediction>, Status> {
+        let symbol = request.into_inner().symbol;
+        let mut redis = self.redis.clone();
+        let fields: HashMap<String, String> = redis
+            .hgetall(format!("{PREDICTION_PREFIX}{symbol}"))
+            .await
+            .map_err(|e| Status::unavailable(format!("redis error: {e}")))?;
+
+        Prediction::from_fields(&symbol, &fields)
+            .map(|p| Response::new(p.into()))
+            .ok_or_else(|| Status::not_found(format!("no prediction for {symbol}")))
+    }
+
+    type StreamPredictionsStream = PredictionStream;
+
+    async fn stream_predictions(
+        &self,
+        request: Request<pb::StreamPredictionsRequest>,
+    ) -> Result<Response<Self::StreamPredictionsStream>, Status> {
+        let symbols = request.into_inner().symbols;
+        let rx = self.live.subscribe();
+
+        let stream = futures::stream::unfold(rx, |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(msg) => match serde_json::from_str::<Prediction>(&msg.payload) {
+                        Ok(p) => return Some((p, rx)),
+                        Err(e) => warn!("⚠️ Invalid prediction JSON: {e}"),
+                    },
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("⚠️ gRPC stream lagged, skipped {n} predictions");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .filter(move |p| std::future::ready(symbols.is_empty() || symbols.contains(&p.symbol)))
+        .map(|p| Ok(p.into()));
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+struct TradeService {
+    live: broadcast::Sender<relay::Message>,
+}
+
+type TradeStream = Pin<Box<dyn Stream<Item = Result<trades_pb::Trade, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Trades for TradeService {
+    type StreamTradesStream = TradeStream;
+
+    async fn stream_trades(
+        &self,
+        request: Request<trades_pb::StreamTradesRequest>,
+    ) -> Result<Response<Self::StreamTradesStream>, Status> {
+        let symbols = request.into_inner().symbols;
+        let rx = self.live.subscribe();
+
+        let stream = futures::stream::unfold(rx, |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(msg) => match serde_json::from_str::<Trade>(&msg.payload) {
+                        Ok(t) => return Some((t, rx)),
+                        Err(e) => warn!("⚠️ Invalid trade JSON: {e}"),
+                    },
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("⚠️ gRPC trade stream lagged, skipped {n} trades");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .filter(move |t| std::future::ready(symbols.is_empty() || symbols.contains(&t.symbol)))
+        .map(|t| Ok(t.into()));
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+/// gRPC streams of predictions and trades
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+}
+
+async fn run(args: ConfigArgs) -> Result<(), String> {
+    let (config, _log) = args.start(env!("CARGO_CRATE_NAME"), &[Need::Redis]).await?;
+    info!("🛰️ gRPC prediction and trade server starting…");
+
+    let addr = env::var("GRPC_ADDR")
+        .unwrap_or_else(|_| DEFAULT_GRPC_ADDR.to_string())
+        .parse()
+        .map_err(|e| format!("Invalid GRPC_ADDR: {e}"))?;
+
+    let health = Health::new("grpc");
+    health::spawn_server(health.clone());
+    let client = RedisClient::open_with(&config.redis_url(), &config.tls.redis).map_err(|e| e.to_string())?;
+    info!("🌐 Connecting to Redis ({})...", client.describe());
+    let redis = client.connect().await.map_err(|e| e.to_string())?;
+    tokio::spawn(health::probe(health, Some(redis.clone()), None));
+    Heartbeat::spawn("grpc", redis.clone()).keep_beating("serving");
+    let (live, _) = broadcast::channel(STREAM_BUFFER);
+    tokio::spawn(relay::run(client.clone(), vec![PREDICTIONS_CHANNEL], live.clone()));
+    let (live_trades, _) = broadcast::channel(TRADE_STREAM_BUFFER);
+    tokio::spawn(relay::run(client, vec![TRADES_CHANNEL], live_trades.clone()));
+
+    info!("✅ Serving predictor.v1.Predictor and trades.v1.Trades on {addr}");
+    let shutdown = shutdown::on_signal();
+    let server = Server::builder()
+        .add_service(PredictorServer::new(PredictorService { redis, live }))
+        .add_service(TradesServer::new(TradeService { live: live_trades }))
+        .serve_with_shutdown(addr, shutdown.clone().cancelled_owned());
+    tokio::select! {
+        res = server => {
+            if let Err(e) = res {
+                error!("❌ gRPC server error: {e}");
+            }
+        }
+        _ = shutdown::drain_deadline(shutdown) => {}
+    }
+    info!("👋 gRPC server stopped");
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse().common).await)
+}
@@ -0,0 +1,60 @@
+use std::process::ExitCode;
+
+use clap::Parser;
+use data_collection::{
+    cli::{self, ConfigArgs},
+    mock_exchange::{MockExchange, Protocol, Scenario},
+};
+use dotenv::dotenv;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:9443";
+
+/// Serves made-up trades for whatever a client subscribes to, e.g. the ingester with
+/// FINNHUB_WS_URL=ws://127.0.0.1:9443
+#[derive(Parser)]
+#[command(
+    version,
+    after_help = "A scenario file is \
+                  {\"steps\": [{\"step\": \"trades\", \"count\": 5, \"interval_ms\": 100}, {\"step\": \"disconnect\"}], \"repeat\": true}; \
+                  steps are trades, burst, malformed (kind: truncated, missing_fields, wrong_types, \
+                  negative_price, bad_timestamp, unknown_type, binary), raw, pause, ping, disconnect and close."
+)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+
+    #[arg(long, default_value = DEFAULT_ADDR)]
+    addr: String,
+
+    /// finnhub or binance
+    #[arg(long, default_value = "finnhub")]
+    protocol: Protocol,
+
+    /// steady, burst, reconnect, malformed, mixed or a scenario FILE.json
+    #[arg(long, default_value = "steady")]
+    scenario: String,
+
+    /// Seed for the made-up prices
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+}
+
+async fn run(args: Cli) -> Result<(), String> {
+    let (_config, _log) = args.common.start(env!("CARGO_CRATE_NAME"), &[]).await?;
+    let scenario = Scenario::load(&args.scenario)?;
+    let (addr, protocol) = (&args.addr, args.protocol);
+
+    let bound = MockExchange::new(protocol, scenario, args.seed)
+        .spawn(addr)
+        .await
+        .map_err(|e| format!("Cannot listen on {addr}: {e}"))?;
+    println!("🎭 Mock {protocol:?} exchange playing '{}' on ws://{bound}", args.scenario);
+    let _ = tokio::signal::ctrl_c().await;
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse()).await)
+}
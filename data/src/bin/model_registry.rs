@@ -0,0 +1,237 @@
+use std::process::ExitCode;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use clap::{Parser, Subcommand};
+use data_collection::{
+    cli::{self, ConfigArgs},
+    config::Need,
+    evaluation,
+    features::{FEATURE_NAMES, FEATURE_SCHEMA_VERSION},
+    fetcher::try_connect_pg,
+    models::ModelSpec,
+    predictions,
+    registry::{self, ModelRecord, Provenance},
+};
+use dotenv::dotenv;
+
+/// Lists, registers, activates and compares predictor models in the registry
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Every registered model; * marks the active one, s the shadow
+    List,
+    /// Register a model, optionally activating it
+    Register {
+        /// rls|rls:0.99|onnx:PATH|native:PATH|torch:PATH|python:PATH, or A,B,... for an ensemble
+        #[arg(long)]
+        spec: String,
+        /// Start of the training window
+        #[arg(long, value_parser = cli::parse_time)]
+        train_from: Option<NaiveDateTime>,
+        /// End of the training window
+        #[arg(long, value_parser = cli::parse_time)]
+        train_to: Option<NaiveDateTime>,
+        /// Offline metrics to record, as JSON
+        #[arg(long, value_parser = parse_metrics)]
+        metrics: Option<serde_json::Value>,
+        #[arg(long)]
+        activate: bool,
+    },
+    /// Make a model active; running predictors switch on their next poll
+    Activate { id: i32 },
+    /// Run a model in shadow next to the active one, or "off" to stop
+    Shadow {
+        #[arg(value_name = "ID|off")]
+        id: String,
+    },
+    /// Realize recent predictions and compare the active and shadow models on them
+    Compare {
+        #[arg(long, default_value_t = DEFAULT_COMPARE_HOURS)]
+        hours: i64,
+    },
+    /// Make the shadow model active
+    Promote,
+    /// Rerun an audited prediction on its stored features
+    Reproduce {
+        #[arg(value_name = "PREDICTION_ID")]
+        id: i64,
+    },
+}
+
+const DEFAULT_COMPARE_HOURS: i64 = 24;
+
+fn parse_metrics(s: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(s).map_err(|e| format!("invalid JSON: {e}"))
+}
+
+fn print(record: &ModelRecord) {
+    let window = match (record.train_from, record.train_to) {
+        (Some(from), Some(to)) => format!("{from} → {to}"),
+        _ => "-".to_string(),
+    };
+    println!(
+        "{} #{:<4} {:<9} {:<32} hash {:<12} trained {:<41} {}",
+        match (record.active, record.shadow) {
+            (true, _) => "*",
+            (_, true) => "s",
+            _ => " ",
+        },
+        record.id,
+        record.name,
+        record.version,
+        record.artifact_hash.as_deref().map(|h| &h[..12.min(h.len())]).unwrap_or("-"),
+        window,
+        record.metrics
+    );
+}
+
+async fn run(args: Cli) -> Result<(), String> {
+    let (config, _log) = args.common.start(env!("CARGO_CRATE_NAME"), &[Need::Postgres]).await?;
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    registry::ensure_table(&pg)
+        .await
+        .map_err(|e| format!("Failed to create model_registry table: {e}"))?;
+
+    match args.command {
+        Command::List => {
+            for record in registry::list(&pg).await? {
+                print(&record);
+            }
+        }
+        Command::Register { spec, train_from, train_to, metrics, activate } => {
+            let name = if spec.contains(',') { "ensemble" } else { "" };
+            let spec = ModelSpec::from_registry(name, &spec, FEATURE_NAMES.len())?;
+            let provenance = Provenance { train_from, train_to, metrics };
+            let mut record = registry::register(&pg, &spec, &provenance).await?;
+            if activate {
+                record = registry::activate(&pg, record.id).await?;
+            }
+            println!("✅ Registered:");
+            print(&record);
+        }
+        Command::Activate { id } => {
+            let record = registry::activate(&pg, id).await?;
+            println!("✅ Activated (running predictors switch on their next poll):");
+            print(&record);
+        }
+        Command::Shadow { id } => {
+            let id = match id.as_str() {
+                "off" => None,
+                id => Some(id.parse().map_err(|_| format!("invalid model id '{id}' (an id or 'off')"))?),
+            };
+            match registry::set_shadow(&pg, id).await? {
+                Some(record) => {
+                    println!("✅ Shadowing (predictions are recorded, never traded):");
+                    print(&record);
+                }
+                None => println!("✅ Shadow cleared"),
+            }
+        }
+        Command::Compare { hours } => {
+            let since = Utc::now().naive_utc() - Duration::hours(hours);
+            let active = registry::active(&pg).await?;
+            let shadow = registry::shadow(&pg).await?;
+            let (Some(active), Some(shadow)) = (active, shadow) else {
+                return Err("comparison needs both an active and a shadow model".to_string());
+            };
+
+            predictions::ensure_table(&pg)
+                .await
+                .map_err(|e| format!("Failed to create predictions table: {e}"))?;
+            let realized = evaluation::realize(&pg, since).await?;
+            println!("✅ Realized {realized} predictions");
+            let scores = evaluation::compare(&pg, active.id, shadow.id, since).await?;
+            println!("📊 Paired live accuracy over the last {hours}h:");
+            for (role, record) in [("active", &active), ("shadow", &shadow)] {
+                match scores.iter().find(|s| s.model_id == record.id) {
+                    Some(s) => println!(
+                        "  {role:<6} #{:<4} {}@{}: {} samples, MAE {:.3e}, RMSE {:.3e}, hit-rate {:.1}%",
+                        record.id,
+                        record.name,
+                        record.version,
+                        s.samples,
+                        s.mae,
+                        s.rmse,
+                        s.hit_rate * 100.0
+                    ),
+                    None => println!(
+                        "  {role:<6} #{:<4} {}@{}: no paired samples yet",
+                        record.id, record.name, record.version
+                    ),
+                }
+            }
+        }
+        Command::Promote => {
+            let shadow = registry::shadow(&pg)
+                .await?
+                .ok_or_else(|| "no shadow model to promote".to_string())?;
+            let record = registry::activate(&pg, shadow.id).await?;
+            println!("✅ Promoted shadow to active (running predictors switch on their next poll):");
+            print(&record);
+        }
+        Command::Reproduce { id } => {
+            predictions::ensure_table(&pg)
+                .await
+                .map_err(|e| format!("Failed to create predictions table: {e}"))?;
+            let audit = predictions::audit(&pg, id)
+                .await?
+                .ok_or_else(|| format!("no audited prediction with id {id}"))?;
+
+            println!(
+                "🔎 Prediction #{} for {} by {}@{} from features at {} (schema v{})",
+                audit.id, audit.symbol, audit.model, audit.model_version, audit.feature_ts, audit.feature_schema_version
+            );
+            for (name, value) in FEATURE_NAMES.iter().zip(&audit.feature_values) {
+                println!("  {name:<24} {value}");
+            }
+            println!("  stored prediction        {:+.6e}", audit.predicted_return);
+            if let Some(realized) = audit.realized_return {
+                println!("  realized return          {realized:+.6e}");
+            }
+
+            if audit.feature_schema_version != FEATURE_SCHEMA_VERSION {
+                return Err(format!(
+                    "recorded with feature schema v{}, this build uses v{FEATURE_SCHEMA_VERSION}",
+                    audit.feature_schema_version
+                ));
+            }
+            let model_id = audit
+                .model_id
+                .ok_or_else(|| "ensemble member predictions have no registry model to rerun".to_string())?;
+            let record = registry::get(&pg, model_id)
+                .await?
+                .ok_or_else(|| format!("registry model #{model_id} no longer exists"))?;
+            let spec = record.load(FEATURE_NAMES.len())?;
+            if spec.is_online() {
+                return Err(format!(
+                    "{}@{} learns online; its state at prediction time is not stored, so the output cannot be recomputed",
+                    record.name, record.version
+                ));
+            }
+            let recomputed = spec.build(FEATURE_NAMES.len()).predict(&audit.feature_values);
+            println!(
+                "  {:<24} {recomputed:+.6e} (Δ {:.3e})",
+                format!("recomputed by #{model_id}"),
+                recomputed - audit.predicted_return
+            );
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse()).await)
+}
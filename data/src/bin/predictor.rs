@@ -0,0 +1,875 @@
+use std::{collections::HashMap, env, process::ExitCode, sync::Arc, time::Duration};
+
+use chrono::Utc;
+use clap::Parser;
+use tracing::{error, info, warn};
+use data_collection::{
+    alerts::{Alert, Webhook},
+    anomaly::{AlertThrottle, AnomalyConfig, AnomalyEvent},
+    bars::Bar,
+    cli::{self, ConfigArgs},
+    config::Need,
+    correlation::Benchmarks,
+    drift::{self, DriftConfig, DriftMonitor, DriftReport},
+    execution::{self, ExecutionConfig, Executor},
+    feature_store,
+    features::{FeatureExtractor, FeatureVector, FEATURE_NAMES, FEATURE_SCHEMA_VERSION},
+    fetcher::try_connect_pg,
+    health::{self, Health},
+    heartbeat::Heartbeat,
+    importance::FeatureImportance,
+    indicators::IndicatorSet,
+    keys::{
+        ANOMALIES_CHANNEL, BARS_CHANNEL, DRIFT_PREFIX, FEATURES_PREFIX, IMPORTANCE_PREFIX,
+        INDICATORS_PREFIX, MEMBER_PREDICTION_PREFIX, NORMALIZER_PREFIX, PAPER_EQUITY_KEY,
+        PATTERNS_CHANNEL, PREDICTIONS_CHANNEL, PREDICTION_PREFIX, REGIMES_CHANNEL, REGIME_PREFIX,
+        SCALED_FEATURES_PREFIX, SHADOW_PREDICTION_PREFIX, SIGNALS_CHANNEL, SIGNAL_PREFIX,
+        VOLATILITY_PREFIX,
+    },
+    metrics::{now_ms, Metrics},
+    mirror::Mirror,
+    notify::Notifier,
+    models::{self, Model, ModelSpec, ModelWatcher},
+    normalize::Normalizer,
+    paper::{self, EquitySnapshot, Fill, PaperBook, PaperConfig},
+    patterns::PatternEvent,
+    predictions::{self, Prediction},
+    redis_conn::{RedisClient, RedisConn},
+    regime::{RegimeConfig, RegimeDetector},
+    registry::{self, ModelRecord},
+    shutdown,
+    signals::{Signal, SignalConfig, SignalGenerator},
+};
+use dotenv::dotenv;
+use futures::StreamExt;
+use tokio_postgres::Client as PgClient;
+use tokio::time::{interval, sleep, timeout};
+
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(3);
+const POSTGRES_TIMEOUT: Duration = Duration::from_secs(5);
+// Weight of the newest outcome in the directional hit-rate
+const CONFIDENCE_ALPHA: f64 = 0.05;
+const DEFAULT_PREDICT_TIMEFRAME: &str = "1m";
+const DEFAULT_DRIFT_REFERENCE_HOURS: i64 = 168;
+
+type SeriesKey = (String, String);
+/// Features, close and (model, predicted return) pairs awaiting the next bar
+type Pending = (Vec<f64>, f64, Vec<(String, f64)>);
+
+/// Everything produced for one bar on the prediction timeframe
+struct BarOutput {
+    /// Feature vector every model below was given
+    inputs: Vec<f64>,
+    prediction: Prediction,
+    /// Individual ensemble member predictions, empty for single models
+    members: Vec<Prediction>,
+    /// Registry id and output of the shadow model, if one is running
+    shadow: Option<(i32, Prediction)>,
+    /// Set on the bars where feature drift was checked
+    drift: Option<DriftReport>,
+    signal: Signal,
+    fill: Option<Fill>,
+    snapshot: Option<EquitySnapshot>,
+}
+
+/// Candidate model run on the same features as the active one, recorded but never acted on
+struct Shadow {
+    id: i32,
+    spec: ModelSpec,
+    models: HashMap<String, Box<dyn Model>>,
+}
+
+/// Per (symbol, timeframe) analytics state, plus per-symbol models on the prediction timeframe
+struct Pipeline {
+    predict_tf: String,
+    model_spec: ModelSpec,
+    indicators: HashMap<SeriesKey, IndicatorSet>,
+    regime_cfg: RegimeConfig,
+    regimes: HashMap<SeriesKey, RegimeDetector>,
+    features: HashMap<SeriesKey, FeatureExtractor>,
+    /// Benchmark returns feeding the cross-symbol features
+    benchmarks: Benchmarks,
+    models: HashMap<String, Box<dyn Model>>,
+    shadow: Option<Shadow>,
+    pending: HashMap<String, Pending>,
+    /// EWMA of directional hits per (symbol, model)
+    hit_rate: HashMap<SeriesKey, f64>,
+    /// EWMA of squared errors per (symbol, model): the spread for models without their own
+    error_var: HashMap<SeriesKey, f64>,
+    signals: SignalGenerator,
+    anomalies: AnomalyConfig,
+    paper: Option<PaperBook>,
+    /// Live feature distributions vs the active model's training data
+    drift: Option<DriftMonitor>,
+    /// Running feature statistics, shared with the dataset exporter through Redis
+    normalizer: Normalizer,
+    /// Per-stage tick-to-prediction latency and drift gauges
+    metrics: Metrics,
+}
+
+impl Pipeline {
+    fn new(predict_tf: String, model_spec: ModelSpec, paper: Option<PaperBook>) -> Self {
+        Self {
+            predict_tf,
+            model_spec,
+            indicators: HashMap::new(),
+            regime_cfg: RegimeConfig::from_env(),
+            regimes: HashMap::new(),
+            features: HashMap::new(),
+            benchmarks: Benchmarks::from_env(),
+            models: HashMap::new(),
+            shadow: None,
+            pending: HashMap::new(),
+            hit_rate: HashMap::new(),
+            error_var: HashMap::new(),
+            signals: SignalGenerator::new(SignalConfig::from_env()),
+            anomalies: AnomalyConfig::from_env(),
+            paper,
+            drift: None,
+            normalizer: Normalizer::from_env(),
+            metrics: Metrics::new("predictor"),
+        }
+    }
+
+    /// Swap in a new model for every symbol; online state restarts from scratch
+    fn set_model(&mut self, model_spec: ModelSpec) {
+        self.model_spec = model_spec;
+        self.models.clear();
+        self.pending.clear();
+        self.hit_rate.clear();
+        self.error_var.clear();
+    }
+
+    /// Start (or stop, with `None`) shadowing a candidate model from scratch
+    fn set_shadow(&mut self, shadow: Option<(i32, ModelSpec)>) {
+        self.shadow = shadow.map(|(id, spec)| Shadow {
+            id,
+            spec,
+            models: HashMap::new(),
+        });
+    }
+
+    /// Promote the shadow to active, keeping the online state it learned while shadowing
+    fn promote_shadow(&mut self) {
+        if let Some(shadow) = self.shadow.take() {
+            self.model_spec = shadow.spec;
+            self.models = shadow.models;
+            self.pending.clear();
+            self.hit_rate.clear();
+            self.error_var.clear();
+        }
+    }
+
+    /// Run every stage for one closed bar and write the results in one pipeline.
+    /// Returns the feature vector and anomalies (any timeframe) and the prediction-timeframe outputs.
+    async fn on_bar(
+        &mut self,
+        redis: &mut RedisConn,
+        bar: &Bar,
+    ) -> redis::RedisResult<(Option<FeatureVector>, Vec<AnomalyEvent>, Option<BarOutput>)> {
+        let received_at = now_ms();
+        let key: SeriesKey = (bar.symbol.clone(), bar.tf.clone());
+        let mut pipe = redis::pipe();
+
+        // --- Indicators ---
+        let snapshot = self.indicators.entry(key.clone()).or_default().update(bar);
+        let fields = snapshot.fields();
+        if !fields.is_empty() {
+            pipe.hset_multiple(format!("{INDICATORS_PREFIX}{}:{}", bar.symbol, bar.tf), &fields)
+                .ignore();
+        }
+
+        // --- Regime ---
+        let (state, change) = self
+            .regimes
+            .entry(key.clone())
+            .or_default()
+            .update(&self.regime_cfg, bar);
+        if let Some(state) = state {
+            pipe.hset_multiple(format!("{REGIME_PREFIX}{}:{}", bar.symbol, bar.tf), &state.fields())
+                .ignore();
+        }
+        if let Some(event) = change
+            && let Ok(json) = serde_json::to_string(&event)
+        {
+            pipe.publish(REGIMES_CHANNEL, json).ignore();
+        }
+
+        // --- Features (and the volatility estimates they carry) ---
+        self.benchmarks.on_bar(bar);
+        let extractor = self.features.entry(key).or_default();
+        let features = extractor.update(bar, &self.benchmarks);
+        let vol = extractor.volatility();
+        let anomalies = self.anomalies.detect(bar, &extractor.anomaly());
+        for event in &anomalies {
+            if let Ok(json) = serde_json::to_string(event) {
+                pipe.publish(ANOMALIES_CHANNEL, json).ignore();
+            }
+        }
+        if !extractor.patterns().is_empty() {
+            let event = PatternEvent {
+                symbol: bar.symbol.clone(),
+                tf: bar.tf.clone(),
+                ts: bar.end(),
+                close: bar.close,
+                patterns: extractor.patterns().to_vec(),
+            };
+            if let Ok(json) = serde_json::to_string(&event) {
+                pipe.publish(PATTERNS_CHANNEL, json).ignore();
+            }
+        }
+        let vol_fields = vol.fields();
+        if !vol_fields.is_empty() {
+            pipe.hset_multiple(format!("{VOLATILITY_PREFIX}{}:{}", bar.symbol, bar.tf), &vol_fields)
+                .ignore();
+        }
+        let Some(fv) = features else {
+            return pipe.query_async(redis).await.map(|()| (None, anomalies, None));
+        };
+        pipe.hset_multiple(format!("{FEATURES_PREFIX}{}:{}", bar.symbol, bar.tf), &fv.fields())
+            .ignore();
+        let stored = fv.clone();
+
+        // --- Prediction: learn from the realized return, then predict the next bar ---
+        if bar.tf != self.predict_tf {
+            return pipe.query_async(redis).await.map(|()| (Some(stored), anomalies, None));
+        }
+
+        let features_at = now_ms();
+
+        // --- Scaling: update the running statistics and persist them with the scaled vector ---
+        self.normalizer.observe(&fv.values);
+        let scaled = FeatureVector {
+            values: self.normalizer.transform(&fv.values),
+            ..fv.clone()
+        };
+        pipe.hset_multiple(format!("{SCALED_FEATURES_PREFIX}{}:{}", bar.symbol, bar.tf), &scaled.fields())
+            .ignore();
+        pipe.set(format!("{NORMALIZER_PREFIX}{}", bar.tf), self.normalizer.to_json())
+            .ignore();
+
+        // --- Drift ---
+        let drift = self.drift.as_mut().and_then(|d| d.observe(&fv.values));
+        if let Some(report) = &drift {
+            pipe.hset_multiple(format!("{DRIFT_PREFIX}{}", bar.tf), &report.fields())
+                .ignore();
+            for (name, psi) in FEATURE_NAMES.iter().zip(&report.psi) {
+                if let Some(psi) = psi {
+                    self.metrics
+                        .set_gauge("feature_psi", &format!("feature=\"{name}\""), *psi);
+                }
+            }
+        }
+
+        let model = self
+            .models
+            .entry(bar.symbol.clone())
+            .or_insert_with(|| self.model_spec.build(FEATURE_NAMES.len()));
+        if let Some((x, prev_close, prev_predicted)) = self.pending.remove(&bar.symbol)
+            && prev_close > 0.0
+            && bar.close > 0.0
+        {
+            let realized = (bar.close / prev_close).ln();
+            model.update(&x, realized);
+            if let Some(shadow) = self.shadow.as_mut()
+                && let Some(m) = shadow.models.get_mut(&bar.symbol)
+            {
+                m.update(&x, realized);
+            }
+            for (name, predicted) in prev_predicted {
+                let hit = if predicted.signum() == realized.signum() { 1.0 } else { 0.0 };
+                let rate = self.hit_rate.entry((bar.symbol.clone(), name.clone())).or_insert(0.5);
+                *rate += CONFIDENCE_ALPHA * (hit - *rate);
+                let sq = (predicted - realized).powi(2);
+                let var = self.error_var.entry((bar.symbol.clone(), name)).or_insert(sq);
+                *var += CONFIDENCE_ALPHA * (sq - *var);
+            }
+        }
+
+        let predicted = model.predict(&fv.values);
+        let predicted_at = now_ms();
+        let target_ts = fv.ts + (bar.end() - bar.start);
+        let make = |name: &str, version: String, predicted: f64, std_dev: Option<f64>| {
+            let key = (bar.symbol.clone(), name.to_string());
+            let mut p = Prediction {
+                symbol: bar.symbol.clone(),
+                horizon: bar.tf.clone(),
+                predicted_return: predicted,
+                predicted_price: bar.close * predicted.exp(),
+                base_price: bar.close,
+                confidence: self.hit_rate.get(&key).copied().unwrap_or(0.5),
+                std_dev: 0.0,
+                q10: predicted,
+                q50: predicted,
+                q90: predicted,
+                model: name.to_string(),
+                model_version: version,
+                feature_ts: fv.ts,
+                target_ts,
+                exchange_ts: bar.closed_by_ts,
+                ingest_ts: bar.published_at,
+                predicted_at,
+            };
+            // The model's own estimate, else its realized error spread on this symbol
+            if let Some(sd) = std_dev.or_else(|| self.error_var.get(&key).map(|v| v.sqrt())) {
+                p.set_uncertainty(sd);
+            }
+            p
+        };
+
+        let prediction = make(model.name(), model.version(), predicted, model.std_dev(&fv.values));
+        pipe.hset_multiple(format!("{PREDICTION_PREFIX}{}", bar.symbol), &prediction.fields())
+            .ignore();
+        if let Ok(json) = serde_json::to_string(&prediction) {
+            pipe.publish(PREDICTIONS_CHANNEL, json).ignore();
+        }
+        if let Some(importance) =
+            FeatureImportance::compute(&bar.symbol, model.as_ref(), &self.normalizer.stds(), fv.ts)
+        {
+            pipe.hset_multiple(format!("{IMPORTANCE_PREFIX}{}", bar.symbol), &importance.fields())
+                .ignore();
+        }
+
+        let mut members = Vec::new();
+        for m in model.members(&fv.values) {
+            let p = make(&m.label, m.version, m.predicted_return, m.std_dev);
+            let mut fields = p.fields();
+            fields.push(("weight".to_string(), m.weight.to_string()));
+            pipe.hset_multiple(format!("{MEMBER_PREDICTION_PREFIX}{}:{}", bar.symbol, m.label), &fields)
+                .ignore();
+            members.push(p);
+        }
+
+        let shadow = self.shadow.as_mut().map(|s| {
+            let m = s
+                .models
+                .entry(bar.symbol.clone())
+                .or_insert_with(|| s.spec.build(FEATURE_NAMES.len()));
+            let std_dev = m.std_dev(&fv.values);
+            (s.id, make(&format!("shadow:{}", m.name()), m.version(), m.predict(&fv.values), std_dev))
+        });
+        if let Some((_, p)) = &shadow {
+            pipe.hset_multiple(format!("{SHADOW_PREDICTION_PREFIX}{}", bar.symbol), &p.fields())
+                .ignore();
+        }
+
+        let outcomes = std::iter::once(&prediction)
+            .chain(&members)
+            .chain(shadow.as_ref().map(|(_, p)| p))
+            .map(|p| (p.model.clone(), p.predicted_return))
+            .collect();
+        let inputs = fv.values.clone();
+        self.pending
+            .insert(bar.symbol.clone(), (fv.values, bar.close, outcomes));
+
+        // --- Latency: exchange → ingest → delivery → features → prediction ---
+        let mut stage = |name: &str, from: i64, to: i64| {
+            if from > 0 {
+                self.metrics
+                    .observe_latency("stage_latency_ms", &format!("stage=\"{name}\""), (to - from) as f64);
+            }
+        };
+        stage("ingest", bar.closed_by_ts, bar.published_at);
+        stage("delivery", bar.published_at, received_at);
+        stage("features", received_at, features_at);
+        stage("prediction", features_at, predicted_at);
+        stage("total", bar.closed_by_ts, predicted_at);
+
+        // --- Signal ---
+        let signal = self.signals.on_prediction(&prediction, state.map(|s| s.regime));
+        pipe.hset_multiple(format!("{SIGNAL_PREFIX}{}", bar.symbol), &signal.fields())
+            .ignore();
+        if let Ok(json) = serde_json::to_string(&signal) {
+            pipe.publish(SIGNALS_CHANNEL, json).ignore();
+        }
+
+        // --- Paper trading ---
+        let (fill, snapshot) = match self.paper.as_mut() {
+            Some(book) => {
+                book.mark(&bar.symbol, bar.close);
+                let fill = book.on_signal(&signal, bar.close, vol.best());
+                let snapshot = book.maybe_snapshot(fv.ts);
+                if let Some(snap) = &snapshot {
+                    pipe.hset_multiple(PAPER_EQUITY_KEY, &snap.fields()).ignore();
+                }
+                (fill, snapshot)
+            }
+            None => (None, None),
+        };
+
+        pipe.query_async(redis).await.map(|()| {
+            (
+                Some(stored),
+                anomalies,
+                Some(BarOutput {
+                    inputs,
+                    prediction,
+                    members,
+                    shadow,
+                    drift,
+                    signal,
+                    fill,
+                    snapshot,
+                }),
+            )
+        })
+    }
+}
+
+/// Drift monitor against the active model's training window, or the last
+/// `DRIFT_REFERENCE_HOURS` of stored features when the registry has none
+async fn drift_monitor(pg: &PgClient, tf: &str, model: &ModelRecord) -> Option<DriftMonitor> {
+    let (from, to) = match (model.train_from, model.train_to) {
+        (Some(from), Some(to)) => (from, to),
+        _ => {
+            let hours = env::var("DRIFT_REFERENCE_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_DRIFT_REFERENCE_HOURS);
+            let now = Utc::now().naive_utc();
+            (now - chrono::Duration::hours(hours), now)
+        }
+    };
+    match drift::load_reference(pg, tf, from, to).await {
+        Ok(rows) => {
+            let monitor = DriftMonitor::new(DriftConfig::from_env(), &rows);
+            match &monitor {
+                Some(_) => info!("📐 Drift reference: {} {tf} vectors {from} → {to}", rows.len()),
+                None => warn!("⚠️ Too few stored {tf} features {from} → {to} — drift detection off"),
+            }
+            monitor
+        }
+        Err(e) => {
+            error!("❌ {e} — drift detection off");
+            None
+        }
+    }
+}
+
+/// `ALERT_WEBHOOK_URL` and the Discord/Slack notifier, whichever are configured
+struct AlertSinks {
+    webhook: Option<Webhook>,
+    notifier: Option<Notifier>,
+}
+
+impl AlertSinks {
+    fn enabled(&self) -> bool {
+        self.webhook.is_some() || self.notifier.is_some()
+    }
+
+    fn send(&self, alert: Alert) {
+        if let Some(n) = &self.notifier {
+            n.notify(alert.clone());
+        }
+        if let Some(hook) = &self.webhook {
+            hook.notify(alert);
+        }
+    }
+}
+
+fn log_active(record: &ModelRecord) {
+    info!(
+        "🗂️ Active model #{} {}@{}{}",
+        record.id,
+        record.name,
+        record.version,
+        record
+            .artifact_hash
+            .as_deref()
+            .map(|h| format!(" (sha256 {})", &h[..12.min(h.len())]))
+            .unwrap_or_default()
+    );
+}
+
+/// Turns closed bars into features, predictions and signals, with optional paper or live trading
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+}
+
+async fn run(args: ConfigArgs) -> Result<(), String> {
+    let (config, _log) = args.start(env!("CARGO_CRATE_NAME"), &[Need::Redis, Need::Postgres]).await?;
+    info!("🔮 Predictor starting…");
+
+    let health = Health::new("predictor");
+    health::spawn_server(health.clone());
+    let client = RedisClient::open_with(&config.redis_url(), &config.tls.redis).map_err(|e| e.to_string())?;
+    info!("🌐 Connecting to Redis ({})...", client.describe());
+    let mut redis = client.connect().await.map_err(|e| e.to_string())?;
+    if let Some(url) = config.redis_mirror_url() {
+        redis.mirror_to(Mirror::open(&url, &config.tls.redis).map_err(|e| e.to_string())?);
+    }
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    let pg = Arc::new(pg);
+    tokio::spawn(health::probe(health.clone(), Some(redis.clone()), Some(pg.clone())));
+    health.expect("bars", None);
+    predictions::ensure_table(&pg)
+        .await
+        .map_err(|e| format!("Failed to create predictions table: {e}"))?;
+
+    // Feature store: persist every vector for skew-free training data (FEATURE_STORE=0 disables)
+    let store_features = env::var("FEATURE_STORE").map_or(true, |v| v != "0" && v != "false");
+    if store_features {
+        feature_store::ensure_tables(&pg)
+            .await
+            .map_err(|e| format!("Failed to create feature store tables: {e}"))?;
+        info!("🗄️ Feature store enabled (schema v{FEATURE_SCHEMA_VERSION})");
+    }
+    let predict_tf =
+        env::var("PREDICT_TIMEFRAME").unwrap_or_else(|_| DEFAULT_PREDICT_TIMEFRAME.to_string());
+    info!("🎯 Predicting next-bar returns on the {predict_tf} timeframe");
+
+    // Optional paper trading on top of the signals
+    let paper_enabled = env::var("PAPER_TRADING").is_ok_and(|v| v == "1" || v == "true");
+    if paper_enabled {
+        paper::ensure_tables(&pg)
+            .await
+            .map_err(|e| format!("Failed to create paper trading tables: {e}"))?;
+        info!("📝 Paper trading enabled");
+    }
+    let book = paper_enabled.then(|| PaperBook::new(PaperConfig::from_env()));
+
+    // Optional live (testnet) order execution
+    let mut executor = ExecutionConfig::from_env().map(|cfg| {
+        info!("💱 Order execution enabled against {}", cfg.base_url);
+        Executor::new(cfg)
+    });
+    if executor.is_some() {
+        execution::ensure_table(&pg)
+            .await
+            .map_err(|e| format!("Failed to create execution_fills table: {e}"))?;
+    }
+
+    // Model registry: the configured model is always registered, but an active
+    // registry entry (e.g. a rollback) takes precedence over it
+    registry::ensure_table(&pg)
+        .await
+        .map_err(|e| format!("Failed to create model_registry table: {e}"))?;
+    let configured = ModelSpec::from_env(FEATURE_NAMES.len());
+    let configured_record = registry::register(&pg, &configured, &Default::default())
+        .await
+        ?;
+    let active = registry::active(&pg).await?;
+    let (model_spec, mut active_model) = match active {
+        Some(record) if record.id != configured_record.id => match record.load(FEATURE_NAMES.len()) {
+            Ok(spec) => (spec, record),
+            Err(e) => {
+                error!("❌ {e} — activating the configured model instead");
+                let record = registry::activate(&pg, configured_record.id)
+                    .await
+                    ?;
+                (configured, record)
+            }
+        },
+        Some(record) => (configured, record),
+        None => {
+            let record = registry::activate(&pg, configured_record.id)
+                .await
+                ?;
+            (configured, record)
+        }
+    };
+    log_active(&active_model);
+    let alerts = AlertSinks {
+        webhook: Webhook::from_env(),
+        notifier: Notifier::from_env("predictor"),
+    };
+    let drift_enabled = env::var("DRIFT_DETECTION").map_or(true, |v| v != "0" && v != "false");
+    let mut pipeline = Pipeline::new(predict_tf.clone(), model_spec, book);
+    match Normalizer::load_redis(&mut redis, &predict_tf).await {
+        Ok(Some(mut n)) => {
+            // The statistics serve either method; NORMALIZE_METHOD decides which is applied
+            n.method = pipeline.normalizer.method;
+            info!("📏 Resuming {} feature statistics over {} vectors", n.method, n.count());
+            pipeline.normalizer = n;
+        }
+        Ok(None) => info!("📏 Starting {} feature statistics", pipeline.normalizer.method),
+        Err(e) => warn!("⚠️ {e} — starting feature statistics from scratch"),
+    }
+    if drift_enabled {
+        pipeline.drift = drift_monitor(&pg, &predict_tf, &active_model).await;
+    }
+    let mut drift_model = active_model.id;
+    let mut throttle = AlertThrottle::default();
+
+    // Hot-reload: poll the model dir and the registry (first tick fires immediately)
+    let mut watcher = ModelWatcher::from_env(FEATURE_NAMES.len());
+    let mut reload_tick = interval(
+        watcher
+            .as_ref()
+            .map(|w| w.poll_every())
+            .unwrap_or_else(models::poll_interval),
+    );
+    let heartbeat = Heartbeat::spawn("predictor", redis.clone());
+    let mut beat_tick = heartbeat.ticker();
+    let shutdown = shutdown::on_signal();
+    let resubscribe_pause = || async {
+        tokio::select! {
+            _ = sleep(RESUBSCRIBE_DELAY) => {}
+            _ = shutdown.cancelled() => {}
+        }
+    };
+
+    while !shutdown.is_cancelled() {
+        heartbeat.pause("subscribing", RESUBSCRIBE_DELAY);
+        let mut pubsub = match client.pubsub().await {
+            Ok(p) => p,
+            Err(e) => {
+                error!("❌ Redis pub/sub connection failed: {e}, retrying...");
+                health.fail("bars", &e);
+                resubscribe_pause().await;
+                continue;
+            }
+        };
+        if let Err(e) = pubsub.subscribe(BARS_CHANNEL).await {
+            error!("❌ Subscribe to '{BARS_CHANNEL}' failed: {e}, retrying...");
+            health.fail("bars", &e);
+            resubscribe_pause().await;
+            continue;
+        }
+        info!("📡 Subscribed to '{BARS_CHANNEL}'");
+        health.ok("bars");
+
+        let mut stream = pubsub.on_message();
+        loop {
+            let msg = tokio::select! {
+                msg = stream.next() => match msg {
+                    Some(m) => m,
+                    None => break,
+                },
+                _ = beat_tick.tick() => {
+                    heartbeat.beat("subscribed");
+                    continue;
+                }
+                _ = shutdown.cancelled() => break,
+                _ = reload_tick.tick() => {
+                    if let Some(spec) = watcher.as_mut().and_then(|w| w.poll()) {
+                        let spec = pipeline.model_spec.reloaded(spec);
+                        // A known version that isn't active was deliberately rolled back
+                        match registry::find(&pg, spec.name(), &spec.version()).await {
+                            Ok(Some(record)) if !record.active => info!(
+                                "🗂️ {}@{} is registered but inactive — keeping registry model #{}",
+                                record.name, record.version, active_model.id
+                            ),
+                            Ok(_) => match registry::deploy(&pg, &spec).await {
+                                Ok(record) => {
+                                    active_model = record;
+                                    log_active(&active_model);
+                                    pipeline.set_model(spec);
+                                }
+                                Err(e) => error!("❌ {e} — keeping current model"),
+                            },
+                            Err(e) => error!("❌ {e}"),
+                        }
+                    }
+                    match registry::active(&pg).await {
+                        Ok(Some(record)) if pipeline.shadow.as_ref().is_some_and(|s| s.id == record.id) => {
+                            info!("🚀 Promoting shadow model #{}", record.id);
+                            active_model = record;
+                            log_active(&active_model);
+                            pipeline.promote_shadow();
+                        }
+                        Ok(Some(record)) if record.id != active_model.id => {
+                            match record.load(FEATURE_NAMES.len()) {
+                                Ok(spec) => {
+                                    active_model = record;
+                                    log_active(&active_model);
+                                    pipeline.set_model(spec);
+                                }
+                                Err(e) => error!("❌ {e} — keeping current model"),
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => error!("❌ {e}"),
+                    }
+                    let running = pipeline.shadow.as_ref().map(|s| s.id);
+                    match registry::shadow(&pg).await {
+                        Ok(Some(record)) if Some(record.id) != running => match record.load(FEATURE_NAMES.len()) {
+                            Ok(spec) => {
+                                info!("👥 Shadowing model #{} {}@{}", record.id, record.name, record.version);
+                                pipeline.set_shadow(Some((record.id, spec)));
+                            }
+                            Err(e) => error!("❌ {e} — not shadowing"),
+                        },
+                        Ok(None) if running.is_some() => {
+                            info!("👥 Shadow model cleared");
+                            pipeline.set_shadow(None);
+                        }
+                        Ok(_) => {}
+                        Err(e) => error!("❌ {e}"),
+                    }
+                    // New active model: compare against its own training data
+                    if drift_enabled && drift_model != active_model.id {
+                        pipeline.drift = drift_monitor(&pg, &predict_tf, &active_model).await;
+                        drift_model = active_model.id;
+                    }
+                    continue;
+                }
+            };
+
+            let payload: String = match msg.get_payload() {
+                Ok(p) => p,
+                Err(e) => {
+                    warn!("⚠️ Unreadable bar payload: {e}");
+                    continue;
+                }
+            };
+            let bar: Bar = match serde_json::from_str(&payload) {
+                Ok(b) => b,
+                Err(e) => {
+                    warn!("⚠️ Invalid bar JSON: {e}");
+                    continue;
+                }
+            };
+
+            let (stored, anomalies, output) = match pipeline.on_bar(&mut redis, &bar).await {
+                Ok(p) => p,
+                Err(e) => {
+                    error!("❌ Redis write error: {e}");
+                    continue;
+                }
+            };
+            if let Err(e) = pipeline.metrics.flush_if_due(&mut redis).await {
+                error!("❌ Redis metrics write error: {e}");
+            }
+
+            for event in anomalies {
+                warn!(
+                    "🚨 {:?} on {} {}: z {:.1} (close {})",
+                    event.kind, event.symbol, event.tf, event.z, event.close
+                );
+                if alerts.enabled() && throttle.allow(&event, pipeline.anomalies.alert_cooldown_ms) {
+                    alerts.send(Alert {
+                        kind: "anomaly".to_string(),
+                        message: format!("{:?} on {} {} (z {:.1})", event.kind, event.symbol, event.tf, event.z),
+                        ts: now_ms(),
+                        details: serde_json::to_value(&event).unwrap_or_default(),
+                    });
+                }
+            }
+
+            if store_features && let Some(fv) = &stored {
+                match timeout(POSTGRES_TIMEOUT, feature_store::insert(&pg, fv)).await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => error!("❌ Postgres feature insert error: {e}"),
+                    Err(_) => warn!("⏱️ Postgres feature insert timed out"),
+                }
+            }
+
+            // --- Persist for later evaluation ---
+            let Some(out) = output else {
+                continue;
+            };
+            match timeout(POSTGRES_TIMEOUT, predictions::insert(&pg, &out.prediction, Some(active_model.id), &out.inputs)).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => error!("❌ Postgres prediction insert error: {e}"),
+                Err(_) => warn!("⏱️ Postgres prediction insert timed out"),
+            }
+            for member in &out.members {
+                if let Err(e) = predictions::insert(&pg, member, None, &out.inputs).await {
+                    error!("❌ Postgres member prediction insert error: {e}");
+                }
+            }
+            if let Some(report) = &out.drift {
+                for (change, features) in [("drifted", &report.drifted), ("recovered", &report.recovered)] {
+                    if features.is_empty() {
+                        continue;
+                    }
+                    let message = format!("Feature drift on {predict_tf}: {} {change}", features.join(", "));
+                    if change == "drifted" {
+                        warn!("🌊 {message}");
+                    } else {
+                        info!("🌊 {message}");
+                    }
+                    if alerts.enabled() {
+                        let psi: serde_json::Map<String, serde_json::Value> = FEATURE_NAMES
+                            .iter()
+                            .zip(&report.psi)
+                            .filter_map(|(name, psi)| Some((name.to_string(), (*psi)?.into())))
+                            .collect();
+                        alerts.send(Alert {
+                            kind: format!("feature_{change}"),
+                            message,
+                            ts: now_ms(),
+                            details: serde_json::json!({
+                                "tf": predict_tf,
+                                "model_id": active_model.id,
+                                "features": features,
+                                "threshold": pipeline.drift.as_ref().map(|d| d.threshold()),
+                                "psi": psi,
+                            }),
+                        });
+                    }
+                }
+            }
+            if let Some((id, shadow)) = &out.shadow
+                && let Err(e) = predictions::insert(&pg, shadow, Some(*id), &out.inputs).await
+            {
+                error!("❌ Postgres shadow prediction insert error: {e}");
+            }
+            if let Some(fill) = &out.fill {
+                info!(
+                    "📝 Paper {} {:.6} {} @ {:.4} ({})",
+                    if fill.qty > 0.0 { "BUY" } else { "SELL" },
+                    fill.qty.abs(),
+                    fill.symbol,
+                    fill.price,
+                    out.signal.side
+                );
+                if let Err(e) = paper::insert_fill(&pg, fill).await {
+                    error!("❌ Postgres paper fill insert error: {e}");
+                }
+            }
+            if let Some(snap) = &out.snapshot
+                && let Err(e) = paper::insert_snapshot(&pg, snap).await
+            {
+                error!("❌ Postgres equity insert error: {e}");
+            }
+
+            // --- Execution ---
+            if let Some(exec) = executor.as_mut() {
+                match exec.on_signal(&mut redis, &out.signal, out.prediction.base_price).await {
+                    Ok(Some(fill)) => {
+                        info!(
+                            "💱 {} {} {:.8} ({}) order {}",
+                            fill.side, fill.symbol, fill.executed_qty, fill.status, fill.order_id
+                        );
+                        if let Err(e) = execution::insert_fill(&pg, &fill).await {
+                            error!("❌ Postgres execution fill insert error: {e}");
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => error!("❌ Execution error for {}: {e}", out.signal.symbol),
+                }
+            }
+        }
+
+        if shutdown.is_cancelled() {
+            break;
+        }
+        warn!("🔁 Bar subscription dropped. Resubscribing...");
+        health.fail("bars", "subscription dropped");
+        resubscribe_pause().await;
+    }
+
+    // A bar in flight has finished above; write out what is buffered before exiting
+    heartbeat.beat("stopping");
+    if let Err(e) = pipeline.metrics.flush(&mut redis).await {
+        error!("❌ Redis metrics write error: {e}");
+    }
+    if let Some(n) = alerts.notifier {
+        n.close().await;
+    }
+    info!("👋 Predictor stopped");
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse().common).await)
+}
@@ -0,0 +1,75 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::{Parser, Subcommand};
+use data_collection::{
+    cli::{self, ConfigArgs},
+    config::Need,
+    keys, redis_conn, snapshot,
+};
+use dotenv::dotenv;
+
+/// Saves the pipeline's Redis state to a file and writes it back
+#[derive(Parser)]
+#[command(version, after_help = "Stop the ingester first for a consistent snapshot.")]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write the pipeline's keys (tracked symbols, latest trades, bars and bar history, indicator
+    /// and model state) to FILE, one JSON key per line; caches and liveness keys are left out
+    Snapshot {
+        file: PathBuf,
+        /// Only keys matching this pattern
+        #[arg(long, default_value_t = keys::ALL_PATTERN.to_string())]
+        pattern: String,
+    },
+    /// Write a snapshot back, keeping keys that already exist
+    Restore {
+        file: PathBuf,
+        /// Replace keys that already exist
+        #[arg(long)]
+        overwrite: bool,
+    },
+}
+
+async fn run(args: Cli) -> Result<(), String> {
+    let (config, _log) = args.common.start(env!("CARGO_CRATE_NAME"), &[Need::Redis]).await?;
+    let mut redis = redis_conn::try_connect(&config.redis_url(), &config.tls.redis)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match args.command {
+        Command::Snapshot { file, pattern } => {
+            let entries = snapshot::take(&mut redis, &pattern)
+                .await
+                .map_err(|e| format!("Snapshot failed: {e}"))?;
+            snapshot::write(&file, &entries).map_err(|e| format!("Could not write {}: {e}", file.display()))?;
+            println!("✅ Saved {} keys matching {pattern} to {}", entries.len(), file.display());
+        }
+        Command::Restore { file, overwrite } => {
+            let entries = snapshot::read(&file)?;
+            let report = snapshot::restore(&mut redis, &entries, overwrite)
+                .await
+                .map_err(|e| format!("Restore failed: {e}"))?;
+            println!(
+                "✅ Restored {} keys from {}, kept {} existing",
+                report.restored,
+                file.display(),
+                report.kept
+            );
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse()).await)
+}
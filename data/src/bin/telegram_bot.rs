@@ -0,0 +1,130 @@
+use std::{process::ExitCode, time::Duration};
+
+use clap::Parser;
+use tracing::{error, info, warn};
+use data_collection::{
+    alerts::Telegram,
+    cli::{self, ConfigArgs},
+    config::Need,
+    heartbeat::Heartbeat,
+    keys::TELEGRAM_CHATS_KEY,
+    redis_conn::{RedisClient, RedisConn},
+    telegram::{self, Command},
+    webhooks::EventType,
+};
+use dotenv::dotenv;
+use futures::StreamExt;
+use redis::AsyncCommands;
+use serde_json::Value;
+use tokio::time::sleep;
+
+const LONG_POLL: Duration = Duration::from_secs(25);
+const RETRY_DELAY: Duration = Duration::from_secs(3);
+
+/// Push every `events` message to the chats in `TELEGRAM_CHATS_KEY`
+async fn push(bot: Telegram, client: RedisClient, mut redis: RedisConn, events: Vec<EventType>) {
+    let channels: Vec<&str> = events.iter().map(EventType::channel).collect();
+    loop {
+        let mut pubsub = match client.pubsub().await {
+            Ok(p) => p,
+            Err(e) => {
+                error!("❌ Redis pub/sub connection failed: {e}, retrying...");
+                sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+        if let Err(e) = pubsub.subscribe(&channels).await {
+            error!("❌ Subscribe failed: {e}, retrying...");
+            sleep(RETRY_DELAY).await;
+            continue;
+        }
+        info!("📡 Pushing {channels:?} to chats with alerts on");
+
+        let mut stream = pubsub.on_message();
+        while let Some(msg) = stream.next().await {
+            let Some(kind) = EventType::from_channel(msg.get_channel_name()) else {
+                continue;
+            };
+            let Ok(Ok(event)) = msg.get_payload::<String>().map(|p| serde_json::from_str::<Value>(&p)) else {
+                warn!("⚠️ Unreadable {kind} payload");
+                continue;
+            };
+            let chats: Vec<String> = match redis.smembers(TELEGRAM_CHATS_KEY).await {
+                Ok(c) => c,
+                Err(e) => {
+                    error!("❌ Redis error: {e}");
+                    continue;
+                }
+            };
+            let text = telegram::format_event(kind, &event);
+            for chat in chats {
+                bot.notify(Some(chat), text.clone());
+            }
+        }
+
+        warn!("🔁 Subscription dropped. Resubscribing...");
+        sleep(RETRY_DELAY).await;
+    }
+}
+
+/// Answers chat commands and pushes pipeline events to subscribed Telegram chats
+#[derive(Parser)]
+#[command(version, after_help = "Needs TELEGRAM_BOT_TOKEN; TELEGRAM_PUSH lists the events pushed to chats")]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+}
+
+async fn run(args: ConfigArgs) -> Result<(), String> {
+    let (config, _log) = args.start(env!("CARGO_CRATE_NAME"), &[Need::Redis]).await?;
+    info!("🤖 Telegram bot starting…");
+
+    let bot = Telegram::from_env().ok_or("TELEGRAM_BOT_TOKEN not set")?;
+    let events = telegram::push_events_from_env().map_err(|e| format!("TELEGRAM_PUSH: {e}"))?;
+    let client = RedisClient::open_with(&config.redis_url(), &config.tls.redis).map_err(|e| e.to_string())?;
+    info!("🌐 Connecting to Redis ({})...", client.describe());
+    let mut redis = client.connect().await.map_err(|e| e.to_string())?;
+    if events.is_empty() {
+        warn!("⚠️ TELEGRAM_PUSH is empty — answering commands only");
+    } else {
+        tokio::spawn(push(bot.clone(), client, redis.clone(), events));
+    }
+
+    let heartbeat = Heartbeat::spawn("telegram_bot", redis.clone());
+    let mut offset = 0;
+    loop {
+        // The long poll plus a retry delay is the longest this loop legitimately waits
+        heartbeat.pause("polling", LONG_POLL + RETRY_DELAY);
+        let messages = match bot.updates(offset, LONG_POLL).await {
+            Ok((next, messages)) => {
+                offset = next;
+                messages
+            }
+            Err(e) => {
+                error!("❌ getUpdates failed: {e}");
+                sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+        for msg in messages {
+            let reply = match Command::parse(&msg.text) {
+                None => continue,
+                Some(Err(usage)) => usage,
+                Some(Ok(command)) => match telegram::answer(&mut redis, msg.chat_id, command).await {
+                    Ok(reply) => reply,
+                    Err(e) => {
+                        error!("❌ Redis error: {e}");
+                        "⚠️ Data store unavailable, try again shortly".to_string()
+                    }
+                },
+            };
+            bot.notify(Some(msg.chat_id.to_string()), reply);
+        }
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse().common).await)
+}
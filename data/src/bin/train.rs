@@ -0,0 +1,314 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use chrono::DateTime;
+use clap::Parser;
+use data_collection::{
+    cli::{self, ConfigArgs},
+    config::Need,
+    dataset::{self, Dataset},
+    features::{FEATURE_NAMES, FEATURE_SCHEMA_VERSION},
+    fetcher::try_connect_pg,
+    importance::shares,
+    models::ModelSpec,
+    native::{Artifact, Estimator, NativeModel, TreeNode},
+    normalize::{Method, Normalizer},
+    registry::{self, Provenance},
+};
+use dotenv::dotenv;
+use linfa::prelude::*;
+use linfa_linear::LinearRegression;
+use linfa_logistic::LogisticRegression;
+use ndarray::{Array1, Array2};
+use serde::Deserialize;
+use serde_json::json;
+use smartcore::{
+    linalg::basic::matrix::DenseMatrix,
+    tree::decision_tree_regressor::{DecisionTreeRegressor, DecisionTreeRegressorParameters},
+};
+
+const DEFAULT_HOLDOUT: f64 = 0.2;
+const DEFAULT_TREES: usize = 100;
+const DEFAULT_DEPTH: u16 = 3;
+const DEFAULT_LEARNING_RATE: f64 = 0.1;
+// Keeps boosted leaves from fitting a handful of outlier returns
+const MIN_SAMPLES_LEAF: usize = 20;
+const LOGISTIC_MAX_ITERATIONS: u64 = 200;
+const TOP_FEATURES: usize = 10;
+
+/// Fits a native model on an exported dataset and optionally registers it
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+
+    /// Dataset from export-dataset, .csv or .parquet
+    #[arg(long)]
+    data: PathBuf,
+
+    /// Where to write the model artifact (JSON)
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Target column (default: the dataset's first)
+    #[arg(long)]
+    target: Option<String>,
+
+    #[arg(long, default_value = "linear", value_parser = ["linear", "logistic", "gbm"])]
+    model: String,
+
+    /// Share of the latest rows held out for evaluation, in [0, 1)
+    #[arg(long, default_value_t = DEFAULT_HOLDOUT, value_parser = parse_holdout)]
+    holdout: f64,
+
+    /// Boosting rounds (gbm)
+    #[arg(long, default_value_t = DEFAULT_TREES)]
+    trees: usize,
+
+    /// Tree depth (gbm)
+    #[arg(long, default_value_t = DEFAULT_DEPTH)]
+    depth: u16,
+
+    /// Shrinkage per tree (gbm)
+    #[arg(long, default_value_t = DEFAULT_LEARNING_RATE)]
+    learning_rate: f64,
+
+    /// Add the artifact to the model registry
+    #[arg(long)]
+    register: bool,
+
+    /// Make the registered model active
+    #[arg(long, requires = "register")]
+    activate: bool,
+}
+
+fn parse_holdout(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(h) if (0.0..1.0).contains(&h) => Ok(h),
+        _ => Err(format!("must be a number in [0, 1), got '{s}'")),
+    }
+}
+
+fn matrix(z: &[Vec<f64>]) -> Array2<f64> {
+    Array2::from_shape_fn((z.len(), FEATURE_NAMES.len()), |(i, j)| z[i][j])
+}
+
+/// smartcore's serialized tree layout
+#[derive(Deserialize)]
+struct SerializedTree {
+    tree_regressor: Option<SerializedNodes>,
+}
+
+#[derive(Deserialize)]
+struct SerializedNodes {
+    nodes: Vec<TreeNode>,
+}
+
+/// OLS on the non-constant columns (constant ones standardize to 0 and would make it singular)
+fn fit_linear(z: &[Vec<f64>], y: &[f64]) -> Result<Estimator, String> {
+    let keep: Vec<usize> = (0..FEATURE_NAMES.len()).filter(|j| z.iter().any(|r| r[*j] != 0.0)).collect();
+    let x = Array2::from_shape_fn((z.len(), keep.len()), |(i, j)| z[i][keep[j]]);
+    let data = linfa::Dataset::new(x, Array1::from(y.to_vec()));
+    let fitted = LinearRegression::new()
+        .fit(&data)
+        .map_err(|e| format!("linear regression failed: {e}"))?;
+    let mut weights = vec![0.0; FEATURE_NAMES.len()];
+    for (j, w) in keep.iter().zip(fitted.params()) {
+        weights[*j] = *w;
+    }
+    Ok(Estimator::Linear {
+        weights,
+        intercept: fitted.intercept(),
+    })
+}
+
+fn fit_logistic(z: &[Vec<f64>], y: &[f64]) -> Result<Estimator, String> {
+    let up: Array1<bool> = y.iter().map(|v| *v > 0.0).collect();
+    let data = linfa::Dataset::new(matrix(z), up);
+    let fitted = LogisticRegression::default()
+        .max_iterations(LOGISTIC_MAX_ITERATIONS)
+        .fit(&data)
+        .map_err(|e| format!("logistic regression failed: {e}"))?;
+    // Probabilities are for the positive class; flip if linfa picked `false`
+    let sign = if fitted.labels().pos.class { 1.0 } else { -1.0 };
+    Ok(Estimator::Logistic {
+        weights: fitted.params().iter().map(|w| sign * w).collect(),
+        intercept: sign * fitted.intercept(),
+        scale: y.iter().map(|v| v.abs()).sum::<f64>() / y.len().max(1) as f64,
+    })
+}
+
+fn fit_gbm(z: &[Vec<f64>], y: &[f64], trees: usize, depth: u16, learning_rate: f64) -> Result<Estimator, String> {
+    let x = DenseMatrix::from_2d_vec(&z.to_vec()).map_err(|e| format!("invalid training matrix: {e}"))?;
+    let base = y.iter().sum::<f64>() / y.len().max(1) as f64;
+    let mut fitted = vec![base; y.len()];
+    let params = DecisionTreeRegressorParameters::default()
+        .with_max_depth(depth)
+        .with_min_samples_leaf(MIN_SAMPLES_LEAF);
+
+    let mut out = Vec::with_capacity(trees);
+    for i in 0..trees {
+        let residuals: Vec<f64> = y.iter().zip(&fitted).map(|(t, f)| t - f).collect();
+        let tree = DecisionTreeRegressor::fit(&x, &residuals, params.clone())
+            .map_err(|e| format!("tree {i} failed: {e}"))?;
+        let step: Vec<f64> = tree.predict(&x).map_err(|e| format!("tree {i} failed: {e}"))?;
+        for (f, s) in fitted.iter_mut().zip(&step) {
+            *f += learning_rate * s;
+        }
+        let nodes = serde_json::to_value(&tree)
+            .and_then(serde_json::from_value::<SerializedTree>)
+            .map_err(|e| format!("cannot export tree {i}: {e}"))?
+            .tree_regressor
+            .map(|t| t.nodes)
+            .unwrap_or_default();
+        out.push(nodes);
+    }
+    Ok(Estimator::Gbm {
+        base,
+        learning_rate,
+        trees: out,
+    })
+}
+
+/// MAE, RMSE and directional hit rate of the artifact on `(x, y)`
+fn evaluate(artifact: &Artifact, x: &[Vec<f64>], y: &[f64]) -> serde_json::Value {
+    let (mut abs, mut sq, mut hits, mut moves) = (0.0, 0.0, 0usize, 0usize);
+    for (x, y) in x.iter().zip(y) {
+        let p = artifact.predict(x);
+        abs += (p - y).abs();
+        sq += (p - y).powi(2);
+        if *y != 0.0 {
+            moves += 1;
+            if p.signum() == y.signum() {
+                hits += 1;
+            }
+        }
+    }
+    let n = y.len().max(1) as f64;
+    json!({
+        "samples": y.len(),
+        "mae": abs / n,
+        "rmse": (sq / n).sqrt(),
+        "hit_rate": if moves > 0 { hits as f64 / moves as f64 } else { 0.0 },
+    })
+}
+
+fn load(path: &Path) -> Result<Dataset, String> {
+    if path.extension().is_some_and(|e| e == "parquet") {
+        #[cfg(feature = "parquet")]
+        return dataset::read_parquet(path);
+        #[cfg(not(feature = "parquet"))]
+        return Err("reading Parquet requires the `parquet` feature".to_string());
+    }
+    dataset::read_csv(path)
+}
+
+async fn run(args: Cli) -> Result<(), String> {
+    let needs: &[Need] = if args.register { &[Need::Postgres] } else { &[] };
+    let (config, _log) = args.common.start(env!("CARGO_CRATE_NAME"), needs).await?;
+    let (data, out, kind, holdout) = (args.data.display(), args.out.as_path(), args.model.as_str(), args.holdout);
+
+    // --- Dataset, split by time ---
+    println!("📂 Loading {data}…");
+    let mut ds = load(&args.data)?;
+    let target = args.target.clone().unwrap_or_else(|| ds.target_names[0].clone());
+    let t = ds
+        .target_names
+        .iter()
+        .position(|n| *n == target)
+        .ok_or_else(|| format!("no target '{target}' in {data} (have {})", ds.target_names.join(", ")))?;
+    ds.rows.retain(|(r, y)| y[t].is_finite() && r.values.iter().all(|v| v.is_finite()));
+    ds.rows.sort_by_key(|(r, _)| r.ts);
+    let split = ((ds.rows.len() as f64) * (1.0 - holdout)).round() as usize;
+    let (train, test) = ds.rows.split_at(split);
+    if train.len() < FEATURE_NAMES.len() * 2 {
+        return Err(format!("only {} training rows", train.len()));
+    }
+    let (x_train, y_train): (Vec<Vec<f64>>, Vec<f64>) = train.iter().map(|(r, y)| (r.values.clone(), y[t])).unzip();
+    let (x_test, y_test): (Vec<Vec<f64>>, Vec<f64>) = test.iter().map(|(r, y)| (r.values.clone(), y[t])).unzip();
+    println!("🧮 {} training / {} holdout rows, target {target}", train.len(), test.len());
+
+    // --- Fit ---
+    let scaler = Normalizer::fit(Method::ZScore, &x_train);
+    let z: Vec<Vec<f64>> = x_train.iter().map(|r| scaler.transform(r)).collect();
+    let estimator = match kind {
+        "logistic" => fit_logistic(&z, &y_train),
+        "gbm" => fit_gbm(&z, &y_train, args.trees, args.depth, args.learning_rate),
+        _ => fit_linear(&z, &y_train),
+    }?;
+
+    let mut artifact = Artifact {
+        schema_version: FEATURE_SCHEMA_VERSION,
+        feature_names: FEATURE_NAMES.iter().map(|s| s.to_string()).collect(),
+        target: target.clone(),
+        mean: scaler.means(),
+        std: scaler.stds(),
+        estimator,
+        metrics: serde_json::Value::Null,
+    };
+    let train_metrics = evaluate(&artifact, &x_train, &y_train);
+    let test_metrics = evaluate(&artifact, &x_test, &y_test);
+    println!("📈 train {train_metrics}");
+    println!("📈 holdout {test_metrics}");
+    let importance: serde_json::Map<String, serde_json::Value> = FEATURE_NAMES
+        .iter()
+        .zip(shares(&artifact.importance()).unwrap_or_default())
+        .map(|(name, share)| (name.to_string(), json!(share)))
+        .collect();
+    let mut ranked: Vec<(&String, f64)> = importance.iter().map(|(k, v)| (k, v.as_f64().unwrap_or(0.0))).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let top: Vec<String> = ranked
+        .iter()
+        .take(TOP_FEATURES)
+        .map(|(name, share)| format!("{name} {:.1}%", share * 100.0))
+        .collect();
+    println!("🔎 top features: {}", top.join(", "));
+    artifact.metrics = json!({
+        "model": kind,
+        "target": target,
+        "train": train_metrics,
+        "holdout": test_metrics,
+        "importance": importance,
+    });
+    artifact.save(out)?;
+    println!("💾 Wrote {}", out.display());
+
+    // --- Registry ---
+    if !args.register {
+        return Ok(());
+    }
+    let model = NativeModel::load(out, FEATURE_NAMES.len())?;
+    let ts = |ms: i64| DateTime::from_timestamp_millis(ms).map(|t| t.naive_utc());
+    let provenance = Provenance {
+        train_from: train.first().and_then(|(r, _)| ts(r.ts)),
+        train_to: train.last().and_then(|(r, _)| ts(r.ts)),
+        metrics: Some(artifact.metrics.clone()),
+    };
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    registry::ensure_table(&pg)
+        .await
+        .map_err(|e| format!("Failed to create model_registry table: {e}"))?;
+    let mut record = registry::register(&pg, &ModelSpec::Native(model), &provenance).await?;
+    if args.activate {
+        record = registry::activate(&pg, record.id).await?;
+    }
+    println!(
+        "✅ Registered #{} {}@{}{}",
+        record.id,
+        record.name,
+        record.version,
+        if record.active { " (active)" } else { "" }
+    );
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse()).await)
+}
@@ -0,0 +1,152 @@
+use std::{env, process::ExitCode, sync::Arc, time::Duration};
+
+use clap::Parser;
+use tracing::{error, info, warn};
+use data_collection::{
+    cli::{self, ConfigArgs},
+    config::Need,
+    fetcher::try_connect_pg,
+    heartbeat::Heartbeat,
+    redis_conn::RedisClient,
+    webhooks::{self, Deliverer, EventType, Subscription},
+};
+use dotenv::dotenv;
+use futures::StreamExt;
+use serde_json::Value;
+use tokio::{
+    sync::Semaphore,
+    time::{interval, sleep},
+};
+use tokio_postgres::Client as PgClient;
+
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(3);
+const DEFAULT_POLL_SECS: u64 = 30;
+// Deliveries in flight, retries included; events beyond it are dropped
+const DEFAULT_CONCURRENCY: usize = 32;
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// Every registered subscription; the previous set when the table cannot be read
+async fn reload(pg: &PgClient, subs: &mut Vec<Subscription>) {
+    match webhooks::list(pg, None).await {
+        Ok(fresh) => {
+            if fresh.len() != subs.len() {
+                info!("🪝 {} webhook subscriptions loaded", fresh.len());
+            }
+            *subs = fresh;
+        }
+        Err(e) => error!("❌ {e}"),
+    }
+}
+
+/// Delivers pipeline events to the registered webhook subscriptions
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    common: ConfigArgs,
+}
+
+async fn run(args: ConfigArgs) -> Result<(), String> {
+    let (config, _log) = args.start(env!("CARGO_CRATE_NAME"), &[Need::Redis, Need::Postgres]).await?;
+    info!("🪝 Webhook delivery worker starting…");
+
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    let pg = Arc::new(pg);
+    webhooks::ensure_table(&pg)
+        .await
+        .map_err(|e| format!("Failed to create webhook_subscriptions table: {e}"))?;
+    let client = RedisClient::open_with(&config.redis_url(), &config.tls.redis).map_err(|e| e.to_string())?;
+    info!("🌐 Connecting to Redis ({})...", client.describe());
+    let redis = client.connect().await.map_err(|e| e.to_string())?;
+
+    let deliverer = Deliverer::from_env();
+    let slots = Arc::new(Semaphore::new(env_or("WEBHOOK_CONCURRENCY", DEFAULT_CONCURRENCY).max(1)));
+    info!("🪝 Up to {} attempts per delivery", deliverer.max_attempts);
+
+    let mut subs = Vec::new();
+    reload(&pg, &mut subs).await;
+    let mut reload_tick = interval(Duration::from_secs(env_or("WEBHOOKS_POLL_SECS", DEFAULT_POLL_SECS).max(1)));
+    reload_tick.tick().await;
+    let channels: Vec<&str> = EventType::ALL.iter().map(EventType::channel).collect();
+    let heartbeat = Heartbeat::spawn("webhooks", redis);
+    let mut beat_tick = heartbeat.ticker();
+
+    loop {
+        heartbeat.pause("subscribing", RESUBSCRIBE_DELAY);
+        let mut pubsub = match client.pubsub().await {
+            Ok(p) => p,
+            Err(e) => {
+                error!("❌ Redis pub/sub connection failed: {e}, retrying...");
+                sleep(RESUBSCRIBE_DELAY).await;
+                continue;
+            }
+        };
+        if let Err(e) = pubsub.subscribe(&channels).await {
+            error!("❌ Subscribe failed: {e}, retrying...");
+            sleep(RESUBSCRIBE_DELAY).await;
+            continue;
+        }
+        info!("📡 Subscribed to {channels:?}");
+
+        let mut stream = pubsub.on_message();
+        loop {
+            let msg = tokio::select! {
+                msg = stream.next() => match msg {
+                    Some(m) => m,
+                    None => break,
+                },
+                _ = beat_tick.tick() => {
+                    heartbeat.beat("subscribed");
+                    continue;
+                }
+                _ = reload_tick.tick() => {
+                    reload(&pg, &mut subs).await;
+                    continue;
+                }
+            };
+            let Some(kind) = EventType::from_channel(msg.get_channel_name()) else {
+                continue;
+            };
+            let event: Value = match msg.get_payload::<String>().map(|p| serde_json::from_str(&p)) {
+                Ok(Ok(v)) => v,
+                _ => {
+                    warn!("⚠️ Unreadable {kind} payload");
+                    continue;
+                }
+            };
+            let event = Arc::new(event);
+
+            for sub in subs.iter().filter(|s| s.matches(kind, &event)) {
+                let Ok(slot) = slots.clone().try_acquire_owned() else {
+                    warn!("⚠️ Webhook #{} skipped a {kind} event: too many deliveries in flight", sub.id);
+                    continue;
+                };
+                let (sub, event, deliverer, pg) = (sub.clone(), event.clone(), deliverer.clone(), pg.clone());
+                tokio::spawn(async move {
+                    let result = deliverer.deliver(&sub, kind, &event).await;
+                    drop(slot);
+                    if let Err(e) = &result {
+                        error!("❌ Webhook #{} {kind} delivery failed: {e}", sub.id);
+                    }
+                    if let Err(e) = webhooks::record_result(&pg, sub.id, &result).await {
+                        warn!("⚠️ {e}");
+                    }
+                });
+            }
+        }
+
+        warn!("🔁 Subscription dropped. Resubscribing...");
+        sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    cli::exit(run(Cli::parse().common).await)
+}
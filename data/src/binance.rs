@@ -0,0 +1,142 @@
+use std::{env, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tokio::time::{sleep, Instant};
+use tracing::warn;
+
+use crate::finnhub::Candle;
+
+const BASE_URL: &str = "https://api.binance.com";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// A 1000-kline request weighs 2 of the 6000 per minute an IP may use; stay well below
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+/// Most klines Binance returns per request
+pub const MAX_KLINES: usize = 1000;
+
+/// Paced client for Binance's public kline endpoint; needs no API key
+pub struct BinanceClient {
+    http: reqwest::Client,
+    base_url: String,
+    last_request: Option<Instant>,
+}
+
+impl Default for BinanceClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BinanceClient {
+    /// `BINANCE_API_URL` overrides the endpoint, e.g. for binance.us
+    pub fn new() -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("❌ Failed to build HTTP client");
+        Self {
+            http,
+            base_url: env::var("BINANCE_API_URL").unwrap_or_else(|_| BASE_URL.to_string()),
+            last_request: None,
+        }
+    }
+
+    async fn pace(&mut self) {
+        if let Some(last) = self.last_request {
+            let elapsed = last.elapsed();
+            if elapsed < MIN_REQUEST_INTERVAL {
+                sleep(MIN_REQUEST_INTERVAL - elapsed).await;
+            }
+        }
+        self.last_request = Some(Instant::now());
+    }
+
+    /// GET `path` with `query`, paced, waiting out 429s as `Retry-After` asks; `what` names
+    /// the request in errors
+    async fn get(&mut self, path: &str, query: &[(&str, &str)], what: &str) -> Result<reqwest::Response, String> {
+        let mut retries = 0;
+        let resp = loop {
+            self.pace().await;
+            let resp = self
+                .http
+                .get(format!("{}{path}", self.base_url))
+                .query(query)
+                .send()
+                .await
+                .map_err(|e| format!("{what} request failed: {e}"))?;
+
+            // 429 asks us to back off; 418 means the IP is already banned for ignoring it
+            if resp.status().as_u16() == 429 && retries < MAX_RATE_LIMIT_RETRIES {
+                let wait = resp
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(60);
+                warn!("⏳ Binance rate limit hit, waiting {wait}s");
+                sleep(Duration::from_secs(wait)).await;
+                retries += 1;
+                continue;
+            }
+            break resp;
+        };
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("{what} request returned {status}: {body}"));
+        }
+        Ok(resp)
+    }
+
+    /// Up to [`MAX_KLINES`] candles opening in `[from, to)`; `pair` is Binance's (`BTCUSDT`),
+    /// `interval` too (`1m`, `1h`…). Waits out 429s as `Retry-After` asks.
+    pub async fn klines(
+        &mut self,
+        pair: &str,
+        interval: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<Candle>, String> {
+        let query = [
+            ("symbol", pair),
+            ("interval", interval),
+            ("startTime", &from.timestamp_millis().to_string()),
+            ("endTime", &(to.timestamp_millis() - 1).to_string()),
+            ("limit", &MAX_KLINES.to_string()),
+        ];
+        let resp = self.get("/api/v3/klines", &query, &format!("kline for {pair}")).await?;
+
+        // Each kline is [open_time, "open", "high", "low", "close", "volume", close_time, ...]
+        let rows: Vec<Vec<Value>> = resp
+            .json()
+            .await
+            .map_err(|e| format!("invalid kline response for {pair}: {e}"))?;
+        let num = |v: Option<&Value>| v?.as_str()?.parse::<f64>().ok();
+        Ok(rows
+            .iter()
+            .filter_map(|k| {
+                Some(Candle {
+                    time: DateTime::from_timestamp_millis(k.first()?.as_i64()?)?.naive_utc(),
+                    open: num(k.get(1))?,
+                    high: num(k.get(2))?,
+                    low: num(k.get(3))?,
+                    close: num(k.get(4))?,
+                    volume: num(k.get(5))?,
+                })
+            })
+            .collect())
+    }
+
+    /// Exchange info of `pairs`: trading rules, assets and filters, one object per pair
+    pub async fn exchange_info(&mut self, pairs: &[&str]) -> Result<Vec<Value>, String> {
+        let symbols = serde_json::to_string(pairs).unwrap_or_default();
+        let resp = self.get("/api/v3/exchangeInfo", &[("symbols", &symbols)], "exchange info").await?;
+        let mut info: Value = resp.json().await.map_err(|e| format!("invalid exchange info: {e}"))?;
+        match info.get_mut("symbols").map(Value::take) {
+            Some(Value::Array(symbols)) => Ok(symbols),
+            _ => Err("exchange info has no symbols".to_string()),
+        }
+    }
+}
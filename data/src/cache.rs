@@ -0,0 +1,79 @@
+use std::{env, future::Future};
+
+use redis::AsyncCommands;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::warn;
+
+use crate::{keys::{CACHE_PREFIX, GENERATION_PREFIX}, redis_conn::RedisConn};
+
+
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 30;
+
+/// Read-through cache for Postgres queries over a symbol's history. Entries are never
+/// deleted; writers bump the symbol's generation instead and old entries expire.
+#[derive(Debug, Clone, Copy)]
+pub struct QueryCache {
+    pub ttl_secs: u64,
+}
+
+impl QueryCache {
+    /// `QUERY_CACHE_TTL_SECS` (default 30); `None` when 0
+    pub fn from_env() -> Option<Self> {
+        let ttl_secs = env::var("QUERY_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_TTL_SECS);
+        (ttl_secs > 0).then_some(Self { ttl_secs })
+    }
+
+    /// The cached result of `kind` for `symbol` and `params`, or `compute`'s, which is
+    /// stored. Redis failures only cost the cache, never the query.
+    pub async fn get_or_compute<T, F, Fut>(
+        &self,
+        redis: &mut RedisConn,
+        kind: &str,
+        symbol: &str,
+        params: &str,
+        compute: F,
+    ) -> Result<T, String>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, String>>,
+    {
+        let key = match redis.get::<_, Option<u64>>(format!("{GENERATION_PREFIX}{symbol}")).await {
+            Ok(generation) => Some(format!("{CACHE_PREFIX}{kind}:{symbol}:{}:{params}", generation.unwrap_or(0))),
+            Err(e) => {
+                warn!("⚠️ Query cache unavailable: {e}");
+                None
+            }
+        };
+        if let Some(key) = &key
+            && let Ok(Some(json)) = redis.get::<_, Option<String>>(key).await
+            && let Ok(hit) = serde_json::from_str(&json)
+        {
+            return Ok(hit);
+        }
+
+        let value = compute().await?;
+        if let Some(key) = key
+            && let Ok(json) = serde_json::to_string(&value)
+            && let Err(e) = redis.set_ex::<_, _, ()>(key, json, self.ttl_secs).await
+        {
+            warn!("⚠️ Query cache write failed: {e}");
+        }
+        Ok(value)
+    }
+}
+
+/// Orphan every cached result for `symbols` after new rows were written for them
+pub async fn invalidate(redis: &mut RedisConn, symbols: &[&str]) -> redis::RedisResult<()> {
+    if symbols.is_empty() {
+        return Ok(());
+    }
+    let mut pipe = redis::pipe();
+    for s in symbols {
+        pipe.incr(format!("{GENERATION_PREFIX}{s}"), 1).ignore();
+    }
+    pipe.query_async(redis).await
+}
@@ -0,0 +1,261 @@
+use std::{
+    env,
+    future::Future,
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+    time::{sleep, Sleep},
+};
+use tokio_postgres::{
+    config::Host,
+    tls::{MakeTlsConnect, TlsConnect},
+    Client as PgClient, Config as PgConfig,
+};
+use tracing::{debug, warn};
+
+const DEFAULT_MAX_DELAY_MS: u64 = 500;
+
+/// Fault probabilities, each 0–1, from `CHAOS_*`; all zero (the default) injects nothing
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChaosConfig {
+    /// `CHAOS_REDIS_DELAY`: a Redis command or pipeline waits before it is sent
+    pub redis_delay: f64,
+    /// `CHAOS_REDIS_FAIL`: it fails as a dropped connection would
+    pub redis_fail: f64,
+    /// `CHAOS_PG_DELAY`: a write to the Postgres socket waits
+    pub pg_delay: f64,
+    /// `CHAOS_PG_FAIL`: it fails, which closes the connection
+    pub pg_fail: f64,
+    /// `CHAOS_WS_DROP`: an exchange WebSocket frame is discarded unread
+    pub ws_drop: f64,
+    /// `CHAOS_TRADE_PANIC`: processing an ingested trade panics
+    pub trade_panic: f64,
+    /// `CHAOS_MAX_DELAY_MS`: delays are uniform up to this (default 500)
+    pub max_delay: Duration,
+}
+
+impl ChaosConfig {
+    pub fn from_env() -> Self {
+        let prob = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .map(|p| p.clamp(0.0, 1.0))
+                .unwrap_or(0.0)
+        };
+        let max_delay_ms = env::var("CHAOS_MAX_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_DELAY_MS);
+        Self {
+            redis_delay: prob("CHAOS_REDIS_DELAY"),
+            redis_fail: prob("CHAOS_REDIS_FAIL"),
+            pg_delay: prob("CHAOS_PG_DELAY"),
+            pg_fail: prob("CHAOS_PG_FAIL"),
+            ws_drop: prob("CHAOS_WS_DROP"),
+            trade_panic: prob("CHAOS_TRADE_PANIC"),
+            max_delay: Duration::from_millis(max_delay_ms),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.redis_delay + self.redis_fail + self.pg_delay + self.pg_fail + self.ws_drop + self.trade_panic > 0.0
+    }
+}
+
+/// What to do to one operation
+enum Fault {
+    None,
+    Delay(Duration),
+    Fail,
+}
+
+struct Chaos {
+    config: ChaosConfig,
+    rng: AtomicU64,
+}
+
+impl Chaos {
+    /// xorshift; `CHAOS_SEED` makes a run repeatable
+    fn random(&self) -> f64 {
+        let mut x = self.rng.load(Ordering::Relaxed);
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng.store(x, Ordering::Relaxed);
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn roll(&self, delay: f64, fail: f64) -> Fault {
+        if fail > 0.0 && self.random() < fail {
+            Fault::Fail
+        } else if delay > 0.0 && self.random() < delay {
+            Fault::Delay(self.config.max_delay.mul_f64(self.random()))
+        } else {
+            Fault::None
+        }
+    }
+}
+
+fn chaos() -> Option<&'static Chaos> {
+    static CHAOS: OnceLock<Option<Chaos>> = OnceLock::new();
+    CHAOS
+        .get_or_init(|| {
+            let config = ChaosConfig::from_env();
+            if !config.is_enabled() {
+                return None;
+            }
+            warn!("🐒 Chaos mode on: {config:?}");
+            let seed = env::var("CHAOS_SEED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(|| crate::metrics::now_ms() as u64);
+            Some(Chaos {
+                config,
+                // xorshift never leaves zero
+                rng: AtomicU64::new(seed.max(1)),
+            })
+        })
+        .as_ref()
+}
+
+/// Before each Redis command or pipeline: maybe wait, maybe fail with an I/O error so the
+/// connection's reconnect-and-retry path runs
+pub async fn redis() -> redis::RedisResult<()> {
+    let Some(chaos) = chaos() else { return Ok(()) };
+    match chaos.roll(chaos.config.redis_delay, chaos.config.redis_fail) {
+        Fault::None => Ok(()),
+        Fault::Delay(d) => {
+            debug!("🐒 Delaying a Redis command by {d:?}");
+            sleep(d).await;
+            Ok(())
+        }
+        Fault::Fail => {
+            debug!("🐒 Failing a Redis command");
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "chaos: injected Redis failure").into())
+        }
+    }
+}
+
+/// Whether to discard an incoming exchange frame
+pub fn drop_frame() -> bool {
+    let Some(chaos) = chaos() else { return false };
+    let drop = chaos.config.ws_drop > 0.0 && chaos.random() < chaos.config.ws_drop;
+    if drop {
+        debug!("🐒 Dropping a WebSocket frame");
+    }
+    drop
+}
+
+/// Maybe panic while processing a trade, to exercise the ingester's panic isolation
+pub fn trade_panic(symbol: &str) {
+    let Some(chaos) = chaos() else { return };
+    if chaos.config.trade_panic > 0.0 && chaos.random() < chaos.config.trade_panic {
+        panic!("chaos: injected panic processing a {symbol} trade");
+    }
+}
+
+/// A socket whose writes may be delayed or fail
+pub struct ChaosStream<S> {
+    inner: S,
+    delay: Option<Pin<Box<Sleep>>>,
+    /// The pending write already had its fault rolled
+    rolled: bool,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for ChaosStream<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for ChaosStream<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        if !self.rolled
+            && let Some(chaos) = chaos()
+        {
+            self.rolled = true;
+            match chaos.roll(chaos.config.pg_delay, chaos.config.pg_fail) {
+                Fault::None => {}
+                Fault::Delay(d) => {
+                    debug!("🐒 Delaying a Postgres write by {d:?}");
+                    self.delay = Some(Box::pin(sleep(d)));
+                }
+                Fault::Fail => {
+                    debug!("🐒 Failing a Postgres write");
+                    self.rolled = false;
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::ConnectionReset,
+                        "chaos: injected Postgres failure",
+                    )));
+                }
+            }
+        }
+        if let Some(delay) = self.delay.as_mut() {
+            ready!(delay.as_mut().poll(cx));
+            self.delay = None;
+        }
+        let written = ready!(Pin::new(&mut self.inner).poll_write(cx, buf));
+        self.rolled = false;
+        Poll::Ready(written)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Connect over a [`ChaosStream`] when Postgres faults are configured. `None` leaves the
+/// normal connect to the caller: chaos off, a Unix-socket host, or a TCP connect that
+/// failed (the caller's attempt reports it properly).
+pub async fn connect_pg<T>(cfg: &PgConfig, mut tls: T) -> Option<Result<PgClient, tokio_postgres::Error>>
+where
+    T: MakeTlsConnect<ChaosStream<TcpStream>>,
+    <T as MakeTlsConnect<ChaosStream<TcpStream>>>::Stream: Send + 'static,
+    <T as MakeTlsConnect<ChaosStream<TcpStream>>>::TlsConnect: Send,
+    <<T as MakeTlsConnect<ChaosStream<TcpStream>>>::TlsConnect as TlsConnect<ChaosStream<TcpStream>>>::Future: Send,
+{
+    let chaos = chaos()?;
+    if chaos.config.pg_delay + chaos.config.pg_fail == 0.0 {
+        return None;
+    }
+    let Some(Host::Tcp(host)) = cfg.get_hosts().first() else { return None };
+    let port = cfg.get_ports().first().copied().unwrap_or(5432);
+    let socket = TcpStream::connect((host.as_str(), port)).await.ok()?;
+    let _ = socket.set_nodelay(true);
+    let stream = ChaosStream {
+        inner: socket,
+        delay: None,
+        rolled: false,
+    };
+    let tls = match MakeTlsConnect::<ChaosStream<TcpStream>>::make_tls_connect(&mut tls, host) {
+        Ok(tls) => tls,
+        Err(e) => {
+            let e: Box<dyn std::error::Error + Send + Sync> = e.into();
+            warn!("⚠️ Chaos: TLS setup for {host} failed ({e}); connecting normally");
+            return None;
+        }
+    };
+    Some(cfg.connect_raw(stream, tls).await.map(|(client, connection)| {
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::error!("❌ Postgres connection error: {e}");
+            }
+        });
+        client
+    }))
+}
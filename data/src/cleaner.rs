@@ -1,67 +1,139 @@
-use std::{env, time::Duration};
+use std::time::Duration;
 use tokio::time::sleep;
 
-// Postgres + native-tls
-use tokio_postgres::{config::SslMode, Client as PgClient, Config};
-use postgres_native_tls::MakeTlsConnector;
-use native_tls::TlsConnector;
+use tokio_postgres::Client as PgClient;
 
-/// Build native-tls (OpenSSL) Postgres connector
-fn build_pg_tls() -> MakeTlsConnector {
-    let connector = TlsConnector::builder()
-        .build()
-        .expect("Failed to create native-tls connector");
-    MakeTlsConnector::new(connector)
+use thiserror::Error;
+
+use tracing::{error, info, warn};
+use crate::{
+    alerts::Alert,
+    config::Config,
+    error::pg_transient,
+    history,
+    metrics::now_ms,
+    notify::Notifier,
+    tls::{TlsError, TlsSettings},
+};
+
+const CONNECT_ATTEMPTS: u32 = 5;
+
+#[derive(Debug, Error)]
+pub enum CleanError {
+    #[error("TLS: {0}")]
+    Tls(#[from] TlsError),
+    #[error("could not connect to Postgres after {attempts} attempt(s): {source}")]
+    Connect { attempts: u32, source: tokio_postgres::Error },
+    #[error("{step} of stock_price_history failed: {source}")]
+    Step { step: &'static str, source: tokio_postgres::Error },
 }
 
-/// Create Postgres config with TLS requirement
-fn pg_config_tls(url: &str) -> Config {
-    use std::str::FromStr;
-    let mut cfg = Config::from_str(url).expect("Invalid DATABASE_URL");
-    cfg.ssl_mode(SslMode::Require);
-    cfg
+impl CleanError {
+    /// Whether the next maintenance window may succeed without intervention
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Connect { source, .. } | Self::Step { source, .. } => pg_transient(source),
+            Self::Tls(_) => false,
+        }
+    }
 }
 
-/// Attempt to connect to Postgres with retries
-async fn connect_pg(cfg: &Config, tls: MakeTlsConnector) -> PgClient {
-    for attempt in 1..=5 {
+/// Connect to Postgres under the TLS settings, retrying failures that may recover
+async fn connect_pg(url: &str, settings: &TlsSettings) -> Result<PgClient, CleanError> {
+    let (cfg, _) = settings.postgres_config(url)?;
+    let tls = settings.postgres_connector()?;
+    let mut attempt = 1;
+    loop {
         match cfg.connect(tls.clone()).await {
             Ok((client, conn)) => {
                 tokio::spawn(async move {
                     if let Err(e) = conn.await {
-                        eprintln!("❌ Postgres connection error: {e}");
+                        error!("❌ Postgres connection error: {e}");
                     }
                 });
-                return client;
+                return Ok(client);
             }
-            Err(e) => {
-                eprintln!("⚠️ Postgres connect failed (attempt {attempt}): {e}");
+            Err(e) if pg_transient(&e) && attempt < CONNECT_ATTEMPTS => {
+                warn!("⚠️ Postgres connect failed (attempt {attempt}): {e}");
                 sleep(Duration::from_secs(2)).await;
+                attempt += 1;
             }
+            Err(source) => return Err(CleanError::Connect { attempts: attempt, source }),
         }
     }
-    panic!("❌ Could not connect to Postgres after 5 attempts");
 }
 
-pub async fn run() {
-    println!("🧼 Cleaner starting…");
-    dotenv::dotenv().ok();
+/// Empty `stock_price_history` and recreate the readers' view in one transaction: readers
+/// wait on the lock and see the full table until the commit and the empty one after, never
+/// a truncate in progress. Truncating in place keeps the table's foreign keys, grants and
+/// owner, which a copy would lose; ids start over.
+async fn truncate_under_view(pg: &mut PgClient) -> Result<(), tokio_postgres::Error> {
+    let tx = pg.transaction().await?;
+    tx.batch_execute(&format!("TRUNCATE TABLE stock_price_history RESTART IDENTITY; {}", history::view_sql()))
+        .await?;
+    tx.commit().await
+}
+
+/// Raise a failed maintenance step in chat, when a notifier is configured
+fn report(notifier: Option<&Notifier>, e: &CleanError) {
+    if let Some(n) = notifier {
+        n.notify(Alert {
+            kind: "cleaner".to_string(),
+            message: e.to_string(),
+            ts: now_ms(),
+            details: serde_json::json!({ "transient": e.is_transient() }),
+        });
+    }
+}
+
+/// Empty `stock_price_history`, or drop rows older than `retention.history_days`, then VACUUM.
+/// Emptying truncates under the readers' view in one transaction; a DELETE needs none, as
+/// readers keep their snapshot. Both steps are attempted; the first failure is returned.
+pub async fn run(config: &Config, notifier: Option<Notifier>) -> Result<(), CleanError> {
+    info!("🧼 Cleaner starting…");
 
-    let pg_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
-    let tls = build_pg_tls();
-    let cfg = pg_config_tls(&pg_url);
-    let pg = connect_pg(&cfg, tls).await;
+    let mut pg = match connect_pg(&config.database_url(), &config.tls.postgres).await {
+        Ok(pg) => pg,
+        Err(e) => {
+            error!("❌ {e}");
+            report(notifier.as_ref(), &e);
+            return Err(e);
+        }
+    };
+    let mut first_error = None;
 
     // --------------------------------- Maintenance -------------------------
-    match pg.execute("TRUNCATE TABLE stock_price_history RESTART IDENTITY", &[]).await {
-        Ok(_) => println!("✅ TRUNCATE succeeded"),
-        Err(e) => eprintln!("❌ TRUNCATE failed: {e}"),
+    let days = config.retention.history_days;
+    let (step, result) = if days == 0 {
+        ("TRUNCATE", truncate_under_view(&mut pg).await.map(|()| 0))
+    } else {
+        let sql = format!(
+            "DELETE FROM stock_price_history WHERE trade_time_stamp < NOW() AT TIME ZONE 'UTC' - INTERVAL '{days} days'"
+        );
+        ("DELETE", pg.execute(&sql, &[]).await)
+    };
+    match result {
+        Ok(n) if days > 0 => info!("✅ DELETE removed {n} rows older than {days} days"),
+        Ok(_) => info!("✅ TRUNCATE succeeded"),
+        Err(source) => first_error = Some(CleanError::Step { step, source }),
+    }
+    if let Some(e) = &first_error {
+        error!("❌ {e}");
+        report(notifier.as_ref(), e);
     }
 
-    match pg.execute("VACUUM stock_price_history", &[]).await {
-        Ok(_) => println!("✅ VACUUM succeeded"),
-        Err(e) => eprintln!("❌ VACUUM failed: {e}"),
+    if config.retention.vacuum {
+        match pg.execute("VACUUM stock_price_history", &[]).await {
+            Ok(_) => info!("✅ VACUUM succeeded"),
+            Err(source) => {
+                let e = CleanError::Step { step: "VACUUM", source };
+                error!("❌ {e}");
+                report(notifier.as_ref(), &e);
+                first_error.get_or_insert(e);
+            }
+        }
     }
 
-    println!("✨ Cleaner finished");
+    info!("✨ Cleaner finished");
+    first_error.map_or(Ok(()), Err)
 }
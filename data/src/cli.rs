@@ -0,0 +1,130 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::Args;
+use tracing::info;
+
+use crate::{
+    bars::Timeframe,
+    config::{Config, Need},
+    logging::{self, LogGuard, LogLevel},
+};
+
+/// Flags every binary takes: where the config comes from and how to log
+#[derive(Debug, Clone, Args)]
+pub struct ConfigArgs {
+    /// TOML or YAML config file (default: config.toml / config.yaml if present)
+    #[arg(short, long, env = "CONFIG_FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    #[arg(long, value_enum, ignore_case = true, env = "LOG_LEVEL", default_value_t = LogLevel::Info, global = true)]
+    pub log_level: LogLevel,
+
+    /// Log as JSON lines (overrides `logging.json` / LOG_JSON)
+    #[arg(long, global = true)]
+    pub log_json: bool,
+
+    /// Also log to rotating files in this directory (overrides `logging.dir` / LOG_DIR)
+    #[arg(long, global = true)]
+    pub log_dir: Option<PathBuf>,
+}
+
+impl ConfigArgs {
+    /// Load the config with these flags over file and environment
+    pub async fn load_config(&self, needs: &[Need]) -> Result<Config, String> {
+        self.load_config_with(needs, |_| {}).await
+    }
+
+    /// `load_config` with the caller's own flags applied as well
+    pub async fn load_config_with(&self, needs: &[Need], overrides: impl FnOnce(&mut Config)) -> Result<Config, String> {
+        Config::load_with(self.config.as_deref(), needs, |config| {
+            if self.log_json {
+                config.logging.json = true;
+            }
+            if let Some(dir) = &self.log_dir {
+                config.logging.dir = Some(dir.clone());
+            }
+            overrides(config);
+        })
+        .await
+    }
+
+    /// Start logging at `--log-level` with the config's `[logging]` settings, then report
+    /// where the config came from
+    pub fn init_logging(&self, service: &str, config: &Config) -> Result<LogGuard, String> {
+        let guard = logging::init(service, self.log_level, &config.logging)?;
+        if let Some(path) = &config.source {
+            info!("⚙️ Loaded config from {}", path.display());
+        }
+        if let Some(store) = config.secrets_store() {
+            info!("🔑 {} from the {:?} secrets backend", store.keys().join(", "), store.backend());
+        }
+        Ok(guard)
+    }
+
+    /// `load_config` then `init_logging`, for binaries with nothing to apply in between
+    pub async fn start(&self, service: &str, needs: &[Need]) -> Result<(Config, LogGuard), String> {
+        let config = self.load_config(needs).await?;
+        let guard = self.init_logging(service, &config)?;
+        Ok((config, guard))
+    }
+}
+
+/// Flags shared by the long-running service binaries; flatten into each binary's parser
+#[derive(Debug, Clone, Args)]
+pub struct ServiceArgs {
+    #[command(flatten)]
+    pub common: ConfigArgs,
+
+    /// Comma-separated EXCHANGE:PAIR list replacing the configured symbols
+    #[arg(long, value_delimiter = ',')]
+    pub symbols: Option<Vec<String>>,
+
+    /// Report what would happen without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Do one unit of work and exit instead of running forever
+    #[arg(long)]
+    pub once: bool,
+}
+
+impl ServiceArgs {
+    /// Load the config with these flags over file and environment
+    pub async fn load_config(&self, needs: &[Need]) -> Result<Config, String> {
+        self.common
+            .load_config_with(needs, |config| {
+                if let Some(symbols) = &self.symbols {
+                    config.symbols = symbols.iter().map(|s| s.trim().to_string()).collect();
+                }
+            })
+            .await
+    }
+
+    pub fn init_logging(&self, service: &str, config: &Config) -> Result<LogGuard, String> {
+        self.common.init_logging(service, config)
+    }
+}
+
+/// `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS`, UTC; a clap value parser
+pub fn parse_time(s: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|d| d.and_time(Default::default())))
+        .map_err(|_| format!("invalid time '{s}' (YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS)"))
+}
+
+/// "30s", "1m", "1h"…; a clap value parser
+pub fn parse_timeframe(s: &str) -> Result<Timeframe, String> {
+    Timeframe::parse(s).ok_or_else(|| format!("invalid timeframe '{s}' (e.g. 30s, 1m, 15m, 1h, 1d)"))
+}
+
+/// `main`'s exit status: failures print to stderr, logging may not be up yet
+pub fn exit(result: Result<(), String>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❌ {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
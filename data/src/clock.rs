@@ -0,0 +1,84 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+
+/// Where time-based logic reads the time: [`SystemClock`] in the services, a [`ManualClock`]
+/// to step bar boundaries, maintenance windows and staleness checks through chosen times
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Wall-clock time
+    fn now(&self) -> DateTime<Utc>;
+    /// Monotonic time, for measuring intervals
+    fn instant(&self) -> Instant;
+
+    /// Wall-clock ms since epoch
+    fn now_ms(&self) -> i64 {
+        self.now().timestamp_millis()
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// A clock that only moves when told to
+#[derive(Debug)]
+pub struct ManualClock {
+    base: Instant,
+    state: Mutex<(DateTime<Utc>, Duration)>,
+}
+
+impl ManualClock {
+    pub fn new(start: DateTime<Utc>) -> Arc<Self> {
+        Arc::new(Self {
+            base: Instant::now(),
+            state: Mutex::new((start, Duration::ZERO)),
+        })
+    }
+
+    /// Move both clocks forward by `by`
+    pub fn advance(&self, by: Duration) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.0 += chrono::Duration::from_std(by).unwrap_or(chrono::Duration::MAX);
+        state.1 += by;
+    }
+
+    /// Jump the wall clock to `to`; the monotonic clock follows forward jumps and ignores
+    /// backward ones, as a real one would
+    pub fn set(&self, to: DateTime<Utc>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Ok(forward) = (to - state.0).to_std() {
+            state.1 += forward;
+        }
+        state.0 = to;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).0
+    }
+
+    fn instant(&self) -> Instant {
+        self.base + self.state.lock().unwrap_or_else(|e| e.into_inner()).1
+    }
+}
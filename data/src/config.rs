@@ -0,0 +1,979 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use chrono::NaiveTime;
+use serde::Deserialize;
+use tokio_postgres::IsolationLevel;
+
+use crate::{
+    bars::{BarRules, Timeframe},
+    keys,
+    logging::LogSettings,
+    notify::{NotifyConfig, Target, DEFAULT_BATCH_SECS, DEFAULT_MAX_PER_MIN},
+    redis_conn::{self, RedisClient},
+    secrets::{Secrets, SecretsSettings},
+    source,
+    symbols,
+    tls::TlsConfig,
+};
+
+/// Looked for in the working directory when `CONFIG_FILE` is unset
+const DEFAULT_FILES: [&str; 3] = ["config.toml", "config.yaml", "config.yml"];
+const DEFAULT_FINNHUB_WS_URL: &str = "wss://ws.finnhub.io";
+const DEFAULT_OKX_WS_URL: &str = "wss://ws.okx.com:8443/ws/v5/public";
+const DEFAULT_BYBIT_WS_URL: &str = "wss://stream.bybit.com/v5/public/linear";
+
+/// What a binary cannot start without
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Need {
+    Redis,
+    Postgres,
+    /// A Finnhub API key, when Finnhub is the exchange source
+    Finnhub,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FinnhubConfig {
+    /// `FINNHUB_API_KEY`
+    pub api_key: Option<String>,
+    /// `FINNHUB_WS_URL`
+    pub ws_url: String,
+}
+
+impl Default for FinnhubConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            ws_url: DEFAULT_FINNHUB_WS_URL.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OkxConfig {
+    /// `OKX_WS_URL`
+    pub ws_url: String,
+}
+
+impl Default for OkxConfig {
+    fn default() -> Self {
+        Self {
+            ws_url: DEFAULT_OKX_WS_URL.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BybitConfig {
+    /// `BYBIT_WS_URL`
+    pub ws_url: String,
+}
+
+impl Default for BybitConfig {
+    fn default() -> Self {
+        Self {
+            ws_url: DEFAULT_BYBIT_WS_URL.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Exchanges {
+    /// Where the ingester reads trades: one of `source::SOURCES` (`EXCHANGE_SOURCE`)
+    pub source: String,
+    pub finnhub: FinnhubConfig,
+    pub okx: OkxConfig,
+    pub bybit: BybitConfig,
+}
+
+impl Default for Exchanges {
+    fn default() -> Self {
+        Self {
+            source: "finnhub".to_string(),
+            finnhub: FinnhubConfig::default(),
+            okx: OkxConfig::default(),
+            bybit: BybitConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Intervals {
+    /// Redis → Postgres snapshot period (`FETCH_INTERVAL_SECS`)
+    pub fetch_secs: u64,
+    /// First WebSocket reconnect delay, doubled up to a minute (`RECONNECT_DELAY_SECS`)
+    pub reconnect_secs: u64,
+    /// Trigger scheduling tick (`TRIGGER_TICK_SECS`)
+    pub trigger_tick_secs: u64,
+    /// How often a connected ingester re-reads the tracked symbols (`SYMBOLS_REFRESH_SECS`)
+    pub symbols_refresh_secs: u64,
+}
+
+impl Default for Intervals {
+    fn default() -> Self {
+        Self {
+            fetch_secs: 10,
+            reconnect_secs: 3,
+            trigger_tick_secs: 2,
+            symbols_refresh_secs: 10,
+        }
+    }
+}
+
+/// How the ingester builds bars; timeframes default to `BAR_TIMEFRAMES`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BarSettings {
+    /// Drop a trade this many percent away from the last price as an outlier (`BAR_MAX_JUMP_PCT`); 0 keeps all
+    pub max_jump_pct: f64,
+    /// Bars closing with fewer trades are not published (`BAR_MIN_TICKS`)
+    pub min_ticks: u32,
+    /// Overrides by symbol, e.g. wider bars and stricter filters for a thin altcoin
+    pub symbols: BTreeMap<String, SymbolBars>,
+}
+
+impl Default for BarSettings {
+    fn default() -> Self {
+        Self {
+            max_jump_pct: 0.0,
+            min_ticks: 1,
+            symbols: BTreeMap::new(),
+        }
+    }
+}
+
+/// One symbol's bar overrides; what is unset comes from [`BarSettings`]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SymbolBars {
+    /// e.g. `["15s", "1m"]`
+    pub timeframes: Option<Vec<String>>,
+    pub max_jump_pct: Option<f64>,
+    pub min_ticks: Option<u32>,
+}
+
+impl BarSettings {
+    /// Default rules over `timeframes`, and every symbol's rules with its overrides applied.
+    /// Invalid timeframes are left out; validation has reported them.
+    pub fn rules(&self, timeframes: Vec<Timeframe>) -> (BarRules, HashMap<String, BarRules>) {
+        let jump = |pct: f64| (pct > 0.0).then_some(pct / 100.0);
+        let defaults = BarRules {
+            timeframes,
+            max_jump: jump(self.max_jump_pct),
+            min_ticks: self.min_ticks,
+        };
+        let symbols = self
+            .symbols
+            .iter()
+            .map(|(symbol, o)| {
+                let timeframes = match &o.timeframes {
+                    Some(tfs) => {
+                        let mut tfs: Vec<Timeframe> = tfs.iter().filter_map(|tf| Timeframe::parse(tf)).collect();
+                        tfs.sort_by_key(|tf| tf.secs);
+                        tfs.dedup();
+                        tfs
+                    }
+                    None => defaults.timeframes.clone(),
+                };
+                let rules = BarRules {
+                    timeframes,
+                    max_jump: o.max_jump_pct.map_or(defaults.max_jump, jump),
+                    min_ticks: o.min_ticks.unwrap_or(defaults.min_ticks),
+                };
+                (symbol.clone(), rules)
+            })
+            .collect();
+        (defaults, symbols)
+    }
+}
+
+impl Intervals {
+    pub fn fetch(&self) -> Duration {
+        Duration::from_secs(self.fetch_secs)
+    }
+
+    pub fn reconnect(&self) -> Duration {
+        Duration::from_secs(self.reconnect_secs)
+    }
+
+    pub fn trigger_tick(&self) -> Duration {
+        Duration::from_secs(self.trigger_tick_secs)
+    }
+
+    pub fn symbols_refresh(&self) -> Duration {
+        Duration::from_secs(self.symbols_refresh_secs)
+    }
+}
+
+/// What the ingester does with a trade whose timestamp cannot be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TsPolicy {
+    /// Drop it
+    Skip,
+    /// Keep it at the local receive time
+    Clamp,
+}
+
+impl FromStr for TsPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "skip" => Ok(Self::Skip),
+            "clamp" => Ok(Self::Clamp),
+            other => Err(format!("unknown timestamp policy '{other}' (skip or clamp)")),
+        }
+    }
+}
+
+/// How the ingester treats the trades it receives
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ingest {
+    /// Trades further than this from the local clock are untrusted (`TRADE_TS_MAX_SKEW_SECS`)
+    pub ts_max_skew_secs: u64,
+    /// What happens to them after they are dead-lettered (`TRADE_TS_POLICY`)
+    pub ts_policy: TsPolicy,
+    /// Untrusted trades kept on the dead-letter list, newest first (`DEAD_LETTER_LEN`)
+    pub dead_letter_len: u32,
+    /// A symbol's OHLCV is dropped after this long without trades; 0 keeps it until the
+    /// symbol is unsubscribed (`OHLCV_IDLE_HOURS`)
+    pub ohlcv_idle_hours: u64,
+}
+
+impl Default for Ingest {
+    fn default() -> Self {
+        Self {
+            ts_max_skew_secs: 300,
+            ts_policy: TsPolicy::Skip,
+            dead_letter_len: 1000,
+            ohlcv_idle_hours: 6,
+        }
+    }
+}
+
+impl Ingest {
+    pub fn ts_max_skew(&self) -> Duration {
+        Duration::from_secs(self.ts_max_skew_secs)
+    }
+
+    pub fn ohlcv_idle(&self) -> Option<Duration> {
+        (self.ohlcv_idle_hours > 0).then(|| Duration::from_secs(self.ohlcv_idle_hours * 3600))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Retention {
+    /// Days of `stock_price_history` the daily clean keeps; 0 empties it (`HISTORY_RETENTION_DAYS`)
+    pub history_days: u32,
+    /// VACUUM after cleaning (`HISTORY_VACUUM`)
+    pub vacuum: bool,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            history_days: 0,
+            vacuum: true,
+        }
+    }
+}
+
+/// Postgres isolation levels a write transaction can run at
+pub const ISOLATION_LEVELS: [&str; 3] = ["read_committed", "repeatable_read", "serializable"];
+
+/// How the fetcher's inserts run, so several writers can share a database
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Writes {
+    /// Isolation of each insert transaction, one of [`ISOLATION_LEVELS`] (`PG_ISOLATION`)
+    pub isolation: String,
+    /// Reruns of a transaction that hit a serialization failure or deadlock (`PG_WRITE_RETRIES`)
+    pub retries: u32,
+    /// First wait before a rerun, doubled each time (`PG_RETRY_BACKOFF_MS`)
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for Writes {
+    fn default() -> Self {
+        Self {
+            isolation: "read_committed".to_string(),
+            retries: 3,
+            retry_backoff_ms: 50,
+        }
+    }
+}
+
+impl Writes {
+    pub fn isolation_level(&self) -> Option<IsolationLevel> {
+        match self.isolation.as_str() {
+            "read_committed" => Some(IsolationLevel::ReadCommitted),
+            "repeatable_read" => Some(IsolationLevel::RepeatableRead),
+            "serializable" => Some(IsolationLevel::Serializable),
+            _ => None,
+        }
+    }
+
+    pub fn retry_backoff(&self) -> Duration {
+        Duration::from_millis(self.retry_backoff_ms)
+    }
+}
+
+/// Most decimal places a `Decimal` holds
+pub const MAX_SCALE: u32 = 28;
+
+/// How prices and volumes are written to Redis and Postgres
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Precision {
+    /// Exact decimals instead of f64: Redis strings hold the exchange's digits, volumes are
+    /// summed exactly and history columns become NUMERIC. Needs the `decimal` feature
+    /// (`PRICE_DECIMAL`)
+    pub decimal: bool,
+    /// Decimal places prices are rounded to; as the exchange sent them when unset (`PRICE_SCALE`)
+    pub price_scale: Option<u32>,
+    /// Decimal places volumes are rounded to; as summed when unset (`VOLUME_SCALE`)
+    pub volume_scale: Option<u32>,
+}
+
+/// How several fetchers split the tracked symbols between them
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sharding {
+    /// Fixed number of fetchers; 1 writes every symbol (`FETCHER_SHARDS`)
+    pub shards: u32,
+    /// This fetcher's shard, `0..shards` (`FETCHER_SHARD_INDEX`)
+    pub index: u32,
+    /// Fetchers find each other in Redis instead, so any number can come and go;
+    /// `shards` and `index` are then ignored (`FETCHER_SHARD_COORDINATED`)
+    pub coordinated: bool,
+    /// A coordinated fetcher that has not refreshed for this long is presumed gone
+    /// (`FETCHER_MEMBER_TTL_SECS`)
+    pub member_ttl_secs: u64,
+}
+
+impl Default for Sharding {
+    fn default() -> Self {
+        Self {
+            shards: 1,
+            index: 0,
+            coordinated: false,
+            member_ttl_secs: 30,
+        }
+    }
+}
+
+/// Daily maintenance window, UTC
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Schedules {
+    /// `MAINT_START`, "HH:MM"
+    #[serde(deserialize_with = "time_of_day")]
+    pub maintenance_start: NaiveTime,
+    /// `MAINT_END`
+    #[serde(deserialize_with = "time_of_day")]
+    pub maintenance_end: NaiveTime,
+    /// Gap repair after the window (`BACKFILL_TIME`); late enough for the window's last 1m candle to close
+    #[serde(deserialize_with = "time_of_day")]
+    pub backfill_at: NaiveTime,
+    /// Maintenance jobs running at once (`MAINT_PARALLELISM`)
+    pub parallelism: usize,
+    /// How often to scan history for missing minutes and repair them (`GAP_SCAN_SECS`); 0 disables
+    pub gap_scan_secs: u64,
+    /// How far back each scan looks (`GAP_LOOKBACK_HOURS`), never past the retention cutoff
+    pub gap_lookback_hours: u64,
+    /// How often to compare the minutes stored since the last run with the exchange's official
+    /// 1m candles (`RECONCILE_SECS`); 0 disables
+    pub reconcile_secs: u64,
+    /// Price difference from the official candle that counts as a mismatch (`RECONCILE_TOLERANCE_BPS`)
+    pub reconcile_tolerance_bps: f64,
+    /// Replace mismatched minutes with the official candle (`RECONCILE_CORRECT`)
+    pub reconcile_correct: bool,
+    /// Predictions the evaluation job realizes and scores (`EVAL_WINDOW_HOURS`)
+    pub eval_window_hours: u64,
+}
+
+impl Schedules {
+    /// Whether `t` (UTC) falls in the maintenance window, start inclusive
+    pub fn in_window(&self, t: NaiveTime) -> bool {
+        t >= self.maintenance_start && t < self.maintenance_end
+    }
+
+    pub fn gap_scan(&self) -> Option<Duration> {
+        (self.gap_scan_secs > 0).then(|| Duration::from_secs(self.gap_scan_secs))
+    }
+
+    pub fn reconcile(&self) -> Option<Duration> {
+        (self.reconcile_secs > 0).then(|| Duration::from_secs(self.reconcile_secs))
+    }
+}
+
+impl Default for Schedules {
+    fn default() -> Self {
+        Self {
+            maintenance_start: NaiveTime::from_hms_opt(5, 0, 0).unwrap(),
+            maintenance_end: NaiveTime::from_hms_opt(5, 5, 0).unwrap(),
+            backfill_at: NaiveTime::from_hms_opt(5, 7, 0).unwrap(),
+            parallelism: 2,
+            gap_scan_secs: 3600,
+            gap_lookback_hours: 24,
+            reconcile_secs: 0,
+            reconcile_tolerance_bps: 10.0,
+            reconcile_correct: false,
+            eval_window_hours: 24,
+        }
+    }
+}
+
+/// Chat notifications from the services that load this config
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sinks {
+    /// `DISCORD_WEBHOOK_URL`
+    pub discord_webhook_url: Option<String>,
+    /// `SLACK_WEBHOOK_URL`
+    pub slack_webhook_url: Option<String>,
+    /// `NOTIFY_BATCH_SECS`
+    pub notify_batch_secs: u64,
+    /// `NOTIFY_MAX_PER_MIN`
+    pub notify_max_per_min: u32,
+}
+
+impl Default for Sinks {
+    fn default() -> Self {
+        Self {
+            discord_webhook_url: None,
+            slack_webhook_url: None,
+            notify_batch_secs: DEFAULT_BATCH_SECS,
+            notify_max_per_min: DEFAULT_MAX_PER_MIN,
+        }
+    }
+}
+
+impl Sinks {
+    /// `None` without a Discord or Slack URL
+    pub fn notify_config(&self) -> Option<NotifyConfig> {
+        let targets: Vec<Target> = [
+            self.discord_webhook_url.clone().map(Target::Discord),
+            self.slack_webhook_url.clone().map(Target::Slack),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!targets.is_empty()).then(|| NotifyConfig {
+            targets,
+            batch_window: Duration::from_secs(self.notify_batch_secs),
+            max_per_min: self.notify_max_per_min,
+        })
+    }
+}
+
+/// Settings for the ingestion services (websocket, trigger, fetcher, cleaner): a TOML or
+/// YAML file, then environment variables on top, then validation
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// `REDIS_URL`; `redis+sentinel://` and `redis+cluster://` select those topologies
+    pub redis_url: Option<String>,
+    /// `REDIS_MIRROR_URL`: a secondary Redis that live writes are copied to asynchronously
+    pub redis_mirror_url: Option<String>,
+    /// `REDIS_NAMESPACE`: what every key and channel starts with, `stock` by default
+    pub redis_namespace: Option<String>,
+    /// `DATABASE_URL`
+    pub database_url: Option<String>,
+    pub exchanges: Exchanges,
+    /// Added to the tracked set at startup, never removed from it (`SYMBOLS`, comma separated)
+    pub symbols: Vec<String>,
+    /// `SYMBOLS_FILE`: TOML or YAML list of symbols with their settings; the trigger syncs it
+    /// into the tracked set at startup and whenever it changes
+    pub symbols_file: Option<PathBuf>,
+    pub intervals: Intervals,
+    pub ingest: Ingest,
+    pub bars: BarSettings,
+    pub retention: Retention,
+    pub writes: Writes,
+    pub precision: Precision,
+    pub sharding: Sharding,
+    pub schedules: Schedules,
+    pub sinks: Sinks,
+    pub logging: LogSettings,
+    /// Vault or AWS Secrets Manager; its values win over the file and the environment
+    pub secrets: SecretsSettings,
+    /// Postgres and Redis TLS; nothing downgrades to plaintext unless a mode is `prefer`
+    pub tls: TlsConfig,
+    /// File the settings came from, if any
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+    /// Live values from the secrets backend, when one is configured
+    #[serde(skip)]
+    secret_store: Option<Secrets>,
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M:%S"))
+        .map_err(|_| format!("'{s}' is not a time of day (HH:MM)"))
+}
+
+fn time_of_day<'de, D: serde::Deserializer<'de>>(d: D) -> Result<NaiveTime, D::Error> {
+    let s = String::deserialize(d)?;
+    parse_time(&s).map_err(serde::de::Error::custom)
+}
+
+/// Applies `NAME` from the environment onto a field, collecting parse failures
+struct Overrides {
+    errors: Vec<String>,
+}
+
+impl Overrides {
+    fn text(&mut self, name: &str, field: &mut Option<String>) {
+        if let Ok(v) = env::var(name) {
+            *field = Some(v);
+        }
+    }
+
+    fn string(&mut self, name: &str, field: &mut String) {
+        if let Ok(v) = env::var(name) {
+            *field = v;
+        }
+    }
+
+    fn parsed<T: FromStr>(&mut self, name: &str, field: &mut T) {
+        if let Ok(v) = env::var(name) {
+            match v.trim().parse() {
+                Ok(x) => *field = x,
+                Err(_) => self.errors.push(format!("{name}: cannot parse '{v}'")),
+            }
+        }
+    }
+
+    fn optional<T: FromStr>(&mut self, name: &str, field: &mut Option<T>) {
+        if let Ok(v) = env::var(name) {
+            match v.trim().parse() {
+                Ok(x) => *field = Some(x),
+                Err(_) => self.errors.push(format!("{name}: cannot parse '{v}'")),
+            }
+        }
+    }
+
+    fn time(&mut self, name: &str, field: &mut NaiveTime) {
+        if let Ok(v) = env::var(name) {
+            match parse_time(v.trim()) {
+                Ok(t) => *field = t,
+                Err(e) => self.errors.push(format!("{name}: {e}")),
+            }
+        }
+    }
+}
+
+impl Config {
+    /// `CONFIG_FILE`, else the first of `config.toml` / `config.yaml` / `config.yml` that exists
+    fn file() -> Result<Option<PathBuf>, String> {
+        match env::var("CONFIG_FILE") {
+            Ok(path) => {
+                let path = PathBuf::from(path);
+                if !path.exists() {
+                    return Err(format!("CONFIG_FILE {} does not exist", path.display()));
+                }
+                Ok(Some(path))
+            }
+            Err(_) => Ok(DEFAULT_FILES.iter().map(PathBuf::from).find(|p| p.exists())),
+        }
+    }
+
+    /// Parse a config file by extension: `.yaml` / `.yml` as YAML, anything else as TOML
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        let is_yaml = matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml"));
+        let mut config: Self = if is_yaml {
+            serde_yaml::from_str(&text).map_err(|e| format!("invalid config {}: {e}", path.display()))?
+        } else {
+            toml::from_str(&text).map_err(|e| format!("invalid config {}: {e}", path.display()))?
+        };
+        config.source = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// File, then environment, then validation of everything plus `needs`; every problem
+    /// is reported at once
+    pub async fn load(needs: &[Need]) -> Result<Self, String> {
+        Self::load_with(None, needs, |_| {}).await
+    }
+
+    /// `load` from `file` when given, with `overrides` (e.g. command-line flags) applied
+    /// over the environment and before the secrets backend and validation
+    pub async fn load_with(file: Option<&Path>, needs: &[Need], overrides: impl FnOnce(&mut Self)) -> Result<Self, String> {
+        let file = match file {
+            Some(path) => Some(path.to_path_buf()),
+            None => Self::file()?,
+        };
+        let mut config = match file {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::default(),
+        };
+
+        let mut env = Overrides { errors: Vec::new() };
+        env.text("REDIS_URL", &mut config.redis_url);
+        env.text("REDIS_MIRROR_URL", &mut config.redis_mirror_url);
+        env.text("REDIS_NAMESPACE", &mut config.redis_namespace);
+        env.text("DATABASE_URL", &mut config.database_url);
+        env.text("FINNHUB_API_KEY", &mut config.exchanges.finnhub.api_key);
+        env.string("FINNHUB_WS_URL", &mut config.exchanges.finnhub.ws_url);
+        env.string("EXCHANGE_SOURCE", &mut config.exchanges.source);
+        env.string("OKX_WS_URL", &mut config.exchanges.okx.ws_url);
+        env.string("BYBIT_WS_URL", &mut config.exchanges.bybit.ws_url);
+        if let Ok(list) = env::var("SYMBOLS") {
+            config.symbols = list
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        if let Ok(path) = env::var("SYMBOLS_FILE") {
+            config.symbols_file = Some(PathBuf::from(path));
+        }
+        env.parsed("FETCH_INTERVAL_SECS", &mut config.intervals.fetch_secs);
+        env.parsed("RECONNECT_DELAY_SECS", &mut config.intervals.reconnect_secs);
+        env.parsed("TRIGGER_TICK_SECS", &mut config.intervals.trigger_tick_secs);
+        env.parsed("SYMBOLS_REFRESH_SECS", &mut config.intervals.symbols_refresh_secs);
+        env.parsed("TRADE_TS_MAX_SKEW_SECS", &mut config.ingest.ts_max_skew_secs);
+        env.parsed("TRADE_TS_POLICY", &mut config.ingest.ts_policy);
+        env.parsed("DEAD_LETTER_LEN", &mut config.ingest.dead_letter_len);
+        env.parsed("OHLCV_IDLE_HOURS", &mut config.ingest.ohlcv_idle_hours);
+        env.parsed("BAR_MAX_JUMP_PCT", &mut config.bars.max_jump_pct);
+        env.parsed("BAR_MIN_TICKS", &mut config.bars.min_ticks);
+        env.parsed("HISTORY_RETENTION_DAYS", &mut config.retention.history_days);
+        env.parsed("HISTORY_VACUUM", &mut config.retention.vacuum);
+        env.string("PG_ISOLATION", &mut config.writes.isolation);
+        env.parsed("PG_WRITE_RETRIES", &mut config.writes.retries);
+        env.parsed("PG_RETRY_BACKOFF_MS", &mut config.writes.retry_backoff_ms);
+        env.parsed("PRICE_DECIMAL", &mut config.precision.decimal);
+        env.optional("PRICE_SCALE", &mut config.precision.price_scale);
+        env.optional("VOLUME_SCALE", &mut config.precision.volume_scale);
+        env.parsed("FETCHER_SHARDS", &mut config.sharding.shards);
+        env.parsed("FETCHER_SHARD_INDEX", &mut config.sharding.index);
+        env.parsed("FETCHER_SHARD_COORDINATED", &mut config.sharding.coordinated);
+        env.parsed("FETCHER_MEMBER_TTL_SECS", &mut config.sharding.member_ttl_secs);
+        env.time("MAINT_START", &mut config.schedules.maintenance_start);
+        env.time("MAINT_END", &mut config.schedules.maintenance_end);
+        env.time("BACKFILL_TIME", &mut config.schedules.backfill_at);
+        env.parsed("MAINT_PARALLELISM", &mut config.schedules.parallelism);
+        env.parsed("GAP_SCAN_SECS", &mut config.schedules.gap_scan_secs);
+        env.parsed("GAP_LOOKBACK_HOURS", &mut config.schedules.gap_lookback_hours);
+        env.parsed("RECONCILE_SECS", &mut config.schedules.reconcile_secs);
+        env.parsed("RECONCILE_TOLERANCE_BPS", &mut config.schedules.reconcile_tolerance_bps);
+        env.parsed("RECONCILE_CORRECT", &mut config.schedules.reconcile_correct);
+        env.parsed("EVAL_WINDOW_HOURS", &mut config.schedules.eval_window_hours);
+        env.text("DISCORD_WEBHOOK_URL", &mut config.sinks.discord_webhook_url);
+        env.text("SLACK_WEBHOOK_URL", &mut config.sinks.slack_webhook_url);
+        env.parsed("NOTIFY_BATCH_SECS", &mut config.sinks.notify_batch_secs);
+        env.parsed("NOTIFY_MAX_PER_MIN", &mut config.sinks.notify_max_per_min);
+
+        let mut errors = env.errors;
+        errors.extend(config.logging.apply_env());
+        errors.extend(config.tls.apply_env());
+        let secret_errors = config.secrets.apply_env();
+        overrides(&mut config);
+
+        // A misconfigured backend is reported with everything else, not contacted
+        if secret_errors.is_empty() {
+            match Secrets::connect(&config.secrets).await {
+                Ok(Some(store)) => config.use_secrets(store),
+                Ok(None) => {}
+                Err(e) => errors.push(format!("secrets: {e}")),
+            }
+        }
+        errors.extend(secret_errors);
+
+        errors.extend(config.problems(needs));
+        if errors.is_empty() {
+            if let Some(ns) = &config.redis_namespace {
+                keys::set_namespace(ns)?;
+            }
+            return Ok(config);
+        }
+        let from = config
+            .source
+            .as_ref()
+            .map(|p| format!(" (file {}, then environment)", p.display()))
+            .unwrap_or_else(|| " (environment only, no config file)".to_string());
+        Err(format!("invalid configuration{from}:\n  - {}", errors.join("\n  - ")))
+    }
+
+    fn problems(&self, needs: &[Need]) -> Vec<String> {
+        let mut errors = Vec::new();
+        let missing = |what: &str, var: &str, key: &str| format!("{what} is required: set {var} or `{key}` in the config file");
+        if needs.contains(&Need::Redis) && self.redis_url.is_none() {
+            errors.push(missing("Redis", "REDIS_URL", "redis_url"));
+        }
+        if needs.contains(&Need::Postgres) && self.database_url.is_none() {
+            errors.push(missing("Postgres", "DATABASE_URL", "database_url"));
+        }
+        if needs.contains(&Need::Finnhub) && self.exchanges.source == "finnhub" && self.exchanges.finnhub.api_key.is_none() {
+            errors.push(missing("A Finnhub API key", "FINNHUB_API_KEY", "exchanges.finnhub.api_key"));
+        }
+        if let Some(ns) = &self.redis_namespace
+            && let Err(e) = keys::validate(ns)
+        {
+            errors.push(format!("redis_namespace: {e}"));
+        }
+        for (name, url) in [("redis_url", &self.redis_url), ("redis_mirror_url", &self.redis_mirror_url)] {
+            let Some(url) = url else { continue };
+            if !redis_conn::SCHEMES.iter().any(|scheme| url.starts_with(scheme)) {
+                errors.push(format!(
+                    "{name} must start with one of {}, got '{url}'",
+                    redis_conn::SCHEMES.join(" ")
+                ));
+            } else if let Err(e) = RedisClient::open_with(url, &self.tls.redis) {
+                errors.push(e.to_string());
+            }
+        }
+        if let Some(url) = &self.database_url
+            && !(url.starts_with("postgres://") || url.starts_with("postgresql://"))
+        {
+            errors.push("database_url must start with postgres:// or postgresql://".to_string());
+        }
+        if !source::SOURCES.contains(&self.exchanges.source.as_str()) {
+            errors.push(format!(
+                "exchanges.source must be one of {}, got '{}'",
+                source::SOURCES.join(", "),
+                self.exchanges.source
+            ));
+        }
+        for (name, url) in [
+            ("exchanges.finnhub.ws_url", &self.exchanges.finnhub.ws_url),
+            ("exchanges.okx.ws_url", &self.exchanges.okx.ws_url),
+            ("exchanges.bybit.ws_url", &self.exchanges.bybit.ws_url),
+        ] {
+            if let Err(e) = url::Url::parse(url) {
+                errors.push(format!("{name}: {e}"));
+            }
+        }
+        for s in &self.symbols {
+            if let Err(e) = symbols::parse(s) {
+                errors.push(format!("symbols: {e}"));
+            }
+        }
+        let b = &self.bars;
+        let overrides = b.symbols.iter().map(|(s, o)| (format!("bars.symbols.{s}"), o.max_jump_pct, o.min_ticks));
+        for (name, pct, ticks) in std::iter::once(("bars".to_string(), Some(b.max_jump_pct), Some(b.min_ticks))).chain(overrides) {
+            if pct.is_some_and(|p| !(p.is_finite() && p >= 0.0)) {
+                errors.push(format!("{name}.max_jump_pct must be a percentage of at least 0"));
+            }
+            if ticks == Some(0) {
+                errors.push(format!("{name}.min_ticks must be at least 1"));
+            }
+        }
+        for (symbol, o) in &b.symbols {
+            if let Err(e) = symbols::parse(symbol) {
+                errors.push(format!("bars.symbols: {e}"));
+            }
+            match &o.timeframes {
+                Some(tfs) if tfs.is_empty() => errors.push(format!("bars.symbols.{symbol}.timeframes must not be empty")),
+                Some(tfs) => {
+                    for tf in tfs.iter().filter(|tf| Timeframe::parse(tf).is_none()) {
+                        errors.push(format!("bars.symbols.{symbol}.timeframes: invalid timeframe '{tf}'"));
+                    }
+                }
+                None => {}
+            }
+        }
+        if let Some(path) = &self.symbols_file
+            && let Err(e) = symbols::load_file(path)
+        {
+            errors.push(format!("symbols_file: {e}"));
+        }
+        for (name, secs) in [
+            ("intervals.fetch_secs", self.intervals.fetch_secs),
+            ("intervals.reconnect_secs", self.intervals.reconnect_secs),
+            ("intervals.trigger_tick_secs", self.intervals.trigger_tick_secs),
+            ("intervals.symbols_refresh_secs", self.intervals.symbols_refresh_secs),
+        ] {
+            if secs == 0 {
+                errors.push(format!("{name} must be at least 1"));
+            }
+        }
+        if self.ingest.ts_max_skew_secs == 0 {
+            errors.push("ingest.ts_max_skew_secs must be at least 1".to_string());
+        }
+        if self.ingest.dead_letter_len == 0 {
+            errors.push("ingest.dead_letter_len must be at least 1".to_string());
+        }
+        if self.writes.isolation_level().is_none() {
+            errors.push(format!(
+                "writes.isolation must be one of {}, got '{}'",
+                ISOLATION_LEVELS.join(", "),
+                self.writes.isolation
+            ));
+        }
+        let p = &self.precision;
+        if p.decimal && cfg!(not(feature = "decimal")) {
+            errors.push("precision.decimal needs a build with the `decimal` feature".to_string());
+        }
+        for (name, scale) in [("precision.price_scale", p.price_scale), ("precision.volume_scale", p.volume_scale)] {
+            if let Some(scale) = scale.filter(|&s| s > MAX_SCALE) {
+                errors.push(format!("{name} must be at most {MAX_SCALE}, got {scale}"));
+            }
+            if scale.is_some() && !p.decimal {
+                errors.push(format!("{name} only applies with precision.decimal"));
+            }
+        }
+        let sh = &self.sharding;
+        if sh.shards == 0 {
+            errors.push("sharding.shards must be at least 1".to_string());
+        } else if !sh.coordinated && sh.index >= sh.shards {
+            errors.push(format!("sharding.index must be below shards ({}), got {}", sh.shards, sh.index));
+        }
+        if sh.coordinated && sh.member_ttl_secs <= self.intervals.fetch_secs {
+            errors.push(format!(
+                "sharding.member_ttl_secs must be longer than intervals.fetch_secs ({}), which is how often fetchers refresh",
+                self.intervals.fetch_secs
+            ));
+        }
+        let s = &self.schedules;
+        if s.maintenance_end <= s.maintenance_start {
+            errors.push(format!(
+                "schedules.maintenance_end ({}) must be after maintenance_start ({})",
+                s.maintenance_end, s.maintenance_start
+            ));
+        }
+        if s.backfill_at < s.maintenance_end {
+            errors.push(format!(
+                "schedules.backfill_at ({}) must not be before maintenance_end ({})",
+                s.backfill_at, s.maintenance_end
+            ));
+        }
+        if s.parallelism == 0 {
+            errors.push("schedules.parallelism must be at least 1".to_string());
+        }
+        if s.gap_scan_secs > 0 && s.gap_lookback_hours == 0 {
+            errors.push("schedules.gap_lookback_hours must be at least 1 while gap scans are on".to_string());
+        }
+        if !(s.reconcile_tolerance_bps.is_finite() && s.reconcile_tolerance_bps >= 0.0) {
+            errors.push("schedules.reconcile_tolerance_bps must be a number of at least 0".to_string());
+        }
+        if s.eval_window_hours == 0 {
+            errors.push("schedules.eval_window_hours must be at least 1".to_string());
+        }
+        if self.sinks.notify_max_per_min == 0 {
+            errors.push("sinks.notify_max_per_min must be at least 1".to_string());
+        }
+        errors
+    }
+
+    /// Copy the backend's values over file and environment so validation sees them
+    fn use_secrets(&mut self, store: Secrets) {
+        for (key, field) in [
+            ("REDIS_URL", &mut self.redis_url),
+            ("REDIS_MIRROR_URL", &mut self.redis_mirror_url),
+            ("DATABASE_URL", &mut self.database_url),
+            ("FINNHUB_API_KEY", &mut self.exchanges.finnhub.api_key),
+            ("DISCORD_WEBHOOK_URL", &mut self.sinks.discord_webhook_url),
+            ("SLACK_WEBHOOK_URL", &mut self.sinks.slack_webhook_url),
+        ] {
+            if let Some(v) = store.get(key) {
+                *field = Some(v);
+            }
+        }
+        self.secret_store = Some(store);
+    }
+
+    /// The secrets backend in use, if any
+    pub fn secrets_store(&self) -> Option<&Secrets> {
+        self.secret_store.as_ref()
+    }
+
+    /// The backend's latest value, so reconnects pick up rotated credentials
+    fn credential(&self, key: &str, field: &Option<String>) -> String {
+        self.secret_store
+            .as_ref()
+            .and_then(|s| s.get(key))
+            .or_else(|| field.clone())
+            .unwrap_or_else(|| panic!("{key} checked by Config::load"))
+    }
+
+    /// Only valid after `load` with `Need::Redis`
+    pub fn redis_url(&self) -> String {
+        self.credential("REDIS_URL", &self.redis_url)
+    }
+
+    /// The mirror, if one is configured
+    pub fn redis_mirror_url(&self) -> Option<String> {
+        self.redis_mirror_url
+            .is_some()
+            .then(|| self.credential("REDIS_MIRROR_URL", &self.redis_mirror_url))
+    }
+
+    /// Only valid after `load` with `Need::Postgres`
+    pub fn database_url(&self) -> String {
+        self.credential("DATABASE_URL", &self.database_url)
+    }
+
+    /// Redis, if configured, for binaries that run without it
+    pub fn optional_redis_url(&self) -> Option<String> {
+        self.redis_url
+            .is_some()
+            .then(|| self.credential("REDIS_URL", &self.redis_url))
+    }
+
+    /// Postgres, if configured, for binaries that run without it
+    pub fn optional_database_url(&self) -> Option<String> {
+        self.database_url
+            .is_some()
+            .then(|| self.credential("DATABASE_URL", &self.database_url))
+    }
+
+    /// The Finnhub API key if one is configured, for jobs that only need it for some symbols
+    pub fn finnhub_key(&self) -> Option<String> {
+        self.secret_store
+            .as_ref()
+            .and_then(|s| s.get("FINNHUB_API_KEY"))
+            .or_else(|| self.exchanges.finnhub.api_key.clone())
+    }
+
+    /// Only valid after `load` with `Need::Finnhub`
+    pub fn finnhub_api_key(&self) -> String {
+        self.credential("FINNHUB_API_KEY", &self.exchanges.finnhub.api_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_timestamp_policy() {
+        assert_eq!("clamp".parse(), Ok(TsPolicy::Clamp));
+        assert_eq!(" Skip ".parse(), Ok(TsPolicy::Skip));
+        assert!("drop".parse::<TsPolicy>().unwrap_err().contains("skip or clamp"));
+
+        assert_eq!(Config::default().ingest.ts_policy, TsPolicy::Skip);
+        let config: Config = toml::from_str("[ingest]\nts_policy = \"clamp\"").unwrap();
+        assert_eq!(config.ingest.ts_policy, TsPolicy::Clamp);
+        assert!(toml::from_str::<Config>("[ingest]\nts_policy = \"drop\"").is_err());
+    }
+
+    #[test]
+    fn rejects_a_zero_skew_or_dead_letter_list() {
+        let mut config = Config::default();
+        assert_eq!(config.problems(&[]), Vec::<String>::new());
+        config.ingest.ts_max_skew_secs = 0;
+        config.ingest.dead_letter_len = 0;
+        assert_eq!(
+            config.problems(&[]),
+            ["ingest.ts_max_skew_secs must be at least 1", "ingest.dead_letter_len must be at least 1"]
+        );
+    }
+}
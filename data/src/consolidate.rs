@@ -0,0 +1,317 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    env,
+};
+
+use serde::Serialize;
+
+
+const DEFAULT_MAX_AGE_SECS: i64 = 30;
+const DEFAULT_MAX_DIVERGENCE_BPS: f64 = 50.0;
+const DEFAULT_MIN_SOURCES: usize = 2;
+const DEFAULT_PUBLISH_MS: i64 = 1000;
+// Time constant of the decaying volume a source is weighted by
+const VOLUME_TAU_MS: f64 = 60_000.0;
+// With two sources there is no majority to say which one is wrong
+const MIN_SOURCES_TO_FLAG: usize = 3;
+
+// Longest first, so USDT is not read as USD + T
+const QUOTES: [&str; 12] = ["FDUSD", "USDT", "USDC", "BUSD", "TUSD", "USD", "EUR", "GBP", "JPY", "BTC", "ETH", "BNB"];
+const STABLECOINS: [&str; 4] = ["USDT", "USDC", "BUSD", "FDUSD"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    /// Median of the sources in line; ignores a single bad print outright
+    Median,
+    /// Mean weighted by each source's recent volume
+    Weighted,
+}
+
+/// Which symbols pool together and when a source is left out
+#[derive(Debug, Clone)]
+pub struct ConsolidationConfig {
+    pub method: Method,
+    /// Quotes older than this (relative to the newest in the group) do not count
+    pub max_age_ms: i64,
+    /// Sources further than this from the median are flagged and excluded
+    pub max_divergence_bps: f64,
+    /// Fresh sources needed before anything is published
+    pub min_sources: usize,
+    /// Minimum gap between publishes per canonical symbol
+    pub publish_ms: i64,
+    /// Count USDT / USDC / BUSD / FDUSD quotes as USD
+    pub fold_stablecoins: bool,
+    /// Explicit `EXCHANGE:PAIR` → canonical mappings where the pair name does not say
+    pub aliases: HashMap<String, String>,
+}
+
+impl Default for ConsolidationConfig {
+    fn default() -> Self {
+        Self {
+            method: Method::Median,
+            max_age_ms: DEFAULT_MAX_AGE_SECS * 1000,
+            max_divergence_bps: DEFAULT_MAX_DIVERGENCE_BPS,
+            min_sources: DEFAULT_MIN_SOURCES,
+            publish_ms: DEFAULT_PUBLISH_MS,
+            fold_stablecoins: true,
+            aliases: HashMap::new(),
+        }
+    }
+}
+
+impl ConsolidationConfig {
+    /// `CONSOLIDATE_METHOD` (median or weighted), `CONSOLIDATE_MAX_AGE_SECS`,
+    /// `CONSOLIDATE_MAX_DIVERGENCE_BPS`, `CONSOLIDATE_MIN_SOURCES`, `CONSOLIDATE_PUBLISH_MS`,
+    /// `CONSOLIDATE_FOLD_STABLECOINS` and `CONSOLIDATE_ALIASES` (`KRAKEN:XBTUSD=BTC-USD,...`)
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let var = |k: &str| env::var(k).ok();
+        Self {
+            method: match var("CONSOLIDATE_METHOD").as_deref().map(str::trim) {
+                Some("weighted") => Method::Weighted,
+                _ => d.method,
+            },
+            max_age_ms: var("CONSOLIDATE_MAX_AGE_SECS")
+                .and_then(|v| v.parse::<i64>().ok())
+                .map_or(d.max_age_ms, |s| s * 1000),
+            max_divergence_bps: var("CONSOLIDATE_MAX_DIVERGENCE_BPS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.max_divergence_bps),
+            min_sources: var("CONSOLIDATE_MIN_SOURCES")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.min_sources)
+                .max(2),
+            publish_ms: var("CONSOLIDATE_PUBLISH_MS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.publish_ms),
+            fold_stablecoins: var("CONSOLIDATE_FOLD_STABLECOINS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.fold_stablecoins),
+            aliases: var("CONSOLIDATE_ALIASES")
+                .map(|list| {
+                    list.split(',')
+                        .filter_map(|pair| pair.split_once('='))
+                        .map(|(symbol, canonical)| (symbol.trim().to_string(), canonical.trim().to_string()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// `BASE-QUOTE` for an `EXCHANGE:PAIR` symbol, e.g. `BINANCE:BTCUSDT` and
+    /// `COINBASE:BTC-USD` both give `BTC-USD`; `None` when the pair cannot be split
+    pub fn canonical(&self, symbol: &str) -> Option<String> {
+        self.assets(symbol).map(|(base, quote)| format!("{base}-{quote}"))
+    }
+
+    /// Base and quote asset of a symbol, after aliases and stablecoin folding
+    pub fn assets(&self, symbol: &str) -> Option<(String, String)> {
+        if let Some(c) = self.aliases.get(symbol) {
+            let (base, quote) = c.split_once('-')?;
+            return Some((base.to_string(), quote.to_string()));
+        }
+        let pair = symbol.split_once(':').map_or(symbol, |(_, pair)| pair).to_uppercase();
+        let (base, quote) = match pair.split_once(['-', '_', '/']) {
+            Some((base, quote)) => (base.to_string(), quote.to_string()),
+            None => {
+                let quote = QUOTES.iter().find(|q| pair.len() > q.len() && pair.ends_with(*q))?;
+                (pair[..pair.len() - quote.len()].to_string(), quote.to_string())
+            }
+        };
+        let base = if base == "XBT" { "BTC".to_string() } else { base };
+        let quote = if self.fold_stablecoins && STABLECOINS.contains(&quote.as_str()) {
+            "USD".to_string()
+        } else {
+            quote
+        };
+        Some((base, quote))
+    }
+}
+
+/// One source's contribution to a consolidated price
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceQuote {
+    pub symbol: String,
+    pub price: f64,
+    pub age_ms: i64,
+    /// Distance from the consolidated price, in basis points
+    pub divergence_bps: f64,
+    /// Decayed recent volume; the weight under `weighted`
+    pub weight: f64,
+    /// `ok`, `stale` or `divergent`; only `ok` sources are in the price
+    pub status: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Consolidated {
+    /// Canonical `BASE-QUOTE`
+    pub symbol: String,
+    pub price: f64,
+    pub method: Method,
+    /// Sources in the price
+    pub used: usize,
+    /// Highest minus lowest price in use, in basis points of `price`
+    pub spread_bps: f64,
+    pub ts: i64,
+    pub sources: Vec<SourceQuote>,
+    /// Sources that started diverging with this update
+    #[serde(skip)]
+    pub newly_flagged: Vec<String>,
+    /// Flagged sources back in line with this update
+    #[serde(skip)]
+    pub cleared: Vec<String>,
+}
+
+impl Consolidated {
+    /// Redis hash fields
+    pub fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("price".to_string(), self.price.to_string()),
+            ("method".to_string(), format!("{:?}", self.method).to_lowercase()),
+            ("used".to_string(), self.used.to_string()),
+            ("sources".to_string(), self.sources.len().to_string()),
+            ("spread_bps".to_string(), self.spread_bps.to_string()),
+            ("ts".to_string(), self.ts.to_string()),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Quote {
+    price: f64,
+    ts: i64,
+    volume: f64,
+}
+
+fn median(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    }
+}
+
+/// Latest quote of every source, grouped by canonical symbol
+pub struct Consolidator {
+    cfg: ConsolidationConfig,
+    canonical: HashMap<String, Option<String>>,
+    groups: HashMap<String, BTreeMap<String, Quote>>,
+    flagged: HashSet<String>,
+    last_publish: HashMap<String, i64>,
+}
+
+impl Consolidator {
+    pub fn new(cfg: ConsolidationConfig) -> Self {
+        Self {
+            cfg,
+            canonical: HashMap::new(),
+            groups: HashMap::new(),
+            flagged: HashSet::new(),
+            last_publish: HashMap::new(),
+        }
+    }
+
+    /// Record a trade; the group's consolidated price when it has enough fresh sources
+    /// and the last publish is at least `publish_ms` old
+    pub fn on_trade(&mut self, symbol: &str, price: f64, volume: f64, ts: i64) -> Option<Consolidated> {
+        if !price.is_finite() || price <= 0.0 {
+            return None;
+        }
+        let canonical = self
+            .canonical
+            .entry(symbol.to_string())
+            .or_insert_with(|| self.cfg.canonical(symbol))
+            .clone()?;
+        let group = self.groups.entry(canonical.clone()).or_default();
+        let quote = group.entry(symbol.to_string()).or_insert(Quote { price, ts, volume: 0.0 });
+        let decay = (-((ts - quote.ts).max(0) as f64) / VOLUME_TAU_MS).exp();
+        *quote = Quote {
+            price,
+            ts: ts.max(quote.ts),
+            volume: quote.volume * decay + volume.max(0.0),
+        };
+        if group.len() < self.cfg.min_sources
+            || self.last_publish.get(&canonical).is_some_and(|t| ts - t < self.cfg.publish_ms)
+        {
+            return None;
+        }
+
+        let now = group.values().map(|q| q.ts).max().unwrap_or(ts);
+        let fresh: Vec<(&String, Quote)> = group
+            .iter()
+            .filter(|(_, q)| now - q.ts <= self.cfg.max_age_ms)
+            .map(|(s, q)| (s, *q))
+            .collect();
+        if fresh.len() < self.cfg.min_sources {
+            return None;
+        }
+
+        let mut prices: Vec<f64> = fresh.iter().map(|(_, q)| q.price).collect();
+        prices.sort_by(f64::total_cmp);
+        let reference = median(&prices);
+        let bps = |p: f64, of: f64| (p - of) / of * 10_000.0;
+        let can_flag = fresh.len() >= MIN_SOURCES_TO_FLAG;
+        let divergent: HashSet<&String> = fresh
+            .iter()
+            .filter(|(_, q)| can_flag && bps(q.price, reference).abs() > self.cfg.max_divergence_bps)
+            .map(|(s, _)| *s)
+            .collect();
+        let used: Vec<(&String, Quote)> = fresh.iter().filter(|(s, _)| !divergent.contains(s)).cloned().collect();
+
+        let weight = |q: &Quote| q.volume * (-((now - q.ts) as f64) / VOLUME_TAU_MS).exp();
+        let mut used_prices: Vec<f64> = used.iter().map(|(_, q)| q.price).collect();
+        used_prices.sort_by(f64::total_cmp);
+        let total_weight: f64 = used.iter().map(|(_, q)| weight(q)).sum();
+        let consolidated = match self.cfg.method {
+            Method::Weighted if total_weight > 0.0 => {
+                used.iter().map(|(_, q)| q.price * weight(q)).sum::<f64>() / total_weight
+            }
+            _ => median(&used_prices),
+        };
+        let spread_bps = (used_prices[used_prices.len() - 1] - used_prices[0]) / consolidated * 10_000.0;
+
+        let (mut newly_flagged, mut cleared) = (Vec::new(), Vec::new());
+        let sources = group
+            .iter()
+            .map(|(s, q)| {
+                let status = if now - q.ts > self.cfg.max_age_ms {
+                    "stale"
+                } else if divergent.contains(s) {
+                    "divergent"
+                } else {
+                    "ok"
+                };
+                if status == "divergent" {
+                    if self.flagged.insert(s.clone()) {
+                        newly_flagged.push(s.clone());
+                    }
+                } else if status == "ok" && self.flagged.remove(s) {
+                    cleared.push(s.clone());
+                }
+                SourceQuote {
+                    symbol: s.clone(),
+                    price: q.price,
+                    age_ms: now - q.ts,
+                    divergence_bps: bps(q.price, consolidated),
+                    weight: weight(q),
+                    status,
+                }
+            })
+            .collect();
+
+        self.last_publish.insert(canonical.clone(), ts);
+        Some(Consolidated {
+            symbol: canonical,
+            price: consolidated,
+            method: self.cfg.method,
+            used: used.len(),
+            spread_bps,
+            ts: now,
+            sources,
+            newly_flagged,
+            cleared,
+        })
+    }
+}
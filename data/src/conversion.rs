@@ -0,0 +1,110 @@
+use std::{collections::HashMap, env};
+
+use crate::consolidate::{Consolidated, ConsolidationConfig};
+
+const DEFAULT_MAX_AGE_SECS: i64 = 60;
+
+/// A price restated in USD
+#[derive(Debug, Clone, Copy)]
+pub struct UsdPrice {
+    /// USD per unit of the pair's quote asset
+    pub rate: f64,
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rate {
+    usd: f64,
+    ts: i64,
+    /// From a consolidated price rather than a single exchange
+    consolidated: bool,
+}
+
+/// Live USD value of every asset some tracked pair quotes against USD (stablecoins count
+/// as USD), used to restate non-USD pairs such as `ETH-BTC`
+pub struct UsdConverter {
+    pairs: ConsolidationConfig,
+    max_age_ms: i64,
+    rates: HashMap<String, Rate>,
+    assets: HashMap<String, Option<(String, String)>>,
+}
+
+impl UsdConverter {
+    /// Pairs are split as `pairs` splits them; rates older than `USD_RATE_MAX_AGE_SECS`
+    /// (default 60) are not used
+    pub fn from_env(pairs: ConsolidationConfig) -> Self {
+        let max_age_secs = env::var("USD_RATE_MAX_AGE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_AGE_SECS);
+        Self {
+            pairs,
+            max_age_ms: max_age_secs * 1000,
+            rates: HashMap::new(),
+            assets: HashMap::new(),
+        }
+    }
+
+    fn assets(&mut self, symbol: &str) -> Option<(String, String)> {
+        self.assets
+            .entry(symbol.to_string())
+            .or_insert_with(|| self.pairs.assets(symbol))
+            .clone()
+    }
+
+    /// A fresh consolidated rate is not overwritten by a single exchange's print
+    fn set(&mut self, asset: &str, usd: f64, ts: i64, consolidated: bool) {
+        if !usd.is_finite() || usd <= 0.0 {
+            return;
+        }
+        if !consolidated
+            && let Some(r) = self.rates.get(asset)
+            && r.consolidated
+            && ts - r.ts <= self.max_age_ms
+        {
+            return;
+        }
+        self.rates.insert(asset.to_string(), Rate { usd, ts, consolidated });
+    }
+
+    fn learn(&mut self, base: &str, quote: &str, price: f64, ts: i64, consolidated: bool) {
+        match (base, quote) {
+            ("USD", "USD") => {}
+            (base, "USD") => self.set(base, price, ts, consolidated),
+            ("USD", quote) => self.set(quote, 1.0 / price, ts, consolidated),
+            _ => {}
+        }
+    }
+
+    /// Learn from a trade on `symbol`
+    pub fn on_trade(&mut self, symbol: &str, price: f64, ts: i64) {
+        if let Some((base, quote)) = self.assets(symbol) {
+            self.learn(&base, &quote, price, ts, false);
+        }
+    }
+
+    /// Learn from a consolidated `BASE-QUOTE` price; preferred over single exchanges
+    pub fn on_consolidated(&mut self, c: &Consolidated) {
+        if let Some((base, quote)) = c.symbol.split_once('-') {
+            self.learn(base, quote, c.price, c.ts, true);
+        }
+    }
+
+    /// USD per unit of `asset` as of `now`, when known and fresh
+    pub fn rate(&self, asset: &str, now: i64) -> Option<f64> {
+        if asset == "USD" {
+            return Some(1.0);
+        }
+        self.rates
+            .get(asset)
+            .filter(|r| now - r.ts <= self.max_age_ms)
+            .map(|r| r.usd)
+    }
+
+    /// `price` of `symbol` in USD at `ts`
+    pub fn to_usd(&mut self, symbol: &str, price: f64, ts: i64) -> Option<UsdPrice> {
+        let (_, quote) = self.assets(symbol)?;
+        let rate = self.rate(&quote, ts)?;
+        Some(UsdPrice { rate, price: price * rate })
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    env,
+};
+
+use crate::bars::Bar;
+
+/// Benchmark slots in the feature schema
+pub const MAX_BENCHMARKS: usize = 2;
+
+const DEFAULT_BENCHMARKS: &str = "BINANCE:BTCUSDT,BINANCE:ETHUSDT";
+// Benchmark returns remembered per timeframe, by bar start
+const HISTORY: usize = 64;
+const WINDOW: usize = 20;
+// Paired returns needed before correlation and beta are reported
+const MIN_PAIRS: usize = 10;
+
+/// Recent bar returns of the benchmark symbols, per timeframe
+#[derive(Debug, Clone)]
+pub struct Benchmarks {
+    symbols: Vec<String>,
+    last_close: HashMap<(usize, String), f64>,
+    returns: HashMap<(usize, String), VecDeque<(i64, f64)>>,
+}
+
+impl Default for Benchmarks {
+    fn default() -> Self {
+        Self::new(DEFAULT_BENCHMARKS.split(',').map(str::to_string).collect())
+    }
+}
+
+impl Benchmarks {
+    /// Only the first `MAX_BENCHMARKS` symbols are used
+    pub fn new(mut symbols: Vec<String>) -> Self {
+        symbols.truncate(MAX_BENCHMARKS);
+        Self {
+            symbols,
+            last_close: HashMap::new(),
+            returns: HashMap::new(),
+        }
+    }
+
+    /// `BENCHMARK_SYMBOLS` (comma separated), BTC and ETH by default
+    pub fn from_env() -> Self {
+        match env::var("BENCHMARK_SYMBOLS") {
+            Ok(list) => Self::new(
+                list.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect(),
+            ),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    /// Record the bar's return if it belongs to a benchmark
+    pub fn on_bar(&mut self, bar: &Bar) {
+        let Some(slot) = self.symbols.iter().position(|s| *s == bar.symbol) else {
+            return;
+        };
+        let key = (slot, bar.tf.clone());
+        if let Some(prev) = self.last_close.insert(key.clone(), bar.close)
+            && prev > 0.0
+            && bar.close > 0.0
+        {
+            let returns = self.returns.entry(key).or_default();
+            returns.push_back((bar.start, (bar.close / prev).ln()));
+            if returns.len() > HISTORY {
+                returns.pop_front();
+            }
+        }
+    }
+
+    /// Return of benchmark `slot` over the `tf` bar starting at `start`
+    pub fn ret(&self, slot: usize, tf: &str, start: i64) -> Option<f64> {
+        self.returns
+            .get(&(slot, tf.to_string()))?
+            .iter()
+            .rev()
+            .find(|(s, _)| *s == start)
+            .map(|(_, r)| *r)
+    }
+}
+
+/// Rolling correlation and beta of one series against each benchmark.
+///
+/// Pairs are formed one bar late: the benchmark bar for the same bucket may
+/// close before or after this one depending on trade arrival, so only the
+/// previous bucket is reliably complete, live and in replay alike.
+#[derive(Debug, Clone, Default)]
+pub struct CrossFeatures {
+    /// Start and return of the previous bar
+    prev: Option<(i64, f64)>,
+    pairs: [VecDeque<(f64, f64)>; MAX_BENCHMARKS],
+}
+
+impl CrossFeatures {
+    /// `[corr, beta, benchmark return of the previous bar]` per benchmark slot, 0 when unknown
+    pub fn update(&mut self, bar: &Bar, ret: Option<f64>, bench: &Benchmarks) -> [f64; 3 * MAX_BENCHMARKS] {
+        let mut out = [0.0; 3 * MAX_BENCHMARKS];
+        if let Some((start, r)) = self.prev {
+            for (slot, pairs) in self.pairs.iter_mut().enumerate() {
+                let Some(rb) = bench.ret(slot, &bar.tf, start) else {
+                    continue;
+                };
+                pairs.push_back((r, rb));
+                if pairs.len() > WINDOW {
+                    pairs.pop_front();
+                }
+                out[3 * slot + 2] = rb;
+            }
+        }
+        for (slot, pairs) in self.pairs.iter().enumerate() {
+            if let Some((corr, beta)) = corr_beta(pairs) {
+                out[3 * slot] = corr;
+                out[3 * slot + 1] = beta;
+            }
+        }
+        self.prev = ret.map(|r| (bar.start, r));
+        out
+    }
+}
+
+/// Pearson correlation and OLS beta of `y` on `x` for `(y, x)` pairs
+fn corr_beta(pairs: &VecDeque<(f64, f64)>) -> Option<(f64, f64)> {
+    if pairs.len() < MIN_PAIRS {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let (my, mx) = pairs.iter().fold((0.0, 0.0), |(a, b), (y, x)| (a + y / n, b + x / n));
+    let (mut cov, mut vx, mut vy) = (0.0, 0.0, 0.0);
+    for (y, x) in pairs {
+        cov += (y - my) * (x - mx);
+        vx += (x - mx).powi(2);
+        vy += (y - my).powi(2);
+    }
+    if vx <= 0.0 || vy <= 0.0 {
+        return None;
+    }
+    Some((cov / (vx * vy).sqrt(), cov / vx))
+}
@@ -0,0 +1,39 @@
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Router,
+};
+use include_dir::{include_dir, Dir};
+
+/// `dashboard/`, compiled into the binary
+static ASSETS: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/dashboard");
+
+/// The live dashboard at `/dashboard/`. The assets are public; the data they show comes
+/// from the guarded API routes with the key the user enters.
+pub fn routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
+        .route("/dashboard", get(|| async { Redirect::permanent("/dashboard/") }))
+        .route("/dashboard/", get(|| async { asset("index.html") }))
+        .route("/dashboard/{*path}", get(|Path(path): Path<String>| async move { asset(&path) }))
+}
+
+fn asset(path: &str) -> Response {
+    let Some(file) = ASSETS.get_file(path) else {
+        return (StatusCode::NOT_FOUND, "not found").into_response();
+    };
+    let content_type = match path.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("json") => "application/json",
+        _ => "application/octet-stream",
+    };
+    (
+        [(header::CONTENT_TYPE, content_type), (header::CACHE_CONTROL, "no-cache")],
+        file.contents(),
+    )
+        .into_response()
+}
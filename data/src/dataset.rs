@@ -0,0 +1,258 @@
+use std::{fs::File, io::Write, path::Path};
+
+use chrono::NaiveDateTime;
+use tokio_postgres::Client as PgClient;
+
+use crate::{
+    backtest::{self, Tick},
+    bars::Timeframe,
+    features::{FEATURE_NAMES, FEATURE_SCHEMA_VERSION},
+    labels::{Label, PriceIndex},
+    normalize::Normalizer,
+};
+
+/// Feature vector at one bar close, as the predictor saw it
+#[derive(Debug, Clone)]
+pub struct FeatureRow {
+    pub symbol: String,
+    /// ms since epoch
+    pub ts: i64,
+    pub values: Vec<f64>,
+}
+
+/// Features joined with targets, ready to write
+#[derive(Debug, Clone, Default)]
+pub struct Dataset {
+    pub target_names: Vec<String>,
+    pub rows: Vec<(FeatureRow, Vec<f64>)>,
+}
+
+/// Stored vectors for `tf` in `[from, to)` at the current schema version
+pub async fn load_stored(
+    pg: &PgClient,
+    tf: &str,
+    symbols: &[String],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<Vec<FeatureRow>, String> {
+    let rows = pg
+        .query(
+            "SELECT symbol, ts, feature_values FROM features \
+             WHERE tf = $1 AND schema_version = $2 AND ts >= $3 AND ts < $4 \
+               AND (cardinality($5::text[]) = 0 OR symbol = ANY($5)) \
+             ORDER BY symbol, ts",
+            &[&tf, &FEATURE_SCHEMA_VERSION, &from, &to, &symbols],
+        )
+        .await
+        .map_err(|e| format!("feature query failed: {e}"))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| FeatureRow {
+            symbol: r.get(0),
+            ts: r.get::<_, NaiveDateTime>(1).and_utc().timestamp_millis(),
+            values: r.get(2),
+        })
+        .filter(|r| r.values.len() == FEATURE_NAMES.len())
+        .collect())
+}
+
+/// Recompute vectors by replaying ticks through the live bar/feature code
+pub fn replay(ticks: &[Tick], tf: Timeframe) -> Vec<FeatureRow> {
+    backtest::build_samples(ticks, tf)
+        .into_iter()
+        .flat_map(|(symbol, series)| {
+            series.samples.into_iter().map(move |s| FeatureRow {
+                symbol: symbol.clone(),
+                ts: s.ts,
+                values: s.x,
+            })
+        })
+        .collect()
+}
+
+/// Attach `labels` to each row; rows missing any label (prices not yet available) are dropped
+pub fn with_labels(rows: Vec<FeatureRow>, prices: &PriceIndex, labels: &[Label]) -> Dataset {
+    let target_names = labels.iter().map(|l| l.to_string()).collect();
+    let rows = rows
+        .into_iter()
+        .filter_map(|row| {
+            let base = prices.at(&row.symbol, row.ts)?;
+            let targets = labels
+                .iter()
+                .map(|l| l.value(prices, &row.symbol, row.ts, base))
+                .collect::<Option<Vec<f64>>>()?;
+            Some((row, targets))
+        })
+        .collect();
+
+    Dataset { target_names, rows }
+}
+
+/// Scale every feature column in place; targets are left as they are
+pub fn normalize(ds: &mut Dataset, normalizer: &Normalizer) {
+    for (row, _) in &mut ds.rows {
+        row.values = normalizer.transform(&row.values);
+    }
+}
+
+fn header(ds: &Dataset) -> Vec<String> {
+    ["symbol", "ts", "schema_version"]
+        .iter()
+        .map(|s| s.to_string())
+        .chain(FEATURE_NAMES.iter().map(|s| s.to_string()))
+        .chain(ds.target_names.iter().cloned())
+        .collect()
+}
+
+/// Target column names from a file header; fails unless its feature columns match this build
+fn target_columns(columns: &[String]) -> Result<Vec<String>, String> {
+    let expected = header(&Dataset::default());
+    if columns.len() <= expected.len() || columns[..expected.len()] != expected[..] {
+        return Err(format!(
+            "dataset columns do not match feature schema v{FEATURE_SCHEMA_VERSION} or it has no targets"
+        ));
+    }
+    Ok(columns[expected.len()..].to_vec())
+}
+
+/// Read a file written by `write_csv`
+pub fn read_csv(path: &Path) -> Result<Dataset, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    let mut lines = text.lines();
+    let columns: Vec<String> = lines.next().unwrap_or_default().split(',').map(str::to_string).collect();
+    let target_names = target_columns(&columns)?;
+    let n_features = FEATURE_NAMES.len();
+
+    let mut rows = Vec::new();
+    for (i, line) in lines.enumerate().filter(|(_, l)| !l.is_empty()) {
+        let bad = || format!("{}: malformed row {}", path.display(), i + 2);
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() != columns.len() {
+            return Err(bad());
+        }
+        let values = fields[3..]
+            .iter()
+            .map(|v| v.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| bad())?;
+        let row = FeatureRow {
+            symbol: fields[0].to_string(),
+            ts: fields[1].parse().map_err(|_| bad())?,
+            values: values[..n_features].to_vec(),
+        };
+        rows.push((row, values[n_features..].to_vec()));
+    }
+    Ok(Dataset { target_names, rows })
+}
+
+pub fn write_csv(ds: &Dataset, path: &Path) -> Result<(), String> {
+    let err = |e: std::io::Error| format!("cannot write {}: {e}", path.display());
+    let mut out = std::io::BufWriter::new(File::create(path).map_err(err)?);
+    writeln!(out, "{}", header(ds).join(",")).map_err(err)?;
+    for (row, targets) in &ds.rows {
+        let values: Vec<String> = row.values.iter().chain(targets).map(|v| v.to_string()).collect();
+        writeln!(out, "{},{},{FEATURE_SCHEMA_VERSION},{}", row.symbol, row.ts, values.join(",")).map_err(err)?;
+    }
+    out.flush().map_err(err)
+}
+
+/// Flat Parquet file: `symbol` (UTF8), `ts` (timestamp ms), `schema_version`, then one DOUBLE per column
+#[cfg(feature = "parquet")]
+pub fn write_parquet(ds: &Dataset, path: &Path) -> Result<(), String> {
+    use std::sync::Arc;
+
+    use parquet::{
+        basic::Compression,
+        data_type::{ByteArray, ByteArrayType, DoubleType, Int32Type, Int64Type},
+        file::{properties::WriterProperties, writer::SerializedFileWriter},
+        schema::parser::parse_message_type,
+    };
+
+    let columns = header(ds);
+    let doubles: String = columns[3..]
+        .iter()
+        .map(|c| format!("REQUIRED DOUBLE {c}; "))
+        .collect();
+    let schema = parse_message_type(&format!(
+        "message dataset {{ REQUIRED BYTE_ARRAY symbol (UTF8); \
+         REQUIRED INT64 ts (TIMESTAMP(MILLIS,true)); REQUIRED INT32 schema_version; {doubles}}}"
+    ))
+    .map_err(|e| format!("invalid parquet schema: {e}"))?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+
+    let err = |e: parquet::errors::ParquetError| format!("cannot write {}: {e}", path.display());
+    let file = File::create(path).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+    let mut writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(props)).map_err(err)?;
+    let mut group = writer.next_row_group().map_err(err)?;
+
+    let symbols: Vec<ByteArray> = ds.rows.iter().map(|(r, _)| r.symbol.as_str().into()).collect();
+    let ts: Vec<i64> = ds.rows.iter().map(|(r, _)| r.ts).collect();
+    let versions = vec![FEATURE_SCHEMA_VERSION; ds.rows.len()];
+    let mut index = 0;
+    while let Some(mut col) = group.next_column().map_err(err)? {
+        match index {
+            0 => col.typed::<ByteArrayType>().write_batch(&symbols, None, None),
+            1 => col.typed::<Int64Type>().write_batch(&ts, None, None),
+            2 => col.typed::<Int32Type>().write_batch(&versions, None, None),
+            i => {
+                let values: Vec<f64> = ds
+                    .rows
+                    .iter()
+                    .map(|(r, t)| {
+                        let c = i - 3;
+                        if c < r.values.len() { r.values[c] } else { t[c - r.values.len()] }
+                    })
+                    .collect();
+                col.typed::<DoubleType>().write_batch(&values, None, None)
+            }
+        }
+        .map_err(err)?;
+        col.close().map_err(err)?;
+        index += 1;
+    }
+    group.close().map_err(err)?;
+    writer.close().map_err(err)?;
+    Ok(())
+}
+
+/// Read a file written by `write_parquet`
+#[cfg(feature = "parquet")]
+pub fn read_parquet(path: &Path) -> Result<Dataset, String> {
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::RowAccessor,
+    };
+
+    let err = |e: parquet::errors::ParquetError| format!("cannot read {}: {e}", path.display());
+    let file = File::open(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    let reader = SerializedFileReader::new(file).map_err(err)?;
+    let columns: Vec<String> = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|c| c.name().to_string())
+        .collect();
+    let target_names = target_columns(&columns)?;
+    let n_features = FEATURE_NAMES.len();
+
+    let mut rows = Vec::new();
+    for row in reader.get_row_iter(None).map_err(err)? {
+        let row = row.map_err(err)?;
+        let values = (3..columns.len())
+            .map(|i| row.get_double(i))
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(err)?;
+        let features = FeatureRow {
+            symbol: row.get_string(0).map_err(err)?.clone(),
+            ts: row.get_timestamp_millis(1).map_err(err)?,
+            values: values[..n_features].to_vec(),
+        };
+        rows.push((features, values[n_features..].to_vec()));
+    }
+    Ok(Dataset { target_names, rows })
+}
@@ -0,0 +1,215 @@
+use std::{collections::VecDeque, env};
+
+use chrono::NaiveDateTime;
+use tokio_postgres::Client as PgClient;
+
+use crate::{dataset, features::FEATURE_NAMES};
+
+
+const BINS: usize = 10;
+const DEFAULT_WINDOW: usize = 500;
+const DEFAULT_CHECK_EVERY: usize = 50;
+// Common rule of thumb: < 0.1 stable, 0.1–0.25 moderate shift, > 0.25 significant
+const DEFAULT_PSI_THRESHOLD: f64 = 0.25;
+// Stand-in for empty bins so the log term stays finite
+const PSI_EPS: f64 = 1e-4;
+// Fewer reference vectors than this give unreliable decile edges
+const MIN_REFERENCE: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct DriftConfig {
+    /// Live vectors in the rolling comparison window
+    pub window: usize,
+    /// Recompute PSI every this many vectors
+    pub check_every: usize,
+    pub threshold: f64,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_WINDOW,
+            check_every: DEFAULT_CHECK_EVERY,
+            threshold: DEFAULT_PSI_THRESHOLD,
+        }
+    }
+}
+
+impl DriftConfig {
+    /// `DRIFT_WINDOW`, `DRIFT_CHECK_EVERY`, `DRIFT_PSI_THRESHOLD`
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let get = |k: &str| env::var(k).ok();
+        Self {
+            window: get("DRIFT_WINDOW").and_then(|v| v.parse().ok()).unwrap_or(d.window),
+            check_every: get("DRIFT_CHECK_EVERY")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.check_every)
+                .max(1),
+            threshold: get("DRIFT_PSI_THRESHOLD")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.threshold),
+        }
+    }
+}
+
+/// Population stability index of `actual` against `expected` bin proportions
+pub fn psi(expected: &[f64], actual: &[f64]) -> f64 {
+    expected
+        .iter()
+        .zip(actual)
+        .map(|(e, a)| {
+            let (e, a) = (e.max(PSI_EPS), a.max(PSI_EPS));
+            (a - e) * (a / e).ln()
+        })
+        .sum()
+}
+
+/// Decile edges of one feature in the reference set and the share of it in each bin
+#[derive(Debug, Clone)]
+struct Reference {
+    edges: Vec<f64>,
+    expected: Vec<f64>,
+}
+
+impl Reference {
+    fn new(mut values: Vec<f64>) -> Option<Self> {
+        values.retain(|v| v.is_finite());
+        if values.len() < MIN_REFERENCE {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let edges: Vec<f64> = (1..BINS).map(|i| values[i * values.len() / BINS]).collect();
+        let mut counts = [0usize; BINS];
+        let mut reference = Self { edges, expected: Vec::new() };
+        for v in &values {
+            counts[reference.bin(*v)] += 1;
+        }
+        reference.expected = counts.iter().map(|c| *c as f64 / values.len() as f64).collect();
+        Some(reference)
+    }
+
+    fn bin(&self, v: f64) -> usize {
+        self.edges.partition_point(|e| *e < v)
+    }
+}
+
+/// PSI of every feature after a check
+#[derive(Debug, Clone)]
+pub struct DriftReport {
+    /// In `FEATURE_NAMES` order; `None` for features without a usable reference
+    pub psi: Vec<Option<f64>>,
+    /// Features that crossed the threshold on this check
+    pub drifted: Vec<&'static str>,
+    /// Features that fell back under it on this check
+    pub recovered: Vec<&'static str>,
+}
+
+impl DriftReport {
+    /// Redis hash fields: feature name → PSI
+    pub fn fields(&self) -> Vec<(String, String)> {
+        FEATURE_NAMES
+            .iter()
+            .zip(&self.psi)
+            .filter_map(|(name, psi)| psi.map(|p| (name.to_string(), p.to_string())))
+            .collect()
+    }
+}
+
+/// Compares a rolling window of live feature vectors (pooled across symbols)
+/// against the distribution the active model was trained on
+pub struct DriftMonitor {
+    cfg: DriftConfig,
+    reference: Vec<Option<Reference>>,
+    /// Bin index of each recent value, per feature
+    window: Vec<VecDeque<usize>>,
+    since_check: usize,
+    drifted: Vec<bool>,
+}
+
+impl DriftMonitor {
+    /// `None` when no feature has enough reference data
+    pub fn new(cfg: DriftConfig, reference_rows: &[Vec<f64>]) -> Option<Self> {
+        let reference: Vec<Option<Reference>> = (0..FEATURE_NAMES.len())
+            .map(|i| Reference::new(reference_rows.iter().filter_map(|r| r.get(i).copied()).collect()))
+            .collect();
+        if reference.iter().all(Option::is_none) {
+            return None;
+        }
+        Some(Self {
+            cfg,
+            window: vec![VecDeque::new(); reference.len()],
+            drifted: vec![false; reference.len()],
+            reference,
+            since_check: 0,
+        })
+    }
+
+    /// Add one live vector; returns a report every `check_every` vectors once the window is full
+    pub fn observe(&mut self, x: &[f64]) -> Option<DriftReport> {
+        for ((reference, window), v) in self.reference.iter().zip(&mut self.window).zip(x) {
+            let Some(reference) = reference else { continue };
+            if !v.is_finite() {
+                continue;
+            }
+            window.push_back(reference.bin(*v));
+            if window.len() > self.cfg.window {
+                window.pop_front();
+            }
+        }
+
+        self.since_check += 1;
+        if self.since_check < self.cfg.check_every {
+            return None;
+        }
+        self.since_check = 0;
+
+        let mut report = DriftReport {
+            psi: Vec::with_capacity(self.reference.len()),
+            drifted: Vec::new(),
+            recovered: Vec::new(),
+        };
+        for (i, (reference, window)) in self.reference.iter().zip(&self.window).enumerate() {
+            let Some(reference) = reference.as_ref().filter(|_| window.len() >= self.cfg.window) else {
+                report.psi.push(None);
+                continue;
+            };
+            let mut counts = [0usize; BINS];
+            for b in window {
+                counts[*b] += 1;
+            }
+            let actual: Vec<f64> = counts.iter().map(|c| *c as f64 / window.len() as f64).collect();
+            let value = psi(&reference.expected, &actual);
+            report.psi.push(Some(value));
+
+            let drifted = value > self.cfg.threshold;
+            if drifted != self.drifted[i] {
+                self.drifted[i] = drifted;
+                if drifted {
+                    report.drifted.push(FEATURE_NAMES[i]);
+                } else {
+                    report.recovered.push(FEATURE_NAMES[i]);
+                }
+            }
+        }
+        report.psi.iter().any(Option::is_some).then_some(report)
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.cfg.threshold
+    }
+}
+
+/// Stored `tf` feature vectors in `[from, to)`: the training reference
+pub async fn load_reference(
+    pg: &PgClient,
+    tf: &str,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<Vec<Vec<f64>>, String> {
+    Ok(dataset::load_stored(pg, tf, &[], from, to)
+        .await?
+        .into_iter()
+        .map(|r| r.values)
+        .collect())
+}
@@ -0,0 +1,73 @@
+use redis::{ErrorKind, RedisError};
+use thiserror::Error;
+use tokio_postgres::error::SqlState;
+
+use crate::tls::TlsError;
+
+/// A Redis or Postgres failure, classified so callers can retry what may recover and stop
+/// on what will not
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("invalid Redis URL: {0}")]
+    RedisUrl(#[source] RedisError),
+    #[error("invalid Redis URL: {0}")]
+    RedisTopology(String),
+    #[error("Redis: {0}")]
+    Redis(#[from] RedisError),
+    #[error("Postgres: {0}")]
+    Postgres(#[from] tokio_postgres::Error),
+    #[error("TLS: {0}")]
+    Tls(#[from] TlsError),
+    #[error("{0} timed out")]
+    Timeout(&'static str),
+}
+
+impl StoreError {
+    /// Worth retrying: connection trouble, timeouts and server-side back-pressure
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Redis(e) => redis_transient(e),
+            Self::Postgres(e) => pg_transient(e),
+            Self::Timeout(_) => true,
+            Self::RedisUrl(_) | Self::RedisTopology(_) | Self::Tls(_) => false,
+        }
+    }
+}
+
+pub fn redis_transient(e: &RedisError) -> bool {
+    e.is_io_error()
+        || e.is_timeout()
+        || e.is_connection_dropped()
+        || e.is_connection_refusal()
+        || matches!(
+            e.kind(),
+            ErrorKind::TryAgain | ErrorKind::BusyLoadingError | ErrorKind::ClusterDown | ErrorKind::MasterDown
+        )
+}
+
+/// Errors without a SQLSTATE are I/O or protocol failures; of the rest, only connection
+/// exceptions (08), insufficient resources (53), operator intervention (57P) and
+/// serialization failures or deadlocks (40001, 40P01) can succeed on retry
+pub fn pg_transient(e: &tokio_postgres::Error) -> bool {
+    if e.is_closed() {
+        return true;
+    }
+    match e.as_db_error() {
+        None => true,
+        Some(db) => {
+            let code = db.code().code();
+            code.starts_with("08")
+                || code.starts_with("53")
+                || code.starts_with("57P")
+                || code == "40001"
+                || code == "40P01"
+        }
+    }
+}
+
+/// A serialization failure or deadlock: the transaction lost to a concurrent one and can be
+/// run again as is
+pub fn pg_conflict(e: &tokio_postgres::Error) -> bool {
+    e.code()
+        .is_some_and(|c| *c == SqlState::T_R_SERIALIZATION_FAILURE || *c == SqlState::T_R_DEADLOCK_DETECTED)
+}
@@ -0,0 +1,177 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use tokio_postgres::Client as PgClient;
+
+use tracing::info;
+use crate::{
+    backtest,
+    bars::Timeframe,
+    config::Config,
+    fetcher::try_connect_pg,
+    labels::{Label, PriceIndex},
+    predictions,
+};
+
+/// Create the `prediction_metrics` table if missing
+pub async fn ensure_table(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS prediction_metrics ( \
+             id BIGSERIAL PRIMARY KEY, \
+             symbol TEXT NOT NULL, \
+             model TEXT NOT NULL, \
+             model_version TEXT NOT NULL, \
+             window_start TIMESTAMP NOT NULL, \
+             window_end TIMESTAMP NOT NULL, \
+             samples BIGINT NOT NULL, \
+             mae DOUBLE PRECISION NOT NULL, \
+             rmse DOUBLE PRECISION NOT NULL, \
+             hit_rate DOUBLE PRECISION NOT NULL, \
+             evaluated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc') \
+         ); \
+         CREATE INDEX IF NOT EXISTS prediction_metrics_model_idx \
+             ON prediction_metrics (model, model_version, evaluated_at);",
+    )
+    .await
+}
+
+/// Fill `realized_price`/`realized_return` for predictions whose target time
+/// has passed, using the same `Label::Return` the dataset exporter trains on
+pub async fn realize(pg: &PgClient, since: NaiveDateTime) -> Result<u64, String> {
+    let now = Utc::now().naive_utc();
+    let pending = pg
+        .query(
+            "SELECT id, symbol, horizon, base_price, feature_ts, target_ts FROM predictions \
+             WHERE realized_price IS NULL AND feature_ts >= $1 AND target_ts <= $2",
+            &[&since, &now],
+        )
+        .await
+        .map_err(|e| format!("pending predictions query failed: {e}"))?;
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let mut symbols: Vec<String> = pending.iter().map(|r| r.get(1)).collect();
+    symbols.sort();
+    symbols.dedup();
+    let from = pending.iter().map(|r| r.get::<_, NaiveDateTime>(4)).min().unwrap_or(since);
+    let to = pending.iter().map(|r| r.get::<_, NaiveDateTime>(5)).max().unwrap_or(now);
+    let ticks = backtest::load_history(pg, &symbols, from, to + Duration::seconds(1)).await?;
+    let prices = PriceIndex::new(&ticks);
+
+    let mut ids: Vec<i64> = Vec::new();
+    let mut realized: Vec<f64> = Vec::new();
+    for row in &pending {
+        let symbol: String = row.get(1);
+        let horizon: String = row.get(2);
+        let base: f64 = row.get(3);
+        let ts = row.get::<_, NaiveDateTime>(4).and_utc().timestamp_millis();
+        let Some(tf) = Timeframe::parse(&horizon) else {
+            continue;
+        };
+        if let Some(r) = Label::Return(tf).value(&prices, &symbol, ts, base) {
+            ids.push(row.get(0));
+            realized.push(r);
+        }
+    }
+
+    pg.execute(
+        "UPDATE predictions p \
+         SET realized_return = r.ret, realized_price = p.base_price * exp(r.ret) \
+         FROM unnest($1::bigint[], $2::float8[]) AS r(id, ret) \
+         WHERE p.id = r.id",
+        &[&ids, &realized],
+    )
+    .await
+    .map_err(|e| format!("realized update failed: {e}"))
+}
+
+/// MAE / RMSE / directional hit-rate per symbol and model version over the last `window`
+pub async fn score(pg: &PgClient, window: Duration) -> Result<u64, tokio_postgres::Error> {
+    let end = Utc::now().naive_utc();
+    let start = end - window;
+    pg.execute(
+        "INSERT INTO prediction_metrics \
+         (symbol, model, model_version, window_start, window_end, samples, mae, rmse, hit_rate) \
+         SELECT symbol, model, model_version, $1, $2, count(*), \
+                avg(abs(predicted_return - realized_return)), \
+                sqrt(avg(power(predicted_return - realized_return, 2))), \
+                avg(CASE WHEN sign(predicted_return) = sign(realized_return) THEN 1.0 ELSE 0.0 END)::float8 \
+         FROM predictions \
+         WHERE realized_return IS NOT NULL AND target_ts >= $1 AND target_ts < $2 \
+         GROUP BY symbol, model, model_version",
+        &[&start, &end],
+    )
+    .await
+}
+
+/// Live accuracy of one registered model
+#[derive(Debug, Clone)]
+pub struct ModelScore {
+    pub model_id: i32,
+    pub samples: i64,
+    pub mae: f64,
+    pub rmse: f64,
+    pub hit_rate: f64,
+}
+
+/// Score two registered models (e.g. active vs shadow) on the bars both predicted
+/// since `since`, so neither benefits from a different sample
+pub async fn compare(pg: &PgClient, a: i32, b: i32, since: NaiveDateTime) -> Result<Vec<ModelScore>, String> {
+    let rows = pg
+        .query(
+            "WITH paired AS ( \
+                 SELECT symbol, horizon, feature_ts FROM predictions \
+                 WHERE model_id IN ($1, $2) AND realized_return IS NOT NULL AND feature_ts >= $3 \
+                 GROUP BY symbol, horizon, feature_ts \
+                 HAVING count(DISTINCT model_id) = 2 \
+             ) \
+             SELECT p.model_id, count(*), \
+                    avg(abs(p.predicted_return - p.realized_return)), \
+                    sqrt(avg(power(p.predicted_return - p.realized_return, 2))), \
+                    avg(CASE WHEN sign(p.predicted_return) = sign(p.realized_return) THEN 1.0 ELSE 0.0 END)::float8 \
+             FROM predictions p \
+             JOIN paired USING (symbol, horizon, feature_ts) \
+             WHERE p.model_id IN ($1, $2) \
+             GROUP BY p.model_id",
+            &[&a, &b, &since],
+        )
+        .await
+        .map_err(|e| format!("model comparison failed: {e}"))?;
+    Ok(rows
+        .iter()
+        .map(|r| ModelScore {
+            model_id: r.get(0),
+            samples: r.get(1),
+            mae: r.get(2),
+            rmse: r.get(3),
+            hit_rate: r.get(4),
+        })
+        .collect())
+}
+
+/// Scheduled evaluation: realize outcomes, then score the window (`schedules.eval_window_hours`)
+pub async fn run(config: &Config) -> Result<u64, String> {
+    info!("📏 Evaluation starting…");
+    let hours = config.schedules.eval_window_hours;
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    predictions::ensure_table(&pg)
+        .await
+        .map_err(|e| format!("predictions table setup failed: {e}"))?;
+    ensure_table(&pg)
+        .await
+        .map_err(|e| format!("prediction_metrics table setup failed: {e}"))?;
+
+    let window = Duration::hours(hours as i64);
+    let realized = realize(&pg, Utc::now().naive_utc() - window)
+        .await
+        .map_err(|e| format!("realizing predictions failed: {e}"))?;
+    info!("✅ Realized {realized} predictions");
+
+    let scored = score(&pg, window)
+        .await
+        .map_err(|e| format!("scoring predictions failed: {e}"))?;
+    info!("✨ Evaluation finished: {scored} symbol/model rows over the last {hours}h");
+    Ok(scored)
+}
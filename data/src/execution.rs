@@ -0,0 +1,284 @@
+use std::{collections::HashMap, env, time::Duration};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use redis::AsyncCommands;
+use serde::Deserialize;
+use sha2::Sha256;
+use tokio_postgres::Client as PgClient;
+
+use tracing::{info, warn};
+use crate::{instruments::{self, Instrument}, keys::KILL_SWITCH_KEY, signals::{Side, Signal}};
+
+use crate::redis_conn::RedisConn;
+
+
+const TESTNET_URL: &str = "https://testnet.binance.vision";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const RECV_WINDOW_MS: u64 = 5_000;
+// Finnhub symbols look like "BINANCE:BTCUSDT"
+const BINANCE_PREFIX: &str = "BINANCE:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderType {
+    Market,
+    Limit,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExecutionConfig {
+    pub base_url: String,
+    pub api_key: String,
+    pub api_secret: String,
+    pub order_type: OrderType,
+    /// Quote-currency notional per position
+    pub notional: f64,
+    /// Limit price offset from the last close, in basis points (towards the market)
+    pub limit_offset_bps: f64,
+}
+
+impl ExecutionConfig {
+    /// Enabled with `EXECUTION_ENABLED=true` plus `BINANCE_API_KEY` / `BINANCE_API_SECRET`.
+    /// Optional: `BINANCE_BASE_URL` (testnet), `EXECUTION_ORDER_TYPE` (market|limit),
+    /// `EXECUTION_NOTIONAL` (quote, default 50), `EXECUTION_LIMIT_OFFSET_BPS` (default 5).
+    pub fn from_env() -> Option<Self> {
+        if !env::var("EXECUTION_ENABLED").is_ok_and(|v| v == "1" || v == "true") {
+            return None;
+        }
+        let order_type = match env::var("EXECUTION_ORDER_TYPE").as_deref() {
+            Ok("limit") => OrderType::Limit,
+            _ => OrderType::Market,
+        };
+        Some(Self {
+            base_url: env::var("BINANCE_BASE_URL").unwrap_or_else(|_| TESTNET_URL.to_string()),
+            api_key: env::var("BINANCE_API_KEY").expect("❌ BINANCE_API_KEY not set"),
+            api_secret: env::var("BINANCE_API_SECRET").expect("❌ BINANCE_API_SECRET not set"),
+            order_type,
+            notional: env::var("EXECUTION_NOTIONAL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(50.0),
+            limit_offset_bps: env::var("EXECUTION_LIMIT_OFFSET_BPS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5.0),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderResponse {
+    order_id: i64,
+    client_order_id: String,
+    status: String,
+    executed_qty: String,
+    cummulative_quote_qty: String,
+}
+
+/// Exchange acknowledgement of one order
+#[derive(Debug, Clone)]
+pub struct ExecutionFill {
+    pub symbol: String,
+    pub side: &'static str,
+    pub order_type: OrderType,
+    pub order_id: i64,
+    pub client_order_id: String,
+    pub status: String,
+    pub executed_qty: f64,
+    pub quote_qty: f64,
+    pub signal_ts: i64,
+}
+
+fn sign(secret: &str, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(payload.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Trim a decimal to at most 8 places without trailing zeros
+fn fmt_decimal(v: f64) -> String {
+    let s = format!("{v:.8}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Spot order executor. Spot cannot short, so `Short` signals are treated as
+/// `Flat`: positions are long-only and closed by selling what was bought.
+pub struct Executor {
+    cfg: ExecutionConfig,
+    http: reqwest::Client,
+    /// Base-asset quantity held per symbol from our own fills
+    holdings: HashMap<String, f64>,
+}
+
+impl Executor {
+    pub fn new(cfg: ExecutionConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("❌ Failed to build HTTP client");
+        Self {
+            cfg,
+            http,
+            holdings: HashMap::new(),
+        }
+    }
+
+    pub async fn kill_switch_engaged(redis: &mut RedisConn) -> bool {
+        // Fail safe: an unreachable Redis counts as engaged
+        redis.exists(KILL_SWITCH_KEY).await.unwrap_or(true)
+    }
+
+    /// Buy on a `Long` signal when flat, sell holdings on `Flat`/`Short`. Prices go to the
+    /// symbol's tick and quantities down to its lot; orders under its minimums are refused.
+    pub async fn on_signal(
+        &mut self,
+        redis: &mut RedisConn,
+        signal: &Signal,
+        price: f64,
+    ) -> Result<Option<ExecutionFill>, String> {
+        let Some(symbol) = signal.symbol.strip_prefix(BINANCE_PREFIX) else {
+            return Ok(None);
+        };
+        let held = self.holdings.get(&signal.symbol).copied().unwrap_or(0.0);
+        let side = match signal.side {
+            Side::Long if held <= 0.0 => "BUY",
+            Side::Flat | Side::Short if held > 0.0 => "SELL",
+            _ => return Ok(None),
+        };
+        if Self::kill_switch_engaged(redis).await {
+            info!("🛑 Kill switch engaged — skipping {side} {symbol}");
+            return Ok(None);
+        }
+
+        // Without refreshed instrument metadata, orders go out unrounded and the exchange judges them
+        let instrument: Option<Instrument> = instruments::load(redis, &signal.symbol).await.unwrap_or_else(|e| {
+            warn!("⚠️ Could not read the {} instrument: {e}", signal.symbol);
+            None
+        });
+        let check = |qty: f64, price: f64| instrument.as_ref().map_or(Ok(()), |i| i.check_order(qty, price));
+        let floor_qty = |qty: f64| instrument.as_ref().map_or(qty, |i| i.floor_qty(qty));
+
+        let mut params: Vec<(&str, String)> = vec![("symbol", symbol.to_string()), ("side", side.to_string())];
+        match (self.cfg.order_type, side) {
+            (OrderType::Market, "BUY") => {
+                check(self.cfg.notional / price, price)?;
+                params.push(("type", "MARKET".into()));
+                params.push(("quoteOrderQty", fmt_decimal(self.cfg.notional)));
+            }
+            (OrderType::Market, _) => {
+                let qty = floor_qty(held);
+                check(qty, price)?;
+                params.push(("type", "MARKET".into()));
+                params.push(("quantity", fmt_decimal(qty)));
+            }
+            (OrderType::Limit, _) => {
+                let offset = self.cfg.limit_offset_bps / 10_000.0;
+                let raw = if side == "BUY" { price * (1.0 + offset) } else { price * (1.0 - offset) };
+                let limit = instrument.as_ref().map_or(raw, |i| i.round_price(raw));
+                let qty = floor_qty(if side == "BUY" { self.cfg.notional / limit } else { held });
+                check(qty, limit)?;
+                params.push(("type", "LIMIT".into()));
+                params.push(("timeInForce", "IOC".into()));
+                params.push(("price", fmt_decimal(limit)));
+                params.push(("quantity", fmt_decimal(qty)));
+            }
+        }
+        params.push(("recvWindow", RECV_WINDOW_MS.to_string()));
+        params.push(("timestamp", Utc::now().timestamp_millis().to_string()));
+
+        let query = params
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+        let signature = sign(&self.cfg.api_secret, &query);
+
+        let resp = self
+            .http
+            .post(format!("{}/api/v3/order?{query}&signature={signature}", self.cfg.base_url))
+            .header("X-MBX-APIKEY", &self.cfg.api_key)
+            .send()
+            .await
+            .map_err(|e| format!("order request failed: {e}"))?;
+        let status = resp.status();
+        let body = resp.text().await.map_err(|e| format!("order response unreadable: {e}"))?;
+        if !status.is_success() {
+            return Err(format!("order rejected ({status}): {body}"));
+        }
+        let order: OrderResponse =
+            serde_json::from_str(&body).map_err(|e| format!("invalid order response: {e}"))?;
+
+        let executed_qty: f64 = order.executed_qty.parse().unwrap_or(0.0);
+        let quote_qty: f64 = order.cummulative_quote_qty.parse().unwrap_or(0.0);
+        let entry = self.holdings.entry(signal.symbol.clone()).or_insert(0.0);
+        if side == "BUY" {
+            *entry += executed_qty;
+        } else {
+            *entry = (*entry - executed_qty).max(0.0);
+        }
+
+        Ok(Some(ExecutionFill {
+            symbol: signal.symbol.clone(),
+            side,
+            order_type: self.cfg.order_type,
+            order_id: order.order_id,
+            client_order_id: order.client_order_id,
+            status: order.status,
+            executed_qty,
+            quote_qty,
+            signal_ts: signal.ts,
+        }))
+    }
+}
+
+/// Create the `execution_fills` table if missing
+pub async fn ensure_table(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS execution_fills ( \
+             id BIGSERIAL PRIMARY KEY, \
+             symbol TEXT NOT NULL, \
+             side TEXT NOT NULL, \
+             order_type TEXT NOT NULL, \
+             order_id BIGINT NOT NULL, \
+             client_order_id TEXT NOT NULL, \
+             status TEXT NOT NULL, \
+             executed_qty DOUBLE PRECISION NOT NULL, \
+             quote_qty DOUBLE PRECISION NOT NULL, \
+             signal_ts TIMESTAMP NOT NULL, \
+             created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc') \
+         );",
+    )
+    .await
+}
+
+pub async fn insert_fill(pg: &PgClient, f: &ExecutionFill) -> Result<u64, tokio_postgres::Error> {
+    let order_type = match f.order_type {
+        OrderType::Market => "market",
+        OrderType::Limit => "limit",
+    };
+    let signal_ts = DateTime::from_timestamp_millis(f.signal_ts)
+        .unwrap_or_default()
+        .naive_utc();
+    pg.execute(
+        "INSERT INTO execution_fills \
+         (symbol, side, order_type, order_id, client_order_id, status, executed_qty, quote_qty, signal_ts) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        &[
+            &f.symbol,
+            &f.side,
+            &order_type,
+            &f.order_id,
+            &f.client_order_id,
+            &f.status,
+            &f.executed_qty,
+            &f.quote_qty,
+            &signal_ts,
+        ],
+    )
+    .await
+}
@@ -0,0 +1,341 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::Infallible,
+    str::FromStr,
+    time::Duration,
+};
+
+use axum::{
+    extract::ws::{Message, WebSocket},
+    response::sse::Event,
+};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use tokio::{
+    sync::{broadcast, mpsc},
+    time::timeout,
+};
+
+use tracing::warn;
+use crate::{
+    auth::{Permission, Principal},
+    keys::{BARS_CHANNEL, PREDICTIONS_CHANNEL, SIGNALS_CHANNEL, TRADES_CHANNEL},
+    ratelimit::Quota,
+    relay,
+};
+
+/// Channels fanned out to WebSocket clients
+pub fn channels() -> [&'static str; 4] {
+    [TRADES_CHANNEL, BARS_CHANNEL, PREDICTIONS_CHANNEL, SIGNALS_CHANNEL].map(|c| c.as_str())
+}
+
+/// Messages queued per client before new ones are dropped
+const CLIENT_QUEUE: usize = 256;
+/// A client that cannot take one frame in this long is disconnected
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a client can subscribe to per symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stream {
+    Trades,
+    Bars,
+    Predictions,
+    Signals,
+}
+
+impl Stream {
+    pub const ALL: [Stream; 4] = [Stream::Trades, Stream::Bars, Stream::Predictions, Stream::Signals];
+
+    pub fn from_channel(channel: &str) -> Option<Self> {
+        match channel {
+            c if c == TRADES_CHANNEL.as_str() => Some(Self::Trades),
+            c if c == BARS_CHANNEL.as_str() => Some(Self::Bars),
+            c if c == PREDICTIONS_CHANNEL.as_str() => Some(Self::Predictions),
+            c if c == SIGNALS_CHANNEL.as_str() => Some(Self::Signals),
+            _ => None,
+        }
+    }
+
+    /// What a client needs to receive this stream
+    pub fn permission(self) -> Permission {
+        match self {
+            Self::Trades | Self::Bars => Permission::ReadPrices,
+            Self::Predictions | Self::Signals => Permission::ReadPredictions,
+        }
+    }
+
+    /// `requested`, or every stream `principal` may read when `None`; errors on a forbidden one
+    pub fn resolve(requested: Option<Vec<Stream>>, principal: &Principal) -> Result<Vec<Stream>, String> {
+        match requested {
+            Some(streams) => match streams.iter().find(|s| !principal.allows(s.permission())) {
+                Some(s) => Err(format!("'{}' lacks the {} permission for {}", principal.name, s.permission(), s.event())),
+                None => Ok(streams),
+            },
+            None => Ok(Stream::ALL
+                .into_iter()
+                .filter(|s| principal.allows(s.permission()))
+                .collect()),
+        }
+    }
+
+    /// Push `type` / SSE event name for one message of this stream
+    pub fn event(self) -> &'static str {
+        match self {
+            Self::Trades => "trade",
+            Self::Bars => "bar",
+            Self::Predictions => "prediction",
+            Self::Signals => "signal",
+        }
+    }
+}
+
+impl FromStr for Stream {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trades" => Ok(Self::Trades),
+            "bars" => Ok(Self::Bars),
+            "predictions" => Ok(Self::Predictions),
+            "signals" => Ok(Self::Signals),
+            other => Err(format!("unknown stream '{other}' (expected trades, bars, predictions or signals)")),
+        }
+    }
+}
+
+/// Client → server: `{"op":"subscribe","symbols":["BINANCE:BTCUSDT"],"streams":["trades"]}`.
+/// `"*"` matches every symbol; omitting `streams` means all of them.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Command {
+    Subscribe {
+        symbols: Vec<String>,
+        streams: Option<Vec<Stream>>,
+    },
+    Unsubscribe {
+        symbols: Vec<String>,
+        streams: Option<Vec<Stream>>,
+    },
+}
+
+/// Server → client envelope
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Push<'a> {
+    Trade { data: &'a RawValue },
+    Bar { data: &'a RawValue },
+    Prediction { data: &'a RawValue },
+    Signal { data: &'a RawValue },
+    Subscribed {
+        symbols: Vec<&'a String>,
+        subscriptions: &'a BTreeMap<String, BTreeSet<Stream>>,
+    },
+    /// Messages this client missed because it read too slowly
+    Lagged { skipped: u64 },
+    Error { message: String },
+}
+
+/// Per-connection symbol → streams
+#[derive(Debug, Default)]
+struct Subscriptions(BTreeMap<String, BTreeSet<Stream>>);
+
+impl Subscriptions {
+    fn apply(&mut self, command: Command, principal: &Principal) -> Result<(), String> {
+        match command {
+            Command::Subscribe { symbols, streams } => {
+                let streams = Stream::resolve(streams, principal)?;
+                for symbol in symbols {
+                    self.0.entry(symbol).or_default().extend(streams.iter().copied());
+                }
+            }
+            Command::Unsubscribe { symbols, streams } => {
+                for symbol in symbols {
+                    match &streams {
+                        Some(streams) => {
+                            if let Some(set) = self.0.get_mut(&symbol) {
+                                for s in streams {
+                                    set.remove(s);
+                                }
+                            }
+                        }
+                        None => {
+                            self.0.remove(&symbol);
+                        }
+                    }
+                }
+                self.0.retain(|_, set| !set.is_empty());
+            }
+        }
+        Ok(())
+    }
+
+    fn wants(&self, symbol: &str, stream: Stream) -> bool {
+        [symbol, "*"]
+            .iter()
+            .any(|s| self.0.get(*s).is_some_and(|set| set.contains(&stream)))
+    }
+
+    /// The stream `msg` belongs to, if this connection subscribed to it
+    fn matching(&self, msg: &relay::Message) -> Option<Stream> {
+        #[derive(Deserialize)]
+        struct Keyed {
+            symbol: String,
+        }
+        let stream = Stream::from_channel(&msg.channel)?;
+        let keyed: Keyed = serde_json::from_str(&msg.payload).ok()?;
+        self.wants(&keyed.symbol, stream).then_some(stream)
+    }
+}
+
+/// Bounded queue in front of the socket writer; overflow is dropped and reported as `lagged`
+struct Outbox {
+    tx: mpsc::Sender<String>,
+    skipped: u64,
+}
+
+impl Outbox {
+    /// False once the writer has gone away
+    fn push(&mut self, push: &Push<'_>) -> bool {
+        if self.skipped > 0 {
+            let notice = serde_json::to_string(&Push::Lagged { skipped: self.skipped }).unwrap_or_default();
+            match self.tx.try_send(notice) {
+                Ok(()) => self.skipped = 0,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    self.skipped += 1;
+                    return true;
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => return false,
+            }
+        }
+        let Ok(text) = serde_json::to_string(push) else {
+            return true;
+        };
+        match self.tx.try_send(text) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.skipped += 1;
+                true
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+/// Serve one WebSocket client: apply its subscribe/unsubscribe commands and forward
+/// matching relay messages without ever blocking on a slow reader. Commands count
+/// against `quota` like HTTP requests do.
+pub async fn serve(
+    socket: WebSocket,
+    mut rx: broadcast::Receiver<relay::Message>,
+    principal: Principal,
+    quota: Option<Quota>,
+) {
+    let (mut sink, mut incoming) = socket.split();
+    let (tx, mut queue) = mpsc::channel::<String>(CLIENT_QUEUE);
+    let mut writer = tokio::spawn(async move {
+        while let Some(text) = queue.recv().await {
+            match timeout(SEND_TIMEOUT, sink.send(Message::Text(text.into()))).await {
+                Ok(Ok(())) => {}
+                Ok(Err(_)) => break,
+                Err(_) => {
+                    warn!("⚠️ WebSocket client too slow — disconnecting");
+                    break;
+                }
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let mut outbox = Outbox { tx, skipped: 0 };
+    let mut subs = Subscriptions::default();
+
+    loop {
+        tokio::select! {
+            _ = &mut writer => return,
+            msg = incoming.next() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                if let Some(Err(wait)) = quota.as_ref().map(Quota::check) {
+                    let message = format!("rate limit exceeded, retry in {}ms", wait.as_millis());
+                    if !outbox.push(&Push::Error { message }) {
+                        break;
+                    }
+                    continue;
+                }
+                let reply = match serde_json::from_str::<Command>(&text) {
+                    Ok(command) => subs.apply(command, &principal).err().map(|message| Push::Error { message }),
+                    Err(e) => Some(Push::Error { message: format!("invalid command: {e}") }),
+                };
+                let symbols: Vec<&String> = subs.0.keys().collect();
+                let reply = reply.unwrap_or(Push::Subscribed { symbols, subscriptions: &subs.0 });
+                if !outbox.push(&reply) {
+                    break;
+                }
+            }
+            event = rx.recv() => {
+                let msg = match event {
+                    Ok(msg) => msg,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        outbox.skipped += skipped;
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(stream) = subs.matching(&msg) else {
+                    continue;
+                };
+                let Ok(data) = serde_json::from_str::<&RawValue>(&msg.payload) else {
+                    continue;
+                };
+                let push = match stream {
+                    Stream::Trades => Push::Trade { data },
+                    Stream::Bars => Push::Bar { data },
+                    Stream::Predictions => Push::Prediction { data },
+                    Stream::Signals => Push::Signal { data },
+                };
+                if !outbox.push(&push) {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Dropping the outbox lets the writer flush what is queued and close
+    drop(outbox);
+    let _ = writer.await;
+}
+
+/// Server-Sent Events for `symbols` (`"*"` for all) on `streams`, which the caller has
+/// already checked against its permissions: one event per message, named like the
+/// WebSocket push types, plus `lagged` when this client fell behind
+pub fn events(
+    rx: broadcast::Receiver<relay::Message>,
+    symbols: Vec<String>,
+    streams: Vec<Stream>,
+) -> impl futures::Stream<Item = Result<Event, Infallible>> {
+    let mut subs = Subscriptions::default();
+    for symbol in symbols {
+        subs.0.entry(symbol).or_default().extend(streams.iter().copied());
+    }
+    futures::stream::unfold((rx, subs), |(mut rx, subs)| async move {
+        loop {
+            let event = match rx.recv().await {
+                Ok(msg) => match subs.matching(&msg) {
+                    Some(stream) => Event::default().event(stream.event()).data(msg.payload),
+                    None => continue,
+                },
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    Event::default().event("lagged").data(format!(r#"{{"skipped":{skipped}}}"#))
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+            return Some((Ok(event), (rx, subs)));
+        }
+    })
+}
+
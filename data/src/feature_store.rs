@@ -0,0 +1,64 @@
+use chrono::{DateTime, NaiveDateTime};
+use tokio_postgres::Client as PgClient;
+
+use tracing::warn;
+use crate::features::{FeatureVector, FEATURE_NAMES, FEATURE_SCHEMA_VERSION};
+
+fn naive_ms(ms: i64) -> NaiveDateTime {
+    DateTime::from_timestamp_millis(ms)
+        .unwrap_or_default()
+        .naive_utc()
+}
+
+/// Create `feature_schemas` and `features` if missing and register the current schema
+pub async fn ensure_tables(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS feature_schemas ( \
+             version INTEGER PRIMARY KEY, \
+             names TEXT[] NOT NULL, \
+             created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc') \
+         ); \
+         CREATE TABLE IF NOT EXISTS features ( \
+             symbol TEXT NOT NULL, \
+             tf TEXT NOT NULL, \
+             ts TIMESTAMP NOT NULL, \
+             schema_version INTEGER NOT NULL REFERENCES feature_schemas (version), \
+             feature_values DOUBLE PRECISION[] NOT NULL, \
+             created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'), \
+             PRIMARY KEY (symbol, tf, ts, schema_version) \
+         );",
+    )
+    .await?;
+
+    let names: Vec<&str> = FEATURE_NAMES.to_vec();
+    let rows = pg
+        .query(
+            "INSERT INTO feature_schemas (version, names) VALUES ($1, $2) \
+             ON CONFLICT (version) DO NOTHING RETURNING version",
+            &[&FEATURE_SCHEMA_VERSION, &names],
+        )
+        .await?;
+    if rows.is_empty() {
+        // Already registered: make sure the code still agrees with it
+        let stored: Vec<String> = pg
+            .query_one("SELECT names FROM feature_schemas WHERE version = $1", &[&FEATURE_SCHEMA_VERSION])
+            .await?
+            .get(0);
+        if stored != names {
+            warn!(
+                "⚠️ Feature schema v{FEATURE_SCHEMA_VERSION} is registered with different columns — bump FEATURE_SCHEMA_VERSION"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Store a vector exactly as the predictor saw it
+pub async fn insert(pg: &PgClient, fv: &FeatureVector) -> Result<u64, tokio_postgres::Error> {
+    pg.execute(
+        "INSERT INTO features (symbol, tf, ts, schema_version, feature_values) \
+         VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+        &[&fv.symbol, &fv.tf, &naive_ms(fv.ts), &FEATURE_SCHEMA_VERSION, &fv.values],
+    )
+    .await
+}
@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+
+use crate::{
+    anomaly::{AnomalyDetector, AnomalyScores},
+    bars::Bar,
+    correlation::{Benchmarks, CrossFeatures},
+    normalize::RollingWindow,
+    patterns::{Pattern, PatternDetector},
+    volatility::{VolatilityEstimator, VolatilitySnapshot},
+};
+
+
+/// Bump whenever `FEATURE_NAMES` or the definition of any feature changes
+pub const FEATURE_SCHEMA_VERSION: i32 = 4;
+
+/// Fixed feature schema; every vector has exactly these columns in this order
+pub const FEATURE_NAMES: [&str; 29] = [
+    "ret_1",
+    "ret_5",
+    "ret_20",
+    "volatility_20",
+    "range",
+    "volume_z_20",
+    "imbalance",
+    "imbalance_5",
+    "trades_z_20",
+    "fair_gap",
+    "garch_vol",
+    "imbalance_20",
+    "arrival_rate",
+    "large_trade",
+    "large_imbalance",
+    "doji",
+    "hammer",
+    "bullish_engulfing",
+    "bearish_engulfing",
+    "three_white_soldiers",
+    "three_black_crows",
+    "jump_z",
+    "volume_spike_z",
+    "bench1_corr_20",
+    "bench1_beta_20",
+    "bench1_ret_lag",
+    "bench2_corr_20",
+    "bench2_beta_20",
+    "bench2_ret_lag",
+];
+
+const LOOKBACK: usize = 20;
+
+/// One feature row for a symbol at a horizon (the bar timeframe)
+#[derive(Debug, Clone)]
+pub struct FeatureVector {
+    pub symbol: String,
+    pub tf: String,
+    /// Close time of the bar the features were computed on, ms since epoch
+    pub ts: i64,
+    pub values: Vec<f64>,
+}
+
+impl FeatureVector {
+    /// Redis hash fields: one per feature plus `ts`
+    pub fn fields(&self) -> Vec<(String, String)> {
+        let mut fields: Vec<(String, String)> = FEATURE_NAMES
+            .iter()
+            .zip(&self.values)
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        fields.push(("ts".to_string(), self.ts.to_string()));
+        fields
+    }
+}
+
+fn push_bounded(buf: &mut VecDeque<f64>, v: f64, cap: usize) {
+    buf.push_back(v);
+    if buf.len() > cap {
+        buf.pop_front();
+    }
+}
+
+/// Rolling state for one symbol/timeframe
+#[derive(Debug, Clone, Default)]
+pub struct FeatureExtractor {
+    closes: VecDeque<f64>,
+    returns: RollingWindow<LOOKBACK>,
+    volumes: RollingWindow<LOOKBACK>,
+    trades: RollingWindow<LOOKBACK>,
+    imbalances: RollingWindow<5>,
+    /// (signed, sided) tick-rule volume per bar
+    flow: VecDeque<(f64, f64)>,
+    volatility: VolatilityEstimator,
+    patterns: PatternDetector,
+    anomaly: AnomalyDetector,
+    cross: CrossFeatures,
+}
+
+impl FeatureExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Volatility estimates as of the last bar
+    pub fn volatility(&self) -> VolatilitySnapshot {
+        self.volatility.snapshot()
+    }
+
+    /// Candlestick patterns completed by the last bar
+    pub fn patterns(&self) -> &[Pattern] {
+        self.patterns.last()
+    }
+
+    /// Return/volume surprise of the last bar
+    pub fn anomaly(&self) -> AnomalyScores {
+        self.anomaly.last()
+    }
+
+    /// Feed a closed bar; returns a vector once `LOOKBACK` bars of history exist.
+    /// `bench` must already have seen every bar closed before this one.
+    pub fn update(&mut self, bar: &Bar, bench: &Benchmarks) -> Option<FeatureVector> {
+        let prev_close = self.closes.back().copied();
+        let ret_1 = match prev_close {
+            Some(p) if p > 0.0 && bar.close > 0.0 => (bar.close / p).ln(),
+            _ => 0.0,
+        };
+        let signed = bar.buy_volume - bar.sell_volume;
+        let sided = bar.buy_volume + bar.sell_volume;
+        let imbalance = if sided > 0.0 { signed / sided } else { 0.0 };
+
+        // Z-scores compare this bar against the bars before it
+        let volume_z = self.volumes.zscore(bar.volume);
+        let trades_z = self.trades.zscore(bar.trades as f64);
+        let warm = self.closes.len() >= LOOKBACK;
+
+        push_bounded(&mut self.closes, bar.close, LOOKBACK + 1);
+        let vol = if prev_close.is_some() {
+            self.returns.push(ret_1);
+            self.volatility.update(ret_1)
+        } else {
+            self.volatility.snapshot()
+        };
+        self.patterns.update(bar);
+        let surprise = self.anomaly.update(bar);
+        let cross = self.cross.update(bar, prev_close.map(|_| ret_1), bench);
+        self.volumes.push(bar.volume);
+        self.trades.push(bar.trades as f64);
+        self.imbalances.push(imbalance);
+        self.flow.push_back((signed, sided));
+        if self.flow.len() > LOOKBACK {
+            self.flow.pop_front();
+        }
+
+        if !warm {
+            return None;
+        }
+
+        let ret_n = |n: usize| -> f64 {
+            let past = self.closes[self.closes.len() - 1 - n];
+            if past > 0.0 && bar.close > 0.0 { (bar.close / past).ln() } else { 0.0 }
+        };
+        let range = if bar.close > 0.0 { (bar.high - bar.low) / bar.close } else { 0.0 };
+        // Order flow: volume-weighted imbalance over the window, trades per second, large prints
+        let (flow_signed, flow_sided) = self
+            .flow
+            .iter()
+            .fold((0.0, 0.0), |(a, b), (s, v)| (a + s, b + v));
+        let imbalance_20 = if flow_sided > 0.0 { flow_signed / flow_sided } else { 0.0 };
+        let secs = (bar.end() - bar.start) as f64 / 1000.0;
+        let arrival_rate = if secs > 0.0 { bar.trades as f64 / secs } else { 0.0 };
+        let large_trade = if bar.large_trades > 0 { 1.0 } else { 0.0 };
+        let large_imbalance = if bar.volume > 0.0 { bar.large_signed_volume / bar.volume } else { 0.0 };
+
+        // Distance of the close from the Kalman fair price
+        let fair_gap = match bar.fair_price {
+            Some(fair) if fair > 0.0 && bar.close > 0.0 => (bar.close / fair).ln(),
+            _ => 0.0,
+        };
+
+        let mut values = vec![
+            ret_1,
+            ret_n(5),
+            ret_n(LOOKBACK),
+            self.returns.std(),
+            range,
+            volume_z,
+            imbalance,
+            self.imbalances.mean(),
+            trades_z,
+            fair_gap,
+            vol.best().unwrap_or(0.0),
+            imbalance_20,
+            arrival_rate,
+            large_trade,
+            large_imbalance,
+        ];
+        values.extend(self.patterns.flags());
+        values.extend([surprise.return_z, surprise.volume_z]);
+        values.extend(cross);
+
+        Some(FeatureVector {
+            symbol: bar.symbol.clone(),
+            tf: bar.tf.clone(),
+            ts: bar.end(),
+            values,
+        })
+    }
+}
@@ -1,158 +1,211 @@
 use std::{
-    collections::HashMap,
-    env,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
+    collections::{HashMap, HashSet},
+    sync::Arc,
     time::Duration,
 };
 
 use chrono::{DateTime, Utc};
 use redis::AsyncCommands;
-use tokio::time::{sleep, timeout};
-use tokio_postgres::{Client as PgClient, NoTls, types::ToSql};
-
-use postgres_native_tls::MakeTlsConnector;
-use native_tls::TlsConnector;
+use tokio::time::{error::Elapsed, sleep, timeout};
+use tokio_postgres::{Client as PgClient, IsolationLevel, types::ToSql};
+use tokio_util::sync::CancellationToken;
+
+use tracing::{error, info, warn};
+use crate::{
+    cache,
+    error::{pg_conflict, pg_transient, StoreError},
+    config::{Config, Writes},
+    health::Health,
+    keys::{OHLCV_PREFIX, SYMBOLS_KEY, UNPERSISTED_KEY},
+    metrics::{Metrics, HOP_COVERED, HOP_PERSISTED},
+    precision::{self, Precision},
+    quality::{DqCounters, SKIPPED_INSERTS},
+    redis_conn,
+    shard::Shard,
+    status,
+    tls::{TlsMode, TlsSettings},
+};
 
-const FETCH_INTERVAL: Duration = Duration::from_secs(10);
 const REDIS_TIMEOUT: Duration = Duration::from_secs(3);
 const POSTGRES_TIMEOUT: Duration = Duration::from_secs(5);
 
-/// Auto-handle Postgres TLS for remote, NoTLS for local
-async fn connect_pg(pg_url: &str) -> PgClient {
-    let is_local = pg_url.contains("localhost") || pg_url.contains("127.0.0.1");
-
-    if is_local {
-        println!("🌐 Connecting to Postgres without TLS (local)...");
-        let (client, connection) = tokio_postgres::connect(pg_url, NoTls)
-            .await
-            .expect("❌ Local Postgres connection failed");
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("❌ Postgres connection error: {}", e);
-            }
-        });
-        return client;
-    }
-
-    println!("🔐 Connecting to Postgres with TLS...");
-    let tls_connector = TlsConnector::new().expect("❌ Failed to create TLS connector");
-    let tls = MakeTlsConnector::new(tls_connector);
-
-    match tokio_postgres::connect(pg_url, tls).await {
-        Ok((client, connection)) => {
-            tokio::spawn(async move {
-                if let Err(e) = connection.await {
-                    eprintln!("❌ Postgres connection error: {}", e);
-                }
-            });
-            println!("✅ Connected to Postgres (TLS)");
-            client
-        }
-        Err(e) => {
-            eprintln!("⚠️ TLS connection failed: {e}");
-            println!("🔓 Falling back to NoTLS...");
-            let (client, connection) = tokio_postgres::connect(pg_url, NoTls)
-                .await
-                .expect("❌ NoTLS Postgres connection failed");
-            tokio::spawn(async move {
-                if let Err(e) = connection.await {
-                    eprintln!("❌ Postgres connection error: {}", e);
-                }
-            });
-            client
+fn spawn_pg_connection<S, T>(connection: tokio_postgres::Connection<S, T>)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+    T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            error!("❌ Postgres connection error: {}", e);
         }
-    }
+    });
 }
 
-/// Auto-handle Redis TLS for remote, NoTLS for local
-async fn connect_redis(redis_url: &str) -> redis::aio::MultiplexedConnection {
-    let is_local = redis_url.contains("localhost") || redis_url.contains("127.0.0.1");
-
-    if is_local || redis_url.starts_with("redis://") {
-        println!("🌐 Connecting to Redis without TLS...");
-        let client = redis::Client::open(redis_url).expect("❌ Invalid Redis URL");
-        return client
-            .get_multiplexed_async_connection()
-            .await
-            .expect("❌ Redis NoTLS connection failed");
+/// Connect to Postgres under the TLS settings; a failed handshake is an error, never a
+/// plaintext retry
+pub async fn try_connect_pg(pg_url: &str, settings: &TlsSettings) -> Result<PgClient, StoreError> {
+    let (cfg, mode) = settings.postgres_config(pg_url)?;
+    match mode {
+        TlsMode::Disable => info!("🌐 Connecting to Postgres without TLS..."),
+        mode => info!("🔐 Connecting to Postgres with TLS ({mode})..."),
+    }
+    #[cfg(feature = "chaos")]
+    if let Some(client) = crate::chaos::connect_pg(&cfg, settings.postgres_connector()?).await {
+        return Ok(client?);
     }
+    let (client, connection) = cfg.connect(settings.postgres_connector()?).await?;
+    spawn_pg_connection(connection);
+    Ok(client)
+}
 
-    println!("🔐 Connecting to Redis with TLS...");
-    let client = redis::Client::open(redis_url).expect("❌ Invalid Redis URL");
+/// Sleep for `d`, or less if `stop` is cancelled meanwhile
+async fn pause(stop: &CancellationToken, d: Duration) {
+    tokio::select! {
+        _ = sleep(d) => {}
+        _ = stop.cancelled() => {}
+    }
+}
 
-    match client.get_multiplexed_async_connection().await {
-        Ok(conn) => {
-            println!("✅ Connected to Redis (TLS verified)");
-            conn
-        }
-        Err(e) => {
-            eprintln!("⚠️ TLS connection failed: {e}");
-            println!("🔓 Retrying Redis connection without TLS...");
-            let url_no_tls = redis_url.replacen("rediss://", "redis://", 1);
-            let client = redis::Client::open(url_no_tls).expect("❌ Invalid Redis URL");
-            client
-                .get_multiplexed_async_connection()
-                .await
-                .expect("❌ Redis NoTLS connection failed")
+/// Run `sql` in its own transaction at `isolation`, rerunning it with backoff when it loses
+/// to a concurrent writer (serialization failure or deadlock) up to `writes.retries` times.
+/// Each attempt has `POSTGRES_TIMEOUT`; one that runs out ends the insert with `Elapsed`.
+async fn insert(
+    pg: &mut PgClient,
+    sql: &str,
+    params: &[&(dyn ToSql + Sync)],
+    isolation: IsolationLevel,
+    writes: &Writes,
+) -> Result<Result<u64, tokio_postgres::Error>, Elapsed> {
+    let mut backoff = writes.retry_backoff();
+    let mut attempt = 0;
+    loop {
+        let result = timeout(POSTGRES_TIMEOUT, async {
+            let tx = pg.build_transaction().isolation_level(isolation).start().await?;
+            let n = tx.execute(sql, params).await?;
+            tx.commit().await?;
+            Ok(n)
+        })
+        .await?;
+        match result {
+            Err(e) if pg_conflict(&e) && attempt < writes.retries => {
+                attempt += 1;
+                warn!("🔁 Insert lost to a concurrent writer ({e}), retry {attempt}/{} in {backoff:?}", writes.retries);
+                sleep(backoff).await;
+                backoff *= 2;
+            }
+            result => return Ok(result),
         }
     }
 }
 
-pub async fn run(flag: Arc<AtomicBool>) {
-    println!("🚀 Fetcher started");
-    dotenv::dotenv().ok();
-
-    let redis_url = env::var("REDIS_URL").expect("❌ REDIS_URL not set");
-    let pg_url = env::var("DATABASE_URL").expect("❌ DATABASE_URL not set");
-
-    // Connect to Redis & Postgres with auto TLS/NoTLS logic
-    let mut redis = connect_redis(&redis_url).await;
-    let pg = connect_pg(&pg_url).await;
+/// Insert OHLCV snapshots until `stop` is cancelled, reporting each cycle as the `fetcher` check.
+/// A cycle in progress, insert included, finishes first. Each insert is one transaction at
+/// `writes.isolation`, rerun when it conflicts with another writer.
+/// Cycle failures that may recover are reported and retried; the rest end the run.
+/// With `precision.decimal` the history columns are made NUMERIC first and the snapshots'
+/// decimal strings inserted as they are.
+/// Inserted rows, and the trades the ingester folded into them, are counted as the
+/// `persisted` and `covered` hops of `flow_total`, labelled with the shard when sharded.
+pub async fn run(stop: CancellationToken, health: Arc<Health>, config: Arc<Config>) -> Result<(), StoreError> {
+    info!("🚀 Fetcher started");
+    let fetch_interval = config.intervals.fetch();
+
+    // Connect to Redis & Postgres under the configured TLS settings
+    let mut redis = redis_conn::try_connect(&config.redis_url(), &config.tls.redis).await?;
+    let mut pg = try_connect_pg(&config.database_url(), &config.tls.postgres).await?;
+    // Validated with the rest of the config
+    let isolation = config.writes.isolation_level().unwrap_or(IsolationLevel::ReadCommitted);
+    info!("🔒 Inserting at {} isolation, up to {} retries on conflict", config.writes.isolation, config.writes.retries);
+    let precision = Precision::from_config(&config.precision);
+    if precision.is_decimal() && precision::ensure_numeric(&mut pg).await? {
+        info!("🔢 Converted stock_price_history prices and volumes to NUMERIC");
+    }
 
     // Preload symbol -> id map from DB
-    println!("📥 Loading stock symbol map from DB...");
-    let rows = pg
-        .query("SELECT id, symbol FROM stocks", &[])
-        .await
-        .expect("❌ Failed to load stock map");
-    println!("✅ Loaded {} stock symbols from DB", rows.len());
-
-    let id_map: HashMap<String, i32> =
+    info!("📥 Loading stock symbol map from DB...");
+    let rows = pg.query("SELECT id, symbol FROM stocks", &[]).await?;
+    info!("✅ Loaded {} stock symbols from DB", rows.len());
+
+    let mut id_map: HashMap<String, i32> =
         rows.into_iter().map(|r| (r.get::<_, String>(1), r.get::<_, i32>(0))).collect();
 
-    const SYMBOLS_KEY: &str = "stock:symbols";
-    const OHLCV_PREFIX: &str = "stock:ohlcv:";
+    let mut dq = DqCounters::from_env();
+
+    // Other fetchers may write part of the symbols
+    let mut shard = Shard::from_config(&config.sharding);
+    if let Some(shard) = &shard {
+        info!("🧩 Writing the symbols of {}", shard.describe());
+    }
+
+    // Shards share the `fetcher` metrics hash, each under its own label
+    let mut metrics = Metrics::new("fetcher");
+    let flow_labels = shard.as_ref().map_or(String::new(), |s| format!("fetcher=\"{}\"", s.member()));
+    // Each symbol's OHLCV `trades` at its last insert
+    let mut trades_seen: HashMap<String, u64> = HashMap::new();
 
-    while flag.load(Ordering::Relaxed) {
+    while !stop.is_cancelled() {
         // 1) Get symbols from Redis
         let symbols: Vec<String> = match timeout(REDIS_TIMEOUT, redis.smembers::<_, Vec<String>>(SYMBOLS_KEY)).await {
             Ok(Ok(v)) => {
                 if v.is_empty() {
-                    println!("⚠️ No symbols found in Redis — skipping insert this cycle.");
+                    warn!("⚠️ No symbols found in Redis — skipping insert this cycle.");
                 }
                 v
             }
             Ok(Err(e)) => {
-                eprintln!("❌ Redis smembers error: {e}");
-                sleep(Duration::from_secs(1)).await;
+                error!("❌ Redis smembers error: {e}");
+                health.fail("fetcher", format!("redis smembers: {e}"));
+                pause(&stop, Duration::from_secs(1)).await;
                 continue;
             }
             Err(_) => {
-                eprintln!("⏱️ Redis smembers timed out");
-                sleep(Duration::from_secs(1)).await;
+                warn!("⏱️ Redis smembers timed out");
+                health.fail("fetcher", "redis smembers timed out");
+                pause(&stop, Duration::from_secs(1)).await;
                 continue;
             }
         };
 
+        // Symbols the symbols file marked `persist = false` stay out of Postgres
+        let symbols = match timeout(REDIS_TIMEOUT, redis.smembers::<_, HashSet<String>>(UNPERSISTED_KEY)).await {
+            Ok(Ok(skip)) if !skip.is_empty() => symbols.into_iter().filter(|s| !skip.contains(s)).collect(),
+            Ok(Err(e)) => {
+                warn!("⚠️ Could not read unpersisted symbols, writing all: {e}");
+                symbols
+            }
+            _ => symbols,
+        };
+
+        // Only this fetcher's share; on a failed refresh the last known members stand
+        let symbols = match &mut shard {
+            Some(shard) => {
+                match timeout(REDIS_TIMEOUT, shard.refresh(&mut redis)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => warn!("⚠️ Could not refresh fetcher membership: {e}"),
+                    Err(_) => warn!("⏱️ Fetcher membership refresh timed out"),
+                }
+                symbols.into_iter().filter(|s| shard.owns(s)).collect()
+            }
+            None => symbols,
+        };
+
         // 2) Fetch OHLCV for all symbols
         if symbols.is_empty() {
-            sleep(FETCH_INTERVAL).await;
+            health.ok("fetcher");
+            pause(&stop, fetch_interval).await;
             continue;
         }
 
+        // Symbols added since startup (e.g. via POST /symbols) need their ids
+        if symbols.iter().any(|s| !id_map.contains_key(s)) {
+            match pg.query("SELECT id, symbol FROM stocks", &[]).await {
+                Ok(rows) => {
+                    id_map = rows.into_iter().map(|r| (r.get::<_, String>(1), r.get::<_, i32>(0))).collect();
+                }
+                Err(e) => error!("❌ Failed to reload stock map: {e}"),
+            }
+        }
+
         let mut pipe = redis::pipe();
         for s in &symbols {
             pipe.hgetall(format!("{OHLCV_PREFIX}{s}"));
@@ -161,13 +214,15 @@ pub async fn run(flag: Arc<AtomicBool>) {
             match timeout(REDIS_TIMEOUT, pipe.query_async(&mut redis)).await {
                 Ok(Ok(v)) => v,
                 Ok(Err(e)) => {
-                    eprintln!("❌ Redis pipeline error: {e}");
-                    sleep(Duration::from_secs(1)).await;
+                    error!("❌ Redis pipeline error: {e}");
+                    health.fail("fetcher", format!("redis pipeline: {e}"));
+                    pause(&stop, Duration::from_secs(1)).await;
                     continue;
                 }
                 Err(_) => {
-                    eprintln!("⏱️ Redis pipeline timed out");
-                    sleep(Duration::from_secs(1)).await;
+                    warn!("⏱️ Redis pipeline timed out");
+                    health.fail("fetcher", "redis pipeline timed out");
+                    pause(&stop, Duration::from_secs(1)).await;
                     continue;
                 }
             };
@@ -179,15 +234,20 @@ pub async fn run(flag: Arc<AtomicBool>) {
         let mut skipped_empty = 0;
         let mut skipped_missing_id = 0;
         let mut skipped_incomplete = 0;
+        let mut inserted: Vec<&str> = Vec::new();
+        let mut covered: Vec<(&str, u64)> = Vec::new();
 
         for (sym, map) in symbols.iter().zip(rows) {
             if map.is_empty() {
                 skipped_empty += 1;
+                dq.add(sym, SKIPPED_INSERTS, 1);
                 continue;
             }
 
-            let num = |k: &str| map.get(k).and_then(|s| s.parse::<f64>().ok());
-            let (o, h, l, c, v) = (num("open"), num("high"), num("low"), num("close"), num("volume"));
+            let num = |k: &str| map.get(k).and_then(|s| precision.param(s));
+            // The running volume total, which readers difference; older ingesters kept it in `volume`
+            let v = num("volume_total").or_else(|| num("volume"));
+            let (o, h, l, c) = (num("open"), num("high"), num("low"), num("close"));
             let ts = map
                 .get("updated_at")
                 .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
@@ -197,6 +257,7 @@ pub async fn run(flag: Arc<AtomicBool>) {
                 (Some(o), Some(h), Some(l), Some(c), Some(v), Some(ts)) => (o, h, l, c, v, ts),
                 _ => {
                     skipped_incomplete += 1;
+                    dq.add(sym, SKIPPED_INSERTS, 1);
                     continue;
                 }
             };
@@ -205,39 +266,42 @@ pub async fn run(flag: Arc<AtomicBool>) {
                 Some(&id) => id,
                 None => {
                     skipped_missing_id += 1;
+                    dq.add(sym, SKIPPED_INSERTS, 1);
                     continue;
                 }
             };
 
+            let t = precision.sql_type();
             placeholders.push(format!(
-                "(${}, ${}, ${}, ${}, ${}, ${}, ${}, ${})",
+                "(${}, ${}, ${}::{t}, ${}::{t}, ${}::{t}, ${}::{t}, ${}::{t}, ${})",
                 i, i + 1, i + 2, i + 3, i + 4, i + 5, i + 6, i + 7
             ));
             i += 8;
 
             values.push(Box::new(stock_id));
             values.push(Box::new(sym.clone()));
-            values.push(Box::new(o));
-            values.push(Box::new(h));
-            values.push(Box::new(l));
-            values.push(Box::new(c));
-            values.push(Box::new(v));
+            values.extend([o, h, l, c, v]);
             values.push(Box::new(ts));
+            inserted.push(sym);
+            if let Some(trades) = map.get("trades").and_then(|s| s.parse().ok()) {
+                covered.push((sym, trades));
+            }
         }
 
         if skipped_empty > 0 {
-            println!("⚠️ Skipped {} symbols with empty OHLCV", skipped_empty);
+            warn!("⚠️ Skipped {} symbols with empty OHLCV", skipped_empty);
         }
         if skipped_incomplete > 0 {
-            println!("⚠️ Skipped {} symbols with incomplete OHLCV", skipped_incomplete);
+            warn!("⚠️ Skipped {} symbols with incomplete OHLCV", skipped_incomplete);
         }
         if skipped_missing_id > 0 {
-            println!("⚠️ Skipped {} symbols not found in DB", skipped_missing_id);
+            warn!("⚠️ Skipped {} symbols not found in DB", skipped_missing_id);
         }
 
         // 4) Insert into DB
         if placeholders.is_empty() {
-            println!("ℹ️ No valid rows to insert this cycle.");
+            info!("ℹ️ No valid rows to insert this cycle.");
+            health.ok("fetcher");
         } else {
             let sql = format!(
                 "INSERT INTO stock_price_history \
@@ -249,15 +313,65 @@ pub async fn run(flag: Arc<AtomicBool>) {
             let params: Vec<&(dyn ToSql + Sync)> =
                 values.iter().map(|v| v.as_ref() as &(dyn ToSql + Sync)).collect();
 
-            match timeout(POSTGRES_TIMEOUT, pg.execute(&sql, &params)).await {
-                Ok(Ok(n)) => println!("✅ Inserted {} rows at {}", n, Utc::now().format("%H:%M:%S")),
-                Ok(Err(e)) => eprintln!("❌ Postgres insert error: {e}"),
-                Err(_) => eprintln!("⏱️ Postgres insert timed out"),
+            match insert(&mut pg, &sql, &params, isolation, &config.writes).await {
+                Ok(Ok(n)) => {
+                    info!("✅ Inserted {} rows at {}", n, Utc::now().format("%H:%M:%S"));
+                    health.ok("fetcher");
+                    metrics.count_hop(HOP_PERSISTED, &flow_labels, n);
+                    // A count below the last one means the ingester started the symbol over
+                    for (sym, trades) in covered {
+                        let last = trades_seen.insert(sym.to_string(), trades).unwrap_or(0);
+                        metrics.count_hop(HOP_COVERED, &flow_labels, if trades >= last { trades - last } else { trades });
+                    }
+                    let fields = [("last_insert_at", Utc::now().to_rfc3339()), ("rows", n.to_string())];
+                    if let Err(e) = status::record(&mut redis, "fetcher", &fields).await {
+                        warn!("⚠️ Could not record fetcher status: {e}");
+                    }
+                    if let Err(e) = cache::invalidate(&mut redis, &inserted).await {
+                        warn!("⚠️ Could not invalidate cached history: {e}");
+                    }
+                }
+                Ok(Err(e)) => {
+                    error!("❌ Postgres insert error: {e}");
+                    for sym in &inserted {
+                        dq.add(sym, SKIPPED_INSERTS, 1);
+                    }
+                    health.fail("fetcher", format!("postgres insert: {e}"));
+                    // A closed client never recovers; the trigger restarts us with a new one
+                    if !pg_transient(&e) || pg.is_closed() {
+                        return Err(e.into());
+                    }
+                }
+                Err(_) => {
+                    warn!("⏱️ Postgres insert timed out");
+                    for sym in &inserted {
+                        dq.add(sym, SKIPPED_INSERTS, 1);
+                    }
+                    health.fail("fetcher", "postgres insert timed out");
+                }
             }
         }
 
-        sleep(FETCH_INTERVAL).await;
+        if let Err(e) = dq.flush_if_due(&mut redis).await {
+            warn!("⚠️ Could not record data-quality counters: {e}");
+        }
+        if let Err(e) = metrics.flush_if_due(&mut redis).await {
+            warn!("⚠️ Could not record fetcher metrics: {e}");
+        }
+        pause(&stop, fetch_interval).await;
     }
 
-    println!("🧹 Fetcher stopped");
+    if let Err(e) = dq.flush(&mut redis).await {
+        warn!("⚠️ Could not record data-quality counters: {e}");
+    }
+    if let Err(e) = metrics.flush(&mut redis).await {
+        warn!("⚠️ Could not record fetcher metrics: {e}");
+    }
+    if let Some(shard) = &shard
+        && let Err(e) = shard.leave(&mut redis).await
+    {
+        warn!("⚠️ Could not leave the fetcher set: {e}");
+    }
+    info!("🧹 Fetcher stopped");
+    Ok(())
 }
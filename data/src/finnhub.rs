@@ -0,0 +1,159 @@
+use std::{env, time::Duration};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+use tokio::time::{sleep, Instant};
+
+const BASE_URL: &str = "https://finnhub.io/api/v1";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Free tier allows 60 calls/minute
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(1100);
+
+/// One exchange candle, timestamped at the start of its interval (UTC)
+#[derive(Debug, Clone)]
+pub struct Candle {
+    pub time: NaiveDateTime,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct CandleResponse {
+    s: String,
+    #[serde(default)]
+    t: Vec<i64>,
+    #[serde(default)]
+    o: Vec<f64>,
+    #[serde(default)]
+    h: Vec<f64>,
+    #[serde(default)]
+    l: Vec<f64>,
+    #[serde(default)]
+    c: Vec<f64>,
+    #[serde(default)]
+    v: Vec<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SymbolResponse {
+    symbol: String,
+}
+
+/// Paced Finnhub REST client
+pub struct FinnhubClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    last_request: Option<Instant>,
+}
+
+impl FinnhubClient {
+    pub fn new(api_key: String) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("❌ Failed to build HTTP client");
+        Self {
+            http,
+            base_url: env::var("FINNHUB_API_URL").unwrap_or_else(|_| BASE_URL.to_string()),
+            api_key,
+            last_request: None,
+        }
+    }
+
+    /// Keep requests under the rate limit
+    async fn pace(&mut self) {
+        if let Some(last) = self.last_request {
+            let elapsed = last.elapsed();
+            if elapsed < MIN_REQUEST_INTERVAL {
+                sleep(MIN_REQUEST_INTERVAL - elapsed).await;
+            }
+        }
+        self.last_request = Some(Instant::now());
+    }
+
+    /// Fetch crypto candles for `[from, to]`; `resolution` is Finnhub's ("1", "5", "60", "D"...)
+    pub async fn crypto_candles(
+        &mut self,
+        symbol: &str,
+        resolution: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<Candle>, String> {
+        self.pace().await;
+
+        let resp = self
+            .http
+            .get(format!("{}/crypto/candle", self.base_url))
+            .query(&[
+                ("symbol", symbol),
+                ("resolution", resolution),
+                ("from", &from.timestamp().to_string()),
+                ("to", &to.timestamp().to_string()),
+                ("token", &self.api_key),
+            ])
+            .send()
+            .await
+            .map_err(|e| format!("candle request for {symbol} failed: {e}"))?;
+
+        if !resp.status().is_success() {
+            return Err(format!("candle request for {symbol} returned {}", resp.status()));
+        }
+
+        let body: CandleResponse = resp
+            .json()
+            .await
+            .map_err(|e| format!("invalid candle response for {symbol}: {e}"))?;
+
+        if body.s == "no_data" {
+            return Ok(Vec::new());
+        }
+        if body.s != "ok" {
+            return Err(format!("candle request for {symbol} returned status '{}'", body.s));
+        }
+
+        let candles = body
+            .t
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &t)| {
+                Some(Candle {
+                    time: DateTime::from_timestamp(t, 0)?.naive_utc(),
+                    open: *body.o.get(i)?,
+                    high: *body.h.get(i)?,
+                    low: *body.l.get(i)?,
+                    close: *body.c.get(i)?,
+                    volume: *body.v.get(i)?,
+                })
+            })
+            .collect();
+
+        Ok(candles)
+    }
+
+    /// Every crypto symbol Finnhub lists for `exchange` (e.g. `BINANCE`), as `EXCHANGE:PAIR`
+    pub async fn crypto_symbols(&mut self, exchange: &str) -> Result<Vec<String>, String> {
+        self.pace().await;
+
+        let resp = self
+            .http
+            .get(format!("{}/crypto/symbol", self.base_url))
+            .query(&[("exchange", exchange), ("token", &self.api_key)])
+            .send()
+            .await
+            .map_err(|e| format!("symbol list request for {exchange} failed: {e}"))?;
+
+        if !resp.status().is_success() {
+            return Err(format!("symbol list request for {exchange} returned {}", resp.status()));
+        }
+
+        let body: Vec<SymbolResponse> = resp
+            .json()
+            .await
+            .map_err(|e| format!("invalid symbol list for {exchange}: {e}"))?;
+        Ok(body.into_iter().map(|s| s.symbol).collect())
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use async_graphql::{
+    http::GraphiQLSource, Context, EmptyMutation, EmptySubscription, Error, Object, Result, Schema,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::{
+    response::{Html, IntoResponse},
+    routing::get,
+    Extension, Router,
+};
+use redis::AsyncCommands;
+
+use crate::{
+    api::{self, AppState, Ohlcv, Price},
+    auth::{Permission, Principal},
+    bars::{Bar, Timeframe},
+    history::{self, Candle, DEFAULT_HISTORY_LIMIT},
+    importance::FeatureImportance,
+    keys::{BAR_PREFIX, IMPORTANCE_PREFIX, PREDICTION_PREFIX, SIGNAL_PREFIX, SYMBOLS_KEY},
+    predictions::Prediction,
+    redis_conn::RedisConn,
+    signals::Signal,
+};
+
+/// Nesting deeper than this is rejected before any resolver runs
+const MAX_DEPTH: usize = 8;
+
+pub type ApiSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn schema(state: AppState) -> ApiSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+/// `POST /graphql` for queries, `GET /graphql` for the GraphiQL explorer. Expects the
+/// auth guard to have inserted a `Principal`, which resolvers check per field.
+pub fn mount(router: Router<AppState>, state: AppState) -> Router<AppState> {
+    let schema = schema(state);
+    let execute = move |Extension(principal): Extension<Principal>, req: GraphQLRequest| async move {
+        GraphQLResponse::from(schema.execute(req.into_inner().data(principal)).await)
+    };
+    router.route("/graphql", get(graphiql).post(execute))
+}
+
+async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+fn require(ctx: &Context<'_>, permission: Permission) -> Result<()> {
+    let principal = ctx.data::<Principal>()?;
+    if principal.allows(permission) {
+        Ok(())
+    } else {
+        Err(Error::new(format!("'{}' lacks the {permission} permission", principal.name)))
+    }
+}
+
+fn redis(ctx: &Context<'_>) -> RedisConn {
+    ctx.data_unchecked::<AppState>().redis.clone()
+}
+
+async fn hash(ctx: &Context<'_>, key: String) -> Result<HashMap<String, String>> {
+    Ok(redis(ctx).hgetall(key).await?)
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Every tracked symbol
+    async fn symbols(&self, ctx: &Context<'_>) -> Result<Vec<Symbol>> {
+        require(ctx, Permission::ReadPrices)?;
+        let mut symbols: Vec<String> = redis(ctx).smembers(SYMBOLS_KEY).await?;
+        symbols.sort();
+        Ok(symbols.into_iter().map(|symbol| Symbol { symbol }).collect())
+    }
+
+    /// One symbol, tracked or not
+    async fn symbol(&self, symbol: String) -> Symbol {
+        Symbol { symbol }
+    }
+}
+
+/// Everything served for one symbol; each field is fetched only when selected
+pub struct Symbol {
+    symbol: String,
+}
+
+#[Object]
+impl Symbol {
+    async fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Last trade
+    async fn price(&self, ctx: &Context<'_>) -> Result<Option<Price>> {
+        require(ctx, Permission::ReadPrices)?;
+        Ok(api::latest_price(&mut redis(ctx), &self.symbol).await?)
+    }
+
+    /// OHLCV of the ingester's current base-timeframe bar
+    async fn ohlcv(&self, ctx: &Context<'_>) -> Result<Option<Ohlcv>> {
+        require(ctx, Permission::ReadPrices)?;
+        Ok(api::latest_ohlcv(&mut redis(ctx), &self.symbol).await?)
+    }
+
+    /// Last closed bar of `tf`
+    async fn bar(&self, ctx: &Context<'_>, #[graphql(default = "1m")] tf: String) -> Result<Option<Bar>> {
+        require(ctx, Permission::ReadPrices)?;
+        let tf = Timeframe::parse(&tf)
+            .ok_or_else(|| Error::new(format!("invalid timeframe '{tf}'")))?
+            .to_string();
+        let fields = hash(ctx, format!("{BAR_PREFIX}{}:{tf}", self.symbol)).await?;
+        Ok(Bar::from_fields(&self.symbol, &tf, &fields))
+    }
+
+    /// Candles resampled from Postgres, oldest first; same bounds as `/history`
+    async fn history(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = "1m")] tf: String,
+        from: Option<String>,
+        to: Option<String>,
+        #[graphql(default_with = "DEFAULT_HISTORY_LIMIT")] limit: i64,
+    ) -> Result<Vec<Candle>> {
+        require(ctx, Permission::ReadPrices)?;
+        let state = ctx.data_unchecked::<AppState>();
+        let pg = state.pg.as_ref().ok_or_else(|| Error::new("history needs DATABASE_URL"))?;
+        let tf = Timeframe::parse(&tf).ok_or_else(|| Error::new(format!("invalid timeframe '{tf}'")))?;
+        let (start, end) = history::window(tf, from.as_deref(), to.as_deref(), limit)?;
+        let query = || history::candles(pg, &self.symbol, tf, start, end, limit);
+        let page = match state.cache {
+            Some(cache) => {
+                let params = history::cache_params(tf, from.as_deref(), to.as_deref(), limit);
+                cache.get_or_compute(&mut redis(ctx), "history", &self.symbol, &params, query).await?
+            }
+            None => query().await?,
+        };
+        Ok(page.candles)
+    }
+
+    /// Latest model prediction
+    async fn prediction(&self, ctx: &Context<'_>) -> Result<Option<Prediction>> {
+        require(ctx, Permission::ReadPredictions)?;
+        let fields = hash(ctx, format!("{PREDICTION_PREFIX}{}", self.symbol)).await?;
+        Ok(Prediction::from_fields(&self.symbol, &fields))
+    }
+
+    /// Current trading signal
+    async fn signal(&self, ctx: &Context<'_>) -> Result<Option<Signal>> {
+        require(ctx, Permission::ReadPredictions)?;
+        let fields = hash(ctx, format!("{SIGNAL_PREFIX}{}", self.symbol)).await?;
+        Ok(Signal::from_fields(&self.symbol, &fields))
+    }
+
+    /// Feature importance of the live model
+    async fn importance(&self, ctx: &Context<'_>) -> Result<Option<FeatureImportance>> {
+        require(ctx, Permission::ReadPredictions)?;
+        let fields = hash(ctx, format!("{IMPORTANCE_PREFIX}{}", self.symbol)).await?;
+        Ok(FeatureImportance::from_fields(&self.symbol, &fields))
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    collections::BTreeMap,
+    env, fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use redis::AsyncCommands;
+use serde::Serialize;
+use tokio::{net::TcpListener, time::timeout};
+use tokio_postgres::Client as PgClient;
+use tracing::{error, info, warn};
+
+use crate::{
+    clock::{self, SharedClock},
+    keys::HEALTH_PREFIX,
+    redis_conn::RedisConn,
+};
+
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const REPORT_TTL_SECS: u64 = 3 * PROBE_INTERVAL.as_secs();
+/// A check failing or stale for this long fails liveness too, so the process gets restarted
+const DEFAULT_LIVENESS_GRACE: Duration = Duration::from_secs(300);
+
+#[derive(Debug)]
+struct Check {
+    ok: bool,
+    error: Option<String>,
+    updated: Instant,
+    /// When the check last went from healthy to failing
+    failing_since: Option<Instant>,
+    /// Fails once nothing has been reported for this long
+    max_age: Option<Duration>,
+}
+
+impl Check {
+    /// When it started failing, counting staleness as a failure
+    fn failing_since(&self, now: Instant) -> Option<Instant> {
+        let stale_at = self.max_age.map(|age| self.updated + age).filter(|at| *at <= now);
+        match (self.failing_since, stale_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CheckReport {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Since the last report
+    pub age_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failing_for_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HealthReport {
+    pub service: &'static str,
+    pub status: &'static str,
+    pub uptime_secs: u64,
+    pub checks: BTreeMap<&'static str, CheckReport>,
+}
+
+/// Dependency status reported by one binary: readiness needs every check healthy,
+/// liveness only fails once one has been unhealthy for longer than the grace period
+#[derive(Debug)]
+pub struct Health {
+    service: &'static str,
+    clock: SharedClock,
+    started: Instant,
+    liveness_grace: Duration,
+    checks: Mutex<BTreeMap<&'static str, Check>>,
+}
+
+impl Health {
+    /// `HEALTH_LIVENESS_GRACE_SECS` overrides the grace period
+    pub fn new(service: &'static str) -> Arc<Self> {
+        Self::with_clock(service, clock::system())
+    }
+
+    /// Ages and grace periods measured on `clock`
+    pub fn with_clock(service: &'static str, clock: SharedClock) -> Arc<Self> {
+        let liveness_grace = env::var("HEALTH_LIVENESS_GRACE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_LIVENESS_GRACE);
+        Arc::new(Self {
+            service,
+            started: clock.instant(),
+            clock,
+            liveness_grace,
+            checks: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    fn update(&self, name: &'static str, error: Option<String>) {
+        let now = self.clock.instant();
+        let mut checks = self.checks.lock().unwrap_or_else(|e| e.into_inner());
+        let check = checks.entry(name).or_insert(Check {
+            ok: false,
+            error: None,
+            updated: now,
+            failing_since: Some(now),
+            max_age: None,
+        });
+        check.failing_since = match (&error, check.failing_since(now)) {
+            (None, _) => None,
+            (Some(_), since) => Some(since.unwrap_or(now)),
+        };
+        check.ok = error.is_none();
+        check.error = error;
+        check.updated = now;
+    }
+
+    /// Register `name` as not yet checked, failing when unreported for `max_age`
+    pub fn expect(&self, name: &'static str, max_age: Option<Duration>) {
+        self.update(name, Some("not checked yet".to_string()));
+        if let Some(check) = self.checks.lock().unwrap_or_else(|e| e.into_inner()).get_mut(name) {
+            check.max_age = max_age;
+        }
+    }
+
+    pub fn ok(&self, name: &'static str) {
+        self.update(name, None);
+    }
+
+    pub fn fail(&self, name: &'static str, error: impl fmt::Display) {
+        self.update(name, Some(error.to_string()));
+    }
+
+    /// Stop tracking `name`, e.g. while it is deliberately paused
+    pub fn clear(&self, name: &'static str) {
+        self.checks.lock().unwrap_or_else(|e| e.into_inner()).remove(name);
+    }
+
+    /// Current state; `ready` and `live` as described on the type
+    pub fn report(&self) -> (HealthReport, bool, bool) {
+        let now = self.clock.instant();
+        let checks = self.checks.lock().unwrap_or_else(|e| e.into_inner());
+        let mut ready = true;
+        let mut live = true;
+        let checks = checks
+            .iter()
+            .map(|(name, c)| {
+                let failing_since = c.failing_since(now);
+                let failing_for = failing_since.map(|t| now.duration_since(t));
+                ready &= failing_for.is_none();
+                live &= failing_for.is_none_or(|d| d < self.liveness_grace);
+                let error = match (&c.error, failing_since) {
+                    (Some(e), _) => Some(e.clone()),
+                    (None, Some(_)) => Some(format!("no report for {}s", now.duration_since(c.updated).as_secs())),
+                    (None, None) => None,
+                };
+                let report = CheckReport {
+                    ok: failing_for.is_none(),
+                    error,
+                    age_ms: now.duration_since(c.updated).as_millis() as u64,
+                    failing_for_ms: failing_for.map(|d| d.as_millis() as u64),
+                };
+                (*name, report)
+            })
+            .collect();
+        let report = HealthReport {
+            service: self.service,
+            status: if ready { "ok" } else if live { "degraded" } else { "down" },
+            uptime_secs: now.duration_since(self.started).as_secs(),
+            checks,
+        };
+        (report, ready, live)
+    }
+}
+
+fn respond(report: HealthReport, healthy: bool) -> (StatusCode, Json<HealthReport>) {
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(report))
+}
+
+/// Liveness: 503 once a check has failed for longer than the grace period
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/healthz", tag = "operations",
+    responses((status = 200, body = HealthReport), (status = 503, body = HealthReport))
+))]
+async fn healthz(State(health): State<Arc<Health>>) -> (StatusCode, Json<HealthReport>) {
+    let (report, _, live) = health.report();
+    respond(report, live)
+}
+
+/// Readiness: 503 while any check is failing
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get, path = "/readyz", tag = "operations",
+    responses((status = 200, body = HealthReport), (status = 503, body = HealthReport))
+))]
+async fn readyz(State(health): State<Arc<Health>>) -> (StatusCode, Json<HealthReport>) {
+    let (report, ready, _) = health.report();
+    respond(report, ready)
+}
+
+/// `GET /healthz` (liveness) and `GET /readyz` (readiness)
+pub fn routes<S>(health: Arc<Health>) -> Router<S> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(health)
+}
+
+/// Serve `routes` on `HEALTH_ADDR`; a no-op when it is not set
+pub fn spawn_server(health: Arc<Health>) {
+    let Ok(addr) = env::var("HEALTH_ADDR") else {
+        return;
+    };
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(&addr).await {
+            Ok(l) => l,
+            Err(e) => {
+                error!("❌ Cannot bind health server on {addr}: {e}");
+                return;
+            }
+        };
+        info!("🩺 Health checks on http://{addr}/healthz and /readyz");
+        if let Err(e) = axum::serve(listener, routes(health)).await {
+            error!("❌ Health server error: {e}");
+        }
+    });
+}
+
+/// Ping Redis over the shared connection and Postgres every few seconds, reporting
+/// them as the `redis` and `postgres` checks, and publish the result under `HEALTH_PREFIX`
+pub async fn probe(health: Arc<Health>, mut redis: Option<RedisConn>, pg: Option<Arc<PgClient>>) {
+    if redis.is_some() {
+        health.expect("redis", None);
+    }
+    if pg.is_some() {
+        health.expect("postgres", None);
+    }
+    loop {
+        if let Some(c) = redis.as_mut() {
+            match timeout(PROBE_TIMEOUT, redis::cmd("PING").query_async::<String>(c)).await {
+                Ok(Ok(_)) => health.ok("redis"),
+                Ok(Err(e)) => health.fail("redis", e),
+                Err(_) => health.fail("redis", "PING timed out"),
+            }
+        }
+        if let Some(pg) = &pg {
+            match timeout(PROBE_TIMEOUT, pg.simple_query("SELECT 1")).await {
+                Ok(Ok(_)) => health.ok("postgres"),
+                Ok(Err(e)) => health.fail("postgres", e),
+                Err(_) => health.fail("postgres", "SELECT 1 timed out"),
+            }
+        }
+        if let Some(c) = redis.as_mut() {
+            let (report, _, _) = health.report();
+            let key = format!("{HEALTH_PREFIX}{}", health.service);
+            let json = serde_json::to_string(&report).unwrap_or_default();
+            if let Err(e) = c.set_ex::<_, _, ()>(key, json, REPORT_TTL_SECS).await {
+                warn!("⚠️ Could not publish health report: {e}");
+            }
+        }
+        tokio::time::sleep(PROBE_INTERVAL).await;
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use tokio::time::{interval, Interval};
+use tracing::warn;
+
+use crate::{keys::HEARTBEAT_PREFIX, metrics::now_ms, redis_conn::RedisConn};
+
+
+const DEFAULT_HEARTBEAT_SECS: u64 = 10;
+/// Beats older than this many intervals mean the work loop is stuck
+const STALE_INTERVALS: u64 = 3;
+/// Kept well past staleness so the watchdog can tell hung from gone
+const KEY_TTL_INTERVALS: u64 = 30;
+
+/// What a heartbeat says; `beat_ms` only moves when the work loop does, `written_ms`
+/// whenever the process is alive enough to write
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HeartbeatReport {
+    pub service: String,
+    pub version: String,
+    pub pid: u32,
+    /// e.g. `streaming`, `reconnecting`, `maintenance`
+    pub state: String,
+    /// Last beat from the work loop, ms since epoch
+    pub beat_ms: i64,
+    /// A deliberate wait announced with the beat, added to the staleness allowance
+    #[serde(default)]
+    pub pause_ms: i64,
+    /// When this report was written, ms since epoch
+    pub written_ms: i64,
+    pub started_ms: i64,
+    /// How often it is written, so readers know when it is stale
+    pub interval_secs: u64,
+}
+
+impl HeartbeatReport {
+    fn stale_after_ms(&self) -> i64 {
+        (STALE_INTERVALS * self.interval_secs * 1000) as i64
+    }
+
+    /// The work loop has not beaten for `STALE_INTERVALS` intervals
+    pub fn is_stale(&self, now_ms: i64) -> bool {
+        now_ms - self.beat_ms > self.stale_after_ms() + self.pause_ms
+    }
+
+    /// Nothing was written either: the process exited or is blocked outright
+    pub fn is_gone(&self, now_ms: i64) -> bool {
+        now_ms - self.written_ms > self.stale_after_ms()
+    }
+}
+
+struct Beat {
+    at_ms: i64,
+    pause_ms: i64,
+    state: String,
+}
+
+/// One binary's heartbeat. The work loop calls [`beat`](Self::beat); a background task writes
+/// the latest beat to Redis and, with `HEARTBEAT_DIR`, to `{dir}/{service}.heartbeat.json`.
+pub struct Heartbeat {
+    service: &'static str,
+    every: Duration,
+    started_ms: i64,
+    last: Mutex<Beat>,
+}
+
+impl Heartbeat {
+    /// Start writing every `HEARTBEAT_SECS` (default 10)
+    pub fn spawn(service: &'static str, redis: RedisConn) -> Arc<Self> {
+        let secs = env::var("HEARTBEAT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(DEFAULT_HEARTBEAT_SECS);
+        let dir = env::var("HEARTBEAT_DIR").ok().map(PathBuf::from);
+        let now = now_ms();
+        let heartbeat = Arc::new(Self {
+            service,
+            every: Duration::from_secs(secs),
+            started_ms: now,
+            last: Mutex::new(Beat {
+                at_ms: now,
+                pause_ms: 0,
+                state: "starting".to_string(),
+            }),
+        });
+        tokio::spawn(write_loop(heartbeat.clone(), redis, dir));
+        heartbeat
+    }
+
+    /// Ticks at the write rate, for `select!` loops that otherwise only wake on input
+    pub fn ticker(&self) -> Interval {
+        interval(self.every)
+    }
+
+    /// For servers without a work loop of their own: beat in `state` while the runtime
+    /// still schedules tasks
+    pub fn keep_beating(self: &Arc<Self>, state: &'static str) {
+        let heartbeat = self.clone();
+        tokio::spawn(async move {
+            let mut tick = heartbeat.ticker();
+            loop {
+                tick.tick().await;
+                heartbeat.beat(state);
+            }
+        });
+    }
+
+    /// The work loop is making progress, currently in `state`
+    pub fn beat(&self, state: &str) {
+        self.pause(state, Duration::ZERO);
+    }
+
+    /// Beat before a deliberate wait of up to `wait` (a backoff, a long poll), so it
+    /// does not read as stuck
+    pub fn pause(&self, state: &str, wait: Duration) {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        last.at_ms = now_ms();
+        last.pause_ms = wait.as_millis() as i64;
+        if last.state != state {
+            last.state = state.to_string();
+        }
+    }
+
+    fn report(&self) -> HeartbeatReport {
+        let last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        HeartbeatReport {
+            service: self.service.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            pid: std::process::id(),
+            state: last.state.clone(),
+            beat_ms: last.at_ms,
+            pause_ms: last.pause_ms,
+            written_ms: now_ms(),
+            started_ms: self.started_ms,
+            interval_secs: self.every.as_secs(),
+        }
+    }
+}
+
+/// Write to a temporary file and rename, so readers never see half a report
+fn write_file(dir: &Path, service: &str, json: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{service}.heartbeat.json"));
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(tmp, path)
+}
+
+async fn write_loop(heartbeat: Arc<Heartbeat>, mut redis: RedisConn, dir: Option<PathBuf>) {
+    let key = format!("{HEARTBEAT_PREFIX}{}", heartbeat.service);
+    let ttl = heartbeat.every.as_secs() * KEY_TTL_INTERVALS;
+    let mut tick = heartbeat.ticker();
+    loop {
+        tick.tick().await;
+        let json = serde_json::to_string(&heartbeat.report()).unwrap_or_default();
+        if let Err(e) = redis.set_ex::<_, _, ()>(&key, &json, ttl).await {
+            warn!("⚠️ Could not write heartbeat: {e}");
+        }
+        if let Some(dir) = &dir
+            && let Err(e) = write_file(dir, heartbeat.service, &json)
+        {
+            warn!("⚠️ Could not write heartbeat file in {}: {e}", dir.display());
+        }
+    }
+}
+
+/// Every heartbeat in Redis, by service
+pub async fn read_all(redis: &mut RedisConn) -> redis::RedisResult<Vec<HeartbeatReport>> {
+    let mut keys: Vec<String> = redis.keys(format!("{HEARTBEAT_PREFIX}*")).await?;
+    if keys.is_empty() {
+        return Ok(Vec::new());
+    }
+    keys.sort();
+    let found: Vec<Option<String>> = redis.mget(&keys).await?;
+    Ok(found
+        .into_iter()
+        .filter_map(|json| serde_json::from_str(&json?).ok())
+        .collect())
+}
@@ -0,0 +1,237 @@
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Client as PgClient;
+
+use crate::bars::Timeframe;
+
+pub const DEFAULT_HISTORY_LIMIT: i64 = 500;
+pub const MAX_HISTORY_LIMIT: i64 = 5000;
+/// Parquet exports are built in memory, so their range is capped
+pub const MAX_PARQUET_CANDLES: usize = 1_000_000;
+
+pub const CSV_HEADER: &str = "symbol,start,open,high,low,close,volume,snapshots\n";
+
+/// What readers query instead of `stock_price_history`; the cleaner recreates it in the
+/// transaction that empties the table
+pub const HISTORY_VIEW: &str = "stock_price_history_current";
+
+/// Statement (re)creating the readers' view; prices and volumes read as f64 whether the
+/// columns are DOUBLE PRECISION or NUMERIC (see [`crate::precision`])
+pub fn view_sql() -> String {
+    format!(
+        "CREATE OR REPLACE VIEW {HISTORY_VIEW} AS \
+         SELECT id, stock_id, symbol, open::float8 AS open, high::float8 AS high, low::float8 AS low, \
+                close::float8 AS close, volume::float8 AS volume, trade_time_stamp, volume_start \
+         FROM stock_price_history"
+    )
+}
+
+/// Create the readers' view over `stock_price_history` if it is missing, adding the
+/// `volume_start` column first on tables from before it. Snapshots leave it NULL, as their
+/// volume is the ingester's running total; backfilled candles set it to their open, as
+/// theirs is the candle's alone.
+pub async fn ensure_view(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    let current = pg
+        .query_opt(
+            "SELECT 1 FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name = $1 AND column_name = 'volume_start'",
+            &[&HISTORY_VIEW],
+        )
+        .await?;
+    if current.is_some() {
+        return Ok(());
+    }
+    pg.batch_execute(&format!(
+        "ALTER TABLE stock_price_history ADD COLUMN IF NOT EXISTS volume_start BIGINT; {}",
+        view_sql()
+    ))
+    .await
+}
+
+/// One resampled candle from persisted OHLCV snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Candle {
+    /// Bucket start, ms since epoch
+    pub start: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Traded volume, differenced from the snapshots' running total
+    pub volume: f64,
+    /// Snapshots in the bucket
+    pub snapshots: i64,
+}
+
+impl Candle {
+    /// One `CSV_HEADER` line
+    pub fn csv_row(&self, symbol: &str) -> String {
+        format!(
+            "{symbol},{},{},{},{},{},{},{}\n",
+            self.start, self.open, self.high, self.low, self.close, self.volume, self.snapshots
+        )
+    }
+}
+
+/// A page of candles; pass `next` as `from` to continue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CandlePage {
+    pub symbol: String,
+    pub tf: String,
+    pub candles: Vec<Candle>,
+    pub next: Option<i64>,
+}
+
+/// `QueryCache` params identifying a `candles` query as the caller phrased it, so an
+/// open-ended window (no `to`) maps to one key however often it is asked for
+pub fn cache_params(tf: Timeframe, from: Option<&str>, to: Option<&str>, limit: i64) -> String {
+    format!("{tf}:{}:{}:{limit}", from.unwrap_or(""), to.unwrap_or(""))
+}
+
+/// `symbol` resampled to `tf` in Postgres over `[from, to)`, at most `limit` candles.
+/// Snapshots carry the latest trade price as `close`, so OHLC come from the closes.
+/// Volume is the per-snapshot increase of the ingester's running total (a drop means the
+/// ingester restarted); backfilled candles count in full, and the snapshot after one adds
+/// nothing, as its total overlaps the candles.
+pub async fn candles(
+    pg: &PgClient,
+    symbol: &str,
+    tf: Timeframe,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    limit: i64,
+) -> Result<CandlePage, String> {
+    let limit = limit.clamp(1, MAX_HISTORY_LIMIT);
+    let rows = pg
+        .query(
+            "WITH snaps AS ( \
+                 SELECT trade_time_stamp AS ts, close, volume, \
+                        CASE WHEN volume_start IS NOT NULL THEN volume \
+                             WHEN lag(volume_start) OVER w IS NULL THEN volume - lag(volume) OVER w END AS dv \
+                 FROM stock_price_history_current \
+                 WHERE symbol = $1 AND trade_time_stamp >= $2 AND trade_time_stamp < $3 \
+                 WINDOW w AS (ORDER BY trade_time_stamp) \
+             ) \
+             SELECT (floor(extract(epoch FROM ts) * 1000 / $4::bigint) * $4::bigint)::bigint AS bucket, \
+                    (array_agg(close ORDER BY ts))[1], max(close), min(close), \
+                    (array_agg(close ORDER BY ts DESC))[1], \
+                    sum(CASE WHEN dv IS NULL THEN 0 WHEN dv >= 0 THEN dv ELSE volume END), \
+                    count(*) \
+             FROM snaps GROUP BY 1 ORDER BY 1 LIMIT $5",
+            &[&symbol, &from, &to, &tf.millis(), &(limit + 1)],
+        )
+        .await
+        .map_err(|e| format!("history query failed: {e}"))?;
+
+    let mut candles: Vec<Candle> = rows
+        .iter()
+        .map(|r| Candle {
+            start: r.get(0),
+            open: r.get(1),
+            high: r.get(2),
+            low: r.get(3),
+            close: r.get(4),
+            volume: r.get(5),
+            snapshots: r.get(6),
+        })
+        .collect();
+    let next = (candles.len() as i64 > limit).then(|| {
+        candles.truncate(limit as usize);
+        candles.last().map(|c| c.start + tf.millis())
+    });
+
+    Ok(CandlePage {
+        symbol: symbol.to_string(),
+        tf: tf.to_string(),
+        candles,
+        next: next.flatten(),
+    })
+}
+
+/// `[from, to)` from optional bounds: `to` defaults to now, `from` to `limit` candles before `to`
+pub fn window(
+    tf: Timeframe,
+    from: Option<&str>,
+    to: Option<&str>,
+    limit: i64,
+) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+    let time = |s: Option<&str>| {
+        s.map(|v| parse_time(v).ok_or_else(|| format!("invalid time '{v}'")))
+            .transpose()
+    };
+    let to = time(to)?.unwrap_or_else(|| Utc::now().naive_utc());
+    let from = time(from)?.unwrap_or(to - Duration::milliseconds(tf.millis() * limit));
+    if from >= to {
+        return Err("`from` must be before `to`".to_string());
+    }
+    Ok((from, to))
+}
+
+/// ms since epoch, RFC 3339, or `YYYY-MM-DD[THH:MM:SS]` (UTC)
+pub fn parse_time(s: &str) -> Option<NaiveDateTime> {
+    if let Ok(ms) = s.parse::<i64>() {
+        return DateTime::from_timestamp_millis(ms).map(|t| t.naive_utc());
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|d| d.and_time(Default::default())))
+        .ok()
+}
+
+/// Snappy Parquet of `candles`: `symbol` (UTF8), `start` (timestamp ms), OHLCV doubles, `snapshots`
+#[cfg(feature = "parquet")]
+pub fn parquet(symbol: &str, candles: &[Candle]) -> Result<Vec<u8>, String> {
+    use std::sync::Arc;
+
+    use parquet::{
+        basic::Compression,
+        data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type},
+        file::{properties::WriterProperties, writer::SerializedFileWriter},
+        schema::parser::parse_message_type,
+    };
+
+    let schema = parse_message_type(
+        "message candles { REQUIRED BYTE_ARRAY symbol (UTF8); REQUIRED INT64 start (TIMESTAMP(MILLIS,true)); \
+         REQUIRED DOUBLE open; REQUIRED DOUBLE high; REQUIRED DOUBLE low; REQUIRED DOUBLE close; \
+         REQUIRED DOUBLE volume; REQUIRED INT64 snapshots; }",
+    )
+    .map_err(|e| format!("invalid parquet schema: {e}"))?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+
+    let err = |e: parquet::errors::ParquetError| format!("cannot encode parquet: {e}");
+    let mut writer = SerializedFileWriter::new(Vec::new(), Arc::new(schema), Arc::new(props)).map_err(err)?;
+    let mut group = writer.next_row_group().map_err(err)?;
+
+    let symbols: Vec<ByteArray> = vec![symbol.into(); candles.len()];
+    let doubles = |f: fn(&Candle) -> f64| candles.iter().map(f).collect::<Vec<f64>>();
+    let mut index = 0;
+    while let Some(mut col) = group.next_column().map_err(err)? {
+        match index {
+            0 => col.typed::<ByteArrayType>().write_batch(&symbols, None, None),
+            1 => {
+                let starts: Vec<i64> = candles.iter().map(|c| c.start).collect();
+                col.typed::<Int64Type>().write_batch(&starts, None, None)
+            }
+            2 => col.typed::<DoubleType>().write_batch(&doubles(|c| c.open), None, None),
+            3 => col.typed::<DoubleType>().write_batch(&doubles(|c| c.high), None, None),
+            4 => col.typed::<DoubleType>().write_batch(&doubles(|c| c.low), None, None),
+            5 => col.typed::<DoubleType>().write_batch(&doubles(|c| c.close), None, None),
+            6 => col.typed::<DoubleType>().write_batch(&doubles(|c| c.volume), None, None),
+            _ => {
+                let snapshots: Vec<i64> = candles.iter().map(|c| c.snapshots).collect();
+                col.typed::<Int64Type>().write_batch(&snapshots, None, None)
+            }
+        }
+        .map_err(err)?;
+        col.close().map_err(err)?;
+        index += 1;
+    }
+    group.close().map_err(err)?;
+    writer.into_inner().map_err(err)
+}
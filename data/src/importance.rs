@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{features::FEATURE_NAMES, models::Model};
+
+
+/// Scale raw importances to shares summing to one; `None` when there is nothing to share
+pub fn shares(raw: &[f64]) -> Option<Vec<f64>> {
+    let total: f64 = raw.iter().map(|v| v.abs()).sum();
+    (total > 0.0 && total.is_finite()).then(|| raw.iter().map(|v| v.abs() / total).collect())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FeatureWeight {
+    pub feature: String,
+    /// Share of the model's total importance, 0..1
+    pub importance: f64,
+}
+
+/// Per-feature importance of one model, most important first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FeatureImportance {
+    pub symbol: String,
+    pub model: String,
+    pub model_version: String,
+    /// Close time of the bar it was computed on, ms since epoch
+    pub ts: i64,
+    pub features: Vec<FeatureWeight>,
+}
+
+impl FeatureImportance {
+    /// `None` for models that cannot explain themselves (ONNX, TorchScript, Python)
+    pub fn compute(symbol: &str, model: &dyn Model, std: &[f64], ts: i64) -> Option<Self> {
+        let raw = model.importance(std)?;
+        let mut features: Vec<FeatureWeight> = FEATURE_NAMES
+            .iter()
+            .zip(shares(&raw)?)
+            .map(|(name, importance)| FeatureWeight {
+                feature: name.to_string(),
+                importance,
+            })
+            .collect();
+        features.sort_by(|a, b| b.importance.total_cmp(&a.importance));
+        Some(Self {
+            symbol: symbol.to_string(),
+            model: model.name().to_string(),
+            model_version: model.version(),
+            ts,
+            features,
+        })
+    }
+
+    /// The `n` most important features
+    pub fn top(&self, n: usize) -> &[FeatureWeight] {
+        &self.features[..n.min(self.features.len())]
+    }
+
+    /// Redis hash fields: one per feature plus the model and `ts`
+    pub fn fields(&self) -> Vec<(String, String)> {
+        let mut fields: Vec<(String, String)> = self
+            .features
+            .iter()
+            .map(|f| (f.feature.clone(), f.importance.to_string()))
+            .collect();
+        fields.push(("model".to_string(), self.model.clone()));
+        fields.push(("model_version".to_string(), self.model_version.clone()));
+        fields.push(("ts".to_string(), self.ts.to_string()));
+        fields
+    }
+
+    pub fn from_fields(symbol: &str, fields: &HashMap<String, String>) -> Option<Self> {
+        let mut features: Vec<FeatureWeight> = FEATURE_NAMES
+            .iter()
+            .filter_map(|name| {
+                Some(FeatureWeight {
+                    feature: name.to_string(),
+                    importance: fields.get(*name)?.parse().ok()?,
+                })
+            })
+            .collect();
+        if features.is_empty() {
+            return None;
+        }
+        features.sort_by(|a, b| b.importance.total_cmp(&a.importance));
+        Some(Self {
+            symbol: symbol.to_string(),
+            model: fields.get("model")?.clone(),
+            model_version: fields.get("model_version")?.clone(),
+            ts: fields.get("ts")?.parse().ok()?,
+            features,
+        })
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::VecDeque;
+
+use crate::bars::Bar;
+
+
+const SMA_PERIOD: usize = 20;
+const EMA_PERIOD: usize = 20;
+const RSI_PERIOD: usize = 14;
+const MACD_FAST: usize = 12;
+const MACD_SLOW: usize = 26;
+const MACD_SIGNAL: usize = 9;
+const BB_PERIOD: usize = 20;
+const BB_WIDTH: f64 = 2.0;
+const ATR_PERIOD: usize = 14;
+const STOCH_K: usize = 14;
+const STOCH_D: usize = 3;
+
+/// Fixed-size window with running sums
+#[derive(Debug, Clone)]
+struct Window {
+    period: usize,
+    values: VecDeque<f64>,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl Window {
+    fn new(period: usize) -> Self {
+        Self {
+            period,
+            values: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+
+    fn push(&mut self, v: f64) {
+        self.values.push_back(v);
+        self.sum += v;
+        self.sum_sq += v * v;
+        if self.values.len() > self.period
+            && let Some(old) = self.values.pop_front()
+        {
+            self.sum -= old;
+            self.sum_sq -= old * old;
+        }
+    }
+
+    fn full(&self) -> bool {
+        self.values.len() == self.period
+    }
+
+    fn mean(&self) -> Option<f64> {
+        self.full().then(|| self.sum / self.period as f64)
+    }
+
+    fn stddev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        Some((self.sum_sq / self.period as f64 - mean * mean).max(0.0).sqrt())
+    }
+
+    fn max(&self) -> Option<f64> {
+        self.full().then(|| self.values.iter().copied().fold(f64::MIN, f64::max))
+    }
+
+    fn min(&self) -> Option<f64> {
+        self.full().then(|| self.values.iter().copied().fold(f64::MAX, f64::min))
+    }
+}
+
+/// Simple moving average
+#[derive(Debug, Clone)]
+pub struct Sma(Window);
+
+impl Sma {
+    pub fn new(period: usize) -> Self {
+        Self(Window::new(period))
+    }
+
+    pub fn update(&mut self, v: f64) -> Option<f64> {
+        self.0.push(v);
+        self.0.mean()
+    }
+}
+
+/// Exponential moving average, seeded with the SMA of the first `period` values
+#[derive(Debug, Clone)]
+pub struct Ema {
+    period: usize,
+    alpha: f64,
+    seed: Vec<f64>,
+    value: Option<f64>,
+}
+
+impl Ema {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            alpha: 2.0 / (period as f64 + 1.0),
+            seed: Vec::with_capacity(period),
+            value: None,
+        }
+    }
+
+    pub fn update(&mut self, v: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(prev) => Some(prev + self.alpha * (v - prev)),
+            None => {
+                self.seed.push(v);
+                (self.seed.len() == self.period)
+                    .then(|| self.seed.iter().sum::<f64>() / self.period as f64)
+            }
+        };
+        self.value
+    }
+}
+
+/// Wilder-smoothed running average
+#[derive(Debug, Clone)]
+struct Wilder {
+    period: usize,
+    count: usize,
+    value: f64,
+}
+
+impl Wilder {
+    fn new(period: usize) -> Self {
+        Self {
+            period,
+            count: 0,
+            value: 0.0,
+        }
+    }
+
+    fn update(&mut self, v: f64) -> Option<f64> {
+        let n = self.period as f64;
+        if self.count < self.period {
+            self.count += 1;
+            self.value += v / n;
+        } else {
+            self.value = (self.value * (n - 1.0) + v) / n;
+        }
+        (self.count == self.period).then_some(self.value)
+    }
+}
+
+/// Relative strength index (Wilder)
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    prev: Option<f64>,
+    gain: Wilder,
+    loss: Wilder,
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Self {
+        Self {
+            prev: None,
+            gain: Wilder::new(period),
+            loss: Wilder::new(period),
+        }
+    }
+
+    pub fn update(&mut self, close: f64) -> Option<f64> {
+        let prev = self.prev.replace(close)?;
+        let change = close - prev;
+        let gain = self.gain.update(change.max(0.0))?;
+        let loss = self.loss.update((-change).max(0.0))?;
+        Some(if loss == 0.0 {
+            100.0
+        } else {
+            100.0 - 100.0 / (1.0 + gain / loss)
+        })
+    }
+}
+
+/// MACD line, signal line and histogram
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+impl Macd {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        Self {
+            fast: Ema::new(fast),
+            slow: Ema::new(slow),
+            signal: Ema::new(signal),
+        }
+    }
+
+    /// Returns `(macd, signal, histogram)` once the signal line is seeded
+    pub fn update(&mut self, close: f64) -> Option<(f64, f64, f64)> {
+        let fast = self.fast.update(close);
+        let slow = self.slow.update(close);
+        let macd = fast? - slow?;
+        let signal = self.signal.update(macd)?;
+        Some((macd, signal, macd - signal))
+    }
+}
+
+/// Bollinger bands around an SMA
+#[derive(Debug, Clone)]
+pub struct Bollinger {
+    window: Window,
+    width: f64,
+}
+
+impl Bollinger {
+    pub fn new(period: usize, width: f64) -> Self {
+        Self {
+            window: Window::new(period),
+            width,
+        }
+    }
+
+    /// Returns `(middle, upper, lower)`
+    pub fn update(&mut self, close: f64) -> Option<(f64, f64, f64)> {
+        self.window.push(close);
+        let mid = self.window.mean()?;
+        let sd = self.window.stddev()?;
+        Some((mid, mid + self.width * sd, mid - self.width * sd))
+    }
+}
+
+/// Average true range (Wilder)
+#[derive(Debug, Clone)]
+pub struct Atr {
+    prev_close: Option<f64>,
+    avg: Wilder,
+}
+
+impl Atr {
+    pub fn new(period: usize) -> Self {
+        Self {
+            prev_close: None,
+            avg: Wilder::new(period),
+        }
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64) -> Option<f64> {
+        let tr = match self.prev_close.replace(close) {
+            Some(pc) => (high - low).max((high - pc).abs()).max((low - pc).abs()),
+            None => high - low,
+        };
+        self.avg.update(tr)
+    }
+}
+
+/// Stochastic oscillator %K / %D
+#[derive(Debug, Clone)]
+pub struct Stochastic {
+    highs: Window,
+    lows: Window,
+    d: Sma,
+}
+
+impl Stochastic {
+    pub fn new(k_period: usize, d_period: usize) -> Self {
+        Self {
+            highs: Window::new(k_period),
+            lows: Window::new(k_period),
+            d: Sma::new(d_period),
+        }
+    }
+
+    /// Returns `(%K, %D)`; %D is `None` until enough %K values exist
+    pub fn update(&mut self, high: f64, low: f64, close: f64) -> Option<(f64, Option<f64>)> {
+        self.highs.push(high);
+        self.lows.push(low);
+        let (hh, ll) = (self.highs.max()?, self.lows.min()?);
+        let k = if hh > ll { 100.0 * (close - ll) / (hh - ll) } else { 50.0 };
+        Some((k, self.d.update(k)))
+    }
+}
+
+/// Latest indicator values; `None` while an indicator is warming up
+#[derive(Debug, Clone, Default)]
+pub struct IndicatorSnapshot {
+    pub sma: Option<f64>,
+    pub ema: Option<f64>,
+    pub rsi: Option<f64>,
+    pub macd: Option<f64>,
+    pub macd_signal: Option<f64>,
+    pub macd_hist: Option<f64>,
+    pub bb_mid: Option<f64>,
+    pub bb_upper: Option<f64>,
+    pub bb_lower: Option<f64>,
+    pub atr: Option<f64>,
+    pub stoch_k: Option<f64>,
+    pub stoch_d: Option<f64>,
+}
+
+impl IndicatorSnapshot {
+    /// Redis hash fields for every warmed-up indicator
+    pub fn fields(&self) -> Vec<(String, String)> {
+        [
+            ("sma", self.sma),
+            ("ema", self.ema),
+            ("rsi", self.rsi),
+            ("macd", self.macd),
+            ("macd_signal", self.macd_signal),
+            ("macd_hist", self.macd_hist),
+            ("bb_mid", self.bb_mid),
+            ("bb_upper", self.bb_upper),
+            ("bb_lower", self.bb_lower),
+            ("atr", self.atr),
+            ("stoch_k", self.stoch_k),
+            ("stoch_d", self.stoch_d),
+        ]
+        .into_iter()
+        .filter_map(|(k, v)| v.map(|v| (k.to_string(), v.to_string())))
+        .collect()
+    }
+}
+
+/// All indicators for one symbol/timeframe, updated once per closed bar
+#[derive(Debug, Clone)]
+pub struct IndicatorSet {
+    sma: Sma,
+    ema: Ema,
+    rsi: Rsi,
+    macd: Macd,
+    bollinger: Bollinger,
+    atr: Atr,
+    stoch: Stochastic,
+}
+
+impl Default for IndicatorSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IndicatorSet {
+    pub fn new() -> Self {
+        Self {
+            sma: Sma::new(SMA_PERIOD),
+            ema: Ema::new(EMA_PERIOD),
+            rsi: Rsi::new(RSI_PERIOD),
+            macd: Macd::new(MACD_FAST, MACD_SLOW, MACD_SIGNAL),
+            bollinger: Bollinger::new(BB_PERIOD, BB_WIDTH),
+            atr: Atr::new(ATR_PERIOD),
+            stoch: Stochastic::new(STOCH_K, STOCH_D),
+        }
+    }
+
+    pub fn update(&mut self, bar: &Bar) -> IndicatorSnapshot {
+        let macd = self.macd.update(bar.close);
+        let bb = self.bollinger.update(bar.close);
+        let stoch = self.stoch.update(bar.high, bar.low, bar.close);
+
+        IndicatorSnapshot {
+            sma: self.sma.update(bar.close),
+            ema: self.ema.update(bar.close),
+            rsi: self.rsi.update(bar.close),
+            macd: macd.map(|m| m.0),
+            macd_signal: macd.map(|m| m.1),
+            macd_hist: macd.map(|m| m.2),
+            bb_mid: bb.map(|b| b.0),
+            bb_upper: bb.map(|b| b.1),
+            bb_lower: bb.map(|b| b.2),
+            atr: self.atr.update(bar.high, bar.low, bar.close),
+            stoch_k: stoch.map(|s| s.0),
+            stoch_d: stoch.and_then(|s| s.1),
+        }
+    }
+}
@@ -0,0 +1,758 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    time::Duration,
+};
+
+use chrono::{Utc, TimeZone};
+use futures::{stream::StreamExt, FutureExt, SinkExt};
+use redis::AsyncCommands;
+use thiserror::Error;
+use tokio::time::{interval, sleep, MissedTickBehavior};
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio_util::sync::CancellationToken;
+
+use tracing::{debug, error, info, warn};
+use crate::{
+    bars::{self, BarEngine, Timeframe},
+    clock::{self, SharedClock},
+    config::{Config, TsPolicy},
+    consolidate::{ConsolidationConfig, Consolidator},
+    conversion::UsdConverter,
+    error::StoreError,
+    health::{self, Health},
+    heartbeat::Heartbeat,
+    keys::{
+        BARS_CHANNEL, BAR_HISTORY_PREFIX, BAR_PREFIX, CONSOLIDATED_CHANNEL, CONSOLIDATED_PREFIX, DEAD_LETTER_KEY,
+        KALMAN_PREFIX, OHLCV_PREFIX, PRICE_PREFIX, SYMBOLS_KEY, TRADES_CHANNEL, TRADE_PREFIX,
+    },
+    metrics::{Metrics, HOP_PARSED, HOP_RECEIVED, HOP_WRITTEN},
+    mirror::Mirror,
+    precision::{Precision, Total},
+    quality::{DqCounters, BAD_TIMESTAMPS, MISSING_VOLUME, OUTLIERS, POISON_TICKS},
+    redis_conn::{RedisClient, RedisConn},
+    relay::Trade,
+    seed,
+    source::{self, SourceTrade},
+    ticks::{Tick, TickBuffer},
+};
+
+// Exchanges ping idle connections or answer ours, so a silent socket this long is dead
+const EXCHANGE_MAX_AGE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum IngestError {
+    #[error(transparent)]
+    Store(#[from] StoreError),
+    #[error("invalid exchange URL: {0}")]
+    Url(#[from] url::ParseError),
+}
+
+impl IngestError {
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Store(e) => e.is_transient(),
+            Self::Url(_) => false,
+        }
+    }
+}
+
+/// OHLCV for one symbol's current bar of its shortest timeframe
+#[derive(Debug, Clone, Copy)]
+struct Ohlcv {
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    /// Volume since `volume_start`
+    volume: Total,
+    /// Volume since the symbol's state was created, never reset, so the fetcher's
+    /// snapshots difference to every trade including a bar's last seconds
+    volume_total: Total,
+    /// Start of the bar, ms since epoch
+    volume_start: i64,
+    /// Trades in that bar that came without a volume
+    missing_volume: u32,
+    /// Local receive time of the last trade, ms since epoch
+    last_seen: i64,
+    /// Trades folded in since the symbol's state was created, so the fetcher can tell how
+    /// many trades each persisted row stands for
+    trades: u64,
+}
+
+impl Ohlcv {
+    /// State for a symbol's first trade
+    fn new(price: f64, volume_start: i64) -> Self {
+        Self {
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: Total::default(),
+            volume_total: Total::default(),
+            volume_start,
+            missing_volume: 0,
+            last_seen: 0,
+            trades: 0,
+        }
+    }
+
+    /// Fold in a trade received at `now`; a later `volume_start` starts a new bar at `price`
+    fn add(&mut self, price: f64, volume: Option<f64>, volume_start: i64, now: i64) {
+        if volume_start > self.volume_start {
+            self.open = price;
+            self.high = price;
+            self.low = price;
+            self.volume = Total::default();
+            self.missing_volume = 0;
+            self.volume_start = volume_start;
+        }
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume.add(volume.unwrap_or(0.0));
+        self.volume_total.add(volume.unwrap_or(0.0));
+        self.missing_volume += u32::from(volume.is_none());
+        self.last_seen = now;
+        self.trades += 1;
+    }
+}
+
+/// Drop symbols missing from `subscribed` and those idle longer than `idle`; returns how many went
+fn evict_ohlcv(
+    map: &mut HashMap<String, Ohlcv>,
+    subscribed: Option<&[String]>,
+    idle: Option<Duration>,
+    now: i64,
+) -> usize {
+    let before = map.len();
+    map.retain(|symbol, o| {
+        subscribed.is_none_or(|s| s.contains(symbol))
+            && idle.is_none_or(|idle| now - o.last_seen <= idle.as_millis() as i64)
+    });
+    before - map.len()
+}
+
+/// Keep a trade the ingester could not use, with why, on the capped `DEAD_LETTER_KEY` list
+async fn dead_letter(
+    redis: &mut RedisConn,
+    trade: &SourceTrade,
+    reason: &str,
+    len: isize,
+    received_at: i64,
+) -> redis::RedisResult<()> {
+    let entry = serde_json::json!({
+        "symbol": trade.symbol,
+        "price": trade.price,
+        "volume": trade.volume,
+        "ts": trade.ts,
+        "reason": reason,
+        "received_at": received_at,
+    })
+    .to_string();
+    redis::pipe()
+        .lpush(DEAD_LETTER_KEY, entry)
+        .ignore()
+        .ltrim(DEAD_LETTER_KEY, 0, len - 1)
+        .ignore()
+        .query_async(redis)
+        .await
+}
+
+/// What a caught panic said, for the log
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+/// How `run` treats what it receives
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    /// Print trades instead of writing them, subscribing to the configured symbols alone
+    pub dry_run: bool,
+    /// Return after the first trade message
+    pub once: bool,
+}
+
+/// Stream trades from the configured exchange source into Redis (last price, trade, OHLCV,
+/// bars, Kalman fair price), reconnecting with backoff; `config` must have been loaded with
+/// `Need::Redis` and `Need::Finnhub`. Only the tracked symbols the source owns are subscribed;
+/// those without a price or bar yet are first seeded from REST quotes (`SEED_QUOTES`).
+/// Prices and volumes are written as `config.precision` says.
+/// Only startup can fail: once running, exchange and Redis errors are retried, and a message
+/// or trade whose processing panics is logged, counted and skipped. Cancelling
+/// `shutdown` closes the socket, flushes the counters and returns.
+pub async fn run(config: &Config, args: Options, shutdown: CancellationToken) -> Result<(), IngestError> {
+    run_with_clock(config, args, clock::system(), shutdown).await
+}
+
+/// [`run`], reading the time from `clock`: receive times, timestamp checks, idle eviction
+/// and bar publish times
+pub async fn run_with_clock(
+    config: &Config,
+    args: Options,
+    clock: SharedClock,
+    shutdown: CancellationToken,
+) -> Result<(), IngestError> {
+    let redis_client = RedisClient::open_with(&config.redis_url(), &config.tls.redis)?;
+    info!("🌐 Connecting to Redis ({})...", redis_client.describe());
+
+    let source = source::from_config(config);
+    let health = Health::new(source.service());
+    health.expect("exchange", Some(EXCHANGE_MAX_AGE));
+    health::spawn_server(health.clone());
+
+    // Persistent Redis connection; it reconnects by itself after this
+    let mut redis_conn = redis_client.connect_with_retry().await;
+    if let Some(url) = config.redis_mirror_url() {
+        redis_conn.mirror_to(Mirror::open(&url, &config.tls.redis)?);
+    }
+    tokio::spawn(health::probe(health.clone(), Some(redis_conn.clone()), None));
+    let heartbeat = Heartbeat::spawn(source.service(), redis_conn.clone());
+
+    info!("✅ Connected to Redis");
+
+    // Symbols from the config join whatever is already tracked; a dry run subscribes to them alone
+    if args.dry_run {
+        info!("🧪 Dry run: trades are printed, nothing is written to Redis");
+    } else if !config.symbols.is_empty() {
+        redis_conn
+            .sadd::<_, _, ()>(SYMBOLS_KEY, &config.symbols)
+            .await
+            .map_err(StoreError::from)?;
+        info!("📌 Tracking configured symbols: {}", config.symbols.join(", "));
+    }
+
+    // Fail on a bad URL now rather than on every reconnect
+    source.url(config)?;
+
+    // OHLCV in-memory state per symbol, evicted once unsubscribed or idle
+    let mut ohlcv_map: HashMap<String, Ohlcv> = HashMap::new();
+    let ohlcv_idle = config.ingest.ohlcv_idle();
+
+    // The last raw ticks per symbol, for features that need more than bars
+    let mut tick_buffer = TickBuffer::from_env();
+    if let Some(buffer) = &tick_buffer {
+        info!("🎞️ Keeping the last {} ticks per symbol", buffer.capacity());
+    }
+
+    // Time-bucketed bars, published on close for the predictor
+    let mut bar_engine = BarEngine::with_clock(Vec::new(), clock.clone());
+    let (bar_rules, symbol_rules) = config.bars.rules(bars::timeframes_from_env());
+    let join = |tfs: &[Timeframe]| tfs.iter().map(|tf| tf.to_string()).collect::<Vec<_>>().join(", ");
+    info!("🕯️ Building bars for timeframes: {}", join(&bar_rules.timeframes));
+    for (symbol, rules) in &symbol_rules {
+        info!(
+            "🕯️ {symbol}: timeframes {}, max jump {}, at least {} ticks per bar",
+            join(&rules.timeframes),
+            rules.max_jump.map_or("off".to_string(), |j| format!("{}%", j * 100.0)),
+            rules.min_ticks
+        );
+    }
+    bar_engine.set_rules(bar_rules, symbol_rules);
+    let bar_history_len = bars::history_len_from_env() as isize;
+
+    // One price per asset across the exchanges it trades on, and USD terms for other quotes
+    let pairs = ConsolidationConfig::from_env();
+    let mut converter = UsdConverter::from_env(pairs.clone());
+    let mut consolidator = Consolidator::new(pairs);
+    let precision = Precision::from_config(&config.precision);
+
+    // Exchange → ingester latency per trade
+    let mut metrics = Metrics::new(source.service());
+    let mut dq = DqCounters::from_env();
+    // Messages and trades whose processing panicked, skipped instead of taking the ingester down
+    let mut panics: u64 = 0;
+    // Trades with an untrusted timestamp are dead-lettered, then skipped or clamped
+    let (ts_policy, ts_max_skew) = (config.ingest.ts_policy, config.ingest.ts_max_skew());
+    let dead_letter_len = config.ingest.dead_letter_len as isize;
+
+    // Prices and bars from REST for symbols that have none yet, before streaming starts
+    if !args.dry_run && seed::enabled_from_env() {
+        match redis_conn.smembers::<_, Vec<String>>(SYMBOLS_KEY).await {
+            Ok(mut symbols) => {
+                symbols.retain(|s| source.owns(s));
+                let timeframes = |s: &str| bar_engine.rules_for(s).timeframes.clone();
+                seed::run(&mut redis_conn, &symbols, &precision, config.finnhub_key(), timeframes).await;
+            }
+            Err(e) => warn!("⚠️ Could not read symbols to seed: {e}"),
+        }
+    }
+
+    let initial_delay = config.intervals.reconnect();
+    let mut reconnect_delay = initial_delay;
+
+    while !shutdown.is_cancelled() {
+        heartbeat.beat("connecting");
+        info!("🌐 Attempting connection to {} WebSocket...", source.name());
+
+        match connect_async(source.url(config)?).await {
+            Ok((mut ws_stream, _)) => {
+                info!("✅ WebSocket connected successfully.");
+                health.ok("exchange");
+                reconnect_delay = initial_delay;
+                // Symbols subscribed on this connection; the tracked set is re-read on a timer
+                // so additions and removals apply without reconnecting
+                let mut subscribed: Vec<String> = Vec::new();
+                let mut symbols_tick = interval(config.intervals.symbols_refresh());
+                symbols_tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+                // Process incoming WebSocket messages
+                let mut beat_tick = heartbeat.ticker();
+                let keepalive = source.keepalive();
+                let mut ping_tick = interval(keepalive.as_ref().map_or(Duration::from_secs(3600), |k| k.every));
+                ping_tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                loop {
+                    let msg = tokio::select! {
+                        msg = ws_stream.next() => match msg {
+                            Some(m) => m,
+                            None => break,
+                        },
+                        _ = symbols_tick.tick() => {
+                            let tracked = if args.dry_run && !config.symbols.is_empty() {
+                                Ok(config.symbols.clone())
+                            } else {
+                                redis_conn.smembers::<_, Vec<String>>(SYMBOLS_KEY).await
+                            };
+                            let mut current = match tracked {
+                                Ok(symbols) => symbols,
+                                Err(e) => {
+                                    error!("❌ Redis symbol fetch error: {}", e);
+                                    continue;
+                                }
+                            };
+                            current.retain(|s| source.owns(s));
+                            current.sort();
+                            if current == subscribed {
+                                continue;
+                            }
+                            if current.is_empty() {
+                                warn!("⚠️ No {} symbols in '{}'", source.name(), SYMBOLS_KEY);
+                            }
+
+                            let removed: Vec<String> = subscribed.iter().filter(|s| !current.contains(s)).cloned().collect();
+                            let added: Vec<String> = current.iter().filter(|s| !subscribed.contains(s)).cloned().collect();
+                            let mut frames = Vec::new();
+                            if !removed.is_empty() {
+                                info!("🔕 Unsubscribing from {} symbols...", removed.len());
+                                frames.extend(source.unsubscribe(&removed));
+                            }
+                            if !added.is_empty() {
+                                info!("🔄 Subscribing to {} symbols...", added.len());
+                                frames.extend(source.subscribe(&added));
+                            }
+                            let evicted = evict_ohlcv(&mut ohlcv_map, Some(&current), None, clock.now_ms());
+                            if evicted > 0 {
+                                info!("🧹 Dropped OHLCV state for {evicted} unsubscribed symbols");
+                                if let Some(buffer) = &mut tick_buffer {
+                                    buffer.retain(|s| ohlcv_map.contains_key(s));
+                                }
+                            }
+                            subscribed = current;
+
+                            // A frame that cannot be sent means the socket is gone; resubscribe on a new one
+                            let mut sent = true;
+                            for frame in frames {
+                                if let Err(e) = ws_stream.send(Message::Text(frame)).await {
+                                    error!("❌ Failed to update subscriptions: {}", e);
+                                    sent = false;
+                                    break;
+                                }
+                                sleep(Duration::from_millis(50)).await;
+                            }
+                            if !sent {
+                                break;
+                            }
+                            continue;
+                        }
+                        _ = beat_tick.tick() => {
+                            heartbeat.beat("streaming");
+                            let evicted = evict_ohlcv(&mut ohlcv_map, None, ohlcv_idle, clock.now_ms());
+                            if evicted > 0 {
+                                info!("🧹 Dropped OHLCV state for {evicted} idle symbols");
+                                if let Some(buffer) = &mut tick_buffer {
+                                    buffer.retain(|s| ohlcv_map.contains_key(s));
+                                }
+                            }
+                            metrics.set_gauge("ohlcv_symbols", "", ohlcv_map.len() as f64);
+                            if !args.dry_run && let Err(e) = metrics.flush_if_due(&mut redis_conn).await {
+                                error!("❌ Redis metrics write error: {}", e);
+                            }
+                            continue;
+                        }
+                        _ = ping_tick.tick(), if keepalive.is_some() => {
+                            if let Some(k) = &keepalive
+                                && let Err(e) = ws_stream.send(k.message.clone()).await
+                            {
+                                error!("❌ Keepalive ping failed: {}", e);
+                                break;
+                            }
+                            continue;
+                        }
+                        _ = shutdown.cancelled() => break,
+                    };
+                    #[cfg(feature = "chaos")]
+                    if crate::chaos::drop_frame() {
+                        continue;
+                    }
+                    if msg.is_ok() {
+                        health.ok("exchange");
+                    }
+                    match msg {
+                        Ok(Message::Text(text)) => {
+                            metrics.count_hop(HOP_RECEIVED, "", 1);
+                            let trades = match panic::catch_unwind(AssertUnwindSafe(|| source.parse(&text))) {
+                                Ok(Ok(trades)) => trades,
+                                Ok(Err(e)) => {
+                                    warn!("⚠️ {} says: {}", source.name(), e);
+                                    continue;
+                                }
+                                Err(panic) => {
+                                    panics += 1;
+                                    error!("💥 Parsing a {} message panicked, skipped: {}", source.name(), panic_message(&*panic));
+                                    metrics.set_gauge("ingest_panics", "", panics as f64);
+                                    continue;
+                                }
+                            };
+                            metrics.count_hop(HOP_PARSED, "", trades.len() as u64);
+                            if !trades.is_empty() {
+                                if args.dry_run {
+                                    for t in &trades {
+                                        info!("🧪 {} {} x {} at {}", t.symbol, t.price, t.volume.unwrap_or(0.0), t.ts);
+                                    }
+                                    if args.once {
+                                        return Ok(());
+                                    }
+                                    continue;
+                                }
+                                for trade in trades {
+                                    let symbol = trade.symbol.clone();
+                                    let processed = AssertUnwindSafe(async {
+                                        debug!("📨 {} {} x {}", trade.symbol, trade.price, trade.volume.unwrap_or(0.0));
+                                        #[cfg(feature = "chaos")]
+                                        crate::chaos::trade_panic(&symbol);
+                                        let price = trade.price;
+                                        let volume = trade.volume.unwrap_or(0.0);
+                                        // Exchange trade time in ms, whatever unit it came in; the receive time if untrusted and clamping
+                                        let received = clock.now_ms();
+                                        let ts = match source::normalize_ts(trade.ts, received, ts_max_skew) {
+                                            Some(ts) => {
+                                                metrics.observe_latency("stage_latency_ms", "stage=\"receive\"", (received - ts) as f64);
+                                                ts
+                                            }
+                                            None => {
+                                                let reason = format!("timestamp {} not within {:?} of the local clock", trade.ts, ts_max_skew);
+                                                dq.add(&symbol, BAD_TIMESTAMPS, 1);
+                                                if let Err(e) = dead_letter(&mut redis_conn, &trade, &reason, dead_letter_len, received).await {
+                                                    error!("❌ Redis dead-letter write error: {}", e);
+                                                }
+                                                if ts_policy == TsPolicy::Skip {
+                                                    warn!("⚠️ Skipping {symbol} trade: {reason}");
+                                                    return;
+                                                }
+                                                warn!("⚠️ Clamping {symbol} trade to the receive time: {reason}");
+                                                // No exchange time to measure the receive latency from
+                                                metrics.add("trades_clamped_total", "", 1);
+                                                received
+                                            }
+                                        };
+                                        let trade = SourceTrade { ts, ..trade };
+                                        // Exchange trade time (ms since epoch) as RFC3339
+                                        let Some(trade_time) = Utc.timestamp_millis_opt(trade.ts).single() else {
+                                            warn!("⚠️ Skipping {symbol} trade with invalid timestamp {}", trade.ts);
+                                            dq.add(&symbol, BAD_TIMESTAMPS, 1);
+                                            return;
+                                        };
+                                        if !price.is_finite() || price <= 0.0 || !volume.is_finite() || volume < 0.0 {
+                                            warn!("⚠️ Skipping {symbol} trade with price {price} x {volume}");
+                                            dq.add(&symbol, OUTLIERS, 1);
+                                            return;
+                                        }
+                                        if bar_engine.is_outlier(&symbol, price) {
+                                            warn!("⚠️ Skipping {symbol} trade at {price}: too far from the last price");
+                                            dq.add(&symbol, OUTLIERS, 1);
+                                            return;
+                                        }
+                                        dq.on_trade(&symbol, trade.ts);
+                                        if let Some(buffer) = &mut tick_buffer {
+                                            buffer.push(&symbol, Tick { price, volume: trade.volume, ts: trade.ts });
+                                        }
+                                        if trade.volume.is_none() {
+                                            dq.add(&symbol, MISSING_VOLUME, 1);
+                                        }
+                                        let trade_time_str = trade_time.to_rfc3339();
+                                        converter.on_trade(&symbol, price, trade.ts);
+                                        let usd = converter.to_usd(&symbol, price, trade.ts);
+
+                                        // --- Redis writes ---
+                                        if let Err(e) = redis_conn
+                                            .set::<_, _, ()>(
+                                                format!("{}{}", PRICE_PREFIX, symbol),
+                                                precision.price(price),
+                                            )
+                                            .await
+                                        {
+                                            error!("❌ Redis SET error: {}", e);
+                                            return;
+                                        }
+
+                                        let live = serde_json::to_string(&Trade {
+                                            symbol: symbol.clone(),
+                                            price,
+                                            volume,
+                                            ts: trade.ts,
+                                            price_usd: usd.map(|u| u.price),
+                                        })
+                                        .unwrap_or_default();
+                                        let mut trade_fields = vec![
+                                            ("price".to_string(), precision.price(price)),
+                                            ("timestamp".to_string(), trade.ts.to_string()),
+                                            ("volume".to_string(), precision.volume(volume)),
+                                            ("updated_at".to_string(), trade_time_str.clone()),
+                                        ];
+                                        if let Some(u) = usd {
+                                            trade_fields.push(("price_usd".to_string(), u.price.to_string()));
+                                            trade_fields.push(("usd_rate".to_string(), u.rate.to_string()));
+                                        }
+                                        let res: redis::RedisResult<()> = redis::pipe()
+                                            .hset_multiple(format!("{}{}", TRADE_PREFIX, symbol), &trade_fields)
+                                            .ignore()
+                                            .publish(TRADES_CHANNEL, live)
+                                            .ignore()
+                                            .query_async(&mut redis_conn)
+                                            .await;
+                                        if let Err(e) = res {
+                                            error!("❌ Redis HSET trade error: {}", e);
+                                            return;
+                                        }
+
+                                        // Update OHLCV state; it restarts with the symbol's shortest bar
+                                        let volume_tf = bar_engine.rules_for(&symbol).timeframes.first().copied();
+                                        let volume_start = volume_tf.map_or(0, |tf| tf.bucket_start(trade.ts));
+                                        let entry = ohlcv_map.entry(symbol.clone()).or_insert_with(|| Ohlcv::new(price, volume_start));
+                                        entry.add(price, trade.volume, volume_start, received);
+
+                                        // Immediate OHLCV flush
+                                        if let Err(e) = redis_conn
+                                            .hset_multiple::<_, _, _, ()>(
+                                                format!("{}{}", OHLCV_PREFIX, symbol),
+                                                &[
+                                                    ("open".to_string(), precision.price(entry.open)),
+                                                    ("high".to_string(), precision.price(entry.high)),
+                                                    ("low".to_string(), precision.price(entry.low)),
+                                                    ("close".to_string(), precision.price(entry.close)),
+                                                    ("volume".to_string(), precision.total(&entry.volume)),
+                                                    ("volume_start".to_string(), entry.volume_start.to_string()),
+                                                    ("volume_total".to_string(), precision.total(&entry.volume_total)),
+                                                    ("missing_volume".to_string(), entry.missing_volume.to_string()),
+                                                    ("volume_known".to_string(), (entry.missing_volume == 0).to_string()),
+                                                    ("updated_at".to_string(), trade_time_str.clone()),
+                                                    ("trades".to_string(), entry.trades.to_string()),
+                                                ],
+                                            )
+                                            .await
+                                        {
+                                            error!("❌ Redis HSET OHLCV error: {}", e);
+                                            return;
+                                        }
+                                        metrics.count_hop(HOP_WRITTEN, "", 1);
+
+                                        // Publish bars closed by this trade
+                                        for mut bar in bar_engine.on_trade(&symbol, price, trade.volume, trade.ts) {
+                                            bar.usd_rate = usd.map(|u| u.rate);
+                                            let payload = match serde_json::to_string(&bar) {
+                                                Ok(p) => p,
+                                                Err(e) => {
+                                                    error!("❌ Bar serialization error: {}", e);
+                                                    continue;
+                                                }
+                                            };
+                                            let history_key = format!("{}{}:{}", BAR_HISTORY_PREFIX, bar.symbol, bar.tf);
+                                            let res: redis::RedisResult<()> = redis::pipe()
+                                                .hset_multiple(
+                                                    format!("{}{}:{}", BAR_PREFIX, bar.symbol, bar.tf),
+                                                    &bar.fields(),
+                                                )
+                                                .ignore()
+                                                .lpush(&history_key, &payload)
+                                                .ignore()
+                                                .ltrim(&history_key, 0, bar_history_len - 1)
+                                                .ignore()
+                                                .publish(BARS_CHANNEL, payload)
+                                                .ignore()
+                                                .query_async(&mut redis_conn)
+                                                .await;
+                                            if let Err(e) = res {
+                                                error!("❌ Redis bar publish error: {}", e);
+                                                break;
+                                            }
+                                        }
+
+                                        // Kalman fair price after this trade
+                                        if let Some(estimate) = bar_engine.fair_price(&symbol)
+                                            && let Err(e) = redis_conn
+                                                .hset_multiple::<_, _, _, ()>(
+                                                    format!("{}{}", KALMAN_PREFIX, symbol),
+                                                    &estimate.fields(),
+                                                )
+                                                .await
+                                        {
+                                            error!("❌ Redis HSET Kalman error: {}", e);
+                                        }
+
+                                        // Cross-exchange price once the asset has enough live sources
+                                        if let Some(c) = consolidator.on_trade(&symbol, price, volume, trade.ts) {
+                                            converter.on_consolidated(&c);
+                                            for s in &c.newly_flagged {
+                                                let div = c.sources.iter().find(|q| &q.symbol == s).map_or(0.0, |q| q.divergence_bps);
+                                                warn!("🚩 {s} is {div:.1} bps off the {} consolidated price; excluded", c.symbol);
+                                            }
+                                            for s in &c.cleared {
+                                                info!("✅ {s} is back in line with {}", c.symbol);
+                                            }
+                                            for q in &c.sources {
+                                                let labels = format!("symbol=\"{}\",canonical=\"{}\"", q.symbol, c.symbol);
+                                                metrics.set_gauge("source_divergence_bps", &labels, q.divergence_bps);
+                                                metrics.set_gauge("source_excluded", &labels, f64::from(u8::from(q.status != "ok")));
+                                            }
+                                            let payload = serde_json::to_string(&c).unwrap_or_default();
+                                            let res: redis::RedisResult<()> = redis::pipe()
+                                                .hset_multiple(format!("{}{}", CONSOLIDATED_PREFIX, c.symbol), &c.fields())
+                                                .ignore()
+                                                .publish(CONSOLIDATED_CHANNEL, payload)
+                                                .ignore()
+                                                .query_async(&mut redis_conn)
+                                                .await;
+                                            if let Err(e) = res {
+                                                error!("❌ Redis consolidated price error: {}", e);
+                                            }
+                                        }
+
+                                        if let Err(e) = metrics.flush_if_due(&mut redis_conn).await {
+                                            error!("❌ Redis metrics write error: {}", e);
+                                        }
+                                        if let Err(e) = dq.flush_if_due(&mut redis_conn).await {
+                                            error!("❌ Redis data-quality write error: {}", e);
+                                        }
+                                        if let Some(buffer) = &mut tick_buffer
+                                            && let Err(e) = buffer.flush_if_due(&mut redis_conn).await
+                                        {
+                                            error!("❌ Redis tick buffer write error: {}", e);
+                                        }
+                                    })
+                                    .catch_unwind()
+                                    .await;
+                                    if let Err(panic) = processed {
+                                        panics += 1;
+                                        error!("💥 Processing a {symbol} trade panicked, skipped: {}", panic_message(&*panic));
+                                        dq.add(&symbol, POISON_TICKS, 1);
+                                        metrics.set_gauge("ingest_panics", "", panics as f64);
+                                    }
+                                }
+                                if args.once {
+                                    info!("✅ First trade batch written, exiting (--once)");
+                                    return Ok(());
+                                }
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            error!("❌ WebSocket stream error: {}", e);
+                            break;
+                        }
+                    }
+                }
+
+                if shutdown.is_cancelled() {
+                    let _ = ws_stream.close(None).await;
+                } else {
+                    warn!("🔁 WebSocket disconnected. Retrying...");
+                    health.fail("exchange", "disconnected");
+                }
+            }
+            Err(e) => {
+                error!("❌ Connection error: {}", e);
+                health.fail("exchange", &e);
+            }
+        }
+
+        if shutdown.is_cancelled() {
+            break;
+        }
+        heartbeat.pause("reconnecting", reconnect_delay);
+        info!("⏳ Waiting {}s before retry...", reconnect_delay.as_secs());
+        tokio::select! {
+            _ = sleep(reconnect_delay) => {}
+            _ = shutdown.cancelled() => break,
+        }
+        reconnect_delay = (reconnect_delay * 2).min(Duration::from_secs(60));
+    }
+
+    heartbeat.beat("stopping");
+    if !args.dry_run {
+        if let Err(e) = metrics.flush(&mut redis_conn).await {
+            error!("❌ Redis metrics write error: {}", e);
+        }
+        if let Err(e) = dq.flush(&mut redis_conn).await {
+            error!("❌ Redis data-quality write error: {}", e);
+        }
+    }
+    info!("👋 Ingester stopped");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    #[test]
+    fn ohlcv_restarts_with_each_bar_but_keeps_the_running_volume() {
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 50).unwrap());
+        let m1 = Timeframe::parse("1m").unwrap();
+        let precision = Precision::default();
+
+        let mut o = Ohlcv::new(100.0, m1.bucket_start(clock.now_ms()));
+        o.add(100.0, Some(1.0), m1.bucket_start(clock.now_ms()), clock.now_ms());
+        clock.advance(Duration::from_secs(5));
+        o.add(105.0, None, m1.bucket_start(clock.now_ms()), clock.now_ms());
+        assert_eq!((o.open, o.high, o.low, o.close, o.missing_volume), (100.0, 105.0, 100.0, 105.0, 1));
+
+        // 00:01:05 is in the next bar
+        clock.advance(Duration::from_secs(10));
+        let start = m1.bucket_start(clock.now_ms());
+        o.add(103.0, Some(2.0), start, clock.now_ms());
+        assert_eq!((o.open, o.high, o.low, o.close, o.missing_volume), (103.0, 103.0, 103.0, 103.0, 0));
+        assert_eq!(o.volume_start, start);
+        assert_eq!(precision.total(&o.volume), "2");
+        assert_eq!(precision.total(&o.volume_total), "3");
+        assert_eq!((o.trades, o.last_seen), (3, clock.now_ms()));
+    }
+
+    #[test]
+    fn idle_and_unsubscribed_symbols_are_evicted() {
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+        let mut map = HashMap::new();
+        for symbol in ["A", "B", "C"] {
+            let mut o = Ohlcv::new(1.0, 0);
+            o.add(1.0, Some(1.0), 0, clock.now_ms());
+            map.insert(symbol.to_string(), o);
+            clock.advance(Duration::from_secs(3600));
+        }
+        let idle = Some(Duration::from_secs(2 * 3600));
+
+        // A was last seen 3h ago, B 2h ago
+        assert_eq!(evict_ohlcv(&mut map, None, idle, clock.now_ms()), 1);
+        assert!(!map.contains_key("A"));
+        assert_eq!(evict_ohlcv(&mut map, Some(&["C".to_string()]), None, clock.now_ms()), 1);
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["C"]);
+    }
+}
@@ -0,0 +1,420 @@
+use std::{collections::HashMap, env, time::Duration};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use redis::AsyncCommands;
+use serde::Serialize;
+use serde_json::Value;
+use tokio_postgres::Client as PgClient;
+use tracing::warn;
+
+use crate::{
+    binance::BinanceClient,
+    config::Config,
+    fetcher::try_connect_pg,
+    keys::{INSTRUMENT_PREFIX, SYMBOLS_KEY},
+    redis_conn::{self, RedisConn},
+};
+
+const OKX_URL: &str = "https://www.okx.com";
+const BYBIT_URL: &str = "https://api.bybit.com";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Symbols per Binance exchange-info request, well inside what its URL length allows
+const BINANCE_BATCH: usize = 100;
+// Slack for float noise when flooring to whole lots
+const LOT_EPSILON: f64 = 1e-9;
+
+/// One symbol's trading rules and facts, as its exchange publishes them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Instrument {
+    pub symbol: String,
+    /// Smallest price step
+    pub tick_size: f64,
+    /// Smallest quantity step; contracts for OKX swaps
+    pub lot_size: f64,
+    /// Smallest order quantity, 0 when there is none
+    pub min_qty: f64,
+    /// Smallest order value in the quote asset, 0 when there is none
+    pub min_notional: f64,
+    pub base_asset: String,
+    pub quote_asset: String,
+    /// First trading day or listing time, ms since epoch; `None` when the exchange does not say
+    pub listed_at: Option<i64>,
+}
+
+impl Instrument {
+    /// Redis hash fields
+    pub fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = vec![
+            ("tick_size".to_string(), self.tick_size.to_string()),
+            ("lot_size".to_string(), self.lot_size.to_string()),
+            ("min_qty".to_string(), self.min_qty.to_string()),
+            ("min_notional".to_string(), self.min_notional.to_string()),
+            ("base_asset".to_string(), self.base_asset.clone()),
+            ("quote_asset".to_string(), self.quote_asset.clone()),
+        ];
+        if let Some(t) = self.listed_at {
+            fields.push(("listed_at".to_string(), t.to_string()));
+        }
+        fields
+    }
+
+    /// Inverse of [`Instrument::fields`]; `None` when the hash is empty or incomplete
+    pub fn from_fields(symbol: &str, fields: &HashMap<String, String>) -> Option<Self> {
+        let num = |k: &str| fields.get(k)?.parse::<f64>().ok();
+        Some(Self {
+            symbol: symbol.to_string(),
+            tick_size: num("tick_size")?,
+            lot_size: num("lot_size")?,
+            min_qty: num("min_qty").unwrap_or(0.0),
+            min_notional: num("min_notional").unwrap_or(0.0),
+            base_asset: fields.get("base_asset")?.clone(),
+            quote_asset: fields.get("quote_asset")?.clone(),
+            listed_at: fields.get("listed_at").and_then(|t| t.parse().ok()),
+        })
+    }
+
+    /// `price` on the nearest tick
+    pub fn round_price(&self, price: f64) -> f64 {
+        if self.tick_size > 0.0 {
+            (price / self.tick_size).round() * self.tick_size
+        } else {
+            price
+        }
+    }
+
+    /// `qty` rounded down to whole lots
+    pub fn floor_qty(&self, qty: f64) -> f64 {
+        if self.lot_size > 0.0 {
+            (qty / self.lot_size + LOT_EPSILON).floor() * self.lot_size
+        } else {
+            qty
+        }
+    }
+
+    /// Why the exchange would reject an order of `qty` at `price`, if it would
+    pub fn check_order(&self, qty: f64, price: f64) -> Result<(), String> {
+        if qty <= 0.0 || qty < self.min_qty {
+            return Err(format!("{} quantity {qty} is below the minimum {}", self.symbol, self.min_qty));
+        }
+        if qty * price < self.min_notional {
+            return Err(format!(
+                "{} order value {} is below the minimum {} {}",
+                self.symbol,
+                qty * price,
+                self.min_notional,
+                self.quote_asset
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// `symbol`'s instrument as last refreshed; `None` before the first refresh or on an
+/// exchange without one
+pub async fn load(redis: &mut RedisConn, symbol: &str) -> redis::RedisResult<Option<Instrument>> {
+    let fields: HashMap<String, String> = redis.hgetall(format!("{INSTRUMENT_PREFIX}{symbol}")).await?;
+    Ok(Instrument::from_fields(symbol, &fields))
+}
+
+/// Add the instrument columns to `stocks`
+pub async fn ensure_columns(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "ALTER TABLE stocks \
+         ADD COLUMN IF NOT EXISTS tick_size DOUBLE PRECISION, \
+         ADD COLUMN IF NOT EXISTS lot_size DOUBLE PRECISION, \
+         ADD COLUMN IF NOT EXISTS min_qty DOUBLE PRECISION, \
+         ADD COLUMN IF NOT EXISTS min_notional DOUBLE PRECISION, \
+         ADD COLUMN IF NOT EXISTS base_asset TEXT, \
+         ADD COLUMN IF NOT EXISTS quote_asset TEXT, \
+         ADD COLUMN IF NOT EXISTS listed_at TIMESTAMP, \
+         ADD COLUMN IF NOT EXISTS instrument_updated_at TIMESTAMP",
+    )
+    .await
+}
+
+/// A number the exchange sent as a string or a number
+fn num(v: Option<&Value>) -> Option<f64> {
+    match v? {
+        Value::String(s) => s.parse().ok(),
+        v => v.as_f64(),
+    }
+}
+
+fn text(v: Option<&Value>) -> Option<String> {
+    v?.as_str().filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+/// From one entry of Binance's exchange info; the listing date comes separately
+fn binance(symbol: &str, info: &Value) -> Option<Instrument> {
+    let filter = |kind: &str| {
+        info.get("filters")?
+            .as_array()?
+            .iter()
+            .find(|f| f.get("filterType").and_then(Value::as_str) == Some(kind))
+    };
+    let price = filter("PRICE_FILTER")?;
+    let lot = filter("LOT_SIZE")?;
+    let notional = filter("NOTIONAL").or_else(|| filter("MIN_NOTIONAL"));
+    Some(Instrument {
+        symbol: symbol.to_string(),
+        tick_size: num(price.get("tickSize"))?,
+        lot_size: num(lot.get("stepSize"))?,
+        min_qty: num(lot.get("minQty")).unwrap_or(0.0),
+        min_notional: notional.and_then(|n| num(n.get("minNotional"))).unwrap_or(0.0),
+        base_asset: text(info.get("baseAsset"))?,
+        quote_asset: text(info.get("quoteAsset"))?,
+        listed_at: None,
+    })
+}
+
+/// From one OKX v5 instrument; swaps name their assets only in the underlying (`BTC-USDT`)
+fn okx(symbol: &str, info: &Value) -> Option<Instrument> {
+    let underlying = text(info.get("uly")).unwrap_or_default();
+    let mut assets = underlying.splitn(2, '-').map(str::to_string);
+    let (uly_base, uly_quote) = (assets.next(), assets.next());
+    Some(Instrument {
+        symbol: symbol.to_string(),
+        tick_size: num(info.get("tickSz"))?,
+        lot_size: num(info.get("lotSz"))?,
+        min_qty: num(info.get("minSz")).unwrap_or(0.0),
+        min_notional: 0.0,
+        base_asset: text(info.get("baseCcy")).or(uly_base)?,
+        quote_asset: text(info.get("quoteCcy")).or(uly_quote)?,
+        listed_at: num(info.get("listTime")).map(|t| t as i64).filter(|&t| t > 0),
+    })
+}
+
+/// From one Bybit v5 linear instrument
+fn bybit(symbol: &str, info: &Value) -> Option<Instrument> {
+    let lot = info.get("lotSizeFilter");
+    Some(Instrument {
+        symbol: symbol.to_string(),
+        tick_size: num(info.get("priceFilter").and_then(|p| p.get("tickSize")))?,
+        lot_size: num(lot.and_then(|l| l.get("qtyStep")))?,
+        min_qty: num(lot.and_then(|l| l.get("minOrderQty"))).unwrap_or(0.0),
+        min_notional: num(lot.and_then(|l| l.get("minNotionalValue"))).unwrap_or(0.0),
+        base_asset: text(info.get("baseCoin"))?,
+        quote_asset: text(info.get("quoteCoin"))?,
+        listed_at: num(info.get("launchTime")).map(|t| t as i64).filter(|&t| t > 0),
+    })
+}
+
+/// Public instrument endpoints of the exchanges with one; no API keys needed.
+/// `BINANCE_API_URL`, `OKX_API_URL` and `BYBIT_API_URL` override them.
+struct Exchanges {
+    binance: BinanceClient,
+    http: reqwest::Client,
+    okx_url: String,
+    bybit_url: String,
+}
+
+impl Exchanges {
+    fn from_env() -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("❌ Failed to build HTTP client");
+        Self {
+            binance: BinanceClient::new(),
+            http,
+            okx_url: env::var("OKX_API_URL").unwrap_or_else(|_| OKX_URL.to_string()),
+            bybit_url: env::var("BYBIT_API_URL").unwrap_or_else(|_| BYBIT_URL.to_string()),
+        }
+    }
+
+    async fn get(&self, url: String, query: &[(&str, &str)]) -> Result<Value, String> {
+        let resp = self
+            .http
+            .get(&url)
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| format!("{url} request failed: {e}"))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("{url} returned {status}: {body}"));
+        }
+        resp.json().await.map_err(|e| format!("invalid {url} response: {e}"))
+    }
+
+    /// `BINANCE:` symbols, listing dates from the first daily candle unless already `known`
+    async fn binance(&mut self, symbols: &[&str], known: &HashMap<String, i64>) -> Result<Vec<Instrument>, String> {
+        let mut out = Vec::new();
+        for batch in symbols.chunks(BINANCE_BATCH) {
+            let pairs: Vec<&str> = batch.iter().map(|s| &s["BINANCE:".len()..]).collect();
+            // Binance refuses a whole batch over one unknown pair; ask for each to find it
+            let infos = match self.binance.exchange_info(&pairs).await {
+                Ok(infos) => infos,
+                Err(e) if pairs.len() > 1 => {
+                    warn!("⚠️ Binance refused a batch of {} pairs ({e}); asking one by one", pairs.len());
+                    let mut infos = Vec::new();
+                    for pair in pairs {
+                        match self.binance.exchange_info(&[pair]).await {
+                            Ok(i) => infos.extend(i),
+                            Err(e) => warn!("⚠️ Could not fetch the BINANCE:{pair} instrument: {e}"),
+                        }
+                    }
+                    infos
+                }
+                Err(e) => return Err(e),
+            };
+            for info in infos {
+                let Some(pair) = info.get("symbol").and_then(Value::as_str) else { continue };
+                if let Some(i) = binance(&format!("BINANCE:{pair}"), &info) {
+                    out.push(i);
+                }
+            }
+        }
+        for i in &mut out {
+            i.listed_at = match known.get(&i.symbol) {
+                Some(&t) => Some(t),
+                None => self
+                    .binance
+                    .klines(&i.symbol["BINANCE:".len()..], "1d", DateTime::UNIX_EPOCH, Utc::now())
+                    .await?
+                    .first()
+                    .map(|c| c.time.and_utc().timestamp_millis()),
+            };
+        }
+        Ok(out)
+    }
+
+    async fn okx(&self, symbol: &str) -> Result<Option<Instrument>, String> {
+        let id = &symbol["OKX:".len()..];
+        let kind = if id.ends_with("-SWAP") { "SWAP" } else { "SPOT" };
+        let body = self
+            .get(format!("{}/api/v5/public/instruments", self.okx_url), &[("instType", kind), ("instId", id)])
+            .await?;
+        Ok(body.get("data").and_then(|d| d.get(0)).and_then(|info| okx(symbol, info)))
+    }
+
+    async fn bybit(&self, symbol: &str) -> Result<Option<Instrument>, String> {
+        let pair = &symbol["BYBIT:".len()..];
+        let body = self
+            .get(format!("{}/v5/market/instruments-info", self.bybit_url), &[("category", "linear"), ("symbol", pair)])
+            .await?;
+        let info = body.get("result").and_then(|r| r.get("list")).and_then(|l| l.get(0));
+        Ok(info.and_then(|info| bybit(symbol, info)))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InstrumentReport {
+    pub updated: usize,
+    /// Tracked symbols on exchanges without an instrument endpoint, e.g. Finnhub stocks
+    pub unsupported: usize,
+    /// Symbols the exchange did not know or whose lookup failed
+    pub missing: usize,
+}
+
+/// Write instruments to `stocks` and their Redis hashes; a listing date once known is kept
+async fn save(pg: &PgClient, redis: &mut RedisConn, instruments: &[Instrument]) -> Result<(), String> {
+    let now = Utc::now().naive_utc();
+    for i in instruments {
+        let listed_at: Option<NaiveDateTime> =
+            i.listed_at.and_then(DateTime::from_timestamp_millis).map(|t| t.naive_utc());
+        pg.execute(
+            "UPDATE stocks SET tick_size = $2, lot_size = $3, min_qty = $4, min_notional = $5, \
+             base_asset = $6, quote_asset = $7, listed_at = COALESCE($8, listed_at), instrument_updated_at = $9 \
+             WHERE symbol = $1",
+            &[
+                &i.symbol,
+                &i.tick_size,
+                &i.lot_size,
+                &i.min_qty,
+                &i.min_notional,
+                &i.base_asset,
+                &i.quote_asset,
+                &listed_at,
+                &now,
+            ],
+        )
+        .await
+        .map_err(|e| format!("stocks update for {} failed: {e}", i.symbol))?;
+    }
+    let mut pipe = redis::pipe();
+    for i in instruments {
+        pipe.hset_multiple(format!("{INSTRUMENT_PREFIX}{}", i.symbol), &i.fields()).ignore();
+    }
+    pipe.query_async::<()>(redis)
+        .await
+        .map_err(|e| format!("Redis instrument write error: {e}"))
+}
+
+/// Refresh every tracked symbol's instrument (tick and lot size, order minimums, base and
+/// quote asset, listing date) from its exchange's public REST API into `stocks` and
+/// `{ns}:instrument:{symbol}`, where order sizing and validation read it. Binance, OKX and
+/// Bybit symbols are covered; a failed exchange leaves the others' updates in place.
+pub async fn run(config: &Config) -> Result<InstrumentReport, String> {
+    let mut redis = redis_conn::try_connect(&config.redis_url(), &config.tls.redis)
+        .await
+        .map_err(|e| e.to_string())?;
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    ensure_columns(&pg)
+        .await
+        .map_err(|e| format!("failed to add instrument columns to stocks: {e}"))?;
+
+    let symbols: Vec<String> = redis
+        .smembers(SYMBOLS_KEY)
+        .await
+        .map_err(|e| format!("Redis smembers error: {e}"))?;
+    let known: HashMap<String, i64> = pg
+        .query(
+            "SELECT symbol, listed_at FROM stocks WHERE symbol = ANY($1) AND listed_at IS NOT NULL",
+            &[&symbols],
+        )
+        .await
+        .map_err(|e| format!("stocks query failed: {e}"))?
+        .into_iter()
+        .map(|r| (r.get(0), r.get::<_, NaiveDateTime>(1).and_utc().timestamp_millis()))
+        .collect();
+
+    let mut exchanges = Exchanges::from_env();
+    let mut report = InstrumentReport::default();
+    let mut found = Vec::new();
+    let mut binance_symbols = Vec::new();
+    for symbol in &symbols {
+        let result = match symbol.split_once(':').map(|(exchange, _)| exchange) {
+            Some("BINANCE") => {
+                binance_symbols.push(symbol.as_str());
+                continue;
+            }
+            Some("OKX") => exchanges.okx(symbol).await,
+            Some("BYBIT") => exchanges.bybit(symbol).await,
+            _ => {
+                report.unsupported += 1;
+                continue;
+            }
+        };
+        match result {
+            Ok(Some(i)) => found.push(i),
+            Ok(None) => {
+                warn!("⚠️ {symbol} is not listed on its exchange");
+                report.missing += 1;
+            }
+            Err(e) => {
+                warn!("⚠️ Could not fetch the {symbol} instrument: {e}");
+                report.missing += 1;
+            }
+        }
+    }
+    if !binance_symbols.is_empty() {
+        match exchanges.binance(&binance_symbols, &known).await {
+            Ok(list) => {
+                report.missing += binance_symbols.len().saturating_sub(list.len());
+                found.extend(list);
+            }
+            Err(e) => {
+                warn!("⚠️ Could not fetch Binance instruments: {e}");
+                report.missing += binance_symbols.len();
+            }
+        }
+    }
+
+    save(&pg, &mut redis, &found).await?;
+    report.updated = found.len();
+    Ok(report)
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    pin::Pin,
+};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use tracing::{error, info, warn};
+
+pub type JobFuture = Pin<Box<dyn Future<Output = Result<(), String>>>>;
+
+/// A named unit of maintenance work that may depend on other jobs
+pub struct Job {
+    name: &'static str,
+    deps: Vec<&'static str>,
+    run: Box<dyn FnOnce() -> JobFuture>,
+}
+
+impl Job {
+    pub fn new<F, Fut>(name: &'static str, run: F) -> Self
+    where
+        F: FnOnce() -> Fut + 'static,
+        Fut: Future<Output = Result<(), String>> + 'static,
+    {
+        Self {
+            name,
+            deps: Vec::new(),
+            run: Box::new(move || Box::pin(run())),
+        }
+    }
+
+    /// Only run this job once `dep` has succeeded
+    pub fn after(mut self, dep: &'static str) -> Self {
+        self.deps.push(dep);
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn deps(&self) -> &[&'static str] {
+        &self.deps
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobOutcome {
+    Succeeded,
+    Failed(String),
+    Skipped(String),
+}
+
+/// Reject a graph that names a dependency no job has, or whose dependencies form a cycle
+pub fn check_graph(jobs: &[Job]) -> Result<(), String> {
+    let known: HashSet<&'static str> = jobs.iter().map(|j| j.name).collect();
+    for job in jobs {
+        if let Some(d) = job.deps.iter().find(|d| !known.contains(*d)) {
+            return Err(format!("job '{}' depends on unknown job '{d}'", job.name));
+        }
+    }
+    // Peel off jobs whose dependencies are all peeled; what remains is on or behind a cycle
+    let mut done: HashSet<&'static str> = HashSet::new();
+    let mut left: Vec<&Job> = jobs.iter().collect();
+    while !left.is_empty() {
+        let before = left.len();
+        left.retain(|j| {
+            let ready = j.deps.iter().all(|d| done.contains(d));
+            if ready {
+                done.insert(j.name);
+            }
+            !ready
+        });
+        if left.len() == before {
+            let names: Vec<&str> = left.iter().map(|j| j.name).collect();
+            return Err(format!("jobs {} are on or behind a dependency cycle", names.join(", ")));
+        }
+    }
+    Ok(())
+}
+
+/// Run jobs in dependency order, at most `parallelism` at a time; jobs whose dependencies
+/// failed are skipped. Nothing runs when [`check_graph`] rejects the graph.
+pub async fn run_jobs(jobs: Vec<Job>, parallelism: usize) -> Result<Vec<(&'static str, JobOutcome)>, String> {
+    check_graph(&jobs)?;
+    let parallelism = parallelism.max(1);
+
+    let mut pending: Vec<Job> = jobs;
+    let mut outcomes: HashMap<&'static str, JobOutcome> = HashMap::new();
+    let mut order: Vec<&'static str> = Vec::new();
+    let mut running = FuturesUnordered::new();
+
+    loop {
+        // 1) Skip jobs that can never run
+        let mut i = 0;
+        while i < pending.len() {
+            let job = &pending[i];
+            let blocked = job.deps.iter().find_map(|d| match outcomes.get(d) {
+                Some(JobOutcome::Succeeded) | None => None,
+                Some(_) => Some(format!("dependency '{d}' did not succeed")),
+            });
+            if let Some(reason) = blocked {
+                let job = pending.remove(i);
+                warn!("⏭️ job '{}' skipped: {reason}", job.name);
+                outcomes.insert(job.name, JobOutcome::Skipped(reason));
+                order.push(job.name);
+            } else {
+                i += 1;
+            }
+        }
+
+        // 2) Launch every ready job up to the parallelism limit
+        let mut i = 0;
+        while i < pending.len() && running.len() < parallelism {
+            let ready = pending[i]
+                .deps
+                .iter()
+                .all(|d| outcomes.get(d) == Some(&JobOutcome::Succeeded));
+            if ready {
+                let job = pending.remove(i);
+                let name = job.name;
+                info!("▶️ job '{name}' started");
+                let fut = (job.run)();
+                running.push(async move { (name, fut.await) });
+            } else {
+                i += 1;
+            }
+        }
+
+        // 3) Wait for the next job to finish, or stop when all are done
+        match running.next().await {
+            Some((name, res)) => {
+                let outcome = match res {
+                    Ok(()) => {
+                        info!("✅ job '{name}' succeeded");
+                        JobOutcome::Succeeded
+                    }
+                    Err(e) => {
+                        error!("❌ job '{name}' failed: {e}");
+                        JobOutcome::Failed(e)
+                    }
+                };
+                outcomes.insert(name, outcome);
+                order.push(name);
+            }
+            // The graph is acyclic, so nothing is left pending once nothing runs
+            None => break,
+        }
+    }
+
+    Ok(order
+        .into_iter()
+        .map(|name| (name, outcomes.remove(name).expect("outcome recorded")))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    type Log = Rc<RefCell<Vec<&'static str>>>;
+
+    /// A job that records that it ran, then fails if `fails`
+    fn job(name: &'static str, log: &Log, fails: bool) -> Job {
+        let log = log.clone();
+        Job::new(name, move || async move {
+            log.borrow_mut().push(name);
+            if fails { Err(format!("{name} broke")) } else { Ok(()) }
+        })
+    }
+
+    #[test]
+    fn rejects_unknown_dependencies_and_cycles() {
+        let log = Log::default();
+        let unknown = [job("a", &log, false).after("nope")];
+        assert_eq!(check_graph(&unknown), Err("job 'a' depends on unknown job 'nope'".to_string()));
+
+        // c is not on the cycle but can never run either
+        let cycle = [
+            job("a", &log, false).after("b"),
+            job("b", &log, false).after("a"),
+            job("c", &log, false).after("a"),
+        ];
+        let err = check_graph(&cycle).unwrap_err();
+        assert!(err.contains("a, b, c"), "{err}");
+
+        let fine = [
+            job("a", &log, false),
+            job("b", &log, false).after("a"),
+            job("c", &log, false).after("a").after("b"),
+        ];
+        assert_eq!(check_graph(&fine), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn nothing_runs_on_a_cyclic_graph() {
+        let log = Log::default();
+        let jobs = vec![
+            job("ok", &log, false),
+            job("a", &log, false).after("b"),
+            job("b", &log, false).after("a"),
+        ];
+        assert!(run_jobs(jobs, 2).await.is_err());
+        assert!(log.borrow().is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_everything_behind_a_failure() {
+        let log = Log::default();
+        let jobs = vec![
+            job("vacuum", &log, false).after("clean"),
+            job("clean", &log, true),
+            job("reindex", &log, false).after("vacuum"),
+            job("snapshot", &log, false),
+        ];
+        let outcomes: HashMap<_, _> = run_jobs(jobs, 1).await.unwrap().into_iter().collect();
+        assert_eq!(outcomes["clean"], JobOutcome::Failed("clean broke".to_string()));
+        assert_eq!(outcomes["vacuum"], JobOutcome::Skipped("dependency 'clean' did not succeed".to_string()));
+        assert_eq!(outcomes["reindex"], JobOutcome::Skipped("dependency 'vacuum' did not succeed".to_string()));
+        assert_eq!(outcomes["snapshot"], JobOutcome::Succeeded);
+        let mut ran = log.borrow().clone();
+        ran.sort();
+        assert_eq!(ran, ["clean", "snapshot"]);
+    }
+
+    #[tokio::test]
+    async fn runs_dependencies_first() {
+        let log = Log::default();
+        let jobs = vec![job("c", &log, false).after("b"), job("b", &log, false).after("a"), job("a", &log, false)];
+        let order: Vec<_> = run_jobs(jobs, 4).await.unwrap().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(order, ["a", "b", "c"]);
+        assert_eq!(*log.borrow(), ["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,114 @@
+use std::env;
+
+
+const DEFAULT_PROCESS_VAR: f64 = 1e-9;
+const DEFAULT_MEASUREMENT_VAR: f64 = 1e-8;
+
+fn env_f64(key: &str, default: f64) -> f64 {
+    env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// Noise parameters in squared log-price units
+#[derive(Debug, Clone, Copy)]
+pub struct KalmanConfig {
+    /// Random-walk variance of the fair price per second
+    pub process_var: f64,
+    /// Variance of a single trade print around the fair price
+    pub measurement_var: f64,
+}
+
+impl Default for KalmanConfig {
+    fn default() -> Self {
+        Self {
+            process_var: DEFAULT_PROCESS_VAR,
+            measurement_var: DEFAULT_MEASUREMENT_VAR,
+        }
+    }
+}
+
+impl KalmanConfig {
+    /// `KALMAN_PROCESS_VAR`, `KALMAN_MEASUREMENT_VAR`
+    pub fn from_env() -> Self {
+        Self {
+            process_var: env_f64("KALMAN_PROCESS_VAR", DEFAULT_PROCESS_VAR),
+            measurement_var: env_f64("KALMAN_MEASUREMENT_VAR", DEFAULT_MEASUREMENT_VAR),
+        }
+    }
+}
+
+/// Current state of one symbol's filter
+#[derive(Debug, Clone, Copy)]
+pub struct KalmanEstimate {
+    pub fair_price: f64,
+    /// One standard deviation of the estimate, in price units
+    pub std: f64,
+    /// Log distance of the last trade from the prior estimate
+    pub innovation: f64,
+    pub ts: i64,
+}
+
+impl KalmanEstimate {
+    /// Redis hash fields
+    pub fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("fair_price".to_string(), self.fair_price.to_string()),
+            ("std".to_string(), self.std.to_string()),
+            ("innovation".to_string(), self.innovation.to_string()),
+            ("ts".to_string(), self.ts.to_string()),
+        ]
+    }
+}
+
+/// Local-level Kalman filter on log price; process noise scales with the time between trades
+#[derive(Debug, Clone)]
+pub struct KalmanFilter {
+    cfg: KalmanConfig,
+    state: Option<(f64, f64, i64)>,
+    innovation: f64,
+}
+
+impl KalmanFilter {
+    pub fn new(cfg: KalmanConfig) -> Self {
+        Self {
+            cfg,
+            state: None,
+            innovation: 0.0,
+        }
+    }
+
+    pub fn estimate(&self) -> Option<KalmanEstimate> {
+        self.state.map(|(x, p, ts)| {
+            let fair_price = x.exp();
+            KalmanEstimate {
+                fair_price,
+                std: fair_price * p.sqrt(),
+                innovation: self.innovation,
+                ts,
+            }
+        })
+    }
+
+    /// Fold in one trade print
+    pub fn update(&mut self, price: f64, ts_ms: i64) -> Option<KalmanEstimate> {
+        if price <= 0.0 || !price.is_finite() {
+            return self.estimate();
+        }
+        let z = price.ln();
+        let r = self.cfg.measurement_var;
+
+        self.state = Some(match self.state {
+            None => {
+                self.innovation = 0.0;
+                (z, r, ts_ms)
+            }
+            Some((x, p, last_ts)) => {
+                let dt = (ts_ms - last_ts).max(0) as f64 / 1000.0;
+                let p = p + self.cfg.process_var * dt;
+                let k = p / (p + r);
+                self.innovation = z - x;
+                (x + k * self.innovation, (1.0 - k) * p, ts_ms.max(last_ts))
+            }
+        });
+        self.estimate()
+    }
+}
@@ -0,0 +1,225 @@
+use std::{fmt, ops::Deref, sync::OnceLock};
+
+use redis::{RedisWrite, ToRedisArgs};
+
+pub const DEFAULT_NAMESPACE: &str = "stock";
+
+static NAMESPACE: OnceLock<String> = OnceLock::new();
+
+/// Letters, digits and `-_.:`; glob characters would break the scans over a prefix
+pub fn validate(namespace: &str) -> Result<(), String> {
+    if namespace.is_empty()
+        || !namespace.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+    {
+        return Err(format!("invalid Redis namespace '{namespace}' (letters, digits and -_.: only)"));
+    }
+    Ok(())
+}
+
+/// What every key and channel starts with, `{namespace}:`, so environments or tenants can
+/// share a Redis: the one set by [`set_namespace`], else `REDIS_NAMESPACE`, else
+/// [`DEFAULT_NAMESPACE`]. It is fixed the first time a key is used; an invalid
+/// `REDIS_NAMESPACE` is fatal.
+pub fn namespace() -> &'static str {
+    NAMESPACE.get_or_init(|| {
+        let ns = std::env::var("REDIS_NAMESPACE")
+            .ok()
+            .filter(|ns| !ns.is_empty())
+            .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string());
+        validate(&ns).unwrap_or_else(|e| panic!("❌ {e}"));
+        ns
+    })
+}
+
+/// Fix the namespace before any key is used; fails once keys are in use under another one
+pub fn set_namespace(namespace: &str) -> Result<(), String> {
+    validate(namespace)?;
+    let current = NAMESPACE.get_or_init(|| namespace.to_string());
+    if current != namespace {
+        return Err(format!("Redis namespace is already '{current}', cannot switch to '{namespace}'"));
+    }
+    Ok(())
+}
+
+/// A key, key prefix or channel; dereferences to the namespaced name
+pub struct Key {
+    name: &'static str,
+    full: OnceLock<String>,
+}
+
+impl Key {
+    const fn new(name: &'static str) -> Self {
+        Self { name, full: OnceLock::new() }
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.full.get_or_init(|| format!("{}:{}", namespace(), self.name))
+    }
+}
+
+impl Deref for Key {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl PartialEq<Key> for str {
+    fn eq(&self, other: &Key) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl ToRedisArgs for Key {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        self.as_str().write_redis_args(out)
+    }
+}
+
+macro_rules! keys {
+    ($($(#[$doc:meta])* $ident:ident = $name:literal;)*) => {
+        $(
+            $(#[$doc])*
+            pub static $ident: &Key = {
+                static KEY: Key = Key::new($name);
+                &KEY
+            };
+        )*
+    };
+}
+
+keys! {
+    /// Everything the pipeline keeps in Redis
+    ALL_PATTERN = "*";
+
+    // --- Ingest ---
+
+    /// Set of symbols the ingester subscribes to
+    SYMBOLS_KEY = "symbols";
+    /// Of those, the ones the symbols file added, so dropping them from the file untracks them
+    SYMBOLS_FILE_KEY = "symbols:file";
+    /// Tracked symbols the fetcher does not write to Postgres (`persist = false` in the symbols file)
+    UNPERSISTED_KEY = "symbols:unpersisted";
+    /// Hash with a file-managed symbol's settings: `{ns}:symbol_meta:{symbol}`
+    SYMBOL_META_PREFIX = "symbol_meta:";
+    /// Hash with a symbol's exchange trading rules, assets and listing date: `{ns}:instrument:{symbol}`
+    INSTRUMENT_PREFIX = "instrument:";
+    /// Last trade price: `{ns}:price:{symbol}`
+    PRICE_PREFIX = "price:";
+    /// Hash holding the last trade: `{ns}:trade:{symbol}`
+    TRADE_PREFIX = "trade:";
+    /// Hash holding the OHLCV of the current shortest bar: `{ns}:ohlcv:{symbol}`
+    OHLCV_PREFIX = "ohlcv:";
+    /// Recent raw trades as a JSON array, oldest first: `{ns}:ticks:{symbol}`
+    TICKS_PREFIX = "ticks:";
+    /// Trades the ingester could not use, newest first, as JSON with the reason
+    DEAD_LETTER_KEY = "dead_letter:trades";
+    /// Pub/sub channel the ingester publishes every trade on (JSON `Trade`)
+    TRADES_CHANNEL = "trades";
+    /// Hash with the latest consolidated price: `{ns}:consolidated:{BASE-QUOTE}`
+    CONSOLIDATED_PREFIX = "consolidated:";
+    /// Pub/sub channel consolidated prices are published on (JSON `Consolidated`)
+    CONSOLIDATED_CHANNEL = "consolidated";
+
+    // --- Bars and per-bar analytics ---
+
+    /// Pub/sub channel closed bars are published on
+    BARS_CHANNEL = "bars";
+    /// Hash holding the last closed bar: `{ns}:bar:{symbol}:{tf}`
+    BAR_PREFIX = "bar:";
+    /// Recent closed bars as JSON, newest first: `{ns}:bar_history:{symbol}:{tf}`
+    BAR_HISTORY_PREFIX = "bar_history:";
+    /// Hash holding the latest fair-price estimate: `{ns}:kalman:{symbol}`
+    KALMAN_PREFIX = "kalman:";
+    /// Hash holding the latest values: `{ns}:indicators:{symbol}:{tf}`
+    INDICATORS_PREFIX = "indicators:";
+    /// Hash holding the latest estimates: `{ns}:volatility:{symbol}:{tf}`
+    VOLATILITY_PREFIX = "volatility:";
+    /// Pub/sub channel pattern detections are published on (JSON `PatternEvent`)
+    PATTERNS_CHANNEL = "patterns";
+    /// Pub/sub channel anomaly events are published on (JSON `AnomalyEvent`)
+    ANOMALIES_CHANNEL = "anomalies";
+    /// Hash holding the current regime: `{ns}:regime:{symbol}:{tf}`
+    REGIME_PREFIX = "regime:";
+    /// Pub/sub channel regime changes are published on (JSON `RegimeEvent`)
+    REGIMES_CHANNEL = "regimes";
+
+    // --- Storage ---
+
+    /// Cached query results: `{ns}:cache:{kind}:{symbol}:{generation}:{params}`
+    CACHE_PREFIX = "cache:";
+    /// Per-symbol counter bumped on every insert, orphaning that symbol's cached results:
+    /// `{ns}:cache:gen:{symbol}`
+    GENERATION_PREFIX = "cache:gen:";
+    /// Sorted set of live coordinated fetchers, scored by their last refresh (ms since epoch)
+    FETCHERS_KEY = "fetchers";
+
+    // --- Schedule ---
+
+    /// Per-day counters, `{symbol}|{counter}` fields of `{ns}:dq:{YYYY-MM-DD}` (UTC)
+    DQ_PREFIX = "dq:";
+
+    // --- Predict ---
+
+    /// Hash holding the latest vector: `{ns}:features:{symbol}:{tf}`
+    FEATURES_PREFIX = "features:";
+    /// JSON `Normalizer` with the live running statistics: `{ns}:normalizer:{tf}`
+    NORMALIZER_PREFIX = "normalizer:";
+    /// Hash holding the latest scaled vector: `{ns}:features_scaled:{symbol}:{tf}`
+    SCALED_FEATURES_PREFIX = "features_scaled:";
+    /// Hash with the latest PSI per feature: `{ns}:drift:{tf}`
+    DRIFT_PREFIX = "drift:";
+    /// Hash holding what the live model relies on: `{ns}:importance:{symbol}`
+    IMPORTANCE_PREFIX = "importance:";
+    /// Hash holding the latest prediction: `{ns}:prediction:{symbol}`
+    PREDICTION_PREFIX = "prediction:";
+    /// Pub/sub channel every new prediction is published on (JSON)
+    PREDICTIONS_CHANNEL = "predictions";
+    /// Per-member ensemble outputs: `{ns}:member_prediction:{symbol}:{label}`
+    MEMBER_PREDICTION_PREFIX = "member_prediction:";
+    /// Latest shadow-model prediction: `{ns}:shadow_prediction:{symbol}` (never published or traded)
+    SHADOW_PREDICTION_PREFIX = "shadow_prediction:";
+    /// Hash holding the current signal: `{ns}:signal:{symbol}`
+    SIGNAL_PREFIX = "signal:";
+    /// Pub/sub channel every signal is published on (JSON)
+    SIGNALS_CHANNEL = "signals";
+    /// Hash holding the latest paper-trading equity snapshot
+    PAPER_EQUITY_KEY = "paper:equity";
+    /// While this key exists no orders are sent
+    KILL_SWITCH_KEY = "execution:kill";
+
+    // --- API and integrations ---
+
+    /// Pub/sub channel every fired rule is published on (JSON `RuleEvent`)
+    RULE_ALERTS_CHANNEL = "rule_alerts";
+    /// Chats that turned on pushed alerts with `/alerts on`
+    TELEGRAM_CHATS_KEY = "telegram:chats";
+
+    // --- Service plumbing ---
+
+    /// Latest report of each running binary, expiring when it stops: `{ns}:health:{service}`
+    HEALTH_PREFIX = "health:";
+    /// Latest heartbeat of each running binary: `{ns}:heartbeat:{service}`, a JSON `HeartbeatReport`
+    HEARTBEAT_PREFIX = "heartbeat:";
+    /// Hash of one pipeline component's last activity: `{ns}:status:{component}`
+    STATUS_PREFIX = "status:";
+    /// Hash holding one component's metrics: `{ns}:metrics:{component}`.
+    /// Field names are Prometheus sample names with labels, e.g.
+    /// `stage_latency_ms_bucket{stage="total",le="50"}`.
+    METRICS_PREFIX = "metrics:";
+    /// Set of the components with a metrics hash, so readers need not scan for them
+    METRICS_COMPONENTS_KEY = "metrics_components";
+}
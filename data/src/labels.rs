@@ -0,0 +1,218 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{backtest::Tick, bars::Timeframe};
+
+/// Time-sorted trade prices per symbol
+pub struct PriceIndex(HashMap<String, Vec<(i64, f64)>>);
+
+impl PriceIndex {
+    pub fn new(ticks: &[Tick]) -> Self {
+        let mut by_symbol: HashMap<String, Vec<(i64, f64)>> = HashMap::new();
+        for t in ticks.iter().filter(|t| t.price > 0.0) {
+            by_symbol.entry(t.symbol.clone()).or_default().push((t.ts_ms, t.price));
+        }
+        for prices in by_symbol.values_mut() {
+            prices.sort_by_key(|p| p.0);
+        }
+        Self(by_symbol)
+    }
+
+    /// Last traded price at or before `ts`
+    pub fn at(&self, symbol: &str, ts: i64) -> Option<f64> {
+        let prices = self.0.get(symbol)?;
+        let i = prices.partition_point(|p| p.0 <= ts);
+        (i > 0).then(|| prices[i - 1].1)
+    }
+
+    /// Trades in `(from, to]`, in time order
+    pub fn between(&self, symbol: &str, from: i64, to: i64) -> &[(i64, f64)] {
+        let Some(prices) = self.0.get(symbol) else {
+            return &[];
+        };
+        let lo = prices.partition_point(|p| p.0 <= from);
+        let hi = prices.partition_point(|p| p.0 <= to);
+        &prices[lo..hi]
+    }
+
+    /// Time of the last known trade
+    pub fn last_ts(&self, symbol: &str) -> Option<i64> {
+        self.0.get(symbol)?.last().map(|p| p.0)
+    }
+}
+
+/// A prediction target, computed from prices after the observation time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Label {
+    /// Log return to the next trade
+    NextTick,
+    /// Log return over a fixed horizon
+    Return(Timeframe),
+    /// Sign of the horizon return: +1, -1 or 0
+    Direction(Timeframe),
+    /// +1 / -1 when the price first crosses the upper / lower barrier within
+    /// the horizon, 0 when neither is hit (vertical barrier)
+    TripleBarrier {
+        horizon: Timeframe,
+        upper_bps: f64,
+        lower_bps: f64,
+    },
+}
+
+impl Label {
+    /// `next_tick`, `ret:5m`, `dir:5m` or `tb:15m:UP_BPS:DOWN_BPS`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = s.trim().split(':').collect();
+        let tf = |p: &str| Timeframe::parse(p).ok_or(format!("invalid horizon '{p}' in label '{s}'"));
+        let bps = |p: &str| {
+            p.parse::<f64>()
+                .ok()
+                .filter(|v| *v > 0.0)
+                .ok_or(format!("invalid barrier '{p}' in label '{s}'"))
+        };
+        match parts.as_slice() {
+            ["next_tick"] => Ok(Self::NextTick),
+            ["ret", h] => Ok(Self::Return(tf(h)?)),
+            ["dir", h] => Ok(Self::Direction(tf(h)?)),
+            ["tb", h, up, down] => Ok(Self::TripleBarrier {
+                horizon: tf(h)?,
+                upper_bps: bps(up)?,
+                lower_bps: bps(down)?,
+            }),
+            _ => Err(format!("unknown label '{s}'")),
+        }
+    }
+
+    /// Comma-separated list of labels
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(',').filter(|l| !l.trim().is_empty()).map(Self::parse).collect()
+    }
+
+    /// How far past the observation the label looks; 0 for `NextTick`
+    pub fn horizon_ms(&self) -> i64 {
+        match self {
+            Self::NextTick => 0,
+            Self::Return(h) | Self::Direction(h) | Self::TripleBarrier { horizon: h, .. } => h.millis(),
+        }
+    }
+
+    /// Label value for an observation at `ts` with price `base`; `None` while
+    /// the prices needed are not available yet
+    pub fn value(&self, prices: &PriceIndex, symbol: &str, ts: i64, base: f64) -> Option<f64> {
+        if base <= 0.0 {
+            return None;
+        }
+        let last = prices.last_ts(symbol)?;
+        let end = ts + self.horizon_ms();
+        if end > last {
+            return None;
+        }
+
+        match *self {
+            Self::NextTick => {
+                let &(_, next) = prices.between(symbol, ts, last).first()?;
+                Some((next / base).ln())
+            }
+            Self::Return(_) => prices.at(symbol, end).map(|p| (p / base).ln()),
+            Self::Direction(_) => prices.at(symbol, end).map(|p| match p {
+                p if p > base => 1.0,
+                p if p < base => -1.0,
+                _ => 0.0,
+            }),
+            Self::TripleBarrier {
+                upper_bps,
+                lower_bps,
+                ..
+            } => {
+                let upper = base * (1.0 + upper_bps / 10_000.0);
+                let lower = base * (1.0 - lower_bps / 10_000.0);
+                let hit = prices
+                    .between(symbol, ts, end)
+                    .iter()
+                    .find_map(|&(_, p)| match p {
+                        p if p >= upper => Some(1.0),
+                        p if p <= lower => Some(-1.0),
+                        _ => None,
+                    });
+                Some(hit.unwrap_or(0.0))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Label {
+    /// Column name used in exported datasets
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NextTick => write!(f, "fwd_ret_tick"),
+            Self::Return(h) => write!(f, "fwd_ret_{h}"),
+            Self::Direction(h) => write!(f, "dir_{h}"),
+            Self::TripleBarrier {
+                horizon,
+                upper_bps,
+                lower_bps,
+            } => write!(f, "tb_{horizon}_{upper_bps}_{lower_bps}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tf(s: &str) -> Timeframe {
+        Timeframe::parse(s).unwrap()
+    }
+
+    /// 100 at 0s, 101 at 30s, 99 at 60s, 102 at 120s
+    fn prices() -> PriceIndex {
+        let tick = |secs: i64, price| Tick { symbol: "X".to_string(), ts_ms: secs * 1000, price, volume: 1.0 };
+        // Out of order on purpose; the bad price is ignored
+        PriceIndex::new(&[tick(60, 99.0), tick(0, 100.0), tick(120, 102.0), tick(30, 101.0), tick(90, 0.0)])
+    }
+
+    #[test]
+    fn parses_and_names_labels() {
+        assert_eq!(Label::parse("next_tick"), Ok(Label::NextTick));
+        assert_eq!(Label::parse(" dir:5m "), Ok(Label::Direction(tf("5m"))));
+        let tb = Label::parse("tb:15m:20:10").unwrap();
+        assert_eq!(tb, Label::TripleBarrier { horizon: tf("15m"), upper_bps: 20.0, lower_bps: 10.0 });
+        assert_eq!(tb.to_string(), "tb_15m_20_10");
+        assert_eq!(Label::parse_list("ret:1m,,next_tick").unwrap(), [Label::Return(tf("1m")), Label::NextTick]);
+
+        assert!(Label::parse("ret:soon").unwrap_err().contains("invalid horizon"));
+        assert!(Label::parse("tb:1m:0:10").unwrap_err().contains("invalid barrier"));
+        assert!(Label::parse("ret").unwrap_err().contains("unknown label"));
+    }
+
+    #[test]
+    fn returns_and_directions_over_the_horizon() {
+        let p = prices();
+        assert_eq!(Label::NextTick.value(&p, "X", 0, 100.0), Some((101.0f64 / 100.0).ln()));
+        // The last trade at or before 60s
+        assert_eq!(Label::Return(tf("1m")).value(&p, "X", 0, 100.0), Some((99.0f64 / 100.0).ln()));
+        assert_eq!(Label::Return(tf("1m")).value(&p, "X", 30_000, 101.0), Some((99.0f64 / 101.0).ln()));
+        assert_eq!(Label::Direction(tf("1m")).value(&p, "X", 0, 100.0), Some(-1.0));
+        assert_eq!(Label::Direction(tf("1m")).value(&p, "X", 60_000, 99.0), Some(1.0));
+        assert_eq!(Label::Direction(tf("1m")).value(&p, "X", 0, 99.0), Some(0.0));
+    }
+
+    #[test]
+    fn triple_barrier_takes_whichever_is_hit_first() {
+        let p = prices();
+        let tb = |upper_bps, lower_bps| Label::TripleBarrier { horizon: tf("1m"), upper_bps, lower_bps };
+        // 101 at 30s crosses +50 bps before 99 at 60s crosses -50 bps
+        assert_eq!(tb(50.0, 50.0).value(&p, "X", 0, 100.0), Some(1.0));
+        assert_eq!(tb(200.0, 50.0).value(&p, "X", 0, 100.0), Some(-1.0));
+        // Vertical barrier
+        assert_eq!(tb(500.0, 500.0).value(&p, "X", 0, 100.0), Some(0.0));
+    }
+
+    #[test]
+    fn no_value_until_the_horizon_has_passed() {
+        let p = prices();
+        assert_eq!(Label::Return(tf("1m")).value(&p, "X", 90_000, 100.0), None);
+        assert_eq!(Label::NextTick.value(&p, "X", 120_000, 102.0), None);
+        assert_eq!(Label::Return(tf("1m")).value(&p, "Y", 0, 100.0), None);
+        assert_eq!(Label::Return(tf("1m")).value(&p, "X", 0, 0.0), None);
+    }
+}
@@ -0,0 +1,86 @@
+use std::{env, time::Duration};
+
+use axum::{
+    http::{header, HeaderName, HeaderValue, Method},
+    Router,
+};
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, CorsLayer},
+};
+
+// Browsers may cache a preflight answer this long
+const DEFAULT_CORS_MAX_AGE_SECS: u64 = 600;
+
+/// Which browser origins may call the API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origins {
+    Any,
+    List(Vec<HeaderValue>),
+}
+
+/// Response middleware around the whole API
+#[derive(Debug, Clone)]
+pub struct HttpLayers {
+    /// `None` sends no CORS headers, so only same-origin pages can read responses
+    pub cors: Option<Origins>,
+    pub cors_max_age: Duration,
+    /// gzip / brotli, negotiated through `Accept-Encoding`
+    pub compression: bool,
+}
+
+impl HttpLayers {
+    /// `CORS_ORIGINS` (comma-separated, or `*`), `CORS_MAX_AGE_SECS`, and
+    /// `COMPRESSION=0` to turn compression off
+    pub fn from_env() -> Result<Self, String> {
+        let cors = match env::var("CORS_ORIGINS") {
+            Ok(v) if v.trim() == "*" => Some(Origins::Any),
+            Ok(v) => {
+                let origins = v
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|o| !o.is_empty())
+                    .map(|o| HeaderValue::from_str(o).map_err(|_| format!("invalid CORS origin '{o}'")))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (!origins.is_empty()).then_some(Origins::List(origins))
+            }
+            Err(_) => None,
+        };
+        let cors_max_age = env::var("CORS_MAX_AGE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CORS_MAX_AGE_SECS);
+        Ok(Self {
+            cors,
+            cors_max_age: Duration::from_secs(cors_max_age),
+            compression: env::var("COMPRESSION").map_or(true, |v| v != "0" && v != "false"),
+        })
+    }
+
+    /// Credentials travel in headers rather than cookies, so credentialed CORS is never enabled
+    fn cors_layer(&self, origins: &Origins) -> CorsLayer {
+        let allow_origin = match origins {
+            Origins::Any => AllowOrigin::any(),
+            Origins::List(list) => AllowOrigin::list(list.iter().cloned()),
+        };
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST, Method::DELETE])
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, HeaderName::from_static("x-api-key")])
+            .expose_headers([header::CONTENT_DISPOSITION, header::RETRY_AFTER])
+            .max_age(self.cors_max_age)
+    }
+
+    /// Compression skips event streams and tiny bodies, so SSE and `/ws` are unaffected
+    pub fn apply(&self, router: Router) -> Router {
+        let router = match &self.cors {
+            Some(origins) => router.layer(self.cors_layer(origins)),
+            None => router,
+        };
+        if self.compression {
+            router.layer(CompressionLayer::new().gzip(true).br(true))
+        } else {
+            router
+        }
+    }
+}
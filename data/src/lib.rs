@@ -1,2 +1,111 @@
+//! Real-time tick pipeline as a library; the binaries in `src/bin` only parse flags,
+//! load [`config::Config`] and call into it.
+//!
+//! - **ingest**: exchange trades into Redis ([`ingest`], [`source`], [`ticks`], [`seed`], [`finnhub`], [`symbols`], [`instruments`], [`relay`], [`consolidate`], [`conversion`])
+//! - **bars**: candles and per-bar analytics ([`bars`], [`kalman`], [`indicators`], …)
+//! - **storage**: Postgres snapshots, history queries and caching ([`fetcher`], [`shard`], [`precision`], [`history`], …)
+//! - **schedule**: the daily fetch / maintenance / backfill cycle ([`schedule`], [`jobs`], …)
+//! - **predict**: features, models, predictions and what acts on them ([`models`], [`predictions`], …)
+//! - **api**: HTTP, streaming and outbound integrations ([`api`], [`webhooks`], [`telegram`], …)
+
+// Service plumbing
+pub mod config;
+pub mod keys;
+pub mod error;
+pub mod cli;
+pub mod logging;
+pub mod shutdown;
+pub mod secrets;
+pub mod health;
+pub mod heartbeat;
+pub mod status;
+pub mod metrics;
+pub mod clock;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+
+// Ingest
+pub mod ingest;
+pub mod source;
+pub mod ticks;
+pub mod seed;
+pub mod finnhub;
+pub mod binance;
+pub mod symbols;
+pub mod instruments;
+pub mod relay;
+pub mod consolidate;
+pub mod conversion;
+pub mod mock_exchange;
+
+// Bars and per-bar analytics
+pub mod bars;
+pub mod kalman;
+pub mod indicators;
+pub mod volatility;
+pub mod patterns;
+pub mod anomaly;
+pub mod correlation;
+pub mod regime;
+
+// Storage
+pub mod tls;
+pub mod redis_conn;
+pub mod mirror;
 pub mod fetcher;
+pub mod shard;
+pub mod precision;
+pub mod history;
+pub mod cache;
+pub mod aggregate;
+pub mod market;
+pub mod snapshot;
+
+// Schedule
+pub mod schedule;
+pub mod jobs;
 pub mod cleaner;
+pub mod backfill;
+pub mod reconcile;
+pub mod quality;
+
+// Predict
+pub mod normalize;
+pub mod features;
+pub mod drift;
+pub mod feature_store;
+pub mod models;
+pub mod native;
+pub mod importance;
+pub mod registry;
+pub mod predictions;
+pub mod evaluation;
+pub mod backtest;
+pub mod labels;
+pub mod dataset;
+pub mod signals;
+pub mod paper;
+pub mod execution;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "torch")]
+pub mod torch;
+
+// API and outbound integrations
+pub mod alerts;
+pub mod notify;
+pub mod rules;
+pub mod webhooks;
+pub mod fanout;
+pub mod auth;
+pub mod ratelimit;
+pub mod layers;
+pub mod api;
+pub mod dashboard;
+pub mod telegram;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "openapi")]
+pub mod openapi;
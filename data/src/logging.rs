@@ -0,0 +1,264 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use tracing::level_filters::LevelFilter;
+use tracing_appender::{non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::{filter::Targets, layer::SubscriberExt, util::SubscriberInitExt, Layer, Registry};
+
+const DEFAULT_MAX_SIZE_MB: u64 = 100;
+const DEFAULT_MAX_FILES: usize = 7;
+
+/// How chatty the services are; errors and warnings always print
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum LogLevel {
+    /// Only errors and warnings
+    Warn,
+    /// Startup, lifecycle and per-cycle progress
+    Info,
+    /// Also every trade as it arrives
+    Debug,
+}
+
+impl LogLevel {
+    fn filter(self) -> LevelFilter {
+        match self {
+            Self::Warn => LevelFilter::WARN,
+            Self::Info => LevelFilter::INFO,
+            Self::Debug => LevelFilter::DEBUG,
+        }
+    }
+}
+
+/// When a log file is closed and a new one started
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rotation {
+    Daily,
+    Hourly,
+    /// Past `max_size_mb`
+    Size,
+    Never,
+}
+
+impl FromStr for Rotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "daily" => Ok(Self::Daily),
+            "hourly" => Ok(Self::Hourly),
+            "size" => Ok(Self::Size),
+            "never" => Ok(Self::Never),
+            other => Err(format!("unknown rotation '{other}' (daily, hourly, size or never)")),
+        }
+    }
+}
+
+/// The `[logging]` config section; every field has an environment override
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogSettings {
+    /// Also write `{service}.log` files here (`LOG_DIR`); stdout only when unset
+    pub dir: Option<PathBuf>,
+    /// `LOG_ROTATION`
+    pub rotation: Rotation,
+    /// Size that starts a new file with `rotation = "size"` (`LOG_MAX_SIZE_MB`)
+    pub max_size_mb: u64,
+    /// Rotated files kept besides the current one (`LOG_MAX_FILES`)
+    pub max_files: usize,
+    /// One JSON object per line, on stdout and in files (`LOG_JSON`)
+    pub json: bool,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            rotation: Rotation::Daily,
+            max_size_mb: DEFAULT_MAX_SIZE_MB,
+            max_files: DEFAULT_MAX_FILES,
+            json: false,
+        }
+    }
+}
+
+impl LogSettings {
+    /// Apply `LOG_DIR`, `LOG_ROTATION`, `LOG_MAX_SIZE_MB`, `LOG_MAX_FILES` and `LOG_JSON`,
+    /// returning the ones that do not parse
+    pub fn apply_env(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Ok(dir) = env::var("LOG_DIR") {
+            self.dir = (!dir.is_empty()).then(|| PathBuf::from(dir));
+        }
+        if let Ok(v) = env::var("LOG_ROTATION") {
+            match v.parse() {
+                Ok(r) => self.rotation = r,
+                Err(e) => errors.push(format!("LOG_ROTATION: {e}")),
+            }
+        }
+        let mut number = |name: &str, field: &mut u64| {
+            if let Ok(v) = env::var(name) {
+                match v.trim().parse() {
+                    Ok(n) => *field = n,
+                    Err(_) => errors.push(format!("{name}: cannot parse '{v}'")),
+                }
+            }
+        };
+        number("LOG_MAX_SIZE_MB", &mut self.max_size_mb);
+        let mut max_files = self.max_files as u64;
+        number("LOG_MAX_FILES", &mut max_files);
+        self.max_files = max_files as usize;
+        if let Ok(v) = env::var("LOG_JSON") {
+            match v.trim().to_lowercase().as_str() {
+                "1" | "true" | "yes" => self.json = true,
+                "0" | "false" | "no" | "" => self.json = false,
+                _ => errors.push(format!("LOG_JSON: cannot parse '{v}'")),
+            }
+        }
+        errors.extend(self.problems());
+        errors
+    }
+
+    pub fn problems(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.rotation == Rotation::Size && self.max_size_mb == 0 {
+            errors.push("logging.max_size_mb must be at least 1".to_string());
+        }
+        if self.max_files == 0 {
+            errors.push("logging.max_files must be at least 1".to_string());
+        }
+        errors
+    }
+}
+
+/// Flushes buffered file output when dropped; keep it alive for the life of `main`
+#[must_use = "logs written after the guard is dropped are lost"]
+pub struct LogGuard(#[allow(dead_code)] Option<WorkerGuard>);
+
+/// `{dir}/{service}.log`, renamed to `.1` … `.{max_files}` (oldest dropped) once it
+/// would grow past `max_bytes`
+struct SizeRotating {
+    base: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    written: u64,
+}
+
+impl SizeRotating {
+    fn open(dir: &Path, service: &str, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let base = dir.join(format!("{service}.log"));
+        let file = OpenOptions::new().create(true).append(true).open(&base)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            base,
+            max_bytes,
+            max_files,
+            file,
+            written,
+        })
+    }
+
+    fn numbered(&self, n: usize) -> PathBuf {
+        let mut name = self.base.clone().into_os_string();
+        name.push(format!(".{n}"));
+        name.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let _ = fs::remove_file(self.numbered(self.max_files));
+        for n in (1..self.max_files).rev() {
+            match fs::rename(self.numbered(n), self.numbered(n + 1)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        fs::rename(&self.base, self.numbered(1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.base)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRotating {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+fn format_layer<W>(writer: W, json: bool, ansi: bool) -> BoxedLayer
+where
+    W: for<'w> tracing_subscriber::fmt::MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer).with_target(false);
+    if json {
+        layer.json().flatten_event(true).boxed()
+    } else {
+        layer.with_ansi(ansi).boxed()
+    }
+}
+
+/// Send this process's logs to stdout and, with `settings.dir`, to rotating files.
+/// `service` names the files and is the binary's crate name (`env!("CARGO_CRATE_NAME")`);
+/// dependencies only log warnings and errors.
+pub fn init(service: &str, level: LogLevel, settings: &LogSettings) -> Result<LogGuard, String> {
+    let filter = Targets::new()
+        .with_default(LevelFilter::WARN)
+        .with_target("data_collection", level.filter())
+        .with_target(service, level.filter());
+
+    let mut layers = vec![format_layer(io::stdout, settings.json, io::stdout().is_terminal())];
+    let mut guard = None;
+    if let Some(dir) = &settings.dir {
+        let file_error = |e: &dyn std::fmt::Display| format!("cannot open log file in {}: {e}", dir.display());
+        let (writer, g) = match settings.rotation {
+            Rotation::Size => tracing_appender::non_blocking(
+                SizeRotating::open(dir, service, settings.max_size_mb * 1024 * 1024, settings.max_files)
+                    .map_err(|e| file_error(&e))?,
+            ),
+            time => {
+                let rotation = match time {
+                    Rotation::Daily => rolling::Rotation::DAILY,
+                    Rotation::Hourly => rolling::Rotation::HOURLY,
+                    _ => rolling::Rotation::NEVER,
+                };
+                let appender = rolling::Builder::new()
+                    .rotation(rotation)
+                    .filename_prefix(service)
+                    .filename_suffix("log")
+                    .max_log_files(settings.max_files + 1)
+                    .build(dir)
+                    .map_err(|e| file_error(&e))?;
+                tracing_appender::non_blocking(appender)
+            }
+        };
+        layers.push(format_layer(writer, settings.json, false));
+        guard = Some(g);
+    }
+
+    tracing_subscriber::registry()
+        .with(layers.with_filter(filter))
+        .try_init()
+        .map_err(|e| format!("logging already initialised: {e}"))?;
+    Ok(LogGuard(guard))
+}
@@ -0,0 +1,165 @@
+use std::{cmp::Ordering, env};
+
+use redis::AsyncCommands;
+use serde::Serialize;
+
+use crate::{
+    bars::{Bar, Timeframe},
+    keys::{BAR_HISTORY_PREFIX, SYMBOLS_KEY},
+    redis_conn::RedisConn,
+};
+
+const DEFAULT_WINDOWS: &str = "1h,24h";
+pub const DEFAULT_TOP: usize = 5;
+pub const MAX_TOP: usize = 50;
+
+/// `MARKET_WINDOWS` (comma separated), used when a request names none
+pub fn windows_from_env() -> String {
+    env::var("MARKET_WINDOWS").unwrap_or_else(|_| DEFAULT_WINDOWS.to_string())
+}
+
+/// One symbol over one window
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Mover {
+    pub symbol: String,
+    /// Last close in the window
+    pub price: f64,
+    /// Last close over the first bar's open, minus one
+    pub change: f64,
+    /// Base-asset volume
+    pub volume: f64,
+    /// Sum of volume × close, comparable across symbols
+    pub quote_volume: f64,
+    /// Standard deviation of bar-to-bar log returns; 0 with fewer than three bars
+    pub volatility: f64,
+    /// Bars the window was computed from; fewer than asked for when history is short
+    pub bars: usize,
+}
+
+impl Mover {
+    /// `bars` newest first, as stored
+    fn from_bars(symbol: &str, bars: &[Bar]) -> Option<Self> {
+        let newest = bars.first()?;
+        let oldest = bars.last()?;
+        let closes: Vec<f64> = bars.iter().rev().map(|b| b.close).collect();
+        let returns: Vec<f64> = closes
+            .windows(2)
+            .filter(|w| w[0] > 0.0 && w[1] > 0.0)
+            .map(|w| (w[1] / w[0]).ln())
+            .collect();
+        let volatility = if returns.len() >= 2 {
+            let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+            let var = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+            var.sqrt()
+        } else {
+            0.0
+        };
+        Some(Self {
+            symbol: symbol.to_string(),
+            price: newest.close,
+            change: if oldest.open > 0.0 { newest.close / oldest.open - 1.0 } else { 0.0 },
+            volume: bars.iter().map(|b| b.volume).sum(),
+            quote_volume: bars.iter().map(|b| b.volume * b.close).sum(),
+            volatility,
+            bars: bars.len(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WindowSummary {
+    pub window: String,
+    /// Symbols with at least one bar in the window
+    pub symbols: usize,
+    pub advancers: usize,
+    pub decliners: usize,
+    pub gainers: Vec<Mover>,
+    pub losers: Vec<Mover>,
+    /// By quote volume
+    pub most_active: Vec<Mover>,
+    pub most_volatile: Vec<Mover>,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MarketSummary {
+    /// Bars the windows are built from
+    pub tf: String,
+    pub windows: Vec<WindowSummary>,
+}
+
+fn top(movers: &[Mover], n: usize, key: impl Fn(&Mover) -> f64, keep: impl Fn(&Mover) -> bool) -> Vec<Mover> {
+    let mut out: Vec<Mover> = movers.iter().filter(|m| keep(m)).cloned().collect();
+    out.sort_by(|a, b| key(b).partial_cmp(&key(a)).unwrap_or(Ordering::Equal));
+    out.truncate(n);
+    out
+}
+
+/// Bars of `tf` in each window; each must be a whole number of them
+pub fn bar_counts(tf: Timeframe, windows: &[Timeframe]) -> Result<Vec<usize>, String> {
+    windows
+        .iter()
+        .map(|w| {
+            if w.secs < tf.secs || w.secs % tf.secs != 0 {
+                return Err(format!("window {w} is not a multiple of the {tf} bars"));
+            }
+            Ok((w.secs / tf.secs) as usize)
+        })
+        .collect()
+}
+
+/// Top `n` movers per window, from the closed `tf` bars the ingester keeps under
+/// `BAR_HISTORY_PREFIX`; `counts` comes from `bar_counts`
+pub async fn summary(
+    redis: &mut RedisConn,
+    tf: Timeframe,
+    windows: &[Timeframe],
+    counts: &[usize],
+    n: usize,
+) -> redis::RedisResult<MarketSummary> {
+    let longest = counts.iter().copied().max().unwrap_or(1);
+
+    let mut symbols: Vec<String> = redis.smembers(SYMBOLS_KEY).await?;
+    symbols.sort();
+    let mut pipe = redis::pipe();
+    for s in &symbols {
+        pipe.lrange(format!("{BAR_HISTORY_PREFIX}{s}:{tf}"), 0, longest as isize - 1);
+    }
+    let lists: Vec<Vec<String>> = pipe.query_async(redis).await?;
+    let history: Vec<(String, Vec<Bar>)> = symbols
+        .into_iter()
+        .zip(lists)
+        .map(|(s, list)| {
+            let bars = list.iter().filter_map(|j| serde_json::from_str(j).ok()).collect();
+            (s, bars)
+        })
+        .collect();
+
+    let windows = windows
+        .iter()
+        .zip(counts)
+        .map(|(window, count)| {
+            let movers: Vec<Mover> = history
+                .iter()
+                .filter_map(|(s, bars)| Mover::from_bars(s, &bars[..(*count).min(bars.len())]))
+                .collect();
+            WindowSummary {
+                window: window.to_string(),
+                symbols: movers.len(),
+                advancers: movers.iter().filter(|m| m.change > 0.0).count(),
+                decliners: movers.iter().filter(|m| m.change < 0.0).count(),
+                gainers: top(&movers, n, |m| m.change, |m| m.change > 0.0),
+                losers: top(&movers, n, |m| -m.change, |m| m.change < 0.0),
+                most_active: top(&movers, n, |m| m.quote_volume, |_| true),
+                most_volatile: top(&movers, n, |m| m.volatility, |_| true),
+            }
+        })
+        .collect();
+
+    Ok(MarketSummary {
+        tf: tf.to_string(),
+        windows,
+    })
+}
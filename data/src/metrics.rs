@@ -0,0 +1,167 @@
+use std::{collections::BTreeMap, env, time::Duration};
+
+use chrono::Utc;
+use tokio::time::Instant;
+
+use crate::{
+    keys::{METRICS_COMPONENTS_KEY, METRICS_PREFIX},
+    redis_conn::RedisConn,
+};
+
+
+/// Upper bounds for latency histograms, in ms
+pub const LATENCY_BUCKETS_MS: [f64; 14] = [
+    1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0, 30000.0,
+];
+
+const DEFAULT_FLUSH_SECS: u64 = 10;
+
+// Hops of `flow_total{hop=..}`, in the order a trade passes them on its way to Postgres.
+// Each counts since its process started; the gap between neighbours is what got lost there.
+/// WebSocket text frames the ingester received
+pub const HOP_RECEIVED: &str = "received";
+/// Trades parsed out of those frames
+pub const HOP_PARSED: &str = "parsed";
+/// Trades written to Redis, OHLCV snapshot included
+pub const HOP_WRITTEN: &str = "written";
+/// Trades summarized by the rows the fetcher inserted
+pub const HOP_COVERED: &str = "covered";
+/// Rows the fetcher inserted into Postgres
+pub const HOP_PERSISTED: &str = "persisted";
+
+/// Wall-clock now, ms since epoch
+pub fn now_ms() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+/// Cumulative-bucket histogram in the Prometheus layout
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    bounds: Vec<f64>,
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &[f64]) -> Self {
+        Self {
+            bounds: bounds.to_vec(),
+            counts: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    pub fn observe(&mut self, v: f64) {
+        if !v.is_finite() {
+            return;
+        }
+        for (bound, count) in self.bounds.iter().zip(self.counts.iter_mut()) {
+            if v <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += v;
+        self.count += 1;
+    }
+
+    /// `{name}_bucket{labels,le=..}`, `{name}_sum{labels}`, `{name}_count{labels}`
+    fn fields(&self, name: &str, labels: &str, out: &mut Vec<(String, String)>) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            out.push((format!("{name}_bucket{{{labels}{sep}le=\"{bound}\"}}"), count.to_string()));
+        }
+        out.push((format!("{name}_bucket{{{labels}{sep}le=\"+Inf\"}}"), self.count.to_string()));
+        let plain = if labels.is_empty() { String::new() } else { format!("{{{labels}}}") };
+        out.push((format!("{name}_sum{plain}"), self.sum.to_string()));
+        out.push((format!("{name}_count{plain}"), self.count.to_string()));
+    }
+}
+
+/// Process-local metrics for one component, periodically written to Redis
+pub struct Metrics {
+    component: String,
+    key: String,
+    histograms: BTreeMap<(String, String), Histogram>,
+    gauges: BTreeMap<(String, String), f64>,
+    flush_every: Duration,
+    last_flush: Instant,
+}
+
+impl Metrics {
+    /// `METRICS_FLUSH_SECS` sets how often `flush_if_due` writes
+    pub fn new(component: &str) -> Self {
+        let secs = env::var("METRICS_FLUSH_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_FLUSH_SECS);
+        Self {
+            component: component.to_string(),
+            key: format!("{METRICS_PREFIX}{component}"),
+            histograms: BTreeMap::new(),
+            gauges: BTreeMap::new(),
+            flush_every: Duration::from_secs(secs),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Record a latency in ms; `labels` is Prometheus label syntax without braces
+    pub fn observe_latency(&mut self, name: &str, labels: &str, ms: f64) {
+        self.histograms
+            .entry((name.to_string(), labels.to_string()))
+            .or_insert_with(|| Histogram::new(&LATENCY_BUCKETS_MS))
+            .observe(ms);
+    }
+
+    /// Set a point-in-time value; `labels` as in `observe_latency`
+    pub fn set_gauge(&mut self, name: &str, labels: &str, value: f64) {
+        self.gauges.insert((name.to_string(), labels.to_string()), value);
+    }
+
+    /// Add `n` to a running count; `labels` as in `observe_latency`
+    pub fn add(&mut self, name: &str, labels: &str, n: u64) {
+        *self.gauges.entry((name.to_string(), labels.to_string())).or_insert(0.0) += n as f64;
+    }
+
+    /// Count `n` more past `hop` (one of the `HOP_*`); `labels` are added to the hop's
+    pub fn count_hop(&mut self, hop: &str, labels: &str, n: u64) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        self.add("flow_total", &format!("hop=\"{hop}\"{sep}{labels}"), n);
+    }
+
+    pub fn fields(&self) -> Vec<(String, String)> {
+        let mut out = Vec::new();
+        for ((name, labels), h) in &self.histograms {
+            h.fields(name, labels, &mut out);
+        }
+        for ((name, labels), value) in &self.gauges {
+            let name = if labels.is_empty() { name.clone() } else { format!("{name}{{{labels}}}") };
+            out.push((name, value.to_string()));
+        }
+        out
+    }
+
+    /// Write every metric, plus the connection's own `redis_*` counters,
+    /// when the flush interval has passed
+    pub async fn flush_if_due(&mut self, redis: &mut RedisConn) -> redis::RedisResult<()> {
+        if self.last_flush.elapsed() < self.flush_every {
+            return Ok(());
+        }
+        self.flush(redis).await
+    }
+
+    /// Write now, e.g. on the way out, registering the component in `METRICS_COMPONENTS_KEY`
+    pub async fn flush(&mut self, redis: &mut RedisConn) -> redis::RedisResult<()> {
+        self.last_flush = Instant::now();
+        let mut fields = self.fields();
+        fields.extend(redis.stats().fields());
+        redis::pipe()
+            .hset_multiple(&self.key, &fields)
+            .ignore()
+            .sadd(METRICS_COMPONENTS_KEY, &self.component)
+            .ignore()
+            .query_async(redis)
+            .await
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use redis::{Arg, Cmd, Pipeline};
+use tokio::{sync::mpsc, time::Instant};
+use tracing::{info, warn};
+
+use crate::{
+    error::StoreError,
+    redis_conn::{RedisClient, RedisConn},
+    tls::TlsSettings,
+};
+
+/// Writes waiting for the mirror before new ones are dropped
+const MIRROR_QUEUE: usize = 10_000;
+/// Queued writes sent to the mirror per pipeline
+const MIRROR_BATCH: usize = 256;
+
+/// Commands copied to the mirror; reads, scans and connection commands are not
+const WRITE_COMMANDS: [&str; 26] = [
+    "SET", "SETEX", "PSETEX", "MSET", "DEL", "UNLINK", "EXPIRE", "PEXPIRE", "HSET", "HMSET", "HDEL", "HINCRBY",
+    "HINCRBYFLOAT", "INCR", "INCRBY", "INCRBYFLOAT", "LPUSH", "RPUSH", "LTRIM", "SADD", "SREM", "ZADD", "ZREM",
+    "ZREMRANGEBYSCORE", "XADD", "PUBLISH",
+];
+
+fn is_write(cmd: &Cmd) -> bool {
+    match cmd.args_iter().next() {
+        Some(Arg::Simple(name)) => WRITE_COMMANDS.iter().any(|w| w.as_bytes().eq_ignore_ascii_case(name)),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    failed: AtomicU64,
+    lag_ms: AtomicU64,
+}
+
+/// What a [`Mirror`] has done since it started
+#[derive(Debug, Clone, Copy)]
+pub struct MirrorStats {
+    /// Writes applied on the mirror
+    pub sent: u64,
+    /// Writes discarded because the queue was full
+    pub dropped: u64,
+    /// Writes the mirror rejected or never received (it was unreachable)
+    pub failed: u64,
+    /// Writes waiting to be sent
+    pub queued: u64,
+    /// Primary-to-mirror delay of the last batch applied
+    pub lag_ms: u64,
+}
+
+impl MirrorStats {
+    pub fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("redis_mirror_sent_total".to_string(), self.sent.to_string()),
+            ("redis_mirror_dropped_total".to_string(), self.dropped.to_string()),
+            ("redis_mirror_failures_total".to_string(), self.failed.to_string()),
+            ("redis_mirror_queued".to_string(), self.queued.to_string()),
+            ("redis_mirror_lag_ms".to_string(), self.lag_ms.to_string()),
+        ]
+    }
+}
+
+/// Copies a connection's successful writes to a secondary Redis (e.g. in another region)
+/// from a background task, so consumers there keep seeing live keys and messages when the
+/// primary's region is down. The primary never waits for it: when the mirror is slow or
+/// unreachable writes queue up to `MIRROR_QUEUE` and the rest are dropped, and failed
+/// batches are not retried. Transactions are replayed without `MULTI`/`EXEC`.
+#[derive(Clone)]
+pub struct Mirror {
+    tx: mpsc::Sender<(Instant, Cmd)>,
+    counters: Arc<Counters>,
+}
+
+impl Mirror {
+    /// Start the writer; it connects in the background, so a mirror that is down at
+    /// startup only means writes are dropped until it is up
+    pub fn spawn(client: RedisClient) -> Self {
+        let (tx, rx) = mpsc::channel(MIRROR_QUEUE);
+        let counters = Arc::new(Counters::default());
+        info!("🪞 Mirroring Redis writes to {}", client.describe());
+        tokio::spawn(run(client, rx, counters.clone()));
+        Self { tx, counters }
+    }
+
+    /// [`Mirror::spawn`] on `url` under the TLS settings
+    pub fn open(url: &str, settings: &TlsSettings) -> Result<Self, StoreError> {
+        Ok(Self::spawn(RedisClient::open_with(url, settings)?))
+    }
+
+    pub fn stats(&self) -> MirrorStats {
+        let c = &self.counters;
+        MirrorStats {
+            sent: c.sent.load(Ordering::Relaxed),
+            dropped: c.dropped.load(Ordering::Relaxed),
+            failed: c.failed.load(Ordering::Relaxed),
+            queued: (self.tx.max_capacity() - self.tx.capacity()) as u64,
+            lag_ms: c.lag_ms.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Queue `cmd` if it writes
+    pub(crate) fn offer(&self, cmd: &Cmd) {
+        if is_write(cmd) && self.tx.try_send((Instant::now(), cmd.clone())).is_err() {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Queue the pipeline's writes
+    pub(crate) fn offer_pipeline(&self, pipe: &Pipeline) {
+        for cmd in pipe.cmd_iter() {
+            self.offer(cmd);
+        }
+    }
+}
+
+async fn run(client: RedisClient, mut rx: mpsc::Receiver<(Instant, Cmd)>, counters: Arc<Counters>) {
+    let mut conn: RedisConn = client.connect_with_retry().await;
+    let mut batch = Vec::with_capacity(MIRROR_BATCH);
+    let mut healthy = true;
+    while rx.recv_many(&mut batch, MIRROR_BATCH).await > 0 {
+        let oldest = batch[0].0;
+        let count = batch.len() as u64;
+        let mut pipe = redis::pipe();
+        for (_, cmd) in batch.drain(..) {
+            pipe.add_command(cmd).ignore();
+        }
+        match pipe.query_async::<()>(&mut conn).await {
+            Ok(()) => {
+                counters.sent.fetch_add(count, Ordering::Relaxed);
+                counters.lag_ms.store(oldest.elapsed().as_millis() as u64, Ordering::Relaxed);
+                if !healthy {
+                    info!("🪞 Redis mirror writes resumed");
+                    healthy = true;
+                }
+            }
+            Err(e) => {
+                counters.failed.fetch_add(count, Ordering::Relaxed);
+                if healthy {
+                    warn!("⚠️ Redis mirror write failed ({e}); dropping writes until it recovers");
+                    healthy = false;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,455 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{SinkExt, StreamExt, stream::SplitSink};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    time::sleep,
+};
+use tokio_tungstenite::{
+    WebSocketStream, accept_hdr_async,
+    tungstenite::{
+        handshake::server::{Request, Response},
+        protocol::Message,
+    },
+};
+use tracing::{info, warn};
+
+use crate::metrics::now_ms;
+
+const START_PRICE: f64 = 100.0;
+/// Largest move between two trades of a symbol
+const MAX_STEP_BPS: f64 = 5.0;
+/// How often a connection without subscriptions checks again
+const SUBSCRIBE_POLL: Duration = Duration::from_millis(50);
+
+/// Wire format the mock speaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// `{"type":"subscribe","symbol":…}` in, `{"type":"trade","data":[…]}` out
+    Finnhub,
+    /// `{"method":"SUBSCRIBE","params":["btcusdt@trade"]}` or a `/ws/btcusdt@trade` path in,
+    /// one `{"e":"trade",…}` per trade out
+    Binance,
+}
+
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "finnhub" => Ok(Self::Finnhub),
+            "binance" => Ok(Self::Binance),
+            other => Err(format!("unknown protocol '{other}' (expected finnhub or binance)")),
+        }
+    }
+}
+
+/// Ways a frame can be wrong
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Malformed {
+    /// Cut off mid-object
+    Truncated,
+    /// A trade without price or time
+    MissingFields,
+    /// Strings where numbers belong
+    WrongTypes,
+    NegativePrice,
+    /// Outside what a timestamp can represent
+    BadTimestamp,
+    /// Valid JSON of a message type the client does not handle
+    UnknownType,
+    /// A binary frame
+    Binary,
+}
+
+/// One instruction of a scenario
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum Step {
+    /// `count` trades per subscribed symbol, one every `interval_ms`
+    Trades {
+        count: u32,
+        #[serde(default)]
+        interval_ms: u64,
+    },
+    /// `count` trades per symbol at once: one frame on Finnhub, back to back on Binance
+    Burst { count: u32 },
+    /// One bad frame per subscribed symbol
+    Malformed { kind: Malformed },
+    /// A frame sent verbatim
+    Raw { text: String },
+    Pause { ms: u64 },
+    /// Finnhub's `{"type":"ping"}`, a ping frame on Binance
+    Ping,
+    /// Drop the connection without a close frame; the next connection resumes after this step
+    Disconnect,
+    /// Close frame, as an exchange sends before maintenance; the next connection resumes after it
+    Close,
+}
+
+/// Steps played to whoever is connected, continuing across reconnects
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scenario {
+    pub steps: Vec<Step>,
+    /// Start over after the last step instead of idling
+    #[serde(default)]
+    pub repeat: bool,
+}
+
+impl Scenario {
+    /// Built-in scenarios: `steady`, `burst`, `reconnect`, `malformed` and `mixed`
+    pub fn builtin(name: &str) -> Option<Self> {
+        use Step::*;
+        let trades = |count, interval_ms| Trades { count, interval_ms };
+        let steps = match name {
+            "steady" => vec![trades(1, 500)],
+            "burst" => vec![trades(5, 500), Burst { count: 500 }, Pause { ms: 2000 }],
+            "reconnect" => vec![trades(5, 200), Disconnect, trades(5, 200), Close],
+            "malformed" => vec![
+                trades(2, 200),
+                Malformed { kind: self::Malformed::Truncated },
+                Malformed { kind: self::Malformed::MissingFields },
+                Malformed { kind: self::Malformed::WrongTypes },
+                Malformed { kind: self::Malformed::NegativePrice },
+                Malformed { kind: self::Malformed::BadTimestamp },
+                Malformed { kind: self::Malformed::UnknownType },
+                Malformed { kind: self::Malformed::Binary },
+                trades(2, 200),
+            ],
+            "mixed" => vec![
+                trades(10, 100),
+                Ping,
+                Burst { count: 200 },
+                Malformed { kind: self::Malformed::Truncated },
+                trades(10, 100),
+                Disconnect,
+                Malformed { kind: self::Malformed::NegativePrice },
+                trades(10, 100),
+                Close,
+            ],
+            _ => return None,
+        };
+        Some(Self { steps, repeat: true })
+    }
+
+    /// A built-in name, or a JSON file of `{"steps": [{"step": "trades", "count": 5}, …], "repeat": true}`
+    pub fn load(name_or_path: &str) -> Result<Self, String> {
+        if let Some(scenario) = Self::builtin(name_or_path) {
+            return Ok(scenario);
+        }
+        let text = std::fs::read_to_string(name_or_path)
+            .map_err(|e| format!("'{name_or_path}' is neither a built-in scenario nor a readable file: {e}"))?;
+        let scenario: Self = serde_json::from_str(&text).map_err(|e| format!("invalid scenario {name_or_path}: {e}"))?;
+        if scenario.steps.is_empty() {
+            return Err(format!("scenario {name_or_path} has no steps"));
+        }
+        Ok(scenario)
+    }
+}
+
+/// State shared by all connections, so prices and the scenario carry on across reconnects
+struct Shared {
+    next_step: usize,
+    prices: HashMap<String, f64>,
+    rng: u64,
+    trade_id: u64,
+}
+
+impl Shared {
+    /// xorshift; deterministic for a given seed
+    fn random(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn next_price(&mut self, symbol: &str) -> f64 {
+        let step = (self.random() * 2.0 - 1.0) * MAX_STEP_BPS / 10_000.0;
+        let price = self.prices.entry(symbol.to_string()).or_insert(START_PRICE);
+        *price *= 1.0 + step;
+        *price
+    }
+}
+
+/// Exchange WebSocket double for end-to-end tests of the ingestion path: streams made-up
+/// trades for whatever the client subscribes to, following a [`Scenario`]
+#[derive(Clone)]
+pub struct MockExchange {
+    protocol: Protocol,
+    scenario: Arc<Scenario>,
+    shared: Arc<Mutex<Shared>>,
+}
+
+type Sink = SplitSink<WebSocketStream<TcpStream>, Message>;
+
+impl MockExchange {
+    pub fn new(protocol: Protocol, scenario: Scenario, seed: u64) -> Self {
+        Self {
+            protocol,
+            scenario: Arc::new(scenario),
+            shared: Arc::new(Mutex::new(Shared {
+                next_step: 0,
+                prices: HashMap::new(),
+                // xorshift never leaves zero
+                rng: seed.max(1),
+                trade_id: 0,
+            })),
+        }
+    }
+
+    /// Bind `addr` (port 0 for any) and serve in the background; returns the bound address
+    pub async fn spawn(self, addr: &str) -> std::io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr).await?;
+        let local = listener.local_addr()?;
+        tokio::spawn(self.serve(listener));
+        Ok(local)
+    }
+
+    pub async fn serve(self, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    let exchange = self.clone();
+                    tokio::spawn(async move {
+                        exchange.handle(stream, peer).await;
+                    });
+                }
+                Err(e) => warn!("⚠️ Mock exchange accept failed: {e}"),
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // The handshake callback's error type is tungstenite's, not ours to shrink
+    #[allow(clippy::result_large_err)]
+    async fn handle(&self, stream: TcpStream, peer: SocketAddr) {
+        let mut path = String::new();
+        let ws = match accept_hdr_async(stream, |req: &Request, resp: Response| {
+            path = req.uri().path().to_string();
+            Ok(resp)
+        })
+        .await
+        {
+            Ok(ws) => ws,
+            Err(e) => {
+                warn!("⚠️ Mock exchange handshake with {peer} failed: {e}");
+                return;
+            }
+        };
+        info!("🔌 Mock exchange: {peer} connected");
+
+        // Binance streams named in the path, e.g. /ws/btcusdt@trade/ethusdt@trade
+        let symbols: Arc<Mutex<BTreeSet<String>>> = Arc::new(Mutex::new(
+            path.strip_prefix("/ws/")
+                .map(|streams| streams.split('/').filter_map(binance_symbol).collect())
+                .unwrap_or_default(),
+        ));
+        let (mut sink, mut source) = ws.split();
+
+        let protocol = self.protocol;
+        let subscribed = symbols.clone();
+        let (replies_tx, mut replies) = tokio::sync::mpsc::unbounded_channel();
+        let reader = tokio::spawn(async move {
+            while let Some(Ok(msg)) = source.next().await {
+                let Message::Text(text) = msg else { continue };
+                if let Some(reply) = on_client_message(protocol, &text, &subscribed) {
+                    let _ = replies_tx.send(reply);
+                }
+            }
+        });
+
+        let steps = self.scenario.steps.len();
+        loop {
+            while let Ok(reply) = replies.try_recv() {
+                if sink.send(Message::Text(reply)).await.is_err() {
+                    break;
+                }
+            }
+            if reader.is_finished() {
+                break;
+            }
+            let current: Vec<String> = symbols.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect();
+            if current.is_empty() {
+                sleep(SUBSCRIBE_POLL).await;
+                continue;
+            }
+
+            let index = {
+                let mut shared = self.lock();
+                if shared.next_step >= steps && self.scenario.repeat {
+                    shared.next_step = 0;
+                }
+                shared.next_step += 1;
+                shared.next_step - 1
+            };
+            // Played through: stay connected and quiet
+            if index >= steps {
+                self.lock().next_step = steps;
+                sleep(SUBSCRIBE_POLL).await;
+                continue;
+            }
+            match self.play(&mut sink, &self.scenario.steps[index], &current).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    info!("✂️ Mock exchange: ending connection to {peer} (step {index})");
+                    break;
+                }
+                Err(e) => {
+                    info!("🔌 Mock exchange: {peer} went away: {e}");
+                    break;
+                }
+            }
+        }
+        reader.abort();
+    }
+
+    /// Play one step; `Ok(false)` ends the connection
+    async fn play(&self, sink: &mut Sink, step: &Step, symbols: &[String]) -> Result<bool, String> {
+        match step {
+            Step::Trades { count, interval_ms } => {
+                for _ in 0..*count {
+                    for frame in self.trade_frames(symbols, 1) {
+                        send(sink, Message::Text(frame)).await?;
+                    }
+                    // After every trade, so a repeated one-trade step is paced too
+                    if *interval_ms > 0 {
+                        sleep(Duration::from_millis(*interval_ms)).await;
+                    }
+                }
+            }
+            Step::Burst { count } => {
+                for frame in self.trade_frames(symbols, *count) {
+                    send(sink, Message::Text(frame)).await?;
+                }
+            }
+            Step::Malformed { kind } => {
+                for symbol in symbols {
+                    let price = self.lock().next_price(symbol);
+                    send(sink, malformed(self.protocol, *kind, symbol, price)).await?;
+                }
+            }
+            Step::Raw { text } => send(sink, Message::Text(text.clone())).await?,
+            Step::Pause { ms } => sleep(Duration::from_millis(*ms)).await,
+            Step::Ping => match self.protocol {
+                Protocol::Finnhub => send(sink, Message::Text(r#"{"type":"ping"}"#.to_string())).await?,
+                Protocol::Binance => send(sink, Message::Ping(Vec::new())).await?,
+            },
+            Step::Disconnect => return Ok(false),
+            Step::Close => {
+                send(sink, Message::Close(None)).await?;
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// `count` trades for each symbol, framed the way the exchange would
+    fn trade_frames(&self, symbols: &[String], count: u32) -> Vec<String> {
+        let mut shared = self.lock();
+        let ts = now_ms();
+        match self.protocol {
+            Protocol::Finnhub => {
+                let data: Vec<_> = (0..count)
+                    .flat_map(|_| symbols.iter())
+                    .map(|s| json!({ "s": s, "p": shared.next_price(s), "v": 0.5, "t": ts }))
+                    .collect();
+                vec![json!({ "type": "trade", "data": data }).to_string()]
+            }
+            Protocol::Binance => (0..count)
+                .flat_map(|_| symbols.iter())
+                .map(|s| {
+                    let price = shared.next_price(s);
+                    shared.trade_id += 1;
+                    binance_trade(s, &format!("{price:.8}"), "0.5", ts, shared.trade_id).to_string()
+                })
+                .collect(),
+        }
+    }
+}
+
+async fn send(sink: &mut Sink, msg: Message) -> Result<(), String> {
+    sink.send(msg).await.map_err(|e| e.to_string())
+}
+
+fn binance_trade(symbol: &str, price: &str, qty: &str, ts: i64, id: u64) -> serde_json::Value {
+    json!({ "e": "trade", "E": ts, "s": symbol, "t": id, "p": price, "q": qty, "T": ts, "m": false })
+}
+
+/// `btcusdt@trade` → `BTCUSDT`
+fn binance_symbol(stream: &str) -> Option<String> {
+    let (symbol, kind) = stream.split_once('@')?;
+    (kind == "trade" && !symbol.is_empty()).then(|| symbol.to_ascii_uppercase())
+}
+
+/// Track (un)subscriptions; returns the reply the exchange would send, if any
+fn on_client_message(protocol: Protocol, text: &str, symbols: &Mutex<BTreeSet<String>>) -> Option<String> {
+    let msg: serde_json::Value = serde_json::from_str(text).ok()?;
+    let mut symbols = symbols.lock().unwrap_or_else(|e| e.into_inner());
+    match protocol {
+        Protocol::Finnhub => {
+            let symbol = msg["symbol"].as_str()?.to_string();
+            match msg["type"].as_str()? {
+                "subscribe" => symbols.insert(symbol),
+                "unsubscribe" => symbols.remove(&symbol),
+                _ => false,
+            };
+            None
+        }
+        Protocol::Binance => {
+            let streams = msg["params"].as_array()?.iter().filter_map(|p| binance_symbol(p.as_str()?));
+            match msg["method"].as_str()? {
+                "SUBSCRIBE" => symbols.extend(streams),
+                "UNSUBSCRIBE" => streams.for_each(|s| {
+                    symbols.remove(&s);
+                }),
+                _ => {}
+            }
+            Some(json!({ "result": null, "id": msg["id"] }).to_string())
+        }
+    }
+}
+
+fn malformed(protocol: Protocol, kind: Malformed, symbol: &str, price: f64) -> Message {
+    let ts = now_ms();
+    let text = match (protocol, kind) {
+        (_, Malformed::Binary) => return Message::Binary(vec![0xde, 0xad, 0xbe, 0xef]),
+        (Protocol::Finnhub, Malformed::Truncated) => format!(r#"{{"type":"trade","data":[{{"s":"{symbol}","p":{price}"#),
+        (Protocol::Finnhub, Malformed::MissingFields) => json!({ "type": "trade", "data": [{ "s": symbol }] }).to_string(),
+        (Protocol::Finnhub, Malformed::WrongTypes) => {
+            json!({ "type": "trade", "data": [{ "s": symbol, "p": price.to_string(), "t": "now" }] }).to_string()
+        }
+        (Protocol::Finnhub, Malformed::NegativePrice) => {
+            json!({ "type": "trade", "data": [{ "s": symbol, "p": -price, "v": 0.5, "t": ts }] }).to_string()
+        }
+        (Protocol::Finnhub, Malformed::BadTimestamp) => {
+            json!({ "type": "trade", "data": [{ "s": symbol, "p": price, "v": 0.5, "t": i64::MAX }] }).to_string()
+        }
+        (Protocol::Finnhub, Malformed::UnknownType) => json!({ "type": "news", "data": [{ "s": symbol }] }).to_string(),
+        (Protocol::Binance, Malformed::Truncated) => format!(r#"{{"e":"trade","s":"{symbol}","p":"{price}"#),
+        (Protocol::Binance, Malformed::MissingFields) => json!({ "e": "trade", "s": symbol }).to_string(),
+        (Protocol::Binance, Malformed::WrongTypes) => {
+            json!({ "e": "trade", "s": symbol, "p": price, "q": true, "T": "now" }).to_string()
+        }
+        (Protocol::Binance, Malformed::NegativePrice) => {
+            binance_trade(symbol, &format!("{:.8}", -price), "0.5", ts, 0).to_string()
+        }
+        (Protocol::Binance, Malformed::BadTimestamp) => {
+            binance_trade(symbol, &format!("{price:.8}"), "0.5", i64::MAX, 0).to_string()
+        }
+        (Protocol::Binance, Malformed::UnknownType) => json!({ "e": "kline", "s": symbol }).to_string(),
+    };
+    Message::Text(text)
+}
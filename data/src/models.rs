@@ -0,0 +1,706 @@
+use std::{
+    env,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use tracing::{error, info, warn};
+use crate::importance;
+
+/// A return predictor over fixed-schema feature vectors
+pub trait Model {
+    fn name(&self) -> &str;
+
+    fn version(&self) -> String;
+
+    /// Predicted next-bar log return
+    fn predict(&self, x: &[f64]) -> f64;
+
+    /// Learn from the realized return for `x`; no-op for offline-trained models
+    fn update(&mut self, _x: &[f64], _y: f64) {}
+
+    /// Standard deviation of the predicted return, for models that can estimate it
+    fn std_dev(&self, _x: &[f64]) -> Option<f64> {
+        None
+    }
+
+    /// Non-negative weight of each feature (`FEATURE_NAMES` order) in the model's output.
+    /// `std` is each raw feature's live spread, for models fitted on unscaled inputs.
+    fn importance(&self, _std: &[f64]) -> Option<Vec<f64>> {
+        None
+    }
+
+    /// Per-member predictions for composite models; empty for single models
+    fn members(&self, _x: &[f64]) -> Vec<MemberPrediction> {
+        Vec::new()
+    }
+}
+
+/// One ensemble member's output and its current weight
+#[derive(Debug, Clone)]
+pub struct MemberPrediction {
+    pub label: String,
+    pub version: String,
+    pub predicted_return: f64,
+    pub std_dev: Option<f64>,
+    pub weight: f64,
+}
+
+const DEFAULT_MODEL_POLL: Duration = Duration::from_secs(30);
+const DEFAULT_FORGETTING: f64 = 0.995;
+const DEFAULT_RIDGE: f64 = 1.0;
+const DEFAULT_ENSEMBLE_ALPHA: f64 = 0.05;
+// Keeps inverse-error weights finite for a member with no error yet
+const MSE_FLOOR: f64 = 1e-12;
+// Weight of the newest squared innovation in the RLS noise estimate
+const NOISE_ALPHA: f64 = 0.02;
+// RLS updates before its uncertainty is reported
+const MIN_UNCERTAINTY_UPDATES: u64 = 20;
+
+/// Recursive least squares with exponential forgetting and a ridge prior.
+/// Weights include a bias term at index 0.
+#[derive(Debug, Clone)]
+pub struct Rls {
+    forgetting: f64,
+    weights: Vec<f64>,
+    /// Inverse covariance estimate, (n+1) x (n+1)
+    p: Vec<Vec<f64>>,
+    /// EWMA of squared a-priori errors: the observation noise variance
+    noise: f64,
+    updates: u64,
+}
+
+impl Rls {
+    pub fn new(n_features: usize) -> Self {
+        Self::with_params(n_features, DEFAULT_FORGETTING, DEFAULT_RIDGE)
+    }
+
+    /// `forgetting` in (0, 1]; `ridge` > 0 is the L2 prior strength (P0 = I / ridge)
+    pub fn with_params(n_features: usize, forgetting: f64, ridge: f64) -> Self {
+        let n = n_features + 1;
+        let p = (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 1.0 / ridge } else { 0.0 }).collect())
+            .collect();
+        Self {
+            forgetting,
+            weights: vec![0.0; n],
+            p,
+            noise: 0.0,
+            updates: 0,
+        }
+    }
+
+    pub fn updates(&self) -> u64 {
+        self.updates
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    fn augment(x: &[f64]) -> Vec<f64> {
+        std::iter::once(1.0).chain(x.iter().copied()).collect()
+    }
+}
+
+impl Model for Rls {
+    fn name(&self) -> &str {
+        "rls"
+    }
+
+    fn version(&self) -> String {
+        format!("online-{}", self.updates)
+    }
+
+    fn predict(&self, x: &[f64]) -> f64 {
+        Self::augment(x)
+            .iter()
+            .zip(&self.weights)
+            .map(|(a, w)| a * w)
+            .sum()
+    }
+
+    fn update(&mut self, x: &[f64], y: f64) {
+        let x = Self::augment(x);
+        if x.len() != self.weights.len() || !y.is_finite() || x.iter().any(|v| !v.is_finite()) {
+            return;
+        }
+
+        // px = P x, denom = λ + xᵀ P x
+        let px: Vec<f64> = self
+            .p
+            .iter()
+            .map(|row| row.iter().zip(&x).map(|(a, b)| a * b).sum())
+            .collect();
+        let denom = self.forgetting + x.iter().zip(&px).map(|(a, b)| a * b).sum::<f64>();
+        if denom.abs() < f64::EPSILON {
+            return;
+        }
+        let gain: Vec<f64> = px.iter().map(|v| v / denom).collect();
+
+        let err = y - self.predict(&x[1..]);
+        self.noise = if self.updates == 0 { err * err } else { self.noise + NOISE_ALPHA * (err * err - self.noise) };
+        for (w, g) in self.weights.iter_mut().zip(&gain) {
+            *w += g * err;
+        }
+
+        // P = (P - k xᵀP) / λ ; xᵀP == pxᵀ since P is symmetric
+        for (row, g) in self.p.iter_mut().zip(&gain) {
+            for (pij, pxj) in row.iter_mut().zip(&px) {
+                *pij = (*pij - g * pxj) / self.forgetting;
+            }
+        }
+        self.updates += 1;
+    }
+
+    /// Noise plus parameter uncertainty: σ²(1 + xᵀ P x)
+    fn std_dev(&self, x: &[f64]) -> Option<f64> {
+        let x = Self::augment(x);
+        if self.updates < MIN_UNCERTAINTY_UPDATES || x.len() != self.weights.len() {
+            return None;
+        }
+        let xpx: f64 = self
+            .p
+            .iter()
+            .zip(&x)
+            .map(|(row, xi)| xi * row.iter().zip(&x).map(|(a, b)| a * b).sum::<f64>())
+            .sum();
+        Some((self.noise * (1.0 + xpx.max(0.0))).sqrt())
+    }
+
+    /// |wᵢ|·σᵢ: the effect of a one-standard-deviation move in each feature
+    fn importance(&self, std: &[f64]) -> Option<Vec<f64>> {
+        if self.updates == 0 || std.len() + 1 != self.weights.len() {
+            return None;
+        }
+        Some(self.weights[1..].iter().zip(std).map(|(w, s)| (w * s).abs()).collect())
+    }
+}
+
+/// How ensemble members are combined
+#[derive(Debug, Clone)]
+pub enum Weighting {
+    /// Fixed weights, normalized to sum to one
+    Fixed(Vec<f64>),
+    /// Inverse of each member's EWMA squared error; `alpha` weights the newest error
+    Adaptive { alpha: f64 },
+}
+
+/// Weighted average of several models run side by side on the same features
+pub struct Ensemble {
+    members: Vec<(String, Box<dyn Model>)>,
+    weighting: Weighting,
+    mse: Vec<f64>,
+}
+
+impl Ensemble {
+    pub fn new(members: Vec<(String, Box<dyn Model>)>, weighting: Weighting) -> Self {
+        let mse = vec![0.0; members.len()];
+        Self {
+            members,
+            weighting,
+            mse,
+        }
+    }
+
+    pub fn weights(&self) -> Vec<f64> {
+        let raw: Vec<f64> = match &self.weighting {
+            Weighting::Fixed(w) => w.iter().map(|v| v.max(0.0)).collect(),
+            Weighting::Adaptive { .. } => self.mse.iter().map(|e| 1.0 / e.max(MSE_FLOOR)).collect(),
+        };
+        let total: f64 = raw.iter().sum();
+        if total > 0.0 && total.is_finite() {
+            raw.iter().map(|w| w / total).collect()
+        } else {
+            vec![1.0 / self.members.len().max(1) as f64; self.members.len()]
+        }
+    }
+}
+
+impl Model for Ensemble {
+    fn name(&self) -> &str {
+        "ensemble"
+    }
+
+    fn version(&self) -> String {
+        self.members
+            .iter()
+            .map(|(label, m)| format!("{label}@{}", m.version()))
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+
+    fn predict(&self, x: &[f64]) -> f64 {
+        self.members
+            .iter()
+            .zip(self.weights())
+            .map(|((_, m), w)| w * m.predict(x))
+            .sum()
+    }
+
+    fn update(&mut self, x: &[f64], y: f64) {
+        if let Weighting::Adaptive { alpha } = self.weighting
+            && y.is_finite()
+        {
+            for ((_, m), mse) in self.members.iter().zip(self.mse.iter_mut()) {
+                let err = m.predict(x) - y;
+                *mse += alpha * (err * err - *mse);
+            }
+        }
+        for (_, m) in self.members.iter_mut() {
+            m.update(x, y);
+        }
+    }
+
+    /// Mixture spread: Σ wᵢ (σᵢ² + (μᵢ − μ)²), with each member's adaptive error
+    /// standing in for σᵢ when it has no estimate; `None` when no member has either
+    fn std_dev(&self, x: &[f64]) -> Option<f64> {
+        let weights = self.weights();
+        let preds: Vec<f64> = self.members.iter().map(|(_, m)| m.predict(x)).collect();
+        let mean: f64 = preds.iter().zip(&weights).map(|(p, w)| p * w).sum();
+        let mut known = false;
+        let mut var = 0.0;
+        for (((_, m), (p, w)), mse) in self.members.iter().zip(preds.iter().zip(&weights)).zip(&self.mse) {
+            let member_var = match (m.std_dev(x), &self.weighting) {
+                (Some(sd), _) => Some(sd * sd),
+                (None, Weighting::Adaptive { .. }) if *mse > 0.0 => Some(*mse),
+                _ => None,
+            };
+            known |= member_var.is_some();
+            var += w * (member_var.unwrap_or(0.0) + (p - mean).powi(2));
+        }
+        known.then(|| var.sqrt())
+    }
+
+    /// Members' importance shares weighted like their predictions
+    fn importance(&self, std: &[f64]) -> Option<Vec<f64>> {
+        let mut total: Option<Vec<f64>> = None;
+        for ((_, m), w) in self.members.iter().zip(self.weights()) {
+            let Some(member) = m.importance(std).as_deref().and_then(importance::shares) else {
+                continue;
+            };
+            let sum = total.get_or_insert_with(|| vec![0.0; member.len()]);
+            for (t, v) in sum.iter_mut().zip(member) {
+                *t += w * v;
+            }
+        }
+        total
+    }
+
+    fn members(&self, x: &[f64]) -> Vec<MemberPrediction> {
+        self.members
+            .iter()
+            .zip(self.weights())
+            .map(|((label, m), weight)| MemberPrediction {
+                label: label.clone(),
+                version: m.version(),
+                predicted_return: m.predict(x),
+                std_dev: m.std_dev(x),
+                weight,
+            })
+            .collect()
+    }
+}
+
+/// Which model each symbol's predictor is built from
+#[derive(Clone)]
+pub enum ModelSpec {
+    /// Per-symbol online RLS
+    Online,
+    /// Per-symbol online RLS with a custom forgetting factor
+    Rls(f64),
+    /// Shared offline-trained ONNX model
+    #[cfg(feature = "onnx")]
+    Onnx(crate::onnx::OnnxModel),
+    /// Shared model fitted by the `train` binary
+    Native(crate::native::NativeModel),
+    /// Shared user-supplied Python model
+    #[cfg(feature = "python")]
+    Python(crate::python::PyModel),
+    /// Shared PyTorch-trained TorchScript model
+    #[cfg(feature = "torch")]
+    Torch(crate::torch::TorchModel),
+    /// Labelled members combined by `Weighting`
+    Ensemble {
+        members: Vec<(String, ModelSpec)>,
+        weighting: Weighting,
+    },
+}
+
+impl ModelSpec {
+    /// `ENSEMBLE_MODELS` selects an ensemble (see `ensemble_from_env`), `MODEL_PATH` an ONNX
+    /// model (requires the `onnx` feature), a `train` artifact for `.json` files, a TorchScript
+    /// model for `.pt` files (requires the `torch` feature) or a Python model for `.py` files
+    /// (requires the `python` feature), otherwise online RLS
+    pub fn from_env(n_features: usize) -> Self {
+        if let Ok(list) = env::var("ENSEMBLE_MODELS") {
+            let spec = Self::ensemble_from_env(&list, n_features).unwrap_or_else(|e| panic!("❌ {e}"));
+            if let Self::Ensemble { members, weighting } = &spec {
+                let labels: Vec<&str> = members.iter().map(|(l, _)| l.as_str()).collect();
+                info!("🧠 Ensemble of [{}] with {weighting:?} weighting", labels.join(", "));
+            }
+            return spec;
+        }
+
+        let Ok(path) = env::var("MODEL_PATH") else {
+            return Self::Online;
+        };
+
+        if path.ends_with(".json") {
+            let model = crate::native::NativeModel::load(std::path::Path::new(&path), n_features)
+                .unwrap_or_else(|e| panic!("❌ {e}"));
+            info!("🧠 Loaded native model {path} (version {})", model.version());
+            return Self::Native(model);
+        }
+
+        if path.ends_with(".py") {
+            #[cfg(feature = "python")]
+            {
+                let model = crate::python::PyModel::load(std::path::Path::new(&path), n_features)
+                    .unwrap_or_else(|e| panic!("❌ {e}"));
+                info!("🐍 Loaded Python model {path} (version {})", model.version());
+                return Self::Python(model);
+            }
+
+            #[cfg(not(feature = "python"))]
+            {
+                warn!("⚠️ MODEL_PATH={path} ignored: built without the `python` feature, using online RLS");
+                return Self::Online;
+            }
+        }
+
+        if path.ends_with(".pt") {
+            #[cfg(feature = "torch")]
+            {
+                let model = crate::torch::TorchModel::load(std::path::Path::new(&path), n_features)
+                    .unwrap_or_else(|e| panic!("❌ {e}"));
+                info!("🔥 Loaded TorchScript model {path} (version {})", model.version());
+                return Self::Torch(model);
+            }
+
+            #[cfg(not(feature = "torch"))]
+            {
+                warn!("⚠️ MODEL_PATH={path} ignored: built without the `torch` feature, using online RLS");
+                return Self::Online;
+            }
+        }
+
+        #[cfg(feature = "onnx")]
+        {
+            let model = crate::onnx::OnnxModel::load(std::path::Path::new(&path), n_features)
+                .unwrap_or_else(|e| panic!("❌ {e}"));
+            info!("🧠 Loaded ONNX model {path} (version {})", model.version());
+            Self::Onnx(model)
+        }
+
+        #[cfg(not(feature = "onnx"))]
+        {
+            warn!("⚠️ MODEL_PATH={path} ignored: built without the `onnx` feature, using online RLS");
+            Self::Online
+        }
+    }
+
+    /// `ENSEMBLE_MODELS` is a comma-separated list of members: `rls`, `rls:<forgetting>`,
+    /// `onnx:<path>`, `native:<path>`, `torch:<path>` or `python:<path>`. `ENSEMBLE_WEIGHTS` gives matching fixed weights; without it weights adapt
+    /// to each member's recent squared error (`ENSEMBLE_ALPHA`).
+    fn ensemble_from_env(list: &str, n_features: usize) -> Result<Self, String> {
+        let mut members: Vec<(String, ModelSpec)> = Vec::new();
+        for item in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let spec = Self::parse_member(item, n_features)?;
+            let label = match item.split_once(':') {
+                Some((kind @ ("onnx" | "native" | "torch" | "python"), _)) => kind,
+                _ => item,
+            };
+            if members.iter().any(|(l, _)| l == label) {
+                return Err(format!("duplicate ensemble member '{label}'"));
+            }
+            members.push((label.to_string(), spec));
+        }
+        if members.is_empty() {
+            return Err("ENSEMBLE_MODELS is empty".to_string());
+        }
+
+        let weighting = match env::var("ENSEMBLE_WEIGHTS") {
+            Ok(w) => {
+                let weights = w
+                    .split(',')
+                    .map(|v| v.trim().parse::<f64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("invalid ENSEMBLE_WEIGHTS: {e}"))?;
+                if weights.len() != members.len() {
+                    return Err(format!(
+                        "ENSEMBLE_WEIGHTS has {} entries for {} members",
+                        weights.len(),
+                        members.len()
+                    ));
+                }
+                Weighting::Fixed(weights)
+            }
+            Err(_) => Weighting::Adaptive {
+                alpha: env::var("ENSEMBLE_ALPHA")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_ENSEMBLE_ALPHA),
+            },
+        };
+
+        Ok(Self::Ensemble { members, weighting })
+    }
+
+    /// One `ENSEMBLE_MODELS` item: `rls`, `rls:<forgetting>`, `onnx:<path>`, `native:<path>`,
+    /// `torch:<path>` or `python:<path>`
+    fn parse_member(item: &str, n_features: usize) -> Result<Self, String> {
+        match item.split_once(':') {
+            None if item == "rls" => Ok(Self::Online),
+            Some(("rls", f)) => match f.parse::<f64>() {
+                Ok(f) if f > 0.0 && f <= 1.0 => Ok(Self::Rls(f)),
+                _ => Err(format!("invalid RLS forgetting factor in model '{item}'")),
+            },
+            #[cfg(feature = "onnx")]
+            Some(("onnx", path)) => Ok(Self::Onnx(crate::onnx::OnnxModel::load(
+                std::path::Path::new(path),
+                n_features,
+            )?)),
+            Some(("native", path)) => Ok(Self::Native(crate::native::NativeModel::load(
+                std::path::Path::new(path),
+                n_features,
+            )?)),
+            #[cfg(feature = "torch")]
+            Some(("torch", path)) => Ok(Self::Torch(crate::torch::TorchModel::load(
+                std::path::Path::new(path),
+                n_features,
+            )?)),
+            #[cfg(feature = "python")]
+            Some(("python", path)) => Ok(Self::Python(crate::python::PyModel::load(
+                std::path::Path::new(path),
+                n_features,
+            )?)),
+            _ => Err(format!("unsupported model '{item}'")),
+        }
+    }
+
+    /// Rebuild a spec stored in the model registry by `name` and `spec_string`
+    pub fn from_registry(name: &str, spec: &str, n_features: usize) -> Result<Self, String> {
+        if name == "ensemble" {
+            Self::ensemble_from_env(spec, n_features)
+        } else {
+            Self::parse_member(spec, n_features)
+        }
+    }
+
+    /// Registry name: `rls`, `onnx`, `native`, `torch`, `python` or `ensemble`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Online | Self::Rls(_) => "rls",
+            #[cfg(feature = "onnx")]
+            Self::Onnx(_) => "onnx",
+            Self::Native(_) => "native",
+            #[cfg(feature = "torch")]
+            Self::Torch(_) => "torch",
+            #[cfg(feature = "python")]
+            Self::Python(_) => "python",
+            Self::Ensemble { .. } => "ensemble",
+        }
+    }
+
+    /// Registry version; stable across restarts unlike `Model::version` of online models
+    pub fn version(&self) -> String {
+        match self {
+            Self::Online => "online".to_string(),
+            Self::Rls(forgetting) => format!("forgetting-{forgetting}"),
+            #[cfg(feature = "onnx")]
+            Self::Onnx(model) => model.version(),
+            Self::Native(model) => model.version(),
+            #[cfg(feature = "torch")]
+            Self::Torch(model) => model.version(),
+            #[cfg(feature = "python")]
+            Self::Python(model) => model.version(),
+            Self::Ensemble { members, .. } => members
+                .iter()
+                .map(|(label, m)| format!("{label}@{}", m.version()))
+                .collect::<Vec<_>>()
+                .join("+"),
+        }
+    }
+
+    /// `ENSEMBLE_MODELS`-style list that rebuilds this spec via `from_registry`
+    pub fn spec_string(&self) -> String {
+        match self {
+            Self::Online => "rls".to_string(),
+            Self::Rls(forgetting) => format!("rls:{forgetting}"),
+            #[cfg(feature = "onnx")]
+            Self::Onnx(model) => format!("onnx:{}", model.path().display()),
+            Self::Native(model) => format!("native:{}", model.path().display()),
+            #[cfg(feature = "torch")]
+            Self::Torch(model) => format!("torch:{}", model.path().display()),
+            #[cfg(feature = "python")]
+            Self::Python(model) => format!("python:{}", model.path().display()),
+            Self::Ensemble { members, .. } => members
+                .iter()
+                .map(|(_, m)| m.spec_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+
+    /// Model file backing this spec, if any
+    pub fn artifact(&self) -> Option<PathBuf> {
+        match self {
+            Self::Online | Self::Rls(_) => None,
+            #[cfg(feature = "onnx")]
+            Self::Onnx(model) => Some(model.path().to_path_buf()),
+            Self::Native(model) => Some(model.path().to_path_buf()),
+            #[cfg(feature = "torch")]
+            Self::Torch(model) => Some(model.path().to_path_buf()),
+            #[cfg(feature = "python")]
+            Self::Python(model) => Some(model.path().to_path_buf()),
+            Self::Ensemble { members, .. } => members.iter().find_map(|(_, m)| m.artifact()),
+        }
+    }
+
+    /// Whether outputs depend on state learned from live data, not just the spec and inputs
+    pub fn is_online(&self) -> bool {
+        match self {
+            Self::Online | Self::Rls(_) => true,
+            #[cfg(feature = "python")]
+            Self::Python(model) => model.online(),
+            Self::Ensemble { members, .. } => members.iter().any(|(_, m)| m.is_online()),
+            _ => false,
+        }
+    }
+
+    fn is_onnx(&self) -> bool {
+        #[cfg(feature = "onnx")]
+        {
+            matches!(self, Self::Onnx(_))
+        }
+        #[cfg(not(feature = "onnx"))]
+        {
+            false
+        }
+    }
+
+    /// Apply a hot-reloaded model: an ensemble swaps its `onnx` member in place,
+    /// anything else is replaced outright
+    pub fn reloaded(&self, new: ModelSpec) -> ModelSpec {
+        match self {
+            Self::Ensemble { members, weighting } if members.iter().any(|(_, m)| m.is_onnx()) => {
+                Self::Ensemble {
+                    members: members
+                        .iter()
+                        .map(|(l, m)| (l.clone(), if m.is_onnx() { new.clone() } else { m.clone() }))
+                        .collect(),
+                    weighting: weighting.clone(),
+                }
+            }
+            _ => new,
+        }
+    }
+
+    pub fn build(&self, n_features: usize) -> Box<dyn Model> {
+        match self {
+            Self::Online => Box::new(Rls::new(n_features)),
+            Self::Rls(forgetting) => Box::new(Rls::with_params(n_features, *forgetting, DEFAULT_RIDGE)),
+            #[cfg(feature = "onnx")]
+            Self::Onnx(model) => Box::new(model.clone()),
+            Self::Native(model) => Box::new(model.clone()),
+            #[cfg(feature = "torch")]
+            Self::Torch(model) => Box::new(model.clone()),
+            #[cfg(feature = "python")]
+            Self::Python(model) => Box::new(model.clone()),
+            Self::Ensemble { members, weighting } => Box::new(Ensemble::new(
+                members
+                    .iter()
+                    .map(|(label, spec)| (label.clone(), spec.build(n_features)))
+                    .collect(),
+                weighting.clone(),
+            )),
+        }
+    }
+}
+
+/// `MODEL_POLL_SECS`: how often the model dir and the registry are checked
+pub fn poll_interval() -> Duration {
+    env::var("MODEL_POLL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_MODEL_POLL)
+}
+
+/// Polls `MODEL_DIR` for the newest `*.onnx` file (by mtime) and loads it when it changes.
+/// Writers should upload to a temp name and rename into place; a model that fails to
+/// load is retried on the next poll while the previous one stays active.
+pub struct ModelWatcher {
+    dir: PathBuf,
+    n_features: usize,
+    poll_every: Duration,
+    current: Option<(PathBuf, SystemTime)>,
+}
+
+impl ModelWatcher {
+    /// `MODEL_DIR` enables watching (requires the `onnx` feature), `MODEL_POLL_SECS` sets the interval
+    pub fn from_env(n_features: usize) -> Option<Self> {
+        let dir = PathBuf::from(env::var("MODEL_DIR").ok()?);
+        if cfg!(not(feature = "onnx")) {
+            warn!("⚠️ MODEL_DIR={} ignored: built without the `onnx` feature", dir.display());
+            return None;
+        }
+        let poll_every = poll_interval();
+        info!("👀 Watching {} for models every {:?}", dir.display(), poll_every);
+        Some(Self {
+            dir,
+            n_features,
+            poll_every,
+            current: None,
+        })
+    }
+
+    pub fn poll_every(&self) -> Duration {
+        self.poll_every
+    }
+
+    fn newest(&self) -> Option<(PathBuf, SystemTime)> {
+        std::fs::read_dir(&self.dir)
+            .map_err(|e| error!("❌ Cannot read model dir {}: {e}", self.dir.display()))
+            .ok()?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "onnx" {
+                    return None;
+                }
+                let modified = path.metadata().ok()?.modified().ok()?;
+                Some((path, modified))
+            })
+            .max_by_key(|(_, modified)| *modified)
+    }
+
+    /// A freshly loaded spec when a newer model file appeared
+    pub fn poll(&mut self) -> Option<ModelSpec> {
+        let newest = self.newest()?;
+        if self.current.as_ref() == Some(&newest) {
+            return None;
+        }
+
+        #[cfg(feature = "onnx")]
+        {
+            match crate::onnx::OnnxModel::load(&newest.0, self.n_features) {
+                Ok(model) => {
+                    info!("🔄 Activated model {} (version {})", newest.0.display(), model.version());
+                    self.current = Some(newest);
+                    Some(ModelSpec::Onnx(model))
+                }
+                Err(e) => {
+                    error!("❌ {e} — keeping current model");
+                    None
+                }
+            }
+        }
+
+        #[cfg(not(feature = "onnx"))]
+        {
+            let _ = self.n_features;
+            None
+        }
+    }
+}
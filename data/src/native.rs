@@ -0,0 +1,179 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    features::{FEATURE_NAMES, FEATURE_SCHEMA_VERSION},
+    models::Model,
+};
+
+/// One regression tree node; `x[split_feature] <= split_value` goes to `true_child`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeNode {
+    pub output: f64,
+    pub split_feature: usize,
+    pub split_value: Option<f64>,
+    pub true_child: Option<usize>,
+    pub false_child: Option<usize>,
+}
+
+fn eval_tree(nodes: &[TreeNode], x: &[f64]) -> f64 {
+    let mut i = 0;
+    while let Some(node) = nodes.get(i) {
+        let next = match node.split_value {
+            Some(v) if x.get(node.split_feature).is_some_and(|f| *f <= v) => node.true_child,
+            Some(_) => node.false_child,
+            None => None,
+        };
+        match next {
+            Some(n) => i = n,
+            None => return node.output,
+        }
+    }
+    0.0
+}
+
+/// Fitted parameters, on standardized features
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Estimator {
+    Linear { weights: Vec<f64>, intercept: f64 },
+    /// Probability of an up move, mapped to `scale * (2p - 1)`
+    Logistic { weights: Vec<f64>, intercept: f64, scale: f64 },
+    /// `base + learning_rate * Σ tree(x)`
+    Gbm {
+        base: f64,
+        learning_rate: f64,
+        trees: Vec<Vec<TreeNode>>,
+    },
+}
+
+/// JSON file written by the `train` binary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    pub schema_version: i32,
+    pub feature_names: Vec<String>,
+    /// Dataset column the model was fitted on
+    pub target: String,
+    /// Per-feature standardization applied before `estimator`
+    pub mean: Vec<f64>,
+    pub std: Vec<f64>,
+    pub estimator: Estimator,
+    /// Holdout metrics from training
+    #[serde(default)]
+    pub metrics: serde_json::Value,
+}
+
+impl Artifact {
+    pub fn predict(&self, x: &[f64]) -> f64 {
+        let z: Vec<f64> = x
+            .iter()
+            .zip(self.mean.iter().zip(&self.std))
+            .map(|(v, (m, s))| if *s > 0.0 { (v - m) / s } else { 0.0 })
+            .collect();
+        let dot = |w: &[f64]| w.iter().zip(&z).map(|(a, b)| a * b).sum::<f64>();
+        match &self.estimator {
+            Estimator::Linear { weights, intercept } => intercept + dot(weights),
+            Estimator::Logistic {
+                weights,
+                intercept,
+                scale,
+            } => {
+                let p = 1.0 / (1.0 + (-(intercept + dot(weights))).exp());
+                scale * (2.0 * p - 1.0)
+            }
+            Estimator::Gbm {
+                base,
+                learning_rate,
+                trees,
+            } => base + learning_rate * trees.iter().map(|t| eval_tree(t, &z)).sum::<f64>(),
+        }
+    }
+
+    /// |weight| per feature for linear models, share of splits for boosted trees
+    pub fn importance(&self) -> Vec<f64> {
+        match &self.estimator {
+            Estimator::Linear { weights, .. } | Estimator::Logistic { weights, .. } => {
+                weights.iter().map(|w| w.abs()).collect()
+            }
+            Estimator::Gbm { trees, .. } => {
+                let mut splits = vec![0.0; self.feature_names.len()];
+                for node in trees.iter().flatten().filter(|n| n.split_value.is_some()) {
+                    if let Some(s) = splits.get_mut(node.split_feature) {
+                        *s += 1.0;
+                    }
+                }
+                splits
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| format!("cannot serialize model: {e}"))?;
+        std::fs::write(path, json).map_err(|e| format!("cannot write {}: {e}", path.display()))
+    }
+}
+
+/// Model fitted in-crate by the `train` binary. Shared across symbols, like ONNX.
+#[derive(Clone)]
+pub struct NativeModel {
+    artifact: Arc<Artifact>,
+    version: String,
+    path: PathBuf,
+}
+
+impl NativeModel {
+    /// Fails when the artifact was trained on another feature schema
+    pub fn load(path: &Path, n_features: usize) -> Result<Self, String> {
+        let fail = |e: String| format!("failed to load native model {}: {e}", path.display());
+        let json = std::fs::read_to_string(path).map_err(|e| fail(e.to_string()))?;
+        let artifact: Artifact = serde_json::from_str(&json).map_err(|e| fail(e.to_string()))?;
+        if artifact.feature_names != FEATURE_NAMES || artifact.feature_names.len() != n_features {
+            return Err(fail(format!(
+                "trained on feature schema v{}, this build uses v{FEATURE_SCHEMA_VERSION}",
+                artifact.schema_version
+            )));
+        }
+
+        let version = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        Ok(Self {
+            artifact: Arc::new(artifact),
+            version,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// File the model was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Model for NativeModel {
+    fn name(&self) -> &str {
+        "native"
+    }
+
+    fn version(&self) -> String {
+        self.version.clone()
+    }
+
+    fn predict(&self, x: &[f64]) -> f64 {
+        if x.len() != self.artifact.feature_names.len() {
+            return 0.0;
+        }
+        self.artifact.predict(x)
+    }
+
+    /// Weights are on standardized features already, so `std` is not needed
+    fn importance(&self, _std: &[f64]) -> Option<Vec<f64>> {
+        Some(self.artifact.importance())
+    }
+}
@@ -0,0 +1,234 @@
+use std::{collections::VecDeque, env, fmt, path::Path, str::FromStr};
+
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+use crate::{features::{FEATURE_NAMES, FEATURE_SCHEMA_VERSION}, keys::NORMALIZER_PREFIX};
+
+use crate::redis_conn::RedisConn;
+
+
+/// Last `N` values with their mean, population std and z-score
+#[derive(Debug, Clone, Default)]
+pub struct RollingWindow<const N: usize> {
+    values: VecDeque<f64>,
+}
+
+impl<const N: usize> RollingWindow<N> {
+    pub fn push(&mut self, v: f64) {
+        self.values.push_back(v);
+        if self.values.len() > N {
+            self.values.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.values.iter().sum::<f64>() / self.values.len().max(1) as f64
+    }
+
+    pub fn std(&self) -> f64 {
+        let m = self.mean();
+        (self.values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / self.values.len().max(1) as f64).sqrt()
+    }
+
+    /// z-score of `v` against the window, 0 when it is flat
+    pub fn zscore(&self, v: f64) -> f64 {
+        let sd = self.std();
+        if sd > 0.0 { (v - self.mean()) / sd } else { 0.0 }
+    }
+}
+
+/// Incremental count, mean, variance (Welford) and range of one column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningStats {
+    pub count: u64,
+    pub mean: f64,
+    /// Sum of squared deviations from the mean
+    pub m2: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl RunningStats {
+    pub fn push(&mut self, v: f64) {
+        if !v.is_finite() {
+            return;
+        }
+        self.count += 1;
+        let delta = v - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (v - self.mean);
+        self.min = self.min.min(v);
+        self.max = self.max.max(v);
+    }
+
+    /// Population standard deviation
+    pub fn std(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { (self.m2 / self.count as f64).sqrt() }
+    }
+
+    /// 0 for a constant (or unseen) column
+    pub fn zscore(&self, v: f64) -> f64 {
+        let sd = self.std();
+        if sd > 0.0 { (v - self.mean) / sd } else { 0.0 }
+    }
+
+    /// `v` mapped so the observed range is 0..1 (not clamped), 0 for a constant column
+    pub fn minmax(&self, v: f64) -> f64 {
+        let span = self.max - self.min;
+        if span > 0.0 { (v - self.min) / span } else { 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    ZScore,
+    MinMax,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Method::ZScore => "zscore",
+            Method::MinMax => "minmax",
+        })
+    }
+}
+
+impl FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zscore" => Ok(Method::ZScore),
+            "minmax" => Ok(Method::MinMax),
+            other => Err(format!("unknown normalization '{other}' (use zscore or minmax)")),
+        }
+    }
+}
+
+/// Per-feature scaling over `FEATURE_NAMES`. The predictor keeps one per
+/// prediction timeframe in Redis and the dataset exporter can load it, so live
+/// and exported vectors are scaled with exactly the same statistics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Normalizer {
+    pub schema_version: i32,
+    pub method: Method,
+    pub stats: Vec<RunningStats>,
+}
+
+impl Normalizer {
+    pub fn new(method: Method) -> Self {
+        Self {
+            schema_version: FEATURE_SCHEMA_VERSION,
+            method,
+            stats: vec![RunningStats::default(); FEATURE_NAMES.len()],
+        }
+    }
+
+    /// `NORMALIZE_METHOD` (zscore | minmax), default zscore
+    pub fn from_env() -> Self {
+        let method = env::var("NORMALIZE_METHOD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Method::ZScore);
+        Self::new(method)
+    }
+
+    /// Statistics of a whole batch of vectors
+    pub fn fit(method: Method, rows: &[Vec<f64>]) -> Self {
+        let mut n = Self::new(method);
+        for row in rows {
+            n.observe(row);
+        }
+        n
+    }
+
+    /// Vectors seen so far
+    pub fn count(&self) -> u64 {
+        self.stats.iter().map(|s| s.count).max().unwrap_or(0)
+    }
+
+    pub fn observe(&mut self, x: &[f64]) {
+        for (s, v) in self.stats.iter_mut().zip(x) {
+            s.push(*v);
+        }
+    }
+
+    pub fn transform(&self, x: &[f64]) -> Vec<f64> {
+        x.iter()
+            .zip(&self.stats)
+            .map(|(v, s)| match self.method {
+                Method::ZScore => s.zscore(*v),
+                Method::MinMax => s.minmax(*v),
+            })
+            .collect()
+    }
+
+    pub fn means(&self) -> Vec<f64> {
+        self.stats.iter().map(|s| s.mean).collect()
+    }
+
+    pub fn stds(&self) -> Vec<f64> {
+        self.stats.iter().map(|s| s.std()).collect()
+    }
+
+    fn check(self) -> Result<Self, String> {
+        if self.schema_version != FEATURE_SCHEMA_VERSION || self.stats.len() != FEATURE_NAMES.len() {
+            return Err(format!(
+                "normalizer stats are for feature schema v{}, this build uses v{FEATURE_SCHEMA_VERSION}",
+                self.schema_version
+            ));
+        }
+        Ok(self)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str::<Self>(json)
+            .map_err(|e| format!("invalid normalizer stats: {e}"))?
+            .check()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        Self::from_json(&json)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_json()).map_err(|e| format!("cannot write {}: {e}", path.display()))
+    }
+
+    /// Live statistics for `tf`, if the predictor has stored any
+    pub async fn load_redis(redis: &mut RedisConn, tf: &str) -> Result<Option<Self>, String> {
+        let json: Option<String> = redis
+            .get(format!("{NORMALIZER_PREFIX}{tf}"))
+            .await
+            .map_err(|e| format!("cannot read normalizer stats: {e}"))?;
+        json.map(|j| Self::from_json(&j)).transpose()
+    }
+}
@@ -0,0 +1,230 @@
+use std::{env, time::Duration};
+
+use tokio::{
+    sync::{mpsc, watch},
+    time::{sleep, sleep_until, Instant},
+};
+
+use tracing::{error, info, warn};
+use crate::alerts::Alert;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_BATCH_SECS: u64 = 5;
+pub const DEFAULT_MAX_PER_MIN: u32 = 20;
+// Discord rejects longer content; Slack's limit is higher, one cap keeps both readable
+const MAX_MESSAGE_CHARS: usize = 1900;
+// Wait used for a 429 without a usable Retry-After
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// A chat incoming-webhook URL
+#[derive(Debug, Clone)]
+pub enum Target {
+    Discord(String),
+    Slack(String),
+}
+
+impl Target {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Discord(_) => "Discord",
+            Self::Slack(_) => "Slack",
+        }
+    }
+
+    fn url(&self) -> &str {
+        match self {
+            Self::Discord(url) | Self::Slack(url) => url,
+        }
+    }
+
+    fn bold(&self, s: &str) -> String {
+        match self {
+            Self::Discord(_) => format!("**{s}**"),
+            Self::Slack(_) => format!("*{s}*"),
+        }
+    }
+
+    fn payload(&self, text: String) -> serde_json::Value {
+        match self {
+            Self::Discord(_) => serde_json::json!({ "content": text }),
+            Self::Slack(_) => serde_json::json!({ "text": text }),
+        }
+    }
+
+    /// One message for `batch` from `source`, cut short with a count past the size limit
+    fn render(&self, source: &str, batch: &[Alert]) -> String {
+        let mut text = match batch.len() {
+            1 => format!("{} {}", self.bold(source), self.bold(&batch[0].kind)),
+            n => format!("{} {n} alerts", self.bold(source)),
+        };
+        for (i, alert) in batch.iter().enumerate() {
+            let line = match batch.len() {
+                1 => format!("\n{}", alert.message),
+                _ => format!("\n• {} {}", self.bold(&alert.kind), alert.message),
+            };
+            if text.len() + line.len() > MAX_MESSAGE_CHARS {
+                text.push_str(&format!("\n…and {} more", batch.len() - i));
+                break;
+            }
+            text.push_str(&line);
+        }
+        text
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NotifyConfig {
+    pub targets: Vec<Target>,
+    /// Alerts arriving this long after the first are sent with it as one message
+    pub batch_window: Duration,
+    /// Messages per target per minute; alerts held back meanwhile join the next batch
+    pub max_per_min: u32,
+}
+
+impl NotifyConfig {
+    /// `DISCORD_WEBHOOK_URL` / `SLACK_WEBHOOK_URL`, `NOTIFY_BATCH_SECS` (default 5),
+    /// `NOTIFY_MAX_PER_MIN` (default 20); `None` when neither URL is set
+    pub fn from_env() -> Option<Self> {
+        let targets: Vec<Target> = [
+            env::var("DISCORD_WEBHOOK_URL").ok().map(Target::Discord),
+            env::var("SLACK_WEBHOOK_URL").ok().map(Target::Slack),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if targets.is_empty() {
+            return None;
+        }
+        let var = |name: &str| env::var(name).ok().and_then(|v| v.parse().ok());
+        Some(Self {
+            targets,
+            batch_window: Duration::from_secs(var("NOTIFY_BATCH_SECS").unwrap_or(DEFAULT_BATCH_SECS)),
+            max_per_min: var("NOTIFY_MAX_PER_MIN")
+                .map(|n: u64| n as u32)
+                .unwrap_or(DEFAULT_MAX_PER_MIN)
+                .max(1),
+        })
+    }
+}
+
+/// Queues alerts for a background task that batches, rate-limits and posts them to
+/// Discord and/or Slack. Cheap to clone; the task flushes and exits once every clone is dropped.
+#[derive(Clone)]
+pub struct Notifier {
+    tx: mpsc::UnboundedSender<Alert>,
+    done: watch::Receiver<bool>,
+}
+
+impl Notifier {
+    /// `source` names the sending service in every message
+    pub fn spawn(source: &str, config: NotifyConfig) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (done_tx, done) = watch::channel(false);
+        let names: Vec<&str> = config.targets.iter().map(Target::name).collect();
+        info!(
+            "📣 Notifying {} (batches of {:?}, at most {}/min)",
+            names.join(" and "),
+            config.batch_window,
+            config.max_per_min
+        );
+        let source = source.to_string();
+        tokio::spawn(async move {
+            run(source, config, rx).await;
+            let _ = done_tx.send(true);
+        });
+        Self { tx, done }
+    }
+
+    /// `None` when no chat webhook is configured; needs a Tokio runtime
+    pub fn from_env(source: &str) -> Option<Self> {
+        NotifyConfig::from_env().map(|config| Self::spawn(source, config))
+    }
+
+    /// Never blocks; the alert goes out with the next batch
+    pub fn notify(&self, alert: Alert) {
+        let _ = self.tx.send(alert);
+    }
+
+    /// Drop this handle and wait until queued alerts are sent, for processes about to exit;
+    /// returns once every other clone is gone too
+    pub async fn close(self) {
+        let mut done = self.done.clone();
+        drop(self);
+        let _ = done.wait_for(|sent| *sent).await;
+    }
+}
+
+async fn run(source: String, config: NotifyConfig, mut rx: mpsc::UnboundedReceiver<Alert>) {
+    let http = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("❌ Failed to build HTTP client");
+    let gap = Duration::from_secs(60) / config.max_per_min;
+    let mut next_slot = Instant::now();
+
+    let mut open = true;
+    while open {
+        let Some(first) = rx.recv().await else {
+            break;
+        };
+        let mut batch = vec![first];
+        let window = sleep(config.batch_window);
+        tokio::pin!(window);
+        loop {
+            tokio::select! {
+                _ = &mut window => break,
+                alert = rx.recv() => match alert {
+                    Some(a) => batch.push(a),
+                    None => {
+                        open = false;
+                        break;
+                    }
+                },
+            }
+        }
+
+        sleep_until(next_slot).await;
+        // Alerts that queued while waiting for the slot ride along
+        while let Ok(a) = rx.try_recv() {
+            batch.push(a);
+        }
+        for target in &config.targets {
+            if let Err(e) = post(&http, target, target.render(&source, &batch)).await {
+                error!("❌ {} notification of {} alerts not delivered: {e}", target.name(), batch.len());
+            }
+        }
+        next_slot = Instant::now() + gap;
+    }
+}
+
+/// POST once, and once more after the wait a 429 asks for
+async fn post(http: &reqwest::Client, target: &Target, text: String) -> Result<(), String> {
+    let body = target.payload(text);
+    let mut rate_limited = false;
+    loop {
+        let resp = http
+            .post(target.url())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("request failed: {e}"))?;
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        if status != reqwest::StatusCode::TOO_MANY_REQUESTS || rate_limited {
+            return Err(format!("webhook returned {status}"));
+        }
+        rate_limited = true;
+        let wait = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|s| s.is_finite() && *s >= 0.0)
+            .map(Duration::from_secs_f64)
+            .unwrap_or(DEFAULT_RETRY_AFTER);
+        warn!("⏳ {} rate limited, retrying in {wait:?}", target.name());
+        sleep(wait).await;
+    }
+}
@@ -0,0 +1,79 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tract_onnx::prelude::*;
+
+use tracing::error;
+use crate::models::Model;
+
+/// Offline-trained ONNX model. Contract: one float32 input of shape
+/// `[1, n_features]` in `FEATURE_NAMES` order; the first element of the
+/// first output is the predicted next-bar log return.
+#[derive(Clone)]
+pub struct OnnxModel {
+    plan: Arc<TypedRunnableModel>,
+    n_features: usize,
+    version: String,
+    path: PathBuf,
+}
+
+impl OnnxModel {
+    pub fn load(path: &Path, n_features: usize) -> Result<Self, String> {
+        let plan = tract_onnx::onnx()
+            .model_for_path(path)
+            .and_then(|m| m.with_input_fact(0, f32::fact([1, n_features]).into()))
+            .and_then(|m| m.into_optimized())
+            .and_then(|m| m.into_runnable())
+            .map_err(|e| format!("failed to load ONNX model {}: {e}", path.display()))?;
+
+        let version = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        Ok(Self {
+            plan,
+            n_features,
+            version,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// File the model was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn run(&self, x: &[f64]) -> TractResult<f64> {
+        let values: Vec<f32> = x.iter().map(|v| *v as f32).collect();
+        let input = Tensor::from_shape(&[1, self.n_features], &values)?;
+        let outputs = self.plan.run(tvec!(input.into()))?;
+        let out = outputs[0].to_plain_array_view::<f32>()?;
+        Ok(out.iter().next().copied().unwrap_or(0.0) as f64)
+    }
+}
+
+impl Model for OnnxModel {
+    fn name(&self) -> &str {
+        "onnx"
+    }
+
+    fn version(&self) -> String {
+        self.version.clone()
+    }
+
+    fn predict(&self, x: &[f64]) -> f64 {
+        if x.len() != self.n_features {
+            return 0.0;
+        }
+        match self.run(x) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("❌ ONNX inference failed: {e}");
+                0.0
+            }
+        }
+    }
+}
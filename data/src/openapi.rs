@@ -0,0 +1,57 @@
+use axum::Router;
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{api, bars::Bar, health};
+
+/// Where the document and the UI are served
+pub const SPEC_PATH: &str = "/openapi.json";
+pub const UI_PATH: &str = "/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Real-Time Crypto Tick Predictor API"),
+    paths(
+        api::symbols, api::add_symbol, api::remove_symbol,
+        api::add_webhook, api::list_webhooks, api::remove_webhook,
+        api::prices, api::price, api::ohlcv, api::ticks, api::history, api::aggregate, api::market_summary, api::export,
+        api::predictions, api::prediction, api::signals, api::signal, api::importances, api::importance,
+        api::sse, api::sse_symbol, api::ws,
+        api::metrics, api::pipeline_status, health::healthz, health::readyz,
+    ),
+    // Served by `/ohlcv/{symbol}?tf=`, which cannot name two bodies for one status
+    components(schemas(Bar)),
+    modifiers(&Security),
+    security(("bearer" = []), ("api_key" = [])),
+    tags(
+        (name = "prices", description = "Needs read_prices"),
+        (name = "predictions", description = "Needs read_predictions"),
+        (name = "symbols", description = "Needs manage_symbols"),
+        (name = "webhooks", description = "Needs manage_webhooks; each caller sees only their own"),
+        (name = "live", description = "Streams; each subscription is checked against the caller's permissions"),
+        (name = "operations", description = "Health, status and metrics"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Bearer JWTs / API keys and the `X-API-Key` header, as accepted by `auth::guard`
+struct Security;
+
+impl Modify for Security {
+    fn modify(&self, doc: &mut utoipa::openapi::OpenApi) {
+        let components = doc.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT or API key").build()),
+        );
+        components.add_security_scheme("api_key", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))));
+    }
+}
+
+/// `GET /openapi.json` and Swagger UI under `/docs`, both outside auth
+pub fn mount<S: Clone + Send + Sync + 'static>(router: Router<S>) -> Router<S> {
+    router.merge(SwaggerUi::new(UI_PATH).url(SPEC_PATH, ApiDoc::openapi()))
+}
@@ -0,0 +1,281 @@
+use std::{collections::HashMap, env};
+
+use chrono::DateTime;
+use serde::Serialize;
+use tokio_postgres::Client as PgClient;
+
+use crate::signals::Signal;
+
+
+const QTY_EPSILON: f64 = 1e-12;
+
+fn env_f64(key: &str, default: f64) -> f64 {
+    env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PaperConfig {
+    pub initial_cash: f64,
+    /// Notional opened per position
+    pub position_notional: f64,
+    pub fee_bps: f64,
+    pub slippage_bps: f64,
+    /// Minimum spacing between equity snapshots
+    pub snapshot_ms: i64,
+    /// Per-bar volatility the notional is scaled to; 0 keeps it fixed
+    pub target_vol_bps: f64,
+    /// Cap on the volatility scaling factor
+    pub max_scale: f64,
+}
+
+impl PaperConfig {
+    /// `PAPER_INITIAL_CASH`, `PAPER_POSITION_NOTIONAL`, `PAPER_FEE_BPS`, `PAPER_SLIPPAGE_BPS`,
+    /// `PAPER_SNAPSHOT_SECS`, `PAPER_TARGET_VOL_BPS`, `PAPER_MAX_SCALE`
+    pub fn from_env() -> Self {
+        Self {
+            initial_cash: env_f64("PAPER_INITIAL_CASH", 10_000.0),
+            position_notional: env_f64("PAPER_POSITION_NOTIONAL", 1_000.0),
+            fee_bps: env_f64("PAPER_FEE_BPS", 10.0),
+            slippage_bps: env_f64("PAPER_SLIPPAGE_BPS", 2.0),
+            snapshot_ms: (env_f64("PAPER_SNAPSHOT_SECS", 60.0) * 1000.0) as i64,
+            target_vol_bps: env_f64("PAPER_TARGET_VOL_BPS", 0.0),
+            max_scale: env_f64("PAPER_MAX_SCALE", 3.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Position {
+    qty: f64,
+    avg_price: f64,
+}
+
+/// One simulated execution
+#[derive(Debug, Clone, Serialize)]
+pub struct Fill {
+    pub symbol: String,
+    /// Signed quantity, positive = buy
+    pub qty: f64,
+    /// Execution price after slippage
+    pub price: f64,
+    pub fee: f64,
+    pub realized_pnl: f64,
+    pub ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EquitySnapshot {
+    pub ts: i64,
+    pub cash: f64,
+    pub positions_value: f64,
+    pub equity: f64,
+    pub realized_pnl: f64,
+    pub fees: f64,
+    pub open_positions: usize,
+}
+
+impl EquitySnapshot {
+    /// Redis hash fields
+    pub fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("ts".to_string(), self.ts.to_string()),
+            ("cash".to_string(), self.cash.to_string()),
+            ("positions_value".to_string(), self.positions_value.to_string()),
+            ("equity".to_string(), self.equity.to_string()),
+            ("realized_pnl".to_string(), self.realized_pnl.to_string()),
+            ("fees".to_string(), self.fees.to_string()),
+            ("open_positions".to_string(), self.open_positions.to_string()),
+        ]
+    }
+}
+
+/// Simulated account that follows signals with fixed-notional positions
+pub struct PaperBook {
+    cfg: PaperConfig,
+    cash: f64,
+    positions: HashMap<String, Position>,
+    marks: HashMap<String, f64>,
+    realized: f64,
+    fees: f64,
+    last_snapshot: Option<i64>,
+}
+
+impl PaperBook {
+    pub fn new(cfg: PaperConfig) -> Self {
+        Self {
+            cfg,
+            cash: cfg.initial_cash,
+            positions: HashMap::new(),
+            marks: HashMap::new(),
+            realized: 0.0,
+            fees: 0.0,
+            last_snapshot: None,
+        }
+    }
+
+    pub fn mark(&mut self, symbol: &str, price: f64) {
+        if price > 0.0 {
+            self.marks.insert(symbol.to_string(), price);
+        }
+    }
+
+    pub fn equity(&self) -> f64 {
+        self.cash + self.positions_value()
+    }
+
+    fn positions_value(&self) -> f64 {
+        self.positions
+            .iter()
+            .map(|(s, p)| p.qty * self.marks.get(s).copied().unwrap_or(p.avg_price))
+            .sum()
+    }
+
+    /// Notional for a new position: fixed, or scaled to `target_vol_bps` by the per-bar volatility
+    fn notional(&self, vol: Option<f64>) -> f64 {
+        match vol {
+            Some(v) if self.cfg.target_vol_bps > 0.0 && v > 0.0 => {
+                let scale = (self.cfg.target_vol_bps / 10_000.0 / v).min(self.cfg.max_scale);
+                self.cfg.position_notional * scale
+            }
+            _ => self.cfg.position_notional,
+        }
+    }
+
+    /// Move to the signal's side at `price`; positions are only resized on a side change
+    pub fn on_signal(&mut self, signal: &Signal, price: f64, vol: Option<f64>) -> Option<Fill> {
+        if price <= 0.0 {
+            return None;
+        }
+        self.mark(&signal.symbol, price);
+
+        let pos = self.positions.get(&signal.symbol).copied().unwrap_or_default();
+        let dir = signal.side.direction();
+        let current_dir = if pos.qty.abs() < QTY_EPSILON { 0.0 } else { pos.qty.signum() };
+        if dir == current_dir {
+            return None;
+        }
+
+        let target = dir * self.notional(vol) / price;
+        let delta = target - pos.qty;
+        let exec = price * (1.0 + delta.signum() * self.cfg.slippage_bps / 10_000.0);
+        let fee = delta.abs() * exec * self.cfg.fee_bps / 10_000.0;
+
+        // Realize PnL on whatever part of the old position is being closed
+        let realized = if current_dir != 0.0 && current_dir != delta.signum() {
+            delta.abs().min(pos.qty.abs()) * (exec - pos.avg_price) * current_dir
+        } else {
+            0.0
+        };
+
+        let new_qty = pos.qty + delta;
+        let avg_price = if new_qty.abs() < QTY_EPSILON {
+            0.0
+        } else if current_dir == 0.0 || current_dir != new_qty.signum() {
+            exec
+        } else {
+            (pos.qty * pos.avg_price + delta * exec) / new_qty
+        };
+
+        self.cash -= delta * exec + fee;
+        self.realized += realized;
+        self.fees += fee;
+        if new_qty.abs() < QTY_EPSILON {
+            self.positions.remove(&signal.symbol);
+        } else {
+            self.positions.insert(
+                signal.symbol.clone(),
+                Position {
+                    qty: new_qty,
+                    avg_price,
+                },
+            );
+        }
+
+        Some(Fill {
+            symbol: signal.symbol.clone(),
+            qty: delta,
+            price: exec,
+            fee,
+            realized_pnl: realized,
+            ts: signal.ts,
+        })
+    }
+
+    /// Equity snapshot if at least `snapshot_ms` passed since the last one
+    pub fn maybe_snapshot(&mut self, ts: i64) -> Option<EquitySnapshot> {
+        if let Some(last) = self.last_snapshot
+            && ts - last < self.cfg.snapshot_ms
+        {
+            return None;
+        }
+        self.last_snapshot = Some(ts);
+
+        let positions_value = self.positions_value();
+        Some(EquitySnapshot {
+            ts,
+            cash: self.cash,
+            positions_value,
+            equity: self.cash + positions_value,
+            realized_pnl: self.realized,
+            fees: self.fees,
+            open_positions: self.positions.len(),
+        })
+    }
+}
+
+fn naive_ms(ms: i64) -> chrono::NaiveDateTime {
+    DateTime::from_timestamp_millis(ms)
+        .unwrap_or_default()
+        .naive_utc()
+}
+
+/// Create `paper_fills` and `paper_equity` if missing
+pub async fn ensure_tables(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS paper_fills ( \
+             id BIGSERIAL PRIMARY KEY, \
+             symbol TEXT NOT NULL, \
+             qty DOUBLE PRECISION NOT NULL, \
+             price DOUBLE PRECISION NOT NULL, \
+             fee DOUBLE PRECISION NOT NULL, \
+             realized_pnl DOUBLE PRECISION NOT NULL, \
+             ts TIMESTAMP NOT NULL \
+         ); \
+         CREATE TABLE IF NOT EXISTS paper_equity ( \
+             ts TIMESTAMP PRIMARY KEY, \
+             cash DOUBLE PRECISION NOT NULL, \
+             positions_value DOUBLE PRECISION NOT NULL, \
+             equity DOUBLE PRECISION NOT NULL, \
+             realized_pnl DOUBLE PRECISION NOT NULL, \
+             fees DOUBLE PRECISION NOT NULL, \
+             open_positions INTEGER NOT NULL \
+         );",
+    )
+    .await
+}
+
+pub async fn insert_fill(pg: &PgClient, f: &Fill) -> Result<u64, tokio_postgres::Error> {
+    pg.execute(
+        "INSERT INTO paper_fills (symbol, qty, price, fee, realized_pnl, ts) \
+         VALUES ($1, $2, $3, $4, $5, $6)",
+        &[&f.symbol, &f.qty, &f.price, &f.fee, &f.realized_pnl, &naive_ms(f.ts)],
+    )
+    .await
+}
+
+pub async fn insert_snapshot(pg: &PgClient, s: &EquitySnapshot) -> Result<u64, tokio_postgres::Error> {
+    pg.execute(
+        "INSERT INTO paper_equity (ts, cash, positions_value, equity, realized_pnl, fees, open_positions) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT (ts) DO NOTHING",
+        &[
+            &naive_ms(s.ts),
+            &s.cash,
+            &s.positions_value,
+            &s.equity,
+            &s.realized_pnl,
+            &s.fees,
+            &(s.open_positions as i32),
+        ],
+    )
+    .await
+}
@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::bars::Bar;
+
+
+// Body at most this share of the range counts as a doji
+const DOJI_BODY: f64 = 0.1;
+// Hammer: lower shadow at least this many bodies, upper shadow at most this share of a body
+const HAMMER_SHADOW: f64 = 2.0;
+const HAMMER_UPPER: f64 = 0.3;
+// Soldiers/crows close within this share of their range from the extreme
+const SOLDIER_WICK: f64 = 0.3;
+
+/// Classic single- and multi-bar candlestick shapes. Detection is on shape only,
+/// without the prior-trend context textbooks add.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    Doji,
+    Hammer,
+    BullishEngulfing,
+    BearishEngulfing,
+    ThreeWhiteSoldiers,
+    ThreeBlackCrows,
+}
+
+impl Pattern {
+    /// Feature column order
+    pub const ALL: [Pattern; 6] = [
+        Pattern::Doji,
+        Pattern::Hammer,
+        Pattern::BullishEngulfing,
+        Pattern::BearishEngulfing,
+        Pattern::ThreeWhiteSoldiers,
+        Pattern::ThreeBlackCrows,
+    ];
+}
+
+/// Published whenever a closed bar completes at least one pattern
+#[derive(Debug, Clone, Serialize)]
+pub struct PatternEvent {
+    pub symbol: String,
+    pub tf: String,
+    /// Close time of the completing bar, ms since epoch
+    pub ts: i64,
+    pub close: f64,
+    pub patterns: Vec<Pattern>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Candle {
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+}
+
+impl Candle {
+    fn body(&self) -> f64 {
+        (self.close - self.open).abs()
+    }
+
+    fn range(&self) -> f64 {
+        self.high - self.low
+    }
+
+    fn upper(&self) -> f64 {
+        self.high - self.open.max(self.close)
+    }
+
+    fn lower(&self) -> f64 {
+        self.open.min(self.close) - self.low
+    }
+
+    fn bullish(&self) -> bool {
+        self.close > self.open
+    }
+
+    fn bearish(&self) -> bool {
+        self.close < self.open
+    }
+}
+
+/// Last three candles of one symbol/timeframe
+#[derive(Debug, Clone, Default)]
+pub struct PatternDetector {
+    candles: VecDeque<Candle>,
+    last: Vec<Pattern>,
+}
+
+impl PatternDetector {
+    /// Patterns completed by the last bar
+    pub fn last(&self) -> &[Pattern] {
+        &self.last
+    }
+
+    /// One 0/1 per `Pattern::ALL` entry for the last bar
+    pub fn flags(&self) -> [f64; Pattern::ALL.len()] {
+        Pattern::ALL.map(|p| if self.last.contains(&p) { 1.0 } else { 0.0 })
+    }
+
+    /// Feed a closed bar; returns the patterns it completes
+    pub fn update(&mut self, bar: &Bar) -> &[Pattern] {
+        self.candles.push_back(Candle {
+            open: bar.open,
+            high: bar.high,
+            low: bar.low,
+            close: bar.close,
+        });
+        if self.candles.len() > 3 {
+            self.candles.pop_front();
+        }
+
+        self.last.clear();
+        let n = self.candles.len();
+        let cur = self.candles[n - 1];
+
+        if cur.range() > 0.0 {
+            if cur.body() <= DOJI_BODY * cur.range() {
+                self.last.push(Pattern::Doji);
+            } else if cur.lower() >= HAMMER_SHADOW * cur.body() && cur.upper() <= HAMMER_UPPER * cur.body() {
+                self.last.push(Pattern::Hammer);
+            }
+        }
+
+        if n >= 2 {
+            let prev = self.candles[n - 2];
+            let engulfs = cur.body() > prev.body()
+                && cur.open.min(cur.close) <= prev.open.min(prev.close)
+                && cur.open.max(cur.close) >= prev.open.max(prev.close);
+            if engulfs && prev.bearish() && cur.bullish() {
+                self.last.push(Pattern::BullishEngulfing);
+            }
+            if engulfs && prev.bullish() && cur.bearish() {
+                self.last.push(Pattern::BearishEngulfing);
+            }
+        }
+
+        if n == 3 {
+            let c = [self.candles[0], self.candles[1], self.candles[2]];
+            let soldiers = c.iter().all(|k| k.bullish() && k.upper() <= SOLDIER_WICK * k.range())
+                && c.windows(2).all(|w| {
+                    w[1].close > w[0].close && w[1].open >= w[0].open && w[1].open <= w[0].close
+                });
+            let crows = c.iter().all(|k| k.bearish() && k.lower() <= SOLDIER_WICK * k.range())
+                && c.windows(2).all(|w| {
+                    w[1].close < w[0].close && w[1].open <= w[0].open && w[1].open >= w[0].close
+                });
+            if soldiers {
+                self.last.push(Pattern::ThreeWhiteSoldiers);
+            }
+            if crows {
+                self.last.push(Pattern::ThreeBlackCrows);
+            }
+        }
+
+        &self.last
+    }
+}
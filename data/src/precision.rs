@@ -0,0 +1,145 @@
+#[cfg(feature = "decimal")]
+use std::str::FromStr;
+
+#[cfg(feature = "decimal")]
+use rust_decimal::{Decimal, RoundingStrategy};
+use tokio_postgres::{Client as PgClient, types::ToSql};
+
+use crate::{config, history};
+
+// History columns that become NUMERIC in decimal mode
+const NUMERIC_COLUMNS: [&str; 5] = ["open", "high", "low", "close", "volume"];
+
+/// The decimal an exchange sent, recovered from its f64: Rust prints the shortest text that
+/// parses back to the same f64, which is the exchange's text for up to 15 significant digits
+#[cfg(feature = "decimal")]
+fn exact(v: f64) -> Option<Decimal> {
+    if !v.is_finite() {
+        return None;
+    }
+    Decimal::from_str(&v.to_string()).ok()
+}
+
+/// A running sum of volumes; exact as well in decimal mode, where an f64 sum drifts (0.1 + 0.2)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Total {
+    value: f64,
+    #[cfg(feature = "decimal")]
+    exact: Decimal,
+}
+
+impl Total {
+    pub fn add(&mut self, v: f64) {
+        self.value += v;
+        #[cfg(feature = "decimal")]
+        if let Some(sum) = exact(v).and_then(|d| self.exact.checked_add(d)) {
+            self.exact = sum;
+        }
+    }
+}
+
+/// How prices and volumes are written to Redis and Postgres: f64 text and DOUBLE PRECISION
+/// columns, or with `precision.decimal` (and the `decimal` feature) exact decimals rounded
+/// half away from zero to the configured scales, and NUMERIC columns. Readers still get f64.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Precision {
+    decimal: bool,
+    price_scale: Option<u32>,
+    volume_scale: Option<u32>,
+}
+
+impl Precision {
+    /// Decimal only when the build has the `decimal` feature; config validation reports
+    /// the setting otherwise
+    pub fn from_config(p: &config::Precision) -> Self {
+        Self {
+            decimal: p.decimal && cfg!(feature = "decimal"),
+            price_scale: p.price_scale,
+            volume_scale: p.volume_scale,
+        }
+    }
+
+    pub fn is_decimal(&self) -> bool {
+        self.decimal
+    }
+
+    pub fn price(&self, v: f64) -> String {
+        self.format(v, self.price_scale)
+    }
+
+    pub fn volume(&self, v: f64) -> String {
+        self.format(v, self.volume_scale)
+    }
+
+    pub fn total(&self, t: &Total) -> String {
+        #[cfg(feature = "decimal")]
+        if self.decimal {
+            return round(t.exact, self.volume_scale).to_string();
+        }
+        t.value.to_string()
+    }
+
+    #[cfg_attr(not(feature = "decimal"), allow(unused_variables))]
+    fn format(&self, v: f64, scale: Option<u32>) -> String {
+        #[cfg(feature = "decimal")]
+        if self.decimal
+            && let Some(d) = exact(v)
+        {
+            return round(d, scale).to_string();
+        }
+        v.to_string()
+    }
+
+    /// SQL type a written price or volume is cast to
+    pub fn sql_type(&self) -> &'static str {
+        if self.decimal { "numeric" } else { "float8" }
+    }
+
+    /// A price or volume as the fetcher read it from Redis, as an insert parameter;
+    /// `None` when it does not parse
+    pub fn param(&self, s: &str) -> Option<Box<dyn ToSql + Sync>> {
+        #[cfg(feature = "decimal")]
+        if self.decimal {
+            return Decimal::from_str(s.trim()).ok().map(|d| Box::new(d) as Box<dyn ToSql + Sync>);
+        }
+        s.parse::<f64>().ok().map(|v| Box::new(v) as Box<dyn ToSql + Sync>)
+    }
+}
+
+#[cfg(feature = "decimal")]
+fn round(d: Decimal, scale: Option<u32>) -> Decimal {
+    scale.map_or(d, |s| d.round_dp_with_strategy(s, RoundingStrategy::MidpointAwayFromZero))
+}
+
+/// Make the history price and volume columns NUMERIC if any is not yet, rebuilding the
+/// readers' view around the change; returns whether anything changed. Existing rows are
+/// converted as they are, f64 noise included.
+pub async fn ensure_numeric(pg: &mut PgClient) -> Result<bool, tokio_postgres::Error> {
+    let columns: Vec<&str> = NUMERIC_COLUMNS.to_vec();
+    let floats: i64 = pg
+        .query_one(
+            "SELECT count(*) FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name = 'stock_price_history' \
+               AND column_name::text = ANY($1) AND data_type <> 'numeric'",
+            &[&columns],
+        )
+        .await?
+        .get(0);
+    if floats == 0 {
+        return Ok(false);
+    }
+    let alters: Vec<String> = NUMERIC_COLUMNS
+        .iter()
+        .map(|c| format!("ALTER COLUMN {c} TYPE NUMERIC USING {c}::numeric"))
+        .collect();
+    let tx = pg.transaction().await?;
+    tx.batch_execute(&format!(
+        "DROP VIEW IF EXISTS {}; ALTER TABLE stock_price_history {}; {}",
+        history::HISTORY_VIEW,
+        alters.join(", "),
+        history::view_sql()
+    ))
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
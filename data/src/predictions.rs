@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Client as PgClient;
+
+use crate::features::FEATURE_SCHEMA_VERSION;
+
+
+// 90th percentile of the standard normal
+const Z90: f64 = 1.281_551_565_545;
+
+/// One model output, as served from Redis and stored for evaluation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Prediction {
+    pub symbol: String,
+    pub horizon: String,
+    pub predicted_return: f64,
+    pub predicted_price: f64,
+    /// Close the prediction was made from
+    pub base_price: f64,
+    /// Rolling directional hit-rate of this model on this symbol, 0..1
+    pub confidence: f64,
+    /// Standard deviation of the predicted log return; 0 while unknown
+    #[serde(default)]
+    pub std_dev: f64,
+    /// 10th / 50th / 90th percentile of the log return (normal approximation)
+    #[serde(default)]
+    pub q10: f64,
+    #[serde(default)]
+    pub q50: f64,
+    #[serde(default)]
+    pub q90: f64,
+    pub model: String,
+    pub model_version: String,
+    /// Close time of the bar the features came from, ms since epoch
+    pub feature_ts: i64,
+    /// When the predicted move should have happened, ms since epoch
+    pub target_ts: i64,
+    /// Exchange time of the trade that closed the source bar, ms since epoch
+    #[serde(default)]
+    pub exchange_ts: i64,
+    /// When the ingester published the source bar, ms since epoch
+    #[serde(default)]
+    pub ingest_ts: i64,
+    /// When this prediction was produced, ms since epoch
+    #[serde(default)]
+    pub predicted_at: i64,
+}
+
+fn naive_ms(ms: i64) -> NaiveDateTime {
+    DateTime::from_timestamp_millis(ms)
+        .unwrap_or_default()
+        .naive_utc()
+}
+
+impl Prediction {
+    /// Set `std_dev` and the quantiles around `predicted_return`
+    pub fn set_uncertainty(&mut self, std_dev: f64) {
+        let std_dev = if std_dev.is_finite() { std_dev.max(0.0) } else { 0.0 };
+        self.std_dev = std_dev;
+        self.q10 = self.predicted_return - Z90 * std_dev;
+        self.q50 = self.predicted_return;
+        self.q90 = self.predicted_return + Z90 * std_dev;
+    }
+
+    /// Redis hash fields
+    pub fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("horizon".to_string(), self.horizon.clone()),
+            ("predicted_return".to_string(), self.predicted_return.to_string()),
+            ("predicted_price".to_string(), self.predicted_price.to_string()),
+            ("base_price".to_string(), self.base_price.to_string()),
+            ("confidence".to_string(), self.confidence.to_string()),
+            ("std_dev".to_string(), self.std_dev.to_string()),
+            ("q10".to_string(), self.q10.to_string()),
+            ("q50".to_string(), self.q50.to_string()),
+            ("q90".to_string(), self.q90.to_string()),
+            ("model".to_string(), self.model.clone()),
+            ("model_version".to_string(), self.model_version.clone()),
+            ("feature_ts".to_string(), self.feature_ts.to_string()),
+            ("target_ts".to_string(), self.target_ts.to_string()),
+            ("exchange_ts".to_string(), self.exchange_ts.to_string()),
+            ("ingest_ts".to_string(), self.ingest_ts.to_string()),
+            ("predicted_at".to_string(), self.predicted_at.to_string()),
+        ]
+    }
+
+    /// Inverse of `fields`; `None` when the hash is missing or incomplete
+    pub fn from_fields(symbol: &str, fields: &HashMap<String, String>) -> Option<Self> {
+        let text = |k: &str| fields.get(k).cloned();
+        let num = |k: &str| fields.get(k)?.parse::<f64>().ok();
+        let ms = |k: &str| fields.get(k)?.parse::<i64>().ok();
+        let predicted_return = num("predicted_return")?;
+        Some(Self {
+            symbol: symbol.to_string(),
+            horizon: text("horizon")?,
+            predicted_return,
+            predicted_price: num("predicted_price")?,
+            base_price: num("base_price")?,
+            confidence: num("confidence")?,
+            std_dev: num("std_dev").unwrap_or(0.0),
+            q10: num("q10").unwrap_or(predicted_return),
+            q50: num("q50").unwrap_or(predicted_return),
+            q90: num("q90").unwrap_or(predicted_return),
+            model: text("model")?,
+            model_version: text("model_version")?,
+            feature_ts: ms("feature_ts")?,
+            target_ts: ms("target_ts")?,
+            exchange_ts: ms("exchange_ts").unwrap_or(0),
+            ingest_ts: ms("ingest_ts").unwrap_or(0),
+            predicted_at: ms("predicted_at").unwrap_or(0),
+        })
+    }
+}
+
+/// Create the `predictions` table if missing
+pub async fn ensure_table(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS predictions ( \
+             id BIGSERIAL PRIMARY KEY, \
+             symbol TEXT NOT NULL, \
+             horizon TEXT NOT NULL, \
+             predicted_return DOUBLE PRECISION NOT NULL, \
+             predicted_price DOUBLE PRECISION NOT NULL, \
+             base_price DOUBLE PRECISION NOT NULL, \
+             confidence DOUBLE PRECISION NOT NULL, \
+             model TEXT NOT NULL, \
+             model_version TEXT NOT NULL, \
+             feature_ts TIMESTAMP NOT NULL, \
+             target_ts TIMESTAMP NOT NULL, \
+             realized_price DOUBLE PRECISION, \
+             realized_return DOUBLE PRECISION, \
+             created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc') \
+         ); \
+         CREATE INDEX IF NOT EXISTS predictions_symbol_target_idx ON predictions (symbol, target_ts); \
+         ALTER TABLE predictions ADD COLUMN IF NOT EXISTS model_id INTEGER; \
+         CREATE INDEX IF NOT EXISTS predictions_model_id_idx ON predictions (model_id, feature_ts); \
+         ALTER TABLE predictions ADD COLUMN IF NOT EXISTS std_dev DOUBLE PRECISION; \
+         ALTER TABLE predictions ADD COLUMN IF NOT EXISTS q10 DOUBLE PRECISION; \
+         ALTER TABLE predictions ADD COLUMN IF NOT EXISTS q90 DOUBLE PRECISION; \
+         ALTER TABLE predictions ADD COLUMN IF NOT EXISTS feature_values DOUBLE PRECISION[]; \
+         ALTER TABLE predictions ADD COLUMN IF NOT EXISTS feature_schema_version INTEGER;",
+    )
+    .await
+}
+
+/// `model_id` is the registry id for active and shadow predictions, `None` for ensemble members.
+/// `inputs` is the exact feature vector the model saw, kept for auditing.
+pub async fn insert(
+    pg: &PgClient,
+    p: &Prediction,
+    model_id: Option<i32>,
+    inputs: &[f64],
+) -> Result<u64, tokio_postgres::Error> {
+    pg.execute(
+        "INSERT INTO predictions \
+         (symbol, horizon, predicted_return, predicted_price, base_price, confidence, \
+          model, model_version, feature_ts, target_ts, model_id, std_dev, q10, q90, \
+          feature_values, feature_schema_version) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)",
+        &[
+            &p.symbol,
+            &p.horizon,
+            &p.predicted_return,
+            &p.predicted_price,
+            &p.base_price,
+            &p.confidence,
+            &p.model,
+            &p.model_version,
+            &naive_ms(p.feature_ts),
+            &naive_ms(p.target_ts),
+            &model_id,
+            &p.std_dev,
+            &p.q10,
+            &p.q90,
+            &inputs,
+            &FEATURE_SCHEMA_VERSION,
+        ],
+    )
+    .await
+}
+
+/// A stored prediction with the inputs it was made from
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub id: i64,
+    pub symbol: String,
+    pub model: String,
+    pub model_version: String,
+    pub model_id: Option<i32>,
+    pub predicted_return: f64,
+    pub feature_ts: NaiveDateTime,
+    pub feature_values: Vec<f64>,
+    pub feature_schema_version: i32,
+    pub realized_return: Option<f64>,
+}
+
+/// The audit snapshot of prediction `id`; `None` when it does not exist or predates auditing
+pub async fn audit(pg: &PgClient, id: i64) -> Result<Option<AuditRecord>, String> {
+    let row = pg
+        .query_opt(
+            "SELECT id, symbol, model, model_version, model_id, predicted_return, feature_ts, \
+                    feature_values, feature_schema_version, realized_return \
+             FROM predictions WHERE id = $1 AND feature_values IS NOT NULL",
+            &[&id],
+        )
+        .await
+        .map_err(|e| format!("prediction lookup failed: {e}"))?;
+    Ok(row.map(|r| AuditRecord {
+        id: r.get(0),
+        symbol: r.get(1),
+        model: r.get(2),
+        model_version: r.get(3),
+        model_id: r.get(4),
+        predicted_return: r.get(5),
+        feature_ts: r.get(6),
+        feature_values: r.get(7),
+        feature_schema_version: r.get::<_, Option<i32>>(8).unwrap_or_default(),
+        realized_return: r.get(9),
+    }))
+}
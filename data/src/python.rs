@@ -0,0 +1,104 @@
+use std::{
+    ffi::CString,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use pyo3::{prelude::*, types::PyModule};
+
+use tracing::error;
+use crate::models::Model;
+
+/// Research model written in Python. Contract: the module defines
+/// `predict(features: list[float]) -> float` returning the next-bar log return
+/// for a vector in `FEATURE_NAMES` order. An optional `update(features, realized)`
+/// makes it learn online and an optional `VERSION` string names the version
+/// (the file stem otherwise). One module instance is shared by every symbol.
+#[derive(Clone)]
+pub struct PyModel {
+    module: Arc<Py<PyModule>>,
+    n_features: usize,
+    version: String,
+    path: PathBuf,
+    online: bool,
+}
+
+impl PyModel {
+    pub fn load(path: &Path, n_features: usize) -> Result<Self, String> {
+        let fail = |e: String| format!("failed to load Python model {}: {e}", path.display());
+        let code = std::fs::read_to_string(path).map_err(|e| fail(e.to_string()))?;
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "model".to_string());
+        let cstr = |s: &str| CString::new(s).map_err(|e| fail(e.to_string()));
+        let (code, file_name, module_name) = (cstr(&code)?, cstr(&path.display().to_string())?, cstr(&stem)?);
+
+        Python::attach(|py| {
+            let module = PyModule::from_code(py, &code, &file_name, &module_name).map_err(|e| fail(e.to_string()))?;
+            if !module.hasattr("predict").unwrap_or(false) {
+                return Err(fail("module defines no predict(features)".to_string()));
+            }
+            let online = module.hasattr("update").unwrap_or(false);
+            let version = module
+                .getattr("VERSION")
+                .and_then(|v| v.extract::<String>())
+                .unwrap_or(stem);
+            Ok(Self {
+                module: Arc::new(module.unbind()),
+                n_features,
+                version,
+                path: path.to_path_buf(),
+                online,
+            })
+        })
+    }
+
+    /// File the model was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the module defines `update`
+    pub fn online(&self) -> bool {
+        self.online
+    }
+}
+
+impl Model for PyModel {
+    fn name(&self) -> &str {
+        "python"
+    }
+
+    fn version(&self) -> String {
+        self.version.clone()
+    }
+
+    fn predict(&self, x: &[f64]) -> f64 {
+        if x.len() != self.n_features {
+            return 0.0;
+        }
+        Python::attach(|py| {
+            self.module
+                .bind(py)
+                .getattr("predict")?
+                .call1((x.to_vec(),))?
+                .extract::<f64>()
+        })
+        .unwrap_or_else(|e| {
+            error!("❌ Python predict failed: {e}");
+            0.0
+        })
+    }
+
+    fn update(&mut self, x: &[f64], y: f64) {
+        if !self.online || x.len() != self.n_features {
+            return;
+        }
+        if let Err(e) = Python::attach(|py| {
+            self.module.bind(py).getattr("update")?.call1((x.to_vec(), y)).map(|_| ())
+        }) {
+            error!("❌ Python update failed: {e}");
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    time::{Duration, Instant},
+};
+
+use chrono::{Duration as ChronoDuration, NaiveDate, Utc};
+use redis::AsyncCommands;
+use serde::Serialize;
+use tokio_postgres::Client as PgClient;
+use tracing::info;
+
+use crate::{
+    alerts::Alert,
+    backfill,
+    config::Config,
+    fetcher::try_connect_pg,
+    keys::{DQ_PREFIX, SYMBOLS_KEY},
+    metrics::now_ms,
+    notify::Notifier,
+    redis_conn::{self, RedisConn},
+};
+
+const FLUSH_EVERY: Duration = Duration::from_secs(10);
+// Long enough for a late report, short enough not to pile up
+const KEY_TTL_SECS: i64 = 7 * 24 * 3600;
+const DEFAULT_STALE_SECS: i64 = 60;
+
+/// Counters the ingester and fetcher keep
+pub const TICKS: &str = "ticks";
+/// Trades dropped as unusable (bad price or volume, or too far from the last price)
+pub const OUTLIERS: &str = "outliers";
+/// Trades whose timestamp was too far from the local clock, skipped or clamped per `ingest.ts_policy`
+pub const BAD_TIMESTAMPS: &str = "bad_timestamps";
+/// Snapshots the fetcher could not insert
+pub const SKIPPED_INSERTS: &str = "skipped_inserts";
+/// A symbol traded again after more than `DQ_STALE_SECS` of silence
+pub const STALE: &str = "stale";
+/// Trades that came without a volume, counted as zero in the bars
+pub const MISSING_VOLUME: &str = "missing_volume";
+/// Stored minutes compared with the exchange's official candle
+pub const RECONCILED: &str = "reconciled";
+/// Of those, minutes whose prices disagreed beyond the tolerance
+pub const BAR_MISMATCHES: &str = "bar_mismatches";
+/// Mismatched minutes replaced with the official candle
+pub const BARS_CORRECTED: &str = "bars_corrected";
+/// Trades whose processing panicked in the ingester, skipped
+pub const POISON_TICKS: &str = "poison_ticks";
+
+/// In-process counts, added to the day's hash every few seconds so a hot loop does no extra writes
+pub struct DqCounters {
+    pending: HashMap<(String, &'static str), u64>,
+    last_trade: HashMap<String, i64>,
+    stale_ms: i64,
+    last_flush: Instant,
+}
+
+impl Default for DqCounters {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+impl DqCounters {
+    /// A silence longer than `DQ_STALE_SECS` (default 60) is a staleness incident
+    pub fn from_env() -> Self {
+        let stale_secs = env::var("DQ_STALE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_STALE_SECS);
+        Self {
+            pending: HashMap::new(),
+            last_trade: HashMap::new(),
+            stale_ms: stale_secs * 1000,
+            last_flush: Instant::now(),
+        }
+    }
+
+    pub fn add(&mut self, symbol: &str, counter: &'static str, n: u64) {
+        *self.pending.entry((symbol.to_string(), counter)).or_default() += n;
+    }
+
+    /// Count a usable trade, and a staleness incident when the symbol had gone quiet
+    pub fn on_trade(&mut self, symbol: &str, ts: i64) {
+        self.add(symbol, TICKS, 1);
+        if let Some(prev) = self.last_trade.insert(symbol.to_string(), ts)
+            && ts - prev > self.stale_ms
+        {
+            self.add(symbol, STALE, 1);
+        }
+    }
+
+    /// Add what was counted since the last flush to today's hash
+    pub async fn flush_if_due(&mut self, redis: &mut RedisConn) -> redis::RedisResult<()> {
+        if self.last_flush.elapsed() < FLUSH_EVERY {
+            return Ok(());
+        }
+        self.flush(redis).await
+    }
+
+    /// Add whatever is pending now, e.g. on the way out
+    pub async fn flush(&mut self, redis: &mut RedisConn) -> redis::RedisResult<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.last_flush = Instant::now();
+        let key = format!("{DQ_PREFIX}{}", Utc::now().date_naive());
+        let mut pipe = redis::pipe();
+        for ((symbol, counter), n) in &self.pending {
+            pipe.hincr(&key, format!("{symbol}|{counter}"), *n).ignore();
+        }
+        pipe.expire(&key, KEY_TTL_SECS).ignore();
+        pipe.query_async::<()>(redis).await?;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// One symbol's day
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SymbolQuality {
+    pub symbol: String,
+    pub ticks: i64,
+    /// Missing-minute ranges found by the gap scan
+    pub gaps: i64,
+    pub gap_minutes: i64,
+    /// Of those, minutes still missing
+    pub unrepaired_minutes: i64,
+    pub outliers: i64,
+    pub bad_timestamps: i64,
+    pub skipped_inserts: i64,
+    pub stale_incidents: i64,
+    pub missing_volume: i64,
+    pub reconciled_minutes: i64,
+    pub bar_mismatches: i64,
+    pub bars_corrected: i64,
+    pub poison_ticks: i64,
+}
+
+impl SymbolQuality {
+    fn issues(&self) -> i64 {
+        self.unrepaired_minutes + self.outliers + self.bad_timestamps + self.skipped_inserts + self.stale_incidents + self.poison_ticks
+            + (self.bar_mismatches - self.bars_corrected)
+            + i64::from(self.ticks == 0)
+    }
+}
+
+pub async fn ensure_table(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS data_quality_reports ( \
+            day                DATE        NOT NULL, \
+            symbol             TEXT        NOT NULL, \
+            ticks              BIGINT      NOT NULL, \
+            gaps               BIGINT      NOT NULL, \
+            gap_minutes        BIGINT      NOT NULL, \
+            unrepaired_minutes BIGINT      NOT NULL, \
+            outliers           BIGINT      NOT NULL, \
+            skipped_inserts    BIGINT      NOT NULL, \
+            stale_incidents    BIGINT      NOT NULL, \
+            missing_volume     BIGINT      NOT NULL DEFAULT 0, \
+            reconciled_minutes BIGINT      NOT NULL DEFAULT 0, \
+            bar_mismatches     BIGINT      NOT NULL DEFAULT 0, \
+            bars_corrected     BIGINT      NOT NULL DEFAULT 0, \
+            poison_ticks       BIGINT      NOT NULL DEFAULT 0, \
+            bad_timestamps     BIGINT      NOT NULL DEFAULT 0, \
+            created_at         TIMESTAMPTZ NOT NULL DEFAULT now(), \
+            PRIMARY KEY (day, symbol)); \
+         ALTER TABLE data_quality_reports ADD COLUMN IF NOT EXISTS missing_volume BIGINT NOT NULL DEFAULT 0; \
+         ALTER TABLE data_quality_reports ADD COLUMN IF NOT EXISTS reconciled_minutes BIGINT NOT NULL DEFAULT 0; \
+         ALTER TABLE data_quality_reports ADD COLUMN IF NOT EXISTS bar_mismatches BIGINT NOT NULL DEFAULT 0; \
+         ALTER TABLE data_quality_reports ADD COLUMN IF NOT EXISTS bars_corrected BIGINT NOT NULL DEFAULT 0; \
+         ALTER TABLE data_quality_reports ADD COLUMN IF NOT EXISTS poison_ticks BIGINT NOT NULL DEFAULT 0; \
+         ALTER TABLE data_quality_reports ADD COLUMN IF NOT EXISTS bad_timestamps BIGINT NOT NULL DEFAULT 0",
+    )
+    .await
+}
+
+/// Every tracked or counted symbol's quality on `day`
+pub async fn build(pg: &PgClient, redis: &mut RedisConn, day: NaiveDate) -> Result<Vec<SymbolQuality>, String> {
+    let counters: HashMap<String, i64> = redis
+        .hgetall(format!("{DQ_PREFIX}{day}"))
+        .await
+        .map_err(|e| format!("Redis hgetall error: {e}"))?;
+    let tracked: Vec<String> = redis
+        .smembers(SYMBOLS_KEY)
+        .await
+        .map_err(|e| format!("Redis smembers error: {e}"))?;
+
+    let mut report: BTreeMap<String, SymbolQuality> = tracked
+        .into_iter()
+        .map(|s| (s.clone(), SymbolQuality { symbol: s, ..Default::default() }))
+        .collect();
+    for (field, n) in counters {
+        let Some((symbol, counter)) = field.rsplit_once('|') else { continue };
+        let q = report
+            .entry(symbol.to_string())
+            .or_insert_with(|| SymbolQuality { symbol: symbol.to_string(), ..Default::default() });
+        match counter {
+            TICKS => q.ticks += n,
+            OUTLIERS => q.outliers += n,
+            BAD_TIMESTAMPS => q.bad_timestamps += n,
+            SKIPPED_INSERTS => q.skipped_inserts += n,
+            STALE => q.stale_incidents += n,
+            MISSING_VOLUME => q.missing_volume += n,
+            RECONCILED => q.reconciled_minutes += n,
+            BAR_MISMATCHES => q.bar_mismatches += n,
+            BARS_CORRECTED => q.bars_corrected += n,
+            POISON_TICKS => q.poison_ticks += n,
+            _ => {}
+        }
+    }
+
+    backfill::ensure_gaps_table(pg)
+        .await
+        .map_err(|e| format!("failed to create price_gaps: {e}"))?;
+    let from = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+    let to = from + ChronoDuration::days(1);
+    let rows = pg
+        .query(
+            "SELECT symbol, count(*), COALESCE(sum(minutes), 0), \
+                    COALESCE(sum(minutes) FILTER (WHERE status <> 'repaired'), 0) \
+             FROM price_gaps WHERE gap_start >= $1 AND gap_start < $2 GROUP BY symbol",
+            &[&from, &to],
+        )
+        .await
+        .map_err(|e| format!("price_gaps query failed: {e}"))?;
+    for r in rows {
+        let symbol: String = r.get(0);
+        let q = report
+            .entry(symbol.clone())
+            .or_insert_with(|| SymbolQuality { symbol, ..Default::default() });
+        q.gaps = r.get(1);
+        q.gap_minutes = r.get(2);
+        q.unrepaired_minutes = r.get(3);
+    }
+    Ok(report.into_values().collect())
+}
+
+/// Upsert the report rows for `day`
+pub async fn save(pg: &PgClient, day: NaiveDate, report: &[SymbolQuality]) -> Result<(), String> {
+    for q in report {
+        pg.execute(
+            "INSERT INTO data_quality_reports \
+             (day, symbol, ticks, gaps, gap_minutes, unrepaired_minutes, outliers, skipped_inserts, stale_incidents, \
+              missing_volume, reconciled_minutes, bar_mismatches, bars_corrected, poison_ticks, bad_timestamps) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15) \
+             ON CONFLICT (day, symbol) DO UPDATE SET \
+             ticks = EXCLUDED.ticks, gaps = EXCLUDED.gaps, gap_minutes = EXCLUDED.gap_minutes, \
+             unrepaired_minutes = EXCLUDED.unrepaired_minutes, outliers = EXCLUDED.outliers, \
+             skipped_inserts = EXCLUDED.skipped_inserts, stale_incidents = EXCLUDED.stale_incidents, \
+             missing_volume = EXCLUDED.missing_volume, reconciled_minutes = EXCLUDED.reconciled_minutes, \
+             bar_mismatches = EXCLUDED.bar_mismatches, bars_corrected = EXCLUDED.bars_corrected, \
+             poison_ticks = EXCLUDED.poison_ticks, bad_timestamps = EXCLUDED.bad_timestamps, created_at = now()",
+            &[
+                &day,
+                &q.symbol,
+                &q.ticks,
+                &q.gaps,
+                &q.gap_minutes,
+                &q.unrepaired_minutes,
+                &q.outliers,
+                &q.skipped_inserts,
+                &q.stale_incidents,
+                &q.missing_volume,
+                &q.reconciled_minutes,
+                &q.bar_mismatches,
+                &q.bars_corrected,
+                &q.poison_ticks,
+                &q.bad_timestamps,
+            ],
+        )
+        .await
+        .map_err(|e| format!("data_quality_reports insert for {} failed: {e}", q.symbol))?;
+    }
+    Ok(())
+}
+
+/// One-paragraph summary with the worst symbols named
+pub fn summarize(day: NaiveDate, report: &[SymbolQuality]) -> String {
+    let sum = |f: fn(&SymbolQuality) -> i64| report.iter().map(f).sum::<i64>();
+    let mut worst: Vec<&SymbolQuality> = report.iter().filter(|q| q.issues() > 0).collect();
+    worst.sort_by_key(|q| std::cmp::Reverse(q.issues()));
+    let mut message = format!(
+        "📋 Data quality {day}: {} symbols, {} ticks, {} gaps ({} min, {} unrepaired), {} outliers, \
+         {} bad timestamps, {} skipped inserts, {} staleness incidents, {} trades without volume, \
+         {} of {} reconciled minutes off the exchange's candles ({} corrected), {} trades that crashed processing",
+        report.len(),
+        sum(|q| q.ticks),
+        sum(|q| q.gaps),
+        sum(|q| q.gap_minutes),
+        sum(|q| q.unrepaired_minutes),
+        sum(|q| q.outliers),
+        sum(|q| q.bad_timestamps),
+        sum(|q| q.skipped_inserts),
+        sum(|q| q.stale_incidents),
+        sum(|q| q.missing_volume),
+        sum(|q| q.bar_mismatches),
+        sum(|q| q.reconciled_minutes),
+        sum(|q| q.bars_corrected),
+        sum(|q| q.poison_ticks),
+    );
+    if !worst.is_empty() {
+        let names: Vec<String> = worst
+            .iter()
+            .take(3)
+            .map(|q| match q.ticks {
+                0 => format!("{} (no ticks)", q.symbol),
+                _ => format!(
+                    "{} ({} unrepaired min, {} outliers, {} bad timestamps, {} skipped, {} stale, {} off the exchange)",
+                    q.symbol,
+                    q.unrepaired_minutes,
+                    q.outliers,
+                    q.bad_timestamps,
+                    q.skipped_inserts,
+                    q.stale_incidents,
+                    q.bar_mismatches - q.bars_corrected
+                ),
+            })
+            .collect();
+        message.push_str(&format!("; worst: {}", names.join(", ")));
+    }
+    message
+}
+
+/// Report yesterday (UTC): build, save and post it
+pub async fn run(config: &Config, notifier: Option<Notifier>) -> Result<(), String> {
+    let day = Utc::now().date_naive() - ChronoDuration::days(1);
+    let mut redis = redis_conn::try_connect(&config.redis_url(), &config.tls.redis)
+        .await
+        .map_err(|e| e.to_string())?;
+    let pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+    ensure_table(&pg)
+        .await
+        .map_err(|e| format!("failed to create data_quality_reports: {e}"))?;
+
+    let report = build(&pg, &mut redis, day).await?;
+    save(&pg, day, &report).await?;
+    let message = summarize(day, &report);
+    info!("{message}");
+    if let Some(n) = notifier {
+        n.notify(Alert {
+            kind: "data_quality".to_string(),
+            message,
+            ts: now_ms(),
+            details: serde_json::json!({ "day": day.to_string(), "symbols": report }),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_RATE_PER_SEC: f64 = 20.0;
+pub const DEFAULT_BURST: f64 = 40.0;
+// Idle buckets are dropped once the table grows past this
+const MAX_BUCKETS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitConfig {
+    /// Sustained requests per second per caller
+    pub rate_per_sec: f64,
+    /// Requests a caller may make at once after being idle
+    pub burst: f64,
+}
+
+impl RateLimitConfig {
+    /// `RATE_LIMIT_PER_SEC` / `RATE_LIMIT_BURST`; `None` when the rate is 0 (disabled)
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str, default: f64| {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| v.is_finite() && *v >= 0.0)
+                .unwrap_or(default)
+        };
+        let rate_per_sec = var("RATE_LIMIT_PER_SEC", DEFAULT_RATE_PER_SEC);
+        (rate_per_sec > 0.0).then(|| Self {
+            rate_per_sec,
+            burst: var("RATE_LIMIT_BURST", DEFAULT_BURST).max(1.0),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket per caller key (API key name or client IP)
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> RateLimitConfig {
+        self.config
+    }
+
+    /// Take one token for `key`, refilling at `rate_per_sec` (the configured rate when
+    /// `None`); `Err` holds how long until the next token
+    pub fn check(&self, key: &str, rate_per_sec: Option<f64>) -> Result<(), Duration> {
+        let rate = rate_per_sec.filter(|r| *r > 0.0).unwrap_or(self.config.rate_per_sec);
+        let burst = self.config.burst.max(rate);
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(key) {
+            // A bucket that would be full again holds no state worth keeping
+            let idle = Duration::from_secs_f64(burst / rate);
+            buckets.retain(|_, b| now.duration_since(b.updated) < idle);
+        }
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+/// One caller's share of a limiter, handed to long-lived connections so they can
+/// meter their own messages
+#[derive(Debug, Clone)]
+pub struct Quota {
+    pub limiter: Arc<RateLimiter>,
+    pub key: String,
+    pub rate_per_sec: Option<f64>,
+}
+
+impl Quota {
+    pub fn check(&self) -> Result<(), Duration> {
+        self.limiter.check(&self.key, self.rate_per_sec)
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime, Timelike, Utc};
+use redis::AsyncCommands;
+use tokio_postgres::Client as PgClient;
+use tracing::{error, info, warn};
+
+use crate::{
+    backfill::{insert_candles, CandleSource, SourceKind},
+    bars::Timeframe,
+    binance::MAX_KLINES,
+    cache,
+    config::Config,
+    fetcher::try_connect_pg,
+    finnhub::Candle,
+    history,
+    keys::SYMBOLS_KEY,
+    quality::{DqCounters, BARS_CORRECTED, BAR_MISMATCHES, RECONCILED},
+    redis_conn,
+};
+
+// The exchange may still amend the last couple of minutes, and the fetcher may not have stored them
+const SETTLE_MINUTES: i64 = 2;
+
+#[derive(Debug, Default)]
+pub struct ReconcileReport {
+    pub minutes: u64,
+    pub mismatches: u64,
+    pub corrected: u64,
+}
+
+/// Whether the stored minute disagrees with the official candle by more than `tolerance`
+/// (a fraction). Snapshots only sample the price, so the stored high and low may sit inside
+/// the official range but never outside it; the open is not compared, as the first snapshot
+/// of a minute comes after its first trade.
+fn mismatch(stored: &history::Candle, official: &Candle, tolerance: f64) -> bool {
+    (stored.close - official.close).abs() > official.close.abs() * tolerance
+        || stored.high > official.high * (1.0 + tolerance)
+        || stored.low < official.low * (1.0 - tolerance)
+}
+
+/// Swap the stored rows of one minute for the official candle
+async fn correct(pg: &mut PgClient, stock_id: i32, symbol: &str, candle: &Candle) -> Result<(), String> {
+    let tx = pg.transaction().await.map_err(|e| format!("transaction failed: {e}"))?;
+    tx.execute(
+        "DELETE FROM stock_price_history \
+         WHERE stock_id = $1 AND trade_time_stamp >= $2 AND trade_time_stamp < $3",
+        &[&stock_id, &candle.time, &(candle.time + Duration::minutes(1))],
+    )
+    .await
+    .map_err(|e| format!("delete for {symbol} failed: {e}"))?;
+    insert_candles(&tx, stock_id, symbol, std::slice::from_ref(candle)).await?;
+    tx.commit().await.map_err(|e| format!("commit failed: {e}"))
+}
+
+/// Compare one symbol's stored minutes in `[from, to)` with the official candles
+async fn reconcile_symbol(
+    pg: &mut PgClient,
+    source: &mut CandleSource,
+    dq: &mut DqCounters,
+    config: &Config,
+    (symbol, stock_id): (&str, i32),
+    (from, to): (NaiveDateTime, NaiveDateTime),
+) -> Result<ReconcileReport, String> {
+    let minute = Timeframe::parse("1m").expect("1m is a valid timeframe");
+    let tolerance = config.schedules.reconcile_tolerance_bps / 10_000.0;
+    let mut report = ReconcileReport::default();
+
+    let mut cursor = from;
+    while cursor < to {
+        let chunk_end = (cursor + Duration::minutes(MAX_KLINES as i64)).min(to);
+        let stored: HashMap<i64, history::Candle> =
+            history::candles(pg, symbol, minute, cursor, chunk_end, MAX_KLINES as i64)
+                .await?
+                .candles
+                .into_iter()
+                .map(|c| (c.start, c))
+                .collect();
+        if !stored.is_empty() {
+            for official in source.minutes(symbol, cursor, chunk_end).await? {
+                let Some(local) = stored.get(&official.time.and_utc().timestamp_millis()) else {
+                    continue;
+                };
+                report.minutes += 1;
+                if !mismatch(local, &official, tolerance) {
+                    continue;
+                }
+                report.mismatches += 1;
+                warn!(
+                    "⚠️ {symbol} {}: stored close {} high {} low {}, exchange close {} high {} low {}",
+                    official.time, local.close, local.high, local.low, official.close, official.high, official.low
+                );
+                if config.schedules.reconcile_correct {
+                    match correct(pg, stock_id, symbol, &official).await {
+                        Ok(()) => report.corrected += 1,
+                        Err(e) => error!("❌ {e}"),
+                    }
+                }
+            }
+        }
+        cursor = chunk_end;
+    }
+
+    dq.add(symbol, RECONCILED, report.minutes);
+    dq.add(symbol, BAR_MISMATCHES, report.mismatches);
+    dq.add(symbol, BARS_CORRECTED, report.corrected);
+    Ok(report)
+}
+
+/// Compare the minutes every tracked symbol stored since the previous run (`reconcile_secs`
+/// back, settled minutes only) with the exchange's official 1m candles. Disagreements are
+/// counted as data-quality metrics and, with `reconcile_correct`, the stored rows of those
+/// minutes are replaced with the official candle. Minutes missing on either side are the
+/// gap scan's business.
+pub async fn run(config: &Config) -> Result<ReconcileReport, String> {
+    let mut redis = redis_conn::try_connect(&config.redis_url(), &config.tls.redis)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut pg = try_connect_pg(&config.database_url(), &config.tls.postgres)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let now = Utc::now().naive_utc();
+    let now = now.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(now);
+    let to = now - Duration::minutes(SETTLE_MINUTES);
+    let minutes = config.schedules.reconcile_secs.div_ceil(60).max(1);
+    let from = to - Duration::minutes(minutes as i64);
+
+    let symbols: Vec<String> = redis
+        .smembers(SYMBOLS_KEY)
+        .await
+        .map_err(|e| format!("Redis smembers error: {e}"))?;
+    let ids: Vec<(String, i32)> = pg
+        .query("SELECT symbol, id FROM stocks WHERE symbol = ANY($1) ORDER BY symbol", &[&symbols])
+        .await
+        .map_err(|e| format!("failed to load stock map: {e}"))?
+        .into_iter()
+        .map(|r| (r.get(0), r.get(1)))
+        .collect();
+
+    let mut report = ReconcileReport::default();
+    let mut source = CandleSource::new(SourceKind::Auto, config.finnhub_key());
+    let mut dq = DqCounters::from_env();
+    for (symbol, stock_id) in &ids {
+        match reconcile_symbol(&mut pg, &mut source, &mut dq, config, (symbol, *stock_id), (from, to)).await {
+            Ok(r) => {
+                if r.mismatches > 0 {
+                    info!("🔎 {symbol}: {} of {} minutes off the exchange, {} corrected", r.mismatches, r.minutes, r.corrected);
+                }
+                if r.corrected > 0
+                    && let Err(e) = cache::invalidate(&mut redis, &[symbol]).await
+                {
+                    warn!("⚠️ Could not invalidate cached history for {symbol}: {e}");
+                }
+                report.minutes += r.minutes;
+                report.mismatches += r.mismatches;
+                report.corrected += r.corrected;
+            }
+            Err(e) => warn!("⚠️ Could not reconcile {symbol}: {e}"),
+        }
+    }
+    dq.flush(&mut redis)
+        .await
+        .map_err(|e| format!("Redis dq write error: {e}"))?;
+    Ok(report)
+}
@@ -0,0 +1,529 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, OnceLock, RwLock,
+    },
+    time::Duration,
+};
+
+use redis::{
+    aio::{ConnectionLike, MultiplexedConnection, PubSub},
+    cluster::ClusterClient,
+    cluster_async::ClusterConnection,
+    sentinel::{SentinelClient, SentinelClientBuilder, SentinelServerType},
+    Cmd, ConnectionAddr, ConnectionInfo, ErrorKind, Pipeline, RedisError, RedisFuture, RedisResult, TlsMode, Value,
+};
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Instant},
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    error::StoreError,
+    mirror::{Mirror, MirrorStats},
+    tls::{self, TlsSettings},
+};
+
+const RETRY_DELAY: Duration = Duration::from_secs(3);
+// While Redis is down, commands fail fast instead of each attempting a reconnect
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_SENTINEL_PORT: u16 = 26379;
+const DEFAULT_REDIS_PORT: u16 = 6379;
+
+/// URL schemes [`RedisClient::open_with`] understands
+pub const SCHEMES: [&str; 6] = [
+    "redis://",
+    "rediss://",
+    "redis+sentinel://",
+    "rediss+sentinel://",
+    "redis+cluster://",
+    "rediss+cluster://",
+];
+
+/// Where Redis lives, from the URL scheme:
+/// - `redis://` / `rediss://`: one server
+/// - `redis+sentinel://[user:pass@]s1:26379,s2:26379/mymaster[/db]`: the primary that the
+///   sentinels name, re-resolved when it fails over
+/// - `redis+cluster://[user:pass@]n1:6379,n2:6379`: a cluster, seeded from these nodes
+///
+/// The `rediss+` forms use TLS to every node, as does any form when the TLS mode asks for
+/// it; credentials are for the Redis nodes, not the sentinels. Cluster mode needs each
+/// pipeline or script to touch a single hash slot.
+#[derive(Clone)]
+pub struct RedisClient {
+    topology: Topology,
+    /// The plaintext client to fall back on under TLS mode `prefer`
+    plaintext: Option<Box<RedisClient>>,
+}
+
+#[derive(Clone)]
+enum Topology {
+    Single(redis::Client),
+    Sentinel {
+        service: String,
+        client: Arc<Mutex<SentinelClient>>,
+    },
+    Cluster {
+        client: Box<ClusterClient>,
+        /// First seed node, for pub/sub (cluster PUBLISH reaches every node)
+        seed: redis::Client,
+    },
+}
+
+impl fmt::Debug for RedisClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.describe())
+    }
+}
+
+/// `[user:pass@]h1:p1,h2:p2/path` as one `ConnectionInfo` per host (credentials and TLS
+/// included) and the path
+fn parse_multi_host(rest: &str, tls: bool, default_port: u16) -> Result<(Vec<ConnectionInfo>, &str), String> {
+    let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let (userinfo, hosts) = match authority.rsplit_once('@') {
+        Some((user, hosts)) => (format!("{user}@"), hosts),
+        None => (String::new(), authority),
+    };
+    let scheme = if tls { "rediss" } else { "redis" };
+    let nodes = hosts
+        .split(',')
+        .filter(|h| !h.is_empty())
+        .map(|h| {
+            let node = if h.contains(':') { h.to_string() } else { format!("{h}:{default_port}") };
+            format!("{scheme}://{userinfo}{node}")
+                .parse::<ConnectionInfo>()
+                .map_err(|e| format!("'{h}': {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if nodes.is_empty() {
+        return Err("no hosts".to_string());
+    }
+    Ok((nodes, path))
+}
+
+fn invalid(url_kind: &str, reason: impl fmt::Display) -> StoreError {
+    StoreError::RedisTopology(format!("{url_kind}: {reason}"))
+}
+
+/// A single-server client for `info`, with the CA bundle, client identity and checks applied
+/// when it is TLS
+fn single_client(mut info: ConnectionInfo, settings: &TlsSettings) -> Result<redis::Client, StoreError> {
+    let ConnectionAddr::TcpTls { insecure, .. } = &mut info.addr else {
+        return redis::Client::open(info).map_err(StoreError::RedisUrl);
+    };
+    *insecure = !settings.verify;
+    let client = match settings.redis_certificates()? {
+        Some(certs) => redis::Client::build_with_tls(info, certs).map_err(StoreError::RedisUrl)?,
+        None => redis::Client::open(info).map_err(StoreError::RedisUrl)?,
+    };
+    if settings.verify_hostname {
+        return Ok(client);
+    }
+    let mut info = client.get_connection_info().clone();
+    info.addr.set_danger_accept_invalid_hostnames(true);
+    redis::Client::open(info).map_err(StoreError::RedisUrl)
+}
+
+/// `url` with its scheme switched to or from the `rediss` form
+fn with_tls(url: &str, tls: bool) -> String {
+    let rest = url.strip_prefix("rediss").or_else(|| url.strip_prefix("redis")).unwrap_or(url);
+    format!("{}{rest}", if tls { "rediss" } else { "redis" })
+}
+
+impl RedisClient {
+    /// Parse `url` under the TLS settings; nothing is contacted until [`connect`](Self::connect)
+    pub fn open_with(url: &str, settings: &TlsSettings) -> Result<Self, StoreError> {
+        match settings.redis_mode(url)? {
+            tls::TlsMode::Disable => Self::build(url, settings),
+            tls::TlsMode::Require => Self::build(&with_tls(url, true), settings),
+            tls::TlsMode::Prefer => {
+                let mut client = Self::build(&with_tls(url, true), settings)?;
+                client.plaintext = Some(Box::new(Self::build(&with_tls(url, false), settings)?));
+                Ok(client)
+            }
+        }
+    }
+
+    fn build(url: &str, settings: &TlsSettings) -> Result<Self, StoreError> {
+        let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+        let tls = scheme.starts_with("rediss");
+        let tls_mode = if settings.verify { TlsMode::Secure } else { TlsMode::Insecure };
+        let certs = if tls { settings.redis_certificates()? } else { None };
+        let topology = match scheme {
+            "redis+sentinel" | "rediss+sentinel" => {
+                let (nodes, path) =
+                    parse_multi_host(rest, tls, DEFAULT_SENTINEL_PORT).map_err(|e| invalid("sentinel URL", e))?;
+                let mut path = path.split('/').filter(|p| !p.is_empty());
+                let service = path
+                    .next()
+                    .ok_or_else(|| invalid("sentinel URL", "no service name (…/mymaster)"))?
+                    .to_string();
+                let db = match path.next() {
+                    Some(db) => Some(
+                        db.parse::<i64>()
+                            .map_err(|_| invalid("sentinel URL", format!("invalid db '{db}'")))?,
+                    ),
+                    None => None,
+                };
+                let credentials = nodes[0].redis.clone();
+                let mut builder = SentinelClientBuilder::new(
+                    nodes.into_iter().map(|n| n.addr),
+                    service.clone(),
+                    SentinelServerType::Master,
+                )
+                .map_err(StoreError::RedisUrl)?;
+                if tls {
+                    if !settings.verify_hostname {
+                        return Err(invalid("sentinel URL", "TLS verify_hostname = false is not supported with sentinels"));
+                    }
+                    builder = builder
+                        .set_client_to_redis_tls_mode(tls_mode)
+                        .set_client_to_sentinel_tls_mode(tls_mode);
+                    if let Some(certs) = certs {
+                        builder = builder
+                            .set_client_to_redis_certificates(certs.clone())
+                            .set_client_to_sentinel_certificates(certs);
+                    }
+                }
+                if let Some(db) = db {
+                    builder = builder.set_client_to_redis_db(db);
+                }
+                if let Some(user) = credentials.username {
+                    builder = builder.set_client_to_redis_username(user);
+                }
+                if let Some(pass) = credentials.password {
+                    builder = builder.set_client_to_redis_password(pass);
+                }
+                Topology::Sentinel {
+                    service,
+                    client: Arc::new(Mutex::new(builder.build().map_err(StoreError::RedisUrl)?)),
+                }
+            }
+            "redis+cluster" | "rediss+cluster" => {
+                let (nodes, _) =
+                    parse_multi_host(rest, tls, DEFAULT_REDIS_PORT).map_err(|e| invalid("cluster URL", e))?;
+                let seed = single_client(nodes[0].clone(), settings)?;
+                let mut builder = ClusterClient::builder(nodes);
+                if tls {
+                    builder = builder.tls(tls_mode).danger_accept_invalid_hostnames(!settings.verify_hostname);
+                    if let Some(certs) = certs {
+                        builder = builder.certs(certs);
+                    }
+                }
+                Topology::Cluster {
+                    seed,
+                    client: Box::new(builder.build().map_err(StoreError::RedisUrl)?),
+                }
+            }
+            _ => Topology::Single(single_client(url.parse().map_err(StoreError::RedisUrl)?, settings)?),
+        };
+        Ok(Self { topology, plaintext: None })
+    }
+
+    /// "standalone", "sentinel service mymaster" or "cluster", for logs
+    pub fn describe(&self) -> String {
+        match &self.topology {
+            Topology::Single(client) => match client.get_connection_info().addr {
+                ConnectionAddr::TcpTls { .. } => "standalone (TLS)".to_string(),
+                _ => "standalone".to_string(),
+            },
+            Topology::Sentinel { service, .. } => format!("sentinel service {service}"),
+            Topology::Cluster { .. } => "cluster".to_string(),
+        }
+    }
+
+    /// A managed connection, shared by cloning; see [`RedisConn`]
+    pub async fn connect(&self) -> Result<RedisConn, StoreError> {
+        let link = match (self.link().await, &self.plaintext) {
+            (Ok(link), _) => link,
+            (Err(e), Some(plaintext)) => {
+                warn!("⚠️ Redis TLS connection failed ({e}); connecting without TLS as TLS mode prefer allows");
+                return Box::pin(plaintext.connect()).await;
+            }
+            (Err(e), None) => return Err(e.into()),
+        };
+        Ok(RedisConn {
+            shared: Arc::new(Shared {
+                client: self.clone(),
+                link: RwLock::new((0, link)),
+                last_failed_reconnect: Mutex::new(None),
+                counters: Counters::default(),
+                mirror: OnceLock::new(),
+            }),
+        })
+    }
+
+    /// A fresh connection to the server, the sentinels' current primary or the cluster
+    async fn link(&self) -> RedisResult<Link> {
+        Ok(match &self.topology {
+            Topology::Single(client) => Link::Multiplexed(client.get_multiplexed_async_connection().await?),
+            Topology::Sentinel { client, .. } => {
+                let primary = client.lock().await.async_get_client().await?;
+                Link::Multiplexed(primary.get_multiplexed_async_connection().await?)
+            }
+            Topology::Cluster { client, .. } => Link::Cluster(client.get_async_connection().await?),
+        })
+    }
+
+    /// `connect` until it succeeds
+    pub async fn connect_with_retry(&self) -> RedisConn {
+        loop {
+            match self.connect().await {
+                Ok(conn) => {
+                    info!("✅ Redis connection established");
+                    return conn;
+                }
+                Err(e) => {
+                    error!("❌ Redis connection failed: {}, retrying in {}s...", e, RETRY_DELAY.as_secs());
+                    sleep(RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+
+    /// Dedicated pub/sub connection (to the current primary, or a cluster seed node)
+    pub async fn pubsub(&self) -> Result<PubSub, StoreError> {
+        let client = match &self.topology {
+            Topology::Single(client) => client.clone(),
+            Topology::Sentinel { client, .. } => client.lock().await.async_get_client().await?,
+            Topology::Cluster { seed, .. } => seed.clone(),
+        };
+        match (client.get_async_pubsub().await, &self.plaintext) {
+            (Ok(pubsub), _) => Ok(pubsub),
+            (Err(e), Some(plaintext)) => {
+                warn!("⚠️ Redis TLS connection failed ({e}); subscribing without TLS as TLS mode prefer allows");
+                Box::pin(plaintext.pubsub()).await
+            }
+            (Err(e), None) => Err(e.into()),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Link {
+    Multiplexed(MultiplexedConnection),
+    /// Follows slot moves and replica promotions itself
+    Cluster(ClusterConnection),
+}
+
+impl ConnectionLike for Link {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match self {
+            Self::Multiplexed(conn) => conn.req_packed_command(cmd),
+            Self::Cluster(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(&'a mut self, cmd: &'a Pipeline, offset: usize, count: usize) -> RedisFuture<'a, Vec<Value>> {
+        match self {
+            Self::Multiplexed(conn) => conn.req_packed_commands(cmd, offset, count),
+            Self::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Self::Multiplexed(conn) => conn.get_db(),
+            Self::Cluster(conn) => conn.get_db(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    commands: AtomicU64,
+    failures: AtomicU64,
+    reconnects: AtomicU64,
+    disconnected: AtomicBool,
+}
+
+/// Counters for one managed connection and its clones, since it was opened
+#[derive(Debug, Clone, Copy)]
+pub struct RedisStats {
+    /// Commands and pipelines sent
+    pub commands: u64,
+    /// Of those, the ones that returned an error
+    pub failures: u64,
+    /// Connections replaced after a loss or failover
+    pub reconnects: u64,
+    /// False between a failed reconnect and the next successful one
+    pub connected: bool,
+    /// With a [`Mirror`] attached
+    pub mirror: Option<MirrorStats>,
+}
+
+impl RedisStats {
+    /// Prometheus-style fields, merged into each component's metrics hash
+    pub fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = vec![
+            ("redis_commands_total".to_string(), self.commands.to_string()),
+            ("redis_command_failures_total".to_string(), self.failures.to_string()),
+            ("redis_reconnects_total".to_string(), self.reconnects.to_string()),
+            ("redis_connected".to_string(), (self.connected as u8).to_string()),
+        ];
+        if let Some(mirror) = &self.mirror {
+            fields.extend(mirror.fields());
+        }
+        fields
+    }
+}
+
+struct Shared {
+    client: RedisClient,
+    /// Generation, bumped on every reconnect so concurrent failures reconnect once
+    link: RwLock<(u64, Link)>,
+    /// Also serialises reconnects
+    last_failed_reconnect: Mutex<Option<Instant>>,
+    counters: Counters,
+    mirror: OnceLock<Mirror>,
+}
+
+impl Shared {
+    fn current(&self) -> (u64, Link) {
+        let link = self.link.read().unwrap();
+        (link.0, link.1.clone())
+    }
+
+    /// A replacement for generation `seen`, or `None` while Redis stays unreachable
+    async fn reconnect(&self, seen: u64, cause: &RedisError) -> Option<Link> {
+        let mut last_failed = self.last_failed_reconnect.lock().await;
+        {
+            let link = self.link.read().unwrap();
+            if link.0 != seen {
+                return Some(link.1.clone());
+            }
+        }
+        if last_failed.is_some_and(|at| at.elapsed() < RECONNECT_BACKOFF) {
+            return None;
+        }
+        warn!("⚠️ Redis connection lost ({cause}), reconnecting to {}...", self.client.describe());
+        match self.client.link().await {
+            Ok(link) => {
+                *self.link.write().unwrap() = (seen + 1, link.clone());
+                *last_failed = None;
+                self.counters.reconnects.fetch_add(1, Ordering::Relaxed);
+                self.counters.disconnected.store(false, Ordering::Relaxed);
+                info!("✅ Reconnected to Redis");
+                Some(link)
+            }
+            Err(e) => {
+                error!("❌ Redis reconnect failed: {e}");
+                *last_failed = Some(Instant::now());
+                self.counters.disconnected.store(true, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    fn record<T>(&self, result: &RedisResult<T>) {
+        self.counters.commands.fetch_add(1, Ordering::Relaxed);
+        if result.is_err() {
+            self.counters.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The connection is gone, or the server it reached is no longer the primary
+fn connection_lost(e: &RedisError) -> bool {
+    e.is_io_error()
+        || e.is_connection_dropped()
+        || e.is_connection_refusal()
+        || matches!(e.kind(), ErrorKind::ReadOnly | ErrorKind::MasterDown)
+}
+
+/// The one Redis connection a process needs, from [`RedisClient::connect`]: every
+/// `AsyncCommands` method works on it and clones share it. A command that finds the
+/// connection lost (or the old primary demoted) reconnects, at most once per
+/// `RECONNECT_BACKOFF` while Redis is down, and is retried once; a write may therefore
+/// be applied twice if the server ran it just before the connection dropped.
+#[derive(Clone)]
+pub struct RedisConn {
+    shared: Arc<Shared>,
+}
+
+impl RedisConn {
+    pub fn stats(&self) -> RedisStats {
+        let c = &self.shared.counters;
+        RedisStats {
+            commands: c.commands.load(Ordering::Relaxed),
+            failures: c.failures.load(Ordering::Relaxed),
+            reconnects: c.reconnects.load(Ordering::Relaxed),
+            connected: !c.disconnected.load(Ordering::Relaxed),
+            mirror: self.shared.mirror.get().map(Mirror::stats),
+        }
+    }
+
+    /// Copy this connection's (and its clones') successful writes to `mirror` from now on
+    pub fn mirror_to(&self, mirror: Mirror) {
+        if self.shared.mirror.set(mirror).is_err() {
+            warn!("⚠️ Redis connection already has a mirror; keeping the first");
+        }
+    }
+}
+
+impl ConnectionLike for RedisConn {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(async move {
+            let shared = &self.shared;
+            let (seen, mut link) = shared.current();
+            let first = async {
+                #[cfg(feature = "chaos")]
+                crate::chaos::redis().await?;
+                link.req_packed_command(cmd).await
+            };
+            let result = match first.await {
+                Err(e) if connection_lost(&e) => match shared.reconnect(seen, &e).await {
+                    Some(mut link) => link.req_packed_command(cmd).await,
+                    None => Err(e),
+                },
+                result => result,
+            };
+            shared.record(&result);
+            if result.is_ok()
+                && let Some(mirror) = shared.mirror.get()
+            {
+                mirror.offer(cmd);
+            }
+            result
+        })
+    }
+
+    fn req_packed_commands<'a>(&'a mut self, cmd: &'a Pipeline, offset: usize, count: usize) -> RedisFuture<'a, Vec<Value>> {
+        Box::pin(async move {
+            let shared = &self.shared;
+            let (seen, mut link) = shared.current();
+            let first = async {
+                #[cfg(feature = "chaos")]
+                crate::chaos::redis().await?;
+                link.req_packed_commands(cmd, offset, count).await
+            };
+            let result = match first.await {
+                Err(e) if connection_lost(&e) => match shared.reconnect(seen, &e).await {
+                    Some(mut link) => link.req_packed_commands(cmd, offset, count).await,
+                    None => Err(e),
+                },
+                result => result,
+            };
+            shared.record(&result);
+            if result.is_ok()
+                && let Some(mirror) = shared.mirror.get()
+            {
+                mirror.offer_pipeline(cmd);
+            }
+            result
+        })
+    }
+
+    fn get_db(&self) -> i64 {
+        self.shared.current().1.get_db()
+    }
+}
+
+/// Open and connect to `redis_url` under the TLS settings
+pub async fn try_connect(redis_url: &str, settings: &TlsSettings) -> Result<RedisConn, StoreError> {
+    let client = RedisClient::open_with(redis_url, settings)?;
+    info!("🌐 Connecting to Redis ({})...", client.describe());
+    client.connect().await
+}
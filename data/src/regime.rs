@@ -0,0 +1,206 @@
+use std::{collections::VecDeque, env, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bars::Bar;
+
+
+const DEFAULT_WINDOW: usize = 20;
+// Net move over total path length above which the market counts as trending
+const DEFAULT_TREND_EFFICIENCY: f64 = 0.3;
+// Short-window vol over its long-run average above which the market counts as high-vol
+const DEFAULT_HIGH_VOL_RATIO: f64 = 1.5;
+// Weight of the newest squared return in the long-run variance
+const LONG_VAR_ALPHA: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum Regime {
+    Trending,
+    Ranging,
+    HighVol,
+}
+
+impl Regime {
+    pub const ALL: [Regime; 3] = [Regime::Trending, Regime::Ranging, Regime::HighVol];
+}
+
+impl fmt::Display for Regime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Regime::Trending => "trending",
+            Regime::Ranging => "ranging",
+            Regime::HighVol => "high_vol",
+        })
+    }
+}
+
+impl FromStr for Regime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trending" => Ok(Regime::Trending),
+            "ranging" => Ok(Regime::Ranging),
+            "high_vol" => Ok(Regime::HighVol),
+            other => Err(format!("unknown regime '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegimeConfig {
+    /// Bars in the rolling window
+    pub window: usize,
+    pub trend_efficiency: f64,
+    pub high_vol_ratio: f64,
+}
+
+impl Default for RegimeConfig {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_WINDOW,
+            trend_efficiency: DEFAULT_TREND_EFFICIENCY,
+            high_vol_ratio: DEFAULT_HIGH_VOL_RATIO,
+        }
+    }
+}
+
+impl RegimeConfig {
+    /// `REGIME_WINDOW`, `REGIME_TREND_EFFICIENCY`, `REGIME_HIGH_VOL_RATIO`
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let get = |k: &str| env::var(k).ok();
+        Self {
+            window: get("REGIME_WINDOW")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.window)
+                .max(2),
+            trend_efficiency: get("REGIME_TREND_EFFICIENCY")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.trend_efficiency),
+            high_vol_ratio: get("REGIME_HIGH_VOL_RATIO")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.high_vol_ratio),
+        }
+    }
+}
+
+/// Current classification and the statistics behind it
+#[derive(Debug, Clone, Copy)]
+pub struct RegimeState {
+    pub regime: Regime,
+    /// |net move| / sum of |bar moves| over the window, 0..1
+    pub efficiency: f64,
+    /// Window return std over its long-run average
+    pub vol_ratio: f64,
+    /// When this regime started, ms since epoch
+    pub since: i64,
+}
+
+impl RegimeState {
+    /// Redis hash fields
+    pub fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("regime".to_string(), self.regime.to_string()),
+            ("efficiency".to_string(), self.efficiency.to_string()),
+            ("vol_ratio".to_string(), self.vol_ratio.to_string()),
+            ("since".to_string(), self.since.to_string()),
+        ]
+    }
+}
+
+/// Published whenever a series changes regime
+#[derive(Debug, Clone, Serialize)]
+pub struct RegimeEvent {
+    pub symbol: String,
+    pub tf: String,
+    /// Close time of the bar, ms since epoch
+    pub ts: i64,
+    pub regime: Regime,
+    pub previous: Option<Regime>,
+    pub efficiency: f64,
+    pub vol_ratio: f64,
+}
+
+/// Classifies one symbol/timeframe from its recent closes: high-vol takes
+/// precedence, then trending by Kaufman efficiency ratio, otherwise ranging
+#[derive(Debug, Clone, Default)]
+pub struct RegimeDetector {
+    closes: VecDeque<f64>,
+    long_var: Option<f64>,
+    state: Option<RegimeState>,
+}
+
+impl RegimeDetector {
+    pub fn state(&self) -> Option<RegimeState> {
+        self.state
+    }
+
+    /// Feed a closed bar; returns the new state and, on a change, the event to publish
+    pub fn update(&mut self, cfg: &RegimeConfig, bar: &Bar) -> (Option<RegimeState>, Option<RegimeEvent>) {
+        if bar.close <= 0.0 {
+            return (self.state, None);
+        }
+        if let Some(&prev) = self.closes.back() {
+            let r = (bar.close / prev).ln();
+            let var = self.long_var.get_or_insert(r * r);
+            *var += LONG_VAR_ALPHA * (r * r - *var);
+        }
+        self.closes.push_back(bar.close);
+        if self.closes.len() > cfg.window + 1 {
+            self.closes.pop_front();
+        }
+        if self.closes.len() <= cfg.window {
+            return (None, None);
+        }
+
+        let returns: Vec<f64> = self
+            .closes
+            .iter()
+            .zip(self.closes.iter().skip(1))
+            .map(|(a, b)| (b / a).ln())
+            .collect();
+        let path: f64 = returns.iter().map(|r| r.abs()).sum();
+        let efficiency = if path > 0.0 { returns.iter().sum::<f64>().abs() / path } else { 0.0 };
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let std = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+        let long = self.long_var.unwrap_or(0.0).sqrt();
+        let vol_ratio = if long > 0.0 { std / long } else { 1.0 };
+
+        let regime = if vol_ratio >= cfg.high_vol_ratio {
+            Regime::HighVol
+        } else if efficiency >= cfg.trend_efficiency {
+            Regime::Trending
+        } else {
+            Regime::Ranging
+        };
+
+        let previous = self.state.map(|s| s.regime);
+        let since = match self.state {
+            Some(s) if s.regime == regime => s.since,
+            _ => bar.end(),
+        };
+        let state = RegimeState {
+            regime,
+            efficiency,
+            vol_ratio,
+            since,
+        };
+        self.state = Some(state);
+
+        let event = (previous != Some(regime)).then(|| RegimeEvent {
+            symbol: bar.symbol.clone(),
+            tf: bar.tf.clone(),
+            ts: bar.end(),
+            regime,
+            previous,
+            efficiency,
+            vol_ratio,
+        });
+        (Some(state), event)
+    }
+}
@@ -0,0 +1,244 @@
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use sha2::{Digest, Sha256};
+use tokio_postgres::{Client as PgClient, Row};
+
+use crate::models::ModelSpec;
+
+/// One registered model. `(name, version)` is unique; `spec` rebuilds it via
+/// `ModelSpec::from_registry`, and `artifact_hash` pins the file it was registered with.
+#[derive(Debug, Clone)]
+pub struct ModelRecord {
+    pub id: i32,
+    pub name: String,
+    pub version: String,
+    pub spec: String,
+    pub artifact_path: Option<String>,
+    pub artifact_hash: Option<String>,
+    pub train_from: Option<NaiveDateTime>,
+    pub train_to: Option<NaiveDateTime>,
+    /// Free-form JSON object, e.g. `{"mse": 1.2e-7, "hit_rate": 0.53}`
+    pub metrics: serde_json::Value,
+    pub active: bool,
+    /// Runs alongside the active model without driving signals
+    pub shadow: bool,
+    pub registered_at: NaiveDateTime,
+    pub activated_at: Option<NaiveDateTime>,
+}
+
+impl ModelRecord {
+    fn from_row(row: &Row) -> Self {
+        let metrics: String = row.get("metrics");
+        Self {
+            id: row.get("id"),
+            name: row.get("name"),
+            version: row.get("version"),
+            spec: row.get("spec"),
+            artifact_path: row.get("artifact_path"),
+            artifact_hash: row.get("artifact_hash"),
+            train_from: row.get("train_from"),
+            train_to: row.get("train_to"),
+            metrics: serde_json::from_str(&metrics).unwrap_or_default(),
+            active: row.get("active"),
+            shadow: row.get("shadow"),
+            registered_at: row.get("registered_at"),
+            activated_at: row.get("activated_at"),
+        }
+    }
+
+    /// Rebuild the model after checking its artifact still matches the registered hash
+    pub fn load(&self, n_features: usize) -> Result<ModelSpec, String> {
+        if let (Some(path), Some(expected)) = (&self.artifact_path, &self.artifact_hash) {
+            let actual = artifact_hash(Path::new(path))?;
+            if &actual != expected {
+                return Err(format!(
+                    "artifact {path} of {}@{} changed since registration (sha256 {actual}, expected {expected})",
+                    self.name, self.version
+                ));
+            }
+        }
+        ModelSpec::from_registry(&self.name, &self.spec, n_features)
+    }
+}
+
+/// Training provenance supplied at registration; all optional
+#[derive(Debug, Clone, Default)]
+pub struct Provenance {
+    pub train_from: Option<NaiveDateTime>,
+    pub train_to: Option<NaiveDateTime>,
+    pub metrics: Option<serde_json::Value>,
+}
+
+const COLUMNS: &str = "id, name, version, spec, artifact_path, artifact_hash, train_from, train_to, \
+                       metrics::text AS metrics, active, shadow, registered_at, activated_at";
+
+/// Hex SHA-256 of a model file
+pub fn artifact_hash(path: &Path) -> Result<String, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("cannot read artifact {}: {e}", path.display()))?;
+    Ok(Sha256::digest(&bytes).iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Create `model_registry` if missing
+pub async fn ensure_table(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS model_registry ( \
+             id SERIAL PRIMARY KEY, \
+             name TEXT NOT NULL, \
+             version TEXT NOT NULL, \
+             spec TEXT NOT NULL, \
+             artifact_path TEXT, \
+             artifact_hash TEXT, \
+             train_from TIMESTAMP, \
+             train_to TIMESTAMP, \
+             metrics JSONB NOT NULL DEFAULT '{}', \
+             active BOOLEAN NOT NULL DEFAULT FALSE, \
+             registered_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'), \
+             activated_at TIMESTAMP, \
+             UNIQUE (name, version) \
+         ); \
+         ALTER TABLE model_registry ADD COLUMN IF NOT EXISTS shadow BOOLEAN NOT NULL DEFAULT FALSE;",
+    )
+    .await
+}
+
+/// Register `spec`, or return the existing record for the same name and version.
+/// Re-registering a version whose artifact hash changed is an error; provenance
+/// given here fills in fields that were previously unknown.
+pub async fn register(pg: &PgClient, spec: &ModelSpec, provenance: &Provenance) -> Result<ModelRecord, String> {
+    let artifact = spec.artifact();
+    let hash = artifact.as_deref().map(artifact_hash).transpose()?;
+    let path = artifact.map(|p| p.display().to_string());
+    let metrics = provenance.metrics.as_ref().map(|m| m.to_string());
+
+    let row = pg
+        .query_opt(
+            &format!(
+                "INSERT INTO model_registry \
+                     (name, version, spec, artifact_path, artifact_hash, train_from, train_to, metrics) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, COALESCE($8::text, '{{}}')::jsonb) \
+                 ON CONFLICT (name, version) DO UPDATE SET \
+                     train_from = COALESCE(model_registry.train_from, EXCLUDED.train_from), \
+                     train_to = COALESCE(model_registry.train_to, EXCLUDED.train_to), \
+                     metrics = CASE WHEN $8::text IS NULL THEN model_registry.metrics \
+                                    ELSE model_registry.metrics || EXCLUDED.metrics END \
+                 WHERE model_registry.artifact_hash IS NOT DISTINCT FROM EXCLUDED.artifact_hash \
+                 RETURNING {COLUMNS}"
+            ),
+            &[
+                &spec.name(),
+                &spec.version(),
+                &spec.spec_string(),
+                &path,
+                &hash,
+                &provenance.train_from,
+                &provenance.train_to,
+                &metrics,
+            ],
+        )
+        .await
+        .map_err(|e| format!("model registration failed: {e}"))?;
+
+    // No row back: the version exists with a different artifact
+    row.map(|r| ModelRecord::from_row(&r)).ok_or_else(|| {
+        format!(
+            "{}@{} is already registered with a different artifact — register the new file under a new version",
+            spec.name(),
+            spec.version()
+        )
+    })
+}
+
+/// Make `id` the only active model; a promoted shadow stops being the shadow
+pub async fn activate(pg: &PgClient, id: i32) -> Result<ModelRecord, String> {
+    let rows = pg
+        .query(
+            &format!(
+                "UPDATE model_registry SET \
+                     active = (id = $1), \
+                     shadow = shadow AND id <> $1, \
+                     activated_at = CASE WHEN id = $1 THEN (now() AT TIME ZONE 'utc') ELSE activated_at END \
+                 WHERE (active OR id = $1) AND EXISTS (SELECT 1 FROM model_registry WHERE id = $1) \
+                 RETURNING {COLUMNS}"
+            ),
+            &[&id],
+        )
+        .await
+        .map_err(|e| format!("model activation failed: {e}"))?;
+    rows.iter()
+        .map(ModelRecord::from_row)
+        .find(|r| r.id == id)
+        .ok_or_else(|| format!("no registered model with id {id}"))
+}
+
+/// Register `spec` and make it the active model
+pub async fn deploy(pg: &PgClient, spec: &ModelSpec) -> Result<ModelRecord, String> {
+    let record = register(pg, spec, &Provenance::default()).await?;
+    activate(pg, record.id).await
+}
+
+/// Make `id` the only shadow model, or clear the shadow with `None`
+pub async fn set_shadow(pg: &PgClient, id: Option<i32>) -> Result<Option<ModelRecord>, String> {
+    let rows = pg
+        .query(
+            &format!(
+                "UPDATE model_registry SET shadow = (id = $1) \
+                 WHERE (shadow OR id = $1) AND NOT (id = $1 AND active) \
+                   AND ($1 = -1 OR EXISTS (SELECT 1 FROM model_registry WHERE id = $1 AND NOT active)) \
+                 RETURNING {COLUMNS}"
+            ),
+            &[&id.unwrap_or(-1)],
+        )
+        .await
+        .map_err(|e| format!("setting shadow model failed: {e}"))?;
+    match id {
+        None => Ok(None),
+        Some(id) => rows
+            .iter()
+            .map(ModelRecord::from_row)
+            .find(|r| r.id == id)
+            .map(Some)
+            .ok_or_else(|| format!("no inactive registered model with id {id}")),
+    }
+}
+
+/// The model currently running in shadow, if any
+pub async fn shadow(pg: &PgClient) -> Result<Option<ModelRecord>, String> {
+    pg.query_opt(&format!("SELECT {COLUMNS} FROM model_registry WHERE shadow LIMIT 1"), &[])
+        .await
+        .map(|row| row.as_ref().map(ModelRecord::from_row))
+        .map_err(|e| format!("shadow model lookup failed: {e}"))
+}
+
+/// The currently active model, if any
+pub async fn active(pg: &PgClient) -> Result<Option<ModelRecord>, String> {
+    pg.query_opt(&format!("SELECT {COLUMNS} FROM model_registry WHERE active LIMIT 1"), &[])
+        .await
+        .map(|row| row.as_ref().map(ModelRecord::from_row))
+        .map_err(|e| format!("active model lookup failed: {e}"))
+}
+
+pub async fn get(pg: &PgClient, id: i32) -> Result<Option<ModelRecord>, String> {
+    pg.query_opt(&format!("SELECT {COLUMNS} FROM model_registry WHERE id = $1"), &[&id])
+        .await
+        .map(|row| row.as_ref().map(ModelRecord::from_row))
+        .map_err(|e| format!("model lookup failed: {e}"))
+}
+
+pub async fn find(pg: &PgClient, name: &str, version: &str) -> Result<Option<ModelRecord>, String> {
+    pg.query_opt(
+        &format!("SELECT {COLUMNS} FROM model_registry WHERE name = $1 AND version = $2"),
+        &[&name, &version],
+    )
+    .await
+    .map(|row| row.as_ref().map(ModelRecord::from_row))
+    .map_err(|e| format!("model lookup failed: {e}"))
+}
+
+/// Every registered model, newest first
+pub async fn list(pg: &PgClient) -> Result<Vec<ModelRecord>, String> {
+    pg.query(&format!("SELECT {COLUMNS} FROM model_registry ORDER BY registered_at DESC, id DESC"), &[])
+        .await
+        .map(|rows| rows.iter().map(ModelRecord::from_row).collect())
+        .map_err(|e| format!("model listing failed: {e}"))
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::{sync::broadcast, time::sleep};
+use tracing::{error, info, warn};
+
+use crate::redis_conn::RedisClient;
+
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(3);
+
+
+/// One exchange trade as published on `TRADES_CHANNEL`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub symbol: String,
+    pub price: f64,
+    pub volume: f64,
+    /// Exchange time, ms since epoch
+    pub ts: i64,
+    /// `price` in USD for pairs quoted in another asset, when a live rate is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_usd: Option<f64>,
+}
+
+/// One pub/sub message as received
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub channel: String,
+    pub payload: String,
+}
+
+/// Subscribe to `channels` and forward every message into `tx`, resubscribing
+/// whenever the connection drops. Runs forever.
+pub async fn run(client: RedisClient, channels: Vec<&'static str>, tx: broadcast::Sender<Message>) {
+    loop {
+        let mut pubsub = match client.pubsub().await {
+            Ok(p) => p,
+            Err(e) => {
+                error!("❌ Redis pub/sub connection failed: {e}, retrying...");
+                sleep(RESUBSCRIBE_DELAY).await;
+                continue;
+            }
+        };
+        if let Err(e) = pubsub.subscribe(&channels).await {
+            error!("❌ Subscribe to {channels:?} failed: {e}, retrying...");
+            sleep(RESUBSCRIBE_DELAY).await;
+            continue;
+        }
+        info!("📡 Subscribed to {channels:?}");
+
+        let mut stream = pubsub.on_message();
+        while let Some(msg) = stream.next().await {
+            let Ok(payload) = msg.get_payload::<String>() else {
+                continue;
+            };
+            // No receivers is fine: nobody is listening right now
+            let _ = tx.send(Message {
+                channel: msg.get_channel_name().to_string(),
+                payload,
+            });
+        }
+
+        warn!("🔁 Subscription to {channels:?} dropped. Resubscribing...");
+        sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
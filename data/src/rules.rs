@@ -0,0 +1,350 @@
+use std::{collections::HashMap, env, fmt, path::Path};
+
+use serde::{Deserialize, Serialize};
+use tokio_postgres::{Client as PgClient, Row};
+
+use tracing::warn;
+use crate::{
+    bars::Bar,
+    predictions::Prediction,
+    regime::{RegimeConfig, RegimeDetector},
+};
+
+
+pub const DEFAULT_COOLDOWN_SECS: i64 = 300;
+
+fn default_cooldown() -> i64 {
+    DEFAULT_COOLDOWN_SECS
+}
+
+/// What a rule watches. Price and volatility conditions fire when the level is
+/// crossed; predicted-return conditions fire on every matching prediction,
+/// rate-limited by the rule's cooldown.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "threshold", rename_all = "snake_case")]
+pub enum Condition {
+    /// A bar closes at or above this price after the previous close was below it
+    PriceCrossAbove(f64),
+    /// A bar closes at or below this price after the previous close was above it
+    PriceCrossBelow(f64),
+    /// Predicted return above this many percent
+    PredictedReturnAbove(f64),
+    /// Predicted return below this many percent (e.g. -0.5)
+    PredictedReturnBelow(f64),
+    /// Window volatility rises to this multiple of its long-run average
+    VolSpike(f64),
+}
+
+impl Condition {
+    pub fn new(kind: &str, threshold: f64) -> Result<Self, String> {
+        match kind {
+            "price_cross_above" => Ok(Self::PriceCrossAbove(threshold)),
+            "price_cross_below" => Ok(Self::PriceCrossBelow(threshold)),
+            "predicted_return_above" => Ok(Self::PredictedReturnAbove(threshold)),
+            "predicted_return_below" => Ok(Self::PredictedReturnBelow(threshold)),
+            "vol_spike" => Ok(Self::VolSpike(threshold)),
+            other => Err(format!(
+                "unknown rule kind '{other}' (use price_cross_above, price_cross_below, \
+                 predicted_return_above, predicted_return_below or vol_spike)"
+            )),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::PriceCrossAbove(_) => "price_cross_above",
+            Self::PriceCrossBelow(_) => "price_cross_below",
+            Self::PredictedReturnAbove(_) => "predicted_return_above",
+            Self::PredictedReturnBelow(_) => "predicted_return_below",
+            Self::VolSpike(_) => "vol_spike",
+        }
+    }
+
+    pub fn threshold(&self) -> f64 {
+        match *self {
+            Self::PriceCrossAbove(t)
+            | Self::PriceCrossBelow(t)
+            | Self::PredictedReturnAbove(t)
+            | Self::PredictedReturnBelow(t)
+            | Self::VolSpike(t) => t,
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PriceCrossAbove(t) => write!(f, "price crosses above {t}"),
+            Self::PriceCrossBelow(t) => write!(f, "price crosses below {t}"),
+            Self::PredictedReturnAbove(t) => write!(f, "predicted return above {t}%"),
+            Self::PredictedReturnBelow(t) => write!(f, "predicted return below {t}%"),
+            Self::VolSpike(t) => write!(f, "volatility at {t}x its average"),
+        }
+    }
+}
+
+/// One user-defined alert, from `ALERT_RULES_FILE` or the `alert_rules` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    /// Row id for table rules
+    #[serde(default)]
+    pub id: Option<i32>,
+    pub name: String,
+    /// Every symbol when absent
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Bar timeframe for price/volatility conditions; every timeframe when absent
+    #[serde(default)]
+    pub tf: Option<String>,
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// Overrides `ALERT_WEBHOOK_URL`
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Overrides `TELEGRAM_CHAT_ID`
+    #[serde(default)]
+    pub telegram_chat_id: Option<String>,
+    /// Minimum time between two firings for the same symbol
+    #[serde(default = "default_cooldown")]
+    pub cooldown_secs: i64,
+}
+
+impl Rule {
+    fn from_row(row: &Row) -> Result<Self, String> {
+        Ok(Self {
+            id: Some(row.get("id")),
+            name: row.get("name"),
+            symbol: row.get("symbol"),
+            tf: row.get("tf"),
+            condition: Condition::new(row.get("kind"), row.get("threshold"))?,
+            webhook_url: row.get("webhook_url"),
+            telegram_chat_id: row.get("telegram_chat_id"),
+            cooldown_secs: row.get::<_, i32>("cooldown_secs") as i64,
+        })
+    }
+
+    /// Identifies the rule in cooldowns and events
+    pub fn key(&self) -> String {
+        match self.id {
+            Some(id) => format!("#{id} {}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    fn matches(&self, symbol: &str, tf: &str) -> bool {
+        self.symbol.as_deref().is_none_or(|s| s == symbol) && self.tf.as_deref().is_none_or(|t| t == tf)
+    }
+}
+
+/// Create the `alert_rules` table if missing
+pub async fn ensure_table(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS alert_rules ( \
+             id SERIAL PRIMARY KEY, \
+             name TEXT NOT NULL, \
+             symbol TEXT, \
+             tf TEXT, \
+             kind TEXT NOT NULL, \
+             threshold DOUBLE PRECISION NOT NULL, \
+             webhook_url TEXT, \
+             telegram_chat_id TEXT, \
+             cooldown_secs INTEGER NOT NULL DEFAULT 300, \
+             enabled BOOLEAN NOT NULL DEFAULT TRUE, \
+             created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc') \
+         );",
+    )
+    .await
+}
+
+/// Enabled table rules; rows with an unknown kind are skipped with a warning
+pub async fn load_table(pg: &PgClient) -> Result<Vec<Rule>, String> {
+    let rows = pg
+        .query("SELECT * FROM alert_rules WHERE enabled ORDER BY id", &[])
+        .await
+        .map_err(|e| format!("alert rules query failed: {e}"))?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| match Rule::from_row(row) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                warn!("⚠️ Skipping alert rule #{}: {e}", row.get::<_, i32>("id"));
+                None
+            }
+        })
+        .collect())
+}
+
+/// Every table rule, enabled or not, with its enabled flag
+pub async fn list(pg: &PgClient) -> Result<Vec<(Rule, bool)>, String> {
+    let rows = pg
+        .query("SELECT * FROM alert_rules ORDER BY id", &[])
+        .await
+        .map_err(|e| format!("alert rules query failed: {e}"))?;
+    rows.iter()
+        .map(|row| Ok((Rule::from_row(row)?, row.get("enabled"))))
+        .collect()
+}
+
+pub async fn insert(pg: &PgClient, rule: &Rule) -> Result<i32, String> {
+    let cooldown = rule.cooldown_secs as i32;
+    let row = pg
+        .query_one(
+            "INSERT INTO alert_rules \
+             (name, symbol, tf, kind, threshold, webhook_url, telegram_chat_id, cooldown_secs) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+            &[
+                &rule.name,
+                &rule.symbol,
+                &rule.tf,
+                &rule.condition.kind(),
+                &rule.condition.threshold(),
+                &rule.webhook_url,
+                &rule.telegram_chat_id,
+                &cooldown,
+            ],
+        )
+        .await
+        .map_err(|e| format!("alert rule insert failed: {e}"))?;
+    Ok(row.get(0))
+}
+
+/// Enable or disable a table rule; false when no rule has that id
+pub async fn set_enabled(pg: &PgClient, id: i32, enabled: bool) -> Result<bool, String> {
+    pg.execute("UPDATE alert_rules SET enabled = $2 WHERE id = $1", &[&id, &enabled])
+        .await
+        .map(|n| n > 0)
+        .map_err(|e| format!("alert rule update failed: {e}"))
+}
+
+pub async fn delete(pg: &PgClient, id: i32) -> Result<bool, String> {
+    pg.execute("DELETE FROM alert_rules WHERE id = $1", &[&id])
+        .await
+        .map(|n| n > 0)
+        .map_err(|e| format!("alert rule delete failed: {e}"))
+}
+
+/// JSON array of rules, e.g. `[{"name":"btc 100k","symbol":"BINANCE:BTCUSDT","kind":"price_cross_above","threshold":100000}]`
+pub fn load_file(path: &Path) -> Result<Vec<Rule>, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    serde_json::from_str(&json).map_err(|e| format!("invalid alert rules in {}: {e}", path.display()))
+}
+
+/// `ALERT_RULES_FILE`, if set
+pub fn from_env() -> Result<Vec<Rule>, String> {
+    match env::var("ALERT_RULES_FILE") {
+        Ok(path) => load_file(Path::new(&path)),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// A rule that matched, published and delivered as an alert
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleEvent {
+    pub rule: String,
+    pub symbol: String,
+    pub tf: String,
+    pub kind: &'static str,
+    pub threshold: f64,
+    /// Price, predicted return (%) or volatility ratio that triggered the rule
+    pub value: f64,
+    /// ms since epoch
+    pub ts: i64,
+    pub message: String,
+}
+
+/// Evaluates every rule against closed bars and predictions
+#[derive(Debug, Default)]
+pub struct RuleEngine {
+    rules: Vec<Rule>,
+    regime_cfg: RegimeConfig,
+    /// Last close per symbol, for price crosses
+    last_close: HashMap<String, f64>,
+    /// Volatility ratio per (symbol, timeframe)
+    vol: HashMap<(String, String), (RegimeDetector, f64)>,
+    /// Last firing per (rule, symbol), ms since epoch
+    fired: HashMap<(String, String), i64>,
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            rules,
+            regime_cfg: RegimeConfig::from_env(),
+            ..Default::default()
+        }
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Swap the rule set; price and volatility history carry over
+    pub fn set_rules(&mut self, rules: Vec<Rule>) {
+        self.rules = rules;
+    }
+
+    fn fire(&mut self, rule: &Rule, symbol: &str, tf: &str, value: f64, ts: i64) -> Option<(Rule, RuleEvent)> {
+        let key = (rule.key(), symbol.to_string());
+        if self.fired.get(&key).is_some_and(|last| ts - last < rule.cooldown_secs * 1000) {
+            return None;
+        }
+        self.fired.insert(key, ts);
+        let event = RuleEvent {
+            rule: rule.key(),
+            symbol: symbol.to_string(),
+            tf: tf.to_string(),
+            kind: rule.condition.kind(),
+            threshold: rule.condition.threshold(),
+            value,
+            ts,
+            message: format!("{}: {symbol} {} ({value:.6})", rule.name, rule.condition),
+        };
+        Some((rule.clone(), event))
+    }
+
+    /// Price crosses and volatility spikes on a closed bar
+    pub fn on_bar(&mut self, bar: &Bar) -> Vec<(Rule, RuleEvent)> {
+        let prev_close = self.last_close.insert(bar.symbol.clone(), bar.close);
+        let (detector, prev_ratio) = self.vol.entry((bar.symbol.clone(), bar.tf.clone())).or_default();
+        let before = *prev_ratio;
+        let ratio = detector.update(&self.regime_cfg, bar).0.map(|s| s.vol_ratio);
+        if let Some(r) = ratio {
+            *prev_ratio = r;
+        }
+
+        let mut fired = Vec::new();
+        for rule in self.rules.clone() {
+            if !rule.matches(&bar.symbol, &bar.tf) {
+                continue;
+            }
+            let value = match (rule.condition, prev_close, ratio) {
+                (Condition::PriceCrossAbove(x), Some(prev), _) if prev < x && bar.close >= x => bar.close,
+                (Condition::PriceCrossBelow(x), Some(prev), _) if prev > x && bar.close <= x => bar.close,
+                (Condition::VolSpike(x), _, Some(r)) if before < x && r >= x => r,
+                _ => continue,
+            };
+            fired.extend(self.fire(&rule, &bar.symbol, &bar.tf, value, bar.end()));
+        }
+        fired
+    }
+
+    /// Predicted-return thresholds
+    pub fn on_prediction(&mut self, p: &Prediction) -> Vec<(Rule, RuleEvent)> {
+        let pct = p.predicted_return * 100.0;
+        let mut fired = Vec::new();
+        for rule in self.rules.clone() {
+            if !rule.matches(&p.symbol, &p.horizon) {
+                continue;
+            }
+            let hit = match rule.condition {
+                Condition::PredictedReturnAbove(x) => pct > x,
+                Condition::PredictedReturnBelow(x) => pct < x,
+                _ => false,
+            };
+            if hit {
+                fired.extend(self.fire(&rule, &p.symbol, &p.horizon, pct, p.feature_ts));
+            }
+        }
+        fired
+    }
+}
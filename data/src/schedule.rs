@@ -0,0 +1,706 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use std::{collections::HashSet, sync::Arc, time::Instant};
+use tokio::{
+    task::{spawn_local, JoinHandle},
+    time::{sleep, timeout, Duration},
+};
+use tokio_util::sync::CancellationToken;
+
+use tracing::{error, info, warn};
+use crate::{
+    alerts::Alert,
+    backfill, cleaner,
+    clock::{self, SharedClock},
+    config::{Config, Schedules},
+    error::StoreError,
+    evaluation,
+    fetcher::{self, try_connect_pg},
+    health::{self, Health},
+    heartbeat::{self, Heartbeat},
+    history,
+    jobs::{self, Job, JobOutcome},
+    metrics::Metrics,
+    notify::Notifier,
+    instruments, quality, reconcile,
+    redis_conn::{self, RedisConn},
+    status,
+    symbols::{self, SymbolsFileWatcher},
+};
+
+//------------------------------------CONFIG & CONSTRAINTS--------------------------------------------------------
+
+const FETCHER_JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+const RESTART_DEBOUNCE: Duration = Duration::from_secs(3);
+// Restarting sooner would hit the same schema or auth error
+const FATAL_RESTART_DELAY: Duration = Duration::from_secs(60);
+// A few missed fetch cycles before the fetcher counts as stuck
+const FETCHER_MAX_AGE: Duration = Duration::from_secs(60);
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(10);
+
+// -----------------------------------HEARTBEAT WATCHDOG------------------------------------------------------------------------------
+
+/// Raises services whose work loop stopped beating, once per episode
+struct Watchdog {
+    flagged: HashSet<String>,
+    last_check: Option<Instant>,
+    notifier: Option<Notifier>,
+    clock: SharedClock,
+}
+
+impl Watchdog {
+    fn new(notifier: Option<Notifier>, clock: SharedClock) -> Self {
+        Self {
+            flagged: HashSet::new(),
+            last_check: None,
+            notifier,
+            clock,
+        }
+    }
+
+    async fn check(&mut self, redis: &mut RedisConn) {
+        let checked_at = self.clock.instant();
+        if self.last_check.is_some_and(|t| checked_at.duration_since(t) < WATCHDOG_INTERVAL) {
+            return;
+        }
+        self.last_check = Some(checked_at);
+        let beats = match heartbeat::read_all(redis).await {
+            Ok(beats) => beats,
+            Err(e) => {
+                warn!("⚠️ Could not read heartbeats: {e}");
+                return;
+            }
+        };
+        let now = self.clock.now_ms();
+        for beat in beats.into_iter().filter(|b| b.service != "trigger") {
+            if !beat.is_stale(now) {
+                if self.flagged.remove(&beat.service) {
+                    info!("💓 {} is beating again ({})", beat.service, beat.state);
+                }
+                continue;
+            }
+            if !self.flagged.insert(beat.service.clone()) {
+                continue;
+            }
+            let hung = !beat.is_gone(now);
+            let message = if hung {
+                format!(
+                    "{} is alive but its work loop has not progressed for {}s (state: {})",
+                    beat.service,
+                    (now - beat.beat_ms) / 1000,
+                    beat.state
+                )
+            } else {
+                format!(
+                    "{} stopped writing heartbeats {}s ago (last state: {})",
+                    beat.service,
+                    (now - beat.written_ms) / 1000,
+                    beat.state
+                )
+            };
+            warn!("💤 {message}");
+            if let Some(n) = &self.notifier {
+                n.notify(Alert {
+                    kind: "heartbeat_stale".to_string(),
+                    message,
+                    ts: now,
+                    details: serde_json::json!({
+                        "service": beat.service,
+                        "state": beat.state,
+                        "hung": hung,
+                        "pid": beat.pid,
+                        "version": beat.version,
+                        "beat_ms": beat.beat_ms,
+                    }),
+                });
+            }
+        }
+    }
+}
+
+// -----------------------------------DAILY WINDOW------------------------------------------------------------------------------
+
+/// What the daily window calls for at one time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Due {
+    /// Inside the window, where the fetcher stays stopped
+    in_window: bool,
+    /// Inside the window and today's maintenance has not run
+    maintenance: bool,
+    /// Past `backfill_at` after today's maintenance, and the backfill has not run
+    backfill: bool,
+}
+
+/// Which of the window's steps have run, by day
+struct WindowPlan {
+    schedules: Schedules,
+    last_maintained: Option<NaiveDate>,
+    last_backfilled: Option<NaiveDate>,
+}
+
+impl WindowPlan {
+    fn new(schedules: Schedules) -> Self {
+        Self {
+            schedules,
+            last_maintained: None,
+            last_backfilled: None,
+        }
+    }
+
+    fn due(&self, now: DateTime<Utc>) -> Due {
+        let today = now.date_naive();
+        let in_window = self.schedules.in_window(now.time());
+        Due {
+            in_window,
+            maintenance: in_window && self.last_maintained != Some(today),
+            backfill: !in_window
+                && now.time() >= self.schedules.backfill_at
+                && self.last_maintained == Some(today)
+                && self.last_backfilled != Some(today),
+        }
+    }
+
+    fn maintained(&mut self, day: NaiveDate) {
+        self.last_maintained = Some(day);
+    }
+
+    fn backfilled(&mut self, day: NaiveDate) {
+        self.last_backfilled = Some(day);
+    }
+}
+
+// -----------------------------------FETCHER PROCESS STRUCTURE------------------------------------------------------------------------------
+
+struct FetcherProc {
+    /// Parent of each run's token, so a shutdown stops the fetcher too
+    shutdown: CancellationToken,
+    /// Set while the fetcher should be running; cancelled to stop it
+    stop: Option<CancellationToken>,
+    handle: Option<JoinHandle<Result<(), StoreError>>>,
+    restart_at: Option<Instant>,
+    health: Arc<Health>,
+    notifier: Option<Notifier>,
+    config: Arc<Config>,
+    clock: SharedClock,
+}
+
+impl FetcherProc {
+    fn new(
+        health: Arc<Health>,
+        notifier: Option<Notifier>,
+        config: Arc<Config>,
+        clock: SharedClock,
+        shutdown: CancellationToken,
+    ) -> Self {
+        Self {
+            shutdown,
+            stop: None,
+            handle: None,
+            restart_at: None,
+            health,
+            notifier,
+            config,
+            clock,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.stop.is_some() && self.handle.as_ref().map(|h| !h.is_finished()).unwrap_or(false)
+    }
+
+    async fn start(&mut self) {
+        if self.is_running() {
+            return;
+        }
+        // Meant to run but finished: it failed, exited or panicked on its own
+        if self.stop.is_some()
+            && let Some(handle) = self.handle.take_if(|h| h.is_finished())
+        {
+            let (reason, delay, fatal) = match handle.await {
+                Ok(Err(e)) if e.is_transient() => (format!("failed: {e}"), RESTART_DEBOUNCE, false),
+                Ok(Err(e)) => (format!("failed: {e}"), FATAL_RESTART_DELAY, true),
+                Ok(Ok(())) => ("exited unexpectedly".to_string(), RESTART_DEBOUNCE, false),
+                Err(e) => (format!("panicked: {e}"), RESTART_DEBOUNCE, false),
+            };
+            warn!("⚠️ fetcher {reason}; restarting in {delay:?}");
+            self.health.fail("fetcher", &reason);
+            if let Some(n) = &self.notifier {
+                n.notify(Alert {
+                    kind: "fetcher_restart".to_string(),
+                    message: format!("Fetcher {reason}; restarting in {}s", delay.as_secs()),
+                    ts: self.clock.now_ms(),
+                    details: serde_json::json!({ "fatal": fatal }),
+                });
+            }
+            self.restart_at = Some(self.clock.instant() + delay);
+        }
+        if let Some(t) = self.restart_at
+            && self.clock.instant() < t
+        {
+            return;
+        }
+        let stop = self.shutdown.child_token();
+        self.stop = Some(stop.clone());
+        let health = self.health.clone();
+        let config = self.config.clone();
+        health.expect("fetcher", Some(FETCHER_MAX_AGE));
+        self.handle = Some(spawn_local(fetcher::run(stop, health, config)));
+        self.restart_at = None;
+        info!("✅ fetcher started");
+    }
+
+    async fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop.cancel();
+        }
+        // Stopped on purpose for maintenance, so not a readiness failure
+        self.health.clear("fetcher");
+
+        if let Some(handle) = self.handle.take() {
+            info!("🛑 stopping fetcher…");
+            match timeout(FETCHER_JOIN_TIMEOUT, handle).await {
+                Ok(Ok(Ok(()))) => info!("🧹 fetcher stopped cleanly"),
+                Ok(Ok(Err(e))) => warn!("⚠️ fetcher had failed before stopping: {e}"),
+                Ok(Err(e)) => warn!("⚠️ fetcher task panicked: {e}"),
+                Err(_) => {
+                    warn!(
+                        "⏳ fetcher didn’t stop in {:?}; force-abort",
+                        FETCHER_JOIN_TIMEOUT
+                    );
+                }
+            }
+        }
+    }
+}
+
+// -----------------------------------MAINTENANCE JOBS------------------------------------------------------------------
+
+/// Run `python3 scripts/push.py <mode>` and surface stderr on failure
+async fn run_push_script(mode: &str) -> Result<(), String> {
+    let output = tokio::process::Command::new("python3")
+        .arg("scripts/push.py")
+        .arg(mode)
+        .output()
+        .await
+        .map_err(|e| format!("failed to launch push.py: {e}"))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+}
+
+/// Daily maintenance graph: archive and score predictions first (both need the
+/// history), then push and clean in parallel; yesterday's data-quality report beside them
+pub fn maintenance_jobs(config: Arc<Config>, notifier: Option<Notifier>) -> Vec<Job> {
+    let (report_config, report_notifier) = (config.clone(), notifier.clone());
+    let (instruments_config, evaluate_config) = (config.clone(), config.clone());
+    vec![
+        Job::new("instruments", move || async move {
+            instruments::run(&instruments_config).await.map(log_instruments)
+        }),
+        Job::new("quality", move || async move { quality::run(&report_config, report_notifier).await }),
+        Job::new("export", || run_push_script("export")),
+        Job::new("evaluate", move || async move { evaluation::run(&evaluate_config).await.map(|_| ()) }),
+        Job::new("push", || run_push_script("push")).after("export"),
+        Job::new("clean", move || async move {
+            cleaner::run(&config, notifier).await.map_err(|e| e.to_string())
+        })
+        .after("export")
+        .after("evaluate"),
+    ]
+}
+
+/// After the window: repair the minutes the fetcher was stopped for
+pub fn post_maintenance_jobs(config: Arc<Config>, day: NaiveDate) -> Vec<Job> {
+    let from = day.and_time(config.schedules.maintenance_start);
+    let to = day.and_time(config.schedules.maintenance_end);
+    vec![Job::new("backfill", move || async move {
+        backfill::run(&config, from, to).await.map(|_| ())
+    })]
+}
+
+/// Run the post-maintenance jobs for `day`
+async fn backfill_window(config: &Arc<Config>, day: NaiveDate) {
+    let parallelism = config.schedules.parallelism;
+    if let Err(e) = jobs::run_jobs(post_maintenance_jobs(config.clone(), day), parallelism).await {
+        error!("❌ post-maintenance jobs not run: {e}");
+    }
+}
+
+/// Find and fill holes restarts and outages left in the history; runs beside the main loop
+async fn scan_gaps(config: Arc<Config>) {
+    match backfill::repair_gaps(&config).await {
+        Ok(r) if r.found == 0 => info!("✅ No gaps in stock_price_history"),
+        Ok(r) => info!(
+            "🩹 Gap scan: {} found, {} repaired ({} rows), {} unrepairable",
+            r.found, r.repaired, r.rows, r.unrepairable
+        ),
+        Err(e) => warn!("⚠️ Gap scan failed: {e}"),
+    }
+}
+
+/// Check the latest stored minutes against the exchange's candles; runs beside the main loop
+async fn reconcile_bars(config: Arc<Config>) {
+    match reconcile::run(&config).await {
+        Ok(r) if r.mismatches == 0 => info!("✅ {} stored minutes match the exchange", r.minutes),
+        Ok(r) => info!(
+            "🔎 Reconciliation: {} of {} minutes off the exchange, {} corrected",
+            r.mismatches, r.minutes, r.corrected
+        ),
+        Err(e) => warn!("⚠️ Reconciliation failed: {e}"),
+    }
+}
+
+fn log_instruments(r: instruments::InstrumentReport) {
+    info!(
+        "📐 Instruments: {} refreshed, {} missing on their exchange, {} on exchanges without metadata",
+        r.updated, r.missing, r.unsupported
+    );
+}
+
+/// Refresh instrument metadata at startup, so symbols added since the last maintenance have it;
+/// runs beside the main loop
+async fn refresh_instruments(config: Arc<Config>) {
+    match instruments::run(&config).await {
+        Ok(r) => log_instruments(r),
+        Err(e) => warn!("⚠️ Instrument refresh failed: {e}"),
+    }
+}
+
+/// Run the maintenance graph, record it as the `maintenance` status and raise failures in chat;
+/// start and finish times come from `clock`
+pub async fn maintain(config: &Arc<Config>, notifier: Option<Notifier>, redis: &mut RedisConn, clock: &SharedClock) {
+    let now = clock.now();
+    let parallelism = config.schedules.parallelism;
+    info!(
+        "🛠️ maintenance starting at {} (parallelism {parallelism})",
+        now.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+
+    let outcomes = match jobs::run_jobs(maintenance_jobs(config.clone(), notifier.clone()), parallelism).await {
+        Ok(outcomes) => outcomes,
+        Err(e) => {
+            error!("❌ maintenance not run: {e}");
+            if let Some(n) = &notifier {
+                n.notify(Alert {
+                    kind: "maintenance".to_string(),
+                    message: format!("maintenance not run: {e}"),
+                    ts: clock.now_ms(),
+                    details: serde_json::json!({ "error": e }),
+                });
+            }
+            return;
+        }
+    };
+    let failed = outcomes
+        .iter()
+        .filter(|(_, o)| *o != JobOutcome::Succeeded)
+        .count();
+
+    info!(
+        "✅ maintenance completed: {} jobs, {failed} failed/skipped",
+        outcomes.len()
+    );
+    let fields = [
+        ("started_at", now.to_rfc3339()),
+        ("finished_at", clock.now().to_rfc3339()),
+        ("jobs", outcomes.len().to_string()),
+        ("failed", failed.to_string()),
+    ];
+    if let Err(e) = status::record(redis, "maintenance", &fields).await {
+        warn!("⚠️ Could not record maintenance status: {e}");
+    }
+    if failed > 0
+        && let Some(n) = &notifier
+    {
+        let failures: Vec<String> = outcomes
+            .iter()
+            .filter_map(|(name, o)| match o {
+                JobOutcome::Succeeded => None,
+                JobOutcome::Failed(e) => Some(format!("{name} failed ({})", e.trim())),
+                JobOutcome::Skipped(why) => Some(format!("{name} skipped ({why})")),
+            })
+            .collect();
+        n.notify(Alert {
+            kind: "maintenance".to_string(),
+            message: format!(
+                "{failed} of {} maintenance jobs did not succeed: {}",
+                outcomes.len(),
+                failures.join(", ")
+            ),
+            ts: clock.now_ms(),
+            details: serde_json::json!({ "failed": failures }),
+        });
+    }
+}
+
+/// What the trigger would do with this config at `clock`'s time, for `--dry-run`
+pub fn print_plan(config: &Arc<Config>, clock: &SharedClock) {
+    let s = &config.schedules;
+    let hm = |t: chrono::NaiveTime| t.format("%H:%M").to_string();
+    println!("🧪 Dry run: nothing is started, stopped or written");
+    println!(
+        "   fetcher: snapshot every {:?}, stopped {}–{} UTC",
+        config.intervals.fetch(),
+        hm(s.maintenance_start),
+        hm(s.maintenance_end)
+    );
+    println!("   maintenance at {} UTC, {} jobs at a time:", hm(s.maintenance_start), s.parallelism);
+    let jobs = maintenance_jobs(config.clone(), None);
+    if let Err(e) = jobs::check_graph(&jobs) {
+        println!("   ❌ {e}; maintenance would not run");
+    }
+    for job in jobs {
+        match job.deps() {
+            [] => println!("     - {}", job.name()),
+            deps => println!("     - {} (after {})", job.name(), deps.join(", ")),
+        }
+    }
+    match config.retention.history_days {
+        0 => println!("   clean: empty stock_price_history"),
+        n => println!("   clean: delete stock_price_history rows older than {n} days"),
+    }
+    println!("   backfill of the window at {} UTC", hm(s.backfill_at));
+    match s.gap_scan() {
+        Some(every) => println!(
+            "   gap repair every {every:?} over the last {}h of retained history",
+            s.gap_lookback_hours
+        ),
+        None => println!("   gap repair: off"),
+    }
+    match s.reconcile() {
+        Some(every) => println!(
+            "   reconciliation with exchange candles every {every:?} ({} bps tolerance, {})",
+            s.reconcile_tolerance_bps,
+            if s.reconcile_correct { "correcting" } else { "report only" }
+        ),
+        None => println!("   reconciliation: off"),
+    }
+    if let Some(path) = &config.symbols_file {
+        println!("   symbols file: {} synced whenever it changes", path.display());
+    }
+    match config.sinks.notify_config() {
+        Some(n) => println!("   notifications: {} chat webhook(s)", n.targets.len()),
+        None => println!("   notifications: none configured"),
+    }
+    if s.in_window(clock.now().time()) {
+        println!("   now: inside the window, the fetcher would be stopped and maintenance run");
+    } else {
+        println!("   now: outside the window, the fetcher would run");
+    }
+}
+
+//-----------------------------------MAIN LOOP------------------------------------------------------------------
+
+/// Maintenance now, then the backfill when its time has passed; for `--once`.
+/// Must run inside a `LocalSet`.
+pub async fn run_once(config: Arc<Config>) -> Result<(), StoreError> {
+    let schedules = &config.schedules;
+    let mut redis = redis_conn::try_connect(&config.redis_url(), &config.tls.redis).await?;
+    let notifier = config.sinks.notify_config().map(|c| Notifier::spawn("trigger", c));
+    let clock = clock::system();
+    maintain(&config, notifier.clone(), &mut redis, &clock).await;
+    let now = clock.now();
+    if now.time() >= schedules.backfill_at {
+        backfill_window(&config, now.date_naive()).await;
+    }
+    if let Some(n) = notifier {
+        n.close().await;
+    }
+    Ok(())
+}
+
+/// Keep the fetcher running outside the daily window, maintain inside it and backfill
+/// after it, until `shutdown` is cancelled; then stop the fetcher, let a gap scan or
+/// reconciliation in progress finish and deliver pending notifications. Must run inside a
+/// `LocalSet`. Fails only if the stores cannot be reached at startup.
+pub async fn run(config: Arc<Config>, shutdown: CancellationToken) -> Result<(), StoreError> {
+    run_with_clock(config, clock::system(), shutdown).await
+}
+
+/// [`run`], reading the time from `clock`: window, backfill and gap-scan decisions, fetcher
+/// restart delays and heartbeat staleness. Ticks still sleep in real time.
+pub async fn run_with_clock(config: Arc<Config>, clock: SharedClock, shutdown: CancellationToken) -> Result<(), StoreError> {
+    let schedules = config.schedules.clone();
+    let loop_tick = config.intervals.trigger_tick();
+    let health = Health::with_clock("trigger", clock.clone());
+
+    health::spawn_server(health.clone());
+    let mut redis = redis_conn::try_connect(&config.redis_url(), &config.tls.redis).await?;
+    let probe_pg = Arc::new(try_connect_pg(&config.database_url(), &config.tls.postgres).await?);
+    tokio::spawn(health::probe(health.clone(), Some(redis.clone()), Some(probe_pg.clone())));
+
+    history::ensure_view(&probe_pg).await?;
+    let notifier = config.sinks.notify_config().map(|c| Notifier::spawn("trigger", c));
+    let mut symbols_file = config.symbols_file.as_ref().map(SymbolsFileWatcher::new);
+    if symbols_file.is_some() {
+        symbols::ensure_columns(&probe_pg).await?;
+    }
+    spawn_local(refresh_instruments(config.clone()));
+    let mut fetcher = FetcherProc::new(health.clone(), notifier.clone(), config.clone(), clock.clone(), shutdown.clone());
+    let mut plan = WindowPlan::new(schedules.clone());
+    let mut last_gap_scan: Option<Instant> = None;
+    let mut gap_scan: Option<JoinHandle<()>> = None;
+    let mut last_reconcile: Option<Instant> = None;
+    let mut reconciling: Option<JoinHandle<()>> = None;
+    let mut metrics = Metrics::new("trigger");
+    let heartbeat = Heartbeat::spawn("trigger", redis.clone());
+    let mut watchdog = Watchdog::new(notifier.clone(), clock.clone());
+    // Maintenance and backfill run inline; either may take up to the window's length
+    let window = (schedules.maintenance_end - schedules.maintenance_start)
+        .to_std()
+        .unwrap_or_default();
+
+    while !shutdown.is_cancelled() {
+        let tick_start = tokio::time::Instant::now();
+        let now = clock.now();
+        let today = now.date_naive();
+        let due = plan.due(now);
+        let in_window = due.in_window;
+        heartbeat.beat(if in_window { "maintenance_window" } else { "running" });
+
+        //-----------------------------------SYMBOLS FILE--------------------------------------
+        if let Some(watcher) = &mut symbols_file
+            && let Some(specs) = watcher.poll()
+        {
+            match symbols::sync(&probe_pg, &mut redis, &specs).await {
+                Ok(r) => info!(
+                    "📄 Synced {} symbols from {} ({} added, {} removed)",
+                    specs.len(),
+                    watcher.path().display(),
+                    r.added,
+                    r.removed
+                ),
+                Err(e) => {
+                    warn!("⚠️ Symbols file sync failed: {e}");
+                    watcher.reload();
+                }
+            }
+        }
+
+        //--------------------------------FETCHER LIFECYCLE MANAGEMENT-----------------------------------------------
+        if in_window {
+            if fetcher.is_running() {
+                fetcher.stop().await;
+            }
+        } else if !fetcher.is_running() {
+            fetcher.start().await;
+        }
+
+        //--------------------------------------MAINTENANCE----------------------------------------
+        if due.maintenance {
+            heartbeat.pause("maintenance", window);
+            maintain(&config, notifier.clone(), &mut redis, &clock).await;
+            plan.maintained(today);
+        }
+
+        //-----------------------------------POST-MAINTENANCE--------------------------------------
+        if due.backfill {
+            heartbeat.pause("backfill", window);
+            backfill_window(&config, today).await;
+            plan.backfilled(today);
+        }
+
+        //-----------------------------------GAP REPAIR--------------------------------------
+        if let Some(every) = schedules.gap_scan()
+            && !in_window
+            && gap_scan.as_ref().is_none_or(|h| h.is_finished())
+            && last_gap_scan.is_none_or(|t| clock.instant().duration_since(t) >= every)
+        {
+            last_gap_scan = Some(clock.instant());
+            gap_scan = Some(spawn_local(scan_gaps(config.clone())));
+        }
+
+        //-----------------------------------RECONCILIATION--------------------------------------
+        if let Some(every) = schedules.reconcile()
+            && !in_window
+            && reconciling.as_ref().is_none_or(|h| h.is_finished())
+            && last_reconcile.is_none_or(|t| clock.instant().duration_since(t) >= every)
+        {
+            last_reconcile = Some(clock.instant());
+            reconciling = Some(spawn_local(reconcile_bars(config.clone())));
+        }
+
+        if let Err(e) = metrics.flush_if_due(&mut redis).await {
+            warn!("⚠️ Redis metrics write error: {e}");
+        }
+        watchdog.check(&mut redis).await;
+
+        // --------------------------------DRIFT-CORRECTED SLEEP-----------------------------------------------------------
+        let elapsed = tick_start.elapsed();
+        tokio::select! {
+            _ = sleep(loop_tick.saturating_sub(elapsed)) => {}
+            _ = shutdown.cancelled() => {}
+        }
+    }
+
+    //-----------------------------------SHUTDOWN--------------------------------------
+    heartbeat.beat("stopping");
+    fetcher.stop().await;
+    if let Some(scan) = gap_scan.filter(|h| !h.is_finished()) {
+        info!("⏳ waiting for the gap scan to finish…");
+        let _ = scan.await;
+    }
+    if let Some(run) = reconciling.filter(|h| !h.is_finished()) {
+        info!("⏳ waiting for the reconciliation to finish…");
+        let _ = run.await;
+    }
+    if let Some(n) = notifier {
+        n.close().await;
+    }
+    info!("👋 Trigger stopped");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    fn at(day: u32, h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, day, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn maintains_once_inside_the_window_then_backfills_after_it() {
+        // Window 05:00–05:05, backfill at 05:07
+        let clock = ManualClock::new(at(1, 4, 59));
+        let mut plan = WindowPlan::new(Schedules::default());
+        assert_eq!(plan.due(clock.now()), Due::default());
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(plan.due(clock.now()), Due { in_window: true, maintenance: true, backfill: false });
+        plan.maintained(clock.now().date_naive());
+        clock.advance(Duration::from_secs(120));
+        assert_eq!(plan.due(clock.now()), Due { in_window: true, ..Due::default() });
+
+        // Left the window, backfill not yet due
+        clock.set(at(1, 5, 5));
+        assert_eq!(plan.due(clock.now()), Due::default());
+        clock.set(at(1, 5, 7));
+        assert_eq!(plan.due(clock.now()), Due { backfill: true, ..Due::default() });
+        plan.backfilled(clock.now().date_naive());
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(plan.due(clock.now()), Due::default());
+
+        // And again the next day
+        clock.set(at(2, 5, 0));
+        assert!(plan.due(clock.now()).maintenance);
+    }
+
+    #[test]
+    fn no_backfill_without_maintenance_that_day() {
+        // Started after the window: nothing was stopped, so nothing to repair
+        let clock = ManualClock::new(at(1, 6, 0));
+        let mut plan = WindowPlan::new(Schedules::default());
+        assert_eq!(plan.due(clock.now()), Due::default());
+
+        // Yesterday's maintenance does not count for today
+        plan.maintained(at(1, 5, 0).date_naive());
+        clock.set(at(2, 6, 0));
+        assert_eq!(plan.due(clock.now()), Due::default());
+    }
+}
@@ -0,0 +1,423 @@
+use std::{
+    collections::HashMap,
+    env, fmt,
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::time::interval;
+use tracing::{info, warn};
+
+const DEFAULT_REFRESH_SECS: u64 = 300;
+const DEFAULT_VAULT_MOUNT: &str = "secret";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Credentials a backend may hold, named like the environment variables they replace
+pub const KEYS: [&str; 5] = [
+    "REDIS_URL",
+    "DATABASE_URL",
+    "FINNHUB_API_KEY",
+    "DISCORD_WEBHOOK_URL",
+    "SLACK_WEBHOOK_URL",
+];
+
+/// Where credentials come from besides files and the environment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Files and environment only
+    #[default]
+    Env,
+    /// HashiCorp Vault KV v2
+    Vault,
+    /// AWS Secrets Manager
+    Aws,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "env" => Ok(Self::Env),
+            "vault" => Ok(Self::Vault),
+            "aws" => Ok(Self::Aws),
+            other => Err(format!("unknown secrets backend '{other}' (env, vault or aws)")),
+        }
+    }
+}
+
+/// Vault KV v2 secret; the token only comes from `VAULT_TOKEN`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VaultSettings {
+    /// `VAULT_ADDR`, e.g. "https://vault.internal:8200"
+    pub addr: Option<String>,
+    /// KV v2 mount (`VAULT_MOUNT`)
+    pub mount: String,
+    /// Secret path under the mount (`VAULT_PATH`)
+    pub path: Option<String>,
+}
+
+impl Default for VaultSettings {
+    fn default() -> Self {
+        Self {
+            addr: None,
+            mount: DEFAULT_VAULT_MOUNT.to_string(),
+            path: None,
+        }
+    }
+}
+
+/// AWS Secrets Manager secret holding a JSON object; keys come from `AWS_ACCESS_KEY_ID`,
+/// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AwsSettings {
+    /// Name or ARN (`AWS_SECRET_ID`)
+    pub secret_id: Option<String>,
+    /// `AWS_REGION`, else `AWS_DEFAULT_REGION`
+    pub region: Option<String>,
+    /// Endpoint override, e.g. LocalStack (`AWS_SECRETS_ENDPOINT`)
+    pub endpoint: Option<String>,
+}
+
+/// The `[secrets]` config section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretsSettings {
+    /// `SECRETS_BACKEND`
+    pub backend: Backend,
+    pub vault: VaultSettings,
+    pub aws: AwsSettings,
+    /// How long fetched values are reused before the backend is asked again; 0 fetches
+    /// once at startup (`SECRETS_REFRESH_SECS`)
+    pub refresh_secs: u64,
+}
+
+impl Default for SecretsSettings {
+    fn default() -> Self {
+        Self {
+            backend: Backend::Env,
+            vault: VaultSettings::default(),
+            aws: AwsSettings::default(),
+            refresh_secs: DEFAULT_REFRESH_SECS,
+        }
+    }
+}
+
+impl SecretsSettings {
+    /// Apply the `SECRETS_*`, `VAULT_*` and `AWS_*` variables, returning what does not parse
+    /// or is missing for the chosen backend
+    pub fn apply_env(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Ok(v) = env::var("SECRETS_BACKEND") {
+            match v.parse() {
+                Ok(b) => self.backend = b,
+                Err(e) => errors.push(format!("SECRETS_BACKEND: {e}")),
+            }
+        }
+        if let Ok(v) = env::var("SECRETS_REFRESH_SECS") {
+            match v.trim().parse() {
+                Ok(n) => self.refresh_secs = n,
+                Err(_) => errors.push(format!("SECRETS_REFRESH_SECS: cannot parse '{v}'")),
+            }
+        }
+        let text = |name: &str, field: &mut Option<String>| {
+            if let Ok(v) = env::var(name) {
+                *field = Some(v);
+            }
+        };
+        text("VAULT_ADDR", &mut self.vault.addr);
+        text("VAULT_PATH", &mut self.vault.path);
+        if let Ok(v) = env::var("VAULT_MOUNT") {
+            self.vault.mount = v;
+        }
+        text("AWS_SECRET_ID", &mut self.aws.secret_id);
+        text("AWS_SECRETS_ENDPOINT", &mut self.aws.endpoint);
+        if self.aws.region.is_none() {
+            text("AWS_DEFAULT_REGION", &mut self.aws.region);
+        }
+        text("AWS_REGION", &mut self.aws.region);
+        errors.extend(self.problems());
+        errors
+    }
+
+    pub fn problems(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let missing = |what: &str, var: &str, key: &str| {
+            format!("{what} is required for the secrets backend: set {var} or `secrets.{key}`")
+        };
+        match self.backend {
+            Backend::Env => {}
+            Backend::Vault => {
+                if self.vault.addr.is_none() {
+                    errors.push(missing("The Vault address", "VAULT_ADDR", "vault.addr"));
+                }
+                if self.vault.path.is_none() {
+                    errors.push(missing("The Vault secret path", "VAULT_PATH", "vault.path"));
+                }
+                if env::var("VAULT_TOKEN").is_err() {
+                    errors.push("VAULT_TOKEN is required for the vault secrets backend".to_string());
+                }
+            }
+            Backend::Aws => {
+                if self.aws.secret_id.is_none() {
+                    errors.push(missing("The secret id", "AWS_SECRET_ID", "aws.secret_id"));
+                }
+                if self.aws.region.is_none() {
+                    errors.push(missing("The AWS region", "AWS_REGION", "aws.region"));
+                }
+                if env::var("AWS_ACCESS_KEY_ID").is_err() || env::var("AWS_SECRET_ACCESS_KEY").is_err() {
+                    errors.push("AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY are required for the aws secrets backend".to_string());
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SecretsError {
+    #[error("{backend} request failed: {source}")]
+    Http {
+        backend: &'static str,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("{backend} returned {status}: {body}")]
+    Status {
+        backend: &'static str,
+        status: reqwest::StatusCode,
+        body: String,
+    },
+    #[error("{backend} secret is not a JSON object of strings: {reason}")]
+    Format { backend: &'static str, reason: String },
+}
+
+/// A backend's current values, shared by every clone; a background task refetches them
+/// every `refresh_secs` so rotated credentials reach new connections
+#[derive(Clone)]
+pub struct Secrets {
+    backend: Backend,
+    values: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Secrets")
+            .field("backend", &self.backend)
+            .field("keys", &self.keys())
+            .finish()
+    }
+}
+
+impl Secrets {
+    /// Fetch once, then keep the values fresh in a spawned task; `None` for `Backend::Env`
+    pub async fn connect(settings: &SecretsSettings) -> Result<Option<Self>, SecretsError> {
+        if settings.backend == Backend::Env {
+            return Ok(None);
+        }
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("default reqwest client");
+        let values = fetch(&client, settings).await?;
+        let secrets = Self {
+            backend: settings.backend,
+            values: Arc::new(RwLock::new(values)),
+        };
+        if settings.refresh_secs > 0 {
+            tokio::spawn(refresh(client, settings.clone(), secrets.values.clone()));
+        }
+        Ok(Some(secrets))
+    }
+
+    /// Latest value of one of [`KEYS`]
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.values.read().unwrap().get(key).cloned()
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Which of [`KEYS`] the backend currently holds
+    pub fn keys(&self) -> Vec<&'static str> {
+        let values = self.values.read().unwrap();
+        KEYS.iter().copied().filter(|k| values.contains_key(*k)).collect()
+    }
+}
+
+async fn refresh(client: reqwest::Client, settings: SecretsSettings, values: Arc<RwLock<HashMap<String, String>>>) {
+    let mut tick = interval(Duration::from_secs(settings.refresh_secs));
+    tick.tick().await;
+    loop {
+        tick.tick().await;
+        match fetch(&client, &settings).await {
+            Ok(fresh) => {
+                let mut current = values.write().unwrap();
+                let rotated: Vec<&str> = KEYS
+                    .iter()
+                    .copied()
+                    .filter(|k| current.get(*k) != fresh.get(*k))
+                    .collect();
+                if !rotated.is_empty() {
+                    info!("🔑 Rotated {}; new connections use the new values", rotated.join(", "));
+                }
+                *current = fresh;
+            }
+            // Keep serving the cached values until the backend answers again
+            Err(e) => warn!("⚠️ Secrets refresh failed, keeping cached values: {e}"),
+        }
+    }
+}
+
+async fn fetch(client: &reqwest::Client, settings: &SecretsSettings) -> Result<HashMap<String, String>, SecretsError> {
+    match settings.backend {
+        Backend::Env => Ok(HashMap::new()),
+        Backend::Vault => fetch_vault(client, &settings.vault).await,
+        Backend::Aws => fetch_aws(client, &settings.aws).await,
+    }
+}
+
+async fn send(backend: &'static str, request: reqwest::RequestBuilder) -> Result<Value, SecretsError> {
+    let http = |source| SecretsError::Http { backend, source };
+    let resp = request.send().await.map_err(http)?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(SecretsError::Status { backend, status, body });
+    }
+    resp.json().await.map_err(http)
+}
+
+fn string_map(backend: &'static str, value: &Value) -> Result<HashMap<String, String>, SecretsError> {
+    let format = |reason: String| SecretsError::Format { backend, reason };
+    let object = value.as_object().ok_or_else(|| format("not an object".to_string()))?;
+    object
+        .iter()
+        .map(|(k, v)| match v {
+            Value::String(s) => Ok((k.clone(), s.clone())),
+            _ => Err(format(format!("{k} is not a string"))),
+        })
+        .collect()
+}
+
+/// `GET {addr}/v1/{mount}/data/{path}`; the values are under `data.data`
+async fn fetch_vault(client: &reqwest::Client, vault: &VaultSettings) -> Result<HashMap<String, String>, SecretsError> {
+    let addr = vault.addr.as_deref().unwrap_or_default().trim_end_matches('/');
+    let path = vault.path.as_deref().unwrap_or_default().trim_matches('/');
+    let url = format!("{addr}/v1/{}/data/{path}", vault.mount.trim_matches('/'));
+    let token = env::var("VAULT_TOKEN").unwrap_or_default();
+    let body = send("Vault", client.get(url).header("X-Vault-Token", token)).await?;
+    string_map("Vault", &body["data"]["data"])
+}
+
+/// `GetSecretValue`, signed with SigV4; `SecretString` holds the JSON object
+async fn fetch_aws(client: &reqwest::Client, aws: &AwsSettings) -> Result<HashMap<String, String>, SecretsError> {
+    let region = aws.region.as_deref().unwrap_or_default();
+    let endpoint = aws
+        .endpoint
+        .clone()
+        .unwrap_or_else(|| format!("https://secretsmanager.{region}.amazonaws.com"));
+    let host = url::Url::parse(&endpoint)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| match u.port() {
+            Some(port) => format!("{h}:{port}"),
+            None => h.to_string(),
+        }))
+        .unwrap_or_default();
+    let payload = serde_json::json!({ "SecretId": aws.secret_id }).to_string();
+
+    let credentials = AwsCredentials {
+        access_key: env::var("AWS_ACCESS_KEY_ID").unwrap_or_default(),
+        secret_key: env::var("AWS_SECRET_ACCESS_KEY").unwrap_or_default(),
+        session_token: env::var("AWS_SESSION_TOKEN").ok(),
+    };
+    let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut headers = vec![
+        ("content-type", "application/x-amz-json-1.1".to_string()),
+        ("host", host),
+        ("x-amz-date", amz_date.clone()),
+        ("x-amz-target", "secretsmanager.GetSecretValue".to_string()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    let authorization = sigv4(&credentials, region, "secretsmanager", &amz_date, &headers, &payload);
+
+    let mut request = client.post(endpoint).header("authorization", authorization).body(payload);
+    for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+        request = request.header(name, value);
+    }
+    let body = send("AWS Secrets Manager", request).await?;
+    let secret = body["SecretString"].as_str().ok_or_else(|| SecretsError::Format {
+        backend: "AWS Secrets Manager",
+        reason: "no SecretString (binary secrets are not supported)".to_string(),
+    })?;
+    let value: Value = serde_json::from_str(secret).map_err(|e| SecretsError::Format {
+        backend: "AWS Secrets Manager",
+        reason: e.to_string(),
+    })?;
+    string_map("AWS Secrets Manager", &value)
+}
+
+struct AwsCredentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// `Authorization` header for a POST to `/` with these (lowercase, sorted) headers
+fn sigv4(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    amz_date: &str,
+    headers: &[(&str, String)],
+    payload: &str,
+) -> String {
+    let mut headers = headers.to_vec();
+    headers.sort_by(|a, b| a.0.cmp(b.0));
+    let canonical_headers: String = headers.iter().map(|(k, v)| format!("{k}:{}\n", v.trim())).collect();
+    let signed_headers = headers.iter().map(|(k, _)| *k).collect::<Vec<_>>().join(";");
+    let canonical_request = format!(
+        "POST\n/\n\n{canonical_headers}\n{signed_headers}\n{}",
+        hex(&Sha256::digest(payload.as_bytes()))
+    );
+
+    let date = &amz_date[..8];
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = [region, service, "aws4_request"]
+        .iter()
+        .fold(hmac(format!("AWS4{}", credentials.secret_key).as_bytes(), date), |key, part| {
+            hmac(&key, part)
+        });
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={}",
+        credentials.access_key,
+        hex(&hmac(&key, &string_to_sign))
+    )
+}
@@ -0,0 +1,146 @@
+use std::env;
+
+use chrono::DateTime;
+use tracing::{info, warn};
+
+use crate::{
+    backfill::{CandleSource, SourceKind},
+    bars::{Bar, Timeframe},
+    binance::MAX_KLINES,
+    finnhub::Candle,
+    keys::{BAR_PREFIX, PRICE_PREFIX},
+    metrics::now_ms,
+    precision::Precision,
+    redis_conn::RedisConn,
+};
+
+const MINUTE_MS: i64 = 60_000;
+
+/// `SEED_QUOTES`, on unless set to `false` or `0`
+pub fn enabled_from_env() -> bool {
+    env::var("SEED_QUOTES").map_or(true, |v| !matches!(v.trim(), "false" | "0"))
+}
+
+#[derive(Debug, Default)]
+pub struct SeedReport {
+    pub prices: usize,
+    pub bars: usize,
+}
+
+/// The last closed `tf` bar in `candles` (1m, oldest first), `None` when it has no minutes
+fn last_closed(symbol: &str, tf: Timeframe, candles: &[Candle], now: i64) -> Option<Bar> {
+    let start = tf.bucket_start(now) - tf.millis();
+    let end = start + tf.millis();
+    let minutes: Vec<&Candle> = candles
+        .iter()
+        .filter(|c| (start..end).contains(&c.time.and_utc().timestamp_millis()))
+        .collect();
+    let (first, last) = (minutes.first()?, minutes.last()?);
+    Some(Bar {
+        symbol: symbol.to_string(),
+        tf: tf.to_string(),
+        start,
+        open: first.open,
+        high: minutes.iter().map(|c| c.high).fold(f64::MIN, f64::max),
+        low: minutes.iter().map(|c| c.low).fold(f64::MAX, f64::min),
+        close: last.close,
+        volume: minutes.iter().map(|c| c.volume).sum(),
+        // No trades were seen; consumers can tell a seeded bar by this
+        trades: 0,
+        volume_known: true,
+        buy_volume: 0.0,
+        sell_volume: 0.0,
+        large_trades: 0,
+        large_signed_volume: 0.0,
+        fair_price: None,
+        usd_rate: None,
+        closed_by_ts: end,
+        published_at: now,
+    })
+}
+
+/// Seed one symbol's missing price and last closed bars from the exchange's 1m candles
+async fn seed_symbol(
+    redis: &mut RedisConn,
+    source: &mut CandleSource,
+    precision: &Precision,
+    symbol: &str,
+    timeframes: &[Timeframe],
+) -> Result<SeedReport, String> {
+    let mut report = SeedReport::default();
+    let price_key = format!("{PRICE_PREFIX}{symbol}");
+    // Sub-minute bars cannot be built from minutes, nor bars older than one request reaches
+    let tfs: Vec<Timeframe> = timeframes
+        .iter()
+        .copied()
+        .filter(|tf| tf.millis() % MINUTE_MS == 0 && tf.millis() * 2 <= MAX_KLINES as i64 * MINUTE_MS)
+        .collect();
+    let mut pipe = redis::pipe();
+    pipe.exists(&price_key);
+    for tf in &tfs {
+        pipe.exists(format!("{BAR_PREFIX}{symbol}:{tf}"));
+    }
+    let exists: Vec<bool> = pipe
+        .query_async(redis)
+        .await
+        .map_err(|e| format!("Redis exists error: {e}"))?;
+    let missing: Vec<Timeframe> = tfs.iter().zip(&exists[1..]).filter(|(_, e)| !**e).map(|(tf, _)| *tf).collect();
+    if exists[0] && missing.is_empty() {
+        return Ok(report);
+    }
+
+    let now = now_ms();
+    let span = missing.iter().map(|tf| tf.millis() * 2).max().unwrap_or(MINUTE_MS);
+    let to = now - now.rem_euclid(MINUTE_MS) + MINUTE_MS;
+    let from = to - span - MINUTE_MS;
+    let naive = |ms: i64| DateTime::from_timestamp_millis(ms).map(|t| t.naive_utc()).unwrap_or_default();
+    let candles = source.minutes(symbol, naive(from), naive(to)).await?;
+    let Some(latest) = candles.last() else {
+        return Ok(report);
+    };
+
+    let mut pipe = redis::pipe();
+    if !exists[0] {
+        pipe.set(&price_key, precision.price(latest.close)).ignore();
+        report.prices += 1;
+    }
+    for tf in missing {
+        if let Some(bar) = last_closed(symbol, tf, &candles, now) {
+            pipe.hset_multiple(format!("{BAR_PREFIX}{symbol}:{tf}"), &bar.fields()).ignore();
+            report.bars += 1;
+        }
+    }
+    pipe.query_async::<()>(redis)
+        .await
+        .map_err(|e| format!("Redis seed write error: {e}"))?;
+    Ok(report)
+}
+
+/// Give symbols without a last price or closed bar in Redis ones from the exchange's REST
+/// candles, so consumers have data before each symbol's first trade. Prices are the latest
+/// minute's close; bars are the last closed bar of each timeframe that is a whole number of
+/// minutes, with `trades` 0. Runs before streaming starts, so nothing live is overwritten;
+/// symbols that already have everything cost no request.
+pub async fn run(
+    redis: &mut RedisConn,
+    symbols: &[String],
+    precision: &Precision,
+    finnhub_key: Option<String>,
+    timeframes: impl Fn(&str) -> Vec<Timeframe>,
+) -> SeedReport {
+    let mut source = CandleSource::new(SourceKind::Auto, finnhub_key);
+    let mut report = SeedReport::default();
+    for symbol in symbols {
+        match seed_symbol(redis, &mut source, precision, symbol, &timeframes(symbol)).await {
+            Ok(r) => {
+                report.prices += r.prices;
+                report.bars += r.bars;
+            }
+            Err(e) => warn!("⚠️ Could not seed {symbol}: {e}"),
+        }
+    }
+    if report.prices + report.bars > 0 {
+        info!("🌱 Seeded {} prices and {} bars from REST quotes", report.prices, report.bars);
+    }
+    report
+}
@@ -0,0 +1,99 @@
+use std::env;
+
+use redis::AsyncCommands;
+use tracing::info;
+
+use crate::{config::Sharding, keys::FETCHERS_KEY, metrics::now_ms, redis_conn::RedisConn};
+
+/// FNV-1a of `member` and `symbol`, finished with a splitmix64 round so similar inputs spread
+/// evenly; stable across builds and platforms, unlike `DefaultHasher`
+fn weight(member: &str, symbol: &str) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in member.bytes().chain([0]).chain(symbol.bytes()) {
+        h ^= u64::from(b);
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
+
+/// The member that writes `symbol`: the highest weight wins (rendezvous hashing), so a member
+/// joining or leaving only moves the symbols it gains or loses
+pub fn owner<'a>(symbol: &str, members: &'a [String]) -> Option<&'a str> {
+    members
+        .iter()
+        .max_by_key(|m| (weight(m, symbol), m.as_str()))
+        .map(String::as_str)
+}
+
+/// Which symbols this fetcher writes when several share the database. Static shards are
+/// numbered `0..shards`; coordinated members are the fetchers that refreshed their entry in
+/// `FETCHERS_KEY` within `member_ttl_secs`. While membership changes, a symbol may be written
+/// twice or skipped for a cycle.
+pub struct Shard {
+    me: String,
+    members: Vec<String>,
+    /// Seconds a coordinated member stays listed without refreshing; `None` for static shards
+    ttl: Option<u64>,
+}
+
+impl Shard {
+    /// `None` when one fetcher writes everything
+    pub fn from_config(s: &Sharding) -> Option<Self> {
+        if s.coordinated {
+            let host = env::var("HOSTNAME").unwrap_or_else(|_| "fetcher".to_string());
+            let me = format!("{host}-{}", std::process::id());
+            return Some(Self { members: vec![me.clone()], me, ttl: Some(s.member_ttl_secs) });
+        }
+        (s.shards > 1).then(|| Self {
+            me: s.index.to_string(),
+            members: (0..s.shards).map(|i| i.to_string()).collect(),
+            ttl: None,
+        })
+    }
+
+    /// This fetcher's shard index or member name
+    pub fn member(&self) -> &str {
+        &self.me
+    }
+
+    pub fn describe(&self) -> String {
+        match self.ttl {
+            Some(_) => format!("member {} of {} coordinated fetchers", self.me, self.members.len()),
+            None => format!("shard {} of {}", self.me, self.members.len()),
+        }
+    }
+
+    /// Announce this fetcher and read who else is live; static shards have nothing to do
+    pub async fn refresh(&mut self, redis: &mut RedisConn) -> redis::RedisResult<()> {
+        let Some(ttl) = self.ttl else { return Ok(()) };
+        let now = now_ms();
+        let (mut members,): (Vec<String>,) = redis::pipe()
+            .zadd(FETCHERS_KEY, &self.me, now)
+            .ignore()
+            .zrembyscore(FETCHERS_KEY, "-inf", now - ttl as i64 * 1000)
+            .ignore()
+            .zrange(FETCHERS_KEY, 0, -1)
+            .query_async(redis)
+            .await?;
+        members.sort();
+        if members != self.members {
+            info!("🧩 Fetchers now {}: {}", members.len(), members.join(", "));
+            self.members = members;
+        }
+        Ok(())
+    }
+
+    pub fn owns(&self, symbol: &str) -> bool {
+        owner(symbol, &self.members).is_none_or(|m| m == self.me)
+    }
+
+    /// Drop out of the coordinated set, so the others take over this fetcher's symbols at once
+    pub async fn leave(&self, redis: &mut RedisConn) -> redis::RedisResult<()> {
+        if self.ttl.is_none() {
+            return Ok(());
+        }
+        redis.zrem(FETCHERS_KEY, &self.me).await
+    }
+}
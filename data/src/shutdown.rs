@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// How long servers wait for open requests and streams once shutdown starts
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Cancelled on the first SIGTERM or Ctrl-C. Components take a child token, finish the
+/// unit of work in hand and return; a second signal exits at once.
+pub fn on_signal() -> CancellationToken {
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        let signal = wait_for_signal().await;
+        info!("🛑 {signal} received, shutting down (send it again to exit now)");
+        cancel.cancel();
+        let signal = wait_for_signal().await;
+        warn!("🛑 {signal} received again, exiting without cleanup");
+        std::process::exit(130);
+    });
+    token
+}
+
+/// Completes [`DRAIN_TIMEOUT`] after `token` is cancelled; races a graceful server so
+/// long-lived streams cannot hold the exit open
+pub async fn drain_deadline(token: CancellationToken) {
+    token.cancelled().await;
+    tokio::time::sleep(DRAIN_TIMEOUT).await;
+    warn!("⏱️ Connections still open after {}s, closing them", DRAIN_TIMEOUT.as_secs());
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+    let Ok(mut term) = signal(SignalKind::terminate()) else {
+        let _ = tokio::signal::ctrl_c().await;
+        return "Ctrl-C";
+    };
+    tokio::select! {
+        _ = term.recv() => "SIGTERM",
+        _ = tokio::signal::ctrl_c() => "Ctrl-C",
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> &'static str {
+    let _ = tokio::signal::ctrl_c().await;
+    "Ctrl-C"
+}
@@ -0,0 +1,217 @@
+use std::{collections::HashMap, env, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{predictions::Prediction, regime::Regime};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum Side {
+    Long,
+    Short,
+    Flat,
+}
+
+impl Side {
+    /// +1 / -1 / 0
+    pub fn direction(&self) -> f64 {
+        match self {
+            Side::Long => 1.0,
+            Side::Short => -1.0,
+            Side::Flat => 0.0,
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Side::Long => "long",
+            Side::Short => "short",
+            Side::Flat => "flat",
+        })
+    }
+}
+
+impl FromStr for Side {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "long" => Ok(Side::Long),
+            "short" => Ok(Side::Short),
+            "flat" => Ok(Side::Flat),
+            other => Err(format!("unknown side '{other}'")),
+        }
+    }
+}
+
+fn env_f64(key: &str, default: f64) -> f64 {
+    env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// Thresholds are on the predicted return in basis points. A position opens at
+/// `enter_bps` and is held until the prediction falls back under `exit_bps`
+/// (hysteresis); no side change happens within `cooldown_ms` of the last one.
+/// The market regime can override the entry threshold or force flat.
+#[derive(Debug, Clone, Copy)]
+pub struct SignalConfig {
+    pub enter_bps: f64,
+    pub exit_bps: f64,
+    pub min_confidence: f64,
+    pub cooldown_ms: i64,
+    /// Entry threshold per regime, in `Regime::ALL` order
+    pub regime_enter_bps: [Option<f64>; Regime::ALL.len()],
+    /// Regimes in which the signal stays flat, in `Regime::ALL` order
+    pub blocked_regimes: [bool; Regime::ALL.len()],
+}
+
+impl SignalConfig {
+    /// `SIGNAL_ENTER_BPS`, `SIGNAL_EXIT_BPS`, `SIGNAL_MIN_CONFIDENCE`, `SIGNAL_COOLDOWN_SECS`,
+    /// `SIGNAL_ENTER_BPS_{TRENDING,RANGING,HIGH_VOL}` and `SIGNAL_BLOCK_REGIMES` (comma separated)
+    pub fn from_env() -> Self {
+        let enter_bps = env_f64("SIGNAL_ENTER_BPS", 5.0);
+        let blocked: Vec<Regime> = env::var("SIGNAL_BLOCK_REGIMES")
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.parse().unwrap_or_else(|e| panic!("❌ SIGNAL_BLOCK_REGIMES: {e}")))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            enter_bps,
+            exit_bps: env_f64("SIGNAL_EXIT_BPS", 2.0).min(enter_bps),
+            min_confidence: env_f64("SIGNAL_MIN_CONFIDENCE", 0.0),
+            cooldown_ms: (env_f64("SIGNAL_COOLDOWN_SECS", 60.0) * 1000.0) as i64,
+            regime_enter_bps: Regime::ALL.map(|r| {
+                env::var(format!("SIGNAL_ENTER_BPS_{}", r.to_string().to_uppercase()))
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+            }),
+            blocked_regimes: Regime::ALL.map(|r| blocked.contains(&r)),
+        }
+    }
+}
+
+/// Discrete trading signal derived from a prediction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Signal {
+    pub symbol: String,
+    pub side: Side,
+    pub predicted_return: f64,
+    pub confidence: f64,
+    pub model_version: String,
+    /// Feature timestamp of the prediction, ms since epoch
+    pub ts: i64,
+    /// When `side` last changed, ms since epoch
+    pub changed_at: i64,
+    /// Market regime the thresholds were chosen for
+    #[serde(default)]
+    pub regime: Option<Regime>,
+}
+
+impl Signal {
+    /// Redis hash fields
+    pub fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = vec![
+            ("side".to_string(), self.side.to_string()),
+            ("predicted_return".to_string(), self.predicted_return.to_string()),
+            ("confidence".to_string(), self.confidence.to_string()),
+            ("model_version".to_string(), self.model_version.clone()),
+            ("ts".to_string(), self.ts.to_string()),
+            ("changed_at".to_string(), self.changed_at.to_string()),
+        ];
+        if let Some(regime) = self.regime {
+            fields.push(("regime".to_string(), regime.to_string()));
+        }
+        fields
+    }
+
+    /// Inverse of `fields`; `None` when the hash is missing or incomplete
+    pub fn from_fields(symbol: &str, fields: &HashMap<String, String>) -> Option<Self> {
+        let num = |k: &str| fields.get(k)?.parse::<f64>().ok();
+        let ms = |k: &str| fields.get(k)?.parse::<i64>().ok();
+        Some(Self {
+            symbol: symbol.to_string(),
+            side: fields.get("side")?.parse().ok()?,
+            predicted_return: num("predicted_return")?,
+            confidence: num("confidence")?,
+            model_version: fields.get("model_version")?.clone(),
+            ts: ms("ts")?,
+            changed_at: ms("changed_at")?,
+            regime: fields.get("regime").and_then(|r| r.parse().ok()),
+        })
+    }
+}
+
+/// Per-symbol signal state machine
+pub struct SignalGenerator {
+    cfg: SignalConfig,
+    state: HashMap<String, (Side, i64)>,
+}
+
+impl SignalGenerator {
+    pub fn new(cfg: SignalConfig) -> Self {
+        Self {
+            cfg,
+            state: HashMap::new(),
+        }
+    }
+
+    fn desired(&self, current: Side, bps: f64, confident: bool, regime: Option<Regime>) -> Side {
+        let slot = regime.and_then(|r| Regime::ALL.iter().position(|a| *a == r));
+        if slot.is_some_and(|i| self.cfg.blocked_regimes[i]) {
+            return Side::Flat;
+        }
+        let enter = slot
+            .and_then(|i| self.cfg.regime_enter_bps[i])
+            .unwrap_or(self.cfg.enter_bps);
+        let exit = self.cfg.exit_bps.min(enter);
+        match current {
+            _ if confident && bps >= enter => Side::Long,
+            _ if confident && bps <= -enter => Side::Short,
+            Side::Long if bps > exit => Side::Long,
+            Side::Short if bps < -exit => Side::Short,
+            _ => Side::Flat,
+        }
+    }
+
+    /// `regime` is the symbol's current regime on the prediction timeframe, if known
+    pub fn on_prediction(&mut self, p: &Prediction, regime: Option<Regime>) -> Signal {
+        let (current, changed_at) = self
+            .state
+            .get(&p.symbol)
+            .copied()
+            .unwrap_or((Side::Flat, i64::MIN / 2));
+
+        let confident = p.confidence >= self.cfg.min_confidence;
+        let mut side = self.desired(current, p.predicted_return * 10_000.0, confident, regime);
+        let mut changed = changed_at;
+        if side != current {
+            if p.feature_ts - changed_at < self.cfg.cooldown_ms {
+                side = current;
+            } else {
+                changed = p.feature_ts;
+            }
+        }
+        self.state.insert(p.symbol.clone(), (side, changed));
+
+        Signal {
+            symbol: p.symbol.clone(),
+            side,
+            predicted_return: p.predicted_return,
+            confidence: p.confidence,
+            model_version: p.model_version.clone(),
+            ts: p.feature_ts,
+            changed_at: changed.max(0),
+            regime,
+        }
+    }
+}
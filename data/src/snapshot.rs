@@ -0,0 +1,167 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    keys::{Key, CACHE_PREFIX, HEALTH_PREFIX, HEARTBEAT_PREFIX, STATUS_PREFIX},
+    redis_conn::RedisConn,
+};
+
+/// Rebuilt or rewritten by running services within seconds; not worth carrying over
+static SKIPPED_PREFIXES: [&Key; 4] = [CACHE_PREFIX, HEARTBEAT_PREFIX, HEALTH_PREFIX, STATUS_PREFIX];
+/// Keys restored per pipeline round trip
+const RESTORE_BATCH: usize = 200;
+
+/// A key's contents, by Redis type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum Value {
+    String(String),
+    Hash(BTreeMap<String, String>),
+    List(Vec<String>),
+    Set(BTreeSet<String>),
+    /// `(member, score)` in score order
+    Zset(Vec<(String, f64)>),
+}
+
+impl Value {
+    fn is_empty(&self) -> bool {
+        match self {
+            Value::String(_) => false,
+            Value::Hash(h) => h.is_empty(),
+            Value::List(l) => l.is_empty(),
+            Value::Set(s) => s.is_empty(),
+            Value::Zset(z) => z.is_empty(),
+        }
+    }
+}
+
+/// One key of a snapshot; a snapshot file is one JSON entry per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub key: String,
+    #[serde(flatten)]
+    pub value: Value,
+    /// Remaining time to live when taken; restored as is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_ms: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub restored: usize,
+    /// Already present and left alone (restore without overwrite)
+    pub kept: usize,
+}
+
+/// Every key matching `pattern`, except caches and liveness keys. Not atomic: keys written
+/// while it runs may be caught before or after the write.
+pub async fn take(redis: &mut RedisConn, pattern: &str) -> redis::RedisResult<Vec<Entry>> {
+    let mut keys: Vec<String> = redis.keys(pattern).await?;
+    keys.retain(|k| !SKIPPED_PREFIXES.iter().any(|p| k.starts_with(p.as_str())));
+    keys.sort();
+
+    let mut entries = Vec::with_capacity(keys.len());
+    for key in keys {
+        let (kind, ttl_ms): (String, i64) = redis::pipe().cmd("TYPE").arg(&key).pttl(&key).query_async(redis).await?;
+        let value = match kind.as_str() {
+            "string" => match redis.get::<_, Option<String>>(&key).await? {
+                Some(v) => Value::String(v),
+                None => continue,
+            },
+            "hash" => Value::Hash(redis.hgetall(&key).await?),
+            "list" => Value::List(redis.lrange(&key, 0, -1).await?),
+            "set" => Value::Set(redis.smembers(&key).await?),
+            "zset" => Value::Zset(redis.zrange_withscores(&key, 0, -1).await?),
+            // Expired since KEYS, or a type the pipeline never writes (streams)
+            _ => continue,
+        };
+        // Redis has no empty collections; the key was deleted while being read
+        if value.is_empty() {
+            continue;
+        }
+        entries.push(Entry {
+            key,
+            value,
+            ttl_ms: (ttl_ms > 0).then_some(ttl_ms),
+        });
+    }
+    Ok(entries)
+}
+
+/// Write to a temporary file and rename, so an interrupted snapshot never replaces a good one
+pub fn write(path: &Path, entries: &[Entry]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    for entry in entries {
+        serde_json::to_writer(&mut out, entry)?;
+        out.write_all(b"\n")?;
+    }
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(tmp, path)
+}
+
+pub fn read(path: &Path) -> Result<Vec<Entry>, String> {
+    let file = File::open(path).map_err(|e| format!("cannot open {}: {e}", path.display()))?;
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|e| format!("{} line {}: {e}", path.display(), i + 1))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Write `entries` back. Each key is replaced whole, not merged; with `overwrite` off,
+/// keys that already exist are kept as they are.
+pub async fn restore(redis: &mut RedisConn, entries: &[Entry], overwrite: bool) -> redis::RedisResult<RestoreReport> {
+    let mut report = RestoreReport::default();
+    for batch in entries.chunks(RESTORE_BATCH) {
+        let batch: Vec<&Entry> = if overwrite {
+            batch.iter().filter(|e| !e.value.is_empty()).collect()
+        } else {
+            let mut pipe = redis::pipe();
+            for entry in batch {
+                pipe.exists(&entry.key);
+            }
+            let exists: Vec<bool> = pipe.query_async(redis).await?;
+            report.kept += exists.iter().filter(|e| **e).count();
+            batch
+                .iter()
+                .zip(exists)
+                .filter(|(entry, e)| !e && !entry.value.is_empty())
+                .map(|(entry, _)| entry)
+                .collect()
+        };
+
+        let mut pipe = redis::pipe();
+        for entry in &batch {
+            let key = &entry.key;
+            pipe.del(key).ignore();
+            match &entry.value {
+                Value::String(v) => pipe.set(key, v).ignore(),
+                Value::Hash(h) => pipe.hset_multiple(key, &h.iter().collect::<Vec<_>>()).ignore(),
+                Value::List(l) => pipe.rpush(key, l).ignore(),
+                Value::Set(s) => pipe.sadd(key, s).ignore(),
+                Value::Zset(z) => pipe
+                    .zadd_multiple(key, &z.iter().map(|(m, s)| (*s, m)).collect::<Vec<_>>())
+                    .ignore(),
+            };
+            if let Some(ttl) = entry.ttl_ms {
+                pipe.pexpire(key, ttl).ignore();
+            }
+        }
+        pipe.query_async::<()>(redis).await?;
+        report.restored += batch.len();
+    }
+    Ok(report)
+}
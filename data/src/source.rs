@@ -0,0 +1,357 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use url::Url;
+
+use crate::config::Config;
+
+/// Exchanges with a direct adapter; tracked symbols under these prefixes are theirs alone
+pub const SOURCES: [&str; 3] = ["finnhub", "okx", "bybit"];
+
+/// One trade from any source, under the tracked `EXCHANGE:PAIR` symbol
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceTrade {
+    pub symbol: String,
+    pub price: f64,
+    pub volume: Option<f64>,
+    /// Exchange trade time as sent, normally ms since epoch; 0 when missing or unreadable
+    pub ts: i64,
+}
+
+/// A trade time in any JSON shape (integer, float or string), 0 when absent or unreadable,
+/// so one bad timestamp does not cost the whole frame
+fn lenient_ts<'de, D: serde::Deserializer<'de>>(d: D) -> Result<i64, D::Error> {
+    Ok(match serde_json::Value::deserialize(d)? {
+        serde_json::Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)).unwrap_or(0),
+        serde_json::Value::String(s) => s
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .or_else(|| s.trim().parse::<f64>().ok().filter(|f| f.is_finite()).map(|f| f as i64))
+            .unwrap_or(0),
+        _ => 0,
+    })
+}
+
+/// `ts` as ms since epoch: exchanges that send seconds, µs or ns are recognised by how far
+/// each reading lands from `now`. `None` when no reading is within `max_skew` of it.
+pub fn normalize_ts(ts: i64, now: i64, max_skew: Duration) -> Option<i64> {
+    if ts <= 0 {
+        return None;
+    }
+    let skew = max_skew.as_millis() as i64;
+    [Some(ts), ts.checked_mul(1_000), Some(ts / 1_000), Some(ts / 1_000_000)]
+        .into_iter()
+        .flatten()
+        .find(|ms| (ms - now).abs() <= skew)
+}
+
+/// Application-level keepalive a source needs on top of WebSocket pings
+#[derive(Debug, Clone)]
+pub struct Keepalive {
+    pub every: Duration,
+    pub message: Message,
+}
+
+/// A public trade stream the ingester can read from
+pub trait ExchangeSource {
+    /// Lowercase name, as in `EXCHANGE_SOURCE`
+    fn name(&self) -> &'static str;
+    /// Heartbeat, health and metrics name of the ingester reading it
+    fn service(&self) -> &'static str;
+    /// Where to connect; built per connection so rotated credentials are picked up
+    fn url(&self, config: &Config) -> Result<Url, url::ParseError>;
+    /// Whether this source streams the tracked `symbol`
+    fn owns(&self, symbol: &str) -> bool;
+    /// Frames subscribing to `symbols` (all owned)
+    fn subscribe(&self, symbols: &[String]) -> Vec<String>;
+    /// Frames unsubscribing from `symbols`, sent when they leave the tracked set
+    fn unsubscribe(&self, symbols: &[String]) -> Vec<String>;
+    /// Trades in a text frame; empty for acks and pongs, `Err` for errors the exchange reports
+    fn parse(&self, text: &str) -> Result<Vec<SourceTrade>, String>;
+    fn keepalive(&self) -> Option<Keepalive> {
+        None
+    }
+}
+
+/// The adapter for `EXCHANGE_SOURCE`; `config` has been validated, so the name is known
+pub fn from_config(config: &Config) -> Box<dyn ExchangeSource> {
+    match config.exchanges.source.as_str() {
+        "okx" => Box::new(Okx),
+        "bybit" => Box::new(Bybit),
+        _ => Box::new(Finnhub),
+    }
+}
+
+fn number(text: &str) -> Result<f64, String> {
+    text.parse().map_err(|_| format!("bad number '{text}'"))
+}
+
+// --- Finnhub ---
+
+/// Finnhub's aggregated feed: every symbol not owned by a direct adapter, e.g. `BINANCE:BTCUSDT`.
+/// Finnhub pings idle clients itself.
+pub struct Finnhub;
+
+#[derive(Debug, Deserialize)]
+struct FinnhubMessage {
+    r#type: String,
+    data: Option<Vec<FinnhubTrade>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FinnhubTrade {
+    s: String,
+    p: f64,
+    v: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_ts")]
+    t: i64,
+}
+
+impl Finnhub {
+    fn requests(kind: &str, symbols: &[String]) -> Vec<String> {
+        symbols.iter().map(|s| json!({ "type": kind, "symbol": s }).to_string()).collect()
+    }
+}
+
+impl ExchangeSource for Finnhub {
+    fn name(&self) -> &'static str {
+        "finnhub"
+    }
+
+    fn service(&self) -> &'static str {
+        "websocket"
+    }
+
+    fn url(&self, config: &Config) -> Result<Url, url::ParseError> {
+        let mut url = Url::parse(&config.exchanges.finnhub.ws_url)?;
+        url.query_pairs_mut().append_pair("token", &config.finnhub_api_key());
+        Ok(url)
+    }
+
+    fn owns(&self, symbol: &str) -> bool {
+        !Okx.owns(symbol) && !Bybit.owns(symbol)
+    }
+
+    fn subscribe(&self, symbols: &[String]) -> Vec<String> {
+        Self::requests("subscribe", symbols)
+    }
+
+    fn unsubscribe(&self, symbols: &[String]) -> Vec<String> {
+        Self::requests("unsubscribe", symbols)
+    }
+
+    fn parse(&self, text: &str) -> Result<Vec<SourceTrade>, String> {
+        // Malformed frames are skipped as before rather than reported
+        let Ok(msg) = serde_json::from_str::<FinnhubMessage>(text) else {
+            return Ok(Vec::new());
+        };
+        if msg.r#type == "error" {
+            return Err(text.to_string());
+        }
+        if msg.r#type != "trade" {
+            return Ok(Vec::new());
+        }
+        Ok(msg
+            .data
+            .unwrap_or_default()
+            .into_iter()
+            .map(|t| SourceTrade { symbol: t.s, price: t.p, volume: t.v, ts: t.t })
+            .collect())
+    }
+}
+
+// --- OKX ---
+
+/// OKX v5 public trades, e.g. `OKX:BTC-USDT-SWAP` for the perpetual (`instId` after the
+/// prefix). Swap volumes are in contracts. OKX drops clients silent for 30s, so a text
+/// `ping` goes out every 20s.
+pub struct Okx;
+
+const OKX_PREFIX: &str = "OKX:";
+/// Channels per (un)subscribe request, well under OKX's 64 KB frame limit
+const OKX_BATCH: usize = 50;
+
+#[derive(Debug, Deserialize)]
+struct OkxMessage {
+    event: Option<String>,
+    msg: Option<String>,
+    data: Option<Vec<OkxTrade>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OkxTrade {
+    inst_id: String,
+    px: String,
+    sz: String,
+    #[serde(default, deserialize_with = "lenient_ts")]
+    ts: i64,
+}
+
+impl Okx {
+    fn requests(op: &str, symbols: &[String]) -> Vec<String> {
+        symbols
+            .chunks(OKX_BATCH)
+            .map(|chunk| {
+                let args: Vec<_> = chunk
+                    .iter()
+                    .map(|s| json!({ "channel": "trades", "instId": &s[OKX_PREFIX.len()..] }))
+                    .collect();
+                json!({ "op": op, "args": args }).to_string()
+            })
+            .collect()
+    }
+}
+
+impl ExchangeSource for Okx {
+    fn name(&self) -> &'static str {
+        "okx"
+    }
+
+    fn service(&self) -> &'static str {
+        "websocket-okx"
+    }
+
+    fn url(&self, config: &Config) -> Result<Url, url::ParseError> {
+        Url::parse(&config.exchanges.okx.ws_url)
+    }
+
+    fn owns(&self, symbol: &str) -> bool {
+        symbol.starts_with(OKX_PREFIX)
+    }
+
+    fn subscribe(&self, symbols: &[String]) -> Vec<String> {
+        Self::requests("subscribe", symbols)
+    }
+
+    fn unsubscribe(&self, symbols: &[String]) -> Vec<String> {
+        Self::requests("unsubscribe", symbols)
+    }
+
+    fn parse(&self, text: &str) -> Result<Vec<SourceTrade>, String> {
+        if text == "pong" {
+            return Ok(Vec::new());
+        }
+        let msg: OkxMessage = serde_json::from_str(text).map_err(|e| format!("unreadable frame: {e}"))?;
+        if msg.event.as_deref() == Some("error") {
+            return Err(msg.msg.unwrap_or_else(|| text.to_string()));
+        }
+        msg.data
+            .unwrap_or_default()
+            .into_iter()
+            .map(|t| {
+                Ok(SourceTrade {
+                    symbol: format!("{OKX_PREFIX}{}", t.inst_id),
+                    price: number(&t.px)?,
+                    volume: Some(number(&t.sz)?),
+                    ts: t.ts,
+                })
+            })
+            .collect()
+    }
+
+    fn keepalive(&self) -> Option<Keepalive> {
+        Some(Keepalive {
+            every: Duration::from_secs(20),
+            message: Message::Text("ping".to_string()),
+        })
+    }
+}
+
+// --- Bybit ---
+
+/// Bybit v5 public trades on the linear (USDT perpetual) stream, e.g. `BYBIT:BTCUSDT`.
+/// Bybit expects `{"op":"ping"}` every 20s.
+pub struct Bybit;
+
+const BYBIT_PREFIX: &str = "BYBIT:";
+const BYBIT_TOPIC: &str = "publicTrade.";
+/// Bybit caps the topics in one (un)subscribe request
+const BYBIT_BATCH: usize = 10;
+
+#[derive(Debug, Deserialize)]
+struct BybitMessage {
+    success: Option<bool>,
+    ret_msg: Option<String>,
+    topic: Option<String>,
+    data: Option<Vec<BybitTrade>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BybitTrade {
+    #[serde(rename = "T", default, deserialize_with = "lenient_ts")]
+    time: i64,
+    s: String,
+    v: String,
+    p: String,
+}
+
+impl Bybit {
+    fn requests(op: &str, symbols: &[String]) -> Vec<String> {
+        symbols
+            .chunks(BYBIT_BATCH)
+            .map(|chunk| {
+                let args: Vec<_> = chunk.iter().map(|s| format!("{BYBIT_TOPIC}{}", &s[BYBIT_PREFIX.len()..])).collect();
+                json!({ "op": op, "args": args }).to_string()
+            })
+            .collect()
+    }
+}
+
+impl ExchangeSource for Bybit {
+    fn name(&self) -> &'static str {
+        "bybit"
+    }
+
+    fn service(&self) -> &'static str {
+        "websocket-bybit"
+    }
+
+    fn url(&self, config: &Config) -> Result<Url, url::ParseError> {
+        Url::parse(&config.exchanges.bybit.ws_url)
+    }
+
+    fn owns(&self, symbol: &str) -> bool {
+        symbol.starts_with(BYBIT_PREFIX)
+    }
+
+    fn subscribe(&self, symbols: &[String]) -> Vec<String> {
+        Self::requests("subscribe", symbols)
+    }
+
+    fn unsubscribe(&self, symbols: &[String]) -> Vec<String> {
+        Self::requests("unsubscribe", symbols)
+    }
+
+    fn parse(&self, text: &str) -> Result<Vec<SourceTrade>, String> {
+        let msg: BybitMessage = serde_json::from_str(text).map_err(|e| format!("unreadable frame: {e}"))?;
+        if msg.success == Some(false) {
+            return Err(msg.ret_msg.unwrap_or_else(|| text.to_string()));
+        }
+        if !msg.topic.is_some_and(|t| t.starts_with(BYBIT_TOPIC)) {
+            return Ok(Vec::new());
+        }
+        msg.data
+            .unwrap_or_default()
+            .into_iter()
+            .map(|t| {
+                Ok(SourceTrade {
+                    symbol: format!("{BYBIT_PREFIX}{}", t.s),
+                    price: number(&t.p)?,
+                    volume: Some(number(&t.v)?),
+                    ts: t.time,
+                })
+            })
+            .collect()
+    }
+
+    fn keepalive(&self) -> Option<Keepalive> {
+        Some(Keepalive {
+            every: Duration::from_secs(20),
+            message: Message::Text(json!({ "op": "ping" }).to_string()),
+        })
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+};
+
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    heartbeat::{self, HeartbeatReport},
+    keys::{HEALTH_PREFIX, STATUS_PREFIX, SYMBOLS_KEY, TRADE_PREFIX},
+};
+
+use crate::redis_conn::RedisConn;
+
+const DEFAULT_STALE_SECS: i64 = 60;
+
+/// Overwrite `stock:status:{component}` with `fields`
+pub async fn record(
+    redis: &mut RedisConn,
+    component: &str,
+    fields: &[(&str, String)],
+) -> redis::RedisResult<()> {
+    let key = format!("{STATUS_PREFIX}{component}");
+    redis::pipe()
+        .atomic()
+        .del(&key)
+        .ignore()
+        .hset_multiple(&key, fields)
+        .ignore()
+        .query_async(redis)
+        .await
+}
+
+fn age_secs(at: &str, now: DateTime<Utc>) -> Option<i64> {
+    DateTime::parse_from_rfc3339(at)
+        .ok()
+        .map(|t| (now - t.with_timezone(&Utc)).num_seconds())
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SymbolStatus {
+    pub symbol: String,
+    /// Exchange time of the last trade, ms since epoch
+    pub last_trade_ts: Option<i64>,
+    pub age_ms: Option<i64>,
+    pub fresh: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExchangeStatus {
+    pub exchange: String,
+    /// `ok` when any symbol traded recently, else `stale`, or `no_data` before the first trade
+    pub status: &'static str,
+    pub symbols: usize,
+    pub fresh: usize,
+    /// Age of the newest trade across the exchange's symbols
+    pub last_trade_age_ms: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FetcherStatus {
+    pub last_insert_at: String,
+    pub rows: u64,
+    pub age_secs: Option<i64>,
+}
+
+impl FetcherStatus {
+    pub fn from_fields(fields: &HashMap<String, String>, now: DateTime<Utc>) -> Option<Self> {
+        let last_insert_at = fields.get("last_insert_at")?.clone();
+        Some(Self {
+            age_secs: age_secs(&last_insert_at, now),
+            rows: fields.get("rows").and_then(|v| v.parse().ok()).unwrap_or(0),
+            last_insert_at,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MaintenanceStatus {
+    pub started_at: String,
+    pub finished_at: String,
+    pub jobs: u64,
+    /// Failed or skipped jobs
+    pub failed: u64,
+    pub age_secs: Option<i64>,
+}
+
+impl MaintenanceStatus {
+    pub fn from_fields(fields: &HashMap<String, String>, now: DateTime<Utc>) -> Option<Self> {
+        let count = |k: &str| fields.get(k).and_then(|v| v.parse().ok()).unwrap_or(0);
+        let finished_at = fields.get("finished_at")?.clone();
+        Some(Self {
+            started_at: fields.get("started_at").cloned().unwrap_or_default(),
+            age_secs: age_secs(&finished_at, now),
+            jobs: count("jobs"),
+            failed: count("failed"),
+            finished_at,
+        })
+    }
+}
+
+/// In-process queues of the API serving the snapshot
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Queues {
+    /// Relay messages not yet read by the slowest live client
+    pub live_backlog: usize,
+    /// Connected WebSocket and SSE clients
+    pub live_clients: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HeartbeatStatus {
+    #[serde(flatten)]
+    pub report: HeartbeatReport,
+    /// The work loop has stopped progressing, though the process may still be up
+    pub stale: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Status {
+    pub generated_at: String,
+    /// Latest `/readyz` report each running binary published; missing ones are down
+    pub services: BTreeMap<String, Value>,
+    /// Latest heartbeat of each binary
+    pub heartbeats: Vec<HeartbeatStatus>,
+    pub exchanges: Vec<ExchangeStatus>,
+    pub symbols: Vec<SymbolStatus>,
+    pub fetcher: Option<FetcherStatus>,
+    pub maintenance: Option<MaintenanceStatus>,
+    pub queues: Queues,
+}
+
+/// Everything above, read from Redis; a symbol is fresh when it traded within
+/// `STATUS_STALE_SECS` (default 60)
+pub async fn snapshot(redis: &mut RedisConn, queues: Queues) -> redis::RedisResult<Status> {
+    snapshot_at(redis, queues, &SystemClock).await
+}
+
+/// [`snapshot`], with ages and staleness measured on `clock`
+pub async fn snapshot_at(redis: &mut RedisConn, queues: Queues, clock: &dyn Clock) -> redis::RedisResult<Status> {
+    let stale_ms = env::var("STATUS_STALE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_STALE_SECS)
+        * 1000;
+
+    let mut service_keys: Vec<String> = redis.keys(format!("{HEALTH_PREFIX}*")).await?;
+    service_keys.sort();
+    let reports: Vec<Option<String>> = if service_keys.is_empty() {
+        Vec::new()
+    } else {
+        redis.mget(&service_keys).await?
+    };
+    let services = service_keys
+        .iter()
+        .zip(reports)
+        .filter_map(|(key, report)| {
+            let report = serde_json::from_str(&report?).ok()?;
+            Some((key.trim_start_matches(HEALTH_PREFIX.as_str()).to_string(), report))
+        })
+        .collect();
+
+    let generated_at = clock.now();
+    let now = generated_at.timestamp_millis();
+    let heartbeats = heartbeat::read_all(redis)
+        .await?
+        .into_iter()
+        .map(|report| HeartbeatStatus {
+            stale: report.is_stale(now),
+            report,
+        })
+        .collect();
+
+    let mut symbols: Vec<String> = redis.smembers(SYMBOLS_KEY).await?;
+    symbols.sort();
+    let mut pipe = redis::pipe();
+    for s in &symbols {
+        pipe.hget(format!("{TRADE_PREFIX}{s}"), "timestamp");
+    }
+    pipe.hgetall(format!("{STATUS_PREFIX}fetcher"));
+    pipe.hgetall(format!("{STATUS_PREFIX}maintenance"));
+    let mut found: Vec<redis::Value> = pipe.query_async(redis).await?;
+    let maintenance: HashMap<String, String> = redis::from_redis_value(&found.pop().unwrap_or(redis::Value::Nil))?;
+    let fetcher: HashMap<String, String> = redis::from_redis_value(&found.pop().unwrap_or(redis::Value::Nil))?;
+
+    let symbols: Vec<SymbolStatus> = symbols
+        .into_iter()
+        .zip(found)
+        .map(|(symbol, ts)| {
+            let last_trade_ts = redis::from_redis_value::<Option<i64>>(&ts).ok().flatten();
+            let age_ms = last_trade_ts.map(|ts| now - ts);
+            SymbolStatus {
+                symbol,
+                last_trade_ts,
+                age_ms,
+                fresh: age_ms.is_some_and(|a| a < stale_ms),
+            }
+        })
+        .collect();
+
+    let mut exchanges: BTreeMap<&str, ExchangeStatus> = BTreeMap::new();
+    for s in &symbols {
+        let exchange = s.symbol.split_once(':').map_or(s.symbol.as_str(), |(e, _)| e);
+        let entry = exchanges.entry(exchange).or_insert_with(|| ExchangeStatus {
+            exchange: exchange.to_string(),
+            status: "no_data",
+            symbols: 0,
+            fresh: 0,
+            last_trade_age_ms: None,
+        });
+        entry.symbols += 1;
+        entry.fresh += usize::from(s.fresh);
+        if let Some(age) = s.age_ms {
+            entry.last_trade_age_ms = Some(entry.last_trade_age_ms.map_or(age, |a| a.min(age)));
+        }
+    }
+    let exchanges = exchanges
+        .into_values()
+        .map(|mut e| {
+            e.status = match (e.fresh, e.last_trade_age_ms) {
+                (0, None) => "no_data",
+                (0, Some(_)) => "stale",
+                _ => "ok",
+            };
+            e
+        })
+        .collect();
+
+    Ok(Status {
+        generated_at: generated_at.to_rfc3339(),
+        services,
+        heartbeats,
+        exchanges,
+        symbols,
+        fetcher: FetcherStatus::from_fields(&fetcher, generated_at),
+        maintenance: MaintenanceStatus::from_fields(&maintenance, generated_at),
+        queues,
+    })
+}
@@ -0,0 +1,291 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use redis::AsyncCommands;
+use serde::Deserialize;
+use tokio_postgres::Client as PgClient;
+use tracing::{error, info};
+
+use crate::{
+    finnhub::FinnhubClient,
+    keys::{SYMBOLS_FILE_KEY, SYMBOLS_KEY, SYMBOL_META_PREFIX, UNPERSISTED_KEY},
+    redis_conn::RedisConn,
+};
+
+
+/// Add the `active` flag to `stocks`; rows stay after removal so their history keeps its `stock_id`
+pub async fn ensure_columns(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute("ALTER TABLE stocks ADD COLUMN IF NOT EXISTS active BOOLEAN NOT NULL DEFAULT TRUE")
+        .await
+}
+
+/// `EXCHANGE:PAIR`, e.g. `BINANCE:BTCUSDT`
+pub fn parse(symbol: &str) -> Result<(&str, &str), String> {
+    match symbol.split_once(':') {
+        Some((exchange, pair))
+            if !exchange.is_empty()
+                && !pair.is_empty()
+                && symbol.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == ':' || c == '-' || c == '_') =>
+        {
+            Ok((exchange, pair))
+        }
+        _ => Err(format!("invalid symbol '{symbol}' (expected EXCHANGE:PAIR, e.g. BINANCE:BTCUSDT)")),
+    }
+}
+
+/// Whether the exchange lists `symbol`; `Err` when it is malformed or the lookup fails
+pub async fn listed(finnhub: &mut FinnhubClient, symbol: &str) -> Result<bool, String> {
+    let (exchange, _) = parse(symbol)?;
+    let listed = finnhub.crypto_symbols(exchange).await?;
+    Ok(listed.iter().any(|s| s == symbol))
+}
+
+/// `active` before a change: `None` when the row does not exist
+async fn previous(pg: &PgClient, symbol: &str) -> Result<Option<bool>, String> {
+    pg.query_opt("SELECT active FROM stocks WHERE symbol = $1", &[&symbol])
+        .await
+        .map(|row| row.map(|r| r.get(0)))
+        .map_err(|e| format!("stocks query failed: {e}"))
+}
+
+async fn set_active(pg: &PgClient, symbol: &str, active: bool) -> Result<(), String> {
+    pg.execute("UPDATE stocks SET active = $2 WHERE symbol = $1", &[&symbol, &active])
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("stocks update failed: {e}"))
+}
+
+/// Track `symbol` in both `stocks` and `SYMBOLS_KEY`; the row is restored if Redis fails.
+/// Returns false when it was already tracked.
+pub async fn add(pg: &PgClient, redis: &mut RedisConn, symbol: &str) -> Result<bool, String> {
+    let before = previous(pg, symbol).await?;
+    match before {
+        None => pg
+            .execute("INSERT INTO stocks (symbol) VALUES ($1)", &[&symbol])
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("stocks insert failed: {e}"))?,
+        Some(false) => set_active(pg, symbol, true).await?,
+        Some(true) => {}
+    }
+
+    match redis.sadd::<_, _, i64>(SYMBOLS_KEY, symbol).await {
+        Ok(added) => Ok(added > 0 || before != Some(true)),
+        Err(e) => {
+            if before != Some(true) {
+                set_active(pg, symbol, false).await?;
+            }
+            Err(format!("redis error: {e}"))
+        }
+    }
+}
+
+/// Stop tracking `symbol`; its `stocks` row is kept inactive. Returns false when it was not tracked.
+pub async fn remove(pg: &PgClient, redis: &mut RedisConn, symbol: &str) -> Result<bool, String> {
+    let before = previous(pg, symbol).await?;
+    if before == Some(true) {
+        set_active(pg, symbol, false).await?;
+    }
+
+    match redis.srem::<_, _, i64>(SYMBOLS_KEY, symbol).await {
+        Ok(removed) => Ok(removed > 0 || before == Some(true)),
+        Err(e) => {
+            if before == Some(true) {
+                set_active(pg, symbol, true).await?;
+            }
+            Err(format!("redis error: {e}"))
+        }
+    }
+}
+
+// -----------------------------------SYMBOLS FILE------------------------------------------------------------------------------
+
+/// Asset classes a symbols file entry may declare, as Finnhub groups them
+pub const ASSET_CLASSES: [&str; 3] = ["crypto", "forex", "stock"];
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SymbolsFile {
+    symbols: Vec<FileEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileEntry {
+    /// `EXCHANGE:PAIR`, or just the pair when `exchange` is given
+    symbol: String,
+    exchange: Option<String>,
+    #[serde(default = "crypto")]
+    asset_class: String,
+    #[serde(default = "persist")]
+    persist: bool,
+}
+
+fn crypto() -> String {
+    "crypto".to_string()
+}
+
+fn persist() -> bool {
+    true
+}
+
+/// One tracked symbol and its settings from the symbols file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolSpec {
+    pub symbol: String,
+    pub exchange: String,
+    pub asset_class: String,
+    /// Whether the fetcher writes its snapshots to Postgres
+    pub persist: bool,
+}
+
+impl FileEntry {
+    fn resolve(self) -> Result<SymbolSpec, String> {
+        let symbol = match &self.exchange {
+            Some(exchange) if !self.symbol.contains(':') => format!("{exchange}:{}", self.symbol),
+            _ => self.symbol,
+        };
+        let (exchange, _) = parse(&symbol)?;
+        if self.exchange.as_ref().is_some_and(|e| e != exchange) {
+            return Err(format!("{symbol}: exchange '{}' does not match the symbol", self.exchange.unwrap_or_default()));
+        }
+        if !ASSET_CLASSES.contains(&self.asset_class.as_str()) {
+            return Err(format!(
+                "{symbol}: asset_class must be one of {}, got '{}'",
+                ASSET_CLASSES.join(", "),
+                self.asset_class
+            ));
+        }
+        Ok(SymbolSpec {
+            exchange: exchange.to_string(),
+            symbol,
+            asset_class: self.asset_class,
+            persist: self.persist,
+        })
+    }
+}
+
+/// Read a symbols file by extension: `.yaml` / `.yml` as YAML, anything else as TOML.
+/// Every entry is checked; a duplicate symbol is an error.
+pub fn load_file(path: &Path) -> Result<Vec<SymbolSpec>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    let file: SymbolsFile = match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => serde_yaml::from_str(&text).map_err(|e| format!("invalid {}: {e}", path.display()))?,
+        _ => toml::from_str(&text).map_err(|e| format!("invalid {}: {e}", path.display()))?,
+    };
+
+    let mut seen = HashSet::new();
+    let mut specs = Vec::with_capacity(file.symbols.len());
+    for entry in file.symbols {
+        let spec = entry.resolve().map_err(|e| format!("{}: {e}", path.display()))?;
+        if !seen.insert(spec.symbol.clone()) {
+            return Err(format!("{}: {} is listed twice", path.display(), spec.symbol));
+        }
+        specs.push(spec);
+    }
+    Ok(specs)
+}
+
+/// Polls a symbols file's mtime and reads it again when it changes. Writers should write a
+/// temp file and rename it into place; a file that fails to load is reported once and
+/// the last good one stays in effect.
+pub struct SymbolsFileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl SymbolsFileWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), modified: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the file again on the next poll, e.g. after a failed sync
+    pub fn reload(&mut self) {
+        self.modified = None;
+    }
+
+    /// The file's entries when it changed since the last poll
+    pub fn poll(&mut self) -> Option<Vec<SymbolSpec>> {
+        let modified = match self.path.metadata().and_then(|m| m.modified()) {
+            Ok(m) => m,
+            Err(e) => {
+                if self.modified.take().is_some() {
+                    error!("❌ Cannot stat symbols file {}: {e}", self.path.display());
+                }
+                return None;
+            }
+        };
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+        match load_file(&self.path) {
+            Ok(specs) => Some(specs),
+            Err(e) => {
+                error!("❌ {e} — keeping the symbols from its last good version");
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Make the file-managed symbols match `specs`: track the new ones (as [`add`] does), untrack
+/// those dropped from the file since the last sync and store every entry's settings. Symbols
+/// tracked some other way (`SYMBOLS`, `POST /symbols`) are left alone.
+pub async fn sync(pg: &PgClient, redis: &mut RedisConn, specs: &[SymbolSpec]) -> Result<SyncReport, String> {
+    let previous: HashSet<String> = redis
+        .smembers(SYMBOLS_FILE_KEY)
+        .await
+        .map_err(|e| format!("redis error: {e}"))?;
+    let current: HashSet<&str> = specs.iter().map(|s| s.symbol.as_str()).collect();
+
+    let mut report = SyncReport::default();
+    for spec in specs {
+        if add(pg, redis, &spec.symbol).await? {
+            info!("📌 {} tracked from the symbols file", spec.symbol);
+            report.added += 1;
+        }
+    }
+    for symbol in previous.iter().filter(|s| !current.contains(s.as_str())) {
+        if remove(pg, redis, symbol).await? {
+            info!("🗑️ {symbol} dropped from the symbols file, untracked");
+            report.removed += 1;
+        }
+    }
+
+    let mut pipe = redis::pipe();
+    pipe.atomic().del(SYMBOLS_FILE_KEY).ignore().del(UNPERSISTED_KEY).ignore();
+    for symbol in &previous {
+        pipe.del(format!("{SYMBOL_META_PREFIX}{symbol}")).ignore();
+    }
+    for spec in specs {
+        pipe.sadd(SYMBOLS_FILE_KEY, &spec.symbol).ignore();
+        if !spec.persist {
+            pipe.sadd(UNPERSISTED_KEY, &spec.symbol).ignore();
+        }
+        pipe.hset_multiple(
+            format!("{SYMBOL_META_PREFIX}{}", spec.symbol),
+            &[
+                ("exchange", spec.exchange.clone()),
+                ("asset_class", spec.asset_class.clone()),
+                ("persist", spec.persist.to_string()),
+            ],
+        )
+        .ignore();
+    }
+    pipe.query_async::<()>(redis).await.map_err(|e| format!("redis error: {e}"))?;
+    Ok(report)
+}
@@ -0,0 +1,169 @@
+use std::{collections::HashMap, env};
+
+use redis::AsyncCommands;
+use serde_json::Value;
+
+use crate::{
+    api,
+    keys::{PREDICTION_PREFIX, SIGNAL_PREFIX, SYMBOLS_KEY, TELEGRAM_CHATS_KEY},
+    metrics::now_ms,
+    predictions::Prediction,
+    redis_conn::RedisConn,
+    signals::Signal,
+    webhooks::EventType,
+};
+
+const DEFAULT_PUSH: &str = "rule_alert,anomaly";
+
+pub const HELP: &str = "/price BTCUSDT — last trade\n\
+                        /predict ETHUSDT — latest prediction and signal\n\
+                        /alerts on | off — pushed alerts for this chat\n\
+                        Symbols may be bare pairs or EXCHANGE:PAIR.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Help,
+    Price(String),
+    Predict(String),
+    Alerts(bool),
+}
+
+impl Command {
+    /// `None` for plain chat; `Err` with a reply for malformed commands
+    pub fn parse(text: &str) -> Option<Result<Self, String>> {
+        let mut words = text.split_whitespace();
+        // Group chats address commands as /price@SomeBot
+        let name = words.next()?.strip_prefix('/')?.split('@').next()?;
+        let arg = words.next().map(str::to_uppercase);
+        Some(match (name, arg) {
+            ("start" | "help", _) => Ok(Self::Help),
+            ("price", Some(s)) => Ok(Self::Price(s)),
+            ("predict", Some(s)) => Ok(Self::Predict(s)),
+            ("price" | "predict", None) => Err(format!("Which symbol? e.g. /{name} BTCUSDT")),
+            ("alerts", Some(a)) if a == "ON" || a == "OFF" => Ok(Self::Alerts(a == "ON")),
+            ("alerts", _) => Err("Use /alerts on or /alerts off".to_string()),
+            _ => Err(format!("Unknown command /{name}\n{HELP}")),
+        })
+    }
+}
+
+/// The tracked symbol `query` names: `EXCHANGE:PAIR` as is, or the first tracked symbol
+/// whose pair is `query`
+pub async fn resolve(redis: &mut RedisConn, query: &str) -> redis::RedisResult<Option<String>> {
+    let mut tracked: Vec<String> = redis.smembers(SYMBOLS_KEY).await?;
+    tracked.sort();
+    Ok(tracked.into_iter().find(|s| {
+        s == query || s.split_once(':').is_some_and(|(_, pair)| pair == query)
+    }))
+}
+
+/// `TELEGRAM_PUSH` (default `rule_alert,anomaly`): events pushed to chats with alerts on
+pub fn push_events_from_env() -> Result<Vec<EventType>, String> {
+    env::var("TELEGRAM_PUSH")
+        .unwrap_or_else(|_| DEFAULT_PUSH.to_string())
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+fn ago(ts: i64) -> String {
+    let secs = (now_ms() - ts).max(0) / 1000;
+    match secs {
+        0..60 => format!("{secs}s ago"),
+        60..3600 => format!("{}m ago", secs / 60),
+        _ => format!("{}h ago", secs / 3600),
+    }
+}
+
+/// Reply to `command` from `chat_id`
+pub async fn answer(redis: &mut RedisConn, chat_id: i64, command: Command) -> redis::RedisResult<String> {
+    let symbol = match &command {
+        Command::Price(q) | Command::Predict(q) => match resolve(redis, q).await? {
+            Some(s) => s,
+            None => return Ok(format!("{q} is not tracked")),
+        },
+        _ => String::new(),
+    };
+    Ok(match command {
+        Command::Help => HELP.to_string(),
+        Command::Price(_) => match api::latest_price(redis, &symbol).await? {
+            Some(p) => format!(
+                "💰 {symbol}: {}{}",
+                p.price,
+                p.ts.map(|ts| format!(" ({})", ago(ts))).unwrap_or_default()
+            ),
+            None => format!("No trades for {symbol} yet"),
+        },
+        Command::Predict(_) => {
+            let fields: HashMap<String, String> = redis.hgetall(format!("{PREDICTION_PREFIX}{symbol}")).await?;
+            let Some(p) = Prediction::from_fields(&symbol, &fields) else {
+                return Ok(format!("No prediction for {symbol} yet"));
+            };
+            let fields: HashMap<String, String> = redis.hgetall(format!("{SIGNAL_PREFIX}{symbol}")).await?;
+            let signal = Signal::from_fields(&symbol, &fields)
+                .map(|s| format!("\nSignal: {}", s.side))
+                .unwrap_or_default();
+            format!(
+                "🔮 {symbol} over {}: {:+.3}% → {:.6} (80% band {:+.3}%…{:+.3}%)\n\
+                 Confidence {:.0}%, {} {}, {}{signal}",
+                p.horizon,
+                p.predicted_return * 100.0,
+                p.predicted_price,
+                p.q10 * 100.0,
+                p.q90 * 100.0,
+                p.confidence * 100.0,
+                p.model,
+                p.model_version,
+                ago(p.predicted_at),
+            )
+        }
+        Command::Alerts(on) => {
+            if on {
+                redis.sadd::<_, _, ()>(TELEGRAM_CHATS_KEY, chat_id).await?;
+                "🔔 Alerts on for this chat".to_string()
+            } else {
+                redis.srem::<_, _, ()>(TELEGRAM_CHATS_KEY, chat_id).await?;
+                "🔕 Alerts off for this chat".to_string()
+            }
+        }
+    })
+}
+
+/// One-line chat text for a pushed pipeline event
+pub fn format_event(kind: EventType, event: &Value) -> String {
+    let text = |k: &str| event[k].as_str().unwrap_or("?");
+    let num = |k: &str| event[k].as_f64().unwrap_or(f64::NAN);
+    match kind {
+        EventType::RuleAlert => format!("🔔 {}", text("message")),
+        EventType::Anomaly => format!(
+            "⚠️ {} {} on {} (z {:.1}), close {}",
+            text("symbol"),
+            text("kind").replace('_', " "),
+            text("tf"),
+            num("z"),
+            num("close")
+        ),
+        EventType::Signal => format!(
+            "📈 {} signal {} ({:+.3}%, confidence {:.0}%)",
+            text("symbol"),
+            text("side"),
+            num("predicted_return") * 100.0,
+            num("confidence") * 100.0
+        ),
+        EventType::Prediction => format!(
+            "🔮 {} {}: {:+.3}%",
+            text("symbol"),
+            text("horizon"),
+            num("predicted_return") * 100.0
+        ),
+        EventType::Bar => format!(
+            "🕯️ {} {} closed {} ({:+.2}%)",
+            text("symbol"),
+            text("tf"),
+            num("close"),
+            (num("close") / num("open") - 1.0) * 100.0
+        ),
+    }
+}
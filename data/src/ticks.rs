@@ -0,0 +1,111 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env,
+    time::{Duration, Instant},
+};
+
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+use crate::{keys::TICKS_PREFIX, redis_conn::RedisConn};
+
+/// Ticks kept per symbol unless `TICK_BUFFER_LEN` says otherwise
+pub const DEFAULT_TICK_BUFFER_LEN: usize = 500;
+
+const FLUSH_EVERY: Duration = Duration::from_secs(1);
+// Outlives an ingester restart, but a symbol that stopped trading is not served stale ticks for long
+const BLOB_TTL_SECS: u64 = 300;
+
+/// One raw trade
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Tick {
+    pub price: f64,
+    /// `None` when the exchange sent none
+    pub volume: Option<f64>,
+    /// Exchange time, ms since epoch
+    pub ts: i64,
+}
+
+/// The last N ticks of every symbol the ingester streams, so short-horizon features can
+/// be computed from raw trades without a tick database. Each symbol's ticks are written
+/// to `{ns}:ticks:{symbol}` as a JSON array, oldest first, at most once a second.
+pub struct TickBuffer {
+    len: usize,
+    ticks: HashMap<String, VecDeque<Tick>>,
+    changed: HashSet<String>,
+    last_flush: Instant,
+}
+
+impl TickBuffer {
+    /// `TICK_BUFFER_LEN` ticks per symbol (default [`DEFAULT_TICK_BUFFER_LEN`]); `None` when 0
+    pub fn from_env() -> Option<Self> {
+        let len = env::var("TICK_BUFFER_LEN")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TICK_BUFFER_LEN);
+        (len > 0).then(|| Self::new(len))
+    }
+
+    pub fn new(len: usize) -> Self {
+        Self {
+            len,
+            ticks: HashMap::new(),
+            changed: HashSet::new(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Ticks kept per symbol
+    pub fn capacity(&self) -> usize {
+        self.len
+    }
+
+    pub fn push(&mut self, symbol: &str, tick: Tick) {
+        let ring = self
+            .ticks
+            .entry(symbol.to_string())
+            .or_insert_with(|| VecDeque::with_capacity(self.len));
+        if ring.len() == self.len {
+            ring.pop_front();
+        }
+        ring.push_back(tick);
+        if !self.changed.contains(symbol) {
+            self.changed.insert(symbol.to_string());
+        }
+    }
+
+    /// `symbol`'s ticks, oldest first
+    pub fn recent(&self, symbol: &str) -> impl Iterator<Item = &Tick> {
+        self.ticks.get(symbol).into_iter().flatten()
+    }
+
+    /// Forget the symbols `keep` rejects, e.g. after they were unsubscribed
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.ticks.retain(|symbol, _| keep(symbol));
+        self.changed.retain(|symbol| keep(symbol));
+    }
+
+    /// Write the symbols that got ticks since the last write, if a second has passed
+    pub async fn flush_if_due(&mut self, redis: &mut RedisConn) -> redis::RedisResult<()> {
+        if self.changed.is_empty() || self.last_flush.elapsed() < FLUSH_EVERY {
+            return Ok(());
+        }
+        self.last_flush = Instant::now();
+        let mut pipe = redis::pipe();
+        for symbol in &self.changed {
+            let ticks: Vec<&Tick> = self.recent(symbol).collect();
+            let blob = serde_json::to_string(&ticks).unwrap_or_default();
+            pipe.set_ex(format!("{TICKS_PREFIX}{symbol}"), blob, BLOB_TTL_SECS).ignore();
+        }
+        pipe.query_async::<()>(redis).await?;
+        self.changed.clear();
+        Ok(())
+    }
+}
+
+/// `symbol`'s last written ticks, oldest first; `None` when the ingester keeps none for it
+pub async fn load(redis: &mut RedisConn, symbol: &str) -> redis::RedisResult<Option<Vec<Tick>>> {
+    let blob: Option<String> = redis.get(format!("{TICKS_PREFIX}{symbol}")).await?;
+    Ok(blob.and_then(|b| serde_json::from_str(&b).ok()))
+}
@@ -0,0 +1,282 @@
+use std::{
+    env, fmt,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use native_tls::{Certificate, Identity, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use redis::{ClientTlsConfig, TlsCertificates};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio_postgres::config::{Config as PgConfig, Host, SslMode};
+
+/// Whether a connection uses TLS. Nothing falls back to plaintext unless `prefer` says so.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TlsMode {
+    /// Plaintext only
+    Disable,
+    /// TLS, or plaintext when the server does not offer it (Postgres) or the TLS
+    /// connection fails (Redis); the downgrade is logged
+    Prefer,
+    /// TLS only; a server without it is an error
+    Require,
+}
+
+impl FromStr for TlsMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "disable" => Ok(Self::Disable),
+            "prefer" => Ok(Self::Prefer),
+            "require" => Ok(Self::Require),
+            other => Err(format!("unknown TLS mode '{other}' (disable, prefer or require)")),
+        }
+    }
+}
+
+impl fmt::Display for TlsMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Disable => "disable",
+            Self::Prefer => "prefer",
+            Self::Require => "require",
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("{0}")]
+    Setting(String),
+    #[error("cannot read {path}: {source}")]
+    File {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("{path}: {reason}")]
+    Pem { path: PathBuf, reason: String },
+    #[error("TLS setup failed: {0}")]
+    Native(#[from] native_tls::Error),
+    #[error("invalid DATABASE_URL: {0}")]
+    PostgresUrl(#[source] tokio_postgres::Error),
+}
+
+/// One service's TLS: `[tls.postgres]` / `[tls.redis]`, or the `PG_TLS_*` / `REDIS_TLS_*`
+/// variables named below with that prefix
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsSettings {
+    /// `*_TLS_MODE`. Unset, the URL decides: `sslmode=` for Postgres (else `disable` for
+    /// a loopback host or socket and `require` for anything else), the scheme for Redis
+    /// (`rediss://` requires TLS, `redis://` disables it)
+    pub mode: Option<TlsMode>,
+    /// PEM bundle trusted instead of the system roots (`*_TLS_CA_FILE`)
+    pub ca_file: Option<PathBuf>,
+    /// PEM client certificate for mutual TLS (`*_TLS_CERT_FILE`)
+    pub cert_file: Option<PathBuf>,
+    /// Its PKCS#8 PEM private key (`*_TLS_KEY_FILE`)
+    pub key_file: Option<PathBuf>,
+    /// Check the server's certificate chain (`*_TLS_VERIFY`); off is for testing only
+    pub verify: bool,
+    /// Check that the certificate names the host (`*_TLS_VERIFY_HOSTNAME`)
+    pub verify_hostname: bool,
+}
+
+impl Default for TlsSettings {
+    fn default() -> Self {
+        Self {
+            mode: None,
+            ca_file: None,
+            cert_file: None,
+            key_file: None,
+            verify: true,
+            verify_hostname: true,
+        }
+    }
+}
+
+/// The `[tls]` config section
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub postgres: TlsSettings,
+    pub redis: TlsSettings,
+}
+
+impl TlsConfig {
+    /// Apply `PG_TLS_*` and `REDIS_TLS_*`, returning what does not parse or fit together
+    pub fn apply_env(&mut self) -> Vec<String> {
+        let mut errors = self.postgres.apply_env("PG");
+        errors.extend(self.redis.apply_env("REDIS"));
+        errors.extend(self.postgres.problems("tls.postgres"));
+        errors.extend(self.redis.problems("tls.redis"));
+        errors
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, TlsError> {
+    std::fs::read(path).map_err(|source| TlsError::File {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Every `CERTIFICATE` block of a PEM bundle; native-tls parses one at a time
+fn pem_certificates(path: &Path, pem: &[u8]) -> Result<Vec<Certificate>, TlsError> {
+    const END: &str = "-----END CERTIFICATE-----";
+    let text = String::from_utf8_lossy(pem);
+    let mut certs = Vec::new();
+    let mut rest = text.as_ref();
+    while let Some(start) = rest.find("-----BEGIN CERTIFICATE-----") {
+        let Some(len) = rest[start..].find(END) else { break };
+        let block = &rest[start..start + len + END.len()];
+        certs.push(Certificate::from_pem(block.as_bytes()).map_err(|e| TlsError::Pem {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?);
+        rest = &rest[start + len + END.len()..];
+    }
+    if certs.is_empty() {
+        return Err(TlsError::Pem {
+            path: path.to_path_buf(),
+            reason: "no PEM certificates".to_string(),
+        });
+    }
+    Ok(certs)
+}
+
+fn is_loopback(host: &Host) -> bool {
+    match host {
+        Host::Tcp(h) => h == "localhost" || h.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback()),
+        #[cfg(unix)]
+        Host::Unix(_) => true,
+    }
+}
+
+impl TlsSettings {
+    /// Apply `{prefix}_TLS_*`, returning what does not parse
+    pub fn apply_env(&mut self, prefix: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        let var = |name: &str| env::var(format!("{prefix}_TLS_{name}")).ok();
+        if let Some(v) = var("MODE") {
+            match v.parse() {
+                Ok(mode) => self.mode = Some(mode),
+                Err(e) => errors.push(format!("{prefix}_TLS_MODE: {e}")),
+            }
+        }
+        for (name, field) in [
+            ("CA_FILE", &mut self.ca_file),
+            ("CERT_FILE", &mut self.cert_file),
+            ("KEY_FILE", &mut self.key_file),
+        ] {
+            if let Some(v) = var(name) {
+                *field = Some(PathBuf::from(v));
+            }
+        }
+        for (name, field) in [("VERIFY", &mut self.verify), ("VERIFY_HOSTNAME", &mut self.verify_hostname)] {
+            if let Some(v) = var(name) {
+                match v.trim().parse() {
+                    Ok(b) => *field = b,
+                    Err(_) => errors.push(format!("{prefix}_TLS_{name}: cannot parse '{v}' (true or false)")),
+                }
+            }
+        }
+        errors
+    }
+
+    pub fn problems(&self, section: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.cert_file.is_some() != self.key_file.is_some() {
+            errors.push(format!("{section}: cert_file and key_file go together"));
+        }
+        if self.mode == Some(TlsMode::Disable)
+            && (self.ca_file.is_some() || self.cert_file.is_some() || !self.verify || !self.verify_hostname)
+        {
+            errors.push(format!("{section}: certificate and verify settings have no effect with mode = disable"));
+        }
+        for path in [&self.ca_file, &self.cert_file, &self.key_file].into_iter().flatten() {
+            if !path.exists() {
+                errors.push(format!("{section}: {} does not exist", path.display()));
+            }
+        }
+        errors
+    }
+
+    /// `url` parsed, with `sslmode` set from the mode in effect
+    pub fn postgres_config(&self, url: &str) -> Result<(PgConfig, TlsMode), TlsError> {
+        let mut cfg = PgConfig::from_str(url).map_err(TlsError::PostgresUrl)?;
+        let mode = match self.mode {
+            Some(mode) => mode,
+            None if url.contains("sslmode=") => match cfg.get_ssl_mode() {
+                SslMode::Disable => TlsMode::Disable,
+                SslMode::Require => TlsMode::Require,
+                _ => TlsMode::Prefer,
+            },
+            None if cfg.get_hosts().iter().all(is_loopback) => TlsMode::Disable,
+            None => TlsMode::Require,
+        };
+        cfg.ssl_mode(match mode {
+            TlsMode::Disable => SslMode::Disable,
+            TlsMode::Prefer => SslMode::Prefer,
+            TlsMode::Require => SslMode::Require,
+        });
+        Ok((cfg, mode))
+    }
+
+    /// native-tls connector for Postgres with the CA bundle, client identity and checks applied
+    pub fn postgres_connector(&self) -> Result<MakeTlsConnector, TlsError> {
+        let mut builder = TlsConnector::builder();
+        if let Some(path) = &self.ca_file {
+            builder.disable_built_in_roots(true);
+            for cert in pem_certificates(path, &read(path)?)? {
+                builder.add_root_certificate(cert);
+            }
+        }
+        if let (Some(cert), Some(key)) = (&self.cert_file, &self.key_file) {
+            let identity = Identity::from_pkcs8(&read(cert)?, &read(key)?).map_err(|e| TlsError::Pem {
+                path: key.clone(),
+                reason: format!("client certificate and key: {e}"),
+            })?;
+            builder.identity(identity);
+        }
+        builder
+            .danger_accept_invalid_certs(!self.verify)
+            .danger_accept_invalid_hostnames(!self.verify_hostname);
+        Ok(MakeTlsConnector::new(builder.build()?))
+    }
+
+    /// Whether a Redis URL connects over TLS, from the mode and the scheme; `disable` with
+    /// a `rediss` URL is an error rather than a quiet downgrade
+    pub fn redis_mode(&self, url: &str) -> Result<TlsMode, TlsError> {
+        let tls_scheme = url.starts_with("rediss");
+        match (self.mode, tls_scheme) {
+            (None, true) => Ok(TlsMode::Require),
+            (None, false) => Ok(TlsMode::Disable),
+            (Some(TlsMode::Disable), true) => Err(TlsError::Setting(
+                "Redis TLS mode is disable but the URL is rediss://; use a redis:// URL for plaintext".to_string(),
+            )),
+            (Some(mode), _) => Ok(mode),
+        }
+    }
+
+    /// The CA bundle and client identity for redis, when either is set
+    pub fn redis_certificates(&self) -> Result<Option<TlsCertificates>, TlsError> {
+        if self.ca_file.is_none() && self.cert_file.is_none() {
+            return Ok(None);
+        }
+        let client_tls = match (&self.cert_file, &self.key_file) {
+            (Some(cert), Some(key)) => Some(ClientTlsConfig {
+                client_cert: read(cert)?,
+                client_key: read(key)?,
+            }),
+            _ => None,
+        };
+        let root_cert = self.ca_file.as_deref().map(read).transpose()?;
+        Ok(Some(TlsCertificates { client_tls, root_cert }))
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tch::{CModule, Device, Kind, TchError, Tensor};
+
+use tracing::error;
+use crate::models::Model;
+
+/// PyTorch-trained model exported to TorchScript (`torch.jit.save`). Same
+/// contract as ONNX: `forward` takes one float32 tensor of shape
+/// `[1, n_features]` in `FEATURE_NAMES` order and the first element of its
+/// output is the predicted next-bar log return. Runs on CUDA when available.
+#[derive(Clone)]
+pub struct TorchModel {
+    module: Arc<CModule>,
+    device: Device,
+    n_features: usize,
+    version: String,
+    path: PathBuf,
+}
+
+impl TorchModel {
+    pub fn load(path: &Path, n_features: usize) -> Result<Self, String> {
+        let device = Device::cuda_if_available();
+        let mut module = CModule::load_on_device(path, device)
+            .map_err(|e| format!("failed to load TorchScript model {}: {e}", path.display()))?;
+        module.set_eval();
+
+        let version = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        Ok(Self {
+            module: Arc::new(module),
+            device,
+            n_features,
+            version,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// File the model was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn run(&self, x: &[f64]) -> Result<f64, TchError> {
+        let values: Vec<f32> = x.iter().map(|v| *v as f32).collect();
+        let input = Tensor::f_from_slice(&values)?
+            .f_reshape([1, self.n_features as i64])?
+            .f_to(self.device)?;
+        let output = tch::no_grad(|| self.module.forward_ts(&[input]))?;
+        output.f_to_kind(Kind::Double)?.f_flatten(0, -1)?.f_double_value(&[0])
+    }
+}
+
+impl Model for TorchModel {
+    fn name(&self) -> &str {
+        "torch"
+    }
+
+    fn version(&self) -> String {
+        self.version.clone()
+    }
+
+    fn predict(&self, x: &[f64]) -> f64 {
+        if x.len() != self.n_features {
+            return 0.0;
+        }
+        match self.run(x) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("❌ TorchScript inference failed: {e}");
+                0.0
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+
+
+const WINDOW: usize = 20;
+/// RiskMetrics decay for the EWMA variance
+const EWMA_LAMBDA: f64 = 0.94;
+const GARCH_ALPHA: f64 = 0.05;
+const GARCH_BETA: f64 = 0.90;
+
+/// Per-bar log-return volatilities (standard deviations, not annualized)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VolatilitySnapshot {
+    /// Sample std of the last `WINDOW` returns
+    pub realized: Option<f64>,
+    pub ewma: Option<f64>,
+    /// GARCH(1,1) forecast for the next bar
+    pub garch: Option<f64>,
+}
+
+impl VolatilitySnapshot {
+    /// Best available estimate: GARCH, then EWMA, then realized
+    pub fn best(&self) -> Option<f64> {
+        self.garch.or(self.ewma).or(self.realized)
+    }
+
+    /// Redis hash fields; estimates still warming up are omitted
+    pub fn fields(&self) -> Vec<(String, String)> {
+        [
+            ("realized", self.realized),
+            ("ewma", self.ewma),
+            ("garch", self.garch),
+        ]
+        .into_iter()
+        .filter_map(|(k, v)| v.map(|v| (k.to_string(), v.to_string())))
+        .collect()
+    }
+}
+
+/// Rolling realized, EWMA and GARCH(1,1) variance from bar returns. GARCH uses
+/// fixed α/β with ω targeting the variance of the first full window.
+#[derive(Debug, Clone, Default)]
+pub struct VolatilityEstimator {
+    returns: VecDeque<f64>,
+    ewma_var: Option<f64>,
+    garch_var: Option<f64>,
+    omega: f64,
+}
+
+impl VolatilityEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn window_var(&self) -> Option<f64> {
+        if self.returns.len() < 2 {
+            return None;
+        }
+        let n = self.returns.len() as f64;
+        let mean = self.returns.iter().sum::<f64>() / n;
+        Some(self.returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0))
+    }
+
+    pub fn update(&mut self, r: f64) -> VolatilitySnapshot {
+        if r.is_finite() {
+            let r2 = r * r;
+            self.ewma_var = Some(match self.ewma_var {
+                Some(v) => EWMA_LAMBDA * v + (1.0 - EWMA_LAMBDA) * r2,
+                None => r2,
+            });
+            if let Some(v) = self.garch_var {
+                self.garch_var = Some(self.omega + GARCH_ALPHA * r2 + GARCH_BETA * v);
+            }
+
+            self.returns.push_back(r);
+            if self.returns.len() > WINDOW {
+                self.returns.pop_front();
+            }
+            if self.garch_var.is_none()
+                && self.returns.len() == WINDOW
+                && let Some(long_run) = self.window_var()
+            {
+                self.omega = long_run * (1.0 - GARCH_ALPHA - GARCH_BETA);
+                self.garch_var = Some(long_run);
+            }
+        }
+        self.snapshot()
+    }
+
+    pub fn snapshot(&self) -> VolatilitySnapshot {
+        VolatilitySnapshot {
+            realized: self.window_var().map(f64::sqrt),
+            ewma: self.ewma_var.map(f64::sqrt),
+            garch: self.garch_var.map(f64::sqrt),
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use std::{env, fmt, str::FromStr, time::Duration};
+
+use chrono::{NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use tokio::time::sleep;
+use tokio_postgres::{Client as PgClient, Row};
+
+use tracing::warn;
+use crate::keys::{ANOMALIES_CHANNEL, BARS_CHANNEL, PREDICTIONS_CHANNEL, RULE_ALERTS_CHANNEL, SIGNALS_CHANNEL};
+
+/// `sha256=<hex>` HMAC of `"{timestamp}.{body}"` keyed with the subscription's secret
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// Unix seconds the delivery was signed at; receivers should reject old ones
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+/// Same on every attempt of one delivery, for receivers to deduplicate retries
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+pub const MIN_SECRET_LEN: usize = 16;
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Doubled after every failed attempt
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Pipeline events a webhook can subscribe to, one per pub/sub channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    /// Closed bar; threshold on |close / open − 1| in percent
+    Bar,
+    /// Model prediction; threshold on |predicted return| in percent
+    Prediction,
+    /// Trading signal; threshold on confidence (0..1)
+    Signal,
+    /// Return jump or volume spike; threshold on |z|
+    Anomaly,
+    /// Fired alert rule; threshold on |value|
+    RuleAlert,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 5] = [
+        EventType::Bar,
+        EventType::Prediction,
+        EventType::Signal,
+        EventType::Anomaly,
+        EventType::RuleAlert,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bar => "bar",
+            Self::Prediction => "prediction",
+            Self::Signal => "signal",
+            Self::Anomaly => "anomaly",
+            Self::RuleAlert => "rule_alert",
+        }
+    }
+
+    pub fn channel(&self) -> &'static str {
+        match self {
+            Self::Bar => BARS_CHANNEL,
+            Self::Prediction => PREDICTIONS_CHANNEL,
+            Self::Signal => SIGNALS_CHANNEL,
+            Self::Anomaly => ANOMALIES_CHANNEL,
+            Self::RuleAlert => RULE_ALERTS_CHANNEL,
+        }
+    }
+
+    pub fn from_channel(channel: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.channel() == channel)
+    }
+
+    /// The number a subscription's threshold is compared against
+    pub fn metric(&self, event: &Value) -> Option<f64> {
+        let num = |k: &str| event.get(k).and_then(Value::as_f64);
+        match self {
+            Self::Bar => {
+                let open = num("open").filter(|o| *o != 0.0)?;
+                Some((num("close")? / open - 1.0).abs() * 100.0)
+            }
+            Self::Prediction => Some(num("predicted_return")?.abs() * 100.0),
+            Self::Signal => num("confidence"),
+            Self::Anomaly => Some(num("z")?.abs()),
+            Self::RuleAlert => Some(num("value")?.abs()),
+        }
+    }
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|e| e.as_str() == s)
+            .ok_or_else(|| format!("unknown event '{s}' (use bar, prediction, signal, anomaly or rule_alert)"))
+    }
+}
+
+/// One registered webhook
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Subscription {
+    pub id: i32,
+    /// Principal that registered it; only they can list or remove it
+    pub owner: String,
+    pub url: String,
+    #[serde(skip)]
+    pub secret: String,
+    /// Every symbol when absent
+    pub symbol: Option<String>,
+    pub event: String,
+    /// Minimum event metric (see `EventType`); every event when absent
+    pub threshold: Option<f64>,
+    /// RFC 3339, UTC
+    pub created_at: String,
+    pub last_delivery_at: Option<String>,
+    /// HTTP status or error of the last delivery's final attempt
+    pub last_result: Option<String>,
+    /// Deliveries failed in a row
+    pub failures: i32,
+}
+
+impl Subscription {
+    fn from_row(row: &Row) -> Self {
+        Self {
+            id: row.get("id"),
+            owner: row.get("owner"),
+            url: row.get("url"),
+            secret: row.get("secret"),
+            symbol: row.get("symbol"),
+            event: row.get("event"),
+            threshold: row.get("threshold"),
+            created_at: row.get::<_, NaiveDateTime>("created_at").and_utc().to_rfc3339(),
+            last_delivery_at: row
+                .get::<_, Option<NaiveDateTime>>("last_delivery_at")
+                .map(|t| t.and_utc().to_rfc3339()),
+            last_result: row.get("last_result"),
+            failures: row.get("failures"),
+        }
+    }
+
+    /// Whether `event` of type `kind` should be delivered here
+    pub fn matches(&self, kind: EventType, event: &Value) -> bool {
+        if self.event != kind.as_str() {
+            return false;
+        }
+        if let Some(symbol) = &self.symbol
+            && event.get("symbol").and_then(Value::as_str) != Some(symbol.as_str())
+        {
+            return false;
+        }
+        self.threshold.is_none_or(|t| kind.metric(event).is_some_and(|m| m >= t))
+    }
+}
+
+/// `POST /webhooks` body
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NewSubscription {
+    pub url: String,
+    /// Shared HMAC key, at least 16 characters; never returned
+    pub secret: String,
+    pub symbol: Option<String>,
+    /// bar, prediction, signal, anomaly or rule_alert
+    pub event: String,
+    pub threshold: Option<f64>,
+}
+
+impl NewSubscription {
+    /// Normalised copy, or why it cannot be registered
+    pub fn validate(self) -> Result<Self, String> {
+        let url = url::Url::parse(self.url.trim()).map_err(|e| format!("invalid url: {e}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("url must be http or https".to_string());
+        }
+        if self.secret.len() < MIN_SECRET_LEN {
+            return Err(format!("secret must be at least {MIN_SECRET_LEN} characters"));
+        }
+        let event: EventType = self.event.trim().parse()?;
+        if self.threshold.is_some_and(|t| !t.is_finite()) {
+            return Err("threshold must be a finite number".to_string());
+        }
+        Ok(Self {
+            url: url.to_string(),
+            secret: self.secret,
+            symbol: self.symbol.map(|s| s.trim().to_uppercase()).filter(|s| !s.is_empty()),
+            event: event.to_string(),
+            threshold: self.threshold,
+        })
+    }
+}
+
+/// Create the `webhook_subscriptions` table if missing
+pub async fn ensure_table(pg: &PgClient) -> Result<(), tokio_postgres::Error> {
+    pg.batch_execute(
+        "CREATE TABLE IF NOT EXISTS webhook_subscriptions ( \
+             id SERIAL PRIMARY KEY, \
+             owner TEXT NOT NULL, \
+             url TEXT NOT NULL, \
+             secret TEXT NOT NULL, \
+             symbol TEXT, \
+             event TEXT NOT NULL, \
+             threshold DOUBLE PRECISION, \
+             created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'), \
+             last_delivery_at TIMESTAMP, \
+             last_result TEXT, \
+             failures INTEGER NOT NULL DEFAULT 0 \
+         );",
+    )
+    .await
+}
+
+pub async fn insert(pg: &PgClient, owner: &str, sub: &NewSubscription) -> Result<Subscription, String> {
+    let row = pg
+        .query_one(
+            "INSERT INTO webhook_subscriptions (owner, url, secret, symbol, event, threshold) \
+             VALUES ($1, $2, $3, $4, $5, $6) RETURNING *",
+            &[&owner, &sub.url, &sub.secret, &sub.symbol, &sub.event, &sub.threshold],
+        )
+        .await
+        .map_err(|e| format!("webhook insert failed: {e}"))?;
+    Ok(Subscription::from_row(&row))
+}
+
+/// `owner`'s subscriptions, or everyone's when `None`
+pub async fn list(pg: &PgClient, owner: Option<&str>) -> Result<Vec<Subscription>, String> {
+    let rows = pg
+        .query(
+            "SELECT * FROM webhook_subscriptions WHERE $1::text IS NULL OR owner = $1 ORDER BY id",
+            &[&owner],
+        )
+        .await
+        .map_err(|e| format!("webhook query failed: {e}"))?;
+    Ok(rows.iter().map(Subscription::from_row).collect())
+}
+
+/// False when `owner` has no subscription with that id
+pub async fn delete(pg: &PgClient, owner: &str, id: i32) -> Result<bool, String> {
+    pg.execute("DELETE FROM webhook_subscriptions WHERE id = $1 AND owner = $2", &[&id, &owner])
+        .await
+        .map(|n| n > 0)
+        .map_err(|e| format!("webhook delete failed: {e}"))
+}
+
+/// Store the outcome of a delivery's final attempt
+pub async fn record_result(pg: &PgClient, id: i32, result: &Result<u16, String>) -> Result<(), String> {
+    let (text, ok) = match result {
+        Ok(status) => (status.to_string(), true),
+        Err(e) => (e.clone(), false),
+    };
+    pg.execute(
+        "UPDATE webhook_subscriptions SET last_delivery_at = now() AT TIME ZONE 'utc', last_result = $2, \
+         failures = CASE WHEN $3 THEN 0 ELSE failures + 1 END WHERE id = $1",
+        &[&id, &text, &ok],
+    )
+    .await
+    .map(|_| ())
+    .map_err(|e| format!("webhook update failed: {e}"))
+}
+
+/// Hex HMAC-SHA256 of `"{timestamp}.{body}"`
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// JSON body posted to a subscriber
+#[derive(Debug, Serialize)]
+pub struct Delivery<'a> {
+    /// `{subscription}-{event ts}-{event}`, stable across retries
+    pub id: String,
+    pub subscription: i32,
+    pub event: &'static str,
+    /// Event time, ms since epoch
+    pub ts: i64,
+    pub data: &'a Value,
+}
+
+/// Signs and posts deliveries, retrying network errors, 429 and 5xx with exponential backoff
+#[derive(Clone)]
+pub struct Deliverer {
+    http: reqwest::Client,
+    pub max_attempts: u32,
+}
+
+impl Deliverer {
+    /// `WEBHOOK_MAX_ATTEMPTS` (default 5)
+    pub fn from_env() -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("❌ Failed to build HTTP client");
+        let max_attempts = env::var("WEBHOOK_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_ATTEMPTS)
+            .max(1);
+        Self { http, max_attempts }
+    }
+
+    /// Status of the accepted attempt, or the last failure
+    pub async fn deliver(&self, sub: &Subscription, kind: EventType, event: &Value) -> Result<u16, String> {
+        let ts = ["ts", "feature_ts", "start"]
+            .into_iter()
+            .find_map(|k| event.get(k).and_then(Value::as_i64))
+            .unwrap_or_else(|| Utc::now().timestamp_millis());
+        let delivery = Delivery {
+            id: format!("{}-{ts}-{kind}", sub.id),
+            subscription: sub.id,
+            event: kind.as_str(),
+            ts,
+            data: event,
+        };
+        let body = serde_json::to_string(&delivery).map_err(|e| format!("cannot encode delivery: {e}"))?;
+
+        let mut delay = FIRST_RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            let signed_at = Utc::now().timestamp();
+            let result = self
+                .http
+                .post(&sub.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, format!("sha256={}", sign(&sub.secret, signed_at, &body)))
+                .header(TIMESTAMP_HEADER, signed_at.to_string())
+                .header(EVENT_HEADER, kind.as_str())
+                .header(DELIVERY_HEADER, &delivery.id)
+                .body(body.clone())
+                .send()
+                .await;
+            let (retry, outcome) = match result {
+                Ok(resp) if resp.status().is_success() => return Ok(resp.status().as_u16()),
+                Ok(resp) => {
+                    let status = resp.status();
+                    let retry = status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                    (retry, format!("{} returned {status}", sub.url))
+                }
+                Err(e) => (true, format!("{} request failed: {e}", sub.url)),
+            };
+            if !retry || attempt >= self.max_attempts {
+                return Err(format!("{outcome} (attempt {attempt}/{})", self.max_attempts));
+            }
+            warn!("⚠️ Webhook #{} attempt {attempt} failed: {outcome}, retrying in {delay:?}", sub.id);
+            sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+}
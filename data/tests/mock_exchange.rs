@@ -0,0 +1,110 @@
+//! The ingester against the scripted mock exchange, end to end into Redis. Needs a Redis at
+//! `TEST_REDIS_URL` and is skipped without one; its keys go under their own namespace.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use data_collection::{
+    config::Config,
+    ingest::{self, Options},
+    keys::{self, DQ_PREFIX, OHLCV_PREFIX, PRICE_PREFIX, SYMBOLS_KEY},
+    mock_exchange::{Malformed, MockExchange, Protocol, Scenario, Step},
+    redis_conn::RedisClient,
+};
+use redis::AsyncCommands;
+use tokio::{task::LocalSet, time::sleep};
+use tokio_util::sync::CancellationToken;
+
+const NAMESPACE: &str = "test-mock-exchange";
+const SYMBOLS: [&str; 2] = ["MOCK:AAA", "MOCK:BBB"];
+// Good trades per symbol in the scenario below
+const TRADES: i64 = 3 + 20 + 2 + 3;
+
+fn scenario() -> Scenario {
+    let trades = |count| Step::Trades { count, interval_ms: 20 };
+    // Trades start with the first subscription; give the rest time to follow
+    let subscribed = || Step::Pause { ms: 300 };
+    Scenario {
+        steps: vec![
+            subscribed(),
+            trades(3),
+            Step::Burst { count: 20 },
+            Step::Malformed { kind: Malformed::Truncated },
+            Step::Malformed { kind: Malformed::NegativePrice },
+            trades(2),
+            Step::Disconnect,
+            subscribed(),
+            trades(3),
+        ],
+        repeat: false,
+    }
+}
+
+#[tokio::test]
+async fn ingests_bursts_skips_bad_frames_and_resumes_after_a_reconnect() {
+    let Ok(redis_url) = std::env::var("TEST_REDIS_URL") else {
+        eprintln!("TEST_REDIS_URL is not set, skipping");
+        return;
+    };
+    keys::set_namespace(NAMESPACE).unwrap();
+    let addr = MockExchange::new(Protocol::Finnhub, scenario(), 7).spawn("127.0.0.1:0").await.unwrap();
+
+    let mut config = Config::default();
+    config.redis_url = Some(redis_url);
+    config.exchanges.finnhub.ws_url = format!("ws://{addr}");
+    config.exchanges.finnhub.api_key = Some("test".to_string());
+    config.symbols = SYMBOLS.map(String::from).to_vec();
+    config.intervals.reconnect_secs = 1;
+
+    let mut redis = RedisClient::open_with(&config.redis_url(), &config.tls.redis)
+        .unwrap()
+        .connect_with_retry()
+        .await;
+    let mut clear = redis::pipe();
+    clear.del(SYMBOLS_KEY).ignore();
+    for symbol in SYMBOLS {
+        clear.del(format!("{OHLCV_PREFIX}{symbol}")).ignore();
+        clear.del(format!("{PRICE_PREFIX}{symbol}")).ignore();
+    }
+    let dq_key = format!("{DQ_PREFIX}{}", Utc::now().date_naive());
+    clear.del(&dq_key).ignore();
+    clear.query_async::<()>(&mut redis).await.unwrap();
+
+    let shutdown = CancellationToken::new();
+    let ingester = {
+        let (config, shutdown) = (config.clone(), shutdown.clone());
+        async move { ingest::run(&config, Options::default(), shutdown).await }
+    };
+    LocalSet::new()
+        .run_until(async {
+            let ingester = tokio::task::spawn_local(ingester);
+            // Everything, including the trades after the reconnect, or give up after a minute
+            for _ in 0..600 {
+                let mut counts = Vec::new();
+                for symbol in SYMBOLS {
+                    let n: Option<i64> = redis.hget(format!("{OHLCV_PREFIX}{symbol}"), "trades").await.unwrap();
+                    counts.push(n.unwrap_or(0));
+                }
+                if counts.iter().all(|&n| n >= TRADES) {
+                    break;
+                }
+                sleep(Duration::from_millis(100)).await;
+            }
+            shutdown.cancel();
+            ingester.await.unwrap().unwrap();
+        })
+        .await;
+
+    for symbol in SYMBOLS {
+        let ohlcv = format!("{OHLCV_PREFIX}{symbol}");
+        let trades: Option<i64> = redis.hget(&ohlcv, "trades").await.unwrap();
+        assert_eq!(trades, Some(TRADES), "{symbol} trades");
+        let price: Option<f64> = redis.get(format!("{PRICE_PREFIX}{symbol}")).await.unwrap();
+        assert!(price.is_some_and(|p| p > 0.0), "{symbol} price {price:?}");
+        // Counted on the way out: every good trade, and the negative price as an outlier
+        let ticks: Option<i64> = redis.hget(&dq_key, format!("{symbol}|ticks")).await.unwrap();
+        assert_eq!(ticks, Some(TRADES), "{symbol} ticks");
+        let outliers: Option<i64> = redis.hget(&dq_key, format!("{symbol}|outliers")).await.unwrap();
+        assert_eq!(outliers, Some(1), "{symbol} outliers");
+    }
+}